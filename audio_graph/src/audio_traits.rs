@@ -157,13 +157,13 @@ impl LiveNew for AudioComponentRef {
     }
     
     fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
-        LiveTypeInfo {
-            module_id: LiveModuleId::from_str(&module_path!()).unwrap(),
-            live_type: LiveType::of::<dyn AudioComponent>(),
-            fields: Vec::new(),
-            live_ignore: true,
-            type_name: LiveId(0)
-        }
+        LiveTypeInfo::new(
+            LiveModuleId::from_str(&module_path!()).unwrap(),
+            LiveType::of::<dyn AudioComponent>(),
+            LiveId(0),
+            true,
+            Vec::new()
+        )
     }
 }
 
@@ -1,7 +1,12 @@
 use {
     crate::{
+        completion::{
+            word_start, CompletionItem, CompletionList, CompletionProvider, Snippet,
+            WordCompletionProvider,
+        },
         decoration::{Decoration, DecorationType},
         layout::{BlockElement, WrappedElement},
+        line_marker::LineMarkerKind,
         selection::Affinity,
         session::{SelectionMode, CodeSession},
         history::{NewGroup},
@@ -13,7 +18,7 @@ use {
     },
     makepad_widgets::*,
     std::fmt::Write,
-    std::{mem, slice::Iter},
+    std::{iter, mem, slice::Iter},
 };
 
 live_design! {
@@ -26,6 +31,14 @@ live_design! {
         delimiter_highlight: #f,
         error_decoration: #f00,
         warning_decoration: #0f0,
+        search_match_decoration: #5A4A1E,
+        added_line_marker: #587C0C,
+        modified_line_marker: #0C7D9D,
+        deleted_line_marker: #94151B,
+        test_line_marker: #6E6E6E,
+        test_passed_line_marker: #73C991,
+        test_failed_line_marker: #F14C4C,
+        test_ignored_line_marker: #CCA700,
         
         unknown: #C0C0C0,
         branch_keyword: #C485BE,
@@ -147,6 +160,13 @@ live_design! {
         draw_decoration: {
           //  draw_depth: 2.0,
         }
+        draw_search_match: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(0., 0., self.rect_size.x, self.rect_size.y, 2.);
+                return sdf.fill(self.color);
+            }
+        }
         draw_selection: {
            // draw_depth: 3.0,
         }
@@ -163,6 +183,76 @@ live_design! {
         }
 
 
+        draw_completion_bg: {
+            color: (THEME_COLOR_BG_APP),
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(0., 0., self.rect_size.x, self.rect_size.y, 3.);
+                sdf.fill_keep(self.color);
+                sdf.stroke(THEME_COLOR_U_2, 1.);
+                return sdf.result;
+            }
+        }
+        draw_completion_selection: {
+            color: (THEME_COLOR_U_2),
+        }
+        draw_completion_text: {
+            text_style: <THEME_FONT_CODE> {},
+            color: (THEME_COLOR_TEXT_DEFAULT),
+        }
+        draw_completion_detail: {
+            text_style: <THEME_FONT_CODE> {},
+            color: (THEME_COLOR_TEXT_META),
+        }
+
+        // the minimap and the sticky headers stay on top of the scrolling text
+        draw_minimap_bg: {
+            draw_depth: 2.0,
+            color: (THEME_COLOR_BG_CONTAINER),
+            fn pixel(self) -> vec4 {
+                if self.pos.x * self.rect_size.x < 1.0 {
+                    return THEME_COLOR_U_2;
+                }
+                return self.color;
+            }
+        }
+        draw_minimap_token: {
+            draw_depth: 2.1,
+        }
+        draw_minimap_viewport: {
+            draw_depth: 2.2,
+            color: #ffffff18,
+            fn pixel(self) -> vec4 {
+                return vec4(self.color.rgb * self.color.a, self.color.a);
+            }
+        }
+        draw_sticky_bg: {
+            draw_depth: 2.0,
+            color: (THEME_COLOR_BG_CONTAINER),
+            fn pixel(self) -> vec4 {
+                if self.pos.y * self.rect_size.y > self.rect_size.y - 1.0 {
+                    return THEME_COLOR_U_2;
+                }
+                return self.color;
+            }
+        }
+        draw_sticky_text: {
+            draw_depth: 2.5,
+            text_style: <THEME_FONT_CODE> {}
+        }
+
+        // others editing the same document, tinted with the color of their cursor
+        draw_remote_selection: {
+            fn pixel(self) -> vec4 {
+                return vec4(self.color.rgb * 0.25, 0.25);
+            }
+        }
+        draw_remote_cursor: {
+            fn pixel(self) -> vec4 {
+                return vec4(self.color.rgb, 1.0);
+            }
+        }
+
         draw_cursor_bg: {
             instance focus: 0.0
             fn pixel(self) -> vec4 {
@@ -229,12 +319,47 @@ pub struct CodeEditor {
     #[live] token_colors: TokenColors,
     #[live] draw_indent_guide: DrawIndentGuide,
     #[live] draw_decoration: DrawDecoration,
+    #[live] draw_search_match: DrawColor,
+    #[live] draw_line_marker: DrawColor,
     #[live] draw_selection: DrawSelection,
     #[live] draw_cursor: DrawColor,
     #[live] draw_cursor_bg: DrawColor,
+    #[live] draw_remote_selection: DrawColor,
+    #[live] draw_remote_cursor: DrawColor,
     #[live] draw_bg: DrawColor,
+    #[live] completion_draw_list: DrawList2d,
+    #[live] draw_completion_bg: DrawColor,
+    #[live] draw_completion_selection: DrawColor,
+    #[live] draw_completion_text: DrawText,
+    #[live] draw_completion_detail: DrawText,
+    #[live(10usize)] completion_rows: usize,
+    #[rust(vec![Box::new(WordCompletionProvider) as Box<dyn CompletionProvider>])] completion_providers: Vec<Box<dyn CompletionProvider>>,
+    #[rust] completion: Option<CompletionList>,
+    // the first visible row of the completion list
+    #[rust] completion_scroll: usize,
+    #[rust] completion_rect: Rect,
+    #[live] draw_minimap_bg: DrawColor,
+    #[live] draw_minimap_token: DrawColor,
+    #[live] draw_minimap_viewport: DrawColor,
+    #[live(true)] show_minimap: bool,
+    #[live(90.0)] minimap_width: f64,
+    // the height of a line and the width of a column in the minimap
+    #[live(2.0)] minimap_line_height: f64,
+    #[live(1.0)] minimap_column_width: f64,
+    #[rust] minimap_rect: Rect,
+    // minimap pixels per pixel scrolled, the minimap viewport top is at scroll_pos.y * minimap_scale
+    #[rust] minimap_scale: f64,
+    #[rust] minimap_drag_offset: f64,
+    #[live] draw_sticky_bg: DrawColor,
+    #[live] draw_sticky_text: DrawText,
+    #[live(true)] show_sticky_headers: bool,
+    #[live(3usize)] max_sticky_headers: usize,
+    // the lines of the enclosing scopes that are pinned to the top, outermost first
+    #[rust] sticky_headers: Vec<usize>,
+    #[rust] sticky_rect: Rect,
     #[rust(KeepCursorInView::Off)] keep_cursor_in_view: KeepCursorInView,
     #[rust] last_cursor_screen_pos: Option<DVec2>,
+    #[rust] last_cursor_line: Option<usize>,
     #[live] pad_left_top: DVec2, 
     #[rust] cell_size: DVec2,
     #[rust] gutter_rect: Rect,
@@ -325,6 +450,7 @@ pub struct CodeEditorRef(WidgetRef);
 impl CodeEditor {
     pub fn redraw(&mut self, cx: &mut Cx) {
         self.scroll_bars.redraw(cx);
+        self.completion_draw_list.redraw(cx);
     }
     
     pub fn area(&self)->Area{
@@ -447,6 +573,12 @@ impl CodeEditor {
         
         let turtle_rect = cx.turtle().rect();
         
+        let show_overlays = !height_is_fit;
+        let minimap_width = if self.show_minimap && show_overlays {
+            self.minimap_width
+        } else {
+            0.0
+        };
         let gutter_width = if self.show_gutter{
             self.gutter_chars =  session
                 .document()
@@ -474,7 +606,7 @@ impl CodeEditor {
                 y: turtle_rect.pos.y,
             },
             size: DVec2 {
-                x: turtle_rect.size.x - gutter_width,
+                x: turtle_rect.size.x - gutter_width - minimap_width,
                 y:if height_is_fit{MAX_HEIGHT}else{turtle_rect.size.y},
             },
         };
@@ -514,10 +646,26 @@ impl CodeEditor {
             self.draw_gutter(cx, session);
         }
         self.draw_selection_layer(cx, session);
+        self.draw_decoration_layer(cx, session, &session.search_matches());
         self.draw_text_layer(cx, session);
         self.draw_indent_guide_layer(cx, session);
-        self.draw_decoration_layer(cx, session);
+        self.draw_decoration_layer(cx, session, &session.document().decorations());
         self.draw_selection_layer(cx, session);
+        self.draw_remote_cursors(cx, session);
+
+        self.minimap_rect = Rect {
+            pos: dvec2(turtle_rect.pos.x + turtle_rect.size.x - minimap_width, turtle_rect.pos.y),
+            size: dvec2(minimap_width, turtle_rect.size.y),
+        };
+        if minimap_width > 0.0 {
+            self.draw_minimap(cx, session, scroll_pos);
+        }
+        self.sticky_headers.clear();
+        self.sticky_rect = Rect::default();
+        if self.show_sticky_headers && show_overlays {
+            self.find_sticky_headers(session, scroll_pos);
+            self.draw_sticky_headers(cx, session, turtle_rect, scroll_pos);
+        }
 
         // Get the last added selection.
         // Get the normalized cursor position. To go from normalized to screen position, multiply by
@@ -530,6 +678,7 @@ impl CodeEditor {
         );
         
         self.scroll_bars.end(cx);
+        self.draw_completion_list(cx, session);
         if session.update_folds() {
             self.scroll_bars.area().redraw(cx);
         } else if self.keep_cursor_in_view.is_locked() {
@@ -552,6 +701,27 @@ impl CodeEditor {
         self.redraw(cx);
     }
 
+    // selects the next (or previous) match of the session's search query and scrolls to it
+    pub fn find_next(&mut self, cx: &mut Cx, session: &mut CodeSession, backwards: bool) -> bool {
+        let found = if backwards {
+            session.find_previous()
+        } else {
+            session.find_next()
+        };
+        if found {
+            self.keep_cursor_in_view = KeepCursorInView::JumpToPosition;
+            self.reset_cursor_blinker(cx);
+        }
+        self.redraw(cx);
+        found
+    }
+
+    // scrolls the cursor into view after a search moved the selection
+    pub fn show_search_result(&mut self, cx: &mut Cx) {
+        self.keep_cursor_in_view = KeepCursorInView::JumpToPosition;
+        self.redraw(cx);
+    }
+
     pub fn reset_font_size(&mut self) {
         self.draw_gutter.text_style.font_size = 9.0;
         self.draw_text.text_style.font_size = 9.0;
@@ -580,6 +750,389 @@ impl CodeEditor {
         }
     }
 
+    fn cursor_position(&self, session: &CodeSession) -> Position {
+        let index = session.last_added_selection_index().unwrap_or(0);
+        session.selections()[index].cursor.position
+    }
+
+    // the word based provider is there by default, hosts add their own on top of it
+    pub fn add_completion_provider(&mut self, provider: Box<dyn CompletionProvider>) {
+        self.completion_providers.push(provider);
+    }
+
+    pub fn is_completion_open(&self) -> bool {
+        self.completion.is_some()
+    }
+
+    fn is_completion_visible(&self) -> bool {
+        self.completion.as_ref().map_or(false, |list| !list.is_empty())
+    }
+
+    fn open_completion(&mut self, cx: &mut Cx, session: &CodeSession, actions: &mut Vec<CodeEditorAction>) {
+        let position = self.cursor_position(session);
+        let text = session.document().as_text();
+        let mut list = CompletionList::new(word_start(&text, position));
+        for provider in &self.completion_providers {
+            list.add_items(provider.complete(&text, position));
+        }
+        drop(text);
+        self.completion = Some(list);
+        self.completion_scroll = 0;
+        self.update_completion(cx, session);
+        actions.push(CodeEditorAction::Complete(position));
+    }
+
+    // adds items to the open completion list, for hosts that find them asynchronously
+    pub fn add_completions(&mut self, cx: &mut Cx, session: &CodeSession, items: Vec<CompletionItem>) {
+        if let Some(list) = &mut self.completion {
+            list.add_items(items);
+            self.update_completion(cx, session);
+        }
+    }
+
+    pub fn close_completion(&mut self, cx: &mut Cx) {
+        if self.completion.take().is_some() {
+            self.completion_draw_list.redraw(cx);
+        }
+    }
+
+    // filters the list by the text typed since it opened, closing it if the cursor left the word
+    fn update_completion(&mut self, cx: &mut Cx, session: &CodeSession) {
+        let cursor = self.cursor_position(session);
+        let Some(list) = &mut self.completion else {
+            return;
+        };
+        if cursor.line_index != list.start.line_index || cursor.byte_index < list.start.byte_index {
+            self.close_completion(cx);
+            return;
+        }
+        let text = session.document().as_text();
+        let prefix = &text.as_lines()[cursor.line_index][list.start.byte_index..cursor.byte_index];
+        list.filter(prefix);
+        self.completion_scroll = 0;
+        self.completion_draw_list.redraw(cx);
+    }
+
+    fn move_completion_selection(&mut self, cx: &mut Cx, delta: isize) {
+        if let Some(list) = &mut self.completion {
+            list.move_selection(delta);
+            let selected = list.selected_index();
+            if selected < self.completion_scroll {
+                self.completion_scroll = selected;
+            } else if selected >= self.completion_scroll + self.completion_rows {
+                self.completion_scroll = selected + 1 - self.completion_rows;
+            }
+            self.completion_draw_list.redraw(cx);
+        }
+    }
+
+    fn accept_completion(&mut self, cx: &mut Cx, session: &CodeSession) {
+        let Some(item) = self.completion.take().and_then(|list| list.selected_item().cloned()) else {
+            return;
+        };
+        let snippet = if item.is_snippet {
+            Snippet::parse(&item.text)
+        } else {
+            Snippet {
+                text: item.text,
+                tab_stops: Vec::new(),
+            }
+        };
+        session.insert_snippet(item.start, &snippet);
+        self.keep_cursor_in_view = KeepCursorInView::Once;
+        self.reset_cursor_blinker(cx);
+        self.redraw(cx);
+    }
+
+    fn draw_completion_list(&mut self, cx: &mut Cx2d, session: &CodeSession) {
+        self.completion_draw_list.begin_overlay_reuse(cx);
+        cx.begin_pass_sized_turtle(Layout::flow_down());
+        if let Some(list) = self.completion.as_ref().filter(|list| !list.is_empty()) {
+            self.draw_completion_text.text_style.font_size = self.draw_text.text_style.font_size;
+            self.draw_completion_detail.text_style.font_size = self.draw_text.text_style.font_size;
+            let (x, y) = session
+                .layout()
+                .logical_to_normalized_position(list.start, Affinity::Before);
+            let row_count = list.len().min(self.completion_rows);
+            let rows = self.completion_scroll..(self.completion_scroll + row_count).min(list.len());
+            // the kind tag, the label and the detail of a row, in columns
+            let label_columns = rows
+                .clone()
+                .filter_map(|index| list.item(index))
+                .map(|item| item.label.column_count())
+                .max()
+                .unwrap_or(0);
+            let detail_columns = rows
+                .clone()
+                .filter_map(|index| list.item(index))
+                .map(|item| item.detail.as_ref().map_or(0, |detail| detail.column_count().min(40) + 2))
+                .max()
+                .unwrap_or(0);
+            let padding = dvec2(4.0, 2.0);
+            let row_height = self.cell_size.y;
+            let width = (6 + label_columns + detail_columns) as f64 * self.cell_size.x;
+            let mut pos = dvec2(x - 6.0, y + 1.0) * self.cell_size + self.viewport_rect.pos
+                - self.scroll_bars.get_scroll_pos();
+            let size = dvec2(width, row_count as f64 * row_height) + padding * 2.0;
+            // open above the cursor if there is no room below it
+            let pass_size = cx.current_pass_size();
+            if pos.y + size.y > pass_size.y {
+                pos.y -= size.y + self.cell_size.y;
+            }
+            pos.x = pos.x.min(pass_size.x - size.x).max(0.0);
+            self.completion_rect = Rect { pos, size };
+            self.draw_completion_bg.draw_abs(cx, self.completion_rect);
+            for (row, index) in rows.enumerate() {
+                let Some(item) = list.item(index) else {
+                    break;
+                };
+                let row_pos = pos + padding + dvec2(0.0, row as f64 * row_height);
+                if index == list.selected_index() {
+                    self.draw_completion_selection.draw_abs(
+                        cx,
+                        Rect {
+                            pos: row_pos,
+                            size: dvec2(width, row_height),
+                        },
+                    );
+                }
+                self.draw_completion_detail.draw_abs(cx, row_pos, item.kind.tag());
+                self.draw_completion_text.draw_abs(
+                    cx,
+                    row_pos + dvec2(6.0 * self.cell_size.x, 0.0),
+                    &item.label,
+                );
+                if let Some(detail) = &item.detail {
+                    let detail: String = detail.lines().next().unwrap_or("").chars().take(40).collect();
+                    self.draw_completion_detail.draw_abs(
+                        cx,
+                        row_pos + dvec2((8 + label_columns) as f64 * self.cell_size.x, 0.0),
+                        &detail,
+                    );
+                }
+            }
+        }
+        cx.end_pass_sized_turtle();
+        self.completion_draw_list.end(cx);
+    }
+
+    fn draw_minimap(&mut self, cx: &mut Cx2d, session: &CodeSession, scroll_pos: DVec2) {
+        let layout = session.layout();
+        let rect = self.minimap_rect;
+        let line_height = self.minimap_line_height;
+        let column_width = self.minimap_column_width;
+        // a minimap taller than the editor scrolls along with it proportionally
+        self.minimap_scale = if layout.height() * line_height > rect.size.y {
+            rect.size.y / (layout.height() * self.cell_size.y).max(1.0)
+        } else {
+            line_height / self.cell_size.y
+        };
+        let viewport_top = scroll_pos.y * self.minimap_scale;
+        let minimap_scroll = scroll_pos.y * line_height / self.cell_size.y - viewport_top;
+        // the minimap doesn't scroll with the text, so undo the scroll the turtle applies
+        let origin = rect.pos + scroll_pos;
+        self.draw_minimap_bg.draw_abs(cx, Rect { pos: origin, size: rect.size });
+
+        let line_start = layout.find_first_line_ending_after_y(minimap_scroll / line_height);
+        let line_end =
+            layout.find_first_line_starting_after_y((minimap_scroll + rect.size.y) / line_height);
+        let max_column_count = ((rect.size.x - 4.0) / column_width).max(0.0) as usize;
+        let mut origin_y = layout.line(line_start).y();
+        for element in layout.block_elements(line_start, line_end) {
+            match element {
+                BlockElement::Line { line, .. } => {
+                    let y = origin_y * line_height - minimap_scroll;
+                    let mut text = line.text();
+                    let mut column_index = 0;
+                    for token in line.tokens() {
+                        if column_index >= max_column_count {
+                            break;
+                        }
+                        let (token_text, rest) = text.split_at(token.len.min(text.len()));
+                        text = rest;
+                        let column_count = token_text.column_count();
+                        if token.kind != TokenKind::Whitespace {
+                            self.draw_minimap_token.color = self.token_colors.color(token.kind);
+                            self.draw_minimap_token.draw_abs(
+                                cx,
+                                Rect {
+                                    pos: origin + dvec2(4.0 + column_index as f64 * column_width, y),
+                                    size: dvec2(
+                                        column_count.min(max_column_count - column_index) as f64
+                                            * column_width,
+                                        line.scale() * line_height,
+                                    ),
+                                },
+                            );
+                        }
+                        column_index += column_count;
+                    }
+                    origin_y += line.height();
+                }
+                BlockElement::Widget(widget) => {
+                    origin_y += widget.height;
+                }
+            }
+        }
+        self.draw_minimap_viewport.draw_abs(
+            cx,
+            Rect {
+                pos: origin + dvec2(0.0, viewport_top),
+                size: dvec2(
+                    rect.size.x,
+                    self.viewport_rect.size.y * line_height / self.cell_size.y,
+                ),
+            },
+        );
+    }
+
+    // scrolls so the top of the minimap viewport, minus where it was grabbed, is `y` below the top
+    // of the minimap
+    fn minimap_scroll_to(&mut self, cx: &mut Cx, y: f64) {
+        if self.minimap_scale <= 0.0 {
+            return;
+        }
+        let scroll_pos = self.scroll_bars.get_scroll_pos();
+        self.scroll_bars.set_scroll_pos(
+            cx,
+            dvec2(
+                scroll_pos.x,
+                ((y - self.minimap_drag_offset) / self.minimap_scale).max(0.0),
+            ),
+        );
+        self.redraw(cx);
+    }
+
+    // walks up from the first visible line, collecting the lines that open the scopes it is in.
+    // Scopes are the indent levels the session folds by, a scope opens on the last line before it
+    // that is less indented
+    fn find_sticky_headers(&mut self, session: &CodeSession, scroll_pos: DVec2) {
+        let layout = session.layout();
+        let tab_column_count = session.settings().tab_column_count.max(1);
+        let line_count = layout.as_text().as_lines().len();
+        let top = layout.find_first_line_ending_after_y(scroll_pos.y / self.cell_size.y);
+        let is_blank = |line_index: usize| layout.line(line_index).text().trim().is_empty();
+        let indent_level = |line_index: usize| layout.line(line_index).indent_column_count() / tab_column_count;
+        let Some(mut level) = (top..line_count)
+            .find(|&line_index| !is_blank(line_index))
+            .map(indent_level)
+        else {
+            return;
+        };
+        let mut line_index = top;
+        while line_index > 0 && level > 0 && self.sticky_headers.len() < self.max_sticky_headers {
+            line_index -= 1;
+            if is_blank(line_index) || indent_level(line_index) >= level {
+                continue;
+            }
+            level = indent_level(line_index);
+            // with the opening delimiter on a line of its own the line before it names the scope
+            let mut header = line_index;
+            if layout.line(header).text().trim().chars().all(|char| "{([".contains(char)) {
+                if let Some(prev) = (0..header)
+                    .rev()
+                    .find(|&prev| !is_blank(prev))
+                    .filter(|&prev| indent_level(prev) == level)
+                {
+                    header = prev;
+                }
+            }
+            self.sticky_headers.push(header);
+            line_index = header;
+        }
+        self.sticky_headers.reverse();
+    }
+
+    fn draw_sticky_headers(
+        &mut self,
+        cx: &mut Cx2d,
+        session: &CodeSession,
+        turtle_rect: Rect,
+        scroll_pos: DVec2,
+    ) {
+        if self.sticky_headers.is_empty() {
+            return;
+        }
+        let layout = session.layout();
+        self.draw_sticky_text.text_style.font_size = self.draw_text.text_style.font_size;
+        self.sticky_rect = Rect {
+            pos: turtle_rect.pos,
+            size: dvec2(
+                turtle_rect.size.x - self.minimap_rect.size.x,
+                self.pad_left_top.y + self.sticky_headers.len() as f64 * self.cell_size.y,
+            ),
+        };
+        self.draw_sticky_bg.draw_abs(
+            cx,
+            Rect {
+                pos: self.sticky_rect.pos + scroll_pos,
+                size: self.sticky_rect.size,
+            },
+        );
+        let max_column_count = (self.viewport_rect.size.x / self.cell_size.x) as usize;
+        let mut buf = String::new();
+        for (row_index, &line_index) in self.sticky_headers.iter().enumerate() {
+            let line = layout.line(line_index);
+            let y = self.sticky_rect.pos.y + self.pad_left_top.y + row_index as f64 * self.cell_size.y;
+            if self.show_gutter {
+                buf.clear();
+                write!(buf, "{: >1$}", line_index + 1, self.gutter_chars.saturating_sub(1)).unwrap();
+                self.draw_sticky_text.color = self.draw_gutter.color;
+                self.draw_sticky_text
+                    .draw_abs(cx, dvec2(self.gutter_rect.pos.x, y) + scroll_pos, &buf);
+            }
+            let mut text = line.text();
+            let mut column_index = 0;
+            for token in line.tokens() {
+                let (token_text, rest) = text.split_at(token.len.min(text.len()));
+                text = rest;
+                let column_count = token_text.column_count();
+                if column_index + column_count > max_column_count {
+                    break;
+                }
+                if token.kind != TokenKind::Whitespace {
+                    self.draw_sticky_text.color = self.token_colors.color(token.kind);
+                    self.draw_sticky_text.draw_abs(
+                        cx,
+                        dvec2(
+                            self.viewport_rect.pos.x + column_index as f64 * self.cell_size.x,
+                            y,
+                        ) + scroll_pos,
+                        token_text,
+                    );
+                }
+                column_index += column_count;
+            }
+        }
+    }
+
+    // scrolls a sticky header back to the top of the editor and puts the cursor on it
+    fn jump_to_sticky_header(&mut self, cx: &mut Cx, session: &mut CodeSession, line_index: usize) {
+        let (byte_index, y) = {
+            let layout = session.layout();
+            let line = layout.line(line_index);
+            (
+                line.text().len() - line.text().trim_start().len(),
+                line.y() * self.cell_size.y,
+            )
+        };
+        session.set_selection(
+            Position {
+                line_index,
+                byte_index,
+            },
+            Affinity::Before,
+            SelectionMode::Simple,
+            NewGroup::Yes,
+        );
+        let scroll_pos = self.scroll_bars.get_scroll_pos();
+        self.scroll_bars.set_scroll_pos(cx, dvec2(scroll_pos.x, y));
+        self.keep_cursor_in_view = KeepCursorInView::Off;
+        cx.set_key_focus(self.scroll_bars.area());
+        self.reset_cursor_blinker(cx);
+        self.redraw(cx);
+    }
+
     pub fn reset_cursor_blinker(&mut self, cx: &mut Cx) {
         if self.read_only{
             self.animator_cut(cx, id!(blink.off));
@@ -617,9 +1170,115 @@ impl CodeEditor {
             self.blink_timer = cx.start_timeout(self.blink_speed)
         }
         let mut keyboard_moved_cursor = false;
+        if self.is_completion_visible() {
+            if let Hit::FingerDown(FingerDownEvent { abs, .. }) =
+                event.hits(cx, self.draw_completion_bg.area())
+            {
+                let row = ((abs.y - self.completion_rect.pos.y - 2.0) / self.cell_size.y).max(0.0);
+                if let Some(list) = &mut self.completion {
+                    list.select(self.completion_scroll + row as usize);
+                }
+                self.accept_completion(cx, session);
+                actions.push(CodeEditorAction::TextDidChange);
+                return actions;
+            }
+        }
+        if self.minimap_rect.size.x > 0.0 {
+            match event.hits(cx, self.draw_minimap_bg.area()) {
+                Hit::FingerDown(FingerDownEvent { abs, .. }) => {
+                    let y = abs.y - self.minimap_rect.pos.y;
+                    let top = self.scroll_bars.get_scroll_pos().y * self.minimap_scale;
+                    let height =
+                        self.viewport_rect.size.y * self.minimap_line_height / self.cell_size.y;
+                    // drag the viewport from where it was grabbed, or center it on the click
+                    self.minimap_drag_offset = if y >= top && y < top + height {
+                        y - top
+                    } else {
+                        height * 0.5
+                    };
+                    self.minimap_scroll_to(cx, y);
+                    return actions;
+                }
+                Hit::FingerMove(FingerMoveEvent { abs, .. }) => {
+                    self.minimap_scroll_to(cx, abs.y - self.minimap_rect.pos.y);
+                    return actions;
+                }
+                Hit::FingerHoverIn(_) | Hit::FingerHoverOver(_) => {
+                    cx.set_cursor(MouseCursor::Default);
+                    return actions;
+                }
+                Hit::FingerUp(_) => return actions,
+                _ => {}
+            }
+        }
+        if !self.sticky_headers.is_empty() {
+            match event.hits(cx, self.draw_sticky_bg.area()) {
+                Hit::FingerDown(FingerDownEvent { abs, .. }) => {
+                    let row_index = ((abs.y - self.sticky_rect.pos.y - self.pad_left_top.y)
+                        / self.cell_size.y)
+                        .max(0.0) as usize;
+                    let line_index =
+                        self.sticky_headers[row_index.min(self.sticky_headers.len() - 1)];
+                    self.jump_to_sticky_header(cx, session, line_index);
+                    return actions;
+                }
+                Hit::FingerHoverIn(_) | Hit::FingerHoverOver(_) => {
+                    cx.set_cursor(MouseCursor::Hand);
+                    return actions;
+                }
+                Hit::FingerMove(_) | Hit::FingerUp(_) => return actions,
+                _ => {}
+            }
+        }
         match event.hits(cx, self.scroll_bars.area()) {
             Hit::KeyFocusLost(_) => {
                 self.animator_play(cx, id!(focus.off));
+                self.close_completion(cx);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::Escape,
+                ..
+            }) if self.completion.is_some() => {
+                self.close_completion(cx);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::ArrowUp,
+                ..
+            }) if self.is_completion_visible() => {
+                self.move_completion_selection(cx, -1);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::ArrowDown,
+                ..
+            }) if self.is_completion_visible() => {
+                self.move_completion_selection(cx, 1);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::PageUp,
+                ..
+            }) if self.is_completion_visible() => {
+                self.move_completion_selection(cx, -(self.completion_rows as isize));
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::PageDown,
+                ..
+            }) if self.is_completion_visible() => {
+                self.move_completion_selection(cx, self.completion_rows as isize);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::ReturnKey | KeyCode::Tab,
+                modifiers: KeyModifiers { shift: false, .. },
+                ..
+            }) if self.is_completion_visible() && !self.read_only => {
+                self.accept_completion(cx, session);
+                actions.push(CodeEditorAction::TextDidChange);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::Space,
+                modifiers: KeyModifiers { control: true, .. },
+                ..
+            }) if !self.read_only => {
+                self.open_completion(cx, session, &mut actions);
             }
             Hit::KeyFocus(_) => {
                 self.animator_play(cx, id!(focus.on));
@@ -629,6 +1288,7 @@ impl CodeEditor {
                 is_repeat: false,
                 ..
             }) => {
+                session.clear_tab_stops();
                 session.fold();
                 if !self.keep_cursor_in_view.is_locked() {
                     self.keep_cursor_in_view = KeepCursorInView::LockStart;
@@ -685,6 +1345,62 @@ impl CodeEditor {
                     self.redraw(cx);
                 }
             }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyF,
+                modifiers: KeyModifiers { control, logo, alt, .. },
+                ..
+            }) => {
+                if control || logo {
+                    actions.push(if alt {CodeEditorAction::Replace} else {CodeEditorAction::Find});
+                }
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyH,
+                modifiers: KeyModifiers { control: true, .. },
+                ..
+            }) if !self.read_only => {
+                actions.push(CodeEditorAction::Replace);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyG,
+                modifiers: KeyModifiers { control, logo, shift, .. },
+                ..
+            }) => {
+                if control || logo {
+                    self.find_next(cx, session, shift);
+                }
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::F3,
+                modifiers: KeyModifiers { shift, .. },
+                ..
+            }) => {
+                self.find_next(cx, session, shift);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::F12,
+                ..
+            }) => {
+                actions.push(CodeEditorAction::GoToDefinition(self.cursor_position(session)));
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyI,
+                modifiers: KeyModifiers { control, logo, .. },
+                ..
+            }) => {
+                if control || logo {
+                    actions.push(CodeEditorAction::Hover(self.cursor_position(session)));
+                }
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyO,
+                modifiers: KeyModifiers { control, logo, shift: true, .. },
+                ..
+            }) => {
+                if control || logo {
+                    actions.push(CodeEditorAction::DocumentSymbols);
+                }
+            }
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::KeyA,
                 modifiers: KeyModifiers {control, logo, ..},
@@ -807,6 +1523,19 @@ impl CodeEditor {
                 ..
             }) if input.len() > 0 && !self.read_only => {
                 session.insert(input.into());
+                session.handle_changes();
+                let is_word = input.chars().all(|char| char.is_alphanumeric() || char == '_');
+                if !is_word {
+                    self.close_completion(cx);
+                } else if self.completion.is_some() {
+                    self.update_completion(cx, session);
+                } else {
+                    // open by itself once a word is two characters long
+                    let cursor = self.cursor_position(session);
+                    if cursor.byte_index - word_start(&session.document().as_text(), cursor).byte_index >= 2 {
+                        self.open_completion(cx, session, &mut actions);
+                    }
+                }
                 self.redraw(cx);
                 keyboard_moved_cursor = true;
                 actions.push(CodeEditorAction::TextDidChange);
@@ -835,7 +1564,9 @@ impl CodeEditor {
                 modifiers: KeyModifiers { shift: false, .. },
                 ..
             }) if !self.read_only => {
-                session.indent();
+                if !session.next_tab_stop() {
+                    session.indent();
+                }
                 self.redraw(cx);
                 keyboard_moved_cursor = true;
                 actions.push(CodeEditorAction::TextDidChange);
@@ -864,6 +1595,8 @@ impl CodeEditor {
                 ..
             }) if !self.read_only=> {
                 session.backspace();
+                session.handle_changes();
+                self.update_completion(cx, session);
                 self.redraw(cx);
                 keyboard_moved_cursor = true;
                 actions.push(CodeEditorAction::TextDidChange);
@@ -919,12 +1652,15 @@ impl CodeEditor {
                     KeyModifiers {
                         alt: false,
                         shift: false,
-                        ..
+                        control,
+                        logo,
                     },
                 ..
             }) => {
                 self.animator_play(cx, id!(focus.on));
                 cx.set_key_focus(self.scroll_bars.area());
+                self.close_completion(cx);
+                session.clear_tab_stops();
                 let ((cursor, affinity), is_in_gutter) = self.pick(session, abs);
                 session.set_selection(
                     cursor,
@@ -941,6 +1677,9 @@ impl CodeEditor {
                     },
                     NewGroup::Yes
                 );
+                if (control || logo) && tap_count == 1 && !is_in_gutter {
+                    actions.push(CodeEditorAction::GoToDefinition(cursor));
+                }
                 self.reset_cursor_blinker(cx);
                 self.keep_cursor_in_view = KeepCursorInView::Always(abs, cx.new_next_frame());
                 self.redraw(cx);
@@ -1003,6 +1742,8 @@ impl CodeEditor {
             _ => {}
         }
         if keyboard_moved_cursor {
+            // moving the cursor out of the word being completed closes the list
+            self.update_completion(cx, session);
             self.keep_cursor_in_view = KeepCursorInView::Once;
             self.reset_cursor_blinker(cx);
         }
@@ -1015,6 +1756,11 @@ impl CodeEditor {
                 self.redraw(cx);
             }
         }
+        let cursor_line = self.cursor_position(session).line_index;
+        if self.last_cursor_line != Some(cursor_line) {
+            self.last_cursor_line = Some(cursor_line);
+            actions.push(CodeEditorAction::CursorLineChanged(cursor_line));
+        }
         actions
     }
 
@@ -1022,12 +1768,63 @@ impl CodeEditor {
         let mut line_index = self.line_start;
         let mut origin_y = session.layout().line(self.line_start).y();
         let mut buf = String::new();
+        let line_markers = session.document().line_markers();
+        let marker_x = self.viewport_rect.pos.x - 0.75 * self.cell_size.x;
         for element in session
             .layout()
             .block_elements(self.line_start, self.line_end)
         {
             match element {
                 BlockElement::Line { line, .. } => {
+                    for marker in line_markers.iter() {
+                        let rect = match marker.kind {
+                            LineMarkerKind::Deleted if marker.start_line == line_index => Rect {
+                                pos: dvec2(marker_x, origin_y * self.cell_size.y - 1.5),
+                                size: dvec2(6.0, 3.0),
+                            },
+                            LineMarkerKind::Added | LineMarkerKind::Modified
+                                if marker.contains(line_index) =>
+                            {
+                                Rect {
+                                    pos: dvec2(marker_x, origin_y * self.cell_size.y),
+                                    size: dvec2(3.0, line.height() * self.cell_size.y),
+                                }
+                            }
+                            // tests get a square at the start of the gutter on their first line
+                            LineMarkerKind::Test
+                            | LineMarkerKind::TestPassed
+                            | LineMarkerKind::TestFailed
+                            | LineMarkerKind::TestIgnored
+                                if marker.start_line == line_index =>
+                            {
+                                Rect {
+                                    pos: dvec2(
+                                        self.gutter_rect.pos.x + 2.0,
+                                        origin_y * self.cell_size.y
+                                            + (line.scale() * self.cell_size.y - 6.0) / 2.0,
+                                    ),
+                                    size: dvec2(6.0, 6.0),
+                                }
+                            }
+                            _ => continue,
+                        };
+                        self.draw_line_marker.color = match marker.kind {
+                            LineMarkerKind::Added => self.token_colors.added_line_marker,
+                            LineMarkerKind::Modified => self.token_colors.modified_line_marker,
+                            LineMarkerKind::Deleted => self.token_colors.deleted_line_marker,
+                            LineMarkerKind::Test => self.token_colors.test_line_marker,
+                            LineMarkerKind::TestPassed => self.token_colors.test_passed_line_marker,
+                            LineMarkerKind::TestFailed => self.token_colors.test_failed_line_marker,
+                            LineMarkerKind::TestIgnored => self.token_colors.test_ignored_line_marker,
+                        };
+                        self.draw_line_marker.draw_abs(
+                            cx,
+                            Rect {
+                                pos: rect.pos + dvec2(0.0, self.viewport_rect.pos.y),
+                                size: rect.size,
+                            },
+                        );
+                    }
                     self.draw_gutter.font_scale = line.scale();
                     buf.clear();
                     match self.gutter_chars{
@@ -1108,24 +1905,7 @@ impl CodeEditor {
                                     };
                                     let (text_0, text_1) = text.split_at(token.len);
                                     text = text_1;
-                                    self.draw_text.color = match token.kind {
-                                        TokenKind::Unknown => self.token_colors.unknown,
-                                        TokenKind::BranchKeyword => {
-                                            self.token_colors.branch_keyword
-                                        }
-                                        TokenKind::Comment => self.token_colors.comment,
-                                        TokenKind::Constant => self.token_colors.constant,
-                                        TokenKind::Delimiter => self.token_colors.delimiter,
-                                        TokenKind::Identifier => self.token_colors.identifier,
-                                        TokenKind::LoopKeyword => self.token_colors.loop_keyword,
-                                        TokenKind::Number => self.token_colors.number,
-                                        TokenKind::OtherKeyword => self.token_colors.other_keyword,
-                                        TokenKind::Punctuator => self.token_colors.punctuator,
-                                        TokenKind::String => self.token_colors.string,
-                                        TokenKind::Function => self.token_colors.function,
-                                        TokenKind::Typename => self.token_colors.typename,
-                                        TokenKind::Whitespace => self.token_colors.whitespace,
-                                    };
+                                    self.draw_text.color = self.token_colors.color(token.kind);
                                     self.draw_text.outline = 0.0;
                                     if let TokenKind::Delimiter = token.kind {
                                         if highlighted_delimiter_positions.contains(&Position {
@@ -1222,9 +2002,8 @@ impl CodeEditor {
         }
     }
 
-    fn draw_decoration_layer(&mut self, cx: &mut Cx2d<'_>, session: &CodeSession) {
+    fn draw_decoration_layer(&mut self, cx: &mut Cx2d<'_>, session: &CodeSession, decorations: &[Decoration]) {
         let mut active_decoration = None;
-        let decorations = session.document().decorations();
         let mut decorations = decorations.iter();
         while decorations.as_slice().first().map_or(false, |decoration| {
             decoration.end().line_index < self.line_start
@@ -1272,6 +2051,79 @@ impl CodeEditor {
         .draw_selection_layer(cx, session)
     }
 
+    // the selections and carets of others editing the same document
+    fn draw_remote_cursors(&mut self, cx: &mut Cx2d<'_>, session: &CodeSession) {
+        let document = session.document();
+        let remote_cursors = document.remote_cursors();
+        let layout = session.layout();
+        let line_count = layout.as_text().as_lines().len();
+        let line_end = self.line_end.min(line_count);
+        for remote_cursor in remote_cursors.iter() {
+            let (start, end) = (remote_cursor.start(), remote_cursor.end());
+            if end.line_index < self.line_start || start.line_index >= line_end {
+                continue;
+            }
+            self.draw_remote_selection.color = remote_cursor.color;
+            self.draw_remote_cursor.color = remote_cursor.color;
+            for line_index in start.line_index.max(self.line_start)..=end.line_index.min(line_end - 1)
+            {
+                let line = layout.line(line_index);
+                let start_byte = if line_index == start.line_index {
+                    start.byte_index
+                } else {
+                    0
+                };
+                let end_byte = if line_index == end.line_index {
+                    end.byte_index
+                } else {
+                    line.text().len()
+                };
+                let (start_row, start_column) = line
+                    .logical_to_grid_position(grapheme_floor(line.text(), start_byte), Affinity::After);
+                let (end_row, end_column) = line
+                    .logical_to_grid_position(grapheme_floor(line.text(), end_byte), Affinity::Before);
+                for row_index in start_row..=end_row {
+                    let (start_x, y) = line.grid_to_normalized_position(
+                        row_index,
+                        if row_index == start_row { start_column } else { 0 },
+                    );
+                    let (end_x, _) = line.grid_to_normalized_position(
+                        row_index,
+                        if row_index == end_row { end_column } else { line.column_count() },
+                    );
+                    if end_x > start_x {
+                        self.draw_remote_selection.draw_abs(
+                            cx,
+                            Rect {
+                                pos: dvec2(start_x, line.y() + y) * self.cell_size
+                                    + self.viewport_rect.pos,
+                                size: dvec2(end_x - start_x, line.scale()) * self.cell_size,
+                            },
+                        );
+                    }
+                }
+            }
+            let cursor = remote_cursor.cursor;
+            if cursor.line_index < self.line_start || cursor.line_index >= line_end {
+                continue;
+            }
+            let line = layout.line(cursor.line_index);
+            let (row_index, column_index) = line.logical_to_grid_position(
+                grapheme_floor(line.text(), cursor.byte_index),
+                Affinity::Before,
+            );
+            let (x, y) = line.grid_to_normalized_position(row_index, column_index);
+            self.draw_remote_cursor.draw_abs(
+                cx,
+                Rect {
+                    pos: dvec2(x, line.y() + y) * self.cell_size + self.viewport_rect.pos
+                        - dvec2(1.0, 0.0),
+                    size: dvec2(2.0, line.scale() * self.cell_size.y),
+                },
+            );
+        }
+    }
+
     fn pick(&self, session: &CodeSession, position: DVec2) -> ((Position, Affinity), bool) {
         let position = (position - self.viewport_rect.pos) / self.cell_size;
         
@@ -1487,9 +2339,30 @@ impl CodeEditor {
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, DefaultNone)]
 pub enum CodeEditorAction {
     TextDidChange,
+    // the user asked for the find bar, with or without the replace row
+    Find,
+    Replace,
+    // requests for a language server, answered by whoever hosts the editor
+    GoToDefinition(Position),
+    Hover(Position),
+    // the completion list opened, hosts can add their items with `add_completions`
+    Complete(Position),
+    DocumentSymbols,
+    // the cursor moved to another line, or the editor saw its session for the first time
+    CursorLineChanged(usize),
     None
 }
 
+// the last grapheme boundary at or before a byte index, positions from others may not be on one
+fn grapheme_floor(text: &str, byte_index: usize) -> usize {
+    text.grapheme_indices()
+        .map(|(index, _)| index)
+        .chain(iter::once(text.len()))
+        .take_while(|index| *index <= byte_index)
+        .last()
+        .unwrap_or(0)
+}
+
 struct DrawDecorationLayer<'a> {
     code_editor: &'a mut CodeEditor,
     active_decoration: Option<ActiveDecoration>,
@@ -1656,26 +2529,30 @@ impl<'a> DrawDecorationLayer<'a> {
     ) {
         let start_x = mem::take(&mut self.active_decoration.as_mut().unwrap().start_x);
         let (x, y) = line.grid_to_normalized_position(row_index, column_index);
-        self.code_editor.draw_decoration.color =
-            match self.active_decoration.as_mut().unwrap().decoration.ty {
-                DecorationType::Warning => self.code_editor.token_colors.warning_decoration,
-                DecorationType::Error => self.code_editor.token_colors.error_decoration,
-            };
-
-        self.code_editor.draw_decoration.draw_abs(
-            cx,
-            Rect {
-                pos: DVec2 {
-                    x: start_x,
-                    y: origin_y + y,
-                } * self.code_editor.cell_size
-                    + self.code_editor.viewport_rect.pos,
-                size: DVec2 {
-                    x: x - start_x,
-                    y: line.scale(),
-                } * self.code_editor.cell_size,
-            },
-        );
+        let rect = Rect {
+            pos: DVec2 {
+                x: start_x,
+                y: origin_y + y,
+            } * self.code_editor.cell_size
+                + self.code_editor.viewport_rect.pos,
+            size: DVec2 {
+                x: x - start_x,
+                y: line.scale(),
+            } * self.code_editor.cell_size,
+        };
+        let color = match self.active_decoration.as_mut().unwrap().decoration.ty {
+            DecorationType::Warning => self.code_editor.token_colors.warning_decoration,
+            DecorationType::Error => self.code_editor.token_colors.error_decoration,
+            DecorationType::SearchMatch => {
+                // search matches are a box behind the text, not a squiggle
+                self.code_editor.draw_search_match.color =
+                    self.code_editor.token_colors.search_match_decoration;
+                self.code_editor.draw_search_match.draw_abs(cx, rect);
+                return;
+            }
+        };
+        self.code_editor.draw_decoration.color = color;
+        self.code_editor.draw_decoration.draw_abs(cx, rect);
     }
 }
 
@@ -1970,6 +2847,43 @@ struct TokenColors {
     error_decoration: Vec4,
     #[live]
     warning_decoration: Vec4,
+    #[live]
+    search_match_decoration: Vec4,
+    #[live]
+    added_line_marker: Vec4,
+    #[live]
+    modified_line_marker: Vec4,
+    #[live]
+    deleted_line_marker: Vec4,
+    #[live]
+    test_line_marker: Vec4,
+    #[live]
+    test_passed_line_marker: Vec4,
+    #[live]
+    test_failed_line_marker: Vec4,
+    #[live]
+    test_ignored_line_marker: Vec4,
+}
+
+impl TokenColors {
+    fn color(&self, kind: TokenKind) -> Vec4 {
+        match kind {
+            TokenKind::Unknown => self.unknown,
+            TokenKind::BranchKeyword => self.branch_keyword,
+            TokenKind::Comment => self.comment,
+            TokenKind::Constant => self.constant,
+            TokenKind::Delimiter => self.delimiter,
+            TokenKind::Identifier => self.identifier,
+            TokenKind::LoopKeyword => self.loop_keyword,
+            TokenKind::Number => self.number,
+            TokenKind::OtherKeyword => self.other_keyword,
+            TokenKind::Punctuator => self.punctuator,
+            TokenKind::String => self.string,
+            TokenKind::Function => self.function,
+            TokenKind::Typename => self.typename,
+            TokenKind::Whitespace => self.whitespace,
+        }
+    }
 }
 
 #[derive(Live, LiveHook, LiveRegister)]
@@ -0,0 +1,690 @@
+use {
+    crate::text::{Position, Text},
+    makepad_widgets::*,
+    std::collections::{BTreeMap, HashMap, HashSet},
+};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CompletionKind {
+    Word,
+    Keyword,
+    Function,
+    Field,
+    Property,
+    Variable,
+    Constant,
+    Type,
+    Module,
+    Snippet,
+}
+
+impl CompletionKind {
+    // the short tag shown in front of an item in the completion list
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::Word => "abc",
+            Self::Keyword => "kw",
+            Self::Function => "fn",
+            Self::Field => "fld",
+            Self::Property => "prop",
+            Self::Variable => "var",
+            Self::Constant => "const",
+            Self::Type => "type",
+            Self::Module => "mod",
+            Self::Snippet => "snip",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: CompletionKind,
+    pub detail: Option<String>,
+    // replaces the text from `start` up to the cursor
+    pub start: Position,
+    pub text: String,
+    // whether `text` is a snippet with tab stops, see `Snippet`
+    pub is_snippet: bool,
+}
+
+// Something that knows what can be typed at a position. Providers are asked when the completion
+// list opens, hosts that get their items later (from a language server for instance) add them to
+// the open list with `CodeEditor::add_completions`.
+pub trait CompletionProvider {
+    fn complete(&self, text: &Text, position: Position) -> Vec<CompletionItem>;
+}
+
+fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+// the start of the word that ends at `position`
+pub fn word_start(text: &Text, position: Position) -> Position {
+    let line = &text.as_lines()[position.line_index];
+    let byte_index = line[..position.byte_index]
+        .char_indices()
+        .rev()
+        .take_while(|(_, char)| is_word_char(*char))
+        .last()
+        .map_or(position.byte_index, |(index, _)| index);
+    Position {
+        line_index: position.line_index,
+        byte_index,
+    }
+}
+
+// Completes words that already occur somewhere in the document.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WordCompletionProvider;
+
+impl CompletionProvider for WordCompletionProvider {
+    fn complete(&self, text: &Text, position: Position) -> Vec<CompletionItem> {
+        let start = word_start(text, position);
+        let mut seen = HashSet::new();
+        let mut items = Vec::new();
+        for (line_index, line) in text.as_lines().iter().enumerate() {
+            let mut word_start = None;
+            for (byte_index, char) in line.char_indices().chain([(line.len(), ' ')]) {
+                match (word_start, is_word_char(char)) {
+                    (None, true) => word_start = Some(byte_index),
+                    (Some(start_index), false) => {
+                        word_start = None;
+                        let word = &line[start_index..byte_index];
+                        // skip the word being typed and things like numbers
+                        if (line_index == start.line_index && start_index == start.byte_index)
+                            || word.len() < 3
+                            || word.starts_with(|char: char| char.is_ascii_digit())
+                            || !seen.insert(word)
+                        {
+                            continue;
+                        }
+                        items.push(CompletionItem {
+                            label: word.to_string(),
+                            kind: CompletionKind::Word,
+                            detail: None,
+                            start,
+                            text: word.to_string(),
+                            is_snippet: false,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+        items
+    }
+}
+
+// Completes the properties of the widget or draw type whose body the cursor is in, inside
+// `live_design!`. The types and their fields come from the live registry.
+#[derive(Clone, Debug, Default)]
+pub struct LiveDesignCompletionProvider {
+    // component names, like `ButtonFlat`, and type names, like `Button`, to their type
+    components: HashMap<LiveId, LiveType>,
+    type_infos: BTreeMap<LiveType, LiveTypeInfo>,
+}
+
+impl LiveDesignCompletionProvider {
+    pub fn from_registry(registry: &LiveRegistry) -> Self {
+        let mut components = HashMap::new();
+        for (live_type, info) in &registry.live_type_infos {
+            components.insert(info.type_name, *live_type);
+        }
+        for file in &registry.live_files {
+            let mut depth = 0;
+            for node in &file.expanded.nodes {
+                if depth == 1 {
+                    if let LiveValue::Class { live_type, .. } = node.value {
+                        components.entry(node.id).or_insert(live_type);
+                    }
+                }
+                if node.value.is_open() {
+                    depth += 1;
+                } else if node.value.is_close() {
+                    depth -= 1;
+                }
+            }
+        }
+        Self {
+            components,
+            type_infos: registry.live_type_infos.clone(),
+        }
+    }
+
+    // the chain of bodies the cursor is in, from the innermost outwards up to the first one
+    // that names its type
+    fn context(text: &Text, position: Position) -> Option<(LiveId, Vec<LiveId>)> {
+        let lines = text.as_lines();
+        let mut fields = Vec::new();
+        let mut depth = 0;
+        let mut line_index = position.line_index;
+        let mut line = &lines[line_index][..position.byte_index];
+        loop {
+            let mut opened = None;
+            for (byte_index, char) in line.char_indices().rev() {
+                match char {
+                    '}' => depth += 1,
+                    '{' if depth > 0 => depth -= 1,
+                    '{' => {
+                        opened = Some(byte_index);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            match opened {
+                Some(byte_index) => {
+                    let before = line[..byte_index].trim_end();
+                    if let Some(before) = before.strip_suffix("}}") {
+                        // {{Type}}
+                        let name = before.rsplit("{{").next()?.trim();
+                        return Some((LiveId::from_str(name), fields));
+                    }
+                    if let Some(before) = before.strip_suffix('>') {
+                        // <Component>
+                        let name = before.rsplit('<').next()?.trim();
+                        return Some((LiveId::from_str(name), fields));
+                    }
+                    let name = before.strip_suffix(':')?.trim_end();
+                    let start = name.len() - name.chars().rev().take_while(|char| is_word_char(*char)).map(char::len_utf8).sum::<usize>();
+                    if start == name.len() {
+                        return None;
+                    }
+                    fields.push(LiveId::from_str(&name[start..]));
+                    line = &line[..start];
+                }
+                None => {
+                    if line_index == 0 {
+                        return None;
+                    }
+                    line_index -= 1;
+                    line = &lines[line_index];
+                }
+            }
+        }
+    }
+
+    fn fields(&self, info: &LiveTypeInfo, out: &mut Vec<(LiveId, LiveFieldKind)>) {
+        for field in &info.fields {
+            match field.live_field_kind {
+                LiveFieldKind::Deref => self.fields(&field.live_type_info, out),
+                LiveFieldKind::Calc => {}
+                kind => out.push((field.id, kind)),
+            }
+        }
+    }
+
+    fn find_field<'a>(info: &'a LiveTypeInfo, id: LiveId) -> Option<&'a LiveTypeInfo> {
+        info.fields.iter().find_map(|field| match field.live_field_kind {
+            LiveFieldKind::Deref => Self::find_field(&field.live_type_info, id),
+            _ if field.id == id => Some(&field.live_type_info),
+            _ => None,
+        })
+    }
+}
+
+impl CompletionProvider for LiveDesignCompletionProvider {
+    fn complete(&self, text: &Text, position: Position) -> Vec<CompletionItem> {
+        let start = word_start(text, position);
+        if !text.as_lines()[..=position.line_index]
+            .iter()
+            .any(|line| line.contains("live_design!"))
+        {
+            return Vec::new();
+        }
+        let Some((component, fields)) = Self::context(text, start) else {
+            return Vec::new();
+        };
+        let Some(mut info) = self
+            .components
+            .get(&component)
+            .and_then(|live_type| self.type_infos.get(live_type))
+        else {
+            return Vec::new();
+        };
+        for field in fields.iter().rev() {
+            let Some(field_info) = Self::find_field(info, *field) else {
+                return Vec::new();
+            };
+            // field types that are registered have more fields than their nested info shows
+            info = self.type_infos.get(&field_info.live_type).unwrap_or(field_info);
+        }
+        let mut fields = Vec::new();
+        self.fields(info, &mut fields);
+        fields
+            .into_iter()
+            .map(|(id, kind)| {
+                let name = id.to_string();
+                CompletionItem {
+                    text: format!("{}: ", name),
+                    label: name,
+                    kind: CompletionKind::Property,
+                    detail: match kind {
+                        LiveFieldKind::Animator => Some("animator".to_string()),
+                        LiveFieldKind::LiveOption => Some("optional".to_string()),
+                        _ => None,
+                    },
+                    start,
+                    is_snippet: false,
+                }
+            })
+            .collect()
+    }
+}
+
+// Scores how well `pattern` matches `label` as a subsequence, ignoring case. Characters at the
+// start of the label or of a word in it, and runs of consecutive characters, score higher.
+pub fn fuzzy_score(pattern: &str, label: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut pattern_chars = pattern.chars().flat_map(char::to_lowercase).peekable();
+    let mut prev_char = None;
+    let mut prev_matched = false;
+    for (index, char) in label.chars().enumerate() {
+        let Some(&pattern_char) = pattern_chars.peek() else {
+            break;
+        };
+        let matched = char.to_lowercase().eq(pattern_char.to_lowercase());
+        if matched {
+            pattern_chars.next();
+            score += 1;
+            let is_word_start = match prev_char {
+                None => true,
+                Some(prev) => {
+                    !is_word_char(prev) || prev == '_' || (prev.is_lowercase() && char.is_uppercase())
+                }
+            };
+            if index == 0 {
+                score += 8;
+            } else if is_word_start {
+                score += 4;
+            }
+            if prev_matched {
+                score += 2;
+            }
+        }
+        prev_matched = matched;
+        prev_char = Some(char);
+    }
+    if pattern_chars.peek().is_some() {
+        return None;
+    }
+    // prefer shorter labels among equal matches
+    Some(score * 16 - label.len().min(15) as i32)
+}
+
+// The items of an open completion list and which of them match what has been typed so far.
+#[derive(Clone, Debug, Default)]
+pub struct CompletionList {
+    items: Vec<CompletionItem>,
+    // indices into `items`, best match first
+    filtered: Vec<usize>,
+    selected: usize,
+    // where the word being completed starts
+    pub start: Position,
+}
+
+impl CompletionList {
+    pub fn new(start: Position) -> Self {
+        Self {
+            start,
+            ..Self::default()
+        }
+    }
+
+    pub fn add_items(&mut self, items: Vec<CompletionItem>) {
+        for item in items {
+            if !self
+                .items
+                .iter()
+                .any(|other| other.label == item.label && other.kind == item.kind)
+            {
+                self.items.push(item);
+            }
+        }
+    }
+
+    pub fn filter(&mut self, prefix: &str) {
+        let mut scored: Vec<_> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| Some((fuzzy_score(prefix, &item.label)?, index)))
+            .collect();
+        scored.sort_by(|(score_a, index_a), (score_b, index_b)| {
+            score_b.cmp(score_a).then(
+                self.items[*index_a]
+                    .label
+                    .cmp(&self.items[*index_b].label),
+            )
+        });
+        self.filtered = scored.into_iter().map(|(_, index)| index).collect();
+        self.selected = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filtered.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.filtered.len()
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.filtered.len().saturating_sub(1));
+    }
+
+    // moves the selection by `delta` rows, wrapping around at the ends
+    pub fn move_selection(&mut self, delta: isize) {
+        let len = self.filtered.len() as isize;
+        if len > 0 {
+            self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        }
+    }
+
+    pub fn item(&self, index: usize) -> Option<&CompletionItem> {
+        self.filtered.get(index).map(|index| &self.items[*index])
+    }
+
+    pub fn selected_item(&self) -> Option<&CompletionItem> {
+        self.item(self.selected)
+    }
+}
+
+// An LSP style snippet. `$1`, `$2` and `${1:default}` are tab stops, visited in order, and `$0` is
+// where the cursor ends up. Choices, `${1|one,two|}`, insert their first option. Variables, `$NAME`
+// and `${NAME:default}`, are not resolved: they become tab stops after the numbered ones, holding
+// their default or their name. Positions are relative to the start of the inserted text.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snippet {
+    pub text: String,
+    pub tab_stops: Vec<(Position, Position)>,
+}
+
+impl Snippet {
+    pub fn parse(source: &str) -> Self {
+        let mut text = String::new();
+        let mut stops: Vec<(usize, Position, Position)> = Vec::new();
+        let mut line_index = 0;
+        let mut line_start = 0;
+        let position = |text: &String, line_index, line_start| Position {
+            line_index,
+            byte_index: text.len() - line_start,
+        };
+        let mut open = Vec::new();
+        // variables are numbered after any tab stop a snippet would use
+        let mut variable_number = usize::MAX / 2;
+        let mut chars = source.chars().peekable();
+        while let Some(char) = chars.next() {
+            match char {
+                '\\' if matches!(chars.peek(), Some('$' | '}' | '\\')) => {
+                    text.push(chars.next().unwrap());
+                }
+                '$' if chars.peek().map_or(false, |char| char.is_ascii_digit()) => {
+                    let mut number = 0;
+                    while let Some(digit) = chars.peek().and_then(|char| char.to_digit(10)) {
+                        number = number * 10 + digit as usize;
+                        chars.next();
+                    }
+                    let at = position(&text, line_index, line_start);
+                    stops.push((number, at, at));
+                }
+                '$' if chars.peek().map_or(false, |char| is_variable_start(*char)) => {
+                    let at = position(&text, line_index, line_start);
+                    text.push_str(&parse_variable_name(&mut chars));
+                    variable_number += 1;
+                    stops.push((variable_number, at, position(&text, line_index, line_start)));
+                }
+                '$' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    let start = position(&text, line_index, line_start);
+                    if chars.peek().map_or(false, |char| is_variable_start(*char)) {
+                        let name = parse_variable_name(&mut chars);
+                        variable_number += 1;
+                        if chars.peek() == Some(&':') {
+                            chars.next();
+                        } else {
+                            text.push_str(&name);
+                        }
+                        open.push((variable_number, start));
+                        continue;
+                    }
+                    let mut number = 0;
+                    while let Some(digit) = chars.peek().and_then(|char| char.to_digit(10)) {
+                        number = number * 10 + digit as usize;
+                        chars.next();
+                    }
+                    match chars.peek() {
+                        Some(':') => {
+                            chars.next();
+                        }
+                        Some('|') => {
+                            chars.next();
+                            let mut is_first = true;
+                            while let Some(char) = chars.next() {
+                                match char {
+                                    '\\' => {
+                                        if let Some(char) = chars.next() {
+                                            if is_first {
+                                                text.push(char);
+                                            }
+                                        }
+                                    }
+                                    ',' => is_first = false,
+                                    '|' if chars.peek() == Some(&'}') => {
+                                        chars.next();
+                                        break;
+                                    }
+                                    char if is_first => text.push(char),
+                                    _ => {}
+                                }
+                            }
+                            stops.push((number, start, position(&text, line_index, line_start)));
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    open.push((number, start));
+                }
+                '}' if !open.is_empty() => {
+                    let (number, start) = open.pop().unwrap();
+                    stops.push((number, start, position(&text, line_index, line_start)));
+                }
+                '\n' => {
+                    text.push('\n');
+                    line_index += 1;
+                    line_start = text.len();
+                }
+                char => text.push(char),
+            }
+        }
+        // $0 comes last, stops with the same number are visited once
+        stops.sort_by_key(|(number, start, _)| (*number == 0, *number, *start));
+        stops.dedup_by_key(|(number, _, _)| *number);
+        Self {
+            text,
+            tab_stops: stops.into_iter().map(|(_, start, end)| (start, end)).collect(),
+        }
+    }
+}
+
+fn is_variable_start(char: char) -> bool {
+    char.is_ascii_alphabetic() || char == '_'
+}
+
+fn parse_variable_name(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut name = String::new();
+    while let Some(char) = chars.next_if(|char| char.is_ascii_alphanumeric() || *char == '_') {
+        name.push(char);
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Button;
+    struct DrawQuad;
+
+    fn position(line_index: usize, byte_index: usize) -> Position {
+        Position {
+            line_index,
+            byte_index,
+        }
+    }
+
+    fn item(label: &str) -> CompletionItem {
+        CompletionItem {
+            label: label.to_string(),
+            kind: CompletionKind::Word,
+            detail: None,
+            start: Position::default(),
+            text: label.to_string(),
+            is_snippet: false,
+        }
+    }
+
+    fn field(id: &str, live_type_info: LiveTypeInfo, live_field_kind: LiveFieldKind) -> LiveTypeField {
+        LiveTypeField {
+            id: LiveId::from_str_with_lut(id).unwrap(),
+            live_type_info,
+            live_field_kind,
+        }
+    }
+
+    fn provider() -> LiveDesignCompletionProvider {
+        let module_id = LiveModuleId::from_str("widgets::button").unwrap();
+        let value = LiveTypeInfo::new(module_id, LiveType::of::<f64>(), LiveId::from_str("f64"), true, Vec::new());
+        let draw_quad = LiveTypeInfo::new(
+            module_id,
+            LiveType::of::<DrawQuad>(),
+            LiveId::from_str("DrawQuad"),
+            false,
+            vec![field("color", value.clone(), LiveFieldKind::Live)],
+        );
+        let button = LiveTypeInfo::new(
+            module_id,
+            LiveType::of::<Button>(),
+            LiveId::from_str("Button"),
+            false,
+            vec![
+                field("width", value.clone(), LiveFieldKind::Live),
+                field("draw_bg", draw_quad.clone(), LiveFieldKind::Live),
+            ],
+        );
+        let mut provider = LiveDesignCompletionProvider::default();
+        provider.components.insert(LiveId::from_str("Button"), button.live_type);
+        provider.components.insert(LiveId::from_str("ButtonFlat"), button.live_type);
+        provider.type_infos.insert(button.live_type, button);
+        provider.type_infos.insert(draw_quad.live_type, draw_quad);
+        provider
+    }
+
+    fn labels(items: &[CompletionItem]) -> Vec<&str> {
+        items.iter().map(|item| item.label.as_str()).collect()
+    }
+
+    #[test]
+    fn fuzzy_scores() {
+        assert_eq!(fuzzy_score("xyz", "width"), None);
+        assert_eq!(fuzzy_score("wdt", "wi"), None);
+        assert!(fuzzy_score("", "width").is_some());
+        // the start of the label beats the middle of a word
+        assert!(fuzzy_score("w", "width") > fuzzy_score("w", "draw"));
+        // word starts in snake and camel case beat other characters
+        assert!(fuzzy_score("db", "draw_bg") > fuzzy_score("db", "dashboard"));
+        assert!(fuzzy_score("dB", "drawBg") > fuzzy_score("db", "dumbo"));
+        // runs of characters beat scattered ones, shorter labels win ties
+        assert!(fuzzy_score("wid", "width") > fuzzy_score("wid", "wxixd"));
+        assert!(fuzzy_score("WIDTH", "width") > fuzzy_score("width", "width_max"));
+    }
+
+    #[test]
+    fn filter_keeps_the_exact_match() {
+        let mut list = CompletionList::new(Position::default());
+        list.add_items(vec![item("width_max"), item("width"), item("height"), item("width")]);
+        list.filter("width");
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.selected_item().unwrap().label, "width");
+        assert_eq!(list.item(1).unwrap().label, "width_max");
+        list.move_selection(-1);
+        assert_eq!(list.selected_index(), 1);
+    }
+
+    #[test]
+    fn snippet_tab_stops() {
+        let snippet = Snippet::parse("fn ${1:name}($2) {\n    $0\n}");
+        assert_eq!(snippet.text, "fn name() {\n    \n}");
+        assert_eq!(
+            snippet.tab_stops,
+            vec![
+                (position(0, 3), position(0, 7)),
+                (position(0, 8), position(0, 8)),
+                (position(1, 4), position(1, 4)),
+            ]
+        );
+        let snippet = Snippet::parse("\\$1 ${1:a ${2:b}} \\}");
+        assert_eq!(snippet.text, "$1 a b }");
+        assert_eq!(
+            snippet.tab_stops,
+            vec![(position(0, 3), position(0, 6)), (position(0, 5), position(0, 6))]
+        );
+    }
+
+    #[test]
+    fn snippet_choices_and_variables() {
+        let snippet = Snippet::parse("${1|pub,pub(crate)\\,x|} fn $0");
+        assert_eq!(snippet.text, "pub fn ");
+        assert_eq!(
+            snippet.tab_stops,
+            vec![(position(0, 0), position(0, 3)), (position(0, 7), position(0, 7))]
+        );
+        let snippet = Snippet::parse("// $TM_FILENAME ${CURRENT_YEAR} ${AUTHOR:me} $1");
+        assert_eq!(snippet.text, "// TM_FILENAME CURRENT_YEAR me ");
+        assert_eq!(
+            snippet.tab_stops,
+            vec![
+                (position(0, 31), position(0, 31)),
+                (position(0, 3), position(0, 14)),
+                (position(0, 15), position(0, 27)),
+                (position(0, 28), position(0, 30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn live_design_context() {
+        let text = Text::from("live_design! {\n    MyButton = <ButtonFlat> {\n        draw_bg: {\n            \n        }\n        other = {{Button}} { label = <Label> {} }\n    }\n}");
+        assert_eq!(
+            LiveDesignCompletionProvider::context(&text, position(3, 12)),
+            Some((LiveId::from_str("ButtonFlat"), vec![LiveId::from_str("draw_bg")]))
+        );
+        assert_eq!(
+            LiveDesignCompletionProvider::context(&text, position(5, 29)),
+            Some((LiveId::from_str("Button"), Vec::new()))
+        );
+        assert_eq!(
+            LiveDesignCompletionProvider::context(&text, position(5, 46)),
+            Some((LiveId::from_str("Label"), Vec::new()))
+        );
+        assert_eq!(LiveDesignCompletionProvider::context(&text, position(0, 14)), None);
+    }
+
+    #[test]
+    fn live_design_fields() {
+        let provider = provider();
+        let text = Text::from("live_design! {\n    A = <ButtonFlat> {\n        wi\n        draw_bg: { co }\n    }\n}");
+        let items = provider.complete(&text, position(2, 10));
+        assert_eq!(labels(&items), vec!["width", "draw_bg"]);
+        assert_eq!(items[0].start, position(2, 8));
+        assert_eq!(items[0].text, "width: ");
+        assert_eq!(labels(&provider.complete(&text, position(3, 21))), vec!["color"]);
+        let text = Text::from("fn main() {\n    wi\n}");
+        assert!(provider.complete(&text, position(1, 6)).is_empty());
+    }
+}
@@ -5,19 +5,21 @@ use {
         history::{EditKind, History},
         inlays::{BlockInlay, InlineInlay},
         iter::IteratorExt,
+        line_marker::LineMarker,
+        remote_cursor::RemoteCursor,
         selection::SelectionSet,
         session::SessionId,
         settings::Settings,
         str::StrExt,
         text::{Change, Drift, Edit, Length, Position, Text},
-        token::{Token, TokenKind},
-        tokenizer::Tokenizer,
+        token::Token,
+        tokenizer::{Language, LineTokenizer, Tokenizer, TokenizerCache},
     },
     std::{
         cell::{Ref, RefCell},
         cmp::Ordering,
         collections::HashMap,
-        iter,
+        iter, mem,
         ops::Range,
         rc::Rc,
         sync::mpsc::Sender,
@@ -29,20 +31,47 @@ pub struct CodeDocument(Rc<DocumentInner>);
 
 impl CodeDocument {
     pub fn new(text: Text, decorations: DecorationSet) -> Self {
+        Self::new_with_language(text, decorations, Language::Rust)
+    }
+
+    pub fn new_with_language(text: Text, decorations: DecorationSet, language: Language) -> Self {
+        let line_count = text.as_lines().len();
+        Self::new_with_line_tokenizer(text, decorations, language.line_tokenizer(line_count))
+    }
+
+    pub fn new_with_tokenizer<T: Tokenizer>(
+        text: Text,
+        decorations: DecorationSet,
+        tokenizer: T,
+    ) -> Self {
+        let line_count = text.as_lines().len();
+        Self::new_with_line_tokenizer(
+            text,
+            decorations,
+            Box::new(TokenizerCache::new(tokenizer, line_count)),
+        )
+    }
+
+    fn new_with_line_tokenizer(
+        text: Text,
+        decorations: DecorationSet,
+        tokenizer: Box<dyn LineTokenizer>,
+    ) -> Self {
         let line_count = text.as_lines().len();
-        let tokens: Vec<_> = (0..line_count)
-            .map(|line| tokenize(&text.as_lines()[line]).collect::<Vec<_>>())
-            .collect();
         let inner = Self(Rc::new(DocumentInner {
             history: RefCell::new(History::from(text)),
             layout: RefCell::new(DocumentLayout {
                 indent_state: (0..line_count).map(|_| None).collect(),
-                tokens,
+                // filled in by the tokenizer below
+                tokens: (0..line_count).map(|_| Vec::new()).collect(),
                 inline_inlays: (0..line_count).map(|_| Vec::new()).collect(),
                 block_inlays: Vec::new(),
             }),
-            tokenizer: RefCell::new(Tokenizer::new(line_count)),
+            tokenizer: RefCell::new(tokenizer),
             decorations: RefCell::new(decorations),
+            line_markers: RefCell::new(Vec::new()),
+            remote_cursors: RefCell::new(Vec::new()),
+            edit_log: RefCell::new(None),
             edit_senders: RefCell::new(HashMap::new()),
         }));
         inner.update_indent_state();
@@ -101,6 +130,29 @@ impl CodeDocument {
         kind: EditKind,
         selections: &SelectionSet,
         settings: &Settings,
+        f: impl FnMut(Editor<'_>, Position, Length),
+    ) {
+        self.edit_selections_internal(session_id, kind, selections, Some(settings.tab_column_count), f);
+    }
+
+    // Like edit_selections, but leaves the indentation of the edited lines alone. Used when
+    // replacing search matches, where the replacement text should go in exactly as typed.
+    pub fn edit_selections_verbatim(
+        &self,
+        session_id: SessionId,
+        kind: EditKind,
+        selections: &SelectionSet,
+        f: impl FnMut(Editor<'_>, Position, Length),
+    ) {
+        self.edit_selections_internal(session_id, kind, selections, None, f);
+    }
+
+    fn edit_selections_internal(
+        &self,
+        session_id: SessionId,
+        kind: EditKind,
+        selections: &SelectionSet,
+        tab_column_count: Option<usize>,
         mut f: impl FnMut(Editor<'_>, Position, Length),
     ) {
         let mut history = self.0.history.borrow_mut();
@@ -162,7 +214,9 @@ impl CodeDocument {
             prev_edit_start = edit_start;
         }
         drop(history);
-        self.autoindent(&line_ranges, settings.tab_column_count, &mut edits);
+        if let Some(tab_column_count) = tab_column_count {
+            self.autoindent(&line_ranges, tab_column_count, &mut edits);
+        }
         self.update_after_edit(Some(session_id), None, &edits);
     }
 
@@ -210,6 +264,138 @@ impl CodeDocument {
         self.0.decorations.borrow_mut().clear()
     }
 
+    pub fn remove_decorations_with_id(&mut self, id: usize) {
+        self.0.decorations.borrow_mut().remove_decorations_with_id(id)
+    }
+
+    // replaces the markers with this id. Markers don't follow edits, whoever sets them is
+    // expected to set them again after one
+    pub fn set_line_markers(&self, id: usize, line_markers: Vec<LineMarker>) {
+        let mut markers = self.0.line_markers.borrow_mut();
+        markers.retain(|marker| marker.id != id);
+        markers.extend(line_markers);
+    }
+
+    pub fn line_markers(&self) -> Ref<'_, [LineMarker]> {
+        Ref::map(self.0.line_markers.borrow(), |line_markers| {
+            line_markers.as_slice()
+        })
+    }
+
+    // replaces the cursors with the given id, positions past the end of the text are moved back
+    pub fn set_remote_cursors(&self, id: usize, remote_cursors: Vec<RemoteCursor>) {
+        let history = self.0.history.borrow();
+        let lines = history.as_text().as_lines();
+        let clamp = |position: Position| {
+            let line_index = position.line_index.min(lines.len() - 1);
+            let line = &lines[line_index];
+            let mut byte_index = position.byte_index.min(line.len());
+            while !line.is_char_boundary(byte_index) {
+                byte_index -= 1;
+            }
+            Position {
+                line_index,
+                byte_index,
+            }
+        };
+        let mut cursors = self.0.remote_cursors.borrow_mut();
+        cursors.retain(|cursor| cursor.id != id);
+        cursors.extend(remote_cursors.into_iter().map(|cursor| RemoteCursor {
+            anchor: clamp(cursor.anchor),
+            cursor: clamp(cursor.cursor),
+            ..cursor
+        }));
+    }
+
+    pub fn remote_cursors(&self) -> Ref<'_, [RemoteCursor]> {
+        Ref::map(self.0.remote_cursors.borrow(), |remote_cursors| {
+            remote_cursors.as_slice()
+        })
+    }
+
+    // starts keeping the edits made to the document, so they can be sent to others editing it
+    pub fn record_edits(&self) {
+        let mut edit_log = self.0.edit_log.borrow_mut();
+        if edit_log.is_none() {
+            *edit_log = Some(Vec::new());
+        }
+    }
+
+    pub fn take_recorded_edits(&self) -> Vec<Edit> {
+        self.0
+            .edit_log
+            .borrow_mut()
+            .as_mut()
+            .map_or(Vec::new(), mem::take)
+    }
+
+    // applies edits made by someone else, they aren't recorded and can't be undone
+    pub fn apply_remote_edits(&self, edits: Vec<Edit>) {
+        let mut history = self.0.history.borrow_mut();
+        for edit in &edits {
+            history.apply_remote_edit(edit.clone());
+        }
+        drop(history);
+        let edit_log = self.0.edit_log.borrow_mut().take();
+        self.update_after_edit(None, None, &edits);
+        *self.0.edit_log.borrow_mut() = edit_log;
+    }
+
+    // replaces the inline inlays with this id on a line, the byte positions must be in order.
+    // Inlays of other ids stay, ones at the same byte as them go first
+    pub fn set_inline_inlays(&self, id: usize, line_index: usize, inlays: Vec<(usize, InlineInlay)>) {
+        let mut layout = self.0.layout.borrow_mut();
+        let Some(line_inlays) = layout.inline_inlays.get_mut(line_index) else {
+            return;
+        };
+        let old_len = line_inlays.len();
+        line_inlays.retain(|(_, inlay_id, _)| *inlay_id != id);
+        if line_inlays.len() == old_len && inlays.is_empty() {
+            return;
+        }
+        for (byte, inlay) in inlays {
+            let index = line_inlays.partition_point(|(other_byte, _, _)| *other_byte <= byte);
+            line_inlays.insert(index, (byte, id, inlay));
+        }
+        drop(layout);
+        self.relayout_line(line_index);
+    }
+
+    // removes the inline inlays with this id from every line
+    pub fn clear_inline_inlays(&self, id: usize) {
+        let lines: Vec<_> = self
+            .0
+            .layout
+            .borrow()
+            .inline_inlays
+            .iter()
+            .enumerate()
+            .filter(|(_, inlays)| inlays.iter().any(|(_, inlay_id, _)| *inlay_id == id))
+            .map(|(line_index, _)| line_index)
+            .collect();
+        for line_index in lines {
+            self.set_inline_inlays(id, line_index, Vec::new());
+        }
+    }
+
+    // sessions cache the layout of every line and only redo it for lines touched by an edit, an
+    // empty insertion tells them the line changed without changing the text
+    fn relayout_line(&self, line_index: usize) {
+        let edit = Edit {
+            change: Change::Insert(
+                Position {
+                    line_index,
+                    byte_index: 0,
+                },
+                Text::new(),
+            ),
+            drift: Drift::Before,
+        };
+        for edit_sender in self.0.edit_senders.borrow().values() {
+            edit_sender.send((None, vec![edit.clone()])).unwrap();
+        }
+    }
+
     pub fn add_session(
         &mut self,
         session_id: SessionId,
@@ -414,6 +600,14 @@ impl CodeDocument {
             decorations.apply_edit(edit);
         }
         drop(decorations);
+        for remote_cursor in self.0.remote_cursors.borrow_mut().iter_mut() {
+            for edit in edits {
+                *remote_cursor = remote_cursor.apply_edit(edit);
+            }
+        }
+        if let Some(edit_log) = self.0.edit_log.borrow_mut().as_mut() {
+            edit_log.extend(edits.iter().cloned());
+        }
         for (&session_id, edit_sender) in &*self.0.edit_senders.borrow() {
             if Some(session_id) == origin_id {
                 edit_sender
@@ -437,122 +631,23 @@ impl CodeDocument {
         }
     }
 
+    // the line tokenizer redoes every line an edit touches right after, so this only keeps one
+    // list of tokens per line
     fn apply_change_to_tokens(&self, change: &Change) {
-        let mut layout = self.0.layout.borrow_mut();
-        let tokens = &mut layout.tokens;
+        let tokens = &mut self.0.layout.borrow_mut().tokens;
         match *change {
             Change::Insert(point, ref text) => {
-                let mut byte = 0;
-                let mut index = tokens[point.line_index]
-                    .iter()
-                    .position(|token| {
-                        if byte + token.len > point.byte_index {
-                            return true;
-                        }
-                        byte += token.len;
-                        false
-                    })
-                    .unwrap_or(tokens[point.line_index].len());
-                if byte != point.byte_index {
-                    let token = tokens[point.line_index][index];
-                    let mid = point.byte_index - byte;
-                    tokens[point.line_index][index] = Token {
-                        len: mid,
-                        kind: token.kind,
-                    };
-                    index += 1;
-                    tokens[point.line_index].insert(
-                        index,
-                        Token {
-                            len: token.len - mid,
-                            kind: token.kind,
-                        },
-                    );
-                }
-                if text.length().line_count == 0 {
-                    tokens[point.line_index]
-                        .splice(index..index, tokenize(text.as_lines().first().unwrap()));
-                } else {
-                    let mut new_tokens = (0..text.as_lines().len())
-                        .map(|line| tokenize(&text.as_lines()[line]).collect::<Vec<_>>())
-                        .collect::<Vec<_>>();
-                    new_tokens
-                        .first_mut()
-                        .unwrap()
-                        .splice(..0, tokens[point.line_index][..index].iter().copied());
-                    new_tokens
-                        .last_mut()
-                        .unwrap()
-                        .splice(..0, tokens[point.line_index][index..].iter().copied());
-                    tokens.splice(point.line_index..point.line_index + 1, new_tokens);
-                }
+                tokens[point.line_index].clear();
+                let line_index = point.line_index + 1;
+                tokens.splice(
+                    line_index..line_index,
+                    (0..text.length().line_count).map(|_| Vec::new()),
+                );
             }
             Change::Delete(start, length) => {
-                let end = start + length;
-                let mut byte = 0;
-                let mut start_token = tokens[start.line_index]
-                    .iter()
-                    .position(|token| {
-                        if byte + token.len > start.byte_index {
-                            return true;
-                        }
-                        byte += token.len;
-                        false
-                    })
-                    .unwrap_or(tokens[start.line_index].len());
-                if byte != start.byte_index {
-                    let token = tokens[start.line_index][start_token];
-                    let mid = start.byte_index - byte;
-                    tokens[start.line_index][start_token] = Token {
-                        len: mid,
-                        kind: token.kind,
-                    };
-                    start_token += 1;
-                    tokens[start.line_index].insert(
-                        start_token,
-                        Token {
-                            len: token.len - mid,
-                            kind: token.kind,
-                        },
-                    );
-                }
-                let mut byte = 0;
-                let mut end_token = tokens[end.line_index]
-                    .iter()
-                    .position(|token| {
-                        if byte + token.len > end.byte_index {
-                            return true;
-                        }
-                        byte += token.len;
-                        false
-                    })
-                    .unwrap_or(tokens[end.line_index].len());
-                if byte != end.byte_index {
-                    let token = tokens[end.line_index][end_token];
-                    let mid = end.byte_index - byte;
-                    tokens[end.line_index][end_token] = Token {
-                        len: mid,
-                        kind: token.kind,
-                    };
-                    end_token += 1;
-                    tokens[end.line_index].insert(
-                        end_token,
-                        Token {
-                            len: token.len - mid,
-                            kind: token.kind,
-                        },
-                    );
-                }
-                if length.line_count == 0 {
-                    tokens[start.line_index].drain(start_token..end_token);
-                } else {
-                    let mut new_tokens = tokens[start.line_index][..start_token]
-                        .iter()
-                        .copied()
-                        .collect::<Vec<_>>();
-                    new_tokens.extend(tokens[end.line_index][end_token..].iter().copied());
-                    tokens.splice(start.line_index..end.line_index + 1, iter::once(new_tokens));
-                }
+                tokens[start.line_index].clear();
+                let line_start = start.line_index + 1;
+                tokens.drain(line_start..line_start + length.line_count);
             }
         }
     }
@@ -564,7 +659,7 @@ impl CodeDocument {
             Change::Insert(point, ref text) => {
                 let index = inline_inlays[point.line_index]
                     .iter()
-                    .position(|(byte, _)| match byte.cmp(&point.byte_index) {
+                    .position(|(byte, _, _)| match byte.cmp(&point.byte_index) {
                         Ordering::Less => false,
                         Ordering::Equal => match drift {
                             Drift::Before => true,
@@ -574,7 +669,7 @@ impl CodeDocument {
                     })
                     .unwrap_or(inline_inlays[point.line_index].len());
                 if text.length().line_count == 0 {
-                    for (byte, _) in &mut inline_inlays[point.line_index][index..] {
+                    for (byte, _, _) in &mut inline_inlays[point.line_index][index..] {
                         *byte += text.length().byte_count;
                     }
                 } else {
@@ -589,8 +684,8 @@ impl CodeDocument {
                         ..0,
                         inline_inlays[point.line_index]
                             .drain(..)
-                            .map(|(byte, inline_inlay)| {
-                                (byte + text.length().byte_count, inline_inlay)
+                            .map(|(byte, id, inline_inlay)| {
+                                (byte + text.length().byte_count, id, inline_inlay)
                             }),
                     );
                     inline_inlays.splice(point.line_index..point.line_index + 1, new_inline_inlays);
@@ -600,15 +695,15 @@ impl CodeDocument {
                 let end = start + length;
                 let start_inlay = inline_inlays[start.line_index]
                     .iter()
-                    .position(|&(byte, _)| byte >= start.byte_index)
+                    .position(|&(byte, _, _)| byte >= start.byte_index)
                     .unwrap_or(inline_inlays[start.line_index].len());
                 let end_inlay = inline_inlays[end.line_index]
                     .iter()
-                    .position(|&(byte, _)| byte >= end.byte_index)
+                    .position(|&(byte, _, _)| byte >= end.byte_index)
                     .unwrap_or(inline_inlays[end.line_index].len());
                 if length.line_count == 0 {
                     inline_inlays[start.line_index].drain(start_inlay..end_inlay);
-                    for (byte, _) in &mut inline_inlays[start.line_index][start_inlay..] {
+                    for (byte, _, _) in &mut inline_inlays[start.line_index][start_inlay..] {
                         *byte = start.byte_index + (*byte - end.byte_index.min(*byte));
                     }
                 } else {
@@ -616,9 +711,10 @@ impl CodeDocument {
                         .drain(..start_inlay)
                         .collect::<Vec<_>>();
                     new_inline_inlays.extend(inline_inlays[end.line_index].drain(end_inlay..).map(
-                        |(byte, inline_inlay)| {
+                        |(byte, id, inline_inlay)| {
                             (
                                 start.byte_index + byte - end.byte_index.min(byte),
+                                id,
                                 inline_inlay,
                             )
                         },
@@ -679,7 +775,8 @@ impl CodeDocument {
 pub struct DocumentLayout {
     pub indent_state: Vec<Option<IndentState>>,
     pub tokens: Vec<Vec<Token>>,
-    pub inline_inlays: Vec<Vec<(usize, InlineInlay)>>,
+    // the byte each inlay is at, the id of who set it and the inlay
+    pub inline_inlays: Vec<Vec<(usize, usize, InlineInlay)>>,
     pub block_inlays: Vec<(usize, BlockInlay)>,
 }
 
@@ -710,18 +807,39 @@ impl<'a> Editor<'a> {
 struct DocumentInner {
     history: RefCell<History>,
     layout: RefCell<DocumentLayout>,
-    tokenizer: RefCell<Tokenizer>,
+    tokenizer: RefCell<Box<dyn LineTokenizer>>,
     decorations: RefCell<DecorationSet>,
+    line_markers: RefCell<Vec<LineMarker>>,
+    remote_cursors: RefCell<Vec<RemoteCursor>>,
+    // the edits made since they were last taken, None until recording starts
+    edit_log: RefCell<Option<Vec<Edit>>>,
     edit_senders: RefCell<HashMap<SessionId, Sender<(Option<SelectionSet>, Vec<Edit>)>>>,
 }
 
-fn tokenize(text: &str) -> impl Iterator<Item = Token> + '_ {
-    text.split_whitespace_boundaries().map(|string| Token {
-        len: string.len(),
-        kind: if string.chars().next().unwrap().is_whitespace() {
-            TokenKind::Whitespace
-        } else {
-            TokenKind::Unknown
-        },
-    })
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inlays(document: &CodeDocument, line_index: usize) -> Vec<(usize, usize, InlineInlay)> {
+        document.0.layout.borrow().inline_inlays[line_index].clone()
+    }
+
+    #[test]
+    fn inline_inlays_by_id() {
+        let document = CodeDocument::new("let a = 1;\nlet b = 2;".into(), DecorationSet::new());
+        let text = |text: &str| InlineInlay::Text(text.to_string());
+        document.set_inline_inlays(0, 0, vec![(10, text("blame"))]);
+        document.set_inline_inlays(1, 0, vec![(4, text("hint")), (10, text("hover"))]);
+        document.set_inline_inlays(1, 1, vec![(10, text("hover"))]);
+        assert_eq!(
+            inlays(&document, 0),
+            vec![(4, 1, text("hint")), (10, 0, text("blame")), (10, 1, text("hover"))]
+        );
+        document.clear_inline_inlays(1);
+        assert_eq!(inlays(&document, 0), vec![(10, 0, text("blame"))]);
+        assert!(inlays(&document, 1).is_empty());
+        document.set_inline_inlays(0, 0, Vec::new());
+        assert!(inlays(&document, 0).is_empty());
+    }
 }
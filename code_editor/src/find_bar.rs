@@ -0,0 +1,182 @@
+use {
+    crate::{regex::RegexError, search::SearchQuery},
+    makepad_widgets::*,
+};
+
+live_design! {
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+
+    FindBarButton = <ButtonFlat> {
+        width: Fit, height: Fit,
+        padding: {left: 6, right: 6, top: 3, bottom: 3}
+        margin: 0
+    }
+
+    FindBarToggle = <CheckBoxToggle> {
+        width: Fit, height: Fit,
+        margin: {left: 4}
+    }
+
+    FindBar = {{FindBar}} {
+        width: Fill, height: Fit,
+        flow: Down,
+        padding: {left: 8, right: 8, top: 4, bottom: 4}
+        spacing: 4,
+        show_bg: true,
+        draw_bg: {color: (THEME_COLOR_BG_CONTAINER)}
+
+        <View> {
+            width: Fill, height: Fit,
+            flow: Right,
+            spacing: 4,
+            align: {y: 0.5}
+            query = <TextInput> {
+                width: Fill, height: Fit,
+                empty_message: "Find"
+            }
+            case_sensitive = <FindBarToggle> {text: "Aa"}
+            whole_word = <FindBarToggle> {text: "Word"}
+            regex = <FindBarToggle> {text: ".*"}
+            status = <Label> {
+                width: 90, height: Fit,
+                draw_text: {color: (THEME_COLOR_TEXT_META)}
+                text: ""
+            }
+            previous = <FindBarButton> {text: "Prev"}
+            next = <FindBarButton> {text: "Next"}
+            select_all = <FindBarButton> {text: "All"}
+            close = <FindBarButton> {text: "x"}
+        }
+        replace_row = <View> {
+            visible: false,
+            width: Fill, height: Fit,
+            flow: Right,
+            spacing: 4,
+            align: {y: 0.5}
+            replace = <TextInput> {
+                width: Fill, height: Fit,
+                empty_message: "Replace"
+            }
+            replace_one = <FindBarButton> {text: "Replace"}
+            replace_all = <FindBarButton> {text: "Replace All"}
+        }
+    }
+}
+
+#[derive(Clone, Debug, DefaultNone)]
+pub enum FindBarAction {
+    QueryChanged(SearchQuery),
+    Next,
+    Previous,
+    SelectAll,
+    Replace(String),
+    ReplaceAll(String),
+    Close,
+    None,
+}
+
+// The find and replace bar that sits on top of a code editor. It only edits the query, the
+// host applies it to the editor's session.
+#[derive(Live, LiveHook, Widget)]
+pub struct FindBar {
+    #[deref]
+    view: View,
+}
+
+impl Widget for FindBar {
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
+        let uid = self.widget_uid();
+        let actions = cx.capture_actions(|cx| self.view.handle_event(cx, event, scope));
+        for action in self.find_bar_actions(cx, &actions) {
+            cx.widget_action(uid, &scope.path, action);
+        }
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope: &mut Scope, walk: Walk) -> DrawStep {
+        self.view.draw_walk(cx, scope, walk)
+    }
+}
+
+impl FindBar {
+    fn find_bar_actions(&self, cx: &mut Cx, actions: &Actions) -> Vec<FindBarAction> {
+        let mut out = Vec::new();
+        let query = self.view.text_input(id!(query));
+        let replace = self.view.text_input(id!(replace));
+        if query.changed(actions).is_some()
+            || [id!(case_sensitive), id!(whole_word), id!(regex)]
+                .iter()
+                .any(|id| self.view.check_box(*id).changed(actions).is_some())
+        {
+            out.push(FindBarAction::QueryChanged(self.query(cx)));
+        }
+        if query.returned(actions).is_some() || self.view.button(id!(next)).clicked(actions) {
+            out.push(FindBarAction::Next);
+        }
+        if self.view.button(id!(previous)).clicked(actions) {
+            out.push(FindBarAction::Previous);
+        }
+        if self.view.button(id!(select_all)).clicked(actions) {
+            out.push(FindBarAction::SelectAll);
+        }
+        if replace.returned(actions).is_some() || self.view.button(id!(replace_one)).clicked(actions) {
+            out.push(FindBarAction::Replace(replace.text()));
+        }
+        if self.view.button(id!(replace_all)).clicked(actions) {
+            out.push(FindBarAction::ReplaceAll(replace.text()));
+        }
+        if query.escape(actions) || replace.escape(actions) || self.view.button(id!(close)).clicked(actions) {
+            out.push(FindBarAction::Close);
+        }
+        out
+    }
+
+    pub fn query(&self, cx: &Cx) -> SearchQuery {
+        SearchQuery {
+            text: self.view.text_input(id!(query)).text(),
+            case_sensitive: self.view.check_box(id!(case_sensitive)).selected(cx),
+            whole_word: self.view.check_box(id!(whole_word)).selected(cx),
+            regex: self.view.check_box(id!(regex)).selected(cx),
+        }
+    }
+
+    // shows the replace row if asked for, optionally seeds the query and focuses it
+    pub fn open(&mut self, cx: &mut Cx, replace: bool, seed: Option<String>) {
+        let query = self.view.text_input(id!(query));
+        if let Some(seed) = seed {
+            query.set_text(&seed);
+        }
+        query.set_cursor(query.text().len(), 0);
+        query.set_key_focus(cx);
+        if replace {
+            self.view.view(id!(replace_row)).set_visible(true);
+        }
+        self.view.redraw(cx);
+    }
+
+    pub fn close(&mut self, cx: &mut Cx) {
+        self.view.view(id!(replace_row)).set_visible(false);
+        self.view.redraw(cx);
+    }
+
+    pub fn set_status(&mut self, cx: &mut Cx, result: &Result<usize, RegexError>, current: Option<usize>) {
+        let status = match result {
+            Ok(0) if self.view.text_input(id!(query)).text().is_empty() => String::new(),
+            Ok(0) => "No results".to_string(),
+            Ok(count) => match current {
+                Some(current) => format!("{} of {}", current + 1, count),
+                None => format!("{} results", count),
+            },
+            Err(err) => err.message.clone(),
+        };
+        self.view.label(id!(status)).set_text_and_redraw(cx, &status);
+    }
+}
+
+impl FindBarRef {
+    pub fn find_bar_actions(&self, actions: &Actions) -> Vec<FindBarAction> {
+        actions
+            .filter_widget_actions_cast::<FindBarAction>(self.widget_uid())
+            .collect()
+    }
+}
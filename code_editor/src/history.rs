@@ -1,7 +1,8 @@
 use crate::{
+    makepad_file_protocol::{transform, TextChange},
     selection::SelectionSet,
     session::SessionId,
-    text::{Edit, Text},
+    text::{Drift, Edit, Text},
 };
 
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
@@ -57,6 +58,17 @@ impl History {
         self.redo_stack.clear();
     }
 
+    // applies an edit made by someone else, which can't be undone here. The edits on the stacks
+    // were made against the text before it, so they are rebased over it the way the collab client
+    // rebases its pending edits
+    pub fn apply_remote_edit(&mut self, edit: Edit) {
+        let change = TextChange::from(edit.change.clone());
+        self.undo_stack.rebase(change.clone());
+        self.redo_stack.rebase(change);
+        self.text.apply_change(edit.change);
+        self.current_desc = None;
+    }
+
     pub fn undo(
         &mut self,
         selections: &SelectionSet,
@@ -173,6 +185,44 @@ impl Stack {
         self.groups.clear();
         self.edits.clear();
     }
+
+    // Rewrites the stack to apply to the text after `change`. The group on top is applied first,
+    // so the change is transformed past each group on the way down.
+    fn rebase(&mut self, change: TextChange) {
+        let mut changes = vec![change];
+        let mut group_edits = Vec::new();
+        let mut edit_end = self.edits.len();
+        for group in self.groups.iter_mut().rev() {
+            let mut new_edits = Vec::new();
+            for edit in self.edits[group.edit_start..edit_end].iter().rev() {
+                let (new_changes, remaining) =
+                    transform(&[edit.change.clone().into()], &changes, false);
+                changes = remaining;
+                new_edits.extend(new_changes.into_iter().map(|change| Edit {
+                    change: change.into(),
+                    drift: edit.drift,
+                }));
+            }
+            // the selections are restored after the group is undone
+            for change in &changes {
+                group.selections.apply_edit(
+                    &Edit {
+                        change: change.clone().into(),
+                        drift: Drift::Before,
+                    },
+                    None,
+                );
+            }
+            new_edits.reverse();
+            group_edits.push(new_edits);
+            edit_end = group.edit_start;
+        }
+        self.edits.clear();
+        for (group, edits) in self.groups.iter_mut().zip(group_edits.into_iter().rev()) {
+            group.edit_start = self.edits.len();
+            self.edits.extend(edits);
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -180,3 +230,55 @@ struct Group {
     selections: SelectionSet,
     edit_start: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            decoration::DecorationSet,
+            document::CodeDocument,
+            session::CodeSession,
+            text::{Change, Position},
+        },
+    };
+
+    fn insert(line_index: usize, byte_index: usize, text: &str) -> Edit {
+        Edit {
+            change: Change::Insert(
+                Position {
+                    line_index,
+                    byte_index,
+                },
+                text.into(),
+            ),
+            drift: Drift::Before,
+        }
+    }
+
+    #[test]
+    fn undo_after_remote_insert() {
+        let session = CodeSession::new(CodeDocument::new("".into(), DecorationSet::new()));
+        let mut history = History::from(Text::from("fn main() {\n}"));
+        history.push_or_extend_group(session.id(), EditKind::Insert, &SelectionSet::new());
+        history.apply_edit(insert(0, 11, "\n    let a = 1;"));
+        history.push_or_extend_group(session.id(), EditKind::Other, &SelectionSet::new());
+        history.apply_edit(insert(1, 14, " // a"));
+        history.apply_remote_edit(insert(0, 0, "// remote\n"));
+        history.apply_remote_edit(insert(2, 4, "x"));
+        assert_eq!(history.as_text().to_string(), "// remote\nfn main() {\n    xlet a = 1; // a\n}");
+
+        let mut edits = Vec::new();
+        assert!(history.undo(&SelectionSet::new(), &mut edits).is_some());
+        assert_eq!(history.as_text().to_string(), "// remote\nfn main() {\n    xlet a = 1;\n}");
+        edits.clear();
+        assert!(history.undo(&SelectionSet::new(), &mut edits).is_some());
+        // the remote insert sat inside the undone one, what is left of it stays
+        assert_eq!(history.as_text().to_string(), "// remote\nfn main() {x\n}");
+
+        history.apply_remote_edit(insert(0, 0, "//\n"));
+        edits.clear();
+        assert!(history.redo(&SelectionSet::new(), &mut edits).is_some());
+        assert_eq!(history.as_text().to_string(), "//\n// remote\nfn main() {\n    xlet a = 1;\n}");
+    }
+}
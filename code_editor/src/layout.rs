@@ -123,7 +123,7 @@ pub struct Lines<'a> {
     text: Iter<'a, String>,
     indent_state: Iter<'a, Option<IndentState>>,
     tokens: Iter<'a, Vec<Token>>,
-    inline_inlays: Iter<'a, Vec<(usize, InlineInlay)>>,
+    inline_inlays: Iter<'a, Vec<(usize, usize, InlineInlay)>>,
     wrap_data: Iter<'a, Option<WrapData>>,
 }
 
@@ -155,7 +155,8 @@ pub struct Line<'a> {
     pub text: &'a str,
     pub indent_state: Option<IndentState>,
     pub tokens: &'a [Token],
-    pub inlays: &'a [(usize, InlineInlay)],
+    // the byte each inlay is at, the id of who set it and the inlay
+    pub inlays: &'a [(usize, usize, InlineInlay)],
     pub wrap_data: Option<&'a WrapData>,
 }
 
@@ -347,7 +348,7 @@ impl<'a> Line<'a> {
 #[derive(Clone, Debug)]
 pub struct InlineElements<'a> {
     text: &'a str,
-    inlays: Iter<'a, (usize, InlineInlay)>,
+    inlays: Iter<'a, (usize, usize, InlineInlay)>,
     position: usize,
 }
 
@@ -359,9 +360,9 @@ impl<'a> Iterator for InlineElements<'a> {
             .inlays
             .as_slice()
             .first()
-            .map_or(false, |&(position, _)| position == self.position)
+            .map_or(false, |&(position, _, _)| position == self.position)
         {
-            let (_, inline_inlay) = self.inlays.next().unwrap();
+            let (_, _, inline_inlay) = self.inlays.next().unwrap();
             return Some(match *inline_inlay {
                 InlineInlay::Text(ref text) => InlineElement::Text {
                     is_inlay: true,
@@ -374,7 +375,7 @@ impl<'a> Iterator for InlineElements<'a> {
             return None;
         }
         let mut len: usize = self.text.len();
-        if let Some(&(position, _)) = self.inlays.as_slice().first() {
+        if let Some(&(position, _, _)) = self.inlays.as_slice().first() {
             len = len.min(position - self.position);
         }
         let (text_0, text_1) = self.text.split_at(len);
@@ -3,12 +3,19 @@ use makepad_widgets::*;
 
 pub mod char;
 pub mod code_editor;
+pub mod completion;
 pub mod decoration;
 pub mod document;
+pub mod find_bar;
 pub mod history;
 pub mod inlays;
 pub mod iter;
 pub mod layout;
+pub mod line_marker;
+pub mod remote_cursor;
+pub use makepad_regex as regex;
+pub use makepad_file_protocol;
+pub mod search;
 pub mod selection;
 pub mod session;
 pub mod settings;
@@ -22,10 +29,11 @@ pub mod code_view;
 
 pub use self::{
     code_editor::CodeEditor, document::CodeDocument, history::History, layout::Line,
-    selection::Selection, session::CodeSession, settings::Settings, token::Token, tokenizer::Tokenizer,
+    search::SearchQuery, selection::Selection, session::CodeSession, settings::Settings, token::Token, tokenizer::{Language, Tokenizer},
 };
 
 pub fn live_design(cx: &mut Cx) {
     crate::code_editor::live_design(cx);
+    crate::find_bar::live_design(cx);
     crate::code_view::live_design(cx);
 }
@@ -0,0 +1,28 @@
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LineMarkerKind {
+    Added,
+    Modified,
+    // lines were removed just above `start_line`, the marker covers no lines
+    Deleted,
+    // a test, by the outcome of its last run
+    Test,
+    TestPassed,
+    TestFailed,
+    TestIgnored,
+}
+
+// marks a range of lines in the gutter, like the lines a version control system sees as changed
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LineMarker {
+    // who set the marker, so several of them can share the gutter
+    pub id: usize,
+    pub kind: LineMarkerKind,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl LineMarker {
+    pub fn contains(self, line_index: usize) -> bool {
+        self.start_line <= line_index && line_index < self.end_line
+    }
+}
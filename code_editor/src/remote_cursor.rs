@@ -0,0 +1,32 @@
+use {
+    crate::text::{Edit, Position},
+    makepad_widgets::Vec4,
+};
+
+// the selection of someone else editing the same document, drawn in their color
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RemoteCursor {
+    // who the cursor belongs to, a participant can have several
+    pub id: usize,
+    pub color: Vec4,
+    pub anchor: Position,
+    pub cursor: Position,
+}
+
+impl RemoteCursor {
+    pub fn start(self) -> Position {
+        self.anchor.min(self.cursor)
+    }
+
+    pub fn end(self) -> Position {
+        self.anchor.max(self.cursor)
+    }
+
+    pub fn apply_edit(self, edit: &Edit) -> Self {
+        Self {
+            anchor: self.anchor.apply_edit(edit),
+            cursor: self.cursor.apply_edit(edit),
+            ..self
+        }
+    }
+}
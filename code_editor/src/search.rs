@@ -0,0 +1,178 @@
+use {
+    crate::{
+        regex::{is_whole_word, Regex, RegexError, RegexMatch},
+        text::Position,
+    },
+    std::ops::Range,
+};
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SearchQuery {
+    pub text: String,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
+}
+
+impl SearchQuery {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn compile(&self) -> Result<SearchPattern, RegexError> {
+        let regex = if self.regex {
+            Regex::new(&self.text, !self.case_sensitive)?
+        } else {
+            Regex::literal(&self.text, !self.case_sensitive)
+        };
+        Ok(SearchPattern {
+            query: self.clone(),
+            regex,
+        })
+    }
+}
+
+// A compiled query. Matches never span lines.
+#[derive(Clone, Debug)]
+pub struct SearchPattern {
+    query: SearchQuery,
+    regex: Regex,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SearchMatch {
+    pub start: Position,
+    pub end: Position,
+    found: RegexMatch,
+}
+
+impl SearchPattern {
+    pub fn query(&self) -> &SearchQuery {
+        &self.query
+    }
+
+    pub fn find_in_line(&self, line: &str) -> Vec<Range<usize>> {
+        self.find_in_line_internal(line)
+            .map(|found| found.range())
+            .collect()
+    }
+
+    pub fn find_in_lines(&self, lines: &[String]) -> Vec<SearchMatch> {
+        let mut matches = Vec::new();
+        for (line_index, line) in lines.iter().enumerate() {
+            for found in self.find_in_line_internal(line) {
+                matches.push(SearchMatch {
+                    start: Position {
+                        line_index,
+                        byte_index: found.start(),
+                    },
+                    end: Position {
+                        line_index,
+                        byte_index: found.end(),
+                    },
+                    found,
+                });
+            }
+        }
+        matches
+    }
+
+    // the text a match gets replaced with. Regex queries can refer to groups with $1
+    pub fn replacement(&self, lines: &[String], found: &SearchMatch, replace: &str) -> String {
+        if self.query.regex {
+            self.regex
+                .expand(&lines[found.start.line_index], &found.found, replace)
+        } else {
+            replace.to_string()
+        }
+    }
+
+    fn find_in_line_internal<'a>(&'a self, line: &'a str) -> impl Iterator<Item = RegexMatch> + 'a {
+        let matches = if self.query.text.is_empty() {
+            Vec::new()
+        } else {
+            self.regex.find_all(line)
+        };
+        matches.into_iter().filter(move |found| {
+            found.end() > found.start() && (!self.query.whole_word || is_whole_word(line, found.range()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{decoration::DecorationSet, document::CodeDocument, session::CodeSession},
+    };
+
+    fn lines(text: &str) -> Vec<String> {
+        text.split('\n').map(|line| line.to_string()).collect()
+    }
+
+    fn replace_all(text: &str, query: &SearchQuery, replace: &str) -> (usize, String) {
+        let session = CodeSession::new(CodeDocument::new(text.into(), DecorationSet::new()));
+        session.set_search_query(Some(query)).unwrap();
+        let count = session.replace_all(replace);
+        let text = session.document().as_text().to_string();
+        (count, text)
+    }
+
+    #[test]
+    fn find_in_lines() {
+        let pattern = SearchQuery::new("foo").compile().unwrap();
+        let matches = pattern.find_in_lines(&lines("foo bar\nFOO\nbar foo"));
+        let positions: Vec<_> = matches
+            .iter()
+            .map(|found| (found.start.line_index, found.start.byte_index, found.end.byte_index))
+            .collect();
+        assert_eq!(positions, vec![(0, 0, 3), (1, 0, 3), (2, 4, 7)]);
+        let pattern = SearchQuery {
+            case_sensitive: true,
+            whole_word: true,
+            ..SearchQuery::new("foo")
+        }
+        .compile()
+        .unwrap();
+        assert_eq!(pattern.find_in_line("foo foobar FOO foo"), vec![0..3, 15..18]);
+        assert!(SearchQuery::new("").compile().unwrap().find_in_line("foo").is_empty());
+    }
+
+    #[test]
+    fn replacement() {
+        let lines = lines("let x = a.b;");
+        let literal = SearchQuery::new("a.b").compile().unwrap();
+        let found = &literal.find_in_lines(&lines)[0];
+        assert_eq!(literal.replacement(&lines, found, "$1"), "$1");
+        let regex = SearchQuery {
+            regex: true,
+            ..SearchQuery::new(r"(\w+)\.(\w+)")
+        }
+        .compile()
+        .unwrap();
+        let found = &regex.find_in_lines(&lines)[0];
+        assert_eq!(regex.replacement(&lines, found, "$2.$1"), "b.a");
+        assert!(SearchQuery { regex: true, ..SearchQuery::new("(a") }.compile().is_err());
+    }
+
+    #[test]
+    fn replace_all_in_session() {
+        let query = SearchQuery::new("foo");
+        assert_eq!(
+            replace_all("foo foo\nbar foo", &query, "baz"),
+            (3, "baz baz\nbar baz".to_string())
+        );
+        let query = SearchQuery {
+            regex: true,
+            ..SearchQuery::new(r"(\d+)px")
+        };
+        assert_eq!(
+            replace_all("width: 10px\nheight: 20px", &query, "${1}em"),
+            (2, "width: 10em\nheight: 20em".to_string())
+        );
+        assert_eq!(replace_all("bar", &SearchQuery::new("foo"), "baz"), (0, "bar".to_string()));
+    }
+}
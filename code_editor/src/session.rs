@@ -1,9 +1,13 @@
 use {
     crate::{
         char::CharExt,
+        completion::Snippet,
+        decoration::{Decoration, DecorationType},
         document::CodeDocument,
         history::{EditKind,NewGroup},
         layout::{BlockElement, Layout, WrappedElement},
+        regex::RegexError,
+        search::{SearchMatch, SearchPattern, SearchQuery},
         selection::{Affinity, Cursor, SelectionSet},
         str::StrExt,
         text::{Change, Drift, Edit, Length, Position, Text},
@@ -30,6 +34,9 @@ pub struct CodeSession {
     selection_state: RefCell<SelectionState>,
     wrap_column: Cell<Option<usize>>,
     fold_state: RefCell<FoldState>,
+    search_state: RefCell<SearchState>,
+    // the tab stops of the last inserted snippet that haven't been visited yet
+    tab_stops: RefCell<Vec<(Position, Position)>>,
     edit_receiver: Receiver<(Option<SelectionSet>, Vec<Edit>)>,
 }
 
@@ -63,6 +70,12 @@ impl CodeSession {
                 folded_lines: HashSet::new(),
                 unfolding_lines: HashSet::new(),
             }),
+            search_state: RefCell::new(SearchState {
+                pattern: None,
+                matches: Vec::new(),
+                decorations: Vec::new(),
+            }),
+            tab_stops: RefCell::new(Vec::new()),
             edit_receiver,
         };
         for line in 0..line_count {
@@ -818,7 +831,281 @@ impl CodeSession {
             .redo(self.id, &self.selection_state.borrow().selections)
     }
 
+    // replaces the text from `start` up to the cursor with a snippet and selects its first tab
+    // stop. Lines after the first get the indentation of the line the snippet starts on
+    pub fn insert_snippet(&self, start: Position, snippet: &Snippet) {
+        let cursor = self.selections()[self.last_added_selection_index().unwrap_or(0)]
+            .cursor
+            .position;
+        let indent = self.document.as_text().as_lines()[start.line_index]
+            .indent()
+            .unwrap_or("")
+            .to_string();
+        let text = snippet.text.replace('\n', &format!("\n{}", indent));
+        self.set_selection(start, Affinity::After, SelectionMode::Simple, NewGroup::Yes);
+        self.move_to(cursor.max(start), Affinity::Before, NewGroup::No);
+        self.paste(text.into());
+        self.handle_pending_edits();
+        *self.tab_stops.borrow_mut() = snippet
+            .tab_stops
+            .iter()
+            .map(|&(stop_start, stop_end)| {
+                let absolute = |position: Position| {
+                    if position.line_index == 0 {
+                        Position {
+                            line_index: start.line_index,
+                            byte_index: start.byte_index + position.byte_index,
+                        }
+                    } else {
+                        Position {
+                            line_index: start.line_index + position.line_index,
+                            byte_index: indent.len() + position.byte_index,
+                        }
+                    }
+                };
+                (absolute(stop_start), absolute(stop_end))
+            })
+            .collect();
+        self.next_tab_stop();
+    }
+
+    // selects the next tab stop of the last snippet, returns false if there is none
+    pub fn next_tab_stop(&self) -> bool {
+        let mut tab_stops = self.tab_stops.borrow_mut();
+        if tab_stops.is_empty() {
+            return false;
+        }
+        let (start, end) = tab_stops.remove(0);
+        drop(tab_stops);
+        self.set_selection(start, Affinity::After, SelectionMode::Simple, NewGroup::No);
+        self.move_to(end, Affinity::Before, NewGroup::No);
+        true
+    }
+
+    pub fn clear_tab_stops(&self) {
+        self.tab_stops.borrow_mut().clear();
+    }
+
+    pub fn search_query(&self) -> Option<SearchQuery> {
+        self.search_state
+            .borrow()
+            .pattern
+            .as_ref()
+            .map(|pattern| pattern.query().clone())
+    }
+
+    pub fn search_matches(&self) -> Ref<'_, [Decoration]> {
+        Ref::map(self.search_state.borrow(), |search_state| {
+            search_state.decorations.as_slice()
+        })
+    }
+
+    // Sets or clears the query that gets highlighted. Returns the number of matches.
+    pub fn set_search_query(&self, query: Option<&SearchQuery>) -> Result<usize, RegexError> {
+        let pattern = match query {
+            Some(query) => Some(query.compile()?),
+            None => None,
+        };
+        self.search_state.borrow_mut().pattern = pattern;
+        self.update_search_matches();
+        Ok(self.search_state.borrow().matches.len())
+    }
+
+    // The selected text if it can be used to seed a query, that is, if it sits on a single line.
+    pub fn search_seed(&self) -> Option<String> {
+        let selection_state = self.selection_state.borrow();
+        let selection = selection_state.selections
+            [selection_state.last_added_selection_index.unwrap_or(0)];
+        if selection.is_empty() || selection.start().line_index != selection.end().line_index {
+            return None;
+        }
+        Some(
+            self.document
+                .as_text()
+                .slice(selection.start(), selection.length())
+                .to_string(),
+        )
+    }
+
+    // The index of the match that is currently selected, if any.
+    pub fn current_search_match_index(&self) -> Option<usize> {
+        let selection = self.last_added_selection();
+        self.search_state
+            .borrow()
+            .matches
+            .iter()
+            .position(|found| found.start == selection.start() && found.end == selection.end())
+    }
+
+    pub fn find_next(&self) -> bool {
+        let end = self.last_added_selection().end();
+        self.select_search_match(|matches| {
+            matches
+                .iter()
+                .position(|found| found.start >= end)
+                .unwrap_or(0)
+        })
+    }
+
+    pub fn find_previous(&self) -> bool {
+        let start = self.last_added_selection().start();
+        self.select_search_match(|matches| {
+            matches
+                .iter()
+                .rposition(|found| found.end <= start)
+                .unwrap_or(matches.len() - 1)
+        })
+    }
+
+    // Selects the first match at or after the selection, used while a query is being typed.
+    pub fn find_nearest(&self) -> bool {
+        let start = self.last_added_selection().start();
+        self.select_search_match(|matches| {
+            matches
+                .iter()
+                .position(|found| found.start >= start)
+                .unwrap_or(0)
+        })
+    }
+
+    // Turns every match into a selection, so they can be edited with multiple cursors.
+    pub fn select_all_matches(&self) -> bool {
+        let search_state = self.search_state.borrow();
+        if search_state.matches.is_empty() {
+            return false;
+        }
+        let cursor = self.last_added_selection().cursor.position;
+        let mut selections = SelectionSet::new();
+        selections.set_selection(search_match_selection(&search_state.matches[0]));
+        for found in &search_state.matches[1..] {
+            selections.add_selection(search_match_selection(found));
+        }
+        let last_added_selection_index = selections
+            .iter()
+            .position(|selection| selection.start() >= cursor)
+            .unwrap_or(0);
+        drop(search_state);
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.mode = SelectionMode::Simple;
+        selection_state.selections = selections;
+        selection_state.last_added_selection_index = Some(last_added_selection_index);
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        self.document().force_new_group();
+        true
+    }
+
+    // Replaces the selected match and moves on to the next one. If no match is selected this
+    // only selects the next one.
+    pub fn replace(&self, replace: &str) -> bool {
+        let Some(index) = self.current_search_match_index() else {
+            return self.find_next();
+        };
+        let search_state = self.search_state.borrow();
+        let found = search_state.matches[index].clone();
+        let text = search_state.pattern.as_ref().unwrap().replacement(
+            self.document.as_text().as_lines(),
+            &found,
+            replace,
+        );
+        drop(search_state);
+        let mut selections = SelectionSet::new();
+        selections.set_selection(search_match_selection(&found));
+        self.replace_selections(&selections, &[text]);
+        self.find_next();
+        true
+    }
+
+    // Replaces every match as a single undo step. Returns the number of replaced matches.
+    pub fn replace_all(&self, replace: &str) -> usize {
+        let search_state = self.search_state.borrow();
+        let Some(pattern) = search_state.pattern.as_ref() else {
+            return 0;
+        };
+        if search_state.matches.is_empty() {
+            return 0;
+        }
+        let mut selections = SelectionSet::new();
+        let mut texts = Vec::new();
+        for (index, found) in search_state.matches.iter().enumerate() {
+            if index == 0 {
+                selections.set_selection(search_match_selection(found));
+            } else {
+                selections.add_selection(search_match_selection(found));
+            }
+            texts.push(pattern.replacement(self.document.as_text().as_lines(), found, replace));
+        }
+        drop(search_state);
+        self.replace_selections(&selections, &texts);
+        texts.len()
+    }
+
+    fn replace_selections(&self, selections: &SelectionSet, texts: &[String]) {
+        let mut texts = texts.iter();
+        self.document.edit_selections_verbatim(
+            self.id,
+            EditKind::Other,
+            selections,
+            |mut editor, position, length| {
+                editor.apply_edit(Edit {
+                    change: Change::Delete(position, length),
+                    drift: Drift::Before,
+                });
+                editor.apply_edit(Edit {
+                    change: Change::Insert(position, texts.next().unwrap().into()),
+                    drift: Drift::Before,
+                });
+            },
+        );
+        self.document.force_new_group();
+        self.handle_pending_edits();
+    }
+
+    fn select_search_match(&self, f: impl FnOnce(&[SearchMatch]) -> usize) -> bool {
+        let search_state = self.search_state.borrow();
+        if search_state.matches.is_empty() {
+            return false;
+        }
+        let selection = search_match_selection(&search_state.matches[f(&search_state.matches)]);
+        drop(search_state);
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.mode = SelectionMode::Simple;
+        selection_state.selections.set_selection(selection);
+        selection_state.last_added_selection_index = Some(0);
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        self.document().force_new_group();
+        true
+    }
+
+    fn last_added_selection(&self) -> Selection {
+        let selection_state = self.selection_state.borrow();
+        selection_state.selections[selection_state.last_added_selection_index.unwrap_or(0)]
+    }
+
+    fn update_search_matches(&self) {
+        let mut search_state = self.search_state.borrow_mut();
+        search_state.matches = match &search_state.pattern {
+            Some(pattern) => pattern.find_in_lines(self.document.as_text().as_lines()),
+            None => Vec::new(),
+        };
+        search_state.decorations = search_state
+            .matches
+            .iter()
+            .enumerate()
+            .map(|(index, found)| {
+                Decoration::new(index, found.start, found.end, DecorationType::SearchMatch)
+            })
+            .collect();
+    }
+
     pub fn handle_changes(&mut self) {
+        self.handle_pending_edits();
+    }
+
+    fn handle_pending_edits(&self) {
         while let Ok((selections, edits)) = self.edit_receiver.try_recv() {
             self.update_after_edit(selections, &edits);
         }
@@ -924,7 +1211,16 @@ impl CodeSession {
             }
         }
         drop(selection_state);
+        for (start, end) in self.tab_stops.borrow_mut().iter_mut() {
+            for edit in edits {
+                *start = start.apply_edit(edit);
+                *end = end.apply_edit(edit);
+            }
+        }
         self.update_highlighted_delimiter_positions();
+        if self.search_state.borrow().pattern.is_some() {
+            self.update_search_matches();
+        }
     }
 
     fn update_y(&self) {
@@ -1053,6 +1349,20 @@ struct SelectionState {
     highlighted_delimiter_positions: HashSet<Position>,
 }
 
+#[derive(Debug)]
+struct SearchState {
+    pattern: Option<SearchPattern>,
+    matches: Vec<SearchMatch>,
+    decorations: Vec<Decoration>,
+}
+
+fn search_match_selection(found: &SearchMatch) -> Selection {
+    Selection {
+        anchor: found.start,
+        cursor: Cursor::from(found.end),
+    }
+}
+
 #[derive(Debug)]
 struct FoldState {
     folding_lines: HashSet<usize>,
@@ -1,9 +1,12 @@
-use std::{
-    cmp::Ordering,
-    fmt, io,
-    io::BufRead,
-    iter,
-    ops::{Add, AddAssign, Sub, SubAssign},
+use {
+    makepad_file_protocol::{TextChange, TextLength, TextPosition},
+    std::{
+        cmp::Ordering,
+        fmt, io,
+        io::BufRead,
+        iter,
+        ops::{Add, AddAssign, Sub, SubAssign},
+    },
 };
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -204,6 +207,46 @@ impl Change {
     }
 }
 
+// the changes of the collab protocol, which are transformed past each other when edits are made
+// concurrently
+impl From<Change> for TextChange {
+    fn from(change: Change) -> Self {
+        let position = |position: Position| TextPosition {
+            line_index: position.line_index,
+            byte_index: position.byte_index,
+        };
+        match change {
+            Change::Insert(point, text) => TextChange::Insert(position(point), text.to_string()),
+            Change::Delete(start, length) => TextChange::Delete(
+                position(start),
+                TextLength {
+                    line_count: length.line_count,
+                    byte_count: length.byte_count,
+                },
+            ),
+        }
+    }
+}
+
+impl From<TextChange> for Change {
+    fn from(change: TextChange) -> Self {
+        let position = |position: TextPosition| Position {
+            line_index: position.line_index,
+            byte_index: position.byte_index,
+        };
+        match change {
+            TextChange::Insert(point, text) => Change::Insert(position(point), text.into()),
+            TextChange::Delete(start, length) => Change::Delete(
+                position(start),
+                Length {
+                    line_count: length.line_count,
+                    byte_count: length.byte_count,
+                },
+            ),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Position {
     pub line_index: usize,
@@ -0,0 +1,301 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{CharExt, Cursor, Tokenizer},
+};
+
+// A tokenizer for languages with C-like syntax, configured with the keywords of the language.
+#[derive(Clone, Debug)]
+pub struct CLikeTokenizer {
+    pub branch_keywords: &'static [&'static str],
+    pub loop_keywords: &'static [&'static str],
+    pub other_keywords: &'static [&'static str],
+    pub constants: &'static [&'static str],
+    // whether backticks delimit (multiline) template strings, as in JavaScript
+    pub template_strings: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum State {
+    #[default]
+    Initial,
+    BlockComment,
+    TemplateString,
+}
+
+impl CLikeTokenizer {
+    pub fn javascript() -> Self {
+        Self {
+            branch_keywords: &[
+                "if", "else", "switch", "case", "default", "return", "throw", "try", "catch",
+                "finally", "yield", "await",
+            ],
+            loop_keywords: &["for", "while", "do", "break", "continue"],
+            other_keywords: &[
+                "async", "class", "const", "debugger", "delete", "export", "extends", "from",
+                "function", "get", "import", "in", "instanceof", "let", "new", "of", "set",
+                "static", "super", "this", "typeof", "var", "void", "with", "as", "enum",
+                "implements", "interface", "type", "declare", "readonly", "public", "private",
+                "protected",
+            ],
+            constants: &["true", "false", "null", "undefined", "NaN", "Infinity"],
+            template_strings: true,
+        }
+    }
+
+    pub fn shader() -> Self {
+        Self {
+            branch_keywords: &["if", "else", "switch", "case", "default", "return", "discard"],
+            loop_keywords: &["for", "while", "do", "loop", "break", "continue"],
+            other_keywords: &[
+                "fn", "let", "var", "const", "struct", "uniform", "in", "out", "inout",
+                "layout", "attribute", "varying", "precision", "highp", "mediump", "lowp",
+                "void", "bool", "int", "uint", "float", "double", "vec2", "vec3", "vec4",
+                "ivec2", "ivec3", "ivec4", "uvec2", "uvec3", "uvec4", "mat2", "mat3", "mat4",
+                "f32", "f16", "i32", "u32", "sampler2D", "texture2D", "float2", "float3",
+                "float4", "half", "half2", "half3", "half4", "kernel", "vertex", "fragment",
+                "using", "namespace", "device", "constant", "thread",
+            ],
+            constants: &["true", "false"],
+            template_strings: false,
+        }
+    }
+
+    fn initial(&self, cursor: &mut Cursor) -> (State, TokenKind) {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('/', '/') => {
+                cursor.skip_to_end();
+                (State::Initial, TokenKind::Comment)
+            }
+            ('/', '*') => {
+                cursor.skip(2);
+                block_comment(cursor)
+            }
+            ('#', _) if cursor.as_str()[..cursor.index()].trim().is_empty() => {
+                // preprocessor directives
+                cursor.skip(1);
+                cursor.skip_while(|char| char.is_identifier_continue());
+                (State::Initial, TokenKind::OtherKeyword)
+            }
+            ('"', _) | ('\'', _) => string(cursor),
+            ('`', _) if self.template_strings => {
+                cursor.skip(1);
+                template_string(cursor)
+            }
+            ('(', _) | (')', _) | ('[', _) | (']', _) | ('{', _) | ('}', _) => {
+                cursor.skip(1);
+                (State::Initial, TokenKind::Delimiter)
+            }
+            ('.', char) | (char, _) if char.is_ascii_digit() => number(cursor),
+            (char, _) if char.is_identifier_start() || char == '$' => {
+                self.identifier_or_keyword(cursor)
+            }
+            (char, _) if char.is_whitespace() => {
+                cursor.skip_while(|char| char.is_whitespace());
+                (State::Initial, TokenKind::Whitespace)
+            }
+            (char, _) if char.is_ascii_punctuation() => {
+                cursor.skip(1);
+                (State::Initial, TokenKind::Punctuator)
+            }
+            _ => {
+                cursor.skip(1);
+                (State::Initial, TokenKind::Unknown)
+            }
+        }
+    }
+
+    fn identifier_or_keyword(&self, cursor: &mut Cursor) -> (State, TokenKind) {
+        let start = cursor.index();
+        cursor.skip(1);
+        cursor.skip_while(|char| char.is_identifier_continue() || char == '$');
+        let string = &cursor.as_str()[start..cursor.index()];
+        let kind = if self.branch_keywords.contains(&string) {
+            TokenKind::BranchKeyword
+        } else if self.loop_keywords.contains(&string) {
+            TokenKind::LoopKeyword
+        } else if self.other_keywords.contains(&string) {
+            TokenKind::OtherKeyword
+        } else if self.constants.contains(&string) {
+            TokenKind::Constant
+        } else if string.starts_with(char::is_uppercase) {
+            TokenKind::Typename
+        } else if cursor.rest().trim_start().starts_with('(') {
+            TokenKind::Function
+        } else {
+            TokenKind::Identifier
+        };
+        (State::Initial, kind)
+    }
+}
+
+impl Tokenizer for CLikeTokenizer {
+    type State = State;
+
+    fn next_token(&self, state: State, cursor: &mut Cursor) -> (State, TokenKind) {
+        match state {
+            State::Initial => self.initial(cursor),
+            State::BlockComment => block_comment(cursor),
+            State::TemplateString => template_string(cursor),
+        }
+    }
+}
+
+fn number(cursor: &mut Cursor) -> (State, TokenKind) {
+    if cursor.peek(0) == '0' && cursor.peek(1).is_ascii_alphabetic() {
+        // hex, octal and binary literals
+        cursor.skip(2);
+        cursor.skip_while(|char| char.is_ascii_hexdigit() || char == '_');
+    } else {
+        cursor.skip_digits(10);
+        if cursor.peek(0) == '.' {
+            cursor.skip(1);
+            cursor.skip_digits(10);
+        }
+        if cursor.peek(0) == 'e' || cursor.peek(0) == 'E' {
+            cursor.skip_exponent();
+        }
+    }
+    // suffixes such as `n`, `f` and `u`
+    cursor.skip_suffix();
+    (State::Initial, TokenKind::Number)
+}
+
+fn string(cursor: &mut Cursor) -> (State, TokenKind) {
+    let quote = cursor.peek(0);
+    cursor.skip(1);
+    loop {
+        match cursor.peek(0) {
+            '\0' => break,
+            '\\' => cursor.skip(2),
+            char if char == quote => {
+                cursor.skip(1);
+                break;
+            }
+            _ => cursor.skip(1),
+        }
+    }
+    (State::Initial, TokenKind::String)
+}
+
+fn template_string(cursor: &mut Cursor) -> (State, TokenKind) {
+    loop {
+        match cursor.peek(0) {
+            '\0' => break (State::TemplateString, TokenKind::String),
+            '\\' => cursor.skip(2),
+            '`' => {
+                cursor.skip(1);
+                break (State::Initial, TokenKind::String);
+            }
+            _ => cursor.skip(1),
+        }
+    }
+}
+
+fn block_comment(cursor: &mut Cursor) -> (State, TokenKind) {
+    loop {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('*', '/') => {
+                cursor.skip(2);
+                break (State::Initial, TokenKind::Comment);
+            }
+            ('\0', _) => break (State::BlockComment, TokenKind::Comment),
+            _ => cursor.skip(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tokenizer::tests::tokenize_lines};
+
+    #[test]
+    fn javascript() {
+        use TokenKind::*;
+        let lines = tokenize_lines(
+            CLikeTokenizer::javascript(),
+            "for (const x of Items) if (f(x)) return 0x1fn;",
+        );
+        assert_eq!(
+            lines[0]
+                .0
+                .iter()
+                .filter(|(_, kind)| *kind != Whitespace)
+                .cloned()
+                .collect::<Vec<_>>(),
+            [
+                ("for".to_string(), LoopKeyword),
+                ("(".to_string(), Delimiter),
+                ("const".to_string(), OtherKeyword),
+                ("x".to_string(), Identifier),
+                ("of".to_string(), OtherKeyword),
+                ("Items".to_string(), Typename),
+                (")".to_string(), Delimiter),
+                ("if".to_string(), BranchKeyword),
+                ("(".to_string(), Delimiter),
+                ("f".to_string(), Function),
+                ("(".to_string(), Delimiter),
+                ("x".to_string(), Identifier),
+                (")".to_string(), Delimiter),
+                (")".to_string(), Delimiter),
+                ("return".to_string(), BranchKeyword),
+                ("0x1fn".to_string(), Number),
+                (";".to_string(), Punctuator),
+            ]
+        );
+    }
+
+    #[test]
+    fn template_strings() {
+        let lines = tokenize_lines(
+            CLikeTokenizer::javascript(),
+            "a = `one\n/* two \\`\nthree` // x",
+        );
+        assert_eq!(lines[0].0[4], ("`one".to_string(), TokenKind::String));
+        assert_eq!(lines[0].1, State::TemplateString);
+        assert_eq!(
+            lines[1],
+            (
+                vec![("/* two \\`".to_string(), TokenKind::String)],
+                State::TemplateString
+            )
+        );
+        assert_eq!(lines[2].0[0], ("three`".to_string(), TokenKind::String));
+        assert_eq!(lines[2].0[2], ("// x".to_string(), TokenKind::Comment));
+        assert_eq!(lines[2].1, State::Initial);
+
+        // shaders have no template strings
+        let lines = tokenize_lines(CLikeTokenizer::shader(), "`a\nb");
+        assert_eq!(lines[0].0[0], ("`".to_string(), TokenKind::Punctuator));
+        assert_eq!(lines[0].1, State::Initial);
+    }
+
+    #[test]
+    fn block_comments() {
+        let lines = tokenize_lines(
+            CLikeTokenizer::shader(),
+            "#version 450\nvec4 a; /* one\n`two`\n*/ discard;",
+        );
+        assert_eq!(
+            lines[0].0[0],
+            ("#version".to_string(), TokenKind::OtherKeyword)
+        );
+        assert_eq!(
+            lines[1].0.last().unwrap(),
+            &("/* one".to_string(), TokenKind::Comment)
+        );
+        assert_eq!(lines[1].1, State::BlockComment);
+        assert_eq!(
+            lines[2],
+            (
+                vec![("`two`".to_string(), TokenKind::Comment)],
+                State::BlockComment
+            )
+        );
+        assert_eq!(lines[3].0[0], ("*/".to_string(), TokenKind::Comment));
+        assert_eq!(
+            lines[3].0[2],
+            ("discard".to_string(), TokenKind::BranchKeyword)
+        );
+        assert_eq!(lines[3].1, State::Initial);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{Cursor, Tokenizer},
+};
+
+// Also accepts the comments of JSON with comments, as used by many config files.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct JsonTokenizer;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum State {
+    #[default]
+    Initial,
+    BlockComment,
+}
+
+impl Tokenizer for JsonTokenizer {
+    type State = State;
+
+    fn next_token(&self, state: State, cursor: &mut Cursor) -> (State, TokenKind) {
+        match state {
+            State::Initial => initial(cursor),
+            State::BlockComment => block_comment(cursor),
+        }
+    }
+}
+
+fn initial(cursor: &mut Cursor) -> (State, TokenKind) {
+    match (cursor.peek(0), cursor.peek(1)) {
+        ('/', '/') => {
+            cursor.skip_to_end();
+            (State::Initial, TokenKind::Comment)
+        }
+        ('/', '*') => {
+            cursor.skip(2);
+            block_comment(cursor)
+        }
+        ('"', _) => string(cursor),
+        ('{', _) | ('}', _) | ('[', _) | (']', _) => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Delimiter)
+        }
+        (':', _) | (',', _) => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Punctuator)
+        }
+        ('-', char) | (char, _) if char.is_ascii_digit() => {
+            cursor.skip_if(|char| char == '-');
+            cursor.skip_while(|char| char.is_ascii_digit());
+            if cursor.peek(0) == '.' {
+                cursor.skip(1);
+                cursor.skip_while(|char| char.is_ascii_digit());
+            }
+            if cursor.peek(0) == 'e' || cursor.peek(0) == 'E' {
+                cursor.skip_exponent();
+            }
+            (State::Initial, TokenKind::Number)
+        }
+        (char, _) if char.is_whitespace() => {
+            cursor.skip_while(|char| char.is_whitespace());
+            (State::Initial, TokenKind::Whitespace)
+        }
+        (char, _) if char.is_ascii_alphabetic() => {
+            let start = cursor.index();
+            cursor.skip_while(|char| char.is_ascii_alphanumeric());
+            let kind = match &cursor.as_str()[start..cursor.index()] {
+                "true" | "false" | "null" => TokenKind::Constant,
+                _ => TokenKind::Unknown,
+            };
+            (State::Initial, kind)
+        }
+        _ => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Unknown)
+        }
+    }
+}
+
+fn string(cursor: &mut Cursor) -> (State, TokenKind) {
+    debug_assert!(cursor.peek(0) == '"');
+    cursor.skip(1);
+    loop {
+        match cursor.peek(0) {
+            '\0' => break,
+            '\\' => cursor.skip(2),
+            '"' => {
+                cursor.skip(1);
+                break;
+            }
+            _ => cursor.skip(1),
+        }
+    }
+    // a string followed by a colon is an object key
+    if cursor.rest().trim_start().starts_with(':') {
+        (State::Initial, TokenKind::Identifier)
+    } else {
+        (State::Initial, TokenKind::String)
+    }
+}
+
+fn block_comment(cursor: &mut Cursor) -> (State, TokenKind) {
+    loop {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('*', '/') => {
+                cursor.skip(2);
+                break (State::Initial, TokenKind::Comment);
+            }
+            ('\0', _) => break (State::BlockComment, TokenKind::Comment),
+            _ => cursor.skip(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tokenizer::tests::tokenize_lines};
+
+    #[test]
+    fn keys_and_values() {
+        use TokenKind::*;
+        let lines = tokenize_lines(
+            JsonTokenizer,
+            "{\"a\": \"b\", \"c\" : [-1.5e3, true, null, nope]}",
+        );
+        assert_eq!(
+            lines[0].0.iter().map(|(_, kind)| *kind).collect::<Vec<_>>(),
+            [
+                Delimiter, Identifier, Punctuator, Whitespace, String, Punctuator, Whitespace,
+                Identifier, Whitespace, Punctuator, Whitespace, Delimiter, Number, Punctuator,
+                Whitespace, Constant, Punctuator, Whitespace, Constant, Punctuator, Whitespace,
+                Unknown, Delimiter, Delimiter,
+            ]
+        );
+        assert_eq!(lines[0].0[12].0, "-1.5e3");
+    }
+
+    #[test]
+    fn comments() {
+        let lines = tokenize_lines(JsonTokenizer, "1 // line\n/* block\n \"still\" */ 2\n/**/3");
+        assert_eq!(lines[0].0[2], ("// line".to_string(), TokenKind::Comment));
+        assert_eq!(lines[0].1, State::Initial);
+        assert_eq!(
+            lines[1],
+            (
+                vec![("/* block".to_string(), TokenKind::Comment)],
+                State::BlockComment
+            )
+        );
+        assert_eq!(
+            lines[2].0[0],
+            (" \"still\" */".to_string(), TokenKind::Comment)
+        );
+        assert_eq!(lines[2].0[2], ("2".to_string(), TokenKind::Number));
+        assert_eq!(lines[2].1, State::Initial);
+        assert_eq!(lines[3].0[0], ("/**/".to_string(), TokenKind::Comment));
+        assert_eq!(lines[3].1, State::Initial);
+    }
+}
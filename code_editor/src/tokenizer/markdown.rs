@@ -0,0 +1,255 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{Cursor, Tokenizer},
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct MarkdownTokenizer;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum State {
+    #[default]
+    Initial,
+    // inside a fenced code block, remembering the fence so only a matching one closes it
+    CodeBlock { fence: char, len: usize },
+}
+
+impl Tokenizer for MarkdownTokenizer {
+    type State = State;
+
+    fn next_token(&self, state: State, cursor: &mut Cursor) -> (State, TokenKind) {
+        let at_line_start = cursor.index() == 0;
+        match state {
+            State::Initial if at_line_start => line_start(cursor),
+            State::Initial => inline(cursor),
+            State::CodeBlock { fence, len } => {
+                let (fence_char, fence_len) = fence_at(cursor.rest().trim_start());
+                cursor.skip_to_end();
+                if at_line_start && fence_char == fence && fence_len >= len {
+                    (State::Initial, TokenKind::Delimiter)
+                } else {
+                    (state, TokenKind::String)
+                }
+            }
+        }
+    }
+}
+
+// returns the fence character and its length if the text starts with a code fence
+fn fence_at(text: &str) -> (char, usize) {
+    let Some(char) = text.chars().next().filter(|char| *char == '`' || *char == '~') else {
+        return ('\0', 0);
+    };
+    let len = text.chars().take_while(|next| *next == char).count();
+    if len >= 3 {
+        (char, len)
+    } else {
+        ('\0', 0)
+    }
+}
+
+fn line_start(cursor: &mut Cursor) -> (State, TokenKind) {
+    let rest = cursor.rest();
+    let trimmed = rest.trim_start();
+    let (fence, len) = fence_at(trimmed);
+    if len > 0 {
+        cursor.skip_to_end();
+        return (State::CodeBlock { fence, len }, TokenKind::Delimiter);
+    }
+    let indent = rest.len() - trimmed.len();
+    if trimmed.starts_with('#') {
+        let hashes = trimmed.chars().take_while(|char| *char == '#').count();
+        let after = trimmed[hashes..].chars().next();
+        if hashes <= 6 && after.map_or(true, |char| char == ' ' || char == '\t') {
+            cursor.skip_to_end();
+            return (State::Initial, TokenKind::Typename);
+        }
+    }
+    if indent > 0 {
+        cursor.skip_while(|char| char.is_whitespace());
+        return (State::Initial, TokenKind::Whitespace);
+    }
+    inline(cursor)
+}
+
+fn inline(cursor: &mut Cursor) -> (State, TokenKind) {
+    match (cursor.peek(0), cursor.peek(1)) {
+        ('`', _) => {
+            let ticks = cursor.rest().chars().take_while(|char| *char == '`').count();
+            let closing = "`".repeat(ticks);
+            cursor.skip(ticks);
+            match cursor.rest().find(&closing) {
+                Some(end) => {
+                    let end = cursor.index() + end + ticks;
+                    while cursor.index() < end {
+                        cursor.skip(1);
+                    }
+                    (State::Initial, TokenKind::String)
+                }
+                None => (State::Initial, TokenKind::Punctuator),
+            }
+        }
+        ('_', _) if follows_word(cursor) => {
+            cursor.skip_while(|char| char == '_');
+            (State::Initial, TokenKind::Unknown)
+        }
+        ('*', '*') | ('_', '_') => emphasis(cursor, 2),
+        ('*', next) | ('_', next) if !next.is_whitespace() && next != '\0' => emphasis(cursor, 1),
+        ('!', '[') | ('[', _) => link(cursor),
+        ('(', _) if cursor.as_str()[..cursor.index()].ends_with(']') && cursor.rest().contains(')') => {
+            let end = cursor.index() + cursor.rest().find(')').unwrap() + 1;
+            while cursor.index() < end {
+                cursor.skip(1);
+            }
+            (State::Initial, TokenKind::String)
+        }
+        ('>', _) | ('-', _) | ('+', _) | ('*', _) if cursor.as_str()[..cursor.index()].trim().is_empty() => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Punctuator)
+        }
+        (char, _) if char.is_whitespace() => {
+            cursor.skip_while(|char| char.is_whitespace());
+            (State::Initial, TokenKind::Whitespace)
+        }
+        (char, _) if char.is_ascii_digit() => {
+            cursor.skip_while(|char| char.is_ascii_digit());
+            (State::Initial, TokenKind::Number)
+        }
+        _ => {
+            cursor.skip(1);
+            cursor.skip_while(|char| {
+                char.is_alphanumeric() || char == ' ' || (char.is_ascii_punctuation() && !is_markup(char))
+            });
+            (State::Initial, TokenKind::Unknown)
+        }
+    }
+}
+
+fn is_markup(char: char) -> bool {
+    matches!(char, '`' | '*' | '_' | '[' | ']' | '!')
+}
+
+// underscores inside words such as snake_case don't start emphasis
+fn follows_word(cursor: &Cursor) -> bool {
+    cursor.as_str()[..cursor.index()]
+        .chars()
+        .next_back()
+        .map_or(false, |char| char.is_alphanumeric())
+}
+
+// `*text*` and `**text**` on a single line
+fn emphasis(cursor: &mut Cursor, len: usize) -> (State, TokenKind) {
+    let marker = &cursor.rest()[..len];
+    match cursor.rest()[len..].find(marker) {
+        Some(end) if end > 0 => {
+            let end = cursor.index() + len + end + len;
+            while cursor.index() < end {
+                cursor.skip(1);
+            }
+            (
+                State::Initial,
+                if len == 2 {
+                    TokenKind::OtherKeyword
+                } else {
+                    TokenKind::Identifier
+                },
+            )
+        }
+        _ => {
+            cursor.skip(len);
+            (State::Initial, TokenKind::Punctuator)
+        }
+    }
+}
+
+// `[text](url)` and `![alt](url)`, the text and the url are separate tokens
+fn link(cursor: &mut Cursor) -> (State, TokenKind) {
+    if cursor.peek(0) == '!' {
+        cursor.skip(1);
+        return (State::Initial, TokenKind::Punctuator);
+    }
+    let rest = cursor.rest();
+    match rest.find("](") {
+        Some(end) if !rest[1..end].contains('[') => {
+            let end = cursor.index() + end + 1;
+            while cursor.index() < end {
+                cursor.skip(1);
+            }
+            (State::Initial, TokenKind::Function)
+        }
+        _ => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Delimiter)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tokenizer::tests::tokenize_lines};
+
+    #[test]
+    fn code_blocks() {
+        let lines = tokenize_lines(
+            MarkdownTokenizer,
+            "````rust\n# not a heading\n```\n````\n*a*",
+        );
+        let fence = State::CodeBlock { fence: '`', len: 4 };
+        assert_eq!(
+            lines[0],
+            (vec![("````rust".to_string(), TokenKind::Delimiter)], fence)
+        );
+        assert_eq!(
+            lines[1],
+            (
+                vec![("# not a heading".to_string(), TokenKind::String)],
+                fence
+            )
+        );
+        // a shorter fence doesn't close the block
+        assert_eq!(
+            lines[2],
+            (vec![("```".to_string(), TokenKind::String)], fence)
+        );
+        assert_eq!(
+            lines[3],
+            (
+                vec![("````".to_string(), TokenKind::Delimiter)],
+                State::Initial
+            )
+        );
+        assert_eq!(lines[4].0, [("*a*".to_string(), TokenKind::Identifier)]);
+    }
+
+    #[test]
+    fn inline() {
+        let lines = tokenize_lines(
+            MarkdownTokenizer,
+            "## Title\n#hashtag\nsome `code` and **bold** in snake_case\n- see [docs](https://x.y)",
+        );
+        assert_eq!(lines[0].0, [("## Title".to_string(), TokenKind::Typename)]);
+        assert_ne!(lines[1].0[0].1, TokenKind::Typename);
+        let strings = |line: &Vec<(String, TokenKind)>| {
+            line.iter()
+                .filter(|(_, kind)| *kind != TokenKind::Unknown && *kind != TokenKind::Whitespace)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            strings(&lines[2].0),
+            [
+                ("`code`".to_string(), TokenKind::String),
+                ("**bold**".to_string(), TokenKind::OtherKeyword),
+            ]
+        );
+        assert_eq!(
+            strings(&lines[3].0),
+            [
+                ("-".to_string(), TokenKind::Punctuator),
+                ("[docs]".to_string(), TokenKind::Function),
+                ("(https://x.y)".to_string(), TokenKind::String),
+            ]
+        );
+        assert!(lines.iter().all(|(_, state)| *state == State::Initial));
+    }
+}
@@ -0,0 +1,453 @@
+pub mod c_like;
+pub mod json;
+pub mod markdown;
+pub mod plain_text;
+pub mod rust;
+pub mod toml;
+
+use {
+    self::{
+        c_like::CLikeTokenizer, json::JsonTokenizer, markdown::MarkdownTokenizer,
+        plain_text::PlainTextTokenizer, rust::RustTokenizer, toml::TomlTokenizer,
+    },
+    crate::{
+        text::{Change, Text},
+        token::TokenKind,
+        Token,
+    },
+    std::fmt::Debug,
+};
+
+/// A tokenizer for a single language.
+///
+/// Lines are tokenized one at a time. Each line starts in the state the previous line ended in,
+/// which is how constructs such as block comments and multiline strings carry over from one
+/// line to the next. The document remembers the start and end state of every line, so after
+/// an edit only lines whose start state changed need to be tokenized again.
+pub trait Tokenizer: Debug + 'static {
+    type State: Clone + Copy + Debug + Default + Eq;
+
+    /// Returns the next token on the line together with the state after it. The cursor is never
+    /// at the end of the line when this is called.
+    fn next_token(&self, state: Self::State, cursor: &mut Cursor) -> (Self::State, TokenKind);
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Language {
+    #[default]
+    Rust,
+    Toml,
+    Json,
+    Markdown,
+    JavaScript,
+    Shader,
+    PlainText,
+}
+
+impl Language {
+    pub fn from_extension(extension: &str) -> Self {
+        match extension.to_lowercase().as_str() {
+            "rs" => Self::Rust,
+            "toml" | "lock" => Self::Toml,
+            "json" | "jsonc" => Self::Json,
+            "md" | "markdown" => Self::Markdown,
+            "js" | "mjs" | "cjs" | "jsx" | "ts" | "tsx" => Self::JavaScript,
+            "wgsl" | "glsl" | "vert" | "frag" | "comp" | "hlsl" | "metal" => Self::Shader,
+            _ => Self::PlainText,
+        }
+    }
+
+    pub(crate) fn line_tokenizer(self, line_count: usize) -> Box<dyn LineTokenizer> {
+        match self {
+            Self::Rust => Box::new(TokenizerCache::new(RustTokenizer, line_count)),
+            Self::Toml => Box::new(TokenizerCache::new(TomlTokenizer, line_count)),
+            Self::Json => Box::new(TokenizerCache::new(JsonTokenizer, line_count)),
+            Self::Markdown => Box::new(TokenizerCache::new(MarkdownTokenizer, line_count)),
+            Self::JavaScript => Box::new(TokenizerCache::new(CLikeTokenizer::javascript(), line_count)),
+            Self::Shader => Box::new(TokenizerCache::new(CLikeTokenizer::shader(), line_count)),
+            Self::PlainText => Box::new(TokenizerCache::new(PlainTextTokenizer, line_count)),
+        }
+    }
+}
+
+// The part of a tokenizer the document needs, with the state type erased.
+pub(crate) trait LineTokenizer: Debug {
+    fn apply_change(&mut self, change: &Change);
+
+    fn update(&mut self, text: &Text, tokens: &mut [Vec<Token>]);
+}
+
+// Remembers the start and end state of every line.
+#[derive(Debug)]
+pub(crate) struct TokenizerCache<T: Tokenizer> {
+    tokenizer: T,
+    state: Vec<Option<(T::State, T::State)>>,
+}
+
+impl<T: Tokenizer> TokenizerCache<T> {
+    pub(crate) fn new(tokenizer: T, line_count: usize) -> Self {
+        Self {
+            tokenizer,
+            state: (0..line_count).map(|_| None).collect(),
+        }
+    }
+}
+
+impl<T: Tokenizer> LineTokenizer for TokenizerCache<T> {
+    fn apply_change(&mut self, change: &Change) {
+        match *change {
+            Change::Insert(point, ref text) => {
+                self.state[point.line_index] = None;
+                let line_count = text.length().line_count;
+                if line_count > 0 {
+                    let line = point.line_index + 1;
+                    self.state.splice(line..line, (0..line_count).map(|_| None));
+                }
+            }
+            Change::Delete(start, length) => {
+                self.state[start.line_index] = None;
+                let line_count = length.line_count;
+                if line_count > 0 {
+                    let start_line = start.line_index + 1;
+                    let end_line = start_line + line_count;
+                    self.state.drain(start_line..end_line);
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, text: &Text, tokens: &mut [Vec<Token>]) {
+        let mut state = T::State::default();
+        for line in 0..text.as_lines().len() {
+            match self.state[line] {
+                Some((start_state, end_state)) if state == start_state => {
+                    state = end_state;
+                }
+                _ => {
+                    let start_state = state;
+                    let mut new_tokens = Vec::new();
+                    let mut cursor = Cursor::new(&text.as_lines()[line]);
+                    while !cursor.rest().is_empty() {
+                        let start = cursor.index;
+                        let (next_state, mut kind) = self.tokenizer.next_token(state, &mut cursor);
+                        // a stray '\0' in the text looks like the end of the line to most
+                        // tokenizers, make sure we always get past it
+                        if cursor.index == start {
+                            cursor.skip(1);
+                            kind = TokenKind::Unknown;
+                        }
+                        state = next_state;
+                        new_tokens.push(Token {
+                            len: cursor.index - start,
+                            kind,
+                        });
+                    }
+                    self.state[line] = Some((start_state, state));
+                    tokens[line] = new_tokens;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Cursor<'a> {
+    string: &'a str,
+    index: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(string: &'a str) -> Self {
+        Cursor { string, index: 0 }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.string
+    }
+
+    // the rest of the line
+    pub fn rest(&self) -> &'a str {
+        &self.string[self.index..]
+    }
+
+    // returns '\0' past the end of the line
+    pub fn peek(&self, index: usize) -> char {
+        self.string[self.index..].chars().nth(index).unwrap_or('\0')
+    }
+
+    pub fn skip(&mut self, count: usize) {
+        self.index = self.string[self.index..]
+            .char_indices()
+            .nth(count)
+            .map_or(self.string.len(), |(index, _)| self.index + index);
+    }
+
+    pub fn skip_to_end(&mut self) {
+        self.index = self.string.len();
+    }
+
+    pub fn skip_if<P>(&mut self, predicate: P) -> bool
+    where
+        P: FnOnce(char) -> bool,
+    {
+        if predicate(self.peek(0)) {
+            self.skip(1);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn skip_while<P>(&mut self, mut predicate: P)
+    where
+        P: FnMut(char) -> bool,
+    {
+        while self.skip_if(&mut predicate) {}
+    }
+
+    pub fn skip_str(&mut self, string: &str) -> bool {
+        if self.rest().starts_with(string) {
+            self.index += string.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn skip_exponent(&mut self) -> bool {
+        debug_assert!(self.peek(0) == 'E' || self.peek(0) == 'e');
+        self.skip(1);
+        if self.peek(0) == '+' || self.peek(0) == '-' {
+            self.skip(1);
+        }
+        self.skip_digits(10)
+    }
+
+    pub fn skip_digits(&mut self, radix: u32) -> bool {
+        let mut has_skip_digits = false;
+        loop {
+            match self.peek(0) {
+                '_' => {
+                    self.skip(1);
+                }
+                char if char.is_digit(radix) => {
+                    self.skip(1);
+                    has_skip_digits = true;
+                }
+                _ => break,
+            }
+        }
+        has_skip_digits
+    }
+
+    pub fn skip_suffix(&mut self) -> bool {
+        if self.peek(0).is_identifier_start() {
+            self.skip(1);
+            while self.skip_if(|char| char.is_identifier_continue()) {}
+            return true;
+        }
+        false
+    }
+}
+
+pub trait CharExt {
+    fn is_identifier_start(self) -> bool;
+    fn is_identifier_continue(self) -> bool;
+}
+
+impl CharExt for char {
+    fn is_identifier_start(self) -> bool {
+        match self {
+            'A'..='Z' | '_' | 'a'..='z' => true,
+            _ => false,
+        }
+    }
+
+    fn is_identifier_continue(self) -> bool {
+        match self {
+            '0'..='9' | 'A'..='Z' | '_' | 'a'..='z' => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {
+        super::*,
+        crate::text::{Length, Position},
+    };
+
+    // tokenizes `text` from scratch and returns the tokens of every line as strings, together
+    // with the state each line ends in
+    pub(crate) fn tokenize_lines<T: Tokenizer>(
+        tokenizer: T,
+        text: &str,
+    ) -> Vec<(Vec<(String, TokenKind)>, T::State)> {
+        let text = Text::from(text);
+        let mut cache = TokenizerCache::new(tokenizer, text.as_lines().len());
+        let mut tokens = vec![Vec::new(); text.as_lines().len()];
+        cache.update(&text, &mut tokens);
+        text.as_lines()
+            .iter()
+            .zip(tokens)
+            .zip(&cache.state)
+            .map(|((line, tokens), state)| (token_strings(line, &tokens), state.unwrap().1))
+            .collect()
+    }
+
+    fn token_strings(line: &str, tokens: &[Token]) -> Vec<(String, TokenKind)> {
+        let mut start = 0;
+        tokens
+            .iter()
+            .map(|token| {
+                let string = line[start..start + token.len].to_string();
+                start += token.len;
+                (string, token.kind)
+            })
+            .collect()
+    }
+
+    #[derive(Debug)]
+    struct CountingTokenizer(std::rc::Rc<std::cell::Cell<usize>>);
+
+    impl Tokenizer for CountingTokenizer {
+        type State = <RustTokenizer as Tokenizer>::State;
+
+        fn next_token(&self, state: Self::State, cursor: &mut Cursor) -> (Self::State, TokenKind) {
+            if cursor.index() == 0 {
+                self.0.set(self.0.get() + 1);
+            }
+            RustTokenizer.next_token(state, cursor)
+        }
+    }
+
+    fn apply(
+        cache: &mut TokenizerCache<CountingTokenizer>,
+        text: &mut Text,
+        tokens: &mut Vec<Vec<Token>>,
+        change: Change,
+    ) {
+        cache.apply_change(&change);
+        match change {
+            Change::Insert(position, ref inserted) => {
+                let line_index = position.line_index + 1;
+                let line_count = inserted.length().line_count;
+                tokens.splice(line_index..line_index, (0..line_count).map(|_| Vec::new()));
+            }
+            Change::Delete(start, length) => {
+                let line_index = start.line_index + 1;
+                tokens.drain(line_index..line_index + length.line_count);
+            }
+        }
+        text.apply_change(change);
+        cache.update(text, tokens);
+    }
+
+    #[test]
+    fn retokenizes_only_changed_lines() {
+        let lines = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut text = Text::from("let a = 1;\nlet b = 2;\nlet c = 3;\nlet d = 4;");
+        let mut cache = TokenizerCache::new(CountingTokenizer(lines.clone()), 4);
+        let mut tokens = vec![Vec::new(); 4];
+        cache.update(&text, &mut tokens);
+        assert_eq!(lines.get(), 4);
+
+        // an edit inside a line only redoes that line
+        lines.set(0);
+        let position = Position {
+            line_index: 1,
+            byte_index: 4,
+        };
+        apply(
+            &mut cache,
+            &mut text,
+            &mut tokens,
+            Change::Insert(position, Text::from("x")),
+        );
+        assert_eq!(lines.get(), 1);
+        assert_eq!(cache.state.len(), 4);
+
+        // inserted lines are tokenized, the lines after them are not
+        lines.set(0);
+        let position = Position {
+            line_index: 1,
+            byte_index: 11,
+        };
+        apply(
+            &mut cache,
+            &mut text,
+            &mut tokens,
+            Change::Insert(position, Text::from("\nlet e = 5;")),
+        );
+        assert_eq!(lines.get(), 2);
+        assert_eq!(cache.state.len(), 5);
+        assert_eq!(tokens.len(), 5);
+
+        // opening a block comment changes the start state of every line after it
+        lines.set(0);
+        let position = Position {
+            line_index: 0,
+            byte_index: 0,
+        };
+        apply(
+            &mut cache,
+            &mut text,
+            &mut tokens,
+            Change::Insert(position, Text::from("/*")),
+        );
+        assert_eq!(lines.get(), 5);
+        assert!(tokens[4]
+            .iter()
+            .all(|token| token.kind == TokenKind::Comment));
+
+        // and closing it again redoes them once more
+        lines.set(0);
+        let length = Length {
+            line_count: 0,
+            byte_count: 2,
+        };
+        apply(
+            &mut cache,
+            &mut text,
+            &mut tokens,
+            Change::Delete(position, length),
+        );
+        assert_eq!(lines.get(), 5);
+        assert_eq!(tokens[4][0].kind, TokenKind::OtherKeyword);
+
+        // deleting lines drops their state and leaves the rest alone
+        lines.set(0);
+        let position = Position {
+            line_index: 1,
+            byte_index: 11,
+        };
+        let length = Length {
+            line_count: 2,
+            byte_count: 10,
+        };
+        apply(
+            &mut cache,
+            &mut text,
+            &mut tokens,
+            Change::Delete(position, length),
+        );
+        assert_eq!(text.as_lines(), ["let a = 1;", "let xb = 2;", "let d = 4;"]);
+        assert_eq!(lines.get(), 1);
+        assert_eq!(cache.state.len(), 3);
+    }
+
+    #[test]
+    fn language_from_extension() {
+        assert_eq!(Language::from_extension("RS"), Language::Rust);
+        assert_eq!(Language::from_extension("lock"), Language::Toml);
+        assert_eq!(Language::from_extension("jsonc"), Language::Json);
+        assert_eq!(Language::from_extension("tsx"), Language::JavaScript);
+        assert_eq!(Language::from_extension("wgsl"), Language::Shader);
+        assert_eq!(Language::from_extension("txt"), Language::PlainText);
+    }
+}
@@ -0,0 +1,23 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{Cursor, Tokenizer},
+};
+
+// Only splits whitespace from everything else, for files in languages we don't know.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct PlainTextTokenizer;
+
+impl Tokenizer for PlainTextTokenizer {
+    type State = ();
+
+    fn next_token(&self, _state: (), cursor: &mut Cursor) -> ((), TokenKind) {
+        if cursor.peek(0).is_whitespace() {
+            cursor.skip_while(|char| char.is_whitespace());
+            ((), TokenKind::Whitespace)
+        } else {
+            cursor.skip(1);
+            cursor.skip_while(|char| !char.is_whitespace() && char != '\0');
+            ((), TokenKind::Unknown)
+        }
+    }
+}
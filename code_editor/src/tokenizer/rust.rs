@@ -1,66 +1,20 @@
 use crate::{
-    text::{Change, Text},
     token::TokenKind,
-    Token,
+    tokenizer::{CharExt, Cursor, Tokenizer},
 };
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct Tokenizer {
-    state: Vec<Option<(State, State)>>,
-}
-
-impl Tokenizer {
-    pub fn new(line_count: usize) -> Self {
-        Self {
-            state: (0..line_count).map(|_| None).collect(),
-        }
-    }
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RustTokenizer;
 
-    pub fn apply_change(&mut self, change: &Change) {
-        match *change {
-            Change::Insert(point, ref text) => {
-                self.state[point.line_index] = None;
-                let line_count = text.length().line_count;
-                if line_count > 0 {
-                    let line = point.line_index + 1;
-                    self.state.splice(line..line, (0..line_count).map(|_| None));
-                }
-            }
-            Change::Delete(start, length) => {
-                self.state[start.line_index] = None;
-                let line_count = length.line_count;
-                if line_count > 0 {
-                    let start_line = start.line_index + 1;
-                    let end_line = start_line + line_count;
-                    self.state.drain(start_line..end_line);
-                }
-            }
-        }
-    }
+impl Tokenizer for RustTokenizer {
+    type State = State;
 
-    pub fn update(&mut self, text: &Text, tokens: &mut [Vec<Token>]) {
-        let mut state = State::default();
-        for line in 0..text.as_lines().len() {
-            match self.state[line] {
-                Some((start_state, end_state)) if state == start_state => {
-                    state = end_state;
-                }
-                _ => {
-                    let start_state = state;
-                    let mut new_tokens = Vec::new();
-                    let mut cursor = Cursor::new(&text.as_lines()[line]);
-                    loop {
-                        let (next_state, token) = state.next(&mut cursor);
-                        state = next_state;
-                        match token {
-                            Some(token) => new_tokens.push(token),
-                            None => break,
-                        }
-                    }
-                    self.state[line] = Some((start_state, state));
-                    tokens[line] = new_tokens;
-                }
-            }
+    fn next_token(&self, state: State, cursor: &mut Cursor) -> (State, TokenKind) {
+        match state {
+            State::Initial(state) => state.next(cursor),
+            State::BlockCommentTail(state) => state.next(cursor),
+            State::DoubleQuotedStringTail(state) => state.next(cursor),
+            State::RawDoubleQuotedStringTail(state) => state.next(cursor),
         }
     }
 }
@@ -79,30 +33,6 @@ impl Default for State {
     }
 }
 
-impl State {
-    pub fn next(self, cursor: &mut Cursor) -> (State, Option<Token>) {
-        if cursor.peek(0) == '\0' {
-            return (self, None);
-        }
-        let start = cursor.index;
-        let (next_state, kind) = match self {
-            State::Initial(state) => state.next(cursor),
-            State::BlockCommentTail(state) => state.next(cursor),
-            State::DoubleQuotedStringTail(state) => state.next(cursor),
-            State::RawDoubleQuotedStringTail(state) => state.next(cursor),
-        };
-        let end = cursor.index;
-        assert!(start < end);
-        (
-            next_state,
-            Some(Token {
-                len: end - start,
-                kind,
-            }),
-        )
-    }
-}
-
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct InitialState;
 
@@ -214,11 +144,11 @@ impl InitialState {
 
     fn identifier_or_keyword(self, cursor: &mut Cursor) -> (State, TokenKind) {
         debug_assert!(cursor.peek(0).is_identifier_start());
-        let start = cursor.index;
+        let start = cursor.index();
         cursor.skip(1);
         while cursor.skip_if(|char| char.is_identifier_continue()) {}
-        let end = cursor.index;
-        let string = &cursor.string[start..end];
+        let end = cursor.index();
+        let string = &cursor.as_str()[start..end];
         (
             State::Initial(InitialState),
             match string {
@@ -470,93 +400,89 @@ impl RawDoubleQuotedStringTailState {
     }
 }
 
-#[derive(Debug)]
-pub struct Cursor<'a> {
-    string: &'a str,
-    index: usize,
-}
-
-impl<'a> Cursor<'a> {
-    pub fn new(string: &'a str) -> Self {
-        Cursor { string, index: 0 }
-    }
-
-    fn peek(&self, index: usize) -> char {
-        self.string[self.index..].chars().nth(index).unwrap_or('\0')
-    }
-
-    fn skip(&mut self, count: usize) {
-        self.index = self.string[self.index..]
-            .char_indices()
-            .nth(count)
-            .map_or(self.string.len(), |(index, _)| self.index + index);
-    }
-
-    fn skip_if<P>(&mut self, predicate: P) -> bool
-    where
-        P: FnOnce(char) -> bool,
-    {
-        if predicate(self.peek(0)) {
-            self.skip(1);
-            true
-        } else {
-            false
-        }
-    }
-
-    fn skip_exponent(&mut self) -> bool {
-        debug_assert!(self.peek(0) == 'E' || self.peek(0) == 'e');
-        self.skip(1);
-        if self.peek(0) == '+' || self.peek(0) == '-' {
-            self.skip(1);
-        }
-        self.skip_digits(10)
-    }
-
-    fn skip_digits(&mut self, radix: u32) -> bool {
-        let mut has_skip_digits = false;
-        loop {
-            match self.peek(0) {
-                '_' => {
-                    self.skip(1);
-                }
-                char if char.is_digit(radix) => {
-                    self.skip(1);
-                    has_skip_digits = true;
-                }
-                _ => break,
-            }
-        }
-        has_skip_digits
-    }
-
-    fn skip_suffix(&mut self) -> bool {
-        if self.peek(0).is_identifier_start() {
-            self.skip(1);
-            while self.skip_if(|char| char.is_identifier_continue()) {}
-            return true;
-        }
-        false
-    }
-}
-
-pub trait CharExt {
-    fn is_identifier_start(self) -> bool;
-    fn is_identifier_continue(self) -> bool;
-}
-
-impl CharExt for char {
-    fn is_identifier_start(self) -> bool {
-        match self {
-            'A'..='Z' | '_' | 'a'..='z' => true,
-            _ => false,
-        }
-    }
-
-    fn is_identifier_continue(self) -> bool {
-        match self {
-            '0'..='9' | 'A'..='Z' | '_' | 'a'..='z' => true,
-            _ => false,
-        }
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tokenizer::tests::tokenize_lines};
+
+    #[test]
+    fn keywords_and_identifiers() {
+        use TokenKind::*;
+        let lines = tokenize_lines(
+            RustTokenizer,
+            "pub fn f(x: Vec<u8>) -> MAX { loop { 'a: while x {} } }",
+        );
+        assert_eq!(
+            lines[0]
+                .0
+                .iter()
+                .filter(|(_, kind)| *kind != Whitespace)
+                .cloned()
+                .collect::<Vec<_>>()[..10],
+            [
+                ("pub".to_string(), OtherKeyword),
+                ("fn".to_string(), OtherKeyword),
+                ("f".to_string(), Function),
+                ("(".to_string(), Delimiter),
+                ("x".to_string(), Identifier),
+                (":".to_string(), Punctuator),
+                ("Vec".to_string(), Typename),
+                ("<".to_string(), Punctuator),
+                ("u8".to_string(), OtherKeyword),
+                (">".to_string(), Punctuator),
+            ]
+        );
+        assert!(lines[0].0.contains(&("MAX".to_string(), Constant)));
+        assert!(lines[0].0.contains(&("loop".to_string(), LoopKeyword)));
+        assert!(lines[0].0.contains(&("'a".to_string(), String)));
+    }
+
+    #[test]
+    fn nested_block_comments() {
+        let lines = tokenize_lines(RustTokenizer, "a /* one /* two\n*/ still\n*/ b");
+        assert_eq!(
+            lines[0].0[2],
+            ("/* one /* two".to_string(), TokenKind::Comment)
+        );
+        assert_eq!(
+            lines[0].1,
+            State::BlockCommentTail(BlockCommentTailState { depth: 1 })
+        );
+        // the first `*/` only closes the inner comment
+        assert_eq!(lines[1].0, [("*/ still".to_string(), TokenKind::Comment)]);
+        assert_eq!(
+            lines[1].1,
+            State::BlockCommentTail(BlockCommentTailState { depth: 0 })
+        );
+        assert_eq!(lines[2].0[0], ("*/".to_string(), TokenKind::Comment));
+        assert_eq!(lines[2].0[2], ("b".to_string(), TokenKind::Identifier));
+        assert_eq!(lines[2].1, State::default());
+    }
+
+    #[test]
+    fn multiline_strings() {
+        let lines = tokenize_lines(RustTokenizer, "\"one\n\\\" two\nthree\" x\nr##\"\"#\n\"##;");
+        assert_eq!(
+            lines[0],
+            (
+                vec![("\"one".to_string(), TokenKind::String)],
+                State::DoubleQuotedStringTail(DoubleQuotedStringTailState)
+            )
+        );
+        assert_eq!(lines[1].0, [("\\\" two".to_string(), TokenKind::String)]);
+        assert_eq!(lines[1].1, lines[0].1);
+        assert_eq!(lines[2].0[0], ("three\"".to_string(), TokenKind::String));
+        assert_eq!(lines[2].1, State::default());
+        // a raw string only ends at a quote with as many hashes as it started with
+        assert_eq!(
+            lines[3],
+            (
+                vec![("r##\"\"#".to_string(), TokenKind::String)],
+                State::RawDoubleQuotedStringTail(RawDoubleQuotedStringTailState {
+                    start_hash_count: 2
+                })
+            )
+        );
+        assert_eq!(lines[4].0[0], ("\"##".to_string(), TokenKind::String));
+        assert_eq!(lines[4].1, State::default());
     }
 }
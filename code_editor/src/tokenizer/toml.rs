@@ -0,0 +1,215 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{Cursor, Tokenizer},
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct TomlTokenizer;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum State {
+    #[default]
+    Initial,
+    // inside a `"""` string
+    MultilineBasicString,
+    // inside a `'''` string
+    MultilineLiteralString,
+}
+
+impl Tokenizer for TomlTokenizer {
+    type State = State;
+
+    fn next_token(&self, state: State, cursor: &mut Cursor) -> (State, TokenKind) {
+        match state {
+            State::Initial => initial(cursor),
+            State::MultilineBasicString => multiline_string(cursor, '"'),
+            State::MultilineLiteralString => multiline_string(cursor, '\''),
+        }
+    }
+}
+
+fn initial(cursor: &mut Cursor) -> (State, TokenKind) {
+    match cursor.peek(0) {
+        '#' => {
+            cursor.skip_to_end();
+            (State::Initial, TokenKind::Comment)
+        }
+        '"' if cursor.skip_str("\"\"\"") => multiline_string(cursor, '"'),
+        '\'' if cursor.skip_str("'''") => multiline_string(cursor, '\''),
+        '"' | '\'' => string(cursor),
+        '[' if is_table_header(cursor) => {
+            cursor.skip_while(|char| char != ']');
+            cursor.skip_while(|char| char == ']');
+            (State::Initial, TokenKind::Typename)
+        }
+        '[' | ']' | '{' | '}' => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Delimiter)
+        }
+        '=' | ',' | '.' => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Punctuator)
+        }
+        char if char.is_whitespace() => {
+            cursor.skip_while(|char| char.is_whitespace());
+            (State::Initial, TokenKind::Whitespace)
+        }
+        char if is_bare_key_char(char) || char == '+' => bare(cursor),
+        _ => {
+            cursor.skip(1);
+            (State::Initial, TokenKind::Unknown)
+        }
+    }
+}
+
+// a `[` at the start of a line opens a table header, unless it is an array inside a multiline
+// array, which has commas or values in it that a table name can't have
+fn is_table_header(cursor: &Cursor) -> bool {
+    if !cursor.as_str()[..cursor.index()].trim().is_empty() {
+        return false;
+    }
+    let header = cursor.rest().trim_start_matches('[');
+    let Some(end) = header.find(']') else {
+        return false;
+    };
+    !header[..end].is_empty()
+        && header[..end]
+            .chars()
+            .all(|char| is_bare_key_char(char) || matches!(char, '.' | '"' | '\'' | ' ' | '\t'))
+}
+
+fn is_bare_key_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || char == '_' || char == '-'
+}
+
+// bare keys, numbers, dates and booleans all start out the same
+fn bare(cursor: &mut Cursor) -> (State, TokenKind) {
+    let start = cursor.index();
+    cursor.skip(1);
+    cursor.skip_while(|char| is_bare_key_char(char) || matches!(char, '+' | ':' | '.'));
+    // a date time can have a space between the date and the time
+    if cursor.peek(0) == ' ' && cursor.peek(1).is_ascii_digit() && cursor.peek(3) == ':' {
+        cursor.skip(1);
+        cursor.skip_while(|char| is_bare_key_char(char) || matches!(char, '+' | ':' | '.'));
+    }
+    let string = &cursor.as_str()[start..cursor.index()];
+    let rest = cursor.rest().trim_start();
+    let kind = if rest.starts_with('=') {
+        TokenKind::Identifier
+    } else if string == "true" || string == "false" {
+        TokenKind::Constant
+    } else if matches!(string, "inf" | "+inf" | "-inf" | "nan" | "+nan" | "-nan")
+        || string.trim_start_matches(['+', '-']).starts_with(|char: char| char.is_ascii_digit())
+    {
+        TokenKind::Number
+    } else {
+        TokenKind::Identifier
+    };
+    (State::Initial, kind)
+}
+
+fn string(cursor: &mut Cursor) -> (State, TokenKind) {
+    let quote = cursor.peek(0);
+    cursor.skip(1);
+    loop {
+        match cursor.peek(0) {
+            '\0' => break,
+            '\\' if quote == '"' => cursor.skip(2),
+            char if char == quote => {
+                cursor.skip(1);
+                break;
+            }
+            _ => cursor.skip(1),
+        }
+    }
+    (State::Initial, TokenKind::String)
+}
+
+fn multiline_string(cursor: &mut Cursor, quote: char) -> (State, TokenKind) {
+    loop {
+        match cursor.peek(0) {
+            '\0' => {
+                let state = if quote == '"' {
+                    State::MultilineBasicString
+                } else {
+                    State::MultilineLiteralString
+                };
+                break (state, TokenKind::String);
+            }
+            '\\' if quote == '"' => cursor.skip(2),
+            char if char == quote && cursor.peek(1) == quote && cursor.peek(2) == quote => {
+                cursor.skip(3);
+                // up to two more quotes are part of the string
+                cursor.skip_if(|char| char == quote);
+                cursor.skip_if(|char| char == quote);
+                break (State::Initial, TokenKind::String);
+            }
+            _ => cursor.skip(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tokenizer::tests::tokenize_lines};
+
+    fn kinds(text: &str) -> Vec<(Vec<TokenKind>, State)> {
+        tokenize_lines(TomlTokenizer, text)
+            .into_iter()
+            .map(|(tokens, state)| (tokens.into_iter().map(|(_, kind)| kind).collect(), state))
+            .collect()
+    }
+
+    #[test]
+    fn table_headers() {
+        let lines = tokenize_lines(
+            TomlTokenizer,
+            "[package]\n[[bin]]\n[a.\"b c\"]\nx = [1, 2]\n  [\n  [1, 2],\n]",
+        );
+        assert_eq!(lines[0].0, [("[package]".to_string(), TokenKind::Typename)]);
+        assert_eq!(lines[1].0, [("[[bin]]".to_string(), TokenKind::Typename)]);
+        assert_eq!(
+            lines[2].0,
+            [("[a.\"b c\"]".to_string(), TokenKind::Typename)]
+        );
+        // arrays are not table headers, not even at the start of a line
+        assert_eq!(lines[3].0[4], ("[".to_string(), TokenKind::Delimiter));
+        assert_eq!(lines[4].0[1], ("[".to_string(), TokenKind::Delimiter));
+        assert_eq!(lines[5].0[1], ("[".to_string(), TokenKind::Delimiter));
+    }
+
+    #[test]
+    fn keys_and_values() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("name = \"x\" # comment\nok = true\nn = -1_000\nd = 1979-05-27 07:32:00Z")
+                .into_iter()
+                .map(|(kinds, _)| kinds)
+                .collect::<Vec<_>>(),
+            [
+                vec![Identifier, Whitespace, Punctuator, Whitespace, String, Whitespace, Comment],
+                vec![Identifier, Whitespace, Punctuator, Whitespace, Constant],
+                vec![Identifier, Whitespace, Punctuator, Whitespace, Number],
+                vec![Identifier, Whitespace, Punctuator, Whitespace, Number],
+            ]
+        );
+    }
+
+    #[test]
+    fn multiline_strings() {
+        let lines = kinds("a = \"\"\"\n[not.a.header]\n\\\"\"\"\"\"\"\nb = '''\nx \\'''\nc = 1");
+        assert_eq!(lines[0].1, State::MultilineBasicString);
+        assert_eq!(
+            lines[1],
+            (vec![TokenKind::String], State::MultilineBasicString)
+        );
+        // an escaped quote doesn't end the string, up to two quotes after the closing ones do
+        // belong to it
+        assert_eq!(lines[2], (vec![TokenKind::String], State::Initial));
+        assert_eq!(lines[3].1, State::MultilineLiteralString);
+        // a literal string has no escapes
+        assert_eq!(lines[4], (vec![TokenKind::String], State::Initial));
+        assert_eq!(lines[5].0[0], TokenKind::Identifier);
+        assert_eq!(lines[5].1, State::Initial);
+    }
+}
@@ -35,6 +35,7 @@ pub use crate::{
         NavOrder,
         NavStop,
         NavItem,
+        NavStops,
         NavScrollIndex
     },
     draw_list_2d::{
@@ -4,10 +4,7 @@ use {
     std::cell::RefCell,
     crate::{
         cx_2d::Cx2d,
-        makepad_platform::Area,
-        makepad_platform::DrawListId,
-        makepad_platform::Margin,
-        makepad_platform::Cx,
+        makepad_platform::*,
     }
 };
 
@@ -37,12 +34,27 @@ impl std::ops::IndexMut<DrawListId> for CxNavTree {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Where a nav stop goes in the Tab order. `Top` stops come first, then `Middle` and then
+/// `Bottom`, ordered by their number and in draw order when that is equal. `Default` counts as
+/// `Middle(0)`, so `Middle(1)` and up come after all the stops that don't set an order.
+#[derive(Debug, Clone, Copy, PartialEq, Live, LiveHook)]
+#[live_ignore]
 pub enum NavOrder {
-    Default,
-    Top(u64),
-    Middle(u64),
-    Bottom(u64),
+    #[pick] Default,
+    #[live(0)] Top(u64),
+    #[live(0)] Middle(u64),
+    #[live(0)] Bottom(u64),
+}
+
+impl NavOrder {
+    fn sort_key(&self) -> (u8, u64) {
+        match self {
+            Self::Top(n) => (0, *n),
+            Self::Default => (1, 0),
+            Self::Middle(n) => (1, *n),
+            Self::Bottom(n) => (2, *n),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -50,7 +62,9 @@ pub struct NavStop {
     pub role: NavRole,
     pub order: NavOrder,
     pub margin: Margin,
-    pub area: Area
+    pub area: Area,
+    // the innermost nav group the stop was drawn in, numbered in draw order by collect_nav_stops
+    pub group: Option<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -58,7 +72,10 @@ pub enum NavItem {
     Child(DrawListId),
     Stop(NavStop),
     BeginScroll(Area),
-    EndScroll(Area)
+    EndScroll(Area),
+    BeginGroup,
+    EndGroup,
+    Trap,
 }
 
 #[derive(Debug, Clone)]
@@ -66,6 +83,69 @@ pub enum NavRole {
     TextInput,
     DropDown,
     Slider,
+    Button,
+    CheckBox,
+    RadioButton,
+}
+
+/// The nav stops of a window in Tab order, each with the scroll areas it sits in and itself last.
+/// When a draw list marked itself as a trap, only the stops inside the innermost one are listed.
+#[derive(Debug, Default)]
+pub struct NavStops {
+    pub stops: Vec<(NavStop, Vec<Area>)>,
+    pub trapped: bool,
+}
+
+impl CxNavTree {
+    /// The nav stops drawn into root and the lists drawn into it, see [`NavStops`].
+    pub fn nav_stops(&self, root: DrawListId) -> NavStops {
+        struct Collect {
+            scroll_stack: Vec<Area>,
+            group_stack: Vec<usize>,
+            groups: usize,
+            trap: Option<Vec<(NavStop, Vec<Area>)>>,
+            traps_found: usize,
+        }
+        fn collect(nav_tree: &CxNavTree, draw_list_id: DrawListId, c: &mut Collect, out: &mut Vec<(NavStop, Vec<Area>)>) {
+            if draw_list_id.index() >= nav_tree.nav_lists.len() {
+                return
+            }
+            // a trap inside this list wins over this one, a later sibling wins over an earlier one
+            let traps_before = c.traps_found;
+            let start = out.len();
+            let mut is_trap = false;
+            for nav_item in &nav_tree[draw_list_id].nav_list {
+                match nav_item {
+                    NavItem::Child(draw_list_id) => collect(nav_tree, *draw_list_id, c, out),
+                    NavItem::Stop(stop) => {
+                        let mut stack = c.scroll_stack.clone();
+                        stack.push(stop.area);
+                        let group = c.group_stack.last().copied();
+                        out.push((NavStop {group, ..stop.clone()}, stack));
+                    }
+                    NavItem::BeginScroll(area) => c.scroll_stack.push(*area),
+                    NavItem::EndScroll(_) => {c.scroll_stack.pop();}
+                    NavItem::BeginGroup => {
+                        c.group_stack.push(c.groups);
+                        c.groups += 1;
+                    }
+                    NavItem::EndGroup => {c.group_stack.pop();}
+                    NavItem::Trap => is_trap = true,
+                }
+            }
+            if is_trap && c.traps_found == traps_before {
+                c.trap = Some(out[start..].to_vec());
+                c.traps_found += 1;
+            }
+        }
+        let mut c = Collect {scroll_stack: Vec::new(), group_stack: Vec::new(), groups: 0, trap: None, traps_found: 0};
+        let mut stops = Vec::new();
+        collect(self, root, &mut c, &mut stops);
+        let trapped = c.trap.is_some();
+        let mut stops = c.trap.unwrap_or(stops);
+        stops.sort_by_key( | (stop, _) | stop.order.sort_key());
+        NavStops {stops, trapped}
+    }
 }
 
 impl<'a> Cx2d<'a> {
@@ -77,41 +157,10 @@ impl<'a> Cx2d<'a> {
         }
     }
     
-    pub fn iterate_nav_stops<F>(cx: &mut Cx, root: DrawListId, mut callback: F) -> Option<(Area, Vec<Area>)> where F: FnMut(&Cx, &NavStop) -> Option<Area> {
+    pub fn collect_nav_stops(cx: &mut Cx, root: DrawListId) -> NavStops {
         let nav_tree_rc = cx.get_global::<CxNavTreeRc>().clone();
-        let nav_tree = &*nav_tree_rc.0.borrow();
-        let mut scroll_stack = Vec::new();
-        fn iterate_nav_stops<F>(cx: &Cx, scroll_stack: &mut Vec<Area>, nav_tree: &CxNavTree, draw_list_id: DrawListId, callback: &mut F) -> Option<Area> 
-        where F: FnMut(&Cx, &NavStop) -> Option<Area> {
-            
-            for i in 0..nav_tree[draw_list_id].nav_list.len() {
-                let nav_item = &nav_tree[draw_list_id].nav_list[i];
-                match nav_item {
-                    NavItem::Child(draw_list_id) => {
-                        if let Some(area) = iterate_nav_stops(cx, scroll_stack, nav_tree, *draw_list_id, callback) {
-                            return Some(area)
-                        }
-                    }
-                    NavItem::Stop(stop) => if let Some(area) = callback(cx, stop) {
-                        scroll_stack.push(area);
-                        return Some(area)
-                    }
-                    NavItem::BeginScroll(area)=>{
-                        scroll_stack.push(*area);
-                    }
-                    NavItem::EndScroll(area)=>{
-                        if *area != scroll_stack.pop().unwrap(){panic!()};
-                    }
-                }
-            }
-            None
-        }
-        if let Some(area) = iterate_nav_stops(cx, &mut scroll_stack, nav_tree, root, &mut callback){
-            Some((area, scroll_stack))
-        }
-        else{
-            None
-        }
+        let nav_tree = nav_tree_rc.0.borrow();
+        nav_tree.nav_stops(root)
     }
     
     pub fn nav_list_clear(&mut self, draw_list_id: DrawListId) {
@@ -128,15 +177,39 @@ impl<'a> Cx2d<'a> {
     }
     
     pub fn add_nav_stop(&mut self, area: Area, role: NavRole, margin: Margin) {
+        self.add_nav_stop_with_order(area, role, margin, NavOrder::Default)
+    }
+    
+    pub fn add_nav_stop_with_order(&mut self, area: Area, role: NavRole, margin: Margin, order: NavOrder) {
         let draw_list_id = *self.draw_list_stack.last().unwrap();
         self.nav_list_item_push(draw_list_id, NavItem::Stop(NavStop {
             role,
             area,
-            order: NavOrder::Default,
-            margin
+            order,
+            margin,
+            group: None,
         }));
     }
     
+    /// Keeps Tab navigation inside the current draw list (and the lists drawn into it) for as
+    /// long as it draws this, used by modals and popups drawn in an overlay.
+    pub fn add_nav_trap(&mut self) {
+        let draw_list_id = *self.draw_list_stack.last().unwrap();
+        self.nav_list_item_push(draw_list_id, NavItem::Trap);
+    }
+    
+    /// The stops added until the matching `add_nav_group_end` belong together, the arrow keys
+    /// only move between the radio buttons of one group.
+    pub fn add_nav_group_begin(&mut self) {
+        let draw_list_id = *self.draw_list_stack.last().unwrap();
+        self.nav_list_item_push(draw_list_id, NavItem::BeginGroup);
+    }
+    
+    pub fn add_nav_group_end(&mut self) {
+        let draw_list_id = *self.draw_list_stack.last().unwrap();
+        self.nav_list_item_push(draw_list_id, NavItem::EndGroup);
+    }
+    
     pub fn add_begin_scroll(&mut self)->NavScrollIndex{
         let mut nav_tree = self.nav_tree_rc.0.borrow_mut();
         let draw_list_id = *self.draw_list_stack.last().unwrap();
@@ -153,4 +226,124 @@ impl<'a> Cx2d<'a> {
     }
 }
 
-pub struct NavScrollIndex(usize);
\ No newline at end of file
+pub struct NavScrollIndex(usize);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a nav tree with one list per entry, the lists get the ids returned with it
+    fn nav_tree(lists: impl FnOnce(&[DrawListId]) -> Vec<Vec<NavItem>>, count: usize) -> (CxNavTree, Vec<DrawListId>) {
+        let mut pool = CxDrawListPool::default();
+        // a dropped draw list gives its id back, so keep them until all are made
+        let draw_lists: Vec<DrawList> = (0..count).map( | _ | pool.alloc()).collect();
+        let ids: Vec<DrawListId> = draw_lists.iter().map( | draw_list | draw_list.id()).collect();
+        let lists = lists(&ids);
+        assert!(ids.iter().enumerate().all( | (index, id) | id.index() == index));
+        (CxNavTree {nav_lists: lists.into_iter().map( | nav_list | CxNavList {nav_list}).collect()}, ids)
+    }
+
+    fn stop(id: usize, order: NavOrder) -> NavItem {
+        NavItem::Stop(NavStop {
+            role: NavRole::Button,
+            order,
+            margin: Margin::default(),
+            area: area(id),
+            group: None,
+        })
+    }
+
+    fn area(id: usize) -> Area {
+        let mut pool = CxDrawListPool::default();
+        Area::Rect(RectArea {draw_list_id: pool.alloc().id(), rect_id: id, redraw_id: 0})
+    }
+
+    fn stop_ids(stops: &NavStops) -> Vec<usize> {
+        stops.stops.iter().map( | (stop, _) | match stop.area {
+            Area::Rect(rect) => rect.rect_id,
+            _ => panic!()
+        }).collect()
+    }
+
+    #[test]
+    fn tab_order() {
+        let (tree, ids) = nav_tree( | _ | vec![vec![
+            stop(0, NavOrder::Default),
+            stop(1, NavOrder::Bottom(0)),
+            stop(2, NavOrder::Top(2)),
+            stop(3, NavOrder::Middle(1)),
+            stop(4, NavOrder::Top(1)),
+            stop(5, NavOrder::Middle(0)),
+            stop(6, NavOrder::Default),
+        ]], 1);
+        let stops = tree.nav_stops(ids[0]);
+        // the same order keeps the draw order, Default is the same as Middle(0)
+        assert_eq!(stop_ids(&stops), [4, 2, 0, 5, 6, 3, 1]);
+        assert!(!stops.trapped);
+    }
+
+    #[test]
+    fn stops_in_child_lists_and_scroll_views() {
+        let (tree, ids) = nav_tree( | ids | vec![
+            vec![
+                stop(0, NavOrder::Default),
+                NavItem::BeginScroll(area(10)),
+                NavItem::Child(ids[1]),
+                NavItem::EndScroll(area(10)),
+                stop(2, NavOrder::Default),
+            ],
+            vec![stop(1, NavOrder::Default)],
+        ], 2);
+        let stops = tree.nav_stops(ids[0]);
+        assert_eq!(stop_ids(&stops), [0, 1, 2]);
+        assert_eq!(stops.stops[1].1, [area(10), area(1)]);
+        assert_eq!(stops.stops[2].1, [area(2)]);
+    }
+
+    #[test]
+    fn innermost_trap_wins() {
+        let (tree, ids) = nav_tree( | ids | vec![
+            vec![stop(0, NavOrder::Default), NavItem::Child(ids[1])],
+            vec![NavItem::Trap, stop(1, NavOrder::Default), NavItem::Child(ids[2])],
+            vec![stop(2, NavOrder::Default), NavItem::Trap, stop(3, NavOrder::Default)],
+        ], 3);
+        let stops = tree.nav_stops(ids[0]);
+        assert!(stops.trapped);
+        assert_eq!(stop_ids(&stops), [2, 3]);
+    }
+
+    #[test]
+    fn last_trap_wins() {
+        let (tree, ids) = nav_tree( | ids | vec![
+            vec![NavItem::Child(ids[1]), NavItem::Child(ids[2]), stop(0, NavOrder::Default)],
+            vec![NavItem::Trap, stop(1, NavOrder::Default)],
+            vec![stop(3, NavOrder::Bottom(0)), stop(2, NavOrder::Default), NavItem::Trap],
+        ], 3);
+        let stops = tree.nav_stops(ids[0]);
+        assert!(stops.trapped);
+        assert_eq!(stop_ids(&stops), [2, 3]);
+    }
+
+    #[test]
+    fn groups() {
+        let (tree, ids) = nav_tree( | ids | vec![
+            vec![
+                NavItem::BeginGroup,
+                stop(0, NavOrder::Default),
+                NavItem::Child(ids[1]),
+                NavItem::EndGroup,
+                NavItem::BeginGroup,
+                stop(2, NavOrder::Default),
+                NavItem::BeginGroup,
+                stop(3, NavOrder::Default),
+                NavItem::EndGroup,
+                stop(4, NavOrder::Default),
+                NavItem::EndGroup,
+                stop(5, NavOrder::Default),
+            ],
+            vec![stop(1, NavOrder::Default)],
+        ], 2);
+        let stops = tree.nav_stops(ids[0]);
+        let groups: Vec<Option<usize>> = stops.stops.iter().map( | (stop, _) | stop.group).collect();
+        assert_eq!(groups, [Some(0), Some(0), Some(1), Some(2), Some(1), None]);
+    }
+}
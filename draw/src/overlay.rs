@@ -28,13 +28,13 @@ impl LiveNew for Overlay {
     }
     
     fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
-        LiveTypeInfo {
-            module_id: LiveModuleId::from_str(&module_path!()).unwrap(),
-            live_type: LiveType::of::<Self>(),
-            live_ignore: true,
-            fields: Vec::new(),
-            type_name: id_lut!(Overlay)
-        }
+        LiveTypeInfo::new(
+            LiveModuleId::from_str(&module_path!()).unwrap(),
+            LiveType::of::<Self>(),
+            id_lut!(Overlay),
+            true,
+            Vec::new()
+        )
     }
 }
 
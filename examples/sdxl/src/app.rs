@@ -519,7 +519,7 @@ impl App {
             while let Ok(message) = rx_request.recv() {
                 // only store last change, fix later
                 match message {
-                    HttpServerRequest::ConnectWebSocket {web_socket_id, response_sender,headers} => {
+                    HttpServerRequest::ConnectWebSocket {web_socket_id, response_sender, headers, ..} => {
                         let ip = if let IpAddr::V4(addr) = headers.addr.ip(){
                             addr
                         }
@@ -1,4 +1,8 @@
- pub mod digest;
- pub mod utils;
- pub mod server;
- pub mod websocket;
+pub mod digest;
+pub mod utils;
+pub mod server;
+pub mod websocket;
+pub mod router;
+pub mod static_files;
+pub mod websocket_deflate;
+pub mod websocket_hub;
@@ -0,0 +1,192 @@
+// a small router on top of the HttpServerRequest channel. Routes are matched in the order they
+// are added, patterns are /literal/:param/*rest and a fallback handles whatever is left.
+
+use std::sync::mpsc;
+use crate::server::{HttpServerRequest, HttpServerResponse};
+use crate::utils::{HttpServerHeaders, percent_decode};
+
+pub struct HttpRouteRequest<'a> {
+    pub headers: &'a HttpServerHeaders,
+    pub params: HttpRouteParams,
+    pub body: &'a [u8],
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HttpRouteParams {
+    pub values: Vec<(String, String)>,
+    pub rest: Option<String>,
+}
+
+impl HttpRouteParams {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.iter().find( | (key, _) | key == name).map( | (_, value) | value.as_str())
+    }
+}
+
+pub trait HttpRouteHandler: Send + Sync {
+    fn handle(&self, request: &HttpRouteRequest) -> HttpServerResponse;
+}
+
+impl<F> HttpRouteHandler for F where F: Fn(&HttpRouteRequest) -> HttpServerResponse + Send + Sync {
+    fn handle(&self, request: &HttpRouteRequest) -> HttpServerResponse {
+        self(request)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum HttpRouteSegment {
+    Literal(String),
+    Param(String),
+    Rest(String),
+}
+
+struct HttpRoute {
+    verb: Option<String>,
+    segments: Vec<HttpRouteSegment>,
+    handler: Box<dyn HttpRouteHandler>,
+}
+
+#[derive(Default)]
+pub struct HttpRouter {
+    routes: Vec<HttpRoute>,
+    fallback: Option<Box<dyn HttpRouteHandler>>,
+}
+
+impl HttpRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // verb None matches every verb. HEAD requests are matched against GET routes
+    pub fn route(&mut self, verb: Option<&str>, pattern: &str, handler: impl HttpRouteHandler + 'static) -> &mut Self {
+        self.routes.push(HttpRoute {
+            verb: verb.map( | v | v.to_string()),
+            segments: parse_pattern(pattern),
+            handler: Box::new(handler)
+        });
+        self
+    }
+
+    pub fn get(&mut self, pattern: &str, handler: impl HttpRouteHandler + 'static) -> &mut Self {
+        self.route(Some("GET"), pattern, handler)
+    }
+
+    pub fn post(&mut self, pattern: &str, handler: impl HttpRouteHandler + 'static) -> &mut Self {
+        self.route(Some("POST"), pattern, handler)
+    }
+
+    // routes everything under prefix to the handler, the remainder ends up in params.rest
+    pub fn mount(&mut self, prefix: &str, handler: impl HttpRouteHandler + 'static) -> &mut Self {
+        let pattern = format!("{}/*rest", prefix.trim_end_matches('/'));
+        self.route(None, &pattern, handler)
+    }
+
+    pub fn fallback(&mut self, handler: impl HttpRouteHandler + 'static) -> &mut Self {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+
+    pub fn respond(&self, headers: &HttpServerHeaders, body: &[u8]) -> HttpServerResponse {
+        let verb = if headers.verb == "HEAD" {"GET"} else {headers.verb.as_str()};
+        let mut path_matched = false;
+        for route in &self.routes {
+            if let Some(params) = match_segments(&route.segments, &headers.path) {
+                if route.verb.as_ref().is_none_or( | v | v == verb) {
+                    return route.handler.handle(&HttpRouteRequest {headers, params, body})
+                }
+                path_matched = true;
+            }
+        }
+        if path_matched {
+            return HttpServerResponse::text(405, "text/plain", "Method Not Allowed")
+        }
+        if let Some(fallback) = &self.fallback {
+            return fallback.handle(&HttpRouteRequest {headers, params: HttpRouteParams::default(), body})
+        }
+        HttpServerResponse::not_found()
+    }
+
+    // answers Get and Post requests, websocket traffic is handed back to the caller
+    pub fn handle_request(&self, request: HttpServerRequest) -> Option<HttpServerRequest> {
+        match request {
+            HttpServerRequest::Get {headers, response_sender} => {
+                let _ = response_sender.send(self.respond(&headers, &[]));
+                None
+            }
+            HttpServerRequest::Post {headers, body, response} => {
+                let _ = response.send(self.respond(&headers, &body));
+                None
+            }
+            request => Some(request)
+        }
+    }
+
+    pub fn serve(&self, rx_request: mpsc::Receiver<HttpServerRequest>, mut other: impl FnMut(HttpServerRequest)) {
+        while let Ok(request) = rx_request.recv() {
+            if let Some(request) = self.handle_request(request) {
+                other(request);
+            }
+        }
+    }
+}
+
+fn parse_pattern(pattern: &str) -> Vec<HttpRouteSegment> {
+    pattern.split('/').filter( | s | !s.is_empty()).map( | s | {
+        if let Some(name) = s.strip_prefix(':') {
+            HttpRouteSegment::Param(name.to_string())
+        }
+        else if let Some(name) = s.strip_prefix('*') {
+            HttpRouteSegment::Rest(name.to_string())
+        }
+        else {
+            HttpRouteSegment::Literal(s.to_string())
+        }
+    }).collect()
+}
+
+fn match_segments(segments: &[HttpRouteSegment], path: &str) -> Option<HttpRouteParams> {
+    let parts: Vec<&str> = path.split('/').filter( | s | !s.is_empty()).collect();
+    let mut params = HttpRouteParams::default();
+    for (index, segment) in segments.iter().enumerate() {
+        match segment {
+            HttpRouteSegment::Rest(name) => {
+                let rest = parts[index.min(parts.len())..].join("/");
+                params.values.push((name.clone(), percent_decode(&rest, false)));
+                params.rest = Some(rest);
+                return Some(params)
+            }
+            HttpRouteSegment::Literal(literal) => {
+                if parts.get(index) != Some(&literal.as_str()) {
+                    return None
+                }
+            }
+            HttpRouteSegment::Param(name) => {
+                params.values.push((name.clone(), percent_decode(parts.get(index)?, false)));
+            }
+        }
+    }
+    if parts.len() != segments.len() {
+        return None
+    }
+    Some(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_params_and_rest() {
+        let segments = parse_pattern("/user/:id/files/*path");
+        let params = match_segments(&segments, "/user/12/files/a/b%20c.txt").unwrap();
+        assert_eq!(params.get("id"), Some("12"));
+        assert_eq!(params.get("path"), Some("a/b c.txt"));
+        assert_eq!(params.rest.as_deref(), Some("a/b%20c.txt"));
+        assert!(match_segments(&segments, "/user/12").is_none());
+
+        let segments = parse_pattern("/user/:id");
+        assert!(match_segments(&segments, "/user/12/more").is_none());
+        assert!(match_segments(&segments, "/users/12").is_none());
+        assert_eq!(match_segments(&segments, "/user/7").unwrap().get("id"), Some("7"));
+    }
+}
@@ -2,9 +2,14 @@
 
 use std::net::{TcpListener, TcpStream, SocketAddr, Shutdown};
 use std::io::prelude::*;
+use std::io::BufReader;
 use std::sync::{mpsc, mpsc::{RecvTimeoutError}};
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 pub use crate::websocket::{SERVER_WEB_SOCKET_PONG_MESSAGE, ServerWebSocket, ServerWebSocketMessage, ServerWebSocketMessageFormat, ServerWebSocketMessageHeader, SERVER_WEB_SOCKET_PING_MESSAGE};
+use crate::websocket::{ServerWebSocketWriter, SERVER_WEB_SOCKET_CLOSE_NORMAL, SERVER_WEB_SOCKET_CLOSE_GOING_AWAY, SERVER_WEB_SOCKET_CLOSE_NO_STATUS};
+use crate::websocket_deflate::PerMessageDeflateConfig;
 use crate::utils::*;
 
 #[derive(Clone)]
@@ -19,11 +24,85 @@ pub struct HttpServerResponse {
     pub body: Vec<u8>
 }
 
+impl HttpServerResponse {
+    // builds a complete response head from a status code and a list of extra headers.
+    // Content-Length is always derived from the body
+    pub fn from_parts(status: u16, headers: &[(&str, &str)], body: Vec<u8>) -> Self {
+        let mut header = format!("HTTP/1.1 {} {}\r\n", status, http_status_text(status));
+        for (key, value) in headers {
+            header.push_str(&format!("{}: {}\r\n", key, value));
+        }
+        header.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+        HttpServerResponse {header, body}
+    }
+    
+    pub fn status(status: u16) -> Self {
+        Self::from_parts(status, &[], Vec::new())
+    }
+    
+    pub fn text(status: u16, content_type: &str, body: impl Into<String>) -> Self {
+        Self::from_parts(status, &[("Content-Type", content_type)], body.into().into_bytes())
+    }
+    
+    pub fn not_found() -> Self {
+        Self::text(404, "text/plain", "Not Found")
+    }
+    
+    pub fn status_code(&self) -> u16 {
+        self.header.split(' ').nth(1).and_then( | v | v.parse().ok()).unwrap_or(0)
+    }
+    
+    fn closes_connection(&self) -> bool {
+        self.header.lines().any( | line | {
+            if let Some((key, value)) = line.split_once(':') {
+                key.trim().eq_ignore_ascii_case("connection") && value.trim().eq_ignore_ascii_case("close")
+            }
+            else {
+                false
+            }
+        })
+    }
+}
+
+// counts the messages sent through the response_sender of a websocket that made it onto the
+// wire, which lets callers see how far behind a slow client is
+#[derive(Clone, Default)]
+pub struct WebSocketWriteCounter {
+    inner: Arc<WriteCounterInner>,
+}
+
+#[derive(Default)]
+struct WriteCounterInner {
+    written: AtomicU64,
+    on_write: Mutex<Option<Box<dyn Fn() + Send>>>,
+}
+
+impl WebSocketWriteCounter {
+    pub fn written(&self) -> u64 {
+        self.inner.written.load(Ordering::Relaxed)
+    }
+    
+    // called on the socket writer thread after every message written
+    pub fn set_on_write(&self, on_write: impl Fn() + Send + 'static) {
+        *self.inner.on_write.lock().unwrap() = Some(Box::new(on_write));
+    }
+    
+    pub(crate) fn add_written(&self) {
+        self.inner.written.fetch_add(1, Ordering::Relaxed);
+        if let Some(on_write) = &*self.inner.on_write.lock().unwrap() {
+            on_write();
+        }
+    }
+}
+
 pub enum HttpServerRequest {
+    // an empty message on response_sender closes the socket
+    #[non_exhaustive]
     ConnectWebSocket {
         web_socket_id: u64,
         headers:HttpServerHeaders,
         response_sender: mpsc::Sender<Vec<u8 >>,
+        messages_written: WebSocketWriteCounter,
     },
     DisconnectWebSocket {
         web_socket_id: u64,
@@ -64,23 +143,45 @@ pub fn start_http_server(
                 let http_server = http_server.clone();
                 connection_counter += 1;
                 let _read_thread = std::thread::spawn(move || {
-                    
-                    let headers = HttpServerHeaders::from_tcp_stream(&mut tcp_stream);
-                    if headers.is_none() {
-                        return http_error_out(tcp_stream, 500);
-                    }
-                    let headers = headers.unwrap();
-                    
-                    if headers.sec_websocket_key.is_some() {
-                        return handle_web_socket(http_server, tcp_stream, headers, connection_counter);
-                    }
-                    if headers.verb == "POST" {
-                        return handle_post(http_server, tcp_stream, headers);
+                    let addr = if let Ok(addr) = tcp_stream.peer_addr() {addr} else {return};
+                    let mut reader = if let Ok(read_stream) = tcp_stream.try_clone() {
+                        BufReader::new(read_stream)
                     }
-                    if headers.verb == "GET" {
-                        return handle_get(http_server, tcp_stream, headers);
+                    else {
+                        return http_error_out(tcp_stream, 500)
+                    };
+                    let mut first_request = true;
+                    loop {
+                        let headers = HttpServerHeaders::from_reader(addr, &mut reader);
+                        if headers.is_none() {
+                            if first_request {
+                                return http_error_out(tcp_stream, 500);
+                            }
+                            let _ = tcp_stream.shutdown(Shutdown::Both);
+                            return
+                        }
+                        let headers = headers.unwrap();
+                        first_request = false;
+                        
+                        if headers.sec_websocket_key.is_some() {
+                            return handle_web_socket(http_server, tcp_stream, headers, connection_counter);
+                        }
+                        let keep_alive = headers.keep_alive();
+                        let response = match headers.verb.as_str() {
+                            "POST" => handle_post(&http_server, &mut reader, headers),
+                            "GET" | "HEAD" => handle_get(&http_server, headers),
+                            _ => None
+                        };
+                        let response = if let Some(response) = response {response} else {
+                            return http_error_out(tcp_stream, 500)
+                        };
+                        if !write_response(&mut tcp_stream, &response.0, response.1) || !keep_alive || response.0.closes_connection() {
+                            let _ = tcp_stream.shutdown(Shutdown::Both);
+                            return
+                        }
+                        // idle keep-alive connections are dropped after a while
+                        let _ = tcp_stream.set_read_timeout(Some(KEEP_ALIVE_TIMEOUT));
                     }
-                    http_error_out(tcp_stream, 500)
                 });
             }
         })
@@ -88,32 +189,27 @@ pub fn start_http_server(
     Some(listen_thread)
 }
 
-fn handle_post(http_server: HttpServer, mut tcp_stream: TcpStream, headers: HttpServerHeaders) {
-    // we have to have a content-length or bust
-    if headers.content_length.is_none() {
-        return http_error_out(tcp_stream, 500);
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// writes a response, leaving out the body for HEAD requests. Returns false if the write failed
+fn write_response(tcp_stream: &mut TcpStream, response: &HttpServerResponse, is_head: bool) -> bool {
+    if write_bytes_to_tcp_stream_no_error(tcp_stream, response.header.as_bytes()) {
+        return false
     }
-    let content_length = headers.content_length.unwrap();
+    if !is_head && write_bytes_to_tcp_stream_no_error(tcp_stream, &response.body) {
+        return false
+    }
+    true
+}
+
+fn handle_post(http_server: &HttpServer, reader: &mut BufReader<TcpStream>, headers: HttpServerHeaders) -> Option<(HttpServerResponse, bool)> {
+    // we have to have a content-length or bust
+    let content_length = headers.content_length?;
     if content_length > http_server.post_max_size {
-        return http_error_out(tcp_stream, 500);
-    }
-    let bytes_total = content_length as usize;
-    let mut body = Vec::new();
-    body.resize(bytes_total, 0u8);
-    
-    let mut bytes_left = bytes_total;
-    while bytes_left > 0 {
-        let buf = &mut body[(bytes_total - bytes_left)..bytes_total];
-        let bytes_read = tcp_stream.read(buf);
-        if bytes_read.is_err() {
-            return http_error_out(tcp_stream, 500);
-        }
-        let bytes_read = bytes_read.unwrap();
-        if bytes_read == 0 {
-            return http_error_out(tcp_stream, 500);
-        }
-        bytes_left -= bytes_read;
+        return None
     }
+    let mut body = vec![0u8; content_length as usize];
+    reader.read_exact(&mut body).ok()?;
     
     let (tx_socket, rx_socket) = mpsc::channel::<HttpServerResponse> ();
     if http_server.request.send(HttpServerRequest::Post {
@@ -121,42 +217,85 @@ fn handle_post(http_server: HttpServer, mut tcp_stream: TcpStream, headers: Http
         body,
         response: tx_socket
     }).is_err() {
-        return http_error_out(tcp_stream, 500);
+        return None
     };
-    
-    if let Ok(response) = rx_socket.recv() {
-        write_bytes_to_tcp_stream_no_error(&mut tcp_stream, response.header.as_bytes());
-        write_bytes_to_tcp_stream_no_error(&mut tcp_stream, &response.body);
-    }
-    let _ = tcp_stream.shutdown(Shutdown::Both);
+    rx_socket.recv().ok().map( | response | (response, false))
+}
+
+// what the websocket write thread sends, callers only ever see the Vec<u8> message channel
+enum WebSocketWrite {
+    Message(Vec<u8>),
+    Pong(Vec<u8>),
+    Close(u16),
 }
 
+const WEB_SOCKET_PING_INTERVAL: Duration = Duration::from_millis(2000);
+// a peer that hasn't sent anything, pongs included, for this long is considered gone
+const WEB_SOCKET_PONG_TIMEOUT: Duration = Duration::from_millis(10000);
+
 fn handle_web_socket(http_server: HttpServer, mut tcp_stream: TcpStream, headers: HttpServerHeaders, web_socket_id: u64) {
-    let upgrade_response = ServerWebSocket::create_upgrade_response(headers.sec_websocket_key.as_ref().unwrap());
+    let deflate = headers.header("Sec-WebSocket-Extensions").and_then(PerMessageDeflateConfig::server_accept);
+    let upgrade_response = ServerWebSocket::create_upgrade_response_with_extensions(headers.sec_websocket_key.as_ref().unwrap(), deflate.as_ref());
 
     write_bytes_to_tcp_stream_no_error(&mut tcp_stream, upgrade_response.as_bytes());
     
     let mut write_tcp_stream = tcp_stream.try_clone().unwrap();
     let (tx_socket, rx_socket) = mpsc::channel::<Vec<u8 >> ();
+    let (tx_write, rx_write) = mpsc::channel::<WebSocketWrite> ();
+    let start = Instant::now();
+    let last_seen = Arc::new(AtomicU64::new(0));
+    let messages_written = WebSocketWriteCounter::default();
+    
+    // callers send whole messages, an empty one closes the socket
+    let tx_forward = tx_write.clone();
+    let _forward_thread = std::thread::spawn(move || {
+        while let Ok(data) = rx_socket.recv() {
+            let close = data.is_empty();
+            let write = if close {WebSocketWrite::Close(SERVER_WEB_SOCKET_CLOSE_NORMAL)} else {WebSocketWrite::Message(data)};
+            if tx_forward.send(write).is_err() || close {
+                break
+            }
+        }
+    });
     
+    let mut writer = ServerWebSocketWriter::new(false);
+    if let Some(deflate) = &deflate {
+        writer = writer.with_deflate(deflate, true);
+    }
+    let write_last_seen = last_seen.clone();
+    let write_messages_written = messages_written.clone();
     let _write_thread = std::thread::spawn(move || {
-        // xx
         loop{
-            match rx_socket.recv_timeout(Duration::from_millis(2000)){
-                Ok(data)=>{
-                    if data.is_empty(){
+            let frame = match rx_write.recv_timeout(WEB_SOCKET_PING_INTERVAL){
+                Ok(WebSocketWrite::Message(data))=>{
+                    let frame = writer.message(ServerWebSocketMessageFormat::Binary, &data);
+                    if write_bytes_to_tcp_stream_no_error(&mut write_tcp_stream, &frame) {
                         break
                     }
-                    let header = ServerWebSocketMessageHeader::from_len(data.len(), ServerWebSocketMessageFormat::Binary, false);
-                    write_bytes_to_tcp_stream_no_error(&mut write_tcp_stream, header.as_slice());
-                    write_bytes_to_tcp_stream_no_error(&mut write_tcp_stream, &data);
+                    write_messages_written.add_written();
+                    continue
                 },
-                Err(RecvTimeoutError::Timeout)=>{ 
-                    write_bytes_to_tcp_stream_no_error(&mut write_tcp_stream, &SERVER_WEB_SOCKET_PING_MESSAGE);
+                Ok(WebSocketWrite::Pong(data))=>{
+                    writer.pong(&data)
+                }
+                Ok(WebSocketWrite::Close(code))=>{
+                    write_bytes_to_tcp_stream_no_error(&mut write_tcp_stream, &writer.close(code, ""));
+                    break
+                }
+                Err(RecvTimeoutError::Timeout)=>{
+                    // the reader can store a newer time between the two reads
+                    let idle = (start.elapsed().as_millis() as u64).saturating_sub(write_last_seen.load(Ordering::Relaxed));
+                    if idle > WEB_SOCKET_PONG_TIMEOUT.as_millis() as u64 {
+                        break
+                    }
+                    writer.ping(&[])
                 }
                 Err(RecvTimeoutError::Disconnected)=>{
                     break
                 }
+            };
+            if write_bytes_to_tcp_stream_no_error(&mut write_tcp_stream, &frame) {
+                break
             }
         }
         let _ = write_tcp_stream.shutdown(Shutdown::Both);
@@ -165,26 +304,33 @@ fn handle_web_socket(http_server: HttpServer, mut tcp_stream: TcpStream, headers
     if http_server.request.send(HttpServerRequest::ConnectWebSocket {
         headers,
         web_socket_id,
-        response_sender: tx_socket.clone()
+        response_sender: tx_socket.clone(),
+        messages_written,
     }).is_err() {
         let _ = tcp_stream.shutdown(Shutdown::Both);
         return
     };
     
     let mut web_socket = ServerWebSocket::new();
-    loop {
+    if let Some(deflate) = &deflate {
+        web_socket = web_socket.with_deflate(deflate, true);
+    }
+    let mut closing = false;
+    while !closing {
         let mut data = [0u8; 65535];
         match tcp_stream.read(&mut data) {
             Ok(n) => {
                 if n == 0 {
-                    let _ = tcp_stream.shutdown(Shutdown::Both);
-                    let _ = tx_socket.send(Vec::new());
                     break 
                 }
+                last_seen.store(start.elapsed().as_millis() as u64, Ordering::Relaxed);
                 web_socket.parse(&data[0..n], | result | {
+                    if closing {
+                        return
+                    }
                     match result {
-                        Ok(ServerWebSocketMessage::Ping(_)) => {
-                            let _ = tx_socket.send(SERVER_WEB_SOCKET_PONG_MESSAGE.to_vec());
+                        Ok(ServerWebSocketMessage::Ping(data)) => {
+                            let _ = tx_write.send(WebSocketWrite::Pong(data.to_vec()));
                         },
                         Ok(ServerWebSocketMessage::Pong(_)) => {
                         },
@@ -197,48 +343,49 @@ fn handle_web_socket(http_server: HttpServer, mut tcp_stream: TcpStream, headers
                                 data: data.to_vec(),
                             }).is_err() {
                                 eprintln!("Websocket message deserialize error");
-                                let _ = tcp_stream.shutdown(Shutdown::Both);
-                                let _ = tx_socket.send(Vec::new());
+                                let _ = tx_write.send(WebSocketWrite::Close(SERVER_WEB_SOCKET_CLOSE_GOING_AWAY));
+                                closing = true;
                             };
                         },
-                        Ok(ServerWebSocketMessage::Close) => {
-                            let _ = tcp_stream.shutdown(Shutdown::Both);
+                        Ok(ServerWebSocketMessage::Close {code, ..}) => {
+                            // echo the code back to complete the closing handshake
+                            let code = if code == SERVER_WEB_SOCKET_CLOSE_NO_STATUS {SERVER_WEB_SOCKET_CLOSE_NORMAL} else {code};
+                            let _ = tx_write.send(WebSocketWrite::Close(code));
+                            closing = true;
                         }
                         Err(e) => {
                             eprintln!("Websocket error {:?}", e);
-                            let _ = tcp_stream.shutdown(Shutdown::Both);
-                            let _ = tx_socket.send(Vec::new());
+                            let _ = tx_write.send(WebSocketWrite::Close(e.close_code()));
+                            closing = true;
                         }
                     }
                 });
             }
             Err(_) => {
                 println!("Websocket closed");
-                let _ = tcp_stream.shutdown(Shutdown::Both);
-                let _ = tx_socket.send(Vec::new());
                 break;
             }
         }
     }
+    if !closing {
+        let _ = tcp_stream.shutdown(Shutdown::Both);
+        let _ = tx_write.send(WebSocketWrite::Close(SERVER_WEB_SOCKET_CLOSE_GOING_AWAY));
+    }
     
     let _ =  http_server.request.send(HttpServerRequest::DisconnectWebSocket {
         web_socket_id,
     });
 }
 
-fn handle_get(http_server: HttpServer, mut tcp_stream: TcpStream, headers: HttpServerHeaders) {
-    // send our channel the post
+fn handle_get(http_server: &HttpServer, headers: HttpServerHeaders) -> Option<(HttpServerResponse, bool)> {
+    // send our channel the get
+    let is_head = headers.verb == "HEAD";
     let (tx_socket, rx_socket) = mpsc::channel::<HttpServerResponse> ();
     if http_server.request.send(HttpServerRequest::Get {
         headers,
         response_sender: tx_socket
     }).is_err() {
-        return http_error_out(tcp_stream, 500);
+        return None
     };
-    
-    if let Ok(response) = rx_socket.recv() {
-        write_bytes_to_tcp_stream_no_error(&mut tcp_stream, response.header.as_bytes());
-        write_bytes_to_tcp_stream_no_error(&mut tcp_stream, &response.body);
-    }
-    let _ = tcp_stream.shutdown(Shutdown::Both);
-}
\ No newline at end of file
+    rx_socket.recv().ok().map( | response | (response, is_head))
+}
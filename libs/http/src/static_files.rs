@@ -0,0 +1,286 @@
+// serves files from a directory with mime types, etags, byte ranges and precompressed
+// .br/.gz siblings picked through Accept-Encoding
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use crate::router::{HttpRouteHandler, HttpRouteRequest};
+use crate::server::HttpServerResponse;
+use crate::utils::{HttpServerHeaders, percent_decode};
+
+pub fn mime_type_from_path(path: &str) -> &'static str {
+    let ext = path.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+    match ext.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" | "md" | "rs" | "toml" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "wasm" => "application/wasm",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        _ => "application/octet-stream"
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpContentEncoding {
+    Identity,
+    Gzip,
+    Brotli,
+}
+
+impl HttpContentEncoding {
+    pub fn token(&self) -> &'static str {
+        match self {
+            Self::Identity => "identity",
+            Self::Gzip => "gzip",
+            Self::Brotli => "br",
+        }
+    }
+
+    fn file_suffix(&self) -> &'static str {
+        match self {
+            Self::Identity => "",
+            Self::Gzip => ".gz",
+            Self::Brotli => ".br",
+        }
+    }
+}
+
+// returns the encodings the client accepts, best first. Entries with q=0 are left out
+pub fn parse_accept_encoding(value: &str) -> Vec<HttpContentEncoding> {
+    let mut out: Vec<(HttpContentEncoding, f32)> = Vec::new();
+    for item in value.split(',') {
+        let mut parts = item.split(';');
+        let token = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+        let mut q = 1.0;
+        for param in parts {
+            if let Some(v) = param.trim().strip_prefix("q=") {
+                q = v.trim().parse().unwrap_or(0.0);
+            }
+        }
+        let encoding = match token.as_str() {
+            "br" => HttpContentEncoding::Brotli,
+            "gzip" | "x-gzip" => HttpContentEncoding::Gzip,
+            _ => continue
+        };
+        if q > 0.0 {
+            out.push((encoding, q));
+        }
+    }
+    // prefer brotli over gzip when the client rates them equal
+    out.sort_by( | a, b | b.1.partial_cmp(&a.1).unwrap().then((a.0 != HttpContentEncoding::Brotli).cmp(&(b.0 != HttpContentEncoding::Brotli))));
+    out.into_iter().map( | (e, _) | e).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpByteRange {
+    Satisfiable {start: u64, end: u64},
+    NotSatisfiable,
+}
+
+// parses a single 'bytes=' range against a resource length. Multiple ranges and other units
+// return None, upon which the whole resource is served as the spec allows
+pub fn parse_range(value: &str, len: u64) -> Option<HttpByteRange> {
+    let spec = value.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+    let range = if start.is_empty() {
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 || len == 0 {
+            return Some(HttpByteRange::NotSatisfiable)
+        }
+        HttpByteRange::Satisfiable {start: len.saturating_sub(suffix), end: len - 1}
+    }
+    else {
+        let start: u64 = start.parse().ok()?;
+        let end: u64 = if end.is_empty() {u64::MAX} else {end.parse().ok()?};
+        if end < start {
+            return None
+        }
+        if start >= len {
+            return Some(HttpByteRange::NotSatisfiable)
+        }
+        HttpByteRange::Satisfiable {start, end: end.min(len - 1)}
+    };
+    Some(range)
+}
+
+pub struct StaticFileService {
+    pub root: PathBuf,
+    pub cache_control: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl StaticFileService {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            cache_control: "max-age=0".to_string(),
+            headers: Vec::new()
+        }
+    }
+
+    pub fn with_header(mut self, key: &str, value: &str) -> Self {
+        self.headers.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    // the headers needed for SharedArrayBuffer, which our wasm builds depend on
+    pub fn with_cross_origin_isolation(self) -> Self {
+        self.with_header("Cross-Origin-Embedder-Policy", "require-corp")
+            .with_header("Cross-Origin-Opener-Policy", "same-origin")
+    }
+
+    pub fn with_cache_control(mut self, cache_control: &str) -> Self {
+        self.cache_control = cache_control.to_string();
+        self
+    }
+
+    // maps a request path onto the root, refusing anything that tries to climb out of it
+    pub fn resolve_path(&self, path: &str) -> Option<PathBuf> {
+        let path = percent_decode(path, false);
+        if path.contains('\\') || path.contains('\0') {
+            return None
+        }
+        let mut out = self.root.clone();
+        for part in path.split('/') {
+            match part {
+                "" | "." => (),
+                ".." => return None,
+                part => out.push(part)
+            }
+        }
+        Some(out)
+    }
+
+    pub fn serve(&self, headers: &HttpServerHeaders, path: &str) -> HttpServerResponse {
+        let file_path = if let Some(file_path) = self.resolve_path(path) {file_path} else {
+            return HttpServerResponse::text(403, "text/plain", "Forbidden")
+        };
+        let range = headers.header("Range");
+
+        // byte ranges are served from the identity encoding only
+        let mut candidates = if range.is_none() {
+            headers.accept_encoding.as_deref().map(parse_accept_encoding).unwrap_or_default()
+        }
+        else {
+            Vec::new()
+        };
+        candidates.push(HttpContentEncoding::Identity);
+
+        for encoding in candidates {
+            let mut encoded_path = file_path.clone().into_os_string();
+            encoded_path.push(encoding.file_suffix());
+            if let Some(response) = self.serve_file(headers, path, Path::new(&encoded_path), encoding, range) {
+                return response
+            }
+        }
+        HttpServerResponse::not_found()
+    }
+
+    fn serve_file(&self, headers: &HttpServerHeaders, path: &str, file_path: &Path, encoding: HttpContentEncoding, range: Option<&str>) -> Option<HttpServerResponse> {
+        let mut file = File::open(file_path).ok()?;
+        let metadata = file.metadata().ok()?;
+        if !metadata.is_file() {
+            return None
+        }
+        let len = metadata.len();
+        let modified = metadata.modified().ok()
+            .and_then( | m | m.duration_since(UNIX_EPOCH).ok())
+            .map( | d | d.as_nanos())
+            .unwrap_or(0);
+        let etag = format!("\"{:x}-{:x}{}\"", len, modified, encoding.file_suffix());
+
+        let mut out_headers: Vec<(&str, &str)> = self.headers.iter().map( | (k, v) | (k.as_str(), v.as_str())).collect();
+        out_headers.push(("ETag", &etag));
+        out_headers.push(("Cache-Control", &self.cache_control));
+        out_headers.push(("Accept-Ranges", "bytes"));
+        out_headers.push(("Vary", "Accept-Encoding"));
+
+        if let Some(if_none_match) = headers.header("If-None-Match") {
+            if if_none_match.split(',').any( | tag | {let tag = tag.trim(); tag == "*" || tag.trim_start_matches("W/") == etag}) {
+                return Some(HttpServerResponse::from_parts(304, &out_headers, Vec::new()))
+            }
+        }
+
+        out_headers.push(("Content-Type", mime_type_from_path(path)));
+        if encoding != HttpContentEncoding::Identity {
+            out_headers.push(("Content-Encoding", encoding.token()));
+        }
+
+        // If-Range only lets the range through when the client still has our current version
+        let range = range.filter( | _ | headers.header("If-Range").is_none_or( | tag | tag == etag));
+        match range.and_then( | range | parse_range(range, len)) {
+            Some(HttpByteRange::Satisfiable {start, end}) => {
+                let mut body = vec![0u8; (end - start + 1) as usize];
+                file.seek(SeekFrom::Start(start)).ok()?;
+                file.read_exact(&mut body).ok()?;
+                let content_range = format!("bytes {}-{}/{}", start, end, len);
+                out_headers.push(("Content-Range", &content_range));
+                Some(HttpServerResponse::from_parts(206, &out_headers, body))
+            }
+            Some(HttpByteRange::NotSatisfiable) => {
+                let content_range = format!("bytes */{}", len);
+                out_headers.push(("Content-Range", &content_range));
+                Some(HttpServerResponse::from_parts(416, &out_headers, Vec::new()))
+            }
+            None => {
+                let mut body = Vec::with_capacity(len as usize);
+                file.read_to_end(&mut body).ok()?;
+                Some(HttpServerResponse::from_parts(200, &out_headers, body))
+            }
+        }
+    }
+}
+
+impl HttpRouteHandler for StaticFileService {
+    fn handle(&self, request: &HttpRouteRequest) -> HttpServerResponse {
+        let path = request.params.rest.as_deref().unwrap_or(&request.headers.path);
+        self.serve(request.headers, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("bytes=0-9", 100), Some(HttpByteRange::Satisfiable {start: 0, end: 9}));
+        assert_eq!(parse_range("bytes=90-", 100), Some(HttpByteRange::Satisfiable {start: 90, end: 99}));
+        assert_eq!(parse_range("bytes=-10", 100), Some(HttpByteRange::Satisfiable {start: 90, end: 99}));
+        assert_eq!(parse_range("bytes=50-500", 100), Some(HttpByteRange::Satisfiable {start: 50, end: 99}));
+        assert_eq!(parse_range("bytes=100-", 100), Some(HttpByteRange::NotSatisfiable));
+        assert_eq!(parse_range("bytes=0-1,5-6", 100), None);
+        assert_eq!(parse_range("items=0-1", 100), None);
+    }
+
+    #[test]
+    fn accept_encoding() {
+        use HttpContentEncoding::*;
+        assert_eq!(parse_accept_encoding("gzip, deflate, br"), vec![Brotli, Gzip]);
+        assert_eq!(parse_accept_encoding("br;q=0.5, gzip"), vec![Gzip, Brotli]);
+        assert_eq!(parse_accept_encoding("br;q=0, identity"), vec![]);
+    }
+}
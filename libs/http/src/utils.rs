@@ -1,6 +1,7 @@
 use std::net::{TcpStream, Shutdown, SocketAddr};
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::str::FromStr;
 
 pub fn write_bytes_to_tcp_stream_no_error(tcp_stream: &mut TcpStream, bytes: &[u8]) -> bool {
     let bytes_total = bytes.len();
@@ -25,6 +26,25 @@ pub fn http_error_out(mut tcp_stream: TcpStream, code: usize) {
     let _ = tcp_stream.shutdown(Shutdown::Both);
 }
 
+pub fn http_status_text(code: u16) -> &'static str {
+    match code {
+        200 => "OK",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        416 => "Range Not Satisfiable",
+        500 => "Internal Server Error",
+        _ => "Unknown"
+    }
+}
+
 
 pub fn split_header_line<'a>(inp: &'a str, what: &str) -> Option<&'a str> {
     let mut what_lc = what.to_string();
@@ -58,7 +78,7 @@ pub fn parse_url_path(url: &str) -> Option<(String, Option<String>)> {
     Some((url, search))
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HttpServerHeaders {
     pub addr: SocketAddr,
     pub lines: Vec<String>,
@@ -73,16 +93,23 @@ pub struct HttpServerHeaders {
 
 impl HttpServerHeaders {
     pub fn from_tcp_stream(tcp_stream: &mut TcpStream) -> Option<HttpServerHeaders> {
-        let addr = tcp_stream.peer_addr().unwrap();
-        let mut reader = BufReader::new(tcp_stream);
-        
+        let addr = tcp_stream.peer_addr().ok()?;
+        Self::from_reader(addr, &mut BufReader::new(tcp_stream))
+    }
+    
+    // reads one request head from a buffered reader. Keep-alive connections reuse the same
+    // reader across requests so bytes buffered past the head are not lost
+    pub fn from_reader<R: BufRead>(addr: SocketAddr, reader: &mut R) -> Option<HttpServerHeaders> {
         let mut lines = Vec::new();
         let mut content_length = None;
         let mut accept_encoding = None;
         let mut sec_websocket_key = None;
         let mut line = String::new();
         
-        while reader.read_line(&mut line).is_ok() { // TODO replace this with a non-line read
+        while let Ok(n) = reader.read_line(&mut line) { // TODO replace this with a non-line read
+            if n == 0 { // connection closed
+                return None
+            }
             if line == "\r\n" { // the newline
                 break;
             }
@@ -112,6 +139,10 @@ impl HttpServerHeaders {
             verb = "GET";
             path = parse_url_path(v)
         }
+        else if let Some(v) = split_header_line(&lines[0], "HEAD ") {
+            verb = "HEAD";
+            path = parse_url_path(v)
+        }
         else if let Some(v) = split_header_line(&lines[0], "POST ") {
             verb = "POST";
             path = parse_url_path(v)
@@ -142,4 +173,72 @@ impl HttpServerHeaders {
             sec_websocket_key
         })
     }
+    
+    // case insensitive lookup of a header value
+    pub fn header(&self, name: &str) -> Option<&str> {
+        for line in self.lines.iter().skip(1) {
+            if let Some((key, value)) = line.split_once(':') {
+                if key.trim().eq_ignore_ascii_case(name) {
+                    return Some(value.trim())
+                }
+            }
+        }
+        None
+    }
+    
+    pub fn header_parse<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.header(name)?.parse().ok()
+    }
+    
+    pub fn is_http_1_0(&self) -> bool {
+        self.lines[0].trim_end().ends_with("HTTP/1.0")
+    }
+    
+    // HTTP/1.1 connections persist unless asked otherwise, HTTP/1.0 only when asked
+    pub fn keep_alive(&self) -> bool {
+        match self.header("Connection") {
+            Some(v) if v.eq_ignore_ascii_case("close") => false,
+            Some(v) if v.eq_ignore_ascii_case("keep-alive") => true,
+            _ => !self.is_http_1_0()
+        }
+    }
+    
+    // splits the search string into its decoded key/value pairs
+    pub fn query(&self) -> Vec<(String, String)> {
+        let mut out = Vec::new();
+        if let Some(search) = &self.search {
+            for pair in search.trim_start_matches('?').split('&') {
+                if pair.is_empty() {
+                    continue
+                }
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                out.push((percent_decode(key, true), percent_decode(value, true)));
+            }
+        }
+        out
+    }
+}
+
+// decodes %XX escapes, in query strings a '+' also stands for a space
+pub fn percent_decode(inp: &str, plus_is_space: bool) -> String {
+    let bytes = inp.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then( | v | u8::from_str_radix(v, 16).ok());
+                if let Some(v) = hex {
+                    out.push(v);
+                    i += 3;
+                    continue
+                }
+                out.push(b'%');
+            }
+            b'+' if plus_is_space => out.push(b' '),
+            c => out.push(c)
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
 }
@@ -1,6 +1,7 @@
-
 use crate::digest::{Sha1, base64_encode};
+use crate::websocket_deflate::{PerMessageDeflateConfig, PerMessageDeflater, PerMessageInflater};
 use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 #[derive(Debug, PartialEq)]
 enum State {
@@ -25,6 +26,32 @@ impl State {
     }
 }
 
+const OPCODE_CONTINUATION: u8 = 0;
+const OPCODE_TEXT: u8 = 1;
+const OPCODE_BINARY: u8 = 2;
+const OPCODE_CLOSE: u8 = 8;
+const OPCODE_PING: u8 = 9;
+const OPCODE_PONG: u8 = 10;
+
+const FLAG_FIN: u8 = 128;
+const FLAG_RSV1: u8 = 64;
+const FLAG_RSV2_3: u8 = 32 | 16;
+
+// close codes from RFC 6455 section 7.4.1
+pub const SERVER_WEB_SOCKET_CLOSE_NORMAL: u16 = 1000;
+pub const SERVER_WEB_SOCKET_CLOSE_GOING_AWAY: u16 = 1001;
+pub const SERVER_WEB_SOCKET_CLOSE_PROTOCOL_ERROR: u16 = 1002;
+pub const SERVER_WEB_SOCKET_CLOSE_UNSUPPORTED_DATA: u16 = 1003;
+pub const SERVER_WEB_SOCKET_CLOSE_NO_STATUS: u16 = 1005;
+pub const SERVER_WEB_SOCKET_CLOSE_ABNORMAL: u16 = 1006;
+pub const SERVER_WEB_SOCKET_CLOSE_INVALID_PAYLOAD: u16 = 1007;
+pub const SERVER_WEB_SOCKET_CLOSE_POLICY_VIOLATION: u16 = 1008;
+pub const SERVER_WEB_SOCKET_CLOSE_MESSAGE_TOO_BIG: u16 = 1009;
+pub const SERVER_WEB_SOCKET_CLOSE_INTERNAL_ERROR: u16 = 1011;
+
+const WEB_SOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
 pub struct ServerWebSocket {
     head: [u8; 8],
     head_expected: usize,
@@ -33,12 +60,19 @@ pub struct ServerWebSocket {
     data_len: usize,
     input_read: usize,
     mask_counter: usize,
-    is_ping: bool,
-    is_pong: bool,
-    is_partial: bool,
-    is_text: bool,
+    opcode: u8,
+    is_final: bool,
+    is_compressed: bool,
     is_masked: bool,
-    state: State
+    state: State,
+    // a fragmented message being reassembled
+    message: Vec<u8>,
+    message_opcode: Option<u8>,
+    message_compressed: bool,
+    inflated: Vec<u8>,
+    inflater: Option<PerMessageInflater>,
+    failed: bool,
+    pub max_message_size: usize,
 }
 
 pub enum ServerWebSocketMessage<'a> {
@@ -46,13 +80,40 @@ pub enum ServerWebSocketMessage<'a> {
     Pong(&'a [u8]),
     Text(&'a str),
     Binary(&'a [u8]),
-    Close
+    // code is SERVER_WEB_SOCKET_CLOSE_NO_STATUS when the peer didn't send one
+    Close {code: u16, reason: &'a str}
 }
 
 #[derive(Debug)]
 pub enum ServerWebSocketError<'a> {
     OpcodeNotSupported(u8),
     TextNotUTF8(&'a [u8]),
+    ReservedBitsSet,
+    ControlFrameFragmented,
+    ControlFrameTooLong,
+    // a 64 bit length with the most significant bit set
+    InvalidLength,
+    UnexpectedContinuation,
+    ExpectedContinuation,
+    InvalidCloseCode(u16),
+    InvalidClosePayload,
+    MessageTooLarge(usize),
+    DecompressFailed,
+}
+
+impl ServerWebSocketError<'_> {
+    // the close code to send to the peer before dropping the connection
+    pub fn close_code(&self) -> u16 {
+        match self {
+            Self::TextNotUTF8(_) | Self::InvalidClosePayload | Self::DecompressFailed => SERVER_WEB_SOCKET_CLOSE_INVALID_PAYLOAD,
+            Self::MessageTooLarge(_) => SERVER_WEB_SOCKET_CLOSE_MESSAGE_TOO_BIG,
+            _ => SERVER_WEB_SOCKET_CLOSE_PROTOCOL_ERROR
+        }
+    }
+}
+
+pub fn is_valid_close_code(code: u16) -> bool {
+    matches!(code, 1000..=1003 | 1007..=1011 | 3000..=4999)
 }
 
 pub const SERVER_WEB_SOCKET_PING_MESSAGE:[u8;2] = [128 | 9,0];
@@ -60,7 +121,24 @@ pub const SERVER_WEB_SOCKET_PONG_MESSAGE:[u8;2] = [128 | 10,0];
 
 pub enum ServerWebSocketMessageFormat {
     Binary,
-    Text
+    Text,
+    Continuation,
+    Close,
+    Ping,
+    Pong,
+}
+
+impl ServerWebSocketMessageFormat {
+    fn opcode(&self) -> u8 {
+        match self {
+            Self::Continuation => OPCODE_CONTINUATION,
+            Self::Text => OPCODE_TEXT,
+            Self::Binary => OPCODE_BINARY,
+            Self::Close => OPCODE_CLOSE,
+            Self::Ping => OPCODE_PING,
+            Self::Pong => OPCODE_PONG,
+        }
+    }
 }
 
 pub struct ServerWebSocketMessageHeader {
@@ -72,11 +150,20 @@ pub struct ServerWebSocketMessageHeader {
 
 impl ServerWebSocketMessageHeader {
     pub fn from_len(len: usize, format: ServerWebSocketMessageFormat, masked: bool)->Self{
+        Self::from_len_flags(len, format, masked, true, false)
+    }
+
+    // is_final is false on all but the last frame of a fragmented message,
+    // compressed sets RSV1 which permessage-deflate uses on the first frame
+    pub fn from_len_flags(len: usize, format: ServerWebSocketMessageFormat, masked: bool, is_final: bool, compressed: bool)->Self{
         let mut data = [0u8;14];
-        
-        match format {
-            ServerWebSocketMessageFormat::Binary => data[0] = 128 | 2,
-            ServerWebSocketMessageFormat::Text => data[0] = 128 | 1,
+
+        data[0] = format.opcode();
+        if is_final {
+            data[0] |= FLAG_FIN;
+        }
+        if compressed {
+            data[0] |= FLAG_RSV1;
         }
 
         if masked {
@@ -92,31 +179,23 @@ impl ServerWebSocketMessageHeader {
         }
         else if len < 65536{
             data[1] |= 126;
-            let bytes = &(len as u16).to_be_bytes();
-            for (i, &byte) in bytes.iter().enumerate() {
-                data[i + 2] = byte;
-            }
+            data[2..4].copy_from_slice(&(len as u16).to_be_bytes());
             header_len = 4;
         }
         else{
             data[1] |= 127;
-            let bytes = &(len as u64).to_be_bytes();
-            for (i, &byte) in bytes.iter().enumerate() {
-                data[i + 2] = byte;
-            }
+            data[2..10].copy_from_slice(&(len as u64).to_be_bytes());
             header_len = 10;
         }
 
         if masked {
-            for i in header_len..header_len + 4 {
-                data[i] = Self::random_byte();
-            }
-            return ServerWebSocketMessageHeader{len: header_len + 4, data, format, masked}
+            data[header_len..header_len + 4].copy_from_slice(&random_u64().to_le_bytes()[0..4]);
+            ServerWebSocketMessageHeader{len: header_len + 4, data, format, masked}
         } else {
-            return ServerWebSocketMessageHeader{len: header_len, data, format, masked}
+            ServerWebSocketMessageHeader{len: header_len, data, format, masked}
         }
     }
-    
+
     pub fn as_slice(&self)->&[u8]{
         &self.data[0..self.len]
     }
@@ -125,7 +204,7 @@ impl ServerWebSocketMessageHeader {
         if self.masked {
             match self.len {
                 6 => Some(&self.data[2..6]),
-                10 => Some(&self.data[6..10]),
+                8 => Some(&self.data[4..8]),
                 14 => Some(&self.data[10..14]),
                 _ => None
             }
@@ -133,12 +212,17 @@ impl ServerWebSocketMessageHeader {
             None
         }
     }
+}
 
-    // TODO Improve this using a proper random number generator
-    fn random_byte() -> u8 {
-        let num = SystemTime::now().duration_since(UNIX_EPOCH).expect("duration_since failed").subsec_nanos();
-        num as u8
-    }
+// masks and handshake keys need to be unpredictable, not cryptographically strong.
+// splitmix64 over the clock and a counter does that without pulling in a dependency
+pub fn random_u64() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map( | d | d.as_nanos() as u64).unwrap_or(0);
+    let mut z = nanos.wrapping_add(COUNTER.fetch_add(0x9E3779B97F4A7C15, Ordering::Relaxed));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
 }
 
 impl ServerWebSocket {
@@ -151,41 +235,111 @@ impl ServerWebSocket {
             data_len: 0,
             input_read: 0,
             mask_counter: 0,
-            is_ping: false,
-            is_pong: false,
+            opcode: 0,
+            is_final: false,
+            is_compressed: false,
             is_masked: false,
-            is_partial: false,
-            is_text: false,
-            state: State::Opcode
+            state: State::Opcode,
+            message: Vec::new(),
+            message_opcode: None,
+            message_compressed: false,
+            inflated: Vec::new(),
+            inflater: None,
+            failed: false,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
         }
     }
-    
+
+    // enables decompression of incoming messages after permessage-deflate was negotiated
+    pub fn with_deflate(mut self, config: &PerMessageDeflateConfig, is_server: bool) -> Self {
+        self.inflater = Some(config.inflater(is_server));
+        self
+    }
+
     pub fn message_to_frame(msg:ServerWebSocketMessage) ->Vec<u8>
     {
         match &msg{
             ServerWebSocketMessage::Text(data)=>{
                 let header = ServerWebSocketMessageHeader::from_len(data.len(), ServerWebSocketMessageFormat::Text, false);
-                ServerWebSocket::build_message(header, &data.to_string().into_bytes())
+                ServerWebSocket::build_message(header, data.as_bytes())
             }
             ServerWebSocketMessage::Binary(data)=>{
                 let header = ServerWebSocketMessageHeader::from_len(data.len(), ServerWebSocketMessageFormat::Binary, false);
-                ServerWebSocket::build_message(header, &data)
+                ServerWebSocket::build_message(header, data)
+            }
+            ServerWebSocketMessage::Ping(data)=>{
+                let header = ServerWebSocketMessageHeader::from_len(data.len(), ServerWebSocketMessageFormat::Ping, false);
+                ServerWebSocket::build_message(header, data)
+            }
+            ServerWebSocketMessage::Pong(data)=>{
+                let header = ServerWebSocketMessageHeader::from_len(data.len(), ServerWebSocketMessageFormat::Pong, false);
+                ServerWebSocket::build_message(header, data)
+            }
+            ServerWebSocketMessage::Close{code, reason}=>{
+                ServerWebSocket::build_close_message(*code, reason, false)
             }
-            _=>panic!()
-        }  
+        }
     }
 
-    pub fn create_upgrade_response(key: &str) -> String {
-        let to_hash = format!("{}258EAFA5-E914-47DA-95CA-C5AB0DC85B11", key);
+    pub fn create_accept_key(key: &str) -> String {
+        let to_hash = format!("{}{}", key, WEB_SOCKET_GUID);
         let mut sha1 = Sha1::new();
         sha1.update(to_hash.as_bytes());
         let out_bytes = sha1.finalise();
-        let base64 = base64_encode(&out_bytes);
-        let response_ack = format!(
-            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
-            base64
-        );
-        response_ack
+        base64_encode(&out_bytes)
+    }
+
+    // a fresh Sec-WebSocket-Key for a client handshake
+    pub fn create_client_key() -> String {
+        let mut bytes = [0u8; 16];
+        bytes[0..8].copy_from_slice(&random_u64().to_le_bytes());
+        bytes[8..16].copy_from_slice(&random_u64().to_le_bytes());
+        base64_encode(&bytes)
+    }
+
+    pub fn create_upgrade_response(key: &str) -> String {
+        Self::create_upgrade_response_with_extensions(key, None)
+    }
+
+    pub fn create_upgrade_response_with_extensions(key: &str, deflate: Option<&PerMessageDeflateConfig>) -> String {
+        let extensions = if let Some(deflate) = deflate {
+            format!("Sec-WebSocket-Extensions: {}\r\n", deflate.to_header_value())
+        }
+        else {
+            String::new()
+        };
+        format!(
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n{}\r\n",
+            Self::create_accept_key(key),
+            extensions
+        )
+    }
+
+    // checks the response head of a client handshake, returning the negotiated
+    // permessage-deflate parameters if the server accepted our offer
+    pub fn validate_upgrade_response(lines: &[String], key: &str) -> Result<Option<PerMessageDeflateConfig>, String> {
+        let status = lines.first().ok_or("Empty websocket upgrade response")?;
+        if status.split(' ').nth(1) != Some("101") {
+            return Err(format!("Websocket upgrade refused: {}", status.trim()))
+        }
+        let header = | name: &str | lines.iter().skip(1).find_map( | line | {
+            let (key, value) = line.split_once(':')?;
+            if key.trim().eq_ignore_ascii_case(name) {Some(value.trim().to_string())} else {None}
+        });
+        if !header("Upgrade").is_some_and( | v | v.eq_ignore_ascii_case("websocket")) {
+            return Err("Websocket upgrade response is missing Upgrade: websocket".into())
+        }
+        if header("Sec-WebSocket-Accept").as_deref() != Some(Self::create_accept_key(key).as_str()) {
+            return Err("Websocket upgrade response has an invalid Sec-WebSocket-Accept".into())
+        }
+        if let Some(extensions) = header("Sec-WebSocket-Extensions") {
+            let mut configs = PerMessageDeflateConfig::parse(&extensions);
+            if configs.len() != 1 {
+                return Err(format!("Websocket server answered with unsupported extensions: {}", extensions))
+            }
+            return Ok(configs.pop())
+        }
+        Ok(None)
     }
 
     pub fn build_message(mut header: ServerWebSocketMessageHeader, data: &[u8])->Vec<u8>{
@@ -199,7 +353,19 @@ impl ServerWebSocket {
         }
         frame
     }
-    
+
+    pub fn build_close_message(code: u16, reason: &str, masked: bool) -> Vec<u8> {
+        let mut payload = code.to_be_bytes().to_vec();
+        // control frames carry at most 125 bytes, so the reason gets cut on a char boundary
+        let mut reason_len = reason.len().min(123);
+        while !reason.is_char_boundary(reason_len) {
+            reason_len -= 1;
+        }
+        payload.extend_from_slice(&reason.as_bytes()[0..reason_len]);
+        let header = ServerWebSocketMessageHeader::from_len(payload.len(), ServerWebSocketMessageFormat::Close, masked);
+        Self::build_message(header, &payload)
+    }
+
     fn parse_head(&mut self, input: &[u8]) -> bool {
         while self.head_expected > 0
             && self.input_read < input.len()
@@ -212,18 +378,17 @@ impl ServerWebSocket {
         }
         self.head_expected != 0
     }
-    
-    fn to_state(&mut self, state: State) {
+
+    fn set_state(&mut self, state: State) {
         match state {
             State::Data => {
                 self.mask_counter = 0;
                 self.data.clear();
             }
             State::Opcode => {
-                self.is_ping = false;
-                self.is_pong = false;
-                self.is_partial = false;
-                self.is_text = false;
+                self.opcode = 0;
+                self.is_final = false;
+                self.is_compressed = false;
                 self.is_masked = false;
             },
             _ => ()
@@ -232,39 +397,166 @@ impl ServerWebSocket {
         self.head_expected = state.head_expected();
         self.state = state;
     }
-    
+
+    fn reset_message(&mut self) {
+        self.message.clear();
+        self.message_opcode = None;
+        self.message_compressed = false;
+    }
+
+    fn after_len(&mut self) -> Result<(), ServerWebSocketError<'static>> {
+        if self.opcode >= OPCODE_CLOSE && self.data_len > 125 {
+            return Err(ServerWebSocketError::ControlFrameTooLong)
+        }
+        let total_len = self.message.len().saturating_add(self.data_len);
+        if total_len > self.max_message_size {
+            return Err(ServerWebSocketError::MessageTooLarge(total_len))
+        }
+        if self.is_masked {
+            self.set_state(State::Mask);
+        }
+        else {
+            self.set_state(State::Data);
+        }
+        Ok(())
+    }
+
+    fn parse_opcode(&mut self) -> Result<(), ServerWebSocketError<'static>> {
+        let byte = self.head[0];
+        if byte & FLAG_RSV2_3 != 0 {
+            return Err(ServerWebSocketError::ReservedBitsSet)
+        }
+        let opcode = byte & 15;
+        let is_final = byte & FLAG_FIN != 0;
+        let is_compressed = byte & FLAG_RSV1 != 0;
+        match opcode {
+            OPCODE_CONTINUATION => {
+                if self.message_opcode.is_none() {
+                    return Err(ServerWebSocketError::UnexpectedContinuation)
+                }
+                if is_compressed {
+                    return Err(ServerWebSocketError::ReservedBitsSet)
+                }
+            }
+            OPCODE_TEXT | OPCODE_BINARY => {
+                if self.message_opcode.is_some() {
+                    return Err(ServerWebSocketError::ExpectedContinuation)
+                }
+                if is_compressed && self.inflater.is_none() {
+                    return Err(ServerWebSocketError::ReservedBitsSet)
+                }
+            }
+            OPCODE_CLOSE | OPCODE_PING | OPCODE_PONG => {
+                if !is_final {
+                    return Err(ServerWebSocketError::ControlFrameFragmented)
+                }
+                if is_compressed {
+                    return Err(ServerWebSocketError::ReservedBitsSet)
+                }
+            }
+            _ => return Err(ServerWebSocketError::OpcodeNotSupported(opcode))
+        }
+        self.set_state(State::Len1);
+        self.opcode = opcode;
+        self.is_final = is_final;
+        self.is_compressed = is_compressed;
+        Ok(())
+    }
+
+    fn parse_close(data: &[u8]) -> Result<ServerWebSocketMessage<'_>, ServerWebSocketError<'_>> {
+        match data.len() {
+            0 => Ok(ServerWebSocketMessage::Close {code: SERVER_WEB_SOCKET_CLOSE_NO_STATUS, reason: ""}),
+            1 => Err(ServerWebSocketError::InvalidClosePayload),
+            _ => {
+                let code = u16::from_be_bytes([data[0], data[1]]);
+                if !is_valid_close_code(code) {
+                    return Err(ServerWebSocketError::InvalidCloseCode(code))
+                }
+                let reason = std::str::from_utf8(&data[2..]).map_err( | _ | ServerWebSocketError::InvalidClosePayload)?;
+                Ok(ServerWebSocketMessage::Close {code, reason})
+            }
+        }
+    }
+
+    // a complete frame is in self.data, returns false if parsing can't continue
+    fn complete_frame<F>(&mut self, result: &mut F) -> bool where F: FnMut(Result<ServerWebSocketMessage, ServerWebSocketError>) {
+        match self.opcode {
+            OPCODE_PING => result(Ok(ServerWebSocketMessage::Ping(&self.data))),
+            OPCODE_PONG => result(Ok(ServerWebSocketMessage::Pong(&self.data))),
+            OPCODE_CLOSE => {
+                let close = Self::parse_close(&self.data);
+                let is_ok = close.is_ok();
+                result(close);
+                return is_ok
+            }
+            opcode => {
+                if opcode != OPCODE_CONTINUATION {
+                    self.message_opcode = Some(opcode);
+                    self.message_compressed = self.is_compressed;
+                    // the common single frame message needs no reassembly copy
+                    if self.is_final && !self.is_compressed {
+                        let is_ok = Self::emit_message(opcode, &self.data, result);
+                        self.reset_message();
+                        return is_ok
+                    }
+                }
+                self.message.extend_from_slice(&self.data);
+                if !self.is_final {
+                    return true
+                }
+                let opcode = self.message_opcode.unwrap();
+                let is_ok = if self.message_compressed {
+                    let inflater = self.inflater.as_mut().unwrap();
+                    if inflater.decompress(&self.message, &mut self.inflated, self.max_message_size).is_err() {
+                        result(Err(ServerWebSocketError::DecompressFailed));
+                        false
+                    }
+                    else {
+                        Self::emit_message(opcode, &self.inflated, result)
+                    }
+                }
+                else {
+                    Self::emit_message(opcode, &self.message, result)
+                };
+                self.reset_message();
+                return is_ok
+            }
+        }
+        true
+    }
+
+    fn emit_message<F>(opcode: u8, data: &[u8], result: &mut F) -> bool where F: FnMut(Result<ServerWebSocketMessage, ServerWebSocketError>) {
+        if opcode == OPCODE_TEXT {
+            if let Ok(text) = std::str::from_utf8(data) {
+                result(Ok(ServerWebSocketMessage::Text(text)));
+            }
+            else {
+                result(Err(ServerWebSocketError::TextNotUTF8(data)));
+                return false
+            }
+        }
+        else {
+            result(Ok(ServerWebSocketMessage::Binary(data)));
+        }
+        true
+    }
+
+    // feeds bytes from the wire into the parser. Complete messages, reassembled from fragments
+    // and decompressed if needed, are passed to result. After an error the connection should
+    // be closed with the errors close_code, the parser drops the rest of the input
     pub fn parse<F>(&mut self, input: &[u8], mut result: F) where F: FnMut(Result<ServerWebSocketMessage, ServerWebSocketError>){
+        if self.failed {
+            return
+        }
         self.input_read = 0;
         // parse a header
         loop {
-            match self.state {
+            let step = match self.state {
                 State::Opcode => {
                     if self.parse_head(input) {
                         break;
                     }
-                    let opcode = self.head[0] & 15;
-                    
-                    if opcode <= 2 {
-                        self.is_partial = (self.head[0] & 128) != 0;
-                        self.is_text = opcode == 1;
-                        self.to_state(State::Len1);
-                    }
-                    else if opcode == 8 {
-                        result(Ok(ServerWebSocketMessage::Close));
-                        break;
-                    }
-                    else if opcode == 9 {
-                        self.is_ping = true;
-                        self.to_state(State::Len1);
-                    }
-                    else if opcode == 10 {
-                        self.is_pong = true;
-                        self.to_state(State::Len1);
-                    }
-                    else {
-                        result(Err(ServerWebSocketError::OpcodeNotSupported(opcode)));
-                        break;
-                    }
+                    self.parse_opcode()
                 },
                 State::Len1 => {
                     if self.parse_head(input) {
@@ -274,18 +566,15 @@ impl ServerWebSocket {
                     let len_type = self.head[0] & 127;
                     if len_type < 126 {
                         self.data_len = len_type as usize;
-                        if !self.is_masked {
-                            self.to_state(State::Data);
-                        }
-                        else {
-                            self.to_state(State::Mask);
-                        }
+                        self.after_len()
                     }
                     else if len_type == 126 {
-                        self.to_state(State::Len2);
+                        self.set_state(State::Len2);
+                        Ok(())
                     }
-                    else if len_type == 127 {
-                        self.to_state(State::Len8);
+                    else {
+                        self.set_state(State::Len8);
+                        Ok(())
                     }
                 },
                 State::Len2 => {
@@ -295,76 +584,62 @@ impl ServerWebSocket {
                     self.data_len = u16::from_be_bytes(
                         self.head[0..2].try_into().unwrap()
                     ) as usize;
-                    if self.is_masked {
-                        self.to_state(State::Mask);
-                    }
-                    else {
-                        self.to_state(State::Data);
-                    }
+                    self.after_len()
                 },
                 State::Len8 => {
                     if self.parse_head(input) {
                         break;
                     }
-                    self.data_len = u64::from_be_bytes(
+                    let len = u64::from_be_bytes(
                         self.head[0..8].try_into().unwrap()
-                    ) as usize;
-                    if self.is_masked {
-                        self.to_state(State::Mask);
+                    );
+                    if len >> 63 != 0 {
+                        Err(ServerWebSocketError::InvalidLength)
                     }
                     else {
-                        self.to_state(State::Data);
+                        self.data_len = len.try_into().unwrap_or(usize::MAX);
+                        self.after_len()
                     }
                 },
                 State::Mask => {
                     if self.parse_head(input) {
                         break;
                     }
-                    self.to_state(State::Data);
+                    self.set_state(State::Data);
+                    Ok(())
                 },
                 State::Data => {
+                    let take = (self.data_len - self.data.len()).min(input.len() - self.input_read);
+                    let chunk = &input[self.input_read..self.input_read + take];
                     if self.is_masked {
-                        while self.data.len() < self.data_len && self.input_read < input.len() {
-                            self.data.push(input[self.input_read] ^ self.head[self.mask_counter]);
+                        for &byte in chunk {
+                            self.data.push(byte ^ self.head[self.mask_counter]);
                             self.mask_counter = (self.mask_counter + 1) & 3;
-                            self.input_read += 1;
                         }
                     }
                     else {
-                        while self.data.len() < self.data_len && self.input_read < input.len() {
-                            self.data.push(input[self.input_read]);
-                            self.input_read += 1;
-                        }
+                        self.data.extend_from_slice(chunk);
                     }
+                    self.input_read += take;
                     if self.data.len() < self.data_len { // not enough data yet
                         break;
                     }
-                    else {
-                        if self.is_ping {
-                            result(Ok(ServerWebSocketMessage::Ping(&self.data)));
-                        }
-                        else if self.is_pong {
-                            result(Ok(ServerWebSocketMessage::Pong(&self.data)));
-                        }
-                        else if self.is_text{
-                            if let Ok(text) = std::str::from_utf8(&self.data){
-                                result(Ok(ServerWebSocketMessage::Text(text)));
-                            }
-                            else{
-                                result(Err(ServerWebSocketError::TextNotUTF8(&self.data)))
-                            }
-                        }
-                        else{
-                            result(Ok(ServerWebSocketMessage::Binary(&self.data)));
-                        }
-                        
-                        self.to_state(State::Opcode);
+                    if !self.complete_frame(&mut result) {
+                        self.failed = true;
+                        break;
                     }
+                    self.set_state(State::Opcode);
+                    Ok(())
                 },
+            };
+            if let Err(err) = step {
+                result(Err(err));
+                self.failed = true;
+                break;
             }
         }
     }
-    
+
 }
 
 impl Default for ServerWebSocket {
@@ -373,3 +648,140 @@ impl Default for ServerWebSocket {
     }
 }
 
+// turns outgoing messages into frames, compressing and fragmenting them as configured.
+// Clients mask every frame, servers never do
+pub struct ServerWebSocketWriter {
+    masked: bool,
+    deflater: Option<PerMessageDeflater>,
+    pub max_frame_size: usize,
+    pub compress_threshold: usize,
+}
+
+impl ServerWebSocketWriter {
+    pub fn new(masked: bool) -> Self {
+        Self {
+            masked,
+            deflater: None,
+            max_frame_size: 1024 * 1024,
+            compress_threshold: 64,
+        }
+    }
+
+    pub fn with_deflate(mut self, config: &PerMessageDeflateConfig, is_server: bool) -> Self {
+        self.deflater = config.deflater(is_server);
+        self
+    }
+
+    pub fn message(&mut self, format: ServerWebSocketMessageFormat, data: &[u8]) -> Vec<u8> {
+        let compressed;
+        let (data, is_compressed) = match &mut self.deflater {
+            Some(deflater) if data.len() >= self.compress_threshold => {
+                compressed = deflater.compress(data);
+                (&compressed[..], true)
+            }
+            _ => (data, false)
+        };
+        let mut out = Vec::with_capacity(data.len() + 14);
+        let mut format = Some(format);
+        let mut chunks = data.chunks(self.max_frame_size.max(1)).peekable();
+        // an empty message still needs its one frame
+        if chunks.peek().is_none() {
+            let header = ServerWebSocketMessageHeader::from_len_flags(0, format.take().unwrap(), self.masked, true, is_compressed);
+            out.extend_from_slice(&ServerWebSocket::build_message(header, &[]));
+        }
+        while let Some(chunk) = chunks.next() {
+            let is_first = format.is_some();
+            let frame_format = format.take().unwrap_or(ServerWebSocketMessageFormat::Continuation);
+            let header = ServerWebSocketMessageHeader::from_len_flags(chunk.len(), frame_format, self.masked, chunks.peek().is_none(), is_compressed && is_first);
+            out.extend_from_slice(&ServerWebSocket::build_message(header, chunk));
+        }
+        out
+    }
+
+    pub fn ping(&mut self, data: &[u8]) -> Vec<u8> {
+        let header = ServerWebSocketMessageHeader::from_len(data.len().min(125), ServerWebSocketMessageFormat::Ping, self.masked);
+        ServerWebSocket::build_message(header, &data[0..data.len().min(125)])
+    }
+
+    pub fn pong(&mut self, data: &[u8]) -> Vec<u8> {
+        let header = ServerWebSocketMessageHeader::from_len(data.len().min(125), ServerWebSocketMessageFormat::Pong, self.masked);
+        ServerWebSocket::build_message(header, &data[0..data.len().min(125)])
+    }
+
+    pub fn close(&mut self, code: u16, reason: &str) -> Vec<u8> {
+        ServerWebSocket::build_close_message(code, reason, self.masked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_all(socket: &mut ServerWebSocket, input: &[u8]) -> Vec<String> {
+        let mut out = Vec::new();
+        // feed byte by byte to exercise the partial header states
+        for byte in input {
+            socket.parse(std::slice::from_ref(byte), | result | out.push(match result {
+                Ok(ServerWebSocketMessage::Text(text)) => format!("text {}", text),
+                Ok(ServerWebSocketMessage::Binary(data)) => format!("binary {}", data.len()),
+                Ok(ServerWebSocketMessage::Ping(data)) => format!("ping {}", data.len()),
+                Ok(ServerWebSocketMessage::Pong(data)) => format!("pong {}", data.len()),
+                Ok(ServerWebSocketMessage::Close {code, reason}) => format!("close {} {}", code, reason),
+                Err(e) => format!("error {}", e.close_code()),
+            }));
+        }
+        out
+    }
+
+    #[test]
+    fn fragmented_compressed_roundtrip() {
+        let config = PerMessageDeflateConfig::default();
+        let mut writer = ServerWebSocketWriter::new(true).with_deflate(&config, false);
+        writer.max_frame_size = 7;
+        let mut socket = ServerWebSocket::new().with_deflate(&config, true);
+        let text = "{\"a\":1,\"b\":[1,2,3],\"c\":\"hello hello hello hello hello hello hello\"}";
+        let mut wire = writer.message(ServerWebSocketMessageFormat::Text, text.as_bytes());
+        // a ping in between fragments is allowed
+        let ping = writer.ping(b"hi");
+        wire.splice(13..13, ping);
+        wire.extend(writer.message(ServerWebSocketMessageFormat::Binary, &[1, 2, 3]));
+        wire.extend(writer.close(SERVER_WEB_SOCKET_CLOSE_GOING_AWAY, "bye"));
+        assert_eq!(parse_all(&mut socket, &wire), vec![
+            "ping 2".to_string(),
+            format!("text {}", text),
+            "binary 3".to_string(),
+            "close 1001 bye".to_string()
+        ]);
+    }
+
+    #[test]
+    fn protocol_errors() {
+        let parse = | input: &[u8] | parse_all(&mut ServerWebSocket::new(), input);
+        // continuation without a message
+        assert_eq!(parse(&[128, 0]), vec!["error 1002"]);
+        // fragmented ping
+        assert_eq!(parse(&[9, 0]), vec!["error 1002"]);
+        // compressed without negotiation
+        assert_eq!(parse(&[128 | 64 | 2, 0]), vec!["error 1002"]);
+        // invalid close code
+        assert_eq!(parse(&[128 | 8, 2, 3, 236]), vec!["error 1002"]);
+        // no close code
+        assert_eq!(parse(&[128 | 8, 0]), vec!["close 1005 "]);
+        // invalid utf8, and nothing is parsed after an error
+        assert_eq!(parse(&[128 | 1, 1, 0xff, 128 | 2, 0]), vec!["error 1007"]);
+        // a 64 bit length with the most significant bit set
+        assert_eq!(parse(&[128 | 2, 127, 0x80, 0, 0, 0, 0, 0, 0, 0]), vec!["error 1002"]);
+        // the largest valid 64 bit length, on top of a started message
+        assert_eq!(parse(&[2, 1, 0, 128, 127, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]), vec!["error 1009"]);
+    }
+
+    #[test]
+    fn handshake() {
+        assert_eq!(ServerWebSocket::create_accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        let key = ServerWebSocket::create_client_key();
+        let response = ServerWebSocket::create_upgrade_response_with_extensions(&key, Some(&PerMessageDeflateConfig::default()));
+        let lines: Vec<String> = response.split_inclusive("\r\n").map( | s | s.to_string()).collect();
+        assert_eq!(ServerWebSocket::validate_upgrade_response(&lines, &key), Ok(Some(PerMessageDeflateConfig::default())));
+        assert!(ServerWebSocket::validate_upgrade_response(&lines, "other").is_err());
+    }
+}
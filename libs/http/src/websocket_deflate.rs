@@ -0,0 +1,241 @@
+// permessage-deflate (RFC 7692) on top of makepad_miniz. Messages are raw deflate streams
+// flushed with a sync flush, with the trailing 00 00 ff ff stripped on the wire.
+
+use makepad_miniz::deflate::core::{CompressorOxide, create_comp_flags_from_zip_params};
+use makepad_miniz::deflate::stream::deflate;
+use makepad_miniz::inflate::stream::{inflate, InflateState};
+use makepad_miniz::{DataFormat, MZFlush, MZError};
+
+const DEFLATE_TAIL: [u8; 4] = [0, 0, 0xff, 0xff];
+const DEFLATE_LEVEL: i32 = 6;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PerMessageDeflateConfig {
+    pub server_no_context_takeover: bool,
+    pub client_no_context_takeover: bool,
+    pub server_max_window_bits: Option<u8>,
+    pub client_max_window_bits: Option<u8>,
+}
+
+impl PerMessageDeflateConfig {
+    // the offer a client puts in Sec-WebSocket-Extensions
+    pub fn client_offer() -> &'static str {
+        "permessage-deflate; client_max_window_bits"
+    }
+
+    // parses all permessage-deflate offers in a Sec-WebSocket-Extensions value.
+    // Offers with unknown or malformed parameters are dropped
+    pub fn parse(value: &str) -> Vec<Self> {
+        let mut out = Vec::new();
+        'offers: for offer in value.split(',') {
+            let mut params = offer.split(';').map( | p | p.trim());
+            if params.next() != Some("permessage-deflate") {
+                continue
+            }
+            let mut config = Self::default();
+            for param in params {
+                let (key, value) = match param.split_once('=') {
+                    Some((key, value)) => (key.trim(), Some(value.trim().trim_matches('"'))),
+                    None => (param, None)
+                };
+                let bits = match value.map( | v | v.parse::<u8>()) {
+                    Some(Ok(bits)) if (8..=15).contains(&bits) => Some(bits),
+                    Some(_) => continue 'offers,
+                    None => None
+                };
+                match key {
+                    "server_no_context_takeover" if value.is_none() => config.server_no_context_takeover = true,
+                    "client_no_context_takeover" if value.is_none() => config.client_no_context_takeover = true,
+                    "server_max_window_bits" if bits.is_some() => config.server_max_window_bits = bits,
+                    // a bare client_max_window_bits only says the client can handle the parameter
+                    "client_max_window_bits" => config.client_max_window_bits = bits.or(Some(15)),
+                    _ => continue 'offers
+                }
+            }
+            out.push(config);
+        }
+        out
+    }
+
+    // picks the first client offer we can honour. Our compressor always uses a 32k window,
+    // so offers that limit the server window below that are declined
+    pub fn server_accept(value: &str) -> Option<Self> {
+        Self::parse(value).into_iter().find( | c | c.server_max_window_bits.is_none_or( | b | b == 15)).map( | mut c | {
+            // we can inflate any window size, so we don't restrict the client
+            c.client_max_window_bits = None;
+            c
+        })
+    }
+
+    pub fn to_header_value(&self) -> String {
+        let mut out = "permessage-deflate".to_string();
+        if self.server_no_context_takeover {
+            out.push_str("; server_no_context_takeover");
+        }
+        if self.client_no_context_takeover {
+            out.push_str("; client_no_context_takeover");
+        }
+        if let Some(bits) = self.server_max_window_bits {
+            out.push_str(&format!("; server_max_window_bits={}", bits));
+        }
+        if let Some(bits) = self.client_max_window_bits {
+            out.push_str(&format!("; client_max_window_bits={}", bits));
+        }
+        out
+    }
+
+    // the compressor for our side of the connection. None when the peer restricted our window
+    // below what miniz can produce, in which case messages go out uncompressed
+    pub fn deflater(&self, is_server: bool) -> Option<PerMessageDeflater> {
+        let (no_context_takeover, max_window_bits) = if is_server {
+            (self.server_no_context_takeover, self.server_max_window_bits)
+        }
+        else {
+            (self.client_no_context_takeover, self.client_max_window_bits)
+        };
+        if max_window_bits.is_some_and( | b | b < 15) {
+            return None
+        }
+        Some(PerMessageDeflater::new(no_context_takeover))
+    }
+
+    pub fn inflater(&self, is_server: bool) -> PerMessageInflater {
+        PerMessageInflater::new(if is_server {self.client_no_context_takeover} else {self.server_no_context_takeover})
+    }
+}
+
+pub struct PerMessageDeflater {
+    compressor: Box<CompressorOxide>,
+    no_context_takeover: bool,
+}
+
+impl PerMessageDeflater {
+    pub fn new(no_context_takeover: bool) -> Self {
+        let flags = create_comp_flags_from_zip_params(DEFLATE_LEVEL, -15, 0);
+        Self {
+            compressor: Box::new(CompressorOxide::new(flags)),
+            no_context_takeover
+        }
+    }
+
+    pub fn compress(&mut self, data: &[u8]) -> Vec<u8> {
+        if self.no_context_takeover {
+            self.compressor.reset();
+        }
+        let mut output = vec![0u8; data.len() / 2 + 64];
+        let mut in_pos = 0;
+        let mut out_pos = 0;
+        loop {
+            let result = deflate(&mut self.compressor, &data[in_pos..], &mut output[out_pos..], MZFlush::Sync);
+            in_pos += result.bytes_consumed;
+            out_pos += result.bytes_written;
+            match result.status {
+                // a sync flush is complete once all input is in and the output wasn't filled up
+                Ok(_) | Err(MZError::Buf) => {
+                    if in_pos == data.len() && out_pos < output.len() {
+                        break
+                    }
+                    output.resize(output.len() * 2, 0);
+                }
+                Err(_) => break
+            }
+        }
+        output.truncate(out_pos);
+        if output.ends_with(&DEFLATE_TAIL) {
+            output.truncate(out_pos - DEFLATE_TAIL.len());
+        }
+        output
+    }
+}
+
+pub struct PerMessageInflater {
+    state: Box<InflateState>,
+    no_context_takeover: bool,
+}
+
+impl PerMessageInflater {
+    pub fn new(no_context_takeover: bool) -> Self {
+        Self {
+            state: Box::new(InflateState::new(DataFormat::Raw)),
+            no_context_takeover
+        }
+    }
+
+    // inflates one message into output. Fails with MZError::Data on corrupt input
+    // and MZError::Buf once max_size is exceeded
+    pub fn decompress(&mut self, data: &[u8], output: &mut Vec<u8>, max_size: usize) -> Result<(), MZError> {
+        if self.no_context_takeover {
+            self.state.reset(DataFormat::Raw);
+        }
+        output.clear();
+        let mut input = Vec::with_capacity(data.len() + DEFLATE_TAIL.len());
+        input.extend_from_slice(data);
+        input.extend_from_slice(&DEFLATE_TAIL);
+        let mut in_pos = 0;
+        let mut buffer = [0u8; 16384];
+        loop {
+            let result = inflate(&mut self.state, &input[in_pos..], &mut buffer, MZFlush::None);
+            in_pos += result.bytes_consumed;
+            output.extend_from_slice(&buffer[0..result.bytes_written]);
+            if output.len() > max_size {
+                return Err(MZError::Buf)
+            }
+            match result.status {
+                Ok(_) => {
+                    if in_pos == input.len() && result.bytes_written < buffer.len() {
+                        return Ok(())
+                    }
+                }
+                // no progress possible, which after the sync marker means we're done
+                Err(MZError::Buf) if in_pos == input.len() => return Ok(()),
+                Err(_) => return Err(MZError::Data)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiate() {
+        let offers = PerMessageDeflateConfig::parse("permessage-deflate; server_max_window_bits=10, permessage-deflate; client_max_window_bits, x-webkit-deflate-frame");
+        assert_eq!(offers.len(), 2);
+        let accepted = PerMessageDeflateConfig::server_accept("permessage-deflate; server_max_window_bits=10, permessage-deflate; client_max_window_bits").unwrap();
+        assert_eq!(accepted, PerMessageDeflateConfig::default());
+        assert!(PerMessageDeflateConfig::server_accept("permessage-deflate; server_max_window_bits=9").is_none());
+        assert!(PerMessageDeflateConfig::parse("permessage-deflate; bogus").is_empty());
+    }
+
+    #[test]
+    fn roundtrip_with_context_takeover() {
+        let config = PerMessageDeflateConfig::default();
+        let mut deflater = config.deflater(true).unwrap();
+        let mut inflater = config.inflater(false);
+        let message = br#"{"kind":"cursor","file":"studio/src/app.rs","line":12,"column":4}"#;
+        let mut sizes = Vec::new();
+        for _ in 0..3 {
+            let compressed = deflater.compress(message);
+            sizes.push(compressed.len());
+            let mut output = Vec::new();
+            inflater.decompress(&compressed, &mut output, 1 << 20).unwrap();
+            assert_eq!(output, message);
+        }
+        // the shared window makes repeated messages nearly free
+        assert!(sizes[2] < sizes[0]);
+    }
+
+    #[test]
+    fn roundtrip_no_context_takeover() {
+        let config = PerMessageDeflateConfig {server_no_context_takeover: true, ..Default::default()};
+        let mut deflater = config.deflater(true).unwrap();
+        let message: Vec<u8> = (0..100000u32).map( | i | (i % 251) as u8).collect();
+        for _ in 0..2 {
+            let compressed = deflater.compress(&message);
+            let mut output = Vec::new();
+            PerMessageInflater::new(true).decompress(&compressed, &mut output, 1 << 20).unwrap();
+            assert_eq!(output, message);
+        }
+    }
+}
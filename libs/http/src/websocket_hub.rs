@@ -0,0 +1,463 @@
+// a broadcast hub on top of the websocket requests of HttpServer. Clients subscribe to named
+// channels and whatever is broadcast on a channel fans out to all its subscribers.
+// Every client has its own bounded queue so a slow reader can't make the server buffer without
+// limit, the drop policy decides what gives when that queue is full.
+// An empty message is how the server is asked to close a socket, so the hub never sends one on
+// behalf of a caller.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::{mpsc, Arc, Mutex, Weak};
+use crate::server::{HttpServerRequest, WebSocketWriteCounter};
+use crate::utils::HttpServerHeaders;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WebSocketHubDropPolicy {
+    // throw away the oldest queued message to make room, fine for state that is resent anyway
+    DropOldest,
+    // throw away the message that doesn't fit
+    DropNewest,
+    // close the connection, for clients that can't work with gaps
+    Disconnect,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WebSocketHubClientConfig {
+    // messages the hub holds on to while the socket is busy
+    pub queue_len: usize,
+    // messages handed to the socket writer that aren't on the wire yet
+    pub max_in_flight: u64,
+    pub drop_policy: WebSocketHubDropPolicy,
+}
+
+impl Default for WebSocketHubClientConfig {
+    fn default() -> Self {
+        Self {
+            queue_len: 256,
+            max_in_flight: 16,
+            drop_policy: WebSocketHubDropPolicy::DropOldest
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum WebSocketHubEvent {
+    Connected {client_id: u64, headers: HttpServerHeaders},
+    Disconnected {client_id: u64},
+    Message {client_id: u64, data: Vec<u8>},
+    Joined {channel: String, client_id: u64},
+    Left {channel: String, client_id: u64},
+    // the client overflowed its queue under the Disconnect policy and is being closed
+    Overflowed {client_id: u64},
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WebSocketHubPresence<'a> {
+    pub channel: &'a str,
+    pub client_id: u64,
+    pub joined: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WebSocketHubClientStats {
+    pub queued: usize,
+    pub in_flight: u64,
+    pub dropped: u64,
+}
+
+type PresenceEncoder = Box<dyn Fn(&WebSocketHubPresence) -> Option<Vec<u8>> + Send>;
+
+struct HubClient {
+    sender: mpsc::Sender<Vec<u8>>,
+    messages_written: WebSocketWriteCounter,
+    messages_sent: u64,
+    queue: VecDeque<Vec<u8>>,
+    dropped: u64,
+    config: WebSocketHubClientConfig,
+    channels: BTreeSet<String>,
+    closed: bool,
+}
+
+impl HubClient {
+    fn in_flight(&self) -> u64 {
+        self.messages_sent.saturating_sub(self.messages_written.written())
+    }
+
+    // moves queued messages to the socket as far as the in flight limit allows.
+    // Returns false once the socket is gone
+    fn flush(&mut self) -> bool {
+        while self.in_flight() < self.config.max_in_flight {
+            let data = if let Some(data) = self.queue.pop_front() {data} else {break};
+            if self.sender.send(data).is_err() {
+                return false
+            }
+            self.messages_sent += 1;
+        }
+        true
+    }
+
+    // returns false when the message overflowed a client with the Disconnect policy
+    fn push(&mut self, data: Vec<u8>) -> bool {
+        if self.closed {
+            return true
+        }
+        if self.queue.is_empty() && self.in_flight() < self.config.max_in_flight {
+            if self.sender.send(data).is_ok() {
+                self.messages_sent += 1;
+            }
+            return true
+        }
+        if self.queue.len() >= self.config.queue_len {
+            match self.config.drop_policy {
+                WebSocketHubDropPolicy::DropOldest => {
+                    self.queue.pop_front();
+                    self.dropped += 1;
+                }
+                WebSocketHubDropPolicy::DropNewest => {
+                    self.dropped += 1;
+                    return true
+                }
+                WebSocketHubDropPolicy::Disconnect => {
+                    return false
+                }
+            }
+        }
+        self.queue.push_back(data);
+        true
+    }
+}
+
+struct HubState {
+    clients: BTreeMap<u64, HubClient>,
+    channels: BTreeMap<String, BTreeSet<u64>>,
+    default_config: WebSocketHubClientConfig,
+    presence: Option<PresenceEncoder>,
+    events: mpsc::Sender<WebSocketHubEvent>,
+    // wakes the flush thread when a socket writer wrote a message
+    wake_flush: mpsc::Sender<()>,
+}
+
+impl HubState {
+    fn broadcast(&mut self, channel: &str, data: &[u8], except: Option<u64>) -> usize {
+        if data.is_empty() {
+            return 0
+        }
+        let members: Vec<u64> = if let Some(members) = self.channels.get(channel) {
+            members.iter().copied().filter( | id | Some(*id) != except).collect()
+        }
+        else {
+            return 0
+        };
+        let mut overflowed = Vec::new();
+        for client_id in &members {
+            if let Some(client) = self.clients.get_mut(client_id) {
+                if !client.push(data.to_vec()) {
+                    overflowed.push(*client_id);
+                }
+            }
+        }
+        for client_id in overflowed {
+            let _ = self.events.send(WebSocketHubEvent::Overflowed {client_id});
+            self.close(client_id);
+        }
+        members.len()
+    }
+
+    fn notify_presence(&mut self, channel: &str, client_id: u64, joined: bool) {
+        let event = if joined {
+            WebSocketHubEvent::Joined {channel: channel.to_string(), client_id}
+        }
+        else {
+            WebSocketHubEvent::Left {channel: channel.to_string(), client_id}
+        };
+        let _ = self.events.send(event);
+        let message = self.presence.as_ref().and_then( | encode | encode(&WebSocketHubPresence {channel, client_id, joined}));
+        if let Some(message) = message {
+            self.broadcast(channel, &message, Some(client_id));
+        }
+    }
+
+    fn subscribe(&mut self, client_id: u64, channel: &str) -> bool {
+        match self.clients.get_mut(&client_id) {
+            Some(client) if !client.closed => {
+                if !client.channels.insert(channel.to_string()) {
+                    return false
+                }
+            }
+            _ => return false
+        }
+        self.channels.entry(channel.to_string()).or_default().insert(client_id);
+        self.notify_presence(channel, client_id, true);
+        true
+    }
+
+    fn unsubscribe(&mut self, client_id: u64, channel: &str) -> bool {
+        if !self.clients.get_mut(&client_id).is_some_and( | c | c.channels.remove(channel)) {
+            return false
+        }
+        if let Some(members) = self.channels.get_mut(channel) {
+            members.remove(&client_id);
+            if members.is_empty() {
+                self.channels.remove(channel);
+            }
+        }
+        self.notify_presence(channel, client_id, false);
+        true
+    }
+
+    // leaves all channels and asks the server to close the socket. The client record stays
+    // until the server reports the disconnect
+    fn close(&mut self, client_id: u64) {
+        let channels: Vec<String> = if let Some(client) = self.clients.get_mut(&client_id) {
+            if client.closed {
+                return
+            }
+            client.closed = true;
+            client.queue.clear();
+            let _ = client.sender.send(Vec::new());
+            client.channels.iter().cloned().collect()
+        }
+        else {
+            return
+        };
+        for channel in channels {
+            self.unsubscribe(client_id, &channel);
+        }
+    }
+
+    fn flush(&mut self) {
+        let mut gone = Vec::new();
+        for (client_id, client) in &mut self.clients {
+            if !client.closed && !client.queue.is_empty() && !client.flush() {
+                gone.push(*client_id);
+            }
+        }
+        for client_id in gone {
+            self.close(client_id);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct WebSocketHub {
+    state: Arc<Mutex<HubState>>,
+}
+
+impl WebSocketHub {
+    // the receiver gets connects, disconnects, incoming messages and presence changes
+    pub fn new(default_config: WebSocketHubClientConfig) -> (Self, mpsc::Receiver<WebSocketHubEvent>) {
+        let (tx_events, rx_events) = mpsc::channel();
+        let (wake_flush, rx_wake_flush) = mpsc::channel();
+        let state = Arc::new(Mutex::new(HubState {
+            clients: BTreeMap::new(),
+            channels: BTreeMap::new(),
+            default_config,
+            presence: None,
+            events: tx_events,
+            wake_flush,
+        }));
+        // queued messages are moved on as the socket writers catch up. The thread ends when the
+        // hub is gone, at the latest when the last socket writer drops its wake sender
+        let weak: Weak<Mutex<HubState>> = Arc::downgrade(&state);
+        std::thread::spawn(move || {
+            while rx_wake_flush.recv().is_ok() {
+                while rx_wake_flush.try_recv().is_ok() {}
+                let Some(state) = weak.upgrade() else {break};
+                state.lock().unwrap().flush();
+            }
+        });
+        (Self {state}, rx_events)
+    }
+
+    // when set, joins and leaves are broadcast in-band to the other subscribers of the channel
+    pub fn set_presence_encoder(&self, encode: impl Fn(&WebSocketHubPresence) -> Option<Vec<u8>> + Send + 'static) {
+        self.state.lock().unwrap().presence = Some(Box::new(encode));
+    }
+
+    // takes the websocket requests of the server, Get and Post are handed back
+    pub fn handle_request(&self, request: HttpServerRequest) -> Option<HttpServerRequest> {
+        let mut state = self.state.lock().unwrap();
+        match request {
+            HttpServerRequest::ConnectWebSocket {web_socket_id, headers, response_sender, messages_written, ..} => {
+                let config = state.default_config;
+                let wake_flush = Mutex::new(state.wake_flush.clone());
+                messages_written.set_on_write(move || {let _ = wake_flush.lock().unwrap().send(());});
+                state.clients.insert(web_socket_id, HubClient {
+                    sender: response_sender,
+                    messages_written,
+                    messages_sent: 0,
+                    queue: VecDeque::new(),
+                    dropped: 0,
+                    config,
+                    channels: BTreeSet::new(),
+                    closed: false,
+                });
+                let _ = state.events.send(WebSocketHubEvent::Connected {client_id: web_socket_id, headers});
+                None
+            }
+            HttpServerRequest::DisconnectWebSocket {web_socket_id} => {
+                state.close(web_socket_id);
+                if state.clients.remove(&web_socket_id).is_some() {
+                    let _ = state.events.send(WebSocketHubEvent::Disconnected {client_id: web_socket_id});
+                }
+                None
+            }
+            HttpServerRequest::BinaryMessage {web_socket_id, data, ..} => {
+                let _ = state.events.send(WebSocketHubEvent::Message {client_id: web_socket_id, data});
+                None
+            }
+            request => Some(request)
+        }
+    }
+
+    pub fn serve(&self, rx_request: mpsc::Receiver<HttpServerRequest>, mut other: impl FnMut(HttpServerRequest)) {
+        while let Ok(request) = rx_request.recv() {
+            if let Some(request) = self.handle_request(request) {
+                other(request);
+            }
+        }
+    }
+
+    pub fn set_client_config(&self, client_id: u64, config: WebSocketHubClientConfig) {
+        if let Some(client) = self.state.lock().unwrap().clients.get_mut(&client_id) {
+            client.config = config;
+        }
+    }
+
+    pub fn subscribe(&self, client_id: u64, channel: &str) -> bool {
+        self.state.lock().unwrap().subscribe(client_id, channel)
+    }
+
+    pub fn unsubscribe(&self, client_id: u64, channel: &str) -> bool {
+        self.state.lock().unwrap().unsubscribe(client_id, channel)
+    }
+
+    // returns the number of subscribers the message was queued for, empty messages are not sent
+    pub fn broadcast(&self, channel: &str, data: &[u8]) -> usize {
+        self.state.lock().unwrap().broadcast(channel, data, None)
+    }
+
+    pub fn broadcast_except(&self, channel: &str, data: &[u8], except: u64) -> usize {
+        self.state.lock().unwrap().broadcast(channel, data, Some(except))
+    }
+
+    // queues a message for one client, false if the client is unknown or closed or the
+    // message is empty. Use disconnect to close a client
+    pub fn send(&self, client_id: u64, data: Vec<u8>) -> bool {
+        if data.is_empty() {
+            return false
+        }
+        let mut state = self.state.lock().unwrap();
+        let ok = match state.clients.get_mut(&client_id) {
+            Some(client) if !client.closed => client.push(data),
+            _ => return false
+        };
+        if !ok {
+            let _ = state.events.send(WebSocketHubEvent::Overflowed {client_id});
+            state.close(client_id);
+        }
+        ok
+    }
+
+    pub fn disconnect(&self, client_id: u64) {
+        self.state.lock().unwrap().close(client_id);
+    }
+
+    pub fn subscribers(&self, channel: &str) -> Vec<u64> {
+        self.state.lock().unwrap().channels.get(channel).map( | m | m.iter().copied().collect()).unwrap_or_default()
+    }
+
+    pub fn channels(&self) -> Vec<String> {
+        self.state.lock().unwrap().channels.keys().cloned().collect()
+    }
+
+    pub fn client_channels(&self, client_id: u64) -> Vec<String> {
+        self.state.lock().unwrap().clients.get(&client_id).map( | c | c.channels.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn client_stats(&self, client_id: u64) -> Option<WebSocketHubClientStats> {
+        self.state.lock().unwrap().clients.get(&client_id).map( | c | WebSocketHubClientStats {
+            queued: c.queue.len(),
+            in_flight: c.in_flight(),
+            dropped: c.dropped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    fn connect(hub: &WebSocketHub, web_socket_id: u64) -> (mpsc::Receiver<Vec<u8>>, WebSocketWriteCounter) {
+        let (tx, rx) = mpsc::channel();
+        let messages_written = WebSocketWriteCounter::default();
+        let headers = HttpServerHeaders::from_reader("127.0.0.1:80".parse().unwrap(), &mut Cursor::new(b"GET /hub HTTP/1.1\r\nHost: localhost\r\n\r\n")).unwrap();
+        hub.handle_request(HttpServerRequest::ConnectWebSocket {web_socket_id, headers, response_sender: tx, messages_written: messages_written.clone()});
+        (rx, messages_written)
+    }
+
+    #[test]
+    fn broadcast_and_presence() {
+        let (hub, events) = WebSocketHub::new(WebSocketHubClientConfig::default());
+        hub.set_presence_encoder( | p | Some(format!("{}:{}:{}", p.channel, p.client_id, p.joined).into_bytes()));
+        let (rx1, _) = connect(&hub, 1);
+        let (rx2, _) = connect(&hub, 2);
+        assert!(hub.subscribe(1, "room"));
+        assert!(hub.subscribe(2, "room"));
+        assert!(!hub.subscribe(2, "room"));
+        assert_eq!(rx1.try_recv().unwrap(), b"room:2:true");
+
+        assert_eq!(hub.broadcast_except("room", b"hi", 1), 1);
+        assert_eq!(rx2.try_recv().unwrap(), b"hi");
+        assert!(rx1.try_recv().is_err());
+
+        hub.handle_request(HttpServerRequest::DisconnectWebSocket {web_socket_id: 2});
+        assert_eq!(rx1.try_recv().unwrap(), b"room:2:false");
+        assert_eq!(hub.subscribers("room"), vec![1]);
+
+        let events: Vec<WebSocketHubEvent> = events.try_iter().collect();
+        assert!(matches!(events.last(), Some(WebSocketHubEvent::Disconnected {client_id: 2})));
+        assert!(events.iter().any( | e | matches!(e, WebSocketHubEvent::Left {client_id: 2, ..})));
+    }
+
+    #[test]
+    fn bounded_queues() {
+        let config = WebSocketHubClientConfig {queue_len: 2, max_in_flight: 1, drop_policy: WebSocketHubDropPolicy::DropOldest};
+        let (hub, _events) = WebSocketHub::new(config);
+        let (rx, written) = connect(&hub, 1);
+        hub.subscribe(1, "a");
+        for i in 0..5u8 {
+            hub.broadcast("a", &[i]);
+        }
+        // one message in flight, the two newest queued
+        assert_eq!(hub.client_stats(1), Some(WebSocketHubClientStats {queued: 2, in_flight: 1, dropped: 2}));
+        assert_eq!(rx.recv().unwrap(), [0]);
+        written.add_written();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)).unwrap(), [3]);
+
+        let (hub, events) = WebSocketHub::new(WebSocketHubClientConfig {drop_policy: WebSocketHubDropPolicy::Disconnect, ..config});
+        let (rx, _) = connect(&hub, 7);
+        hub.subscribe(7, "a");
+        for i in 0..4u8 {
+            hub.broadcast("a", &[i]);
+        }
+        assert!(events.try_iter().any( | e | matches!(e, WebSocketHubEvent::Overflowed {client_id: 7})));
+        assert!(hub.subscribers("a").is_empty());
+        // the empty message asks the server to close the socket
+        assert_eq!(rx.try_iter().last().unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn empty_messages_are_not_sent() {
+        let (hub, _events) = WebSocketHub::new(WebSocketHubClientConfig::default());
+        let (rx, _) = connect(&hub, 1);
+        hub.subscribe(1, "a");
+        assert_eq!(hub.broadcast("a", &[]), 0);
+        assert!(!hub.send(1, Vec::new()));
+        assert!(rx.try_recv().is_err());
+        assert_eq!(hub.subscribers("a"), vec![1]);
+        assert!(hub.send(1, vec![1]));
+        assert_eq!(rx.try_recv().unwrap(), [1]);
+    }
+}
@@ -0,0 +1,613 @@
+use {
+    std::collections::HashMap,
+    std::fmt,
+    crate::{
+        plural::{PluralCategory, plural_category},
+        format::{format_number, format_date},
+    },
+};
+
+// The subset of Fluent we read: messages and -terms with multiline patterns, .attributes,
+// placeables with $variables, string and number literals, message and term references,
+// NUMBER() and DATE() calls and select expressions on plural categories or exact numbers.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FluentValue {
+    String(String),
+    Number(f64),
+    // seconds since the unix epoch, formatted as a short date
+    Date(i64),
+}
+
+impl From<&str> for FluentValue {
+    fn from(v: &str) -> Self {Self::String(v.to_string())}
+}
+
+impl From<String> for FluentValue {
+    fn from(v: String) -> Self {Self::String(v)}
+}
+
+impl From<&String> for FluentValue {
+    fn from(v: &String) -> Self {Self::String(v.clone())}
+}
+
+macro_rules! fluent_value_from_number {
+    ($($ty: ty),*) => {
+        $(impl From<$ty> for FluentValue {
+            fn from(v: $ty) -> Self {Self::Number(v as f64)}
+        })*
+    }
+}
+
+fluent_value_from_number!(i8, i16, i32, i64, u8, u16, u32, u64, usize, isize, f32, f64);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CatalogError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Element {
+    Text(String),
+    Placeable(Expr),
+}
+
+type Pattern = Vec<Element>;
+
+#[derive(Clone, Debug)]
+enum VariantKey {
+    Name(String),
+    Number(f64),
+}
+
+#[derive(Clone, Debug)]
+struct Variant {
+    key: VariantKey,
+    value: Pattern,
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Str(String),
+    Number {value: f64, fraction_digits: usize},
+    Var(String),
+    Message(String),
+    Term(String),
+    Call {name: String, args: Vec<Expr>, named: Vec<(String, Expr)>},
+    Select {selector: Box<Expr>, variants: Vec<Variant>, default: usize},
+}
+
+#[derive(Default)]
+pub struct Catalog {
+    messages: HashMap<String, Pattern>,
+    terms: HashMap<String, Pattern>,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+    
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).cloned()
+    }
+    
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+    
+    fn error<T>(&self, message: &str) -> Result<T, CatalogError> {
+        Err(CatalogError {line: self.line + 1, message: message.to_string()})
+    }
+    
+    fn accept(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.next();
+            return true
+        }
+        false
+    }
+    
+    fn expect(&mut self, c: char) -> Result<(), CatalogError> {
+        if !self.accept(c) {
+            return self.error(&format!("expected '{}'", c))
+        }
+        Ok(())
+    }
+    
+    fn skip_inline_space(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.next();
+        }
+    }
+    
+    fn skip_space(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t') | Some('\n') | Some('\r')) {
+            self.next();
+        }
+    }
+    
+    fn skip_line(&mut self) {
+        while let Some(c) = self.next() {
+            if c == '\n' {
+                break
+            }
+        }
+    }
+    
+    fn identifier(&mut self) -> Result<String, CatalogError> {
+        let mut id = String::new();
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() => (),
+            _ => return self.error("expected an identifier")
+        }
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                id.push(c);
+                self.next();
+            }
+            else {
+                break
+            }
+        }
+        Ok(id)
+    }
+    
+    fn number(&mut self) -> Result<Expr, CatalogError> {
+        let mut s = String::new();
+        if self.accept('-') {
+            s.push('-');
+        }
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || c == '.' {
+                s.push(c);
+                self.next();
+            }
+            else {
+                break
+            }
+        }
+        let fraction_digits = s.split_once('.').map_or(0, |(_, f)| f.len());
+        match s.parse() {
+            Ok(value) => Ok(Expr::Number {value, fraction_digits}),
+            Err(_) => self.error("invalid number")
+        }
+    }
+    
+    fn string(&mut self) -> Result<String, CatalogError> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.next() {
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.next()).collect();
+                        match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                            Some(c) => s.push(c),
+                            None => return self.error("invalid unicode escape")
+                        }
+                    }
+                    Some(c) => s.push(c),
+                    None => return self.error("unterminated string")
+                }
+                Some('\n') | None => return self.error("unterminated string"),
+                Some(c) => s.push(c)
+            }
+        }
+    }
+    
+    // an indented line continues the pattern, unless it starts a variant or an attribute
+    fn continues_pattern(&self) -> bool {
+        let mut offset = 1;
+        let mut indented = false;
+        loop {
+            match self.peek_at(offset) {
+                Some(' ') => indented = true,
+                Some('\n') | Some('\r') => return false,
+                Some('[') | Some('*') | Some('.') | Some('}') | None => return false,
+                Some(_) => return indented
+            }
+            offset += 1;
+        }
+    }
+    
+    fn pattern(&mut self) -> Result<Pattern, CatalogError> {
+        let mut pattern = Vec::new();
+        let mut text = String::new();
+        self.skip_inline_space();
+        // a pattern can start on the line after the =
+        if self.peek() == Some('\n') && self.continues_pattern() {
+            self.next();
+            self.skip_inline_space();
+        }
+        loop {
+            match self.peek() {
+                Some('{') => {
+                    self.next();
+                    if !text.is_empty() {
+                        pattern.push(Element::Text(std::mem::take(&mut text)));
+                    }
+                    pattern.push(Element::Placeable(self.placeable()?));
+                }
+                Some('}') => return self.error("unbalanced '}'"),
+                Some('\r') => {
+                    self.next();
+                }
+                Some('\n') => {
+                    if !self.continues_pattern() {
+                        break
+                    }
+                    self.next();
+                    self.skip_inline_space();
+                    let trimmed = text.trim_end().len();
+                    text.truncate(trimmed);
+                    text.push('\n');
+                }
+                Some(c) => {
+                    self.next();
+                    text.push(c);
+                }
+                None => break
+            }
+        }
+        let trimmed = text.trim_end().len();
+        text.truncate(trimmed);
+        if !text.is_empty() {
+            pattern.push(Element::Text(text));
+        }
+        Ok(pattern)
+    }
+    
+    fn inline_expr(&mut self) -> Result<Expr, CatalogError> {
+        match self.peek() {
+            Some('"') => Ok(Expr::Str(self.string()?)),
+            Some('$') => {
+                self.next();
+                Ok(Expr::Var(self.identifier()?))
+            }
+            Some('-') if self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) => self.number(),
+            Some('-') => {
+                self.next();
+                Ok(Expr::Term(self.identifier()?))
+            }
+            Some(c) if c.is_ascii_digit() => self.number(),
+            Some('{') => {
+                self.next();
+                self.placeable()
+            }
+            Some(_) => {
+                let mut id = self.identifier()?;
+                if self.accept('(') {
+                    return self.call(id)
+                }
+                if self.accept('.') {
+                    id.push('.');
+                    id.push_str(&self.identifier()?);
+                }
+                Ok(Expr::Message(id))
+            }
+            None => self.error("expected an expression")
+        }
+    }
+    
+    fn call(&mut self, name: String) -> Result<Expr, CatalogError> {
+        let mut args = Vec::new();
+        let mut named = Vec::new();
+        loop {
+            self.skip_space();
+            if self.accept(')') {
+                break
+            }
+            let arg = self.inline_expr()?;
+            self.skip_space();
+            if let (Expr::Message(id), true) = (&arg, self.peek() == Some(':')) {
+                self.next();
+                self.skip_space();
+                named.push((id.clone(), self.inline_expr()?));
+            }
+            else {
+                args.push(arg);
+            }
+            self.skip_space();
+            if !self.accept(',') {
+                self.expect(')')?;
+                break
+            }
+        }
+        Ok(Expr::Call {name, args, named})
+    }
+    
+    // after the opening brace
+    fn placeable(&mut self) -> Result<Expr, CatalogError> {
+        self.skip_space();
+        let expr = self.inline_expr()?;
+        self.skip_space();
+        if self.accept('-') {
+            self.expect('>')?;
+            let mut variants = Vec::new();
+            let mut default = None;
+            loop {
+                self.skip_space();
+                if self.accept('}') {
+                    break
+                }
+                if self.accept('*') {
+                    if default.is_some() {
+                        return self.error("more than one default variant")
+                    }
+                    default = Some(variants.len());
+                }
+                self.expect('[')?;
+                self.skip_inline_space();
+                let key = match self.peek() {
+                    Some(c) if c.is_ascii_digit() || c == '-' => match self.number()? {
+                        Expr::Number {value, ..} => VariantKey::Number(value),
+                        _ => unreachable!()
+                    }
+                    _ => VariantKey::Name(self.identifier()?)
+                };
+                self.skip_inline_space();
+                self.expect(']')?;
+                let value = self.pattern()?;
+                variants.push(Variant {key, value});
+            }
+            let Some(default) = default else {
+                return self.error("select expression without a default variant")
+            };
+            return Ok(Expr::Select {selector: Box::new(expr), variants, default})
+        }
+        self.expect('}')?;
+        Ok(expr)
+    }
+    
+    fn resource(&mut self, catalog: &mut Catalog) -> Result<(), CatalogError> {
+        loop {
+            match self.peek() {
+                None => return Ok(()),
+                Some('#') => self.skip_line(),
+                Some('\n') | Some('\r') | Some(' ') | Some('\t') => {
+                    self.next();
+                }
+                Some(_) => {
+                    let term = self.accept('-');
+                    let id = self.identifier()?;
+                    self.skip_inline_space();
+                    self.expect('=')?;
+                    let value = self.pattern()?;
+                    // attributes on the lines after
+                    loop {
+                        let mut offset = 1;
+                        while self.peek_at(offset) == Some(' ') {
+                            offset += 1;
+                        }
+                        if self.peek() != Some('\n') || offset == 1 || self.peek_at(offset) != Some('.') {
+                            break
+                        }
+                        self.skip_space();
+                        self.expect('.')?;
+                        let attribute = self.identifier()?;
+                        self.skip_inline_space();
+                        self.expect('=')?;
+                        let value = self.pattern()?;
+                        catalog.messages.insert(format!("{}.{}", id, attribute), value);
+                    }
+                    if term {
+                        catalog.terms.insert(id, value);
+                    }
+                    else if !value.is_empty() {
+                        catalog.messages.insert(id, value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct Scope<'a> {
+    catalog: &'a Catalog,
+    locale: &'a str,
+    args: &'a [(&'a str, FluentValue)],
+    depth: usize,
+}
+
+enum Resolved {
+    String(String),
+    Number {value: f64, min: usize, max: usize},
+}
+
+impl Resolved {
+    fn fraction_digits(value: f64, min: usize, max: usize) -> usize {
+        let digits = format!("{:.*}", max, value.abs());
+        let fraction = digits.split_once('.').map_or("", |(_, f)| f);
+        fraction.trim_end_matches('0').len().max(min)
+    }
+    
+    fn number(value: f64) -> Self {
+        if value.fract() == 0.0 {
+            Self::Number {value, min: 0, max: 0}
+        }
+        else {
+            Self::Number {value, min: 0, max: 3}
+        }
+    }
+}
+
+impl<'a> Scope<'a> {
+    fn write_resolved(&self, resolved: Resolved, out: &mut String) {
+        match resolved {
+            Resolved::String(s) => out.push_str(&s),
+            Resolved::Number {value, min, max} => out.push_str(&format_number(self.locale, value, min, max)),
+        }
+    }
+    
+    fn write_pattern(&mut self, pattern: &Pattern, out: &mut String) {
+        // references that go in circles stop somewhere
+        if self.depth > 16 {
+            out.push_str("{???}");
+            return
+        }
+        self.depth += 1;
+        for element in pattern {
+            match element {
+                Element::Text(text) => out.push_str(text),
+                Element::Placeable(expr) => {
+                    let resolved = self.resolve(expr);
+                    self.write_resolved(resolved, out);
+                }
+            }
+        }
+        self.depth -= 1;
+    }
+    
+    fn reference(&mut self, patterns: &'a HashMap<String, Pattern>, id: &str, prefix: &str) -> Resolved {
+        let mut out = String::new();
+        match patterns.get(id) {
+            Some(pattern) => self.write_pattern(pattern, &mut out),
+            None => out = format!("{{{}{}}}", prefix, id)
+        }
+        Resolved::String(out)
+    }
+    
+    fn resolve(&mut self, expr: &Expr) -> Resolved {
+        match expr {
+            Expr::Str(s) => Resolved::String(s.clone()),
+            Expr::Number {value, fraction_digits} => Resolved::Number {value: *value, min: *fraction_digits, max: *fraction_digits},
+            Expr::Var(name) => match self.args.iter().find(|(n, _)| n == name) {
+                Some((_, FluentValue::String(s))) => Resolved::String(s.clone()),
+                Some((_, FluentValue::Number(v))) => Resolved::number(*v),
+                Some((_, FluentValue::Date(t))) => Resolved::String(format_date(self.locale, *t)),
+                None => Resolved::String(format!("{{${}}}", name))
+            }
+            Expr::Message(id) => self.reference(&self.catalog.messages, id, ""),
+            Expr::Term(id) => self.reference(&self.catalog.terms, id, "-"),
+            Expr::Call {name, args, named} => {
+                let arg = args.first().map(|arg| self.resolve(arg));
+                let option = |key: &str| named.iter().find(|(n, _)| n == key).and_then(|(_, v)| match v {
+                    Expr::Number {value, ..} => Some(*value as usize),
+                    _ => None
+                });
+                match (name.as_str(), arg) {
+                    ("NUMBER", Some(Resolved::Number {value, min, max})) => {
+                        let min = option("minimumFractionDigits").unwrap_or(min);
+                        let max = option("maximumFractionDigits").unwrap_or(max.max(min));
+                        Resolved::Number {value, min, max: max.max(min)}
+                    }
+                    ("DATE", Some(Resolved::Number {value, ..})) => Resolved::String(format_date(self.locale, value as i64)),
+                    (_, Some(arg)) => arg,
+                    (_, None) => Resolved::String(format!("{{{}()}}", name))
+                }
+            }
+            Expr::Select {selector, variants, default} => {
+                let selector = self.resolve(selector);
+                let chosen = match &selector {
+                    Resolved::Number {value, min, max} => {
+                        let category = plural_category(self.locale, *value, Resolved::fraction_digits(*value, *min, *max));
+                        variants.iter().position(|v| matches!(v.key, VariantKey::Number(n) if n == *value)).or_else(|| {
+                            variants.iter().position(|v| match &v.key {
+                                VariantKey::Name(name) => PluralCategory::from_name(name) == Some(category),
+                                _ => false
+                            })
+                        })
+                    }
+                    Resolved::String(s) => variants.iter().position(|v| matches!(&v.key, VariantKey::Name(name) if name == s))
+                };
+                let mut out = String::new();
+                self.write_pattern(&variants[chosen.unwrap_or(*default)].value, &mut out);
+                Resolved::String(out)
+            }
+        }
+    }
+}
+
+impl Catalog {
+    pub fn parse(source: &str) -> Result<Catalog, CatalogError> {
+        let mut catalog = Catalog::default();
+        let mut parser = Parser {chars: source.chars().collect(), pos: 0, line: 0};
+        parser.resource(&mut catalog)?;
+        Ok(catalog)
+    }
+    
+    pub fn has_message(&self, id: &str) -> bool {
+        self.messages.contains_key(id)
+    }
+    
+    // the message id, or id.attribute, formatted for locale with args for its variables
+    pub fn format(&self, locale: &str, id: &str, args: &[(&str, FluentValue)]) -> Option<String> {
+        let pattern = self.messages.get(id)?;
+        let mut scope = Scope {catalog: self, locale, args, depth: 0};
+        let mut out = String::new();
+        scope.write_pattern(pattern, &mut out);
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+# the app
+-brand = Makepad
+hello = Hello, { $name }!
+welcome = Welcome to { -brand }
+multiline =
+    First line
+    second line
+emails = { $count ->
+    [0] No new emails
+    [one] One new email
+   *[other] { $count } new emails
+}
+price = Total: { NUMBER($amount, minimumFractionDigits: 2) }
+login = Log in
+    .tooltip = Log in with your { -brand } account
+"#;
+
+    #[test]
+    fn messages() {
+        let catalog = Catalog::parse(SOURCE).unwrap();
+        assert_eq!(catalog.format("en", "hello", &[("name", "Ana".into())]).unwrap(), "Hello, Ana!");
+        assert_eq!(catalog.format("en", "welcome", &[]).unwrap(), "Welcome to Makepad");
+        assert_eq!(catalog.format("en", "multiline", &[]).unwrap(), "First line\nsecond line");
+        assert_eq!(catalog.format("en", "login", &[]).unwrap(), "Log in");
+        assert_eq!(catalog.format("en", "login.tooltip", &[]).unwrap(), "Log in with your Makepad account");
+        assert_eq!(catalog.format("en", "hello", &[]).unwrap(), "Hello, {$name}!");
+        assert!(catalog.format("en", "brand", &[]).is_none());
+    }
+
+    #[test]
+    fn plurals_and_numbers() {
+        let catalog = Catalog::parse(SOURCE).unwrap();
+        assert_eq!(catalog.format("en", "emails", &[("count", 0.into())]).unwrap(), "No new emails");
+        assert_eq!(catalog.format("en", "emails", &[("count", 1.into())]).unwrap(), "One new email");
+        assert_eq!(catalog.format("en", "emails", &[("count", 1200.into())]).unwrap(), "1,200 new emails");
+        assert_eq!(catalog.format("de", "emails", &[("count", 1200.into())]).unwrap(), "1.200 new emails");
+        assert_eq!(catalog.format("de", "price", &[("amount", 3.5.into())]).unwrap(), "Total: 3,50");
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Catalog::parse("a = { $x ->\n [one] x\n}").err().unwrap().line, 3);
+        assert!(Catalog::parse("a = unbalanced }").is_err());
+        assert!(Catalog::parse("= value").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use crate::locale_language;
+
+struct NumberSymbols {
+    decimal: &'static str,
+    group: &'static str,
+    // the number of digits in front of the first group before it gets a separator
+    min_grouping: usize,
+}
+
+fn number_symbols(locale: &str) -> NumberSymbols {
+    let (decimal, group, min_grouping) = match locale_language(locale) {
+        "de" | "nl" | "it" | "id" | "tr" | "el" | "da" => (",", ".", 1),
+        "es" => (",", ".", 2),
+        "pt" if locale == "pt-PT" || locale == "pt_PT" => (",", "\u{a0}", 2),
+        "pt" => (",", ".", 1),
+        "fr" => (",", "\u{202f}", 1),
+        "pl" => (",", "\u{a0}", 2),
+        "ru" | "uk" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "bg" | "hu" | "et" => (",", "\u{a0}", 1),
+        _ => (".", ",", 1)
+    };
+    NumberSymbols {decimal, group, min_grouping}
+}
+
+// formats value with between min_fraction and max_fraction fraction digits, grouped and
+// with the separators of the locale
+pub fn format_number(locale: &str, value: f64, min_fraction: usize, max_fraction: usize) -> String {
+    let symbols = number_symbols(locale);
+    let max_fraction = max_fraction.max(min_fraction);
+    let digits = format!("{:.*}", max_fraction, value.abs());
+    let (int, all_fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+    let trimmed = all_fraction.trim_end_matches('0').len().max(min_fraction);
+    let fraction = &all_fraction[..trimmed];
+    let mut out = String::new();
+    if value < 0.0 && digits.chars().any(|c| c != '0' && c != '.') {
+        out.push('-');
+    }
+    if int.len() >= 3 + symbols.min_grouping {
+        for (index, c) in int.chars().enumerate() {
+            if index > 0 && (int.len() - index) % 3 == 0 {
+                out.push_str(symbols.group);
+            }
+            out.push(c);
+        }
+    }
+    else {
+        out.push_str(int);
+    }
+    if !fraction.is_empty() {
+        out.push_str(symbols.decimal);
+        out.push_str(fraction);
+    }
+    out
+}
+
+// the short date pattern of the locale, in the y/M/d letters of the CLDR patterns
+fn date_pattern(locale: &str) -> &'static str {
+    match locale_language(locale) {
+        "en" => match locale {
+            "en-US" | "en_US" | "en" => "M/d/yyyy",
+            _ => "dd/MM/yyyy"
+        },
+        "de" | "ru" | "uk" | "pl" | "tr" | "nb" | "no" | "bg" => "dd.MM.yyyy",
+        "fi" | "he" => "d.M.yyyy",
+        "fr" | "es" | "it" | "pt" | "ar" | "id" => "dd/MM/yyyy",
+        "el" => "d/M/yyyy",
+        "nl" => "dd-MM-yyyy",
+        "cs" | "sk" => "d. M. yyyy",
+        "sv" | "lt" | "da" => "yyyy-MM-dd",
+        "ja" | "zh" => "yyyy/MM/dd",
+        "ko" | "hu" => "yyyy. MM. dd.",
+        _ => "yyyy-MM-dd"
+    }
+}
+
+// days since 1970-01-01 to a proleptic gregorian year, month and day
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 {mp + 3} else {mp - 9} as u32;
+    let year = yoe + era * 400 + if month <= 2 {1} else {0};
+    (year, month, day)
+}
+
+// the short date of a unix timestamp in UTC
+pub fn format_date(locale: &str, unix_seconds: i64) -> String {
+    let (year, month, day) = civil_from_days(unix_seconds.div_euclid(86400));
+    let pattern = date_pattern(locale);
+    let mut out = String::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut run = 1;
+        while chars.peek() == Some(&c) {
+            chars.next();
+            run += 1;
+        }
+        match c {
+            'y' => out.push_str(&format!("{:04}", year)),
+            'M' => out.push_str(&format!("{:01$}", month, run)),
+            'd' => out.push_str(&format!("{:01$}", day, run)),
+            _ => for _ in 0..run {out.push(c)}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        assert_eq!(format_number("en", 1234567.891, 0, 2), "1,234,567.89");
+        assert_eq!(format_number("de", 1234.5, 2, 2), "1.234,50");
+        assert_eq!(format_number("es", 1234.0, 0, 0), "1234");
+        assert_eq!(format_number("es", 12345.0, 0, 0), "12.345");
+        assert_eq!(format_number("fr", -1000.25, 0, 3), "-1\u{202f}000,25");
+        assert_eq!(format_number("en", -0.001, 0, 2), "0");
+        assert_eq!(format_number("en", 2.5, 1, 3), "2.5");
+        assert_eq!(format_number("en", 2.0, 1, 3), "2.0");
+    }
+
+    #[test]
+    fn dates() {
+        // 2024-03-05
+        let t = 1709596800;
+        assert_eq!(format_date("en-US", t), "3/5/2024");
+        assert_eq!(format_date("en-GB", t), "05/03/2024");
+        assert_eq!(format_date("de", t), "05.03.2024");
+        assert_eq!(format_date("ja", t), "2024/03/05");
+        assert_eq!(format_date("cs", t), "5. 3. 2024");
+        assert_eq!(format_date("sv", 0), "1970-01-01");
+    }
+}
@@ -0,0 +1,19 @@
+// Message catalogs in a subset of the Fluent syntax, parsed in-house, with the CLDR plural
+// rules and number/date formats for the locales we ship in.
+
+mod plural;
+mod format;
+mod fluent;
+mod localization;
+
+pub use {
+    plural::{PluralCategory, plural_category},
+    format::{format_number, format_date},
+    fluent::{Catalog, CatalogError, FluentValue},
+    localization::Localization,
+};
+
+// the language part of a locale tag, en-US and en_US both give en
+pub fn locale_language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
@@ -0,0 +1,68 @@
+use crate::fluent::{Catalog, FluentValue};
+
+// the catalogs per locale and the one in use. a message missing from the current locale
+// comes from the fallback locale, and when that lacks it too the message id is shown
+pub struct Localization {
+    locale: String,
+    fallback: String,
+    catalogs: Vec<(String, Catalog)>,
+}
+
+impl Default for Localization {
+    fn default() -> Self {Self::new()}
+}
+
+impl Localization {
+    pub const fn new() -> Self {
+        Self {
+            locale: String::new(),
+            fallback: String::new(),
+            catalogs: Vec::new(),
+        }
+    }
+    
+    pub fn locale(&self) -> &str {
+        if self.locale.is_empty() {"en"} else {&self.locale}
+    }
+    
+    pub fn set_locale(&mut self, locale: &str) -> bool {
+        if self.locale == locale {
+            return false
+        }
+        self.locale = locale.to_string();
+        true
+    }
+    
+    pub fn set_fallback(&mut self, locale: &str) {
+        self.fallback = locale.to_string();
+    }
+    
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        self.catalogs.iter().map(|(locale, _)| locale.as_str())
+    }
+    
+    // a second catalog for a locale replaces the first
+    pub fn add_catalog(&mut self, locale: &str, catalog: Catalog) {
+        self.catalogs.retain(|(l, _)| l != locale);
+        self.catalogs.push((locale.to_string(), catalog));
+    }
+    
+    fn catalog_for(&self, locale: &str) -> Option<&Catalog> {
+        // de-AT falls back on de
+        self.catalogs.iter().find(|(l, _)| l == locale).or_else(|| {
+            let language = crate::locale_language(locale);
+            self.catalogs.iter().find(|(l, _)| l == language)
+        }).map(|(_, c)| c)
+    }
+    
+    pub fn format(&self, id: &str, args: &[(&str, FluentValue)]) -> String {
+        let locale = self.locale();
+        if let Some(out) = self.catalog_for(locale).and_then(|c| c.format(locale, id, args)) {
+            return out
+        }
+        if let Some(out) = self.catalog_for(&self.fallback).and_then(|c| c.format(&self.fallback, id, args)) {
+            return out
+        }
+        id.to_string()
+    }
+}
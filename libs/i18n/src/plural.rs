@@ -0,0 +1,156 @@
+use crate::locale_language;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "zero" => Self::Zero,
+            "one" => Self::One,
+            "two" => Self::Two,
+            "few" => Self::Few,
+            "many" => Self::Many,
+            "other" => Self::Other,
+            _ => return None
+        })
+    }
+}
+
+// the CLDR operands, n the absolute value, i its integer digits, v the number of visible
+// fraction digits and t those digits as an integer without trailing zeros. f, the digits with the
+// zeros, is left out as none of our languages need it
+struct Operands {
+    n: f64,
+    i: u64,
+    v: usize,
+    t: u64,
+}
+
+impl Operands {
+    fn new(value: f64, fraction_digits: usize) -> Self {
+        let n = value.abs();
+        let fraction = format!("{:.*}", fraction_digits, n);
+        let digits = fraction.split('.').nth(1).unwrap_or("");
+        let t = digits.trim_end_matches('0').parse().unwrap_or(0);
+        Self {n, i: n.trunc() as u64, v: fraction_digits, t}
+    }
+}
+
+// the cardinal plural category of value shown with fraction_digits digits, for the
+// languages we ship in. anything else only has other
+pub fn plural_category(locale: &str, value: f64, fraction_digits: usize) -> PluralCategory {
+    use PluralCategory::*;
+    let o = Operands::new(value, fraction_digits);
+    let (n, i, v) = (o.n, o.i, o.v);
+    let i10 = i % 10;
+    let i100 = i % 100;
+    match locale_language(locale) {
+        "ja" | "zh" | "ko" | "th" | "vi" | "id" | "ms" => Other,
+        "en" | "de" | "nl" | "sv" | "it" | "fi" | "et" | "ca" | "da" if i == 1 && v == 0 => One,
+        "da" if n == 1.0 || (o.t != 0 && (i == 0 || i == 1)) => One,
+        "es" | "el" | "hu" | "tr" | "bg" | "nb" | "no" if n == 1.0 => One,
+        "fr" if i == 0 || i == 1 => One,
+        "pt" if locale == "pt-PT" || locale == "pt_PT" => if i == 1 && v == 0 {One} else {Other},
+        "pt" if i == 0 || i == 1 => One,
+        "ru" | "uk" if v == 0 => {
+            if i10 == 1 && i100 != 11 {One}
+            else if (2..=4).contains(&i10) && !(12..=14).contains(&i100) {Few}
+            else {Many}
+        }
+        "pl" if v == 0 => {
+            if i == 1 {One}
+            else if (2..=4).contains(&i10) && !(12..=14).contains(&i100) {Few}
+            else {Many}
+        }
+        "cs" | "sk" => {
+            if v != 0 {Many}
+            else if i == 1 {One}
+            else if (2..=4).contains(&i) {Few}
+            else {Other}
+        }
+        "he" if (v == 0 && i == 1) || (i == 0 && v != 0) => One,
+        "he" if v == 0 && i == 2 => Two,
+        "ar" if v == 0 => {
+            if i == 0 {Zero}
+            else if i == 1 {One}
+            else if i == 2 {Two}
+            else if (3..=10).contains(&i100) {Few}
+            else if (11..=99).contains(&i100) {Many}
+            else {Other}
+        }
+        _ => Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PluralCategory::*;
+
+    #[test]
+    fn english() {
+        assert_eq!(plural_category("en-US", 1.0, 0), One);
+        assert_eq!(plural_category("en", 1.0, 1), Other);
+        assert_eq!(plural_category("en", 0.0, 0), Other);
+    }
+
+    #[test]
+    fn slavic() {
+        assert_eq!(plural_category("ru", 21.0, 0), One);
+        assert_eq!(plural_category("ru", 11.0, 0), Many);
+        assert_eq!(plural_category("ru", 23.0, 0), Few);
+        assert_eq!(plural_category("ru", 1.5, 1), Other);
+        assert_eq!(plural_category("pl", 22.0, 0), Few);
+        assert_eq!(plural_category("pl", 12.0, 0), Many);
+        assert_eq!(plural_category("cs", 3.0, 0), Few);
+        assert_eq!(plural_category("cs", 1.5, 1), Many);
+    }
+
+    #[test]
+    fn arabic() {
+        assert_eq!(plural_category("ar", 0.0, 0), Zero);
+        assert_eq!(plural_category("ar", 2.0, 0), Two);
+        assert_eq!(plural_category("ar", 105.0, 0), Few);
+        assert_eq!(plural_category("ar", 111.0, 0), Many);
+        assert_eq!(plural_category("ar", 100.0, 0), Other);
+    }
+
+    #[test]
+    fn french_and_portuguese() {
+        assert_eq!(plural_category("fr", 0.0, 0), One);
+        assert_eq!(plural_category("fr", 1.5, 1), One);
+        assert_eq!(plural_category("pt-BR", 0.0, 0), One);
+        assert_eq!(plural_category("pt-PT", 0.0, 0), Other);
+    }
+
+    // the examples CLDR lists for each category
+    #[test]
+    fn danish() {
+        for (value, digits) in [(1.0, 0), (0.1, 1), (1.0, 1), (1.5, 1), (1.61, 2), (0.01, 2)] {
+            assert_eq!(plural_category("da", value, digits), One, "{value} with {digits} digits");
+        }
+        for (value, digits) in [(0.0, 0), (2.0, 0), (16.0, 0), (0.0, 1), (2.0, 1), (2.5, 1), (0.0, 2), (1.0, 2)] {
+            let expected = if value == 1.0 {One} else {Other};
+            assert_eq!(plural_category("da", value, digits), expected, "{value} with {digits} digits");
+        }
+    }
+
+    #[test]
+    fn hebrew() {
+        for (value, digits) in [(1.0, 0), (0.0, 1), (0.5, 1), (0.25, 2)] {
+            assert_eq!(plural_category("he", value, digits), One, "{value} with {digits} digits");
+        }
+        assert_eq!(plural_category("he", 2.0, 0), Two);
+        for (value, digits) in [(0.0, 0), (3.0, 0), (10.0, 0), (20.0, 0), (1.0, 1), (2.0, 1), (1.5, 1)] {
+            assert_eq!(plural_category("he", value, digits), Other, "{value} with {digits} digits");
+        }
+    }
+}
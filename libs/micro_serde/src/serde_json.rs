@@ -335,6 +335,37 @@ impl DeJsonState {
         Err(self.err_token("ident"))
     }
     
+    // reads a \u escape, the cursor is on the 'u'. Leaves the cursor on the character after
+    // the escape, characters outside the basic plane take a surrogate pair of escapes
+    fn unicode_escape(&mut self, i: &mut Chars) -> Result<char, DeJsonErr> {
+        let high = self.hex4(i) ?;
+        if (0xD800..0xDC00).contains(&high) {
+            if self.cur != '\\' {
+                return Err(self.err_parse("unicode escape"));
+            }
+            self.next(i);
+            if self.cur != 'u' {
+                return Err(self.err_parse("unicode escape"));
+            }
+            let low = self.hex4(i) ?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.err_parse("unicode escape"));
+            }
+            return char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)).ok_or_else( || self.err_parse("unicode escape"))
+        }
+        char::from_u32(high).ok_or_else( || self.err_parse("unicode escape"))
+    }
+    
+    fn hex4(&mut self, i: &mut Chars) -> Result<u32, DeJsonErr> {
+        let mut value = 0;
+        for _ in 0..4 {
+            self.next(i);
+            value = value * 16 + self.cur.to_digit(16).ok_or_else( || self.err_parse("unicode escape")) ?;
+        }
+        self.next(i);
+        Ok(value)
+    }
+    
     pub fn next_tok(&mut self, i: &mut Chars) -> Result<(), DeJsonErr> {
         while self.cur == '\n' || self.cur == '\r' || self.cur == '\t' || self.cur == ' ' {
             self.next(i);
@@ -455,7 +486,14 @@ impl DeJsonState {
                             'n'=>self.strbuf.push('\n'),
                             'r'=>self.strbuf.push('\r'),
                             't'=>self.strbuf.push('\t'),
+                            'b'=>self.strbuf.push('\u{8}'),
+                            'f'=>self.strbuf.push('\u{c}'),
                             '0'=>self.strbuf.push('\0'),
+                            'u'=>{
+                                let c = self.unicode_escape(i) ?;
+                                self.strbuf.push(c);
+                                continue;
+                            }
                             '\0'=>{
                                 return Err(self.err_parse("string"));
                             },
@@ -620,6 +658,7 @@ impl SerJson for String {
                 '\0'=>{s.out.push('\\');s.out.push('0');},
                 '\\'=>{s.out.push('\\');s.out.push('\\');},
                 '"'=>{s.out.push('\\');s.out.push('"');},
+                c if (c as u32) < 0x20 =>{s.out.push_str(&format!("\\u{:04x}", c as u32));},
                 _=>s.out.push(c)
             }
         }
@@ -700,6 +739,37 @@ impl JsonValue{
         }
         None
     }
+    pub fn array(&self)->Option<&Vec<JsonValue>>{
+        if let JsonValue::Array(arr) = self{
+            return Some(arr)
+        }
+        None
+    }
+    pub fn u64(&self)->Option<u64>{
+        match self{
+            JsonValue::U64(v)=>Some(*v),
+            JsonValue::I64(v)=>u64::try_from(*v).ok(),
+            JsonValue::F64(v) if *v >= 0.0 && v.fract() == 0.0=>Some(*v as u64),
+            _=>None
+        }
+    }
+    pub fn i64(&self)->Option<i64>{
+        match self{
+            JsonValue::U64(v)=>i64::try_from(*v).ok(),
+            JsonValue::I64(v)=>Some(*v),
+            JsonValue::F64(v) if v.fract() == 0.0=>Some(*v as i64),
+            _=>None
+        }
+    }
+    pub fn bool(&self)->Option<bool>{
+        if let JsonValue::Bool(v) = self{
+            return Some(*v)
+        }
+        None
+    }
+    pub fn is_null(&self)->bool{
+        matches!(self, JsonValue::Null | JsonValue::Undefined)
+    }
 }
 
 impl SerJson for JsonValue{
@@ -926,7 +996,7 @@ impl<K, V> SerJson for HashMap<K, V> where K: SerJson,
 V: SerJson {
     fn ser_json(&self, d: usize, s: &mut SerJsonState) {
         s.out.push('{');
-        let last = self.len() - 1;
+        let last = self.len().saturating_sub(1);
         for (index, (k, v)) in self.iter().enumerate() {
             s.indent(d + 1);
             k.ser_json(d + 1, s);
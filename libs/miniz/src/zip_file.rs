@@ -1,7 +1,12 @@
-// zip fileformat reading
+// zip fileformat reading and writing
 
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{self, Read, Write, Seek, SeekFrom, Cursor};
 pub use crate::inflate::decompress_to_vec;
+use crate::deflate::compress_to_vec;
+use crate::deflate::core::{CompressorOxide, create_comp_flags_from_zip_params};
+use crate::deflate::stream::deflate;
+use crate::inflate::stream::{inflate, InflateState};
+use crate::{DataFormat, MZFlush, MZStatus, MZError};
 
 pub const COMPRESS_METHOD_UNCOMPRESSED:u16 = 0;
 pub const COMPRESS_METHOD_DEFLATED:u16 = 8;
@@ -22,6 +27,7 @@ pub struct LocalFileHeader {
     pub file_name_length: u16,
     pub extra_field_length: u16,
     pub file_name: String,
+    pub extra_field: Vec<u8>,
 }
 
 impl LocalFileHeader{
@@ -42,7 +48,7 @@ impl LocalFileHeader{
         let extra_field_length = read_u16(zip_data)?;
 
         let file_name = read_string(zip_data, file_name_length as usize)?;
-        zip_data.seek(SeekFrom::Current(extra_field_length as i64)).map_err(|_| ZipError::CantSeekSkip)?;
+        let extra_field = read_binary(zip_data, extra_field_length as usize)?;
         
         Ok(Self{
             signature,
@@ -57,6 +63,7 @@ impl LocalFileHeader{
             file_name_length,
             extra_field_length,
             file_name,
+            extra_field,
         })
     }
 }
@@ -85,6 +92,7 @@ pub struct CentralDirectoryFileHeader {
 
     pub file_name: String,
     pub file_comment: String,
+    pub extra_field: Vec<u8>,
 }
 
 impl CentralDirectoryFileHeader{
@@ -110,7 +118,7 @@ impl CentralDirectoryFileHeader{
         let external_file_attributes = read_u32(zip_data)?;
         let relative_offset_of_local_header = read_u32(zip_data)?;
         let file_name = read_string(zip_data, file_name_length as usize)?;
-        zip_data.seek(SeekFrom::Current(extra_field_length as i64)).map_err(|_| ZipError::CantSeekSkip)?;
+        let extra_field = read_binary(zip_data, extra_field_length as usize)?;
         let file_comment = read_string(zip_data, file_comment_length as usize)?;
         
         Ok(Self{
@@ -132,9 +140,52 @@ impl CentralDirectoryFileHeader{
             external_file_attributes,
             relative_offset_of_local_header,
             file_name,
-            file_comment
+            file_comment,
+            extra_field,
         })
     }
+    
+    // the ZIP64 extra field holds, in order, whichever of these overflowed 32 bits
+    fn zip64_values(&self)->(u64, u64, u64){
+        let mut uncompressed = self.uncompressed_size as u64;
+        let mut compressed = self.compressed_size as u64;
+        let mut offset = self.relative_offset_of_local_header as u64;
+        if let Some(mut data) = find_extra_field(&self.extra_field, ZIP64_EXTRA_FIELD_ID){
+            for (value, raw) in [(&mut uncompressed, self.uncompressed_size), (&mut compressed, self.compressed_size), (&mut offset, self.relative_offset_of_local_header)]{
+                if raw == 0xFFFF_FFFF && data.len() >= 8{
+                    *value = u64::from_le_bytes(data[0..8].try_into().unwrap());
+                    data = &data[8..];
+                }
+            }
+        }
+        (uncompressed, compressed, offset)
+    }
+    
+    pub fn uncompressed_size_u64(&self)->u64{
+        self.zip64_values().0
+    }
+    
+    pub fn compressed_size_u64(&self)->u64{
+        self.zip64_values().1
+    }
+    
+    pub fn local_header_offset_u64(&self)->u64{
+        self.zip64_values().2
+    }
+    
+    pub fn is_directory(&self)->bool{
+        self.file_name.ends_with('/')
+    }
+    
+    // the unix permission bits for archives made on unix
+    pub fn unix_mode(&self)->Option<u32>{
+        if self.version_made_by >> 8 == 3{
+            Some(self.external_file_attributes >> 16)
+        }
+        else{
+            None
+        }
+    }
 }
 
 pub const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
@@ -172,51 +223,74 @@ impl EndOfCentralDirectory{
 
 fn read_u16(zip_data:&mut impl Read)->Result<u16, ZipError>{
     let mut bytes = [0u8;2];
-    if let Ok(size) = zip_data.read(&mut bytes){
-        if size != 2{
-            return Err(ZipError::DataReadError)
-        }
-        return Ok(u16::from_le_bytes(bytes))
-    }
-    Err(ZipError::DataReadError)
+    zip_data.read_exact(&mut bytes).map_err(|_| ZipError::DataReadError)?;
+    Ok(u16::from_le_bytes(bytes))
 }
 
 fn read_u32(zip_data:&mut impl Read)->Result<u32, ZipError>{
     let mut bytes = [0u8;4];
-    if let Ok(size) = zip_data.read(&mut bytes){
-        if size != 4{
-            return Err(ZipError::DataReadError)
-        }
-        return Ok(u32::from_le_bytes(bytes))
-    }
-    Err(ZipError::DataReadError)
+    zip_data.read_exact(&mut bytes).map_err(|_| ZipError::DataReadError)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(zip_data:&mut impl Read)->Result<u64, ZipError>{
+    let mut bytes = [0u8;8];
+    zip_data.read_exact(&mut bytes).map_err(|_| ZipError::DataReadError)?;
+    Ok(u64::from_le_bytes(bytes))
 }
 
 fn read_string(zip_data:&mut impl Read, len:usize)->Result<String, ZipError>{
-    let mut data = Vec::new();
-    data.resize(len,0u8);
-    if let Ok(size) = zip_data.read(&mut data){
-        if size != data.len(){
-            return Err(ZipError::DataReadError)
-        }
-        if let Ok(s) = String::from_utf8(data){
-            return Ok(s)
+    let data = read_binary(zip_data, len)?;
+    String::from_utf8(data).map_err(|_| ZipError::ReadStringError)
+}
+
+fn read_binary(zip_data:&mut impl Read, len:usize)->Result<Vec<u8>, ZipError>{
+    let mut data = vec![0u8; len];
+    zip_data.read_exact(&mut data).map_err(|_| ZipError::DataReadError)?;
+    Ok(data)
+}
+
+pub const ZIP64_EXTRA_FIELD_ID:u16 = 0x0001;
+// what zipalign uses to pad local headers: the alignment followed by zeros
+pub const ALIGNMENT_EXTRA_FIELD_ID:u16 = 0xd935;
+
+fn find_extra_field(extra:&[u8], id:u16)->Option<&[u8]>{
+    let mut pos = 0;
+    while pos + 4 <= extra.len(){
+        let field_id = u16::from_le_bytes([extra[pos], extra[pos+1]]);
+        let len = u16::from_le_bytes([extra[pos+2], extra[pos+3]]) as usize;
+        let data = extra.get(pos + 4..pos + 4 + len)?;
+        if field_id == id{
+            return Some(data)
         }
-        return Err(ZipError::ReadStringError)
+        pos += 4 + len;
     }
-    Err(ZipError::DataReadError)
+    None
 }
 
-fn read_binary(zip_data:&mut impl Read, len:usize)->Result<Vec<u8>, ZipError>{
-    let mut data = Vec::new();
-    data.resize(len,0u8);
-    if let Ok(size) = zip_data.read(&mut data){
-        if size != data.len(){
-            return Err(ZipError::DataReadError)
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256{
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8{
+            c = if c & 1 != 0 {0xEDB8_8320 ^ (c >> 1)} else {c >> 1};
+            k += 1;
         }
-        return Ok(data)
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+// continues a crc32 over more data, start with 0
+pub fn crc32_update(crc:u32, data:&[u8])->u32{
+    let mut c = !crc;
+    for byte in data{
+        c = CRC32_TABLE[((c ^ *byte as u32) & 0xff) as usize] ^ (c >> 8);
     }
-    Err(ZipError::DataReadError)
+    !c
 }
 
 pub struct ZipCentralDirectory{
@@ -227,22 +301,103 @@ pub struct ZipCentralDirectory{
 impl CentralDirectoryFileHeader{
     // lets read and unzip specific files.
     pub fn extract(&self, zip_data: &mut (impl Seek+Read))->Result<Vec<u8>, ZipError>{
-        zip_data.seek(SeekFrom::Start(self.relative_offset_of_local_header as u64)).map_err(|_| ZipError::CantSeekToFileHeader)?;
-        let header = LocalFileHeader::from_stream(zip_data)?;
-        if header.compression_method == COMPRESS_METHOD_UNCOMPRESSED{
-            let decompressed = read_binary(zip_data, self.uncompressed_size as usize)?;
-            return Ok(decompressed)
-        }
-        else if header.compression_method == COMPRESS_METHOD_DEFLATED{
-            let compressed = read_binary(zip_data, self.compressed_size as usize)?;
-            if let Ok(decompressed) = decompress_to_vec(&compressed){
-                return Ok(decompressed);
+        let mut reader = self.reader(zip_data)?;
+        let mut out = Vec::with_capacity(self.uncompressed_size_u64() as usize);
+        reader.read_to_end(&mut out).map_err(|e| {
+            if e.kind() == io::ErrorKind::InvalidData {ZipError::DecompressionError} else {ZipError::DataReadError}
+        })?;
+        Ok(out)
+    }
+    
+    // a reader that decompresses the entry as it goes and checks its crc at the end
+    pub fn reader<'a, R: Seek+Read>(&self, zip_data: &'a mut R)->Result<ZipEntryReader<'a, R>, ZipError>{
+        zip_data.seek(SeekFrom::Start(self.local_header_offset_u64())).map_err(|_| ZipError::CantSeekToFileHeader)?;
+        LocalFileHeader::from_stream(zip_data)?;
+        let inflate = match self.compression_method{
+            COMPRESS_METHOD_UNCOMPRESSED=>None,
+            COMPRESS_METHOD_DEFLATED=>Some(InflateState::new_boxed(DataFormat::Raw)),
+            _=>return Err(ZipError::UnsupportedCompressionMethod)
+        };
+        Ok(ZipEntryReader{
+            input: zip_data.take(self.compressed_size_u64()),
+            inflate,
+            in_buf: vec![0u8; 32768],
+            in_pos: 0,
+            in_len: 0,
+            input_done: false,
+            done: false,
+            crc: 0,
+            expected_crc: self.crc32,
+            remaining: self.uncompressed_size_u64(),
+        })
+    }
+}
+
+pub struct ZipEntryReader<'a, R: Read>{
+    input: io::Take<&'a mut R>,
+    inflate: Option<Box<InflateState>>,
+    in_buf: Vec<u8>,
+    in_pos: usize,
+    in_len: usize,
+    input_done: bool,
+    done: bool,
+    crc: u32,
+    expected_crc: u32,
+    remaining: u64,
+}
+
+impl<'a, R: Read> ZipEntryReader<'a, R>{
+    fn check_end(&mut self)->io::Result<usize>{
+        self.done = true;
+        if self.remaining != 0{
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "zip entry is truncated"))
+        }
+        if self.crc != self.expected_crc{
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "zip entry crc mismatch"))
+        }
+        Ok(0)
+    }
+    
+    fn produced(&mut self, data:&[u8])->io::Result<usize>{
+        if data.len() as u64 > self.remaining{
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "zip entry is larger than its header says"))
+        }
+        self.remaining -= data.len() as u64;
+        self.crc = crc32_update(self.crc, data);
+        Ok(data.len())
+    }
+}
+
+impl<'a, R: Read> Read for ZipEntryReader<'a, R>{
+    fn read(&mut self, buf: &mut [u8])->io::Result<usize>{
+        if self.done || buf.is_empty(){
+            return Ok(0)
+        }
+        let state = if let Some(state) = &mut self.inflate{state} else{
+            let n = self.input.read(buf)?;
+            if n == 0{
+                return self.check_end()
             }
-            else{
-                return Err(ZipError::DecompressionError)
+            return self.produced(&buf[0..n])
+        };
+        loop{
+            if self.in_pos == self.in_len && !self.input_done{
+                self.in_len = self.input.read(&mut self.in_buf)?;
+                self.in_pos = 0;
+                self.input_done = self.in_len == 0;
+            }
+            let flush = if self.input_done {MZFlush::Finish} else {MZFlush::None};
+            let result = inflate(state, &self.in_buf[self.in_pos..self.in_len], buf, flush);
+            self.in_pos += result.bytes_consumed;
+            let written = result.bytes_written;
+            match result.status{
+                Ok(MZStatus::StreamEnd) if written == 0 =>return self.check_end(),
+                Ok(_) | Err(MZError::Buf) if written > 0 =>return self.produced(&buf[0..written]),
+                Ok(_)=>(),
+                Err(MZError::Buf) if !self.input_done || self.in_pos < self.in_len=>(),
+                Err(_)=>return Err(io::Error::new(io::ErrorKind::InvalidData, "zip entry deflate stream is corrupt"))
             }
         }
-        Err(ZipError::UnsupportedCompressionMethod)
     }
 }
 
@@ -259,16 +414,61 @@ pub enum ZipError{
     CantSeekToDirStart,
     UnsupportedCompressionMethod,
     DecompressionError,
-    DataReadError
+    DataReadError,
+    DataWriteError,
+    CompressionError,
+    NameTooLong,
+    NoEntryStarted,
+    // a streamed entry grew past 4GB without large_file set in its options
+    Zip64Required,
+}
+
+pub const ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06064b50;
+pub const ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+pub const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x08074b50;
+
+impl ZipCentralDirectory{
+    pub fn find(&self, file_name:&str)->Option<&CentralDirectoryFileHeader>{
+        self.file_headers.iter().find(|v| v.file_name == file_name)
+    }
 }
 
 pub fn zip_read_central_directory(zip_data:&mut (impl Seek+Read))->Result<ZipCentralDirectory, ZipError>{
-    // lets read the the dirend
-    zip_data.seek(SeekFrom::End(-(END_OF_CENTRAL_DIRECTORY_SIZE as i64))).map_err(|_| ZipError::CantSeekToDirEnd)?;
-    let eocd = EndOfCentralDirectory::from_stream(zip_data)?;
-    zip_data.seek(SeekFrom::Start(eocd.central_directory_offset as u64)).map_err(|_| ZipError::CantSeekToDirStart)?;
+    // the end record sits before an archive comment of up to 64k, so look for it backwards
+    let len = zip_data.seek(SeekFrom::End(0)).map_err(|_| ZipError::CantSeekToDirEnd)?;
+    let search = len.min(END_OF_CENTRAL_DIRECTORY_SIZE as u64 + 0xFFFF);
+    zip_data.seek(SeekFrom::Start(len - search)).map_err(|_| ZipError::CantSeekToDirEnd)?;
+    let tail = read_binary(zip_data, search as usize)?;
+    let signature = END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes();
+    let eocd_pos = (0..tail.len().saturating_sub(END_OF_CENTRAL_DIRECTORY_SIZE - 1)).rev()
+        .find(|i| tail[*i..*i+4] == signature)
+        .ok_or(ZipError::EndOfCentralDirectoryInvalid)?;
+    let eocd = EndOfCentralDirectory::from_stream(&mut Cursor::new(&tail[eocd_pos..]))?;
+    
+    let mut entries = eocd.total_entries_all_disk as u64;
+    let mut central_directory_offset = eocd.central_directory_offset as u64;
+    let locator_pos = (len - search) + eocd_pos as u64;
+    if locator_pos >= 20 && (entries == 0xFFFF || central_directory_offset == 0xFFFF_FFFF || eocd.size_of_the_central_directory == 0xFFFF_FFFF){
+        zip_data.seek(SeekFrom::Start(locator_pos - 20)).map_err(|_| ZipError::CantSeekToDirEnd)?;
+        if read_u32(zip_data)? == ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE{
+            let _disk = read_u32(zip_data)?;
+            let zip64_eocd_offset = read_u64(zip_data)?;
+            zip_data.seek(SeekFrom::Start(zip64_eocd_offset)).map_err(|_| ZipError::CantSeekToDirEnd)?;
+            if read_u32(zip_data)? != ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE{
+                return Err(ZipError::EndOfCentralDirectoryInvalid)
+            }
+            // record size, versions and disk numbers
+            read_binary(zip_data, 8 + 2 + 2 + 4 + 4)?;
+            let _entries_this_disk = read_u64(zip_data)?;
+            entries = read_u64(zip_data)?;
+            let _size = read_u64(zip_data)?;
+            central_directory_offset = read_u64(zip_data)?;
+        }
+    }
+    
+    zip_data.seek(SeekFrom::Start(central_directory_offset)).map_err(|_| ZipError::CantSeekToDirStart)?;
     let mut file_headers = Vec::new();
-    for _ in 0..eocd.total_entries_all_disk as usize{
+    for _ in 0..entries{
         file_headers.push(CentralDirectoryFileHeader::from_stream(zip_data)?);
     }
     Ok(ZipCentralDirectory{
@@ -276,3 +476,507 @@ pub fn zip_read_central_directory(zip_data:&mut (impl Seek+Read))->Result<ZipCen
         file_headers
     })
 }
+
+// seconds since the unix epoch to the dos time and date zip headers use. Dos dates start at 1980
+pub fn zip_dos_date_time(unix_secs:u64)->(u16, u16){
+    let days = (unix_secs / 86400) as i64;
+    let secs = unix_secs % 86400;
+    // civil date from days, after Howard Hinnant
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 {mp + 3} else {mp - 9};
+    let year = yoe + era * 400 + if month <= 2 {1} else {0};
+    if year < 1980{
+        return (0, (1 << 5) | 1)
+    }
+    let time = ((secs / 3600) << 11) as u16 | (((secs % 3600) / 60) << 5) as u16 | ((secs % 60) / 2) as u16;
+    let date = (((year - 1980).min(127) as u16) << 9) | ((month as u16) << 5) | day as u16;
+    (time, date)
+}
+
+#[derive(Clone, Debug)]
+pub struct ZipEntryOptions{
+    pub compression_method: u16,
+    // deflate level 0-10
+    pub level: u8,
+    // stored entries get their data aligned to this in the archive, like zipalign does
+    pub alignment: u16,
+    pub last_mod_file_time: u16,
+    pub last_mod_file_date: u16,
+    pub unix_mode: Option<u32>,
+    // streamed entries need to know up front if they might pass 4GB
+    pub large_file: bool,
+}
+
+impl Default for ZipEntryOptions{
+    fn default()->Self{
+        Self{
+            compression_method: COMPRESS_METHOD_DEFLATED,
+            level: 6,
+            alignment: 1,
+            last_mod_file_time: 0,
+            last_mod_file_date: (1 << 5) | 1,
+            unix_mode: None,
+            large_file: false,
+        }
+    }
+}
+
+impl ZipEntryOptions{
+    pub fn stored()->Self{
+        Self{compression_method: COMPRESS_METHOD_UNCOMPRESSED, ..Self::default()}
+    }
+    
+    pub fn with_alignment(mut self, alignment:u16)->Self{
+        self.alignment = alignment.max(1);
+        self
+    }
+    
+    pub fn with_unix_mode(mut self, mode:u32)->Self{
+        self.unix_mode = Some(mode);
+        self
+    }
+    
+    pub fn with_modified(mut self, unix_secs:u64)->Self{
+        (self.last_mod_file_time, self.last_mod_file_date) = zip_dos_date_time(unix_secs);
+        self
+    }
+    
+    pub fn with_large_file(mut self, large_file:bool)->Self{
+        self.large_file = large_file;
+        self
+    }
+}
+
+const FLAG_DATA_DESCRIPTOR:u16 = 1 << 3;
+const FLAG_UTF8:u16 = 1 << 11;
+const VERSION_DEFAULT:u16 = 20;
+const VERSION_ZIP64:u16 = 45;
+const U32_MAX:u64 = 0xFFFF_FFFF;
+
+struct ZipWriterEntry{
+    file_name: String,
+    flags: u16,
+    compression_method: u16,
+    last_mod_file_time: u16,
+    last_mod_file_date: u16,
+    crc32: u32,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    local_header_offset: u64,
+    unix_mode: Option<u32>,
+    zip64: bool,
+}
+
+struct ZipStreamingEntry{
+    entry: ZipWriterEntry,
+    compressor: Option<Box<CompressorOxide>>,
+}
+
+pub struct ZipWriter<W: Write>{
+    out: W,
+    offset: u64,
+    entries: Vec<ZipWriterEntry>,
+    current: Option<ZipStreamingEntry>,
+    comment: String,
+}
+
+impl<W: Write> ZipWriter<W>{
+    pub fn new(out: W)->Self{
+        Self{
+            out,
+            offset: 0,
+            entries: Vec::new(),
+            current: None,
+            comment: String::new(),
+        }
+    }
+    
+    pub fn set_comment(&mut self, comment:&str){
+        self.comment = comment.to_string();
+    }
+    
+    fn write_all_counted(&mut self, data:&[u8])->Result<(), ZipError>{
+        self.out.write_all(data).map_err(|_| ZipError::DataWriteError)?;
+        self.offset += data.len() as u64;
+        Ok(())
+    }
+    
+    fn new_entry(&self, file_name:&str, options:&ZipEntryOptions, compression_method:u16)->Result<ZipWriterEntry, ZipError>{
+        if file_name.len() > 0xFFFF{
+            return Err(ZipError::NameTooLong)
+        }
+        Ok(ZipWriterEntry{
+            file_name: file_name.to_string(),
+            flags: if file_name.is_ascii() {0} else {FLAG_UTF8},
+            compression_method,
+            last_mod_file_time: options.last_mod_file_time,
+            last_mod_file_date: options.last_mod_file_date,
+            crc32: 0,
+            compressed_size: 0,
+            uncompressed_size: 0,
+            local_header_offset: self.offset,
+            unix_mode: options.unix_mode,
+            zip64: false,
+        })
+    }
+    
+    fn write_local_header(&mut self, entry:&ZipWriterEntry, alignment:u16)->Result<(), ZipError>{
+        let mut extra = Vec::new();
+        if entry.zip64{
+            extra.extend_from_slice(&ZIP64_EXTRA_FIELD_ID.to_le_bytes());
+            extra.extend_from_slice(&16u16.to_le_bytes());
+            extra.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+            extra.extend_from_slice(&entry.compressed_size.to_le_bytes());
+        }
+        if alignment > 1 && entry.compression_method == COMPRESS_METHOD_UNCOMPRESSED{
+            let data_start = self.offset + (LOCAL_FILE_HEADER_SIZE + entry.file_name.len() + extra.len() + 6) as u64;
+            let pad = (alignment as u64 - data_start % alignment as u64) % alignment as u64;
+            extra.extend_from_slice(&ALIGNMENT_EXTRA_FIELD_ID.to_le_bytes());
+            extra.extend_from_slice(&(2 + pad as u16).to_le_bytes());
+            extra.extend_from_slice(&alignment.to_le_bytes());
+            extra.resize(extra.len() + pad as usize, 0);
+        }
+        let (compressed, uncompressed) = if entry.zip64{
+            (U32_MAX as u32, U32_MAX as u32)
+        }
+        else{
+            (entry.compressed_size as u32, entry.uncompressed_size as u32)
+        };
+        let mut header = Vec::with_capacity(LOCAL_FILE_HEADER_SIZE + entry.file_name.len() + extra.len());
+        header.extend_from_slice(&LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+        header.extend_from_slice(&(if entry.zip64 {VERSION_ZIP64} else {VERSION_DEFAULT}).to_le_bytes());
+        header.extend_from_slice(&entry.flags.to_le_bytes());
+        header.extend_from_slice(&entry.compression_method.to_le_bytes());
+        header.extend_from_slice(&entry.last_mod_file_time.to_le_bytes());
+        header.extend_from_slice(&entry.last_mod_file_date.to_le_bytes());
+        header.extend_from_slice(&entry.crc32.to_le_bytes());
+        header.extend_from_slice(&compressed.to_le_bytes());
+        header.extend_from_slice(&uncompressed.to_le_bytes());
+        header.extend_from_slice(&(entry.file_name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        header.extend_from_slice(entry.file_name.as_bytes());
+        header.extend_from_slice(&extra);
+        self.write_all_counted(&header)
+    }
+    
+    // adds an entry whose data is known up front, so its sizes go in the local header
+    pub fn add_entry(&mut self, file_name:&str, data:&[u8], options:&ZipEntryOptions)->Result<(), ZipError>{
+        self.finish_entry()?;
+        let compressed = if options.compression_method == COMPRESS_METHOD_DEFLATED{
+            let compressed = compress_to_vec(data, options.level);
+            // incompressible data goes in as is
+            if compressed.len() < data.len() {Some(compressed)} else {None}
+        }
+        else if options.compression_method == COMPRESS_METHOD_UNCOMPRESSED{
+            None
+        }
+        else{
+            return Err(ZipError::UnsupportedCompressionMethod)
+        };
+        let method = if compressed.is_some() {COMPRESS_METHOD_DEFLATED} else {COMPRESS_METHOD_UNCOMPRESSED};
+        let mut entry = self.new_entry(file_name, options, method)?;
+        let body = compressed.as_deref().unwrap_or(data);
+        entry.crc32 = crc32_update(0, data);
+        entry.uncompressed_size = data.len() as u64;
+        entry.compressed_size = body.len() as u64;
+        entry.zip64 = options.large_file || entry.uncompressed_size >= U32_MAX;
+        self.write_local_header(&entry, options.alignment)?;
+        self.write_all_counted(body)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+    
+    pub fn add_directory(&mut self, name:&str, options:&ZipEntryOptions)->Result<(), ZipError>{
+        let name = if name.ends_with('/') {name.to_string()} else {format!("{}/", name)};
+        let options = ZipEntryOptions{unix_mode: Some(options.unix_mode.unwrap_or(0o755) | 0o040000), ..options.clone()};
+        self.add_entry(&name, &[], &ZipEntryOptions{compression_method: COMPRESS_METHOD_UNCOMPRESSED, ..options})
+    }
+    
+    // starts an entry that is written through the Write impl. Its crc and sizes follow
+    // the data in a data descriptor
+    pub fn start_entry(&mut self, file_name:&str, options:&ZipEntryOptions)->Result<(), ZipError>{
+        self.finish_entry()?;
+        let compressor = match options.compression_method{
+            COMPRESS_METHOD_DEFLATED=>{
+                let flags = create_comp_flags_from_zip_params(options.level as i32, -15, 0);
+                Some(Box::new(CompressorOxide::new(flags)))
+            }
+            COMPRESS_METHOD_UNCOMPRESSED=>None,
+            _=>return Err(ZipError::UnsupportedCompressionMethod)
+        };
+        let mut entry = self.new_entry(file_name, options, options.compression_method)?;
+        entry.flags |= FLAG_DATA_DESCRIPTOR;
+        entry.zip64 = options.large_file;
+        self.write_local_header(&entry, options.alignment)?;
+        self.current = Some(ZipStreamingEntry{entry, compressor});
+        Ok(())
+    }
+    
+    fn write_entry_data(&mut self, data:&[u8], flush:MZFlush)->Result<(), ZipError>{
+        let mut current = self.current.take().ok_or(ZipError::NoEntryStarted)?;
+        current.entry.crc32 = crc32_update(current.entry.crc32, data);
+        current.entry.uncompressed_size += data.len() as u64;
+        let result = if let Some(compressor) = &mut current.compressor{
+            let mut buf = [0u8; 32768];
+            let mut pos = 0;
+            loop{
+                let result = deflate(compressor, &data[pos..], &mut buf, flush);
+                pos += result.bytes_consumed;
+                if let Err(e) = self.write_all_counted(&buf[0..result.bytes_written]){
+                    break Err(e)
+                }
+                current.entry.compressed_size += result.bytes_written as u64;
+                match result.status{
+                    Ok(MZStatus::StreamEnd)=>break Ok(()),
+                    Ok(_) | Err(MZError::Buf)=>{
+                        if pos == data.len() && flush != MZFlush::Finish && result.bytes_written < buf.len(){
+                            break Ok(())
+                        }
+                    }
+                    Err(_)=>break Err(ZipError::CompressionError)
+                }
+            }
+        }
+        else{
+            current.entry.compressed_size += data.len() as u64;
+            self.write_all_counted(data)
+        };
+        let too_large = !current.entry.zip64 && (current.entry.uncompressed_size >= U32_MAX || current.entry.compressed_size >= U32_MAX);
+        self.current = Some(current);
+        result?;
+        if too_large{
+            return Err(ZipError::Zip64Required)
+        }
+        Ok(())
+    }
+    
+    // completes a streamed entry. Called implicitly when the next entry starts
+    pub fn finish_entry(&mut self)->Result<(), ZipError>{
+        if self.current.is_none(){
+            return Ok(())
+        }
+        self.write_entry_data(&[], MZFlush::Finish)?;
+        let current = self.current.take().unwrap();
+        let entry = current.entry;
+        let mut descriptor = Vec::with_capacity(24);
+        descriptor.extend_from_slice(&DATA_DESCRIPTOR_SIGNATURE.to_le_bytes());
+        descriptor.extend_from_slice(&entry.crc32.to_le_bytes());
+        if entry.zip64{
+            descriptor.extend_from_slice(&entry.compressed_size.to_le_bytes());
+            descriptor.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+        }
+        else{
+            descriptor.extend_from_slice(&(entry.compressed_size as u32).to_le_bytes());
+            descriptor.extend_from_slice(&(entry.uncompressed_size as u32).to_le_bytes());
+        }
+        self.write_all_counted(&descriptor)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+    
+    // copies an entry from another archive without recompressing it
+    pub fn copy_entry(&mut self, header:&CentralDirectoryFileHeader, zip_data:&mut (impl Seek+Read), alignment:u16)->Result<(), ZipError>{
+        self.finish_entry()?;
+        zip_data.seek(SeekFrom::Start(header.local_header_offset_u64())).map_err(|_| ZipError::CantSeekToFileHeader)?;
+        LocalFileHeader::from_stream(zip_data)?;
+        let options = ZipEntryOptions{
+            last_mod_file_time: header.last_mod_file_time,
+            last_mod_file_date: header.last_mod_file_date,
+            unix_mode: header.unix_mode(),
+            ..Default::default()
+        };
+        let mut entry = self.new_entry(&header.file_name, &options, header.compression_method)?;
+        entry.flags = header.general_purpose_bit_flag & !FLAG_DATA_DESCRIPTOR;
+        entry.crc32 = header.crc32;
+        entry.compressed_size = header.compressed_size_u64();
+        entry.uncompressed_size = header.uncompressed_size_u64();
+        entry.zip64 = entry.compressed_size >= U32_MAX || entry.uncompressed_size >= U32_MAX;
+        self.write_local_header(&entry, alignment)?;
+        let copied = io::copy(&mut zip_data.take(entry.compressed_size), &mut self.out).map_err(|_| ZipError::DataWriteError)?;
+        if copied != entry.compressed_size{
+            return Err(ZipError::DataReadError)
+        }
+        self.offset += copied;
+        self.entries.push(entry);
+        Ok(())
+    }
+    
+    // writes the central directory and hands back the output
+    pub fn finish(mut self)->Result<W, ZipError>{
+        self.finish_entry()?;
+        let central_directory_offset = self.offset;
+        let entries = std::mem::take(&mut self.entries);
+        for entry in &entries{
+            let mut zip64 = Vec::new();
+            for value in [entry.uncompressed_size, entry.compressed_size, entry.local_header_offset]{
+                if value >= U32_MAX{
+                    zip64.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            let mut extra = Vec::new();
+            if !zip64.is_empty(){
+                extra.extend_from_slice(&ZIP64_EXTRA_FIELD_ID.to_le_bytes());
+                extra.extend_from_slice(&(zip64.len() as u16).to_le_bytes());
+                extra.extend_from_slice(&zip64);
+            }
+            let clamp = |v:u64| v.min(U32_MAX) as u32;
+            let version = if entry.zip64 || !zip64.is_empty() {VERSION_ZIP64} else {VERSION_DEFAULT};
+            let (version_made_by, external_attributes) = match entry.unix_mode{
+                Some(mode)=>((3 << 8) | version, mode << 16),
+                None=>(version, if entry.file_name.ends_with('/') {0x10} else {0}),
+            };
+            let mut header = Vec::with_capacity(CENTRAL_DIR_FILE_HEADER_SIZE + entry.file_name.len() + extra.len());
+            header.extend_from_slice(&CENTRAL_DIR_FILE_HEADER_SIGNATURE.to_le_bytes());
+            header.extend_from_slice(&version_made_by.to_le_bytes());
+            header.extend_from_slice(&version.to_le_bytes());
+            header.extend_from_slice(&entry.flags.to_le_bytes());
+            header.extend_from_slice(&entry.compression_method.to_le_bytes());
+            header.extend_from_slice(&entry.last_mod_file_time.to_le_bytes());
+            header.extend_from_slice(&entry.last_mod_file_date.to_le_bytes());
+            header.extend_from_slice(&entry.crc32.to_le_bytes());
+            header.extend_from_slice(&clamp(entry.compressed_size).to_le_bytes());
+            header.extend_from_slice(&clamp(entry.uncompressed_size).to_le_bytes());
+            header.extend_from_slice(&(entry.file_name.len() as u16).to_le_bytes());
+            header.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+            header.extend_from_slice(&0u16.to_le_bytes());
+            header.extend_from_slice(&0u16.to_le_bytes());
+            header.extend_from_slice(&0u16.to_le_bytes());
+            header.extend_from_slice(&external_attributes.to_le_bytes());
+            header.extend_from_slice(&clamp(entry.local_header_offset).to_le_bytes());
+            header.extend_from_slice(entry.file_name.as_bytes());
+            header.extend_from_slice(&extra);
+            self.write_all_counted(&header)?;
+        }
+        let central_directory_size = self.offset - central_directory_offset;
+        let count = entries.len() as u64;
+        
+        let mut tail = Vec::new();
+        if count >= 0xFFFF || central_directory_offset >= U32_MAX || central_directory_size >= U32_MAX{
+            let zip64_eocd_offset = self.offset;
+            tail.extend_from_slice(&ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+            tail.extend_from_slice(&44u64.to_le_bytes());
+            tail.extend_from_slice(&VERSION_ZIP64.to_le_bytes());
+            tail.extend_from_slice(&VERSION_ZIP64.to_le_bytes());
+            tail.extend_from_slice(&0u32.to_le_bytes());
+            tail.extend_from_slice(&0u32.to_le_bytes());
+            tail.extend_from_slice(&count.to_le_bytes());
+            tail.extend_from_slice(&count.to_le_bytes());
+            tail.extend_from_slice(&central_directory_size.to_le_bytes());
+            tail.extend_from_slice(&central_directory_offset.to_le_bytes());
+            tail.extend_from_slice(&ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE.to_le_bytes());
+            tail.extend_from_slice(&0u32.to_le_bytes());
+            tail.extend_from_slice(&zip64_eocd_offset.to_le_bytes());
+            tail.extend_from_slice(&1u32.to_le_bytes());
+        }
+        let comment = self.comment.as_bytes();
+        let comment = &comment[0..comment.len().min(0xFFFF)];
+        tail.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        tail.extend_from_slice(&0u16.to_le_bytes());
+        tail.extend_from_slice(&0u16.to_le_bytes());
+        tail.extend_from_slice(&(count.min(0xFFFF) as u16).to_le_bytes());
+        tail.extend_from_slice(&(count.min(0xFFFF) as u16).to_le_bytes());
+        tail.extend_from_slice(&(central_directory_size.min(U32_MAX) as u32).to_le_bytes());
+        tail.extend_from_slice(&(central_directory_offset.min(U32_MAX) as u32).to_le_bytes());
+        tail.extend_from_slice(&(comment.len() as u16).to_le_bytes());
+        tail.extend_from_slice(comment);
+        self.write_all_counted(&tail)?;
+        self.out.flush().map_err(|_| ZipError::DataWriteError)?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> Write for ZipWriter<W>{
+    fn write(&mut self, buf:&[u8])->io::Result<usize>{
+        self.write_entry_data(buf, MZFlush::None).map_err(|e| io::Error::other(format!("{:?}", e)))?;
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self)->io::Result<()>{
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    
+    #[test]
+    fn write_and_read_back(){
+        let text: Vec<u8> = (0..200000u32).map(|i| b"makepad zip "[(i % 12) as usize]).collect();
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.set_comment("archive comment");
+        writer.add_entry("text.txt", &text, &ZipEntryOptions::default()).unwrap();
+        writer.add_entry("lib/arm64-v8a/libmakepad.so", &[1, 2, 3, 4, 5], &ZipEntryOptions::stored().with_alignment(4096)).unwrap();
+        writer.add_directory("assets", &ZipEntryOptions::default()).unwrap();
+        writer.start_entry("streamed.bin", &ZipEntryOptions::default().with_large_file(true).with_unix_mode(0o100755)).unwrap();
+        for _ in 0..10{
+            writer.write_all(&text[0..50000]).unwrap();
+        }
+        writer.start_entry("stored_stream.txt", &ZipEntryOptions::stored()).unwrap();
+        writer.write_all(b"hello").unwrap();
+        let mut zip = writer.finish().unwrap();
+        
+        let dir = zip_read_central_directory(&mut zip).unwrap();
+        assert_eq!(dir.file_headers.len(), 5);
+        assert_eq!(dir.find("text.txt").unwrap().extract(&mut zip).unwrap(), text);
+        assert_eq!(dir.find("text.txt").unwrap().compression_method, COMPRESS_METHOD_DEFLATED);
+        
+        let lib = dir.find("lib/arm64-v8a/libmakepad.so").unwrap();
+        zip.seek(SeekFrom::Start(lib.local_header_offset_u64())).unwrap();
+        LocalFileHeader::from_stream(&mut zip).unwrap();
+        assert_eq!(zip.stream_position().unwrap() % 4096, 0);
+        assert_eq!(lib.extract(&mut zip).unwrap(), [1, 2, 3, 4, 5]);
+        
+        assert!(dir.find("assets/").unwrap().is_directory());
+        let streamed = dir.find("streamed.bin").unwrap();
+        assert_eq!(streamed.unix_mode(), Some(0o100755));
+        let mut reader = streamed.reader(&mut zip).unwrap();
+        let mut out = Vec::new();
+        let mut buf = [0u8; 777];
+        loop{
+            let n = reader.read(&mut buf).unwrap();
+            if n == 0{
+                break
+            }
+            out.extend_from_slice(&buf[0..n]);
+        }
+        assert_eq!(out.len(), 500000);
+        assert_eq!(&out[0..50000], &text[0..50000]);
+        assert_eq!(dir.find("stored_stream.txt").unwrap().extract(&mut zip).unwrap(), b"hello");
+        
+        // copying entries raw keeps them intact
+        let mut copy = ZipWriter::new(Cursor::new(Vec::new()));
+        for header in &dir.file_headers{
+            copy.copy_entry(header, &mut zip, 4).unwrap();
+        }
+        let mut copy = copy.finish().unwrap();
+        let copy_dir = zip_read_central_directory(&mut copy).unwrap();
+        assert_eq!(copy_dir.find("text.txt").unwrap().extract(&mut copy).unwrap(), text);
+        assert_eq!(copy_dir.find("streamed.bin").unwrap().extract(&mut copy).unwrap().len(), 500000);
+    }
+    
+    #[test]
+    fn corrupt_crc_is_detected(){
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.add_entry("a", b"some data", &ZipEntryOptions::stored()).unwrap();
+        let mut data = writer.finish().unwrap().into_inner();
+        data[LOCAL_FILE_HEADER_SIZE + 1] ^= 1;
+        let mut zip = Cursor::new(data);
+        let dir = zip_read_central_directory(&mut zip).unwrap();
+        assert!(matches!(dir.file_headers[0].extract(&mut zip), Err(ZipError::DecompressionError)));
+    }
+    
+    #[test]
+    fn dos_dates(){
+        // 2024-02-29 13:45:30 UTC
+        let (time, date) = zip_dos_date_time(1709214330);
+        assert_eq!(date, ((2024 - 1980) << 9) | (2 << 5) | 29);
+        assert_eq!(time, (13 << 11) | (45 << 5) | 15);
+    }
+}
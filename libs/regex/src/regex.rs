@@ -0,0 +1,743 @@
+// A small backtracking regex engine for searching in the editor and in files. It supports
+// the usual syntax: literals, `.`, classes, `\d \w \s \b`, anchors, groups, alternation and
+// greedy or lazy repetition. Matching is memoized per (instruction, position) pair so it runs
+// in linear time in the size of the program times the size of the haystack.
+
+use std::ops::Range;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RegexError {
+    pub message: String,
+    pub offset: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Regex {
+    program: Vec<Inst>,
+    group_count: usize,
+    case_insensitive: bool,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RegexMatch {
+    groups: Vec<Option<Range<usize>>>,
+}
+
+impl RegexMatch {
+    pub fn range(&self) -> Range<usize> {
+        self.groups[0].clone().unwrap()
+    }
+
+    pub fn start(&self) -> usize {
+        self.range().start
+    }
+
+    pub fn end(&self) -> usize {
+        self.range().end
+    }
+
+    pub fn group(&self, index: usize) -> Option<Range<usize>> {
+        self.groups.get(index).cloned().flatten()
+    }
+}
+
+impl Regex {
+    pub fn new(pattern: &str, case_insensitive: bool) -> Result<Self, RegexError> {
+        let mut parser = Parser {
+            chars: pattern.char_indices().collect(),
+            index: 0,
+            len: pattern.len(),
+            group_count: 1,
+        };
+        let node = parser.parse_alternation()?;
+        if parser.index < parser.chars.len() {
+            return Err(parser.error("unmatched )"));
+        }
+        let mut compiler = Compiler {
+            program: Vec::new(),
+        };
+        compiler.program.push(Inst::Save(0));
+        compiler.compile(&node);
+        compiler.program.push(Inst::Save(1));
+        compiler.program.push(Inst::Match);
+        Ok(Self {
+            program: compiler.program,
+            group_count: parser.group_count,
+            case_insensitive,
+        })
+    }
+
+    // a regex that matches text literally
+    pub fn literal(text: &str, case_insensitive: bool) -> Self {
+        Self::new(&escape(text), case_insensitive).unwrap()
+    }
+
+    pub fn group_count(&self) -> usize {
+        self.group_count
+    }
+
+    pub fn find_at(&self, haystack: &str, start: usize) -> Option<RegexMatch> {
+        let mut matcher = Matcher {
+            regex: self,
+            haystack,
+            visited: vec![0u64; (self.program.len() * (haystack.len() + 1)).div_ceil(64)],
+            slots: vec![None; self.group_count * 2],
+        };
+        let mut pos = start;
+        loop {
+            if matcher.run(pos) {
+                let slots = &matcher.slots;
+                return Some(RegexMatch {
+                    groups: (0..self.group_count)
+                        .map(|group| match (slots[group * 2], slots[group * 2 + 1]) {
+                            (Some(start), Some(end)) => Some(start..end),
+                            _ => None,
+                        })
+                        .collect(),
+                });
+            }
+            match haystack[pos..].chars().next() {
+                Some(char) => pos += char.len_utf8(),
+                None => return None,
+            }
+        }
+    }
+
+    // all non overlapping matches, left to right
+    pub fn find_all(&self, haystack: &str) -> Vec<RegexMatch> {
+        let mut matches = Vec::new();
+        let mut pos = 0;
+        while pos <= haystack.len() {
+            let Some(found) = self.find_at(haystack, pos) else {
+                break;
+            };
+            pos = if found.end() > found.start() {
+                found.end()
+            } else {
+                match haystack[found.end()..].chars().next() {
+                    Some(char) => found.end() + char.len_utf8(),
+                    None => haystack.len() + 1,
+                }
+            };
+            matches.push(found);
+        }
+        matches
+    }
+
+    // expands `$1`, `${12}` and `$$` in a replacement string
+    pub fn expand(&self, haystack: &str, found: &RegexMatch, replacement: &str) -> String {
+        let mut out = String::new();
+        let mut chars = replacement.chars().peekable();
+        while let Some(char) = chars.next() {
+            if char != '$' {
+                out.push(char);
+                continue;
+            }
+            let mut digits = String::new();
+            match chars.peek() {
+                Some('$') => {
+                    chars.next();
+                    out.push('$');
+                    continue;
+                }
+                Some('{') => {
+                    chars.next();
+                    while let Some(&char) = chars.peek() {
+                        chars.next();
+                        if char == '}' {
+                            break;
+                        }
+                        digits.push(char);
+                    }
+                }
+                _ => {
+                    while let Some(&char) = chars.peek() {
+                        if !char.is_ascii_digit() {
+                            break;
+                        }
+                        digits.push(char);
+                        chars.next();
+                    }
+                }
+            }
+            match digits.parse::<usize>() {
+                Ok(index) => {
+                    if let Some(range) = found.group(index) {
+                        out.push_str(&haystack[range]);
+                    }
+                }
+                Err(_) => {
+                    out.push('$');
+                    out.push_str(&digits);
+                }
+            }
+        }
+        out
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for char in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(char) {
+            out.push('\\');
+        }
+        out.push(char);
+    }
+    out
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+    },
+}
+
+#[derive(Clone, Debug)]
+struct Class {
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl Class {
+    fn digit() -> Self {
+        Self {
+            ranges: vec![('0', '9')],
+            negated: false,
+        }
+    }
+
+    fn word() -> Self {
+        Self {
+            ranges: vec![('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')],
+            negated: false,
+        }
+    }
+
+    fn space() -> Self {
+        Self {
+            ranges: vec![('\t', '\r'), (' ', ' ')],
+            negated: false,
+        }
+    }
+
+    fn negate(mut self) -> Self {
+        self.negated = !self.negated;
+        self
+    }
+
+    fn matches(&self, char: char, case_insensitive: bool) -> bool {
+        let test = |char: char| self.ranges.iter().any(|&(start, end)| start <= char && char <= end);
+        let found = test(char)
+            || case_insensitive
+                && (char.to_lowercase().any(test) || char.to_uppercase().any(test));
+        found != self.negated
+    }
+}
+
+const MAX_REPEAT: u32 = 1000;
+
+struct Parser {
+    chars: Vec<(usize, char)>,
+    index: usize,
+    len: usize,
+    group_count: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> RegexError {
+        RegexError {
+            message: message.to_string(),
+            offset: self.chars.get(self.index).map_or(self.len, |&(offset, _)| offset),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).map(|&(_, char)| char)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let char = self.peek();
+        self.index += 1;
+        char
+    }
+
+    fn eat(&mut self, char: char) -> bool {
+        if self.peek() == Some(char) {
+            self.index += 1;
+            return true;
+        }
+        false
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node, RegexError> {
+        let mut alternatives = vec![self.parse_concat()?];
+        while self.eat('|') {
+            alternatives.push(self.parse_concat()?);
+        }
+        if alternatives.len() == 1 {
+            return Ok(alternatives.pop().unwrap());
+        }
+        Ok(Node::Alternation(alternatives))
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = Vec::new();
+        while let Some(char) = self.peek() {
+            if char == '|' || char == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_repeat(atom)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn parse_repeat(&mut self, atom: Node) -> Result<Node, RegexError> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.parse_counts()? {
+                Some(counts) => counts,
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        self.index += 1;
+        if matches!(atom, Node::Start | Node::End | Node::WordBoundary(_) | Node::Empty) {
+            return Err(self.error("nothing to repeat"));
+        }
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    // parses {n}, {n,} and {n,m}. Anything else is a literal {
+    fn parse_counts(&mut self) -> Result<Option<(u32, Option<u32>)>, RegexError> {
+        let start = self.index;
+        self.index += 1;
+        let number = |parser: &mut Self| {
+            let mut digits = String::new();
+            while let Some(char) = parser.peek().filter(|char| char.is_ascii_digit()) {
+                digits.push(char);
+                parser.index += 1;
+            }
+            digits.parse::<u32>().ok()
+        };
+        let Some(min) = number(self) else {
+            self.index = start;
+            return Ok(None);
+        };
+        let max = if self.eat(',') { number(self) } else { Some(min) };
+        if self.peek() != Some('}') {
+            self.index = start;
+            return Ok(None);
+        }
+        if min > MAX_REPEAT || max.is_some_and(|max| max > MAX_REPEAT || max < min) {
+            return Err(self.error("invalid repetition count"));
+        }
+        Ok(Some((min, max)))
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, RegexError> {
+        match self.next().unwrap() {
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '(' => {
+                let index = if self.peek() == Some('?') {
+                    self.index += 1;
+                    if !self.eat(':') {
+                        return Err(self.error("unsupported group flag"));
+                    }
+                    None
+                } else {
+                    self.group_count += 1;
+                    Some(self.group_count - 1)
+                };
+                let node = self.parse_alternation()?;
+                if !self.eat(')') {
+                    return Err(self.error("missing )"));
+                }
+                Ok(Node::Group(Box::new(node), index))
+            }
+            '[' => self.parse_class(),
+            '\\' => self.parse_escape(false),
+            '*' | '+' | '?' => {
+                self.index -= 1;
+                Err(self.error("nothing to repeat"))
+            }
+            char => Ok(Node::Char(char)),
+        }
+    }
+
+    fn parse_escape(&mut self, in_class: bool) -> Result<Node, RegexError> {
+        let Some(char) = self.next() else {
+            return Err(self.error("trailing backslash"));
+        };
+        Ok(match char {
+            'd' => Node::Class(Class::digit()),
+            'D' => Node::Class(Class::digit().negate()),
+            'w' => Node::Class(Class::word()),
+            'W' => Node::Class(Class::word().negate()),
+            's' => Node::Class(Class::space()),
+            'S' => Node::Class(Class::space().negate()),
+            'b' if !in_class => Node::WordBoundary(true),
+            'B' if !in_class => Node::WordBoundary(false),
+            'n' => Node::Char('\n'),
+            'r' => Node::Char('\r'),
+            't' => Node::Char('\t'),
+            '0' => Node::Char('\0'),
+            char if char.is_alphanumeric() => {
+                self.index -= 1;
+                return Err(self.error("unknown escape"));
+            }
+            char => Node::Char(char),
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<Node, RegexError> {
+        let mut class = Class {
+            ranges: Vec::new(),
+            negated: self.eat('^'),
+        };
+        let mut first = true;
+        loop {
+            let char = match self.next() {
+                None => return Err(self.error("missing ]")),
+                Some(']') if !first => break,
+                Some(char) => char,
+            };
+            first = false;
+            let start = if char == '\\' {
+                match self.parse_escape(true)? {
+                    Node::Char(char) => char,
+                    Node::Class(sub) => {
+                        if sub.negated {
+                            // \D \W \S inside a class: add the gaps between the ranges
+                            let mut prev = '\0';
+                            for (start, end) in sub.ranges {
+                                if start > prev {
+                                    class.ranges.push((prev, char::from_u32(start as u32 - 1).unwrap_or(prev)));
+                                }
+                                prev = char::from_u32(end as u32 + 1).unwrap_or(end);
+                            }
+                            class.ranges.push((prev, char::MAX));
+                        } else {
+                            class.ranges.extend(sub.ranges);
+                        }
+                        continue;
+                    }
+                    _ => unreachable!(),
+                }
+            } else {
+                char
+            };
+            if self.peek() == Some('-') && self.chars.get(self.index + 1).is_some_and(|&(_, char)| char != ']') {
+                self.index += 1;
+                let end = match self.next().unwrap() {
+                    '\\' => match self.parse_escape(true)? {
+                        Node::Char(char) => char,
+                        _ => return Err(self.error("invalid class range")),
+                    },
+                    char => char,
+                };
+                if end < start {
+                    return Err(self.error("invalid class range"));
+                }
+                class.ranges.push((start, end));
+            } else {
+                class.ranges.push((start, start));
+            }
+        }
+        Ok(Node::Class(class))
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    Split(usize, usize),
+    Jump(usize),
+    Save(usize),
+    Match,
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn compile(&mut self, node: &Node) {
+        match node {
+            Node::Empty => {}
+            Node::Char(char) => self.program.push(Inst::Char(*char)),
+            Node::Any => self.program.push(Inst::Any),
+            Node::Class(class) => self.program.push(Inst::Class(class.clone())),
+            Node::Start => self.program.push(Inst::Start),
+            Node::End => self.program.push(Inst::End),
+            Node::WordBoundary(is_boundary) => self.program.push(Inst::WordBoundary(*is_boundary)),
+            Node::Group(node, index) => {
+                if let Some(index) = index {
+                    self.program.push(Inst::Save(index * 2));
+                }
+                self.compile(node);
+                if let Some(index) = index {
+                    self.program.push(Inst::Save(index * 2 + 1));
+                }
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.compile(node);
+                }
+            }
+            Node::Alternation(nodes) => {
+                let mut jumps = Vec::new();
+                for (index, node) in nodes.iter().enumerate() {
+                    if index + 1 < nodes.len() {
+                        let split = self.program.len();
+                        self.program.push(Inst::Split(split + 1, 0));
+                        self.compile(node);
+                        jumps.push(self.program.len());
+                        self.program.push(Inst::Jump(0));
+                        let next = self.program.len();
+                        self.program[split] = Inst::Split(split + 1, next);
+                    } else {
+                        self.compile(node);
+                    }
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                for _ in 0..*min {
+                    self.compile(node);
+                }
+                match max {
+                    None => {
+                        // loop: split body, out
+                        let split = self.program.len();
+                        self.program.push(Inst::Split(0, 0));
+                        self.compile(node);
+                        self.program.push(Inst::Jump(split));
+                        let out = self.program.len();
+                        self.program[split] = self.split(split + 1, out, *greedy);
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.program.len());
+                            self.program.push(Inst::Split(0, 0));
+                            self.compile(node);
+                        }
+                        let out = self.program.len();
+                        for split in splits {
+                            self.program[split] = self.split(split + 1, out, *greedy);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn split(&self, body: usize, out: usize, greedy: bool) -> Inst {
+        if greedy {
+            Inst::Split(body, out)
+        } else {
+            Inst::Split(out, body)
+        }
+    }
+}
+
+enum Job {
+    Thread(usize, usize),
+    RestoreSlot(usize, Option<usize>),
+}
+
+struct Matcher<'a> {
+    regex: &'a Regex,
+    haystack: &'a str,
+    visited: Vec<u64>,
+    slots: Vec<Option<usize>>,
+}
+
+pub fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+// true if the range isn't directly preceded or followed by a word character
+pub fn is_whole_word(haystack: &str, range: Range<usize>) -> bool {
+    !haystack[..range.start].chars().next_back().is_some_and(is_word_char)
+        && !haystack[range.end..].chars().next().is_some_and(is_word_char)
+}
+
+impl<'a> Matcher<'a> {
+    fn run(&mut self, start: usize) -> bool {
+        for slot in &mut self.slots {
+            *slot = None;
+        }
+        let mut jobs = vec![Job::Thread(0, start)];
+        while let Some(job) = jobs.pop() {
+            let (mut pc, mut pos) = match job {
+                Job::Thread(pc, pos) => (pc, pos),
+                Job::RestoreSlot(slot, value) => {
+                    self.slots[slot] = value;
+                    continue;
+                }
+            };
+            loop {
+                let bit = pc * (self.haystack.len() + 1) + pos;
+                if self.visited[bit / 64] & (1 << (bit % 64)) != 0 {
+                    break;
+                }
+                self.visited[bit / 64] |= 1 << (bit % 64);
+                let next_char = self.haystack[pos..].chars().next();
+                match &self.regex.program[pc] {
+                    Inst::Char(expected) => match next_char {
+                        Some(char) if self.chars_equal(char, *expected) => {
+                            pc += 1;
+                            pos += char.len_utf8();
+                        }
+                        _ => break,
+                    },
+                    Inst::Any => match next_char {
+                        Some(char) if char != '\n' => {
+                            pc += 1;
+                            pos += char.len_utf8();
+                        }
+                        _ => break,
+                    },
+                    Inst::Class(class) => match next_char {
+                        Some(char) if class.matches(char, self.regex.case_insensitive) => {
+                            pc += 1;
+                            pos += char.len_utf8();
+                        }
+                        _ => break,
+                    },
+                    Inst::Start => {
+                        if pos != 0 {
+                            break;
+                        }
+                        pc += 1;
+                    }
+                    Inst::End => {
+                        if pos != self.haystack.len() {
+                            break;
+                        }
+                        pc += 1;
+                    }
+                    Inst::WordBoundary(expected) => {
+                        let before = self.haystack[..pos].chars().next_back().is_some_and(is_word_char);
+                        let after = next_char.is_some_and(is_word_char);
+                        if (before != after) != *expected {
+                            break;
+                        }
+                        pc += 1;
+                    }
+                    Inst::Split(first, second) => {
+                        jobs.push(Job::Thread(*second, pos));
+                        pc = *first;
+                    }
+                    Inst::Jump(target) => pc = *target,
+                    Inst::Save(slot) => {
+                        jobs.push(Job::RestoreSlot(*slot, self.slots[*slot]));
+                        self.slots[*slot] = Some(pos);
+                        pc += 1;
+                    }
+                    Inst::Match => return true,
+                }
+            }
+        }
+        false
+    }
+
+    fn chars_equal(&self, a: char, b: char) -> bool {
+        a == b || self.regex.case_insensitive && a.to_lowercase().eq(b.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(regex: &Regex, haystack: &str) -> Vec<Range<usize>> {
+        regex.find_all(haystack).iter().map(|found| found.range()).collect()
+    }
+
+    #[test]
+    fn matches() {
+        let regex = Regex::new("a{2,3}?b|c+", false).unwrap();
+        assert_eq!(ranges(&regex, "aaab ccc ab"), vec![0..4, 5..8]);
+        let regex = Regex::new(r"^\s*[^a-c\d]x$", false).unwrap();
+        assert_eq!(ranges(&regex, "  zx"), vec![0..4]);
+        assert_eq!(ranges(&regex, "  1x"), vec![]);
+        let regex = Regex::new(r"\bfoo\b", false).unwrap();
+        assert_eq!(ranges(&regex, "foo foobar foo"), vec![0..3, 11..14]);
+        assert_eq!(ranges(&Regex::literal("a.b", true), "axb A.B"), vec![4..7]);
+    }
+
+    #[test]
+    fn no_exponential_blowup() {
+        let regex = Regex::new("(a*)*b", false).unwrap();
+        assert_eq!(regex.find_at(&"a".repeat(5000), 0), None);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(Regex::new("(a", false).is_err());
+        assert!(Regex::new("a)", false).is_err());
+        assert!(Regex::new("*a", false).is_err());
+        assert!(Regex::new("[a", false).is_err());
+    }
+
+    #[test]
+    fn expand() {
+        let regex = Regex::new(r"(\w+)@(\w+)\.com", true).unwrap();
+        let haystack = "mail: Foo@Bar.COM ok";
+        let found = regex.find_at(haystack, 0).unwrap();
+        assert_eq!(found.range(), 6..17);
+        assert_eq!(regex.expand(haystack, &found, "$2/$1 $$ ${1}"), "Bar/Foo $ Foo");
+    }
+
+    #[test]
+    fn whole_word() {
+        assert!(is_whole_word("a foo b", 2..5));
+        assert!(!is_whole_word("a foo_b", 2..5));
+        assert!(!is_whole_word("éfoo", 2..5));
+    }
+}
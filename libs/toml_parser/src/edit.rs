@@ -0,0 +1,137 @@
+// edits a TOML document in place. Only the text of the touched value or line changes,
+// comments and formatting elsewhere are left alone. The result is parsed again before it
+// is handed back so an edit can't produce a broken file.
+
+use crate::toml::*;
+use crate::serialize::toml_key;
+
+fn find_table<'a>(root: &'a TomlTable, path: &[&str]) -> Result<(&'a TomlTable, usize), TomlErr> {
+    let mut table = root;
+    for (depth, key) in path.iter().enumerate() {
+        table = match table.get(key) {
+            Some(Toml::Table(sub)) => sub,
+            Some(value) if value.is_array_of_tables() => value.as_array().unwrap().last().unwrap().as_table().unwrap(),
+            Some(value) => return Err(TomlErr::new(format!("{} is not a table", key), value.span())),
+            None => return Ok((table, depth))
+        };
+    }
+    Ok((table, path.len()))
+}
+
+fn checked(out: String) -> Result<String, TomlErr> {
+    parse_toml_doc(&out)?;
+    Ok(out)
+}
+
+// sets the value at path, adding the key and if needed a new [table] section
+pub fn toml_set(src: &str, path: &[&str], value: &Toml) -> Result<String, TomlErr> {
+    let (key, parents) = path.split_last().ok_or_else( | | TomlErr::new("empty path", TomlSpan::default()))?;
+    let root = parse_toml_doc(src)?;
+    let newline = if src.contains("\r\n") {"\r\n"} else {"\n"};
+    let value_text = value.to_toml_value_string();
+    let line = format!("{} = {}", toml_key(key), value_text);
+    let (table, depth) = find_table(&root, parents)?;
+
+    if depth < parents.len() || table.kind == TomlTableKind::Implicit {
+        let mut out = src.to_string();
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push_str(newline);
+        }
+        if !out.is_empty() {
+            out.push_str(newline);
+        }
+        let header: Vec<String> = parents.iter().map( | k | toml_key(k)).collect();
+        out.push_str(&format!("[{}]{}{}{}", header.join("."), newline, line, newline));
+        return checked(out)
+    }
+
+    if let Some(item) = table.item(key) {
+        if matches!(&item.value, Toml::Table(t) if t.kind != TomlTableKind::Inline) || item.value.is_array_of_tables() {
+            return Err(TomlErr::new(format!("{} is a table section", key), item.key_span))
+        }
+        let span = item.value.span();
+        return checked(format!("{}{}{}", &src[..span.start], value_text, &src[span.end()..]))
+    }
+
+    match table.kind {
+        TomlTableKind::Inline => {
+            let inner_end = src[..table.span.end() - 1].trim_end().len();
+            if table.is_empty() {
+                checked(format!("{}{{ {} }}{}", &src[..table.span.start], line, &src[table.span.end()..]))
+            }
+            else {
+                checked(format!("{}, {}{}", &src[..inner_end], line, &src[inner_end..]))
+            }
+        }
+        TomlTableKind::Dotted => {
+            Err(TomlErr::new("cannot add keys to a table defined by dotted keys", table.span))
+        }
+        _ => {
+            let pos = table.insert_pos;
+            let lead = if pos > 0 && !src[..pos].ends_with('\n') {newline} else {""};
+            checked(format!("{}{}{}{}{}", &src[..pos], lead, line, newline, &src[pos..]))
+        }
+    }
+}
+
+// removes a key/value, taking its whole line along when it is the only thing on it
+pub fn toml_remove(src: &str, path: &[&str]) -> Result<String, TomlErr> {
+    let (key, parents) = path.split_last().ok_or_else( | | TomlErr::new("empty path", TomlSpan::default()))?;
+    let root = parse_toml_doc(src)?;
+    let (table, depth) = find_table(&root, parents)?;
+    let item = match table.item(key) {
+        Some(item) if depth == parents.len() => item,
+        _ => return Ok(src.to_string())
+    };
+    if matches!(&item.value, Toml::Table(t) if t.kind != TomlTableKind::Inline) || item.value.is_array_of_tables() {
+        return Err(TomlErr::new(format!("{} is a table section", key), item.key_span))
+    }
+    let value_end = item.value.span().end();
+    if table.kind == TomlTableKind::Inline {
+        let rest = &src[value_end..];
+        let after = rest.trim_start();
+        if let Some(after_comma) = after.strip_prefix(',') {
+            let cut = value_end + (rest.len() - after_comma.len());
+            let next = cut + (after_comma.len() - after_comma.trim_start().len());
+            return checked(format!("{}{}", &src[..item.key_span.start], &src[next..]))
+        }
+        // the last item, take the comma in front of it
+        let before = src[..item.key_span.start].trim_end();
+        let start = if before.ends_with(',') {before.len() - 1} else {item.key_span.start};
+        return checked(format!("{}{}", &src[..start], &src[value_end..]))
+    }
+    let line_start = src[..item.key_span.start].rfind('\n').map( | p | p + 1).unwrap_or(0);
+    let line_end = src[value_end..].find('\n').map( | p | value_end + p + 1).unwrap_or(src.len());
+    checked(format!("{}{}", &src[..line_start], &src[line_end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_in_place() {
+        let src = "# crate\n[package]\nname = \"demo\" # keep me\nversion = \"0.1.0\"\n\n[dependencies]\nfoo = { path = \"foo\", version = \"0.1.0\" }\n";
+        let out = toml_set(src, &["package", "version"], &Toml::Str("0.2.0".into(), TomlSpan::default())).unwrap();
+        assert_eq!(out, src.replace("version = \"0.1.0\"\n", "version = \"0.2.0\"\n"));
+
+        let out = toml_set(src, &["dependencies", "foo", "version"], &Toml::Str("0.3.0".into(), TomlSpan::default())).unwrap();
+        assert!(out.contains("foo = { path = \"foo\", version = \"0.3.0\" }"));
+
+        let out = toml_set(src, &["dependencies", "foo", "features"], &Toml::Array(vec![Toml::Str("a".into(), TomlSpan::default())], TomlSpan::default())).unwrap();
+        assert!(out.contains("foo = { path = \"foo\", version = \"0.1.0\", features = [\"a\"] }"));
+
+        let out = toml_set(src, &["package", "edition"], &Toml::Str("2021".into(), TomlSpan::default())).unwrap();
+        assert!(out.contains("version = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]"));
+
+        let out = toml_set(src, &["package", "metadata", "makepad-auto-version"], &Toml::Str("x".into(), TomlSpan::default())).unwrap();
+        assert!(out.ends_with("\n\n[package.metadata]\nmakepad-auto-version = \"x\"\n"));
+
+        let out = toml_remove(src, &["package", "name"]).unwrap();
+        assert_eq!(out, src.replace("name = \"demo\" # keep me\n", ""));
+        let out = toml_remove(src, &["dependencies", "foo", "path"]).unwrap();
+        assert!(out.contains("foo = { version = \"0.1.0\" }"));
+        let out = toml_remove(src, &["dependencies", "foo", "version"]).unwrap();
+        assert!(out.contains("foo = { path = \"foo\" }"));
+    }
+}
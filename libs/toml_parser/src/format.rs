@@ -0,0 +1,107 @@
+// normalizes the layout of a TOML document while keeping its comments, key order and the way
+// values are written. Single line arrays and inline tables get uniform spacing, multi-line
+// values are left as they are.
+
+use crate::toml::*;
+
+fn key_text(src: &str, keys: &[TomlSpan]) -> String {
+    let keys: Vec<&str> = keys.iter().map( | span | src[span.start..span.end()].trim()).collect();
+    keys.join(".")
+}
+
+fn format_inline_items(src: &str, prefix: &str, table: &TomlTable, out: &mut Vec<String>) {
+    for item in &table.items {
+        let key = format!("{}{}", prefix, src[item.key_span.start..item.key_span.end()].trim());
+        match &item.value {
+            Toml::Table(sub) if sub.kind == TomlTableKind::Dotted => {
+                format_inline_items(src, &format!("{}.", key), sub, out);
+            }
+            value => out.push(format!("{} = {}", key, format_value(src, value)))
+        }
+    }
+}
+
+fn format_value(src: &str, value: &Toml) -> String {
+    let span = value.span();
+    let raw = &src[span.start..span.end()];
+    match value {
+        Toml::Array(values, _) if !raw.contains('\n') => {
+            let values: Vec<String> = values.iter().map( | v | format_value(src, v)).collect();
+            format!("[{}]", values.join(", "))
+        }
+        Toml::Table(table) if table.kind == TomlTableKind::Inline => {
+            let mut items = Vec::new();
+            format_inline_items(src, "", table, &mut items);
+            if items.is_empty() {
+                "{}".to_string()
+            }
+            else {
+                format!("{{ {} }}", items.join(", "))
+            }
+        }
+        _ => raw.to_string()
+    }
+}
+
+pub fn format_toml(src: &str) -> Result<String, TomlErr> {
+    let (_, lines) = parse_toml_lines(src)?;
+    let newline = if src.contains("\r\n") {"\r\n"} else {"\n"};
+    let mut out: Vec<String> = Vec::new();
+    let mut pending_blank = false;
+    let mut last_was_key_value = false;
+    let with_comment = | line: String, comment: &Option<TomlSpan> | {
+        match comment {
+            Some(span) => format!("{} {}", line, src[span.start..span.end()].trim_end()),
+            None => line
+        }
+    };
+    for line in &lines {
+        let text = match line {
+            TomlLine::Blank => {
+                pending_blank = !out.is_empty();
+                continue
+            }
+            TomlLine::Comment(span) => {
+                last_was_key_value = false;
+                src[span.start..span.end()].trim_end().to_string()
+            }
+            TomlLine::Header {keys, array, comment} => {
+                // a header directly below a key/value line gets separated, comments stay attached
+                if last_was_key_value {
+                    pending_blank = true;
+                }
+                last_was_key_value = false;
+                let keys = key_text(src, keys);
+                with_comment(if *array {format!("[[{}]]", keys)} else {format!("[{}]", keys)}, comment)
+            }
+            TomlLine::KeyValue {keys, value, comment} => {
+                last_was_key_value = true;
+                with_comment(format!("{} = {}", key_text(src, keys), format_value(src, value)), comment)
+            }
+        };
+        if pending_blank {
+            out.push(String::new());
+            pending_blank = false;
+        }
+        out.push(text);
+    }
+    let mut result = out.join(newline);
+    if !result.is_empty() {
+        result.push_str(newline);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_keeps_comments() {
+        let src = "# header comment\n\n\n  name   =  \"x\"   # the name\nlist=[1,2 ,  3]\n[package]\n  table={a=1,b.c=[ 'x' ]}\n\n\n# about deps\n[ dependencies . foo ]\nmulti = [\n  1, # one\n  2,\n]\n\n\n";
+        let out = format_toml(src).unwrap();
+        assert_eq!(out, "# header comment\n\nname = \"x\" # the name\nlist = [1, 2, 3]\n\n[package]\ntable = { a = 1, b.c = ['x'] }\n\n# about deps\n[dependencies.foo]\nmulti = [\n  1, # one\n  2,\n]\n");
+        // formatting is stable
+        assert_eq!(format_toml(&out).unwrap(), out);
+    }
+}
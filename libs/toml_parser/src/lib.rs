@@ -1,2 +1,8 @@
 mod toml;
-pub use crate::toml::*;
\ No newline at end of file
+mod serialize;
+mod format;
+mod edit;
+pub use crate::toml::*;
+pub use crate::serialize::*;
+pub use crate::format::*;
+pub use crate::edit::*;
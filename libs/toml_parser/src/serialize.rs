@@ -0,0 +1,133 @@
+// writes documents back out as TOML. Nested tables become [sections] and arrays of tables
+// [[sections]], inline tables stay inline.
+
+use crate::toml::*;
+
+pub fn toml_key(key: &str) -> String {
+    if !key.is_empty() && key.chars().all(is_bare_key_char) {
+        key.to_string()
+    }
+    else {
+        toml_string(key)
+    }
+}
+
+pub fn toml_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' || c == '\u{7f}' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl Toml {
+    // the value as it appears after the = of a key/value line. Tables are written inline
+    pub fn to_toml_value_string(&self) -> String {
+        match self {
+            Toml::Str(v, _) => toml_string(v),
+            Toml::Bool(v, _) => v.to_string(),
+            Toml::Int(v, _) => v.to_string(),
+            Toml::Float(v, _) => {
+                if v.is_nan() {
+                    "nan".to_string()
+                }
+                else if v.is_infinite() {
+                    if *v > 0.0 {"inf"} else {"-inf"}.to_string()
+                }
+                else {
+                    format!("{:?}", v)
+                }
+            }
+            Toml::Date(v, _) => v.clone(),
+            Toml::Array(values, _) => {
+                let values: Vec<String> = values.iter().map( | v | v.to_toml_value_string()).collect();
+                format!("[{}]", values.join(", "))
+            }
+            Toml::Table(table) => {
+                if table.is_empty() {
+                    return "{}".to_string()
+                }
+                let items: Vec<String> = table.items.iter().map( | item | {
+                    format!("{} = {}", toml_key(&item.key), item.value.to_toml_value_string())
+                }).collect();
+                format!("{{ {} }}", items.join(", "))
+            }
+        }
+    }
+}
+
+impl TomlTable {
+    pub fn to_toml_string(&self) -> String {
+        let mut out = String::new();
+        write_table(&mut out, &mut Vec::new(), self, false);
+        out
+    }
+}
+
+fn is_section(value: &Toml) -> bool {
+    matches!(value, Toml::Table(table) if table.kind != TomlTableKind::Inline) || value.is_array_of_tables()
+}
+
+fn write_table(out: &mut String, path: &mut Vec<String>, table: &TomlTable, array: bool) {
+    let has_values = table.items.iter().any( | item | !is_section(&item.value));
+    let has_sections = table.items.iter().any( | item | is_section(&item.value));
+    // parents that only hold other sections don't need a header of their own
+    if !path.is_empty() && (array || has_values || !has_sections) {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let header = path.join(".");
+        if array {
+            out.push_str(&format!("[[{}]]\n", header));
+        }
+        else {
+            out.push_str(&format!("[{}]\n", header));
+        }
+    }
+    for item in table.items.iter().filter( | item | !is_section(&item.value)) {
+        out.push_str(&format!("{} = {}\n", toml_key(&item.key), item.value.to_toml_value_string()));
+    }
+    for item in table.items.iter().filter( | item | is_section(&item.value)) {
+        path.push(toml_key(&item.key));
+        match &item.value {
+            Toml::Table(sub) => write_table(out, path, sub, false),
+            Toml::Array(values, _) => for value in values {
+                if let Toml::Table(sub) = value {
+                    write_table(out, path, sub, true);
+                }
+            }
+            _ => ()
+        }
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_roundtrip() {
+        let src = "a = \"x\\ty\"\nb.c = 1.0\n[d]\ne = [1, 2]\nf = { g = true }\n[[h]]\ni = 1979-05-27\n[[h]]\n[j.k]\n\"l m\" = -inf\n";
+        let doc = parse_toml_doc(src).unwrap();
+        let out = doc.to_toml_string();
+        assert_eq!(out, "a = \"x\\ty\"\n\n[b]\nc = 1.0\n\n[d]\ne = [1, 2]\nf = { g = true }\n\n[[h]]\ni = 1979-05-27\n\n[[h]]\n\n[j.k]\n\"l m\" = -inf\n");
+        // spans differ, the values don't
+        let values = | src: &str | {
+            let mut flat: Vec<(String, String)> = parse_toml(src).unwrap().into_iter().map( | (k, v) | (k, v.to_toml_value_string())).collect();
+            flat.sort();
+            flat
+        };
+        assert_eq!(values(&out), values(src));
+    }
+}
@@ -1,53 +1,107 @@
-use std::collections::{HashMap};
-use std::str::Chars;
+// a TOML 1.0 parser producing a nested document. Every value, key and table header keeps its
+// byte span in the source so tools can patch Cargo.toml files in place.
 
-#[derive(Default)]
-pub struct TomlParser {
-    pub cur: char,
-    pub pos: usize,
-}
+use std::collections::HashMap;
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
 pub struct TomlSpan {
     pub start: usize,
     pub len: usize
 }
 
-#[derive(PartialEq, Debug)]
-pub struct TomlTokWithSpan {
-    pub span: TomlSpan,
-    pub tok: TomlTok
+impl TomlSpan {
+    pub fn end(&self) -> usize {
+        self.start + self.len
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum TomlTableKind {
+    #[default]
+    Root,
+    // created as a parent of a [a.b] header, can still be defined once with its own header
+    Implicit,
+    Header,
+    ArrayElement,
+    // created by a dotted key like a.b = 1
+    Dotted,
+    Inline,
 }
 
-#[derive(PartialEq, Debug)]
-pub enum TomlTok {
-    Ident(String),
-    Str(String),
-    U64(u64),
-    I64(i64),
-    F64(f64),
-    Bool(bool),
-    Nan(bool),
-    Inf(bool),
-    Date(String),
-    Equals,
-    BlockOpen,
-    BlockClose,
-    ObjectOpen,
-    ObjectClose,
-    Comma,
-    Bof,
-    Eof
+#[derive(PartialEq, Debug, Clone)]
+pub struct TomlItem {
+    pub key: String,
+    pub key_span: TomlSpan,
+    pub value: Toml,
+}
+
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct TomlTable {
+    pub kind: TomlTableKind,
+    pub items: Vec<TomlItem>,
+    // the header for [table] and [[array]] entries, the braces for inline tables
+    // and the key for implicit and dotted tables
+    pub span: TomlSpan,
+    // the end of the last line belonging to this table, where a new key/value line can go
+    pub insert_pos: usize,
 }
 
+impl TomlTable {
+    pub fn item(&self, key: &str) -> Option<&TomlItem> {
+        self.items.iter().find( | item | item.key == key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Toml> {
+        self.item(key).map( | item | &item.value)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Toml> {
+        self.items.iter_mut().find( | item | item.key == key).map( | item | &mut item.value)
+    }
+
+    // follows a path of keys through nested tables. Arrays of tables resolve to their last
+    // entry, the same way a [a.b] header after [[a]] does
+    pub fn get_path(&self, path: &[&str]) -> Option<&Toml> {
+        let (last, parents) = path.split_last()?;
+        let mut table = self;
+        for key in parents {
+            table = table.get(key)?.as_table_or_last()?;
+        }
+        table.get(last)
+    }
+
+    pub fn insert(&mut self, key: &str, value: Toml) {
+        if let Some(old) = self.get_mut(key) {
+            *old = value;
+        }
+        else {
+            self.items.push(TomlItem {key: key.to_string(), key_span: TomlSpan::default(), value});
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.items.iter().map( | item | item.key.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Toml {
     Str(String, TomlSpan),
     Bool(bool, TomlSpan),
-    Num(f64, TomlSpan),
+    Int(i64, TomlSpan),
+    Float(f64, TomlSpan),
+    // dates and times are kept as written
     Date(String, TomlSpan),
-    Array(Vec<Toml>),
+    Array(Vec<Toml>, TomlSpan),
+    Table(TomlTable),
 }
 
 impl Toml {
@@ -57,6 +111,92 @@ impl Toml {
             _ => None
         }
     }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Str(v, _) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(v, _) => Some(*v),
+            _ => None
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Int(v, _) => Some(*v),
+            _ => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(v, _) => Some(*v as f64),
+            Self::Float(v, _) => Some(*v),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Toml]> {
+        match self {
+            Self::Array(v, _) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&TomlTable> {
+        match self {
+            Self::Table(v) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Toml> {
+        self.as_table()?.get(key)
+    }
+
+    pub fn span(&self) -> TomlSpan {
+        match self {
+            Self::Str(_, span) | Self::Bool(_, span) | Self::Int(_, span) | Self::Float(_, span) |
+            Self::Date(_, span) | Self::Array(_, span) => *span,
+            Self::Table(table) => table.span,
+        }
+    }
+
+    // true for arrays built from [[header]] entries, as opposed to array values
+    pub fn is_array_of_tables(&self) -> bool {
+        match self {
+            Self::Array(v, _) => !v.is_empty() && v.iter().all( | v | matches!(v, Toml::Table(t) if t.kind == TomlTableKind::ArrayElement)),
+            _ => false
+        }
+    }
+
+    fn as_table_or_last(&self) -> Option<&TomlTable> {
+        match self {
+            Self::Table(table) => Some(table),
+            Self::Array(v, _) if self.is_array_of_tables() => v.last()?.as_table(),
+            _ => None
+        }
+    }
+
+    fn as_table_or_last_mut(&mut self) -> Option<&mut TomlTable> {
+        if self.is_array_of_tables() {
+            if let Self::Array(v, _) = self {
+                if let Some(Toml::Table(table)) = v.last_mut() {
+                    return Some(table)
+                }
+            }
+            return None
+        }
+        match self {
+            Self::Table(table) => Some(table),
+            _ => None
+        }
+    }
 }
 
 pub struct TomlErr {
@@ -64,334 +204,779 @@ pub struct TomlErr {
     pub span: TomlSpan,
 }
 
+impl TomlErr {
+    pub fn new(msg: impl Into<String>, span: TomlSpan) -> Self {
+        Self {msg: msg.into(), span}
+    }
+
+    // 1 based line and column of the error start
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        let before = &source[..self.span.start.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let col = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        (line, col)
+    }
+}
+
 impl std::fmt::Debug for TomlErr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Toml error: {}, start:{} len:{}", self.msg, self.span.start, self.span.len)
     }
 }
 
-pub fn parse_toml(data: &str) -> Result<HashMap<String, Toml>, TomlErr> {
-    let i = &mut data.chars();
-    let mut t = TomlParser::default();
-    t.next(i);
-    let mut out = HashMap::new();
-    let mut local_scope = String::new();
-    loop {
-        let tok = t.next_tok(i) ?;
-        match tok.tok {
-            TomlTok::Eof => { // at eof.
-                return Ok(out);
-            },
-            TomlTok::BlockOpen => { // its a scope
-                // we should expect an ident or a string
-                let tok = t.next_tok(i) ?;
-                let (tok, double_block) = if let TomlTok::BlockOpen = tok.tok {
-                    (t.next_tok(i) ?, true)
-                }
-                else {(tok, false)};
-                
-                match tok.tok {
-                    TomlTok::Str(key) => { // a key
-                        local_scope = key;
-                    },
-                    TomlTok::Ident(key) => { // also a key
-                        local_scope = key;
-                    },
-                    _ => return Err(t.err_token(tok))
-                }
-                let tok = t.next_tok(i) ?;
-                
-                if tok.tok != TomlTok::BlockClose {
-                    return Err(t.err_token(tok))
-                }
-                if double_block {
-                    let tok = t.next_tok(i) ?;
-                    if tok.tok != TomlTok::BlockClose {
-                        return Err(t.err_token(tok))
-                    }
-                }
-            },
-            TomlTok::Str(key) => { // a key
-                t.parse_key_value(&local_scope, key, i, &mut out) ?;
-            },
-            TomlTok::Ident(key) => { // also a key
-                t.parse_key_value(&local_scope, key, i, &mut out) ?;
-            },
-            _ => return Err(t.err_token(tok))
-        }
-    }
+// the statements of a document in order, used by the formatter to reproduce the layout
+#[derive(Debug, Clone)]
+pub(crate) enum TomlLine {
+    Blank,
+    Comment(TomlSpan),
+    Header {keys: Vec<TomlSpan>, array: bool, comment: Option<TomlSpan>},
+    KeyValue {keys: Vec<TomlSpan>, value: Toml, comment: Option<TomlSpan>},
 }
 
-impl TomlParser {
-    pub fn to_val(&mut self, tok: TomlTokWithSpan, i: &mut Chars) -> Result<Toml, TomlErr> {
-        match tok.tok {
-            TomlTok::BlockOpen => {
-                let mut vals = Vec::new();
-                loop {
-                    let tok = self.next_tok(i) ?;
-                    match tok.tok {
-                        TomlTok::BlockClose | TomlTok::Eof => {
-                            break;
-                        }
-                        TomlTok::Comma => {}
-                        _ => {
-                            vals.push(self.to_val(tok, i) ?);
-                        }
-                    }
-                }
-                Ok(Toml::Array(vals))
-            },
-            TomlTok::Str(v) => Ok(Toml::Str(v, tok.span)),
-            TomlTok::U64(v) => Ok(Toml::Num(v as f64, tok.span)),
-            TomlTok::I64(v) => Ok(Toml::Num(v as f64, tok.span)),
-            TomlTok::F64(v) => Ok(Toml::Num(v, tok.span)),
-            TomlTok::Bool(v) => Ok(Toml::Bool(v, tok.span)),
-            TomlTok::Nan(v) => Ok(Toml::Num(if v {-std::f64::NAN}else {std::f64::NAN}, tok.span)),
-            TomlTok::Inf(v) => Ok(Toml::Num(if v {-std::f64::INFINITY}else {std::f64::INFINITY}, tok.span)),
-            TomlTok::Date(v) => Ok(Toml::Date(v, tok.span)),
-            _ => Err(self.err_token(tok))
-        }
-    }
-    
-    pub fn parse_key_value(&mut self, local_scope: &str, key: String, i: &mut Chars, out: &mut HashMap<String, Toml>) -> Result<(), TomlErr> {
-        let tok = self.next_tok(i) ?;
-        if tok.tok != TomlTok::Equals {
-            return Err(self.err_token(tok));
-        }
-        let tok = self.next_tok(i) ?;
-        // if we are an ObjectOpen we do a subscope
-        if let TomlTok::ObjectOpen = tok.tok {
-            let local_scope = if !local_scope.is_empty() {
-                format!("{}.{}", local_scope, key)
+pub fn parse_toml_doc(data: &str) -> Result<TomlTable, TomlErr> {
+    TomlParser::new(data).parse().map( | (root, _) | root)
+}
+
+pub(crate) fn parse_toml_lines(data: &str) -> Result<(TomlTable, Vec<TomlLine>), TomlErr> {
+    TomlParser::new(data).parse()
+}
+
+// the flat view of a document: every value under its dotted path, like "package.version".
+// Keys that aren't bare are single quoted as in "target.'cfg(windows)'.dependencies"
+// and entries of arrays of tables get their index as a path segment
+pub fn parse_toml(data: &str) -> Result<HashMap<String, Toml>, TomlErr> {
+    fn flatten(prefix: &str, table: &TomlTable, out: &mut HashMap<String, Toml>) {
+        for item in &table.items {
+            let key = if item.key.chars().all(is_bare_key_char) && !item.key.is_empty() {
+                item.key.clone()
             }
             else {
-                key
+                format!("'{}'", item.key)
             };
-            loop {
-                let tok = self.next_tok(i) ?;
-                match tok.tok {
-                    TomlTok::ObjectClose | TomlTok::Eof => {
-                        break;
+            let path = if prefix.is_empty() {key} else {format!("{}.{}", prefix, key)};
+            match &item.value {
+                Toml::Table(table) => flatten(&path, table, out),
+                Toml::Array(values, _) if item.value.is_array_of_tables() => {
+                    for (index, value) in values.iter().enumerate() {
+                        if let Toml::Table(table) = value {
+                            flatten(&format!("{}.{}", path, index), table, out);
+                        }
                     }
-                    TomlTok::Comma => {}
-                    TomlTok::Str(key) => { // a key
-                        self.parse_key_value(&local_scope, key, i, out) ?;
-                    },
-                    TomlTok::Ident(key) => { // also a key
-                        self.parse_key_value(&local_scope, key, i, out) ?;
-                    },
-                    _ => return Err(self.err_token(tok))
+                }
+                value => {
+                    out.insert(path, value.clone());
                 }
             }
         }
-        else {
-            let val = self.to_val(tok, i) ?;
-            let key = if !local_scope.is_empty() {
-                format!("{}.{}", local_scope, key)
-            }
-            else {
-                key
-            };
-            out.insert(key, val);
+    }
+    let root = parse_toml_doc(data)?;
+    let mut out = HashMap::new();
+    flatten("", &root, &mut out);
+    Ok(out)
+}
+
+pub(crate) fn is_bare_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+type TomlKeyPath = Vec<(String, TomlSpan)>;
+
+struct TomlParser<'a> {
+    src: &'a str,
+    pos: usize,
+    root: TomlTable,
+    current: Vec<String>,
+    lines: Vec<TomlLine>,
+}
+
+impl<'a> TomlParser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            root: TomlTable::default(),
+            current: Vec::new(),
+            lines: Vec::new(),
         }
-        Ok(())
     }
-    
-    pub fn next(&mut self, i: &mut Chars) {
-        if let Some(c) = i.next() {
-            self.cur = c;
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.src.as_bytes().get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, what: &str) -> bool {
+        self.src[self.pos..].starts_with(what)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn err(&self, msg: &str) -> TomlErr {
+        TomlErr::new(msg, TomlSpan {start: self.pos, len: 0})
+    }
+
+    fn err_from(&self, msg: &str, start: usize) -> TomlErr {
+        TomlErr::new(msg, TomlSpan {start, len: self.pos - start})
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek_at(0), Some(b' ') | Some(b'\t')) {
             self.pos += 1;
         }
+    }
+
+    fn eat_newline(&mut self) -> bool {
+        if self.starts_with("\n") {
+            self.pos += 1;
+            true
+        }
+        else if self.starts_with("\r\n") {
+            self.pos += 2;
+            true
+        }
         else {
-            self.cur = '\0';
+            false
         }
     }
-    
-    pub fn err_token(&self, tok: TomlTokWithSpan) -> TomlErr {
-        TomlErr {msg: format!("Unexpected token {:?} ", tok), span: tok.span}
+
+    fn parse_comment(&mut self) -> Result<TomlSpan, TomlErr> {
+        let start = self.pos;
+        self.pos += 1;
+        while let Some(c) = self.peek() {
+            if c == '\n' || c == '\r' && self.peek_at(1) == Some(b'\n') {
+                break
+            }
+            if is_control(c) {
+                return Err(self.err("control character in comment"))
+            }
+            self.bump();
+        }
+        Ok(TomlSpan {start, len: self.pos - start})
     }
-    
-    pub fn err_parse(&self, what: &str) -> TomlErr {
-        TomlErr {msg: format!("Cannot parse toml {} ", what), span: TomlSpan {start: self.pos, len: 0}}
+
+    // whitespace, an optional comment, then a newline or the end of the document
+    fn parse_line_end(&mut self) -> Result<Option<TomlSpan>, TomlErr> {
+        self.skip_ws();
+        let comment = if self.peek() == Some('#') {Some(self.parse_comment()?)} else {None};
+        if !self.eat_newline() && self.peek().is_some() {
+            return Err(self.err("expected a newline"))
+        }
+        Ok(comment)
     }
-    
-    pub fn next_tok(&mut self, i: &mut Chars) -> Result<TomlTokWithSpan, TomlErr> {
-        
-        while self.cur == '\n' || self.cur == '\r' || self.cur == '\t' || self.cur == ' ' || self.cur == '#' {
-            if self.cur == '#' {
-                while self.cur != '\n' && self.cur != '\0' {
-                    self.next(i);
+
+    // skips whitespace, newlines and comments inside arrays
+    fn skip_ws_comments_newlines(&mut self) -> Result<(), TomlErr> {
+        loop {
+            self.skip_ws();
+            if self.peek() == Some('#') {
+                self.parse_comment()?;
+            }
+            else if !self.eat_newline() {
+                return Ok(())
+            }
+        }
+    }
+
+    fn parse(mut self) -> Result<(TomlTable, Vec<TomlLine>), TomlErr> {
+        loop {
+            self.skip_ws();
+            match self.peek() {
+                None => break,
+                Some('#') => {
+                    let span = self.parse_comment()?;
+                    self.parse_line_end()?;
+                    self.lines.push(TomlLine::Comment(span));
+                }
+                Some('[') => self.parse_header()?,
+                Some(_) if self.eat_newline() => self.lines.push(TomlLine::Blank),
+                Some(_) => {
+                    let keys = self.parse_key()?;
+                    self.skip_ws();
+                    if self.bump() != Some('=') {
+                        return Err(self.err("expected = after key"))
+                    }
+                    self.skip_ws();
+                    let value = self.parse_value()?;
+                    let comment = self.parse_line_end()?;
+                    let line_value = value.clone();
+                    let pos = self.pos;
+                    let table = table_at_mut(&mut self.root, &self.current);
+                    insert_dotted(table, &keys, value)?;
+                    table.insert_pos = pos;
+                    self.lines.push(TomlLine::KeyValue {keys: keys.into_iter().map( | (_, span) | span).collect(), value: line_value, comment});
                 }
-                continue
             }
-            self.next(i);
         }
+        Ok((self.root, self.lines))
+    }
+
+    fn parse_header(&mut self) -> Result<(), TomlErr> {
         let start = self.pos;
-        match self.cur {
-            '\0' => {
-                Ok(TomlTokWithSpan {tok: TomlTok::Eof, span: TomlSpan {start, len: 0}})
+        self.pos += 1;
+        let array = self.starts_with("[");
+        if array {
+            self.pos += 1;
+        }
+        self.skip_ws();
+        let keys = self.parse_key()?;
+        self.skip_ws();
+        let close = if array {"]]"} else {"]"};
+        if !self.starts_with(close) {
+            return Err(self.err_from("expected ] to close the table header", start))
+        }
+        self.pos += close.len();
+        let span = TomlSpan {start, len: self.pos - start};
+        let comment = self.parse_line_end()?;
+        open_table(&mut self.root, &keys, span, array, self.pos)?;
+        self.current = keys.iter().map( | (key, _) | key.clone()).collect();
+        self.lines.push(TomlLine::Header {keys: keys.into_iter().map( | (_, span) | span).collect(), array, comment});
+        Ok(())
+    }
+
+    fn parse_key(&mut self) -> Result<TomlKeyPath, TomlErr> {
+        let mut keys = Vec::new();
+        loop {
+            self.skip_ws();
+            let start = self.pos;
+            let key = match self.peek() {
+                Some('"') => {
+                    if self.starts_with("\"\"\"") {
+                        return Err(self.err("multi-line strings can't be keys"))
+                    }
+                    self.parse_basic_string()?
+                }
+                Some('\'') => {
+                    if self.starts_with("'''") {
+                        return Err(self.err("multi-line strings can't be keys"))
+                    }
+                    self.parse_literal_string()?
+                }
+                Some(c) if is_bare_key_char(c) => {
+                    while self.peek().is_some_and(is_bare_key_char) {
+                        self.pos += 1;
+                    }
+                    self.src[start..self.pos].to_string()
+                }
+                _ => return Err(self.err("expected a key"))
+            };
+            keys.push((key, TomlSpan {start, len: self.pos - start}));
+            self.skip_ws();
+            if self.peek() != Some('.') {
+                return Ok(keys)
             }
-            ',' => {
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::Comma, span: TomlSpan {start, len: 1}})
+            self.pos += 1;
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Toml, TomlErr> {
+        let start = self.pos;
+        let span = | p: &Self | TomlSpan {start, len: p.pos - start};
+        match self.peek() {
+            Some('"') => {
+                let value = if self.starts_with("\"\"\"") {self.parse_multiline_string('"')?} else {self.parse_basic_string()?};
+                Ok(Toml::Str(value, span(self)))
             }
-            '[' => {
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::BlockOpen, span: TomlSpan {start, len: 1}})
+            Some('\'') => {
+                let value = if self.starts_with("'''") {self.parse_multiline_string('\'')?} else {self.parse_literal_string()?};
+                Ok(Toml::Str(value, span(self)))
             }
-            ']' => {
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::BlockClose, span: TomlSpan {start, len: 1}})
+            Some('[') => self.parse_array(),
+            Some('{') => self.parse_inline_table(),
+            Some(c) if c.is_ascii_alphanumeric() || c == '+' || c == '-' => {
+                while self.peek().is_some_and( | c | c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-' | '.' | ':')) {
+                    self.pos += 1;
+                }
+                // a date followed by a space and a time is one value
+                if is_date(&self.src[start..self.pos]) && self.peek_at(0) == Some(b' ') && self.peek_at(1).is_some_and( | b | b.is_ascii_digit()) {
+                    self.pos += 1;
+                    while self.peek().is_some_and( | c | c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.' | ':')) {
+                        self.pos += 1;
+                    }
+                }
+                let text = &self.src[start..self.pos];
+                match text {
+                    "true" => return Ok(Toml::Bool(true, span(self))),
+                    "false" => return Ok(Toml::Bool(false, span(self))),
+                    _ => ()
+                }
+                if is_datetime(text) {
+                    return Ok(Toml::Date(text.to_string(), span(self)))
+                }
+                if let Some(value) = parse_integer(text) {
+                    return Ok(Toml::Int(value, span(self)))
+                }
+                if let Some(value) = parse_float(text) {
+                    return Ok(Toml::Float(value, span(self)))
+                }
+                Err(self.err_from("invalid value", start))
             }
-            '{' => {
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::ObjectOpen, span: TomlSpan {start, len: 1}})
+            _ => Err(self.err("expected a value"))
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Toml, TomlErr> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut values = Vec::new();
+        loop {
+            self.skip_ws_comments_newlines()?;
+            if self.peek() == Some(']') {
+                break
+            }
+            values.push(self.parse_value()?);
+            self.skip_ws_comments_newlines()?;
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => break,
+                _ => return Err(self.err("expected , or ] in array"))
             }
-            '}' => {
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::ObjectClose, span: TomlSpan {start, len: 1}})
+        }
+        self.pos += 1;
+        Ok(Toml::Array(values, TomlSpan {start, len: self.pos - start}))
+    }
+
+    fn parse_inline_table(&mut self) -> Result<Toml, TomlErr> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut table = TomlTable {kind: TomlTableKind::Inline, ..Default::default()};
+        self.skip_ws();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+        }
+        else {
+            loop {
+                let keys = self.parse_key()?;
+                self.skip_ws();
+                if self.bump() != Some('=') {
+                    return Err(self.err("expected = after key"))
+                }
+                self.skip_ws();
+                let value = self.parse_value()?;
+                insert_dotted(&mut table, &keys, value)?;
+                self.skip_ws();
+                match self.bump() {
+                    Some(',') => (),
+                    Some('}') => break,
+                    _ => return Err(self.err("expected , or } in inline table"))
+                }
             }
-            '=' => {
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::Equals, span: TomlSpan {start, len: 1}})
+        }
+        table.span = TomlSpan {start, len: self.pos - start};
+        Ok(Toml::Table(table))
+    }
+
+    fn parse_escape(&mut self, out: &mut String) -> Result<(), TomlErr> {
+        let start = self.pos;
+        self.pos += 1;
+        let c = match self.bump() {
+            Some('b') => '\u{8}',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('f') => '\u{c}',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some(c @ ('u' | 'U')) => {
+                let len = if c == 'u' {4} else {8};
+                let hex = self.src.get(self.pos..self.pos + len).ok_or_else( | | self.err("unterminated unicode escape"))?;
+                if !hex.bytes().all( | b | b.is_ascii_hexdigit()) {
+                    return Err(self.err_from("invalid unicode escape", start))
+                }
+                self.pos += len;
+                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32).ok_or_else( | | self.err_from("invalid unicode scalar", start))?
             }
-            '+' | '-' | '0'..='9' => {
-                let mut num = String::new();
-                let is_neg = if self.cur == '-' {
-                    num.push(self.cur);
-                    self.next(i);
-                    true
+            _ => return Err(self.err_from("invalid escape", start))
+        };
+        out.push(c);
+        Ok(())
+    }
+
+    fn parse_basic_string(&mut self) -> Result<String, TomlErr> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(out)
                 }
-                else {
-                    if self.cur == '+' {
-                        self.next(i);
-                    }
-                    false
-                };
-                if self.cur == 'n' {
-                    self.next(i);
-                    if self.cur == 'a' {
-                        self.next(i);
-                        if self.cur == 'n' {
-                            self.next(i);
-                            return Ok(TomlTokWithSpan {tok: TomlTok::Nan(is_neg), span: TomlSpan {start, len: self.pos - start}})
-                        }
-                        else {
-                            return Err(self.err_parse("nan"))
-                        }
-                    }
-                    else {
-                        return Err(self.err_parse("nan"))
-                    }
+                Some('\\') => self.parse_escape(&mut out)?,
+                Some(c) if c == '\n' || c == '\r' || is_control(c) => return Err(self.err("unexpected character in string")),
+                Some(c) => {
+                    out.push(c);
+                    self.pos += c.len_utf8();
                 }
-                if self.cur == 'i' {
-                    self.next(i);
-                    if self.cur == 'n' {
-                        self.next(i);
-                        if self.cur == 'f' {
-                            self.next(i);
-                            return Ok(TomlTokWithSpan {tok: TomlTok::Inf(is_neg), span: TomlSpan {start, len: self.pos - start}})
-                        }
-                        else {
-                            return Err(self.err_parse("inf"))
-                        }
-                    }
-                    else {
-                        return Err(self.err_parse("nan"))
-                    }
+                None => return Err(self.err_from("unterminated string", start))
+            }
+        }
+    }
+
+    fn parse_literal_string(&mut self) -> Result<String, TomlErr> {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                Some('\'') => {
+                    self.pos += 1;
+                    return Ok(self.src[start + 1..self.pos - 1].to_string())
                 }
-                while self.cur >= '0' && self.cur <= '9' || self.cur == '_' {
-                    if self.cur != '_' {
-                        num.push(self.cur);
+                Some(c) if c == '\n' || c == '\r' || is_control(c) => return Err(self.err("unexpected character in string")),
+                Some(c) => self.pos += c.len_utf8(),
+                None => return Err(self.err_from("unterminated string", start))
+            }
+        }
+    }
+
+    // """ and ''' strings. A newline right after the opening quotes is dropped and
+    // up to two quotes may directly precede the closing ones
+    fn parse_multiline_string(&mut self, quote: char) -> Result<String, TomlErr> {
+        let start = self.pos;
+        self.pos += 3;
+        self.eat_newline();
+        let mut out = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if c == quote => {
+                    let mut count = 0;
+                    while self.peek() == Some(quote) {
+                        count += 1;
+                        self.pos += 1;
                     }
-                    self.next(i);
-                }
-                if self.cur == '.' {
-                    num.push(self.cur);
-                    self.next(i);
-                    while self.cur >= '0' && self.cur <= '9' || self.cur == '_' {
-                        if self.cur != '_' {
-                            num.push(self.cur);
+                    if count >= 3 {
+                        if count > 5 {
+                            return Err(self.err("too many quotes in multi-line string"))
                         }
-                        self.next(i);
-                    }
-                    if let Ok(num) = num.parse() {
-                        Ok(TomlTokWithSpan {tok: TomlTok::F64(num), span: TomlSpan {start, len: self.pos - start}})
+                        for _ in 3..count {
+                            out.push(quote);
+                        }
+                        return Ok(out)
                     }
-                    else {
-                        Err(self.err_parse("number"))
+                    for _ in 0..count {
+                        out.push(quote);
                     }
                 }
-                else if self.cur == '-' { // lets assume its a date. whatever. i don't feel like more parsing today
-                    num.push(self.cur);
-                    self.next(i);
-                    while self.cur >= '0' && self.cur <= '9' || self.cur == ':' || self.cur == '-' || self.cur == 'T' {
-                        num.push(self.cur);
-                        self.next(i);
+                Some('\\') if quote == '"' => {
+                    // a backslash at the end of a line trims all whitespace up to the next text
+                    let mut ahead = self.pos + 1;
+                    let bytes = self.src.as_bytes();
+                    while ahead < bytes.len() && (bytes[ahead] == b' ' || bytes[ahead] == b'\t') {
+                        ahead += 1;
                     }
-                    return Ok(TomlTokWithSpan {tok: TomlTok::Date(num), span: TomlSpan {start, len: self.pos - start}})
-                }
-                else {
-                    if is_neg {
-                        if let Ok(num) = num.parse() {
-                            return Ok(TomlTokWithSpan {tok: TomlTok::I64(num), span: TomlSpan {start, len: self.pos - start}})
+                    if bytes.get(ahead) == Some(&b'\n') || bytes.get(ahead) == Some(&b'\r') && bytes.get(ahead + 1) == Some(&b'\n') {
+                        self.pos = ahead;
+                        while matches!(self.peek(), Some(' ' | '\t')) || self.eat_newline() {
+                            if matches!(self.peek(), Some(' ' | '\t')) {
+                                self.pos += 1;
+                            }
                         }
-                        else {
-                            return Err(self.err_parse("number"));
-                        }
-                    }
-                    if let Ok(num) = num.parse() {
-                        return Ok(TomlTokWithSpan {tok: TomlTok::U64(num), span: TomlSpan {start, len: self.pos - start}})
                     }
                     else {
-                        return Err(self.err_parse("number"));
+                        self.parse_escape(&mut out)?;
                     }
                 }
-            },
-            'a'..='z' | 'A'..='Z' | '_' => {
-                let mut ident = String::new();
-                while self.cur >= 'a' && self.cur <= 'z'
-                    || self.cur >= 'A' && self.cur <= 'Z'
-                    || self.cur >= '0' && self.cur <= '9'
-                    || self.cur == '_' || self.cur == '-' || self.cur == '.'
-                    || self.cur == '\'' || self.cur == '(' || self.cur == ')' {
-                    ident.push(self.cur);
-                    self.next(i);
-                }
-                if ident == "true" {
-                    return Ok(TomlTokWithSpan {tok: TomlTok::Bool(true), span: TomlSpan {start, len: self.pos - start}})
-                }
-                if ident == "false" {
-                    return Ok(TomlTokWithSpan {tok: TomlTok::Bool(false), span: TomlSpan {start, len: self.pos - start}})
-                }
-                if ident == "inf" {
-                    return Ok(TomlTokWithSpan {tok: TomlTok::Inf(false), span: TomlSpan {start, len: self.pos - start}})
+                Some('\r') if self.peek_at(1) == Some(b'\n') => {
+                    out.push('\n');
+                    self.pos += 2;
                 }
-                if ident == "nan" {
-                    return Ok(TomlTokWithSpan {tok: TomlTok::Nan(false), span: TomlSpan {start, len: self.pos - start}})
+                Some(c) if c != '\n' && is_control(c) || c == '\r' => return Err(self.err("unexpected character in string")),
+                Some(c) => {
+                    out.push(c);
+                    self.pos += c.len_utf8();
                 }
-                Ok(TomlTokWithSpan {tok: TomlTok::Ident(ident), span: TomlSpan {start, len: self.pos - start}})
-            },
-            
-            '"' => {
-                let mut val = String::new();
-                self.next(i);
-                while self.cur != '"' {
-                    if self.cur == '\\' {
-                        self.next(i);
-                    }
-                    if self.cur == '\0' {
-                        return Err(self.err_parse("string"));
-                    }
-                    val.push(self.cur);
-                    self.next(i);
-                }
-                self.next(i);
-                Ok(TomlTokWithSpan {tok: TomlTok::Str(val), span: TomlSpan {start, len: self.pos - start}})
-            },
-            _ => {
-                Err(self.err_parse("tokenizer"))
+                None => return Err(self.err_from("unterminated string", start))
             }
         }
     }
-}
\ No newline at end of file
+}
+
+fn is_control(c: char) -> bool {
+    (c < ' ' && c != '\t') || c == '\u{7f}'
+}
+
+fn table_at_mut<'t>(mut table: &'t mut TomlTable, path: &[String]) -> &'t mut TomlTable {
+    for key in path {
+        // the header that set this path already made sure every step is a table
+        table = table.get_mut(key).and_then( | v | v.as_table_or_last_mut()).unwrap();
+    }
+    table
+}
+
+fn insert_dotted(mut table: &mut TomlTable, keys: &[(String, TomlSpan)], value: Toml) -> Result<(), TomlErr> {
+    let ((last, last_span), parents) = keys.split_last().unwrap();
+    for (key, span) in parents {
+        if table.get(key).is_none() {
+            table.items.push(TomlItem {
+                key: key.clone(),
+                key_span: *span,
+                value: Toml::Table(TomlTable {kind: TomlTableKind::Dotted, span: *span, ..Default::default()})
+            });
+        }
+        table = match table.get_mut(key) {
+            Some(Toml::Table(sub)) if sub.kind == TomlTableKind::Dotted => sub,
+            _ => return Err(TomlErr::new(format!("cannot add dotted keys to {}", key), *span))
+        };
+    }
+    if table.get(last).is_some() {
+        return Err(TomlErr::new(format!("duplicate key {}", last), *last_span))
+    }
+    table.items.push(TomlItem {key: last.clone(), key_span: *last_span, value});
+    Ok(())
+}
+
+fn open_table(mut table: &mut TomlTable, keys: &[(String, TomlSpan)], span: TomlSpan, array: bool, insert_pos: usize) -> Result<(), TomlErr> {
+    let ((last, last_span), parents) = keys.split_last().unwrap();
+    for (key, key_span) in parents {
+        if table.get(key).is_none() {
+            table.items.push(TomlItem {
+                key: key.clone(),
+                key_span: *key_span,
+                value: Toml::Table(TomlTable {kind: TomlTableKind::Implicit, span: *key_span, ..Default::default()})
+            });
+        }
+        table = match table.get_mut(key).and_then( | v | v.as_table_or_last_mut()) {
+            Some(sub) if sub.kind != TomlTableKind::Inline => sub,
+            _ => return Err(TomlErr::new(format!("{} is not a table", key), *key_span))
+        };
+    }
+    let new_table = TomlTable {
+        kind: if array {TomlTableKind::ArrayElement} else {TomlTableKind::Header},
+        items: Vec::new(),
+        span,
+        insert_pos
+    };
+    match table.get_mut(last) {
+        None => {
+            let value = if array {Toml::Array(vec![Toml::Table(new_table)], span)} else {Toml::Table(new_table)};
+            table.items.push(TomlItem {key: last.clone(), key_span: *last_span, value});
+        }
+        Some(existing) if array && existing.is_array_of_tables() => {
+            if let Toml::Array(values, _) = existing {
+                values.push(Toml::Table(new_table));
+            }
+        }
+        Some(Toml::Table(existing)) if !array && existing.kind == TomlTableKind::Implicit => {
+            existing.kind = TomlTableKind::Header;
+            existing.span = span;
+            existing.insert_pos = insert_pos;
+        }
+        Some(_) => return Err(TomlErr::new(format!("{} is defined twice", last), span))
+    }
+    Ok(())
+}
+
+fn digits_ok(digits: &str, radix: u32) -> bool {
+    // underscores only between digits
+    !digits.is_empty() && !digits.starts_with('_') && !digits.ends_with('_') && !digits.contains("__")
+        && digits.chars().all( | c | c == '_' || c.is_digit(radix))
+}
+
+fn parse_integer(text: &str) -> Option<i64> {
+    for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+        if let Some(digits) = text.strip_prefix(prefix) {
+            if !digits_ok(digits, radix) {
+                return None
+            }
+            return i64::from_str_radix(&digits.replace('_', ""), radix).ok()
+        }
+    }
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    if !digits_ok(digits, 10) || digits.len() > 1 && digits.starts_with('0') {
+        return None
+    }
+    text.replace('_', "").parse().ok()
+}
+
+fn parse_float(text: &str) -> Option<f64> {
+    let unsigned = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let negative = text.starts_with('-');
+    match unsigned {
+        "inf" => return Some(if negative {f64::NEG_INFINITY} else {f64::INFINITY}),
+        "nan" => return Some(if negative {-f64::NAN} else {f64::NAN}),
+        _ => ()
+    }
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(at) => (&unsigned[..at], Some(&unsigned[at + 1..])),
+        None => (unsigned, None)
+    };
+    let (int_part, frac_part) = match mantissa.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (mantissa, None)
+    };
+    if frac_part.is_none() && exponent.is_none() {
+        return None
+    }
+    if !digits_ok(int_part, 10) || int_part.len() > 1 && int_part.starts_with('0') {
+        return None
+    }
+    if frac_part.is_some_and( | f | !digits_ok(f, 10)) {
+        return None
+    }
+    if let Some(exponent) = exponent {
+        if !digits_ok(exponent.strip_prefix(['+', '-']).unwrap_or(exponent), 10) {
+            return None
+        }
+    }
+    text.replace('_', "").parse().ok()
+}
+
+fn two_digits(text: &str, at: usize, max: u32) -> Option<u32> {
+    let v: u32 = text.get(at..at + 2)?.parse().ok()?;
+    if text.as_bytes()[at].is_ascii_digit() && v <= max {Some(v)} else {None}
+}
+
+fn is_date(text: &str) -> bool {
+    let b = text.as_bytes();
+    if b.len() != 10 || b[4] != b'-' || b[7] != b'-' || !b[0..4].iter().all(u8::is_ascii_digit) {
+        return false
+    }
+    let year: u32 = text[0..4].parse().unwrap();
+    let (Some(month), Some(day)) = (two_digits(text, 5, 12), two_digits(text, 8, 31)) else {return false};
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days = match month {
+        2 => if leap {29} else {28},
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    };
+    month >= 1 && day >= 1 && day <= days
+}
+
+// HH:MM:SS with optional fraction
+fn time_len(text: &str) -> Option<usize> {
+    let b = text.as_bytes();
+    if b.len() < 8 || b[2] != b':' || b[5] != b':' {
+        return None
+    }
+    two_digits(text, 0, 23)?;
+    two_digits(text, 3, 59)?;
+    two_digits(text, 6, 60)?;
+    let mut len = 8;
+    if b.get(8) == Some(&b'.') {
+        len = 9;
+        while b.get(len).is_some_and(u8::is_ascii_digit) {
+            len += 1;
+        }
+        if len == 9 {
+            return None
+        }
+    }
+    Some(len)
+}
+
+fn is_offset(text: &str) -> bool {
+    match text {
+        "" | "Z" | "z" => true,
+        _ => {
+            let b = text.as_bytes();
+            b.len() == 6 && (b[0] == b'+' || b[0] == b'-') && b[3] == b':'
+                && two_digits(text, 1, 23).is_some() && two_digits(text, 4, 59).is_some()
+        }
+    }
+}
+
+// offset date-times, local date-times, local dates and local times
+fn is_datetime(text: &str) -> bool {
+    if let Some(len) = time_len(text) {
+        return len == text.len()
+    }
+    if text.len() < 10 || !is_date(&text[0..10]) {
+        return false
+    }
+    if text.len() == 10 {
+        return true
+    }
+    if !matches!(text.as_bytes()[10], b'T' | b't' | b' ') {
+        return false
+    }
+    let time = &text[11..];
+    match time_len(time) {
+        Some(len) => is_offset(&time[len..]),
+        None => false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_document() {
+        let src = r#"
+title = "demo" # trailing
+dates = [1979-05-27T07:32:00Z, 1979-05-27 00:32:00.999, 07:32:00]
+nums = [ 0xff, 0o17, 0b101, 1_000, -3, +1.5e3, inf, -nan ]
+name.first = 'Tom'
+name."last" = """
+Pres\
+   ton"""
+
+[dependencies]
+makepad-widgets = { path = "widgets", version = "0.6.0" }
+
+[target.'cfg(windows)'.dependencies]
+windows = "0.48"
+
+[[bin]]
+name = "a"
+[bin.meta]
+x = 1
+[[bin]]
+name = "b"
+"#;
+        let doc = parse_toml_doc(src).unwrap();
+        assert_eq!(doc.get("title").unwrap().as_str(), Some("demo"));
+        assert_eq!(&src[doc.get("title").unwrap().span().start..doc.get("title").unwrap().span().end()], "\"demo\"");
+        let nums = doc.get("nums").unwrap().as_array().unwrap();
+        assert_eq!(nums[0].as_i64(), Some(255));
+        assert_eq!(nums[1].as_i64(), Some(15));
+        assert_eq!(nums[2].as_i64(), Some(5));
+        assert_eq!(nums[3].as_i64(), Some(1000));
+        assert_eq!(nums[5].as_f64(), Some(1500.0));
+        assert!(nums[7].as_f64().unwrap().is_nan());
+        assert_eq!(doc.get("dates").unwrap().as_array().unwrap().len(), 3);
+        assert_eq!(doc.get_path(&["name", "last"]).unwrap().as_str(), Some("Preston"));
+        assert_eq!(doc.get_path(&["dependencies", "makepad-widgets", "version"]).unwrap().as_str(), Some("0.6.0"));
+        assert_eq!(doc.get_path(&["target", "cfg(windows)", "dependencies", "windows"]).unwrap().as_str(), Some("0.48"));
+        let bins = doc.get("bin").unwrap();
+        assert!(bins.is_array_of_tables());
+        assert_eq!(bins.as_array().unwrap()[0].get("meta").unwrap().get("x").unwrap().as_i64(), Some(1));
+        assert_eq!(doc.get_path(&["bin", "name"]).unwrap().as_str(), Some("b"));
+
+        let flat = parse_toml(src).unwrap();
+        assert_eq!(flat.get("target.'cfg(windows)'.dependencies.windows").unwrap().as_str(), Some("0.48"));
+        assert_eq!(flat.get("dependencies.makepad-widgets.version").unwrap().as_str(), Some("0.6.0"));
+        assert_eq!(flat.get("bin.1.name").unwrap().as_str(), Some("b"));
+    }
+
+    #[test]
+    fn invalid_documents() {
+        for src in [
+            "a = 1\na = 2",
+            "[a]\n[a]",
+            "a.b = 1\n[a]",
+            "a = {b = 1}\n[a.c]",
+            "a = [1]\n[[a]]",
+            "a = 1 b = 2",
+            "a = 01",
+            "a = 1__0",
+            "a = 1.",
+            "a = {b = 1,}",
+            "a = \"\\q\"",
+            "a = 2023-02-30",
+            "[[a]]\n[a]",
+            "[a.b.c]\n[a]\nb.c.d = 1",
+            "a = \"\"\"x\"\"\"\"\"\"",
+        ] {
+            assert!(parse_toml_doc(src).is_err(), "{:?} should not parse", src);
+        }
+        let err = parse_toml_doc("a = 1\nb = ?").err().unwrap();
+        assert_eq!(err.line_col("a = 1\nb = ?"), (2, 5));
+    }
+}
@@ -7,6 +7,7 @@ export function init_env(env) {
     env.js_open_web_socket = (id, url_ptr, url_len) => console.error("js_open_web_socket out of context");
     env.js_web_socket_send_string = (id, str_ptr, url_len) => console.error("js_web_socket_send_string out of context");
     env.js_web_socket_send_binary = (id, bin_ptr, bin_len) => console.error("js_web_socket_send_binary out of context");
+    env.js_web_socket_close = (id, code, reason_ptr, reason_len) => console.error("js_web_socket_close out of context");
 
     return (wasm) => { _wasm = wasm };
 }
@@ -81,6 +81,10 @@ fn derive_live_impl_inner(parser: &mut TokenParser, tb: &mut TokenBuilder) -> Re
             tb.add("         self.").ident(&animator_field.name).add(".cut_to_live(cx, state);");
             tb.add("         self.animator_apply_state(cx, scope);");
             tb.add("    }");
+            tb.add("    fn animator_seek_with_scope(&mut self, cx: &mut Cx, state: &[LiveId;2], time: f64, scope:&mut Scope) {");
+            tb.add("         self.").ident(&animator_field.name).add(".seek_to_live(cx, state, time);");
+            tb.add("         self.animator_apply_state(cx, scope);");
+            tb.add("    }");
                         
             tb.add("    fn animator_after_apply(&mut self, cx:&mut Cx, apply:&mut Apply, index:usize, nodes:&[LiveNode]){");
             tb.add("        let mut index = index + 1;");
@@ -425,15 +429,14 @@ fn derive_live_impl_inner(parser: &mut TokenParser, tb: &mut TokenBuilder) -> Re
                 tb.add("});");
             }
         }
-        tb.add("        LiveTypeInfo{");
-        tb.add("            module_id: LiveModuleId::from_str(&module_path!()).unwrap(),");
-        tb.add("            live_type: LiveType::of::<Self>(),");
         let live_ignore = main_attribs.iter().any( | attr | attr.name == "live_ignore");
-        tb.add("            live_ignore: ").ident(if live_ignore {"true"} else {"false"}).add(",");
-        tb.add("            fields,");
-        
-        tb.add("            type_name: LiveId::from_str_with_lut(").string(&struct_name).add(").unwrap()");
-        tb.add("        }");
+        tb.add("        LiveTypeInfo::new(");
+        tb.add("            LiveModuleId::from_str(&module_path!()).unwrap(),");
+        tb.add("            LiveType::of::<Self>(),");
+        tb.add("            LiveId::from_str_with_lut(").string(&struct_name).add(").unwrap(),");
+        tb.add("            ").ident(if live_ignore {"true"} else {"false"}).add(",");
+        tb.add("            fields");
+        tb.add("        )");
         tb.add("    }");
         
         tb.add("    fn live_design_with(cx: &mut Cx) {");
@@ -574,15 +577,20 @@ fn derive_live_impl_inner(parser: &mut TokenParser, tb: &mut TokenBuilder) -> Re
         tb.add("    }");
         
         tb.add("    fn live_type_info(cx:&mut Cx) -> LiveTypeInfo {");
-        tb.add("        LiveTypeInfo{");
-        tb.add("            module_id: LiveModuleId::from_str(&module_path!()).unwrap(),");
-        tb.add("            live_type: LiveType::of::<Self>(),");
-        tb.add("            fields: Vec::new(),");
         let live_ignore = main_attribs.iter().any( | attr | attr.name == "live_ignore");
-        tb.add("            live_ignore: ").ident(if live_ignore {"true"} else {"false"}).add(",");
-        tb.add("            type_name: LiveId::from_str_with_lut(").string(&enum_name).add(").unwrap(),");
-        /*tb.add("            kind: LiveTypeKind::Enum,");*/
-        tb.add("        }");
+        tb.add("        LiveTypeInfo::new(");
+        tb.add("            LiveModuleId::from_str(&module_path!()).unwrap(),");
+        tb.add("            LiveType::of::<Self>(),");
+        tb.add("            LiveId::from_str_with_lut(").string(&enum_name).add(").unwrap(),");
+        tb.add("            ").ident(if live_ignore {"true"} else {"false"}).add(",");
+        tb.add("            Vec::new()");
+        tb.add("        ).with_variants(vec![");
+        for item in &items {
+            if let EnumKind::Bare = item.kind {
+                tb.add("LiveId::from_str_with_lut(").string(&item.name).add(").unwrap(),");
+            }
+        }
+        tb.add("        ])");
         tb.add("    }");
         
         tb.add("    fn live_design_with(cx: &mut Cx) {");
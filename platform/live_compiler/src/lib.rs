@@ -16,6 +16,8 @@ pub mod live_component;
 pub mod live_node_cbor;
 //pub mod live_node_cbor;
 pub mod live_node_reader;
+pub mod live_format;
+pub mod live_lint;
 
 pub use makepad_math;
 pub use makepad_derive_live;
@@ -99,6 +101,8 @@ pub use {
             LiveError,
             LiveFileError
         },
-        live_document::{LiveOriginal, LiveExpanded}
+        live_document::{LiveOriginal, LiveExpanded},
+        live_format::{format_live_design, LiveFormatOptions, LivePropertyOrder},
+        live_lint::{lint_live_design, LiveLint, LiveLintKind},
     }
 };
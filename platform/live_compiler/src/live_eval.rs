@@ -62,6 +62,7 @@ pub fn live_eval_value(live_registry: &LiveRegistry, index: &mut usize, nodes: &
         LiveValue::Str(_) |
         LiveValue::InlineString(_) |
         LiveValue::Dependency(_) |
+        LiveValue::Translate(_) |
         LiveValue::String(_) |
         LiveValue::Float32(_) |
         LiveValue::Float64(_) |
@@ -0,0 +1,440 @@
+use {
+    crate::{
+        makepad_live_tokenizer::{
+            live_error_origin,
+            LiveErrorOrigin,
+            Delim,
+            FullToken,
+            State,
+            Cursor,
+        },
+        makepad_live_id::*,
+        live_error::LiveError,
+        span::{TextSpan, TextPos},
+    }
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LivePropertyOrder {
+    /// statements keep the order they were written in
+    #[default]
+    Source,
+    /// within a block, `key: value` properties move above the child instances next to them.
+    /// Anything else (fn, instance, import) stays where it is and splits the runs that get moved
+    PropertiesFirst,
+}
+
+#[derive(Clone, Debug)]
+pub struct LiveFormatOptions {
+    pub indent: usize,
+    pub max_blank_lines: usize,
+    pub property_order: LivePropertyOrder,
+}
+
+impl Default for LiveFormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            max_blank_lines: 1,
+            property_order: LivePropertyOrder::Source,
+        }
+    }
+}
+
+// the formatter only ever changes whitespace and the order of whole statements. Line breaks are
+// kept as written (with runs of blank lines collapsed), indentation is derived from the delimiter
+// nesting and spacing between tokens follows the style of the widget themes
+pub fn format_live_design(source: &str, options: &LiveFormatOptions) -> Result<String, LiveError> {
+    let chars: Vec<char> = source.chars().collect();
+    let tokens = raw_tokens(&chars);
+
+    let mut out = String::new();
+    let mut last = 0;
+    for (open, close) in find_live_design_bodies(&tokens) {
+        out.extend(&chars[last..tokens[open].end]);
+        let base_indent = line_indent(&chars, tokens[open].start);
+        out.push_str(&format_body(&chars, &tokens[open + 1..close], &base_indent, options) ?);
+        last = tokens[close].start;
+    }
+    out.extend(&chars[last..]);
+    Ok(out)
+}
+
+struct RawToken {
+    token: FullToken,
+    start: usize,
+    end: usize,
+}
+
+fn raw_tokens(chars: &[char]) -> Vec<RawToken> {
+    let mut scratch = String::new();
+    let mut cursor = Cursor::new(chars, &mut scratch);
+    let mut state = State::default();
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    while let (next_state, Some(token)) = state.next(&mut cursor) {
+        tokens.push(RawToken {token: token.token, start, end: start + token.len});
+        start += token.len;
+        state = next_state;
+    }
+    tokens
+}
+
+// the (open, close) brace token indices of every live_design!{} in a rust file
+fn find_live_design_bodies(tokens: &[RawToken]) -> Vec<(usize, usize)> {
+    let mut bodies = Vec::new();
+    let significant: Vec<usize> = (0..tokens.len()).filter( | i | !tokens[*i].token.is_ws_or_comment()).collect();
+    let mut i = 0;
+    while i + 2 < significant.len() {
+        if tokens[significant[i]].token == FullToken::Ident(live_id!(live_design))
+            && tokens[significant[i + 1]].token == FullToken::Punct(live_id!(!))
+            && tokens[significant[i + 2]].token.is_open_delim(Delim::Brace) {
+            let open = significant[i + 2];
+            let mut depth = 0;
+            for (j, &index) in significant.iter().enumerate().skip(i + 3) {
+                match &tokens[index].token {
+                    FullToken::Open(Delim::Brace) => depth += 1,
+                    FullToken::Close(Delim::Brace) if depth == 0 => {
+                        bodies.push((open, index));
+                        i = j;
+                        break;
+                    }
+                    FullToken::Close(Delim::Brace) => depth -= 1,
+                    _ => ()
+                }
+            }
+        }
+        i += 1;
+    }
+    bodies
+}
+
+fn line_indent(chars: &[char], index: usize) -> String {
+    let line_start = chars[..index].iter().rposition( | c | *c == '\n').map(|p| p + 1).unwrap_or(0);
+    chars[line_start..].iter().take_while( | c | **c == ' ' || **c == '\t').collect()
+}
+
+fn text_pos(chars: &[char], index: usize) -> TextPos {
+    let line = chars[..index].iter().filter( | c | **c == '\n').count();
+    let line_start = chars[..index].iter().rposition( | c | *c == '\n').map(|p| p + 1).unwrap_or(0);
+    TextPos {line: line as u32, column: (index - line_start) as u32}
+}
+
+struct Item {
+    token: FullToken,
+    text: String,
+    in_shader: bool,
+    in_class_ref: bool,
+}
+
+struct Line {
+    items: Vec<Item>,
+    blank_before: usize,
+    level: usize,
+}
+
+impl Line {
+    fn starts_with_close(&self) -> bool {
+        self.items[0].token.is_close()
+    }
+
+    fn is_comment(&self) -> bool {
+        self.items.iter().all( | item | item.token.is_comment())
+    }
+
+    fn kind(&self) -> StatementKind {
+        match (self.items.first().map( | i | &i.token), self.items.get(1).map( | i | &i.token)) {
+            (Some(FullToken::Ident(_)), Some(FullToken::Punct(live_id!(:)))) => StatementKind::Property,
+            (Some(FullToken::Ident(_)), Some(FullToken::Punct(live_id!(=)))) => StatementKind::Child,
+            (Some(FullToken::Punct(live_id!(<))), _) => StatementKind::Child,
+            _ => StatementKind::Other
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum StatementKind {
+    Property,
+    Child,
+    Other
+}
+
+fn format_body(chars: &[char], tokens: &[RawToken], base_indent: &str, options: &LiveFormatOptions) -> Result<String, LiveError> {
+    // split into lines, and mark what is inside shader fn bodies
+    let mut lines: Vec<Line> = Vec::new();
+    let mut newlines = 0;
+    let mut delims = Vec::new();
+    let mut fn_pending = false;
+    let mut in_class_ref = false;
+    for token in tokens {
+        match &token.token {
+            FullToken::Whitespace => {
+                newlines += chars[token.start..token.end].iter().filter( | c | **c == '\n').count();
+                continue;
+            }
+            FullToken::Unknown | FullToken::OtherNumber | FullToken::Lifetime => {
+                return Err(LiveError {
+                    origin: live_error_origin!(),
+                    span: TextSpan {
+                        start: text_pos(chars, token.start),
+                        end: text_pos(chars, token.end),
+                        ..TextSpan::default()
+                    }.into(),
+                    message: "Error tokenizing".to_string()
+                })
+            }
+            _ => ()
+        }
+        let in_shader = delims.last().copied().unwrap_or(false);
+        match &token.token {
+            FullToken::Ident(live_id!(fn)) if !in_shader => fn_pending = true,
+            FullToken::Open(Delim::Brace) => {
+                delims.push(in_shader || fn_pending);
+                fn_pending = false;
+            }
+            FullToken::Open(_) => delims.push(in_shader),
+            FullToken::Close(_) => {delims.pop();}
+            FullToken::Punct(live_id!(<)) if !in_shader => in_class_ref = true,
+            FullToken::Punct(live_id!(>)) if !in_shader => in_class_ref = false,
+            _ => ()
+        }
+        let item = Item {
+            token: token.token.clone(),
+            text: chars[token.start..token.end].iter().collect(),
+            in_shader,
+            in_class_ref,
+        };
+        if newlines > 0 || lines.is_empty() {
+            lines.push(Line {items: vec![item], blank_before: newlines.saturating_sub(1), level: 0});
+        }
+        else {
+            lines.last_mut().unwrap().items.push(item);
+        }
+        newlines = 0;
+    }
+    if lines.is_empty() {
+        return Ok(String::new())
+    }
+
+    // nesting level at the start of each line, after its leading closers
+    let mut level: usize = 0;
+    for line in &mut lines {
+        let leading = line.items.iter().take_while( | i | i.token.is_close()).count();
+        line.level = level.saturating_sub(leading);
+        for item in &line.items {
+            if item.token.is_open() {
+                level += 1;
+            }
+            else if item.token.is_close() {
+                level = level.saturating_sub(1);
+            }
+        }
+    }
+
+    if options.property_order == LivePropertyOrder::PropertiesFirst {
+        // top level definitions can refer to each other, so only blocks get reordered
+        lines = reorder_statements(lines, 0, false);
+    }
+
+    // print, indenting each line by one step per line that has unclosed delimiters
+    let mut out = String::from("\n");
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    let mut prev_opens = false;
+    for line in &lines {
+        let mut indent = stack.last().map( | s | s.1).unwrap_or(0);
+        let mut items = line.items.iter().peekable();
+        while let Some(item) = items.peek() {
+            if !item.token.is_close() {
+                break
+            }
+            if let Some((opener_indent, _)) = stack.pop() {
+                indent = opener_indent;
+            }
+            items.next();
+        }
+        if !prev_opens && !line.starts_with_close() {
+            for _ in 0..line.blank_before.min(options.max_blank_lines) {
+                out.push('\n');
+            }
+        }
+        out.push_str(base_indent);
+        out.push_str(&" ".repeat((indent + 1) * options.indent));
+
+        let pushed_before = stack.len();
+        let mut prev: Option<&Item> = None;
+        let mut prev_prev: Option<&Item> = None;
+        for item in &line.items {
+            if let Some(prev) = prev {
+                if needs_space(prev_prev, prev, item) {
+                    out.push(' ');
+                }
+            }
+            out.push_str(&item.text);
+            prev_prev = prev;
+            prev = Some(item);
+        }
+        for item in line.items.iter().skip_while( | i | i.token.is_close()) {
+            if item.token.is_open() {
+                let inner = if stack.len() > pushed_before {stack.last().unwrap().1} else {indent + 1};
+                stack.push((indent, inner));
+            }
+            else if item.token.is_close() {
+                stack.pop();
+            }
+        }
+        prev_opens = stack.len() > pushed_before;
+        out.push('\n');
+    }
+    out.push_str(base_indent);
+    Ok(out)
+}
+
+// splits lines at `level` into statements (comment lines stick to the statement below them, lines
+// nested deeper or starting with a closer continue the one above) and moves properties above the
+// child instances they are mixed with, recursing into the nested lines of each statement
+fn reorder_statements(lines: Vec<Line>, level: usize, partition: bool) -> Vec<Line> {
+    let mut statements: Vec<(StatementKind, Vec<Line>)> = Vec::new();
+    let mut open_statement = false;
+    let mut comments: Vec<Line> = Vec::new();
+    for line in lines {
+        if line.level > level || line.starts_with_close() && open_statement {
+            if let Some((_, statement)) = statements.last_mut() {
+                statement.push(line);
+                continue;
+            }
+        }
+        if line.is_comment() {
+            comments.push(line);
+            continue;
+        }
+        let kind = if !partition || line.items[0].in_shader {StatementKind::Other} else {line.kind()};
+        let mut statement = std::mem::take(&mut comments);
+        statement.push(line);
+        statements.push((kind, statement));
+        open_statement = true;
+    }
+
+    let mut out = Vec::new();
+    let mut run: Vec<(StatementKind, Vec<Line>)> = Vec::new();
+    let flush = | run: &mut Vec<(StatementKind, Vec<Line>)>, out: &mut Vec<Line> | {
+        let (props, children): (Vec<_>, Vec<_>) = run.drain(..).partition( | (kind, _) | *kind == StatementKind::Property);
+        for (_, statement) in props.into_iter().chain(children) {
+            out.extend(reorder_nested(statement, level));
+        }
+    };
+    for (kind, statement) in statements {
+        if kind == StatementKind::Other {
+            flush(&mut run, &mut out);
+            out.extend(reorder_nested(statement, level));
+        }
+        else {
+            run.push((kind, statement));
+        }
+    }
+    flush(&mut run, &mut out);
+    out.extend(comments);
+    out
+}
+
+fn reorder_nested(mut statement: Vec<Line>, level: usize) -> Vec<Line> {
+    let Some(first) = statement.iter().position( | l | l.level > level) else {
+        return statement
+    };
+    let end = statement.iter().rposition( | l | l.level > level).unwrap() + 1;
+    // not the min of the range, a `} else {` in between sits at this level again
+    let tail = statement.split_off(end);
+    let nested = statement.split_off(first);
+    statement.extend(reorder_statements(nested, level + 1, true));
+    statement.extend(tail);
+    statement
+}
+
+fn is_keyword(id: LiveId) -> bool {
+    matches!(id, live_id!(if) | live_id!(else) | live_id!(return) | live_id!(match) | live_id!(while) | live_id!(for) | live_id!(in) | live_id!(let))
+}
+
+fn is_unary(prev_prev: Option<&Item>, op: &Item) -> bool {
+    match op.token {
+        FullToken::Punct(live_id!(!)) => true,
+        FullToken::Punct(live_id!(-)) | FullToken::Punct(live_id!(+)) | FullToken::Punct(live_id!(&)) | FullToken::Punct(live_id!(*)) => match prev_prev.map( | i | &i.token) {
+            None | Some(FullToken::Open(_)) | Some(FullToken::Punct(_)) => true,
+            Some(FullToken::Ident(id)) => is_keyword(*id),
+            _ => false
+        },
+        _ => false
+    }
+}
+
+fn needs_space(prev_prev: Option<&Item>, prev: &Item, next: &Item) -> bool {
+    match (&prev.token, &next.token) {
+        (_, FullToken::Comment) | (FullToken::Comment, _) => true,
+        (FullToken::Open(_), _) | (_, FullToken::Close(_)) => false,
+        (_, FullToken::Punct(live_id!(,)))
+            | (_, FullToken::Punct(live_id!(;)))
+            | (_, FullToken::Punct(live_id!(:)))
+            | (_, FullToken::Punct(live_id!(.)))
+            | (_, FullToken::Punct(live_id!(::))) => false,
+        (FullToken::Punct(live_id!(.)), _)
+            | (FullToken::Punct(live_id!(::)), _)
+            | (FullToken::Punct(live_id!(#)), _)
+            | (FullToken::Punct(live_id!($)), _) => false,
+        // the designer writes <View dx:12.0 dy:4.0> without spaces
+        (FullToken::Punct(live_id!(:)), _) if prev.in_class_ref => false,
+        (FullToken::Punct(live_id!(,)), _)
+            | (FullToken::Punct(live_id!(;)), _)
+            | (FullToken::Punct(live_id!(:)), _) => true,
+        (FullToken::Punct(_), _) if is_unary(prev_prev, prev) => false,
+        (_, FullToken::Open(Delim::Brace)) => true,
+        // <View> in the DSL, comparisons in shader code
+        (FullToken::Punct(live_id!(<)), _) if !prev.in_shader => false,
+        (_, FullToken::Punct(live_id!(>))) if !next.in_shader => false,
+        (FullToken::Ident(id), FullToken::Open(_)) => is_keyword(*id),
+        (FullToken::Close(_), FullToken::Open(_)) => false,
+        _ => true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(source: &str, property_order: LivePropertyOrder) -> String {
+        format_live_design(source, &LiveFormatOptions {property_order, ..Default::default()}).unwrap()
+    }
+
+    #[test]
+    fn indents_and_spaces() {
+        let source = "live_design!{\nA=<View>{\nwidth:Fill,height:10\nb=<Label>{text:\"hi\"}\n}\n}\n";
+        assert_eq!(
+            format(source, LivePropertyOrder::Source),
+            "live_design!{\n    A = <View> {\n        width: Fill, height: 10\n        b = <Label> {text: \"hi\"}\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn is_idempotent() {
+        let source = "live_design!{\n  A = <View>{\n\n\n   b = <Label> {}\n  width:Fill\n draw_bg:{\nfn pixel(self)->vec4{\nif self.x>0.5{\nreturn #f00;\n}else{\nreturn #0f0;\n}\n}\n}\n}\n}\n";
+        for order in [LivePropertyOrder::Source, LivePropertyOrder::PropertiesFirst] {
+            let once = format(source, order);
+            assert_eq!(format(&once, order), once);
+        }
+    }
+
+    #[test]
+    fn properties_first_in_nested_blocks() {
+        let source = "live_design!{\nA = <View> {\nb = <View> {\nc = <Label> {}\nheight: 10\n}\nwidth: Fill\n}\n}\n";
+        assert_eq!(
+            format(source, LivePropertyOrder::PropertiesFirst),
+            "live_design!{\n    A = <View> {\n        width: Fill\n        b = <View> {\n            height: 10\n            c = <Label> {}\n        }\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn properties_first_leaves_shader_fns_alone() {
+        let source = "live_design!{\nA = <View> {\nc = <Label> {}\ndraw_bg: {\nfn pixel(self) -> vec4 {\nif x {\nreturn #f00;\n} else {\nlet y: float = 1.0;\nreturn #0f0;\n}\n}\n}\n}\n}\n";
+        assert_eq!(
+            format(source, LivePropertyOrder::PropertiesFirst),
+            "live_design!{\n    A = <View> {\n        draw_bg: {\n            fn pixel(self) -> vec4 {\n                if x {\n                    return #f00;\n                } else {\n                    let y: float = 1.0;\n                    return #0f0;\n                }\n            }\n        }\n        c = <Label> {}\n    }\n}\n"
+        );
+    }
+}
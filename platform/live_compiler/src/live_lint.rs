@@ -0,0 +1,258 @@
+use {
+    std::fmt,
+    crate::{
+        makepad_live_tokenizer::Delim,
+        makepad_live_id::*,
+        live_error::LiveError,
+        live_token::{LiveToken, TokenWithSpan},
+        live_ptr::LiveFileId,
+        live_node::{LiveValue, LiveTypeInfo, LiveFieldKind},
+        live_node_vec::LiveNodeSliceApi,
+        live_registry::LiveRegistry,
+        span::{TextSpan, TextPos},
+    }
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LiveLintKind {
+    UnknownProperty,
+    UnusedImport,
+    ShadowedId,
+}
+
+#[derive(Clone, Debug)]
+pub struct LiveLint {
+    pub kind: LiveLintKind,
+    pub span: TextSpan,
+    pub message: String,
+}
+
+impl fmt::Display for LiveLint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{} - {}", self.span.start.line + 1, self.span.start.column + 1, self.message)
+    }
+}
+
+// the properties #[walk] and #[layout] splat into a widget, they are not in its LiveTypeInfo
+const WALK_LAYOUT_FIELDS: [LiveId; 11] = [
+    live_id!(abs_pos), live_id!(margin), live_id!(width), live_id!(height),
+    live_id!(scroll), live_id!(clip_x), live_id!(clip_y), live_id!(padding),
+    live_id!(align), live_id!(flow), live_id!(spacing)
+];
+
+// unused imports and shadowed ids, from the tokens of the live_design!{} in a rust file. a value
+// set twice is only reported when one of the two is not an object, objects merge into each other
+pub fn lint_live_design(source: &str, file_id: LiveFileId) -> Result<Vec<LiveLint>, LiveError> {
+    let tokens = LiveRegistry::tokenize_from_str_live_design(source, TextPos::default(), file_id, None) ?;
+    let mut lints = Vec::new();
+
+    // (name, span of the import statement, path as written)
+    let mut imports: Vec<(LiveId, TextSpan, String)> = Vec::new();
+    let mut in_import = Vec::new();
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token.token {
+            LiveToken::Open(_) => depth += 1,
+            LiveToken::Close(_) => depth -= 1,
+            LiveToken::Ident(live_id!(import)) if depth == 0 => {
+                // a::b::Name or a::b::*, the ; is optional
+                let mut end = i + 1;
+                while end + 2 < tokens.len() && tokens[end + 1].token == LiveToken::Punct(live_id!(::)) {
+                    end += 2;
+                }
+                let path = &tokens[i + 1..=end];
+                in_import.extend(i..=end);
+                if tokens.get(end + 1).is_some_and( | t | t.token == LiveToken::Punct(live_id!(;))) {
+                    in_import.push(end + 1);
+                }
+                if let LiveToken::Ident(name) = path[path.len() - 1].token {
+                    let text = path.iter().map( | t | match t.token {
+                        LiveToken::Ident(id) | LiveToken::Punct(id) => id.to_string(),
+                        _ => String::new()
+                    }).collect();
+                    imports.push((name, span_over(token, &tokens[end]), text));
+                }
+            }
+            _ => ()
+        }
+    }
+    for (name, span, path) in &imports {
+        let used = tokens.iter().enumerate().any( | (i, t) | t.token == LiveToken::Ident(*name) && !in_import.contains(&i));
+        if !used {
+            lints.push(LiveLint {
+                kind: LiveLintKind::UnusedImport,
+                span: *span,
+                message: format!("unused import {}", path)
+            });
+        }
+    }
+
+    // ids set twice in the same block, and definitions that hide an import
+    let mut blocks: Vec<Vec<(LiveId, bool, bool)>> = vec![Vec::new()];
+    let mut shader_depth = None;
+    let mut fn_pending = false;
+    let mut in_class_ref = false;
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token.token {
+            LiveToken::Open(delim) => {
+                depth += 1;
+                if delim == Delim::Brace {
+                    if fn_pending && shader_depth.is_none() {
+                        shader_depth = Some(depth);
+                    }
+                    fn_pending = false;
+                }
+                blocks.push(Vec::new());
+                continue;
+            }
+            LiveToken::Close(_) => {
+                if shader_depth == Some(depth) {
+                    shader_depth = None;
+                }
+                depth -= 1;
+                blocks.pop();
+                continue;
+            }
+            LiveToken::Ident(live_id!(fn)) => fn_pending = true,
+            LiveToken::Punct(live_id!(<)) if shader_depth.is_none() => in_class_ref = true,
+            LiveToken::Punct(live_id!(>)) if shader_depth.is_none() => in_class_ref = false,
+            _ => ()
+        }
+        // <View dx:.. dy:..> carries the designer positions, not properties
+        if shader_depth.is_some() || in_class_ref || in_import.contains(&i) {
+            continue;
+        }
+        let LiveToken::Ident(id) = token.token else {continue};
+        let is_key = matches!(tokens.get(i + 1).map( | t | &t.token), Some(LiveToken::Punct(live_id!(:))) | Some(LiveToken::Punct(live_id!(=))));
+        let after_path = i > 0 && matches!(tokens[i - 1].token, LiveToken::Punct(live_id!(::)) | LiveToken::Punct(live_id!(.)));
+        if !is_key || after_path {
+            continue;
+        }
+        // `instance x: ..` declares, `x: ..` sets, the two are allowed to meet
+        let prefixed = i > 0 && matches!(tokens[i - 1].token, LiveToken::Ident(_));
+        // `{..}`, `<Class> {..}` and `Struct {..}` merge with an earlier value, others replace it
+        let is_object = match tokens.get(i + 2).map( | t | &t.token) {
+            Some(LiveToken::Open(Delim::Brace)) | Some(LiveToken::Punct(live_id!(<))) => true,
+            Some(LiveToken::Ident(_)) => tokens.get(i + 3).is_some_and( | t | t.token == LiveToken::Open(Delim::Brace)),
+            _ => false
+        };
+        let block = blocks.last_mut().unwrap();
+        if let Some(earlier) = block.iter_mut().find( | (earlier, earlier_prefixed, _) | *earlier == id && *earlier_prefixed == prefixed) {
+            if !(earlier.2 && is_object) {
+                lints.push(LiveLint {
+                    kind: LiveLintKind::ShadowedId,
+                    span: token.span,
+                    message: format!("{} is set again in the same block, the earlier value is overridden", id)
+                });
+            }
+            earlier.2 = is_object;
+        }
+        else {
+            block.push((id, prefixed, is_object));
+        }
+        // Name = <Name> {} re-exports an import, that is not shadowing it
+        let reexport = tokens[i + 1..].iter().take(3).any( | t | t.token == LiveToken::Ident(id));
+        if depth == 0 && !reexport {
+            if let Some((_, _, path)) = imports.iter().find( | (name, _, _) | *name == id) {
+                lints.push(LiveLint {
+                    kind: LiveLintKind::ShadowedId,
+                    span: token.span,
+                    message: format!("{} shadows the import of {}", id, path)
+                });
+            }
+        }
+    }
+    lints.sort_by_key( | lint | (lint.span.start.line, lint.span.start.column));
+    Ok(lints)
+}
+
+fn span_over(start: &TokenWithSpan, end: &TokenWithSpan) -> TextSpan {
+    TextSpan {file_id: start.span.file_id, start: start.span.start, end: end.span.end}
+}
+
+impl LiveRegistry {
+    // properties set on a widget in this file that its rust type does not have and that no
+    // live_design it derives from declares. Checks the expanded document, so the file has to be
+    // registered and expanded first
+    pub fn lint_unknown_properties(&self, file_id: LiveFileId) -> Vec<LiveLint> {
+        let mut lints = Vec::new();
+        let nodes = &self.file_id_to_file(file_id).expanded.nodes;
+        for (index, node) in nodes.iter().enumerate() {
+            let LiveValue::Class {live_type, ..} = &node.value else {continue};
+            let Some(info) = self.live_type_infos.get(live_type) else {continue};
+            if info.fields.is_empty() {
+                continue;
+            }
+            let mut child = nodes.first_child(index);
+            while let Some(child_index) = child {
+                let prop = &nodes[child_index];
+                child = nodes.next_child(child_index);
+                if !prop.is_field_prop() || prop.origin.node_has_prefix() || WALK_LAYOUT_FIELDS.contains(&prop.id) {
+                    continue;
+                }
+                let Some(first_def) = prop.origin.first_def() else {continue};
+                if first_def.file_id() != Some(file_id) || has_field(info, prop.id) {
+                    continue;
+                }
+                lints.push(LiveLint {
+                    kind: LiveLintKind::UnknownProperty,
+                    span: self.token_id_to_span(first_def),
+                    message: format!("unknown property {} for {}", prop.id, info.type_name)
+                });
+            }
+        }
+        lints.sort_by_key( | lint | (lint.span.start.line, lint.span.start.column));
+        lints.dedup_by_key( | lint | (lint.span.start.line, lint.span.start.column));
+        lints
+    }
+}
+
+fn has_field(info: &LiveTypeInfo, id: LiveId) -> bool {
+    info.fields.iter().any( | field | field.id == id || field.live_field_kind == LiveFieldKind::Deref && has_field(&field.live_type_info, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(source: &str) -> Vec<(LiveLintKind, String)> {
+        lint_live_design(source, LiveFileId(0)).unwrap().into_iter().map( | l | (l.kind, l.message)).collect()
+    }
+
+    #[test]
+    fn unused_imports() {
+        let lints = lint("live_design!{\n    import a::b::Used;\n    import a::b::Unused;\n    X = <Used> {}\n}\n");
+        assert_eq!(lints, vec![(LiveLintKind::UnusedImport, "unused import a::b::Unused".to_string())]);
+    }
+
+    #[test]
+    fn glob_imports_are_not_linted() {
+        assert!(lint("live_design!{\n    import a::b::*;\n    X = <View> {}\n}\n").is_empty());
+    }
+
+    #[test]
+    fn ids_set_twice() {
+        let lints = lint("live_design!{\n    X = <View> {\n        width: 10\n        height: 10\n        width: 20\n        inner = <View> {width: 5}\n    }\n}\n");
+        assert_eq!(lints, vec![(LiveLintKind::ShadowedId, "width is set again in the same block, the earlier value is overridden".to_string())]);
+    }
+
+    #[test]
+    fn objects_set_twice_merge() {
+        assert!(lint("live_design!{\n    X = <View> {\n        draw_bg: {color: #f00}\n        draw_bg: {radius: 2.0}\n        inner = <View> {}\n        inner = <View> {width: 5}\n        walk: Walk {width: 10}\n        walk: Walk {height: 10}\n    }\n}\n").is_empty());
+        // an object replacing a value, or a value replacing an object, does override
+        let lints = lint("live_design!{\n    X = <View> {\n        draw_bg: {color: #f00}\n        draw_bg: 0.0\n        draw_bg: {color: #0f0}\n    }\n}\n");
+        assert_eq!(lints.len(), 2);
+    }
+
+    #[test]
+    fn declaring_and_setting_is_allowed() {
+        assert!(lint("live_design!{\n    X = <View> {\n        draw_bg: {\n            instance hover: 0.0\n            hover: 1.0\n            fn pixel(self) -> vec4 {\n                let a = 1.0;\n                let a = 2.0;\n                return #f00;\n            }\n        }\n    }\n}\n").is_empty());
+    }
+
+    #[test]
+    fn definitions_shadowing_imports() {
+        let lints = lint("live_design!{\n    import a::b::Button;\n    import a::b::Label;\n    Button = <Button> {}\n    Label = <View> {}\n}\n");
+        assert_eq!(lints, vec![(LiveLintKind::ShadowedId, "Label shadows the import of a::b::Label".to_string())]);
+    }
+}
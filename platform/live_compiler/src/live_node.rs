@@ -74,6 +74,8 @@ pub enum LiveValue {
     String(Arc<String>),
     InlineString(InlineString),
     Dependency(Arc<String>),
+    // a message id, looked up in the message catalog of the current locale on apply
+    Translate(Arc<String>),
     Bool(bool),
     Int64(i64),
     Uint64(u64),
@@ -420,13 +422,35 @@ impl LiveEditInfo {
 
 pub type LiveType = std::any::TypeId;
 
+// built with LiveTypeInfo::new, so fields can be added without breaking the LiveNew impls out there
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct LiveTypeInfo {
     pub live_type: LiveType,
     pub type_name: LiveId,
     pub module_id: LiveModuleId,
     pub live_ignore: bool,
-    pub fields: Vec<LiveTypeField>
+    pub fields: Vec<LiveTypeField>,
+    // the bare variants of an enum, the values a property of its type can be set to
+    pub variants: Vec<LiveId>
+}
+
+impl LiveTypeInfo {
+    pub fn new(module_id: LiveModuleId, live_type: LiveType, type_name: LiveId, live_ignore: bool, fields: Vec<LiveTypeField>) -> Self {
+        Self {
+            live_type,
+            type_name,
+            module_id,
+            live_ignore,
+            fields,
+            variants: Vec::new()
+        }
+    }
+    
+    pub fn with_variants(mut self, variants: Vec<LiveId>) -> Self {
+        self.variants = variants;
+        self
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -589,6 +613,7 @@ impl LiveValue {
             Self::String(_) |
             Self::InlineString {..} |
             Self::Dependency {..} |
+            Self::Translate(_) |
             Self::Bool(_) |
             Self::Int64(_) |
             Self::Uint64(_) |
@@ -746,6 +771,7 @@ impl LiveValue {
             
             Self::DSL {..} => 31,
             Self::Import {..} => 32,
+            Self::Translate(_) => 33,
             //Self::Registry {..} => 30,
         }
     }
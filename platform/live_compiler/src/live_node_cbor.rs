@@ -372,6 +372,9 @@ impl<T> LiveNodeSliceToCbor for T where T: AsRef<[LiveNode]> {
                 LiveValue::Dependency {..} => {
                     return Err("Cannot serialise LiveValue::Dependency".into())
                 },
+                LiveValue::Translate {..} => {
+                    return Err("Cannot serialise LiveValue::Translate".into())
+                },
                 LiveValue::Class {..} => {
                     return Err("Cannot serialise LiveValue::Class".into())
                 }, // subnodes including this one
@@ -667,6 +667,9 @@ impl<T> LiveNodeSliceApi for T where T: AsRef<[LiveNode]> {
                 LiveValue::Dependency (s) => {
                     writeln!(f, "{}{} <Dependency> {}", node.id, pt, s).unwrap();
                 },
+                LiveValue::Translate (s) => {
+                    writeln!(f, "{}{} <Translate> {}", node.id, pt, s).unwrap();
+                },
                 LiveValue::Bool(v) => {
                     writeln!(f, "{}{} <Bool> {}", node.id, pt, v).unwrap();
                 }
@@ -18,7 +18,7 @@ use {
         span::{TextSpan, TextPos},
         live_error::{LiveError},
         live_document::LiveOriginal,
-        live_node::{LiveDesignInfo, LiveDesignInfoIndex, LiveImport, LivePropType, LiveNode, LiveValue, LiveTypeInfo, LiveBinOp, LiveUnOp, LiveNodeOrigin, LiveEditInfo},
+        live_node::{LiveDesignInfo, LiveDesignInfoIndex, LiveImport, LivePropType, LiveNode, LiveValue, LiveTypeInfo, LiveType, LiveBinOp, LiveUnOp, LiveNodeOrigin, LiveEditInfo},
     }
 };
 
@@ -27,6 +27,9 @@ pub struct LiveParser<'a> {
     pub file_id: LiveFileId,
     pub live_type_info_counter: usize,
     pub live_type_infos: &'a [LiveTypeInfo],
+    // {{Type}} is looked up by name instead of by position, for documents that are not
+    // compiled in from a live_design! macro
+    pub resolve_type_names: bool,
     pub tokens_with_span: Cloned<Iter<'a, TokenWithSpan >>,
     pub token_with_span: TokenWithSpan,
     pub end: TextPos,
@@ -41,11 +44,18 @@ impl<'a> LiveParser<'a> {
             file_id,
             tokens_with_span,
             live_type_infos,
+            resolve_type_names: false,
             token_with_span,
             token_index: 0,
             end: TextPos::default(),
         }
     }
+    
+    pub fn new_resolving_type_names(tokens: &'a [TokenWithSpan], live_type_infos: &'a [LiveTypeInfo], file_id: LiveFileId) -> Self {
+        let mut parser = Self::new(tokens, live_type_infos, file_id);
+        parser.resolve_type_names = true;
+        parser
+    }
 }
 
 impl<'a> LiveParser<'a> {
@@ -110,6 +120,47 @@ impl<'a> LiveParser<'a> {
         true
     }
     
+    // {{Type}} or {{crate::module::Type}}, the module path is needed when more than one
+    // registered rust type has the name
+    fn expect_type_path(&mut self) -> Result<LiveType, LiveError> {
+        let mut path = vec![self.expect_ident() ?];
+        while self.accept_token(LiveToken::Punct(live_id!(::))) {
+            path.push(self.expect_ident() ?);
+        }
+        let type_name = path.pop().unwrap();
+        let module_id = if path.is_empty() {
+            None
+        }
+        else {
+            let module_path = path.iter().map( | id | id.to_string()).collect::<Vec<_>>().join("::");
+            Some(LiveModuleId::from_str(&module_path).map_err( | message | self.error(message, live_error_origin!())) ?)
+        };
+        let mut candidates = self.live_type_infos.iter().filter( | info | {
+            info.type_name == type_name && module_id.is_none_or( | module_id | info.module_id == module_id)
+        });
+        match (candidates.next(), candidates.next()) {
+            (Some(info), None) => Ok(info.live_type),
+            (None, _) => match module_id {
+                Some(module_id) => Err(self.error(format!("unknown rust type {{{{{}::{}}}}}", module_id, type_name), live_error_origin!())),
+                None => Err(self.error(format!("unknown rust type {{{{{}}}}}", type_name), live_error_origin!()))
+            },
+            (Some(first), Some(second)) => {
+                let mut modules: Vec<String> = [first, second].into_iter()
+                    .chain(candidates)
+                    .map( | info | info.module_id.to_string())
+                    .collect();
+                modules.sort();
+                Err(self.error(format!(
+                    "rust type {{{{{}}}}} is ambiguous, it is in {}. Write the module path like {{{{{}::{}}}}}",
+                    type_name,
+                    modules.join(", "),
+                    modules[0],
+                    type_name
+                ), live_error_origin!()))
+            }
+        }
+    }
+    
     fn expect_ident(&mut self) -> Result<LiveId, LiveError> {
         match self.peek_token() {
             LiveToken::Ident(ident) => {
@@ -553,11 +604,16 @@ impl<'a> LiveParser<'a> {
                     let val = self.live_type_info_counter;
                     self.live_type_info_counter += 1;
                     
-                    self.accept_ident();
-                    
-                    if val >= self.live_type_infos.len() {
-                        return Err(self.error(format!("live_type index out of range {}", val), live_error_origin!()));
+                    let live_type = if self.resolve_type_names {
+                        self.expect_type_path() ?
                     }
+                    else {
+                        self.accept_ident();
+                        if val >= self.live_type_infos.len() {
+                            return Err(self.error(format!("live_type index out of range {}", val), live_error_origin!()));
+                        }
+                        self.live_type_infos[val].live_type
+                    };
                     
                     self.expect_token(LiveToken::Close(Delim::Brace)) ?;
                     self.expect_token(LiveToken::Close(Delim::Brace)) ?;
@@ -571,7 +627,7 @@ impl<'a> LiveParser<'a> {
                             origin,
                             id: prop_id,
                             value: LiveValue::Deref{
-                                live_type: self.live_type_infos[val].live_type,
+                                live_type,
                                 clone: ident,
                                 design_info:LiveDesignInfoIndex::invalid()
                             }
@@ -582,7 +638,7 @@ impl<'a> LiveParser<'a> {
                             origin,
                             id: prop_id,
                             value: LiveValue::Class {
-                                live_type: self.live_type_infos[val].live_type,
+                                live_type,
                                 class_parent: LivePtr::invalid(),
                                 design_info:LiveDesignInfoIndex::invalid()
                             }
@@ -689,6 +745,25 @@ impl<'a> LiveParser<'a> {
                     });
                 }
             }
+            LiveToken::Ident(live_id!(tr)) => {
+                self.skip_token();
+                if self.accept_token(LiveToken::Open(Delim::Paren)) {
+                    let x = self.expect_string() ?;
+                    ld.nodes.push(LiveNode {
+                        origin,
+                        id: prop_id,
+                        value: LiveValue::Translate(x)
+                    });
+                    self.expect_token(LiveToken::Close(Delim::Paren)) ?;
+                }
+                else {
+                    ld.nodes.push(LiveNode {
+                        origin,
+                        id: prop_id,
+                        value: LiveValue::Id(live_id!(tr))
+                    });
+                }
+            }
             LiveToken::Ident(live_id!(vec2)) => {
                 self.skip_token();
                 if self.accept_token(LiveToken::Open(Delim::Paren)) {
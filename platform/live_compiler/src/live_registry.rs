@@ -8,7 +8,7 @@ use {
         live_error::{LiveError, LiveErrorSpan, LiveFileError},
         live_parser::LiveParser,
         live_document::{LiveOriginal, LiveExpanded},
-        live_node::{LiveNodeOrigin, LiveNode, LiveValue, LiveType, LiveTypeInfo, LiveIdAsProp, LiveDesignInfo, LiveDesignInfoIndex},
+        live_node::{LiveNodeOrigin, LiveNode, LiveValue, LiveType, LiveTypeInfo, LiveFieldKind, LivePropType, LiveIdAsProp, LiveDesignInfo, LiveDesignInfoIndex},
         /*live_node_reader::{LiveNodeMutReader},*/
         live_node_vec::{LiveNodeSliceApi, /*LiveNodeVecApi*/},
         live_ptr::{LiveFileId, LivePtr, LiveModuleId, LiveFileGeneration},
@@ -34,6 +34,8 @@ pub struct LiveFile {
     pub original: LiveOriginal,
     pub next_original: Option<LiveOriginal>,
     pub expanded: LiveExpanded,
+    // another file whose top level definitions replace ours, with our own nodes to go back to
+    pub (crate) overlay: Option<(LiveFileId, Vec<LiveNode>)>,
     
     pub live_type_infos: Vec<LiveTypeInfo>,
 }
@@ -274,6 +276,146 @@ impl LiveRegistry {
         None
     }
     
+    // sets the value of a property written in the same file as the node, and moves the tokens
+    // after it to where the replacement text puts them. returns where the old value was in the source
+    pub fn patch_value(&mut self, live_ptr: LivePtr, value: LiveValue, replace: &str) -> Option<(&str, DesignInfoRange)> {
+        let live_file = &mut self.live_files[live_ptr.file_id.to_index()];
+        if live_file.generation != live_ptr.generation {
+            return None
+        }
+        let token_id = live_file.expanded.nodes[live_ptr.index as usize].origin.token_id()?;
+        if token_id.file_id() != Some(live_ptr.file_id){
+            return None
+        }
+        let tokens = &mut live_file.original.tokens;
+        // the value follows the : after the property name
+        let colon = (token_id.token_index() + 1..tokens.len()).take(4).find(|i| {
+            tokens[*i].token == LiveToken::Punct(live_id!(:))
+        })?;
+        let start = colon + 1;
+        let end = if tokens[start].token == LiveToken::Punct(live_id!(-)){start + 2}else{start + 1};
+        match tokens.get(end - 1).map(|t| &t.token){
+            Some(LiveToken::Bool(_)) | Some(LiveToken::Int(_)) | Some(LiveToken::Float(_)) |
+            Some(LiveToken::Color(_)) | Some(LiveToken::String(_)) | Some(LiveToken::Ident(_))=>(),
+            _=>return None
+        }
+        // values spanning lines aren't patched in place
+        let line = tokens[start].span.start.line;
+        if tokens[end - 1].span.end.line != line{
+            return None
+        }
+        let range = DesignInfoRange{
+            line,
+            start_column: tokens[start].span.start.column,
+            end_column: tokens[end - 1].span.end.column
+        };
+        // columns count chars
+        let new_end = range.start_column + replace.chars().count() as u32;
+        tokens[start].span.end.column = new_end;
+        if end - start == 2{
+            tokens[start + 1].span.start.column = new_end;
+            tokens[start + 1].span.end.column = new_end;
+        }
+        for token in &mut tokens[end..]{
+            if token.span.start.line != line{
+                break
+            }
+            token.span.start.column = token.span.start.column + new_end - range.end_column;
+            if token.span.end.line == line{
+                token.span.end.column = token.span.end.column + new_end - range.end_column;
+            }
+        }
+        if let Some(index) = live_file.original.nodes.first_node_with_token_id(token_id, false){
+            live_file.original.nodes[index].value = value.clone();
+        }
+        live_file.expanded.nodes[live_ptr.index as usize].value = value;
+        Some((&live_file.file_name, range))
+    }
+
+    // where a new property can be written into the body of the class the node opens
+    pub fn new_property_location(&self, live_ptr: LivePtr) -> Option<(&str, DesignInfoRange)> {
+        let live_file = &self.live_files[live_ptr.file_id.to_index()];
+        let token_id = live_file.expanded.nodes[live_ptr.index as usize].origin.token_id()?;
+        if token_id.file_id() != Some(live_ptr.file_id){
+            return None
+        }
+        let tokens = &live_file.original.tokens;
+        let open = (token_id.token_index()..tokens.len()).find(|i| {
+            tokens[*i].token == LiveToken::Open(Delim::Brace)
+        })?;
+        let end = tokens[open].span.end;
+        Some((
+            &live_file.file_name,
+            DesignInfoRange{
+                line: end.line,
+                start_column: end.column,
+                end_column: end.column,
+            }
+        ))
+    }
+
+    // the index of the brace that closes the body of the class the node opens
+    fn class_close_token(&self, live_ptr: LivePtr) -> Option<(LiveTokenId, usize)> {
+        let live_file = &self.live_files[live_ptr.file_id.to_index()];
+        let token_id = live_file.expanded.nodes[live_ptr.index as usize].origin.token_id()?;
+        if token_id.file_id() != Some(live_ptr.file_id){
+            return None
+        }
+        let tokens = &live_file.original.tokens;
+        let mut depth = 0;
+        for i in token_id.token_index()..tokens.len(){
+            match tokens[i].token{
+                LiveToken::Open(Delim::Brace)=>depth += 1,
+                LiveToken::Close(Delim::Brace)=>{
+                    depth -= 1;
+                    if depth == 0{
+                        return Some((token_id, i))
+                    }
+                }
+                LiveToken::Eof=>return None,
+                _=>()
+            }
+        }
+        None
+    }
+
+    // where the source of a class node starts and ends, with the delimiter after it. when the node has
+    // its lines to itself the range covers those lines whole, and the bool is true
+    pub fn node_source_range(&self, live_ptr: LivePtr) -> Option<(&str, TextSpan, bool)> {
+        let (token_id, mut close) = self.class_close_token(live_ptr)?;
+        let live_file = &self.live_files[live_ptr.file_id.to_index()];
+        let tokens = &live_file.original.tokens;
+        let start = token_id.token_index();
+        if let LiveToken::Punct(live_id!(,)) | LiveToken::Punct(live_id!(;)) = tokens[close + 1].token{
+            close += 1;
+        }
+        let mut span = TextSpan{
+            file_id: live_ptr.file_id,
+            start: tokens[start].span.start,
+            end: tokens[close].span.end
+        };
+        let own_lines = (start == 0 || tokens[start - 1].span.end.line < span.start.line) &&
+            (tokens[close + 1].token == LiveToken::Eof || tokens[close + 1].span.start.line > span.end.line);
+        if own_lines{
+            span.start.column = 0;
+            span.end = TextPos{line: span.end.line + 1, column: 0};
+        }
+        Some((&live_file.file_name, span, own_lines))
+    }
+
+    // where a new child goes at the end of the body of the class the node opens. when the closing brace
+    // starts its line, the position is the start of that line and its indentation is returned with it
+    pub fn new_child_location(&self, live_ptr: LivePtr) -> Option<(&str, TextPos, Option<u32>)> {
+        let (_, close) = self.class_close_token(live_ptr)?;
+        let live_file = &self.live_files[live_ptr.file_id.to_index()];
+        let tokens = &live_file.original.tokens;
+        let pos = tokens[close].span.start;
+        if tokens[close - 1].span.end.line < pos.line{
+            return Some((&live_file.file_name, TextPos{line: pos.line, column: 0}, Some(pos.column)))
+        }
+        Some((&live_file.file_name, pos, None))
+    }
+
     pub fn new_design_info_location(&self, live_ptr: LivePtr) -> Option<(&str, DesignInfoRange)> {
         let live_file = &self.live_files[live_ptr.file_id.to_index()];
         let node = &live_file.expanded.nodes[live_ptr.index as usize];
@@ -681,6 +823,7 @@ impl LiveRegistry {
     
     pub fn process_file_changes(&mut self, changes: Vec<LiveFileChange>, errors:&mut Vec<LiveError >){
         let mut any_changes = false;
+        let mut changed = Vec::new();
         for change in changes {
             if let Some(file_id) = self.file_name_to_file_id(&change.file_name){
                 let module_id = self.file_id_to_module_id(file_id).unwrap();
@@ -706,21 +849,108 @@ impl LiveRegistry {
                                 }
                                 any_changes = true;
                                 ld.tokens = new_tokens;
+                                if let Some((_, base)) = &mut live_file.overlay{
+                                    *base = std::mem::take(&mut ld.nodes);
+                                }
                                 live_file.original = ld;
                                 live_file.reexpand = true;
                                 live_file.generation.next_gen();
+                                changed.push(file_id);
                             }
                         };
                     }
                 }
             }
         }
+        // files overlaid by a changed file, or changed themselves, get their overlay put back on
+        for index in 0..self.live_files.len(){
+            if let Some((overlay_id, _)) = &self.live_files[index].overlay{
+                if changed.contains(overlay_id) || changed.contains(&LiveFileId::new(index)){
+                    self.apply_overlay(LiveFileId::new(index));
+                }
+            }
+        }
         if any_changes{
             // try to re-expand
             self.expand_all_documents(errors);
         }
     }
 
+    // swaps the top level definitions of another module in over the ones with the same name in
+    // target, and re-expands everything depending on it. this is how a theme set replaces the
+    // palette of the base theme at runtime. None puts the target's own definitions back
+    pub fn set_file_overlay(&mut self, target: LiveModuleId, overlay: Option<LiveModuleId>, errors: &mut Vec<LiveError>) -> bool {
+        let Some(file_id) = self.module_id_to_file_id(target) else {
+            return false
+        };
+        let overlay_id = match overlay {
+            Some(overlay) => match self.module_id_to_file_id(overlay) {
+                Some(overlay_id) if overlay_id != file_id => Some(overlay_id),
+                _ => return false
+            }
+            None => None
+        };
+        let live_file = self.file_id_to_file_mut(file_id);
+        if live_file.overlay.as_ref().map(|(id, _)| *id) == overlay_id {
+            return false
+        }
+        match (live_file.overlay.take(), overlay_id) {
+            (Some((_, base)), Some(overlay_id)) => live_file.overlay = Some((overlay_id, base)),
+            (Some((_, base)), None) => live_file.original.nodes = base,
+            (None, Some(overlay_id)) => live_file.overlay = Some((overlay_id, live_file.original.nodes.clone())),
+            (None, None) => ()
+        }
+        if overlay_id.is_some() {
+            self.apply_overlay(file_id);
+        }
+        else {
+            let live_file = self.file_id_to_file_mut(file_id);
+            live_file.reexpand = true;
+            live_file.generation.next_gen();
+        }
+        self.expand_all_documents(errors);
+        true
+    }
+    
+    fn apply_overlay(&mut self, file_id: LiveFileId) {
+        let Some((overlay_id, base)) = &self.file_id_to_file(file_id).overlay else {
+            return
+        };
+        let overlay = &self.file_id_to_file(*overlay_id).original.nodes;
+        let mut replace = Vec::new();
+        let mut index = 1;
+        while index < overlay.len().saturating_sub(1) {
+            let next = overlay.skip_node(index);
+            if !matches!(overlay[index].value, LiveValue::Import(_)) {
+                replace.push((overlay[index].id, index, next));
+            }
+            index = next;
+        }
+        // a name defined twice in the base is defined once by the overlay
+        let mut nodes = Vec::with_capacity(base.len());
+        let mut replaced = Vec::new();
+        nodes.push(base[0].clone());
+        let mut index = 1;
+        while index < base.len().saturating_sub(1) {
+            let next = base.skip_node(index);
+            if let Some((id, start, end)) = replace.iter().find(|(id, _, _)| *id == base[index].id) {
+                if !replaced.contains(id) {
+                    replaced.push(*id);
+                    nodes.extend(overlay[*start..*end].iter().cloned());
+                }
+            }
+            else {
+                nodes.extend(base[index..next].iter().cloned());
+            }
+            index = next;
+        }
+        nodes.extend(base[index..].iter().cloned());
+        let live_file = self.file_id_to_file_mut(file_id);
+        live_file.original.nodes = nodes;
+        live_file.reexpand = true;
+        live_file.generation.next_gen();
+    }
+    
     pub fn register_live_file(
         &mut self,
         file_name: &str,
@@ -749,6 +979,79 @@ impl LiveRegistry {
         };
         original.tokens = tokens;
         
+        self.add_live_file(file_id, file_name, cargo_manifest_path, own_module_id, source, live_type_infos, start_pos, original);
+        
+        Ok(file_id)
+    }
+    
+    // registers a document that wasn't compiled in, like a skin read from disk or ui sent by a
+    // server. {{Type}} refers to any registered rust type by name, and registering the same
+    // file name again replaces the document. Expansion is left to the caller
+    pub fn register_live_source(
+        &mut self,
+        file_name: &str,
+        own_module_id: LiveModuleId,
+        source: String,
+    ) -> Result<LiveFileId, LiveFileError> {
+        let existing = self.file_ids.get(file_name).cloned();
+        let file_id = existing.unwrap_or(LiveFileId::new(self.live_files.len()));
+        if let Some(file_id) = existing {
+            if self.file_id_to_module_id(file_id) != Some(own_module_id) {
+                return Err(LiveFileError {
+                    origin: live_error_origin!(),
+                    file: file_name.to_string(),
+                    span: TextSpan {file_id, ..TextSpan::default()},
+                    message: format!("{} is already registered as another module", file_name)
+                })
+            }
+        }
+        else if let Some(other) = self.module_id_to_file_id.get(&own_module_id) {
+            return Err(LiveFileError {
+                origin: live_error_origin!(),
+                file: file_name.to_string(),
+                span: TextSpan {file_id, ..TextSpan::default()},
+                message: format!("module {} is already registered by {}", own_module_id, self.file_id_to_file_name(*other))
+            })
+        }
+        
+        let tokens = match Self::tokenize_from_str(&source, TextPos::default(), file_id) {
+            Err(msg) => return Err(msg.into_live_file_error(file_name)),
+            Ok(lex_result) => lex_result
+        };
+        let live_type_infos: Vec<LiveTypeInfo> = self.live_type_infos.values().cloned().collect();
+        let mut parser = LiveParser::new_resolving_type_names(&tokens, &live_type_infos, file_id);
+        let mut original = match parser.parse_live_document() {
+            Err(msg) => return Err(msg.into_live_file_error(file_name)),
+            Ok(ld) => ld
+        };
+        original.tokens = tokens;
+        
+        if existing.is_some() {
+            self.live_files[file_id.to_index()].deps = self.collect_deps(own_module_id, &mut original);
+            let live_file = self.file_id_to_file_mut(file_id);
+            live_file.source = source;
+            live_file.original = original;
+            live_file.reexpand = true;
+            live_file.generation.next_gen();
+        }
+        else {
+            self.add_live_file(file_id, file_name, "", own_module_id, source, Vec::new(), TextPos::default(), original);
+        }
+        Ok(file_id)
+    }
+    
+    #[allow(clippy::too_many_arguments)]
+    fn add_live_file(
+        &mut self,
+        file_id: LiveFileId,
+        file_name: &str,
+        cargo_manifest_path: &str,
+        own_module_id: LiveModuleId,
+        source: String,
+        live_type_infos: Vec<LiveTypeInfo>,
+        start_pos: TextPos,
+        mut original: LiveOriginal,
+    ) {
         // update our live type info
         for live_type_info in &live_type_infos {
             if let Some(info) = self.live_type_infos.get(&live_type_info.live_type) {
@@ -760,6 +1063,31 @@ impl LiveRegistry {
             self.live_type_infos.insert(live_type_info.live_type, live_type_info.clone());
         }
         
+        let deps = self.collect_deps(own_module_id, &mut original);
+        
+        let live_file = LiveFile {
+            cargo_manifest_path: cargo_manifest_path.to_string(),
+            reexpand: true,
+            module_id: own_module_id,
+            file_name: file_name.to_string(),
+            start_pos,
+            deps,
+            source,
+            generation: LiveFileGeneration::default(),
+            live_type_infos,
+            original,
+            next_original: None,
+            expanded: LiveExpanded::new(),
+            overlay: None,
+        };
+        self.module_id_to_file_id.insert(own_module_id, file_id);
+        
+        self.file_ids.insert(file_name.to_string(), file_id);
+        self.live_files.push(live_file);
+    }
+    
+    // patches up crate imports and returns the modules a document depends on
+    fn collect_deps(&self, own_module_id: LiveModuleId, original: &mut LiveOriginal) -> BTreeSet<LiveModuleId> {
         let mut deps = BTreeSet::new();
         
         for node in &mut original.nodes {
@@ -798,27 +1126,42 @@ impl LiveRegistry {
                 }
             }
         }
-        
-        let live_file = LiveFile {
-            cargo_manifest_path: cargo_manifest_path.to_string(),
-            reexpand: true,
-            module_id: own_module_id,
-            file_name: file_name.to_string(),
-            start_pos,
-            deps,
-            source,
-            generation: LiveFileGeneration::default(),
-            live_type_infos,
-            original,
-            next_original: None,
-            expanded: LiveExpanded::new()
-        };
-        self.module_id_to_file_id.insert(own_module_id, file_id);
-        
-        self.file_ids.insert(file_name.to_string(), file_id);
-        self.live_files.push(live_file);
-        
-        Ok(file_id)
+        deps
+    }
+    
+    // the properties written in an expanded document that the rust type of their class doesn't
+    // have. Types that take any property, like the shader backed ones, are left alone
+    pub fn check_properties(&self, file_id: LiveFileId, errors: &mut Vec<LiveError>) {
+        fn has_field(info: &LiveTypeInfo, id: LiveId) -> bool {
+            info.live_ignore || info.fields.iter().any( | field | {
+                field.id == id || field.live_field_kind == LiveFieldKind::Deref && has_field(&field.live_type_info, id)
+            })
+        }
+        let nodes = &self.live_files[file_id.to_index()].expanded.nodes;
+        for (index, node) in nodes.iter().enumerate() {
+            let LiveValue::Class {live_type, ..} = &node.value else {
+                continue
+            };
+            let Some(info) = self.live_type_infos.get(live_type) else {
+                continue
+            };
+            let mut child = index + 1;
+            while child < nodes.len() && !nodes[child].value.is_close() {
+                let prop = &nodes[child];
+                // only what this document wrote, the classes it builds on are checked where they live
+                let token_id = prop.origin.token_id().filter( | token_id | token_id.file_id() == Some(file_id));
+                if let Some(token_id) = token_id {
+                    if prop.origin.prop_type() == LivePropType::Field && !prop.origin.node_has_prefix() && !has_field(info, prop.id) {
+                        errors.push(LiveError {
+                            origin: live_error_origin!(),
+                            span: self.token_id_to_span(token_id).into(),
+                            message: format!("{} has no property {}", info.type_name, prop.id)
+                        });
+                    }
+                }
+                child = nodes.skip_node(child);
+            }
+        }
     }
     
     pub fn expand_all_documents(&mut self, errors: &mut Vec<LiveError>) {
@@ -960,4 +1303,80 @@ impl FileDepIter {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::live_node::LiveTypeField,
+    };
+    
+    struct Value;
+    struct Label;
+    struct OtherLabel;
+    
+    fn info<T: 'static>(module: &str, name: &str, live_ignore: bool, fields: &[&str]) -> LiveTypeInfo {
+        let fields = fields.iter().map( | field | LiveTypeField {
+            id: LiveId::from_str(field),
+            live_type_info: info::<Value>("a::value", "Value", true, &[]),
+            live_field_kind: LiveFieldKind::Live
+        }).collect();
+        LiveTypeInfo::new(LiveModuleId::from_str(module).unwrap(), LiveType::of::<T>(), LiveId::from_str(name), live_ignore, fields)
+    }
+    
+    fn label_registry(other_label: bool) -> LiveRegistry {
+        let mut registry = LiveRegistry::default();
+        let mut infos = vec![info::<Label>("a::label", "Label", false, &["text"])];
+        let mut source = "Label = {{Label}} {text: \"\"}".to_string();
+        if other_label {
+            infos.push(info::<OtherLabel>("b::label", "Label", false, &["caption"]));
+            source.push_str(" OtherLabel = {{Label}} {}");
+        }
+        registry.register_live_file("a/label.rs", "", LiveModuleId::from_str("a::label").unwrap(), source, infos, TextPos::default()).ok().unwrap();
+        registry
+    }
+    
+    fn load(registry: &mut LiveRegistry, source: &str) -> Result<Vec<String>, String> {
+        let module_id = LiveModuleId::from_str("skin::main").unwrap();
+        let file_id = registry.register_live_source("skin.live", module_id, source.to_string()).map_err( | err | err.message) ?;
+        let mut errors = Vec::new();
+        registry.expand_all_documents(&mut errors);
+        registry.check_properties(file_id, &mut errors);
+        Ok(errors.into_iter().map( | err | err.message).collect())
+    }
+    
+    #[test]
+    fn type_by_name() {
+        let mut registry = label_registry(false);
+        assert_eq!(load(&mut registry, "Title = {{Label}} {text: \"hi\"}"), Ok(vec![]));
+    }
+    
+    #[test]
+    fn unknown_type() {
+        let mut registry = label_registry(false);
+        assert_eq!(load(&mut registry, "Title = {{Button}} {}"), Err("unknown rust type {{Button}}".to_string()));
+    }
+    
+    #[test]
+    fn ambiguous_type() {
+        let mut registry = label_registry(true);
+        assert_eq!(
+            load(&mut registry, "Title = {{Label}} {}"),
+            Err("rust type {{Label}} is ambiguous, it is in a::label, b::label. Write the module path like {{a::label::Label}}".to_string())
+        );
+    }
+    
+    #[test]
+    fn type_by_module_path() {
+        let mut registry = label_registry(true);
+        assert_eq!(load(&mut registry, "Title = {{b::label::Label}} {caption: \"hi\"}"), Ok(vec![]));
+        let mut other = label_registry(true);
+        assert_eq!(load(&mut other, "Title = {{c::label::Label}} {}"), Err("unknown rust type {{c::label::Label}}".to_string()));
+    }
+    
+    #[test]
+    fn unknown_property() {
+        let mut registry = label_registry(false);
+        assert_eq!(load(&mut registry, "Title = {{Label}} {text: \"hi\", colour: 1.0}"), Ok(vec!["Label has no property colour".to_string()]));
+    }
+}
@@ -1,6 +1,7 @@
 
 use {
     std::f64::consts::PI,
+    std::collections::HashMap,
     crate::{
         makepad_live_compiler::{
             LiveRef,
@@ -40,6 +41,11 @@ pub trait AnimatorImpl {
     }
     fn animator_cut_with_scope(&mut self, cx: &mut Cx, state: &[LiveId; 2], scope:&mut Scope);
     fn animator_play_with_scope(&mut self, cx: &mut Cx, state: &[LiveId; 2], scope:&mut Scope);
+    // shows the animation to a state as it is `time` seconds in, see Animator::seek_to_live
+    fn animator_seek(&mut self, cx: &mut Cx, state: &[LiveId; 2], time: f64){
+        self.animator_seek_with_scope(cx, state, time, &mut Scope::empty())
+    }
+    fn animator_seek_with_scope(&mut self, cx: &mut Cx, state: &[LiveId; 2], time: f64, scope:&mut Scope);
     fn animator_toggle_with_scope(&mut self, cx: &mut Cx, is_state_1: bool, animate: Animate, state1: &[LiveId; 2], state2: &[LiveId; 2], scope:&mut Scope) {
         if is_state_1 {
             if let Animate::Yes = animate {
@@ -108,6 +114,11 @@ pub enum Play {
     
     #[live {duration: 1.0, end: 1.0}]
     BounceLoop {duration: f64, end: f64},
+    
+    // moves to the last keyframe value like a damped spring, keyframe times and eases are not used.
+    // an interrupted spring goes on from where it is and how fast it moves
+    #[live {stiffness: 170.0, damping: 26.0, mass: 1.0}]
+    Spring {stiffness: f64, damping: f64, mass: f64},
 }
 //pub type StatePair = [LiveId; 2];
 
@@ -150,6 +161,7 @@ impl Play {
                 };
                 (false, local_time)
             },
+            Self::Spring {..} => (false, 1.0),
         }
     }
 }
@@ -539,8 +551,25 @@ pub struct Animator {
     pub live_ptr: LiveRef,
     pub state: Option<Vec<LiveNode >>,
     pub next_frame: NextFrame,
+    pub springs: HashMap<LiveId, SpringState>,
 }
 
+// the motion of one value animated by a Play::Spring, by the path of the value in the state
+#[derive(Clone, Debug)]
+pub struct SpringState {
+    pub track: LiveId,
+    pub position: [f64; 4],
+    pub velocity: [f64; 4],
+    pub last_time: f64,
+    pub at_rest: bool,
+}
+
+const SPRING_STEP: f64 = 1.0 / 240.0;
+const SPRING_MAX_DT: f64 = 0.1;
+// a seek integrates a spring from the start of its animation in one go
+const SPRING_SEEK_MAX_DT: f64 = 10.0;
+const SPRING_REST: f64 = 0.001;
+
 #[derive(Copy, Clone)]
 pub enum AnimatorAction {
     Animating {redraw: bool},
@@ -552,13 +581,13 @@ impl LiveNew for Animator {
     fn new(_cx: &mut Cx) -> Self {Self::default()}
     
     fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
-        LiveTypeInfo {
-            module_id: LiveModuleId::from_str(&module_path!()).unwrap(),
-            live_type: LiveType::of::<Self>(),
-            live_ignore: true,
-            fields: Vec::new(),
-            type_name: id_lut!(States)
-        }
+        LiveTypeInfo::new(
+            LiveModuleId::from_str(&module_path!()).unwrap(),
+            LiveType::of::<Self>(),
+            id_lut!(States),
+            true,
+            Vec::new()
+        )
     }
 }
 impl LiveApply for Animator {
@@ -621,60 +650,90 @@ impl Animator {
     pub fn handle_event(&mut self, cx: &mut Cx, event: &Event) -> AnimatorAction {
         
         if let Event::NextFrame(nf) = event {
-            if !nf.set.contains(&self.next_frame) {
+            if !nf.seek && !nf.set.contains(&self.next_frame) {
                 return AnimatorAction::None
             }
             if self.state.is_none() {
                 return AnimatorAction::None
             }
-            let state_nodes = self.state.as_mut().unwrap();
+            let (ended, redraw) = self.update_timelines(cx, nf.time, None, SPRING_MAX_DT);
+            if !ended {
+                self.next_frame = cx.new_next_frame();
+            }
             
-            let mut state_index = state_nodes.child_by_name(0, live_id!(state).as_field()).unwrap();
-            let mut stack_depth = 0;
-            let mut ended = true;
-            let mut redraw = false;
-            while state_index < state_nodes.len() {
-                let state_node = &state_nodes[state_index];
-                if state_node.is_array() {
-                    // ok so. lets compute our value and store it in the last slot
-                    let (play_ended, play_redraw) = Self::update_timeline_value(cx, state_index, state_nodes, nf.time);
-                    if !play_ended {
-                        ended = false;
-                    }
-                    if play_redraw {
-                        redraw = true;
-                    }
+            return AnimatorAction::Animating {redraw}
+        }
+        AnimatorAction::None
+    }
+    
+    // computes the values of the timelines, of one track or of all, at a time
+    fn update_timelines(&mut self, cx: &mut Cx, time: f64, only_track: Option<LiveId>, spring_max_dt: f64) -> (bool, bool) {
+        let state_nodes = self.state.as_mut().unwrap();
+        
+        let mut state_index = state_nodes.child_by_name(0, live_id!(state).as_field()).unwrap();
+        let mut stack_depth = 0;
+        let mut ended = true;
+        let mut redraw = false;
+        let mut path = Vec::new();
+        while state_index < state_nodes.len() {
+            let state_node = &state_nodes[state_index];
+            if state_node.is_array() {
+                if only_track.is_some_and( | track | state_nodes[state_index + 1].value != LiveValue::Id(track)) {
                     state_index = state_nodes.skip_node(state_index);
+                    continue;
                 }
-                else { // we have to create a timeline ourselves
-                    if state_node.value.is_open() {
-                        stack_depth += 1;
-                        state_index += 1;
-                    }
-                    else if state_node.value.is_close() {
-                        stack_depth -= 1;
-                        state_index += 1;
-                        if stack_depth == 0 {
-                            break;
-                        }
-                    }
-                    else {
-                        state_index = state_nodes.skip_node(state_index);
+                // ok so. lets compute our value and store it in the last slot
+                let key = Self::spring_key(&path).id_append(state_node.id);
+                let (play_ended, play_redraw) = Self::update_timeline_value(cx, state_index, state_nodes, time, &mut self.springs, key, spring_max_dt);
+                if !play_ended {
+                    ended = false;
+                }
+                if play_redraw {
+                    redraw = true;
+                }
+                state_index = state_nodes.skip_node(state_index);
+            }
+            else { // we have to create a timeline ourselves
+                if state_node.value.is_open() {
+                    path.push(state_node.id);
+                    stack_depth += 1;
+                    state_index += 1;
+                }
+                else if state_node.value.is_close() {
+                    path.pop();
+                    stack_depth -= 1;
+                    state_index += 1;
+                    if stack_depth == 0 {
+                        break;
                     }
                 }
+                else {
+                    state_index = state_nodes.skip_node(state_index);
+                }
             }
-            //println!("{}", state_nodes.to_string(0,100));
-            if !ended {
-                self.next_frame = cx.new_next_frame();
+        }
+        // a spring track has ended once all of its values came to rest
+        let in_pass = | spring: &SpringState | only_track.map_or(true, | track | spring.track == track);
+        let mut spring_tracks: Vec<(LiveId, bool)> = Vec::new();
+        for spring in self.springs.values().filter( | spring | in_pass(spring) && spring.last_time == time) {
+            match spring_tracks.iter_mut().find( | (track, _) | *track == spring.track) {
+                Some((_, at_rest)) => *at_rest &= spring.at_rest,
+                None => spring_tracks.push((spring.track, spring.at_rest))
             }
-            
-            return AnimatorAction::Animating {redraw}
         }
-        AnimatorAction::None
+        self.springs.retain( | _, spring | !in_pass(spring) || spring.last_time == time && !spring.at_rest);
+        for (track, at_rest) in spring_tracks {
+            if at_rest {
+                if let Some(index) = state_nodes.child_by_path(0, &[live_id!(tracks).as_field(), track.as_field(), live_id!(ended).as_field()]) {
+                    state_nodes[index].value = LiveValue::Int64(cx.event_id as i64);
+                }
+            }
+        }
+        (ended, redraw)
     }
     
     // this find the last keyframe value from an array node
-    pub fn update_timeline_value(cx: &mut Cx, index: usize, nodes: &mut [LiveNode], ext_time: f64) -> (bool, bool) {
+    pub fn update_timeline_value(cx: &mut Cx, index: usize, nodes: &mut [LiveNode], ext_time: f64, springs: &mut HashMap<LiveId, SpringState>, key: LiveId, spring_max_dt: f64) -> (bool, bool) {
         // OK so. we have an array with keyframes
         if nodes[index].is_array() {
             let mut node_iter = nodes.first_child(index);
@@ -710,7 +769,28 @@ impl Animator {
                     };
                     node_iter = nodes.next_child(id_index);
                     
-                    let (ended, time) = play.get_ended_time(ext_time - start_time);
+                    let redraw = if let Some(index) = nodes.child_by_name(track_index, live_id!(redraw).as_field()) {
+                        if let LiveValue::Bool(redraw) = &nodes[index].value {
+                            *redraw
+                        }else {false}
+                    }else {false};
+                    
+                    // springs mark their track as ended in handle_event, when all its values rest
+                    if let Play::Spring {stiffness, damping, mass} = play {
+                        let spring = springs.entry(key).or_insert_with( || SpringState {
+                            track: track_id,
+                            position: [0.0; 4],
+                            velocity: [0.0; 4],
+                            last_time: start_time,
+                            at_rest: true,
+                        });
+                        let ended = Self::update_spring_value(index, nodes, ext_time, spring, stiffness, damping, mass, spring_max_dt);
+                        return (ended, redraw)
+                    }
+                    springs.remove(&key);
+                    
+                    // the clock can be moved back to before the animation started
+                    let (ended, time) = play.get_ended_time((ext_time - start_time).max(0.0));
                     
                     if ended { // mark ended step 1
                         if let Some(index) = nodes.child_by_name(track_index, live_id!(ended).as_field()) {
@@ -718,12 +798,6 @@ impl Animator {
                         }
                     }
                     
-                    let redraw = if let Some(index) = nodes.child_by_name(track_index, live_id!(redraw).as_field()) {
-                        if let LiveValue::Bool(redraw) = &nodes[index].value {
-                            *redraw
-                        }else {false}
-                    }else {false};
-                    
                     (ended, time, redraw, track_id)
                 }
                 else {panic!()}
@@ -848,6 +922,88 @@ impl Animator {
     }
     
     
+    fn spring_key(path: &[LiveId]) -> LiveId {
+        path.iter().fold(LiveId(0), | key, id | key.id_append(*id))
+    }
+    
+    // moves the value in the last slot of a timeline towards the last keyframe, returns true when it rests.
+    // at most max_dt of time is integrated, so a frame hitch doesn't run thousands of steps
+    fn update_spring_value(index: usize, nodes: &mut [LiveNode], ext_time: f64, spring: &mut SpringState, stiffness: f64, damping: f64, mass: f64, max_dt: f64) -> bool {
+        let Some(current_index) = nodes.last_child(index) else {return true};
+        let mut target_index = None;
+        let mut child = nodes.next_child(nodes.first_child(index).unwrap());
+        while let Some(child_index) = child {
+            if child_index == current_index {
+                break;
+            }
+            target_index = Some(child_index);
+            child = nodes.next_child(child_index);
+        }
+        let Some(mut target_index) = target_index else {return true};
+        if nodes[target_index].value.is_object() {
+            let Some(value_index) = nodes.child_by_name(target_index, live_id!(value).as_field()) else {return true};
+            target_index = value_index;
+        }
+        let target_value = nodes[target_index].value.clone();
+        let (Some(target), Some(current)) = (Self::spring_vector(&target_value), Self::spring_vector(&nodes[current_index].value)) else {
+            // ids and such can't move, they just switch
+            nodes[current_index].value = target_value;
+            return true
+        };
+        // a spring that rested starts from the value, a moving one keeps its own more precise position
+        if spring.at_rest {
+            spring.position = current;
+        }
+        let dt = (ext_time - spring.last_time).clamp(0.0, max_dt);
+        let steps = (dt / SPRING_STEP).ceil().max(1.0);
+        let step = dt / steps;
+        for _ in 0..steps as usize {
+            for i in 0..4 {
+                let force = -stiffness * (spring.position[i] - target[i]) - damping * spring.velocity[i];
+                spring.velocity[i] += force / mass.max(0.0001) * step;
+                spring.position[i] += spring.velocity[i] * step;
+            }
+        }
+        spring.last_time = ext_time;
+        spring.at_rest = (0..4).all( | i | (spring.position[i] - target[i]).abs() < SPRING_REST && spring.velocity[i].abs() < SPRING_REST);
+        if spring.at_rest {
+            spring.position = target;
+            spring.velocity = [0.0; 4];
+        }
+        nodes[current_index].value = Self::spring_value(&target_value, spring.position);
+        spring.at_rest
+    }
+    
+    fn spring_vector(value: &LiveValue) -> Option<[f64; 4]> {
+        match value {
+            LiveValue::Int64(v) => Some([*v as f64, 0.0, 0.0, 0.0]),
+            LiveValue::Float64(v) => Some([*v, 0.0, 0.0, 0.0]),
+            LiveValue::Vec2(v) => Some([v.x as f64, v.y as f64, 0.0, 0.0]),
+            LiveValue::Vec3(v) => Some([v.x as f64, v.y as f64, v.z as f64, 0.0]),
+            LiveValue::Vec4(v) => Some([v.x as f64, v.y as f64, v.z as f64, v.w as f64]),
+            LiveValue::Color(c) => {
+                let v = Vec4::from_u32(*c);
+                Some([v.x as f64, v.y as f64, v.z as f64, v.w as f64])
+            }
+            _ => None
+        }
+    }
+    
+    fn spring_value(like: &LiveValue, p: [f64; 4]) -> LiveValue {
+        match like {
+            LiveValue::Int64(_) => LiveValue::Int64(p[0].round() as i64),
+            LiveValue::Vec2(_) => LiveValue::Vec2(vec2(p[0] as f32, p[1] as f32)),
+            LiveValue::Vec3(_) => LiveValue::Vec3(vec3(p[0] as f32, p[1] as f32, p[2] as f32)),
+            LiveValue::Vec4(_) => LiveValue::Vec4(vec4(p[0] as f32, p[1] as f32, p[2] as f32, p[3] as f32)),
+            // a spring overshoots, which a color can't
+            LiveValue::Color(_) => {
+                let c = | v: f64 | v.clamp(0.0, 1.0) as f32;
+                LiveValue::Color(vec4(c(p[0]), c(p[1]), c(p[2]), c(p[3])).to_u32())
+            }
+            _ => LiveValue::Float64(p[0])
+        }
+    }
+    
     pub fn last_keyframe_value_from_array(index: usize, nodes: &[LiveNode]) -> Option<usize> {
         if let Some(index) = nodes.last_child(index) {
             if nodes[index].value.is_object() {
@@ -954,6 +1110,7 @@ impl Animator {
         state.replace_or_insert_last_node_by_path(0, &[live_id!(tracks).as_field(), track.as_field()], live_object!{
             [track]: {state_id: (state_pair[1]), ended: 1}
         });
+        self.springs.retain( | _, spring | spring.track != track);
         
         let mut path = Vec::new();
         path.push(live_id!(state).as_field());
@@ -1048,6 +1205,56 @@ impl Animator {
         }
     }
     
+    // puts a track `time` seconds into its animation to state_pair, from the state it was in before.
+    // seeking again to the same state starts from that same state, so a timeline can be scrubbed.
+    // the animation does not run on by itself, the values only change with the next seek
+    pub fn seek_to_live(&mut self, cx: &mut Cx, state_pair: &[LiveId; 2], time: f64) {
+        if let Some(live_ptr) = self.live_ptr {
+            let live_registry_rc = cx.live_registry.clone();
+            let live_registry = live_registry_rc.borrow();
+            if live_registry.generation_valid(live_ptr) {
+                let (nodes, index) = live_registry.ptr_to_nodes_index(live_ptr);
+                
+                self.init_as_needed(cx, index, nodes);
+                
+                let track = state_pair[0];
+                let state = self.state.as_ref().unwrap();
+                let track_path = [live_id!(tracks).as_field(), track.as_field()];
+                let state_id = state.child_by_path(0, &track_path).and_then( | index | state.child_value_by_path(index, &[live_id!(state_id).as_field()]));
+                let seek_from = state.child_by_path(0, &track_path).and_then( | index | state.child_value_by_path(index, &[live_id!(seek_from).as_field()]));
+                let from_id = match (state_id, seek_from) {
+                    (Some(LiveValue::Id(state_id)), Some(LiveValue::Id(seek_from))) if *state_id == state_pair[1] => *seek_from,
+                    (Some(LiveValue::Id(state_id)), _) => *state_id,
+                    _ => {
+                        error!("seek_to_live {} has no state", track);
+                        return
+                    }
+                };
+                let from_index = nodes.child_by_path(index, &[track.as_instance(), from_id.as_instance()]);
+                let to_index = nodes.child_by_path(index, &[track.as_instance(), state_pair[1].as_instance()]);
+                let (Some(from_index), Some(to_index)) = (from_index, to_index) else {
+                    error!("seek_to_live {}.{} not found", state_pair[0], state_pair[1]);
+                    return
+                };
+                self.cut_to(cx, &[track, from_id], from_index, nodes);
+                self.animate_to(cx, state_pair, to_index, nodes);
+                
+                let state = self.state.as_mut().unwrap();
+                if let Some(index) = state.child_by_path(0, &[live_id!(tracks).as_field(), track.as_field(), live_id!(time).as_field()]) {
+                    state[index].value = LiveValue::Float64(0.0);
+                }
+                state.replace_or_insert_last_node_by_path(0, &[live_id!(tracks).as_field(), track.as_field(), live_id!(seek_from).as_field()], live_object!{
+                    seek_from: (from_id)
+                });
+                self.update_timelines(cx, time, Some(track), SPRING_SEEK_MAX_DT);
+                self.next_frame = NextFrame::default();
+            }
+            else {
+                error!("seek_to_live generation invalid");
+            }
+        }
+    }
+    
     pub fn animate_to(&mut self, cx: &mut Cx, state_pair: &[LiveId; 2], index: usize, nodes: &[LiveNode]) {
         
         if let Some(index) = nodes.child_by_name(index, live_id!(cursor).as_field()) {
@@ -1224,3 +1431,285 @@ impl Animator {
     }
     
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        std::{rc::Rc, cell::RefCell, collections::HashSet},
+        crate::{
+            makepad_live_compiler::{LiveNode, LiveNodeSliceApi, LiveNodeVecApi, LiveIdAsProp, LiveValue},
+            makepad_live_id::*,
+            makepad_math::*,
+            animator::{Animator, SpringState, SPRING_MAX_DT},
+            event::{Event, NextFrameEvent},
+            cx::Cx,
+            live_traits::LiveNew,
+        }
+    };
+    
+    const ANIM: &str = "
+        Anim = {
+            hover = {
+                default: off
+                off = {
+                    from: {all: Forward {duration: 1.0}}
+                    apply: {x: 0.0}
+                }
+                on = {
+                    from: {all: Forward {duration: 1.0}}
+                    apply: {x: 10.0}
+                }
+            }
+            spring = {
+                default: off
+                off = {
+                    from: {all: Spring {}}
+                    apply: {y: 0.0}
+                }
+                on = {
+                    from: {all: Spring {}}
+                    apply: {y: 10.0}
+                }
+            }
+        }
+    ";
+    
+    fn animator(cx: &mut Cx) -> Animator {
+        let module_id = cx.load_live_source("animator_test.rs", "animator_test", ANIM.to_string())
+            .unwrap_or_else( | errs | panic!("{}", errs[0]));
+        let live_ptr = cx.live_registry.borrow().module_id_and_name_to_ptr(module_id, live_id!(Anim));
+        Animator::new_from_ptr(cx, live_ptr)
+    }
+    
+    fn value(animator: &Animator, id: LiveId) -> f64 {
+        let state = animator.state.as_ref().unwrap();
+        let index = state.child_by_path(0, &[live_id!(state).as_field(), id.as_field()]).unwrap();
+        state[state.last_child(index).unwrap()].value.as_float().unwrap()
+    }
+    
+    fn value_x(animator: &Animator) -> f64 {
+        value(animator, live_id!(x))
+    }
+    
+    fn frame(cx: &mut Cx, animator: &mut Animator, time: f64) {
+        let mut set = HashSet::new();
+        set.insert(animator.next_frame);
+        animator.handle_event(cx, &Event::NextFrame(NextFrameEvent {frame: 0, time, set, seek: false}));
+    }
+    
+    // a timeline from `from` to `to` with its current value in the last slot
+    fn timeline(from: LiveValue, to: LiveValue) -> Vec<LiveNode> {
+        let mut nodes = Vec::new();
+        nodes.open_array(LiveId(0));
+        nodes.push_id(LiveId(0), live_id!(spring));
+        nodes.push(LiveNode::from_value(from.clone()));
+        nodes.push(LiveNode::from_value(to));
+        nodes.push(LiveNode::from_value(from));
+        nodes.close();
+        nodes
+    }
+    
+    fn spring_state() -> SpringState {
+        SpringState {track: live_id!(spring), position: [0.0; 4], velocity: [0.0; 4], last_time: 0.0, at_rest: true}
+    }
+    
+    // a cx that records the time of the NextFrame events it gets
+    fn cx_with_frame_times() -> (Cx, Rc<RefCell<Vec<f64>>>) {
+        let times = Rc::new(RefCell::new(Vec::new()));
+        let times_in = times.clone();
+        let cx = Cx::new(Box::new(move | _, event | if let Event::NextFrame(nf) = event {
+            times_in.borrow_mut().push(nf.time);
+        }));
+        (cx, times)
+    }
+    
+    #[test]
+    fn virtual_time_replaces_frame_time() {
+        let (mut cx, times) = cx_with_frame_times();
+        cx.last_frame_time = 2.0;
+        cx.new_next_frame();
+        cx.advance_virtual_time(0.5);
+        assert_eq!(cx.virtual_time(), Some(2.5));
+        cx.new_next_frame();
+        cx.call_next_frame_event(10.0);
+        cx.set_virtual_time(None);
+        cx.new_next_frame();
+        cx.call_next_frame_event(11.0);
+        assert_eq!(*times.borrow(), vec![2.5, 2.5, 11.0]);
+    }
+    
+    #[test]
+    fn advance_virtual_time_without_waiting_frames() {
+        let (mut cx, times) = cx_with_frame_times();
+        cx.set_virtual_time(Some(1.0));
+        cx.advance_virtual_time(0.25);
+        cx.advance_virtual_time(0.25);
+        assert_eq!(cx.virtual_time(), Some(1.5));
+        assert!(times.borrow().is_empty());
+    }
+    
+    #[test]
+    fn resuming_the_animation_clock_carries_on_from_its_time() {
+        let (mut cx, times) = cx_with_frame_times();
+        cx.last_frame_time = 100.0;
+        cx.set_animation_clock(true, 0.0);
+        cx.set_animation_clock(true, 2.0);
+        assert_eq!(cx.virtual_time(), Some(102.0));
+        // real time went on while paused
+        cx.last_frame_time = 150.0;
+        cx.set_animation_clock(false, 0.0);
+        assert_eq!(cx.virtual_time(), None);
+        cx.new_next_frame();
+        cx.call_next_frame_event(151.0);
+        // pausing again starts from the animation time, not the real one
+        cx.set_animation_clock(true, 0.0);
+        assert_eq!(*times.borrow(), vec![103.0]);
+        assert_eq!(cx.virtual_time(), Some(103.0));
+    }
+    
+    #[test]
+    fn seek_to_live_scrubs_from_the_previous_state() {
+        let mut cx = Cx::new(Box::new( | _, _ | ()));
+        let mut animator = animator(&mut cx);
+        animator.seek_to_live(&mut cx, id!(hover.on), 0.5);
+        assert_eq!(value_x(&animator), 5.0);
+        animator.seek_to_live(&mut cx, id!(hover.on), 0.25);
+        assert_eq!(value_x(&animator), 2.5);
+        animator.seek_to_live(&mut cx, id!(hover.on), 2.0);
+        assert_eq!(value_x(&animator), 10.0);
+        assert!(animator.animator_in_state(&cx, id!(hover.on)));
+    }
+    
+    #[test]
+    fn seek_frames_move_finished_animations() {
+        let mut cx = Cx::new(Box::new( | _, _ | ()));
+        let mut animator = animator(&mut cx);
+        animator.animate_to_live(&mut cx, id!(hover.on));
+        frame(&mut cx, &mut animator, 10.0);
+        frame(&mut cx, &mut animator, 12.0);
+        assert_eq!(value_x(&animator), 10.0);
+        let mut seek = | cx: &mut Cx, time, seek | {
+            animator.handle_event(cx, &Event::NextFrame(NextFrameEvent {frame: 0, time, set: HashSet::new(), seek}));
+            value_x(&animator)
+        };
+        // a finished animation doesn't wait for frames anymore
+        assert_eq!(seek(&mut cx, 10.5, false), 10.0);
+        assert_eq!(seek(&mut cx, 10.5, true), 5.0);
+        // before the animation started it is at its start
+        assert_eq!(seek(&mut cx, 9.0, true), 0.0);
+        assert_eq!(seek(&mut cx, 11.0, true), 10.0);
+    }
+    
+    #[test]
+    fn moving_the_animation_clock_sends_a_seek_frame() {
+        let seeks = Rc::new(RefCell::new(Vec::new()));
+        let seeks_in = seeks.clone();
+        let mut cx = Cx::new(Box::new(move | _, event | if let Event::NextFrame(nf) = event {
+            seeks_in.borrow_mut().push((nf.time, nf.seek));
+        }));
+        cx.last_frame_time = 100.0;
+        cx.set_animation_clock(true, -1.5);
+        cx.call_next_frame_event(101.0);
+        cx.new_next_frame();
+        cx.call_next_frame_event(102.0);
+        assert_eq!(*seeks.borrow(), vec![(98.5, true), (98.5, false)]);
+    }
+    
+    #[test]
+    fn advancing_virtual_time_is_not_a_frame() {
+        let (mut cx, times) = cx_with_frame_times();
+        cx.last_frame_time = 2.0;
+        cx.new_next_frame();
+        cx.advance_virtual_time(0.5);
+        assert_eq!(*times.borrow(), vec![2.5]);
+        assert_eq!(cx.last_frame_time, 2.0);
+    }
+    
+    #[test]
+    fn seek_to_live_back_to_the_start_state() {
+        let mut cx = Cx::new(Box::new( | _, _ | ()));
+        let mut animator = animator(&mut cx);
+        animator.seek_to_live(&mut cx, id!(hover.on), 1.0);
+        animator.seek_to_live(&mut cx, id!(hover.off), 0.5);
+        assert_eq!(value_x(&animator), 5.0);
+        animator.seek_to_live(&mut cx, id!(hover.off), 1.0);
+        assert_eq!(value_x(&animator), 0.0);
+    }
+    
+    #[test]
+    fn spring_comes_to_rest_on_the_target() {
+        let mut nodes = timeline(LiveValue::Float64(0.0), LiveValue::Float64(10.0));
+        let mut spring = spring_state();
+        let mut frames = 0;
+        while !Animator::update_spring_value(0, &mut nodes, (frames + 1) as f64 / 60.0, &mut spring, 170.0, 26.0, 1.0, SPRING_MAX_DT) {
+            frames += 1;
+            assert!(frames < 600, "spring did not come to rest");
+        }
+        assert!(frames > 10);
+        assert_eq!(nodes[4].value, LiveValue::Float64(10.0));
+        assert_eq!(spring.velocity, [0.0; 4]);
+    }
+    
+    #[test]
+    fn spring_keeps_the_value_type() {
+        let mut nodes = timeline(LiveValue::Int64(0), LiveValue::Int64(10));
+        let mut spring = spring_state();
+        Animator::update_spring_value(0, &mut nodes, 0.05, &mut spring, 170.0, 26.0, 1.0, SPRING_MAX_DT);
+        assert!(matches!(nodes[4].value, LiveValue::Int64(v) if v > 0 && v < 10));
+        // an underdamped spring overshoots white, the color stays white
+        let white = vec4(1.0, 1.0, 1.0, 1.0).to_u32();
+        let mut nodes = timeline(LiveValue::Color(vec4(0.0, 0.0, 0.0, 1.0).to_u32()), LiveValue::Color(white));
+        let mut spring = spring_state();
+        let mut overshot = false;
+        for frame in 1..60 {
+            Animator::update_spring_value(0, &mut nodes, frame as f64 / 60.0, &mut spring, 300.0, 2.0, 1.0, SPRING_MAX_DT);
+            overshot |= spring.position[0] > 1.0;
+            let LiveValue::Color(c) = nodes[4].value else {panic!()};
+            assert!(Vec4::from_u32(c).x <= 1.0);
+        }
+        assert!(overshot);
+    }
+    
+    #[test]
+    fn spring_frame_steps_are_clamped() {
+        let mut nodes = timeline(LiveValue::Float64(0.0), LiveValue::Float64(10.0));
+        let mut spring = spring_state();
+        Animator::update_spring_value(0, &mut nodes, 1.0 / 60.0, &mut spring, 170.0, 26.0, 1.0, SPRING_MAX_DT);
+        let after_one = spring.position[0];
+        // a one second hitch moves it as far as SPRING_MAX_DT does
+        let mut nodes = timeline(LiveValue::Float64(0.0), LiveValue::Float64(10.0));
+        let mut hitch = spring_state();
+        Animator::update_spring_value(0, &mut nodes, 1.0, &mut hitch, 170.0, 26.0, 1.0, SPRING_MAX_DT);
+        let mut nodes = timeline(LiveValue::Float64(0.0), LiveValue::Float64(10.0));
+        let mut max = spring_state();
+        Animator::update_spring_value(0, &mut nodes, SPRING_MAX_DT, &mut max, 170.0, 26.0, 1.0, SPRING_MAX_DT);
+        assert!(after_one < hitch.position[0]);
+        assert_eq!(hitch.position, max.position);
+    }
+    
+    #[test]
+    fn spring_keeps_its_velocity_when_retargeted() {
+        let mut cx = Cx::new(Box::new( | _, _ | ()));
+        let mut animator = animator(&mut cx);
+        animator.animate_to_live(&mut cx, id!(spring.on));
+        let mut time = 0.0;
+        for _ in 0..6 {
+            frame(&mut cx, &mut animator, time);
+            time += 1.0 / 60.0;
+        }
+        let before = value(&animator, live_id!(y));
+        let velocity = animator.springs.values().next().unwrap().velocity[0];
+        assert!(before > 0.0 && velocity > 0.0);
+        // going back to 0 it first slows down, it doesn't turn around at once
+        animator.animate_to_live(&mut cx, id!(spring.off));
+        assert_eq!(animator.springs.values().next().unwrap().velocity[0], velocity);
+        frame(&mut cx, &mut animator, time);
+        assert!(value(&animator, live_id!(y)) > before);
+        for _ in 0..600 {
+            time += 1.0 / 60.0;
+            frame(&mut cx, &mut animator, time);
+        }
+        assert_eq!(value(&animator, live_id!(y)), 0.0);
+        assert!(animator.springs.is_empty());
+    }
+}
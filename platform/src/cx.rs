@@ -14,7 +14,8 @@ use {
         action::{ActionSendSync,ACTION_SENDER_GLOBAL},
         makepad_live_compiler::{
             LiveRegistry,
-            LiveFileChange
+            LiveFileChange,
+            LiveModuleId,
         },
         makepad_shader_compiler::ShaderRegistry,
         draw_shader::CxDrawShaders,
@@ -32,7 +33,8 @@ use {
             NextFrame,
         },
         action::ActionsBuf,
-        cx_api::CxOsOp,
+        cx_api::{CxOsOp, ColorScheme},
+        live_cx::ColorSchemeOverlays,
         area::Area,
         gpu_info::GpuInfo,
         window::CxWindowPool,
@@ -77,6 +79,11 @@ pub struct Cx {
     pub (crate) event_id: u64,
     pub (crate) timer_id: u64,
     pub (crate) next_frame_id: u64,
+    pub (crate) virtual_time: Option<f64>,
+    pub (crate) last_frame_time: f64,
+    pub (crate) animation_pause_time: Option<f64>,
+    pub (crate) animation_seek: bool,
+    pub (crate) animation_time_offset: f64,
     
     pub keyboard: CxKeyboard,
     pub fingers: CxFingers,
@@ -98,6 +105,15 @@ pub struct Cx {
     pub (crate) live_file_change_receiver: std::sync::mpsc::Receiver<Vec<LiveFileChange>>,
     pub (crate) live_file_change_sender: std::sync::mpsc::Sender<Vec<LiveFileChange >>,
     
+    pub (crate) live_overlay_changes: Vec<(LiveModuleId, Option<LiveModuleId>)>,
+    pub (crate) color_scheme_receiver: std::sync::mpsc::Receiver<ColorScheme>,
+    pub (crate) color_scheme_sender: std::sync::mpsc::Sender<ColorScheme>,
+    pub (crate) color_scheme_overlays: Option<ColorSchemeOverlays>,
+    pub (crate) locale_changed: bool,
+    // the last LiveEdit only switched the locale, the live documents are as they were
+    pub (crate) locale_only_live_edit: bool,
+    pub (crate) live_source_changed: bool,
+    
     pub (crate) action_receiver: std::sync::mpsc::Receiver<ActionSendSync>,
     
     pub shader_registry: ShaderRegistry,
@@ -228,6 +244,7 @@ impl Cx {
         
         let (executor, spawner) = executor::new_executor_and_spawner();
         let (live_file_change_sender, live_file_change_receiver) = std::sync::mpsc::channel();
+        let (color_scheme_sender, color_scheme_receiver) = std::sync::mpsc::channel();
         let (action_sender, action_receiver) = std::sync::mpsc::channel();
         if let Ok(mut sender) = ACTION_SENDER_GLOBAL.lock(){
             *sender = Some(action_sender);
@@ -260,6 +277,11 @@ impl Cx {
             repaint_id: 1,
             timer_id: 1,
             next_frame_id: 1,
+            virtual_time: None,
+            last_frame_time: 0.0,
+            animation_pause_time: None,
+            animation_seek: false,
+            animation_time_offset: 0.0,
             
             keyboard: Default::default(),
             fingers: Default::default(),
@@ -278,6 +300,13 @@ impl Cx {
             
             live_file_change_receiver,
             live_file_change_sender,
+            live_overlay_changes: Vec::new(),
+            color_scheme_receiver,
+            color_scheme_sender,
+            color_scheme_overlays: None,
+            locale_changed: false,
+            locale_only_live_edit: false,
+            live_source_changed: false,
             action_receiver,
             
             shader_registry: ShaderRegistry::new(true),
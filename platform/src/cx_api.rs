@@ -26,6 +26,14 @@ pub enum OpenUrlInPlace{
     Yes,
     No
 }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorScheme{
+    NoPreference,
+    Dark,
+    Light
+}
+
 pub trait CxOsApi {
     fn init_cx_os(&mut self);
 
@@ -44,6 +52,9 @@ pub trait CxOsApi {
     
     fn default_window_size(&self)->DVec2{dvec2(800.,600.)}
     
+    // reports the OS dark/light preference on color_scheme_sender, now and whenever it changes
+    fn start_color_scheme_watcher(&mut self){}
+    
     /*
     fn web_socket_open(&mut self, url: String, rec: WebSocketAutoReconnect) -> WebSocket;
     fn web_socket_send(&mut self, socket: WebSocket, data: Vec<u8>);*/
@@ -449,6 +460,29 @@ impl Cx {
         self.keyboard.has_key_focus(focus_area)
     }
 
+    /// Replaces the time NextFrame events carry, and so the clock animations run on, with a
+    /// virtual one that only moves when it is set or advanced. `None` goes back to real time.
+    pub fn set_virtual_time(&mut self, time: Option<f64>) {
+        self.virtual_time = time;
+    }
+    
+    pub fn virtual_time(&self) -> Option<f64> {
+        self.virtual_time
+    }
+    
+    /// Moves the virtual clock on by `dt` seconds, starting it at the last frame time if it wasn't
+    /// set, and delivers the NextFrame events that are waiting so animations step to the new time.
+    ///
+    /// This calls the event handler of the app, so it can't be called while handling an event.
+    /// It is meant for tests that drive the app from outside.
+    pub fn advance_virtual_time(&mut self, dt: f64) {
+        let time = self.virtual_time.unwrap_or(self.last_frame_time + self.animation_time_offset) + dt;
+        self.virtual_time = Some(time);
+        if !self.new_next_frames.is_empty() {
+            self.dispatch_next_frame_event(time);
+        }
+    }
+    
     pub fn new_next_frame(&mut self) -> NextFrame {
         let res = NextFrame(self.next_frame_id);
         self.next_frame_id += 1;
@@ -105,13 +105,13 @@ impl LiveNew for DrawVars {
     }
     
     fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
-        LiveTypeInfo {
-            module_id: LiveModuleId::from_str(&module_path!()).unwrap(),
-            live_type: std::any::TypeId::of::<Self>(),
-            live_ignore: true,
-            fields: Vec::new(),
-            type_name: id_lut!(DrawVars)
-        }
+        LiveTypeInfo::new(
+            LiveModuleId::from_str(&module_path!()).unwrap(),
+            std::any::TypeId::of::<Self>(),
+            id_lut!(DrawVars),
+            true,
+            Vec::new()
+        )
     }
 }
 
@@ -442,7 +442,10 @@ pub enum VirtualKeyboardEvent{
 pub struct NextFrameEvent {
     pub frame: u64,
     pub time: f64,
-    pub set: HashSet<NextFrame>
+    pub set: HashSet<NextFrame>,
+    // the animation clock was moved, every animator recomputes its values at `time`, also the
+    // ones that weren't waiting for this frame
+    pub seek: bool,
 }
 
 #[derive(Clone, Debug)]
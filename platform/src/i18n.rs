@@ -0,0 +1,66 @@
+use {
+    std::sync::Mutex,
+    crate::{
+        makepad_i18n::{Localization, Catalog, FluentValue, format_number, format_date},
+        cx::Cx,
+    },
+};
+
+// global so tr! works without a Cx, from threads as well
+static LOCALIZATION: Mutex<Localization> = Mutex::new(Localization::new());
+
+// the message id formatted in the current locale, tr! is the short way to call this
+pub fn tr(id: &str, args: &[(&str, FluentValue)]) -> String {
+    LOCALIZATION.lock().unwrap().format(id, args)
+}
+
+#[macro_export]
+macro_rules!tr {
+    ( $ id: expr) => {
+        $crate::i18n::tr( $ id, &[])
+    };
+    ( $ id: expr, $ ( $ arg: ident: $ value: expr), + $ (,) ?) => {
+        $crate::i18n::tr( $ id, &[ $ ((stringify!( $ arg), $crate::makepad_i18n::FluentValue::from( $ value))), +])
+    };
+}
+
+impl Cx {
+    // adds the messages of a catalog in the Fluent syntax for locale, replacing what it had
+    pub fn add_message_catalog(&mut self, locale: &str, source: &str) -> Result<(), String> {
+        let catalog = Catalog::parse(source).map_err(|e| format!("Message catalog {}: {}", locale, e))?;
+        LOCALIZATION.lock().unwrap().add_catalog(locale, catalog);
+        Ok(())
+    }
+    
+    // adds a catalog file that a live_design loads with dep()
+    pub fn load_message_catalog(&mut self, locale: &str, dep_path: &str) -> Result<(), String> {
+        let data = self.get_dependency(dep_path)?;
+        let source = std::str::from_utf8(&data).map_err(|_| format!("Message catalog {} is not utf8", dep_path))?;
+        self.add_message_catalog(locale, source)
+    }
+    
+    pub fn locale(&self) -> String {
+        LOCALIZATION.lock().unwrap().locale().to_string()
+    }
+    
+    // switches the locale and applies every tr("..") in the main live_design again, so they
+    // pick up the new language
+    pub fn set_locale(&mut self, locale: &str) {
+        if LOCALIZATION.lock().unwrap().set_locale(locale) {
+            self.locale_changed = true;
+        }
+    }
+    
+    // where messages missing from a catalog come from
+    pub fn set_fallback_locale(&mut self, locale: &str) {
+        LOCALIZATION.lock().unwrap().set_fallback(locale);
+    }
+    
+    pub fn format_number(&self, value: f64, min_fraction: usize, max_fraction: usize) -> String {
+        format_number(&self.locale(), value, min_fraction, max_fraction)
+    }
+    
+    pub fn format_date(&self, unix_seconds: i64) -> String {
+        format_date(&self.locale(), unix_seconds)
+    }
+}
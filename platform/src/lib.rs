@@ -51,6 +51,9 @@ pub mod audio_stream;
 
 pub mod file_dialogs;
 
+#[macro_use]
+pub mod i18n;
+
 mod media_api;
 
 #[macro_use]
@@ -66,6 +69,7 @@ pub use makepad_objc_sys;
 pub use ::windows as windows;
 
 pub use makepad_futures;
+pub use makepad_i18n;
  
 pub use {
     makepad_shader_compiler,
@@ -111,6 +115,7 @@ pub use {
         LiveNodeSliceToCbor,
         LiveNodeVecFromCbor,
         LiveModuleId,
+        LiveFileError,
         LiveNodeSlice,
         LiveNodeVec,
         LiveNodeSliceApi,
@@ -126,7 +131,7 @@ pub use {
     },
     crate::{
         os::*,
-        cx_api::{CxOsApi,OpenUrlInPlace},
+        cx_api::{CxOsApi,OpenUrlInPlace,ColorScheme},
         media_api::CxMediaApi,
         scope::*,
         draw_list::{
@@ -240,6 +245,7 @@ pub use {
             LiveDependency,
             ArcStringMut,
         },
+        live_cx::ColorSchemeOverlays,
         live_traits::{
             LiveHookDeref,
             LiveBody,
@@ -10,6 +10,7 @@ use {
             LiveId,
             LiveProp,
             LiveError,
+            LiveFileError,
             LiveModuleId,
            /*LiveToken,*/
             LivePtr,
@@ -22,9 +23,17 @@ use {
         /*makepad_math::*,*/
         cx::Cx,
         cx::CxDependency,
+        cx_api::{CxOsApi, ColorScheme},
     },
 };
 
+// what follow_color_scheme swaps in over its target for each OS preference
+pub struct ColorSchemeOverlays {
+    pub target: LiveModuleId,
+    pub dark: Option<LiveModuleId>,
+    pub light: Option<LiveModuleId>,
+}
+
 pub struct LiveBody {
     pub file: String,
     pub cargo_manifest_path: String,
@@ -223,10 +232,50 @@ impl Cx {
         });
     }
     
+    // puts the top level definitions of overlay over the ones in target, or takes them off again
+    // with None. applied with the next live edit, so all widgets get their live state reapplied
+    pub fn set_live_overlay(&mut self, target: LiveModuleId, overlay: Option<LiveModuleId>){
+        self.live_overlay_changes.retain(|(t,_)| *t != target);
+        self.live_overlay_changes.push((target, overlay));
+    }
+    
+    // keeps the overlay on target in line with the dark/light preference of the OS
+    pub fn follow_color_scheme(&mut self, overlays: ColorSchemeOverlays){
+        let start = self.color_scheme_overlays.is_none();
+        self.color_scheme_overlays = Some(overlays);
+        if start{
+            self.start_color_scheme_watcher();
+        }
+    }
+    
+    // studio pausing and scrubbing the animations. the clock stops where it was and moves `time` on
+    // from there, or back with a negative time. on resume it carries on from the time it was moved
+    // to, so animations that started or ran while paused don't jump. every animator is seeked to
+    // the new time with the next frame, also the ones that already finished. springs only move
+    // forward, they stay where they are when the clock goes back. the redraw wakes up an app that
+    // was idle, so the seek frame gets sent
+    pub (crate) fn set_animation_clock(&mut self, paused: bool, time: f64){
+        self.animation_seek = true;
+        self.new_next_frame();
+        self.redraw_all();
+        if paused{
+            let start = *self.animation_pause_time.get_or_insert(self.last_frame_time + self.animation_time_offset);
+            self.virtual_time = Some(start + time);
+        }
+        else{
+            if self.animation_pause_time.take().is_some(){
+                if let Some(virtual_time) = self.virtual_time.take(){
+                    self.animation_time_offset = virtual_time - self.last_frame_time;
+                }
+            }
+        }
+    }
+    
     pub fn handle_live_edit(&mut self)->bool{
         // lets poll our studio connection
         let mut all_changes:Vec<LiveFileChange> = Vec::new();
         let mut actions = Vec::new();
+        let mut clock = Vec::new();
         if let Some(studio_socket) = &mut self.studio_web_socket{
             while let Ok(msg) = studio_socket.try_recv(){
                 match msg {
@@ -238,6 +287,9 @@ impl Cx {
                                         all_changes.retain(|v| v.file_name != file_name); 
                                         all_changes.push(LiveFileChange{file_name, content})
                                     }
+                                    StudioToApp::AnimationClock{paused, time}=>{
+                                        clock.push((paused, time));
+                                    }
                                     x=>{
                                         actions.push(x);
                                     }
@@ -250,6 +302,9 @@ impl Cx {
                 }
             }
         }
+        for (paused, time) in clock{
+            self.set_animation_clock(paused, time);
+        }
         for action in actions{
             self.action(action);
         }
@@ -262,10 +317,26 @@ impl Cx {
         while let Ok(changes) = self.live_file_change_receiver.try_recv(){
             all_changes.extend(changes);
         }
-        if all_changes.len()>0{
+        while let Ok(scheme) = self.color_scheme_receiver.try_recv(){
+            if let Some(overlays) = &self.color_scheme_overlays{
+                let overlay = match scheme{
+                    ColorScheme::Dark=>overlays.dark,
+                    ColorScheme::Light=>overlays.light,
+                    ColorScheme::NoPreference=>continue
+                };
+                let target = overlays.target;
+                self.set_live_overlay(target, overlay);
+            }
+        }
+        let overlay_changes = std::mem::take(&mut self.live_overlay_changes);
+        if all_changes.len()>0 || overlay_changes.len()>0{
             let mut live_registry = self.live_registry.borrow_mut();
             let mut errs = Vec::new();
+            let mut any_changes = all_changes.len()>0;
             live_registry.process_file_changes(all_changes, &mut errs);
+            for (target, overlay) in overlay_changes{
+                any_changes |= live_registry.set_file_overlay(target, overlay, &mut errs);
+            }
             for err in errs {
                 
                 // alright we need to output the correct error
@@ -284,14 +355,30 @@ impl Cx {
                 }
                 error!("check_live_file_watcher: Error expanding live file {}", err);
             }
-            self.draw_shaders.reset_for_live_reload();
-            true
+            if any_changes{
+                self.draw_shaders.reset_for_live_reload();
+            }
+            drop(live_registry);
+            self.take_live_edit(any_changes)
         }
         else{
-            false
+            self.take_live_edit(false)
         }
     }
     
+    fn take_live_edit(&mut self, any_changes: bool)->bool{
+        let locale_changed = std::mem::take(&mut self.locale_changed);
+        let source_changed = any_changes || std::mem::take(&mut self.live_source_changed);
+        self.locale_only_live_edit = locale_changed && !source_changed;
+        locale_changed || source_changed
+    }
+    
+    // true while handling a LiveEdit that only switched the locale, then only the strings
+    // coming from tr("..") need applying again
+    pub fn is_locale_only_live_edit(&self)->bool{
+        self.locale_only_live_edit
+    }
+    
     // ok so now what. now we should run the expansion
     pub fn live_expand(&mut self) {
         let mut errs = Vec::new();
@@ -380,6 +467,72 @@ impl Cx {
             }
         }
     }
+    // registers a live_design document at runtime, like a skin read from disk or ui sent by a
+    // server. Other documents import it by module_path, and live_ptr_from_module finds its
+    // definitions. Loading the same file name again replaces it and re-applies the app the way
+    // a live edit does
+    pub fn load_live_source(&mut self, file_name: &str, module_path: &str, source: String) -> Result<LiveModuleId, Vec<LiveFileError>> {
+        let module_id = LiveModuleId::from_str(module_path).map_err(|message| vec![LiveFileError{
+            origin: live_error_origin!(),
+            file: file_name.to_string(),
+            span: Default::default(),
+            message
+        }])?;
+        let mut live_registry = self.live_registry.borrow_mut();
+        let replaced = live_registry.file_name_to_file_id(file_name).is_some();
+        let file_id = live_registry.register_live_source(file_name, module_id, source).map_err(|err| vec![err])?;
+        let mut errs = Vec::new();
+        live_registry.expand_all_documents(&mut errs);
+        if errs.is_empty(){
+            live_registry.check_properties(file_id, &mut errs);
+        }
+        if replaced{
+            self.draw_shaders.reset_for_live_reload();
+            self.live_source_changed = true;
+        }
+        if errs.len()>0{
+            return Err(errs.into_iter().map(|err| live_registry.live_error_to_live_file_error(err)).collect())
+        }
+        Ok(module_id)
+    }
+    
+    pub fn load_live_file(&mut self, path: &str, module_path: &str) -> Result<LiveModuleId, Vec<LiveFileError>> {
+        match std::fs::read_to_string(path){
+            Ok(source)=>self.load_live_source(path, module_path, source),
+            Err(err)=>Err(vec![LiveFileError{
+                origin: live_error_origin!(),
+                file: path.to_string(),
+                span: Default::default(),
+                message: format!("Cannot read live file: {}", err)
+            }])
+        }
+    }
+    
+    // finds a top level definition of a module, checking it is a rust type that is registered
+    pub fn live_ptr_from_module(&self, module_id: LiveModuleId, name: LiveId) -> Result<LivePtr, LiveFileError> {
+        let live_registry = self.live_registry.borrow();
+        let error = |file: String, message: String| LiveFileError{
+            origin: live_error_origin!(),
+            file,
+            span: Default::default(),
+            message
+        };
+        let Some(file_id) = live_registry.module_id_to_file_id(module_id) else{
+            return Err(error(String::new(), format!("Module {} is not registered", module_id)))
+        };
+        let file_name = live_registry.file_id_to_file_name(file_id).to_string();
+        let Some(live_ptr) = live_registry.module_id_and_name_to_ptr(module_id, name) else{
+            return Err(error(file_name, format!("No definition {} in module {}", name, module_id)))
+        };
+        let node = live_registry.ptr_to_node(live_ptr);
+        match &node.value{
+            LiveValue::Class{live_type, ..} if live_registry.live_type_infos.contains_key(live_type)=>Ok(live_ptr),
+            _=>Err(LiveFileError{
+                span: node.origin.token_id().map(|token_id| live_registry.token_id_to_span(token_id)).unwrap_or_default(),
+                ..error(file_name, format!("{} in module {} is not a registered rust type", name, module_id))
+            })
+        }
+    }
     /*
     fn update_buffer_from_live_value(slots: usize, output: &mut [f32], offset: usize, v: &LiveValue) {
         match slots {
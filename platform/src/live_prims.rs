@@ -41,14 +41,13 @@ macro_rules!live_primitive {
             }
             
             fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
-                LiveTypeInfo {
-                    module_id: LiveModuleId::from_str(&module_path!()).unwrap(),
-                    live_type: LiveType::of::<Self>(),
-                    fields: Vec::new(),
-                    live_ignore: true,
-                    type_name: LiveId::from_str_with_lut(stringify!( $ ty)).unwrap(),
-                    //kind: LiveTypeKind::Primitive
-                }
+                LiveTypeInfo::new(
+                    LiveModuleId::from_str(&module_path!()).unwrap(),
+                    LiveType::of::<Self>(),
+                    LiveId::from_str_with_lut(stringify!( $ ty)).unwrap(),
+                    true,
+                    Vec::new()
+                )
             }
         }
     }
@@ -661,6 +660,10 @@ live_primitive!(
                 self.push_str(v.as_str());
                 index + 1
             }
+            LiveValue::Translate(id) => {
+                *self = crate::i18n::tr(id, &[]);
+                index + 1
+            }
             LiveValue::Expr {..} => {
                 panic!("Expr node found whilst deserialising DSL")
             },
@@ -774,6 +777,10 @@ live_primitive!(
                 *self = ArcStringMut::String(v.as_str().to_string());
                 index + 1
             }
+            LiveValue::Translate(id) => {
+                *self = ArcStringMut::String(crate::i18n::tr(id, &[]));
+                index + 1
+            }
             LiveValue::Expr {..} => {
                 panic!("Expr node found whilst deserialising DSL")
             },
@@ -124,7 +124,12 @@ pub trait LiveNew: LiveApply {
             let live_registry = live_registry_rc.borrow_mut();
             live_registry.main_module.as_ref().unwrap().clone()
         };
-        self.update_from_module(cx, lti.module_id, lti.type_name);
+        if cx.is_locale_only_live_edit(){
+            self.update_translations_from_module(cx, lti.module_id, lti.type_name);
+        }
+        else{
+            self.update_from_module(cx, lti.module_id, lti.type_name);
+        }
     }
     
     fn new_local(cx: &mut Cx) -> Self where Self: Sized {
@@ -156,6 +161,51 @@ pub trait LiveNew: LiveApply {
             }
         }
     }
+    
+    // applies over only the tr("..") values, leaving everything else in the tree as it is
+    fn update_translations_from_module(&mut self, cx: &mut Cx, module_id: LiveModuleId, id: LiveId)  {
+        let mut nodes = Vec::new();
+        {
+            let live_registry = cx.live_registry.borrow();
+            let Some(file_id) = live_registry.module_id_to_file_id.get(&module_id) else{
+                return
+            };
+            let file = live_registry.file_id_to_file(*file_id);
+            if let Some(index) = file.expanded.nodes.child_by_name(0, id.as_instance()) {
+                collect_translate_nodes(&file.expanded.nodes, index, &mut nodes);
+            }
+        }
+        if !nodes.is_empty(){
+            self.apply(cx, &mut ApplyFrom::Over.into(), 0, &nodes);
+        }
+    }
+}
+
+// copies the Translate values under index with the nodes leading up to them, false if there are none
+fn collect_translate_nodes(nodes: &[LiveNode], index: usize, out: &mut Vec<LiveNode>)->bool{
+    let node = &nodes[index];
+    if !node.value.is_open(){
+        if let LiveValue::Translate(_) = node.value{
+            out.push(node.clone());
+            return true
+        }
+        return false
+    }
+    let start = out.len();
+    out.push(node.clone());
+    let mut any = false;
+    let mut child = index + 1;
+    while !nodes[child].value.is_close(){
+        any |= collect_translate_nodes(nodes, child, out);
+        child = nodes.skip_node(child);
+    }
+    if any{
+        out.push(nodes[child].clone());
+    }
+    else{
+        out.truncate(start);
+    }
+    any
 }
 
 pub trait ToLiveValue {
@@ -420,3 +470,41 @@ impl<T, const N:usize> LiveNew for [T;N] where T: LiveApply + LiveNew + 'static{
 
 
 
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        crate::makepad_live_id::*,
+        std::sync::Arc,
+    };
+    
+    #[test]
+    fn translate_nodes_keep_their_path(){
+        let translate = |id, message: &str| LiveNode::from_id_value(id, LiveValue::Translate(Arc::new(message.to_string())));
+        let mut nodes = LiveNodeVec::new();
+        nodes.open_object(LiveId(0));
+        nodes.push_float64(live_id!(width), 1.0);
+        nodes.open_object(live_id!(header));
+        nodes.push(translate(live_id!(text), "title"));
+        nodes.push_bool(live_id!(visible), true);
+        nodes.close();
+        nodes.open_object(live_id!(body));
+        nodes.push_str(live_id!(text), "not translated");
+        nodes.close();
+        nodes.open_array(live_id!(items));
+        nodes.push(translate(LiveId(0), "item"));
+        nodes.close();
+        nodes.close();
+        
+        let mut out = Vec::new();
+        assert!(collect_translate_nodes(&nodes, 0, &mut out));
+        let ids: Vec<LiveId> = out.iter().filter(|node| !node.value.is_close()).map(|node| node.id).collect();
+        assert_eq!(ids, vec![LiveId(0), live_id!(header), live_id!(text), live_id!(items), LiveId(0)]);
+        assert_eq!(out.iter().filter(|node| node.value.is_open()).count(), out.iter().filter(|node| node.value.is_close()).count());
+        
+        let mut out = Vec::new();
+        assert!(!collect_translate_nodes(&nodes, nodes.child_by_name(0, live_id!(body).as_field()).unwrap(), &mut out));
+        assert!(out.is_empty());
+    }
+}
@@ -262,6 +262,11 @@ impl OsWebSocket{
                     let () = msg_send![msg, initWithData: nsdata];
                     msg
                 }
+                WebSocketMessage::Close(code, reason)=>{
+                    let nsdata: ObjcId = msg_send![class!(NSData), dataWithBytes: reason.as_ptr() length: reason.len()];
+                    let () = msg_send![*Arc::as_ptr(&self.data_task), cancelWithCloseCode: *code as i64 reason: nsdata];
+                    return Ok(())
+                }
                 _=>panic!()
             };
                         
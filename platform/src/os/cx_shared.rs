@@ -205,11 +205,19 @@ impl Cx {
     }
 
     pub (crate) fn call_next_frame_event(&mut self, time: f64) {
+        self.performance_stats.process_frame_data(time);
+        self.last_frame_time = time;
+        // animations run on the virtual clock when it is set
+        let time = self.virtual_time.unwrap_or(time + self.animation_time_offset);
+        self.dispatch_next_frame_event(time);
+    }
+    
+    // sends the waiting NextFrame events with the animation time, without counting a frame
+    pub (crate) fn dispatch_next_frame_event(&mut self, time: f64) {
         let mut set = HashSet::default();
         std::mem::swap(&mut set, &mut self.new_next_frames);
+        let seek = std::mem::take(&mut self.animation_seek);
 
-        self.performance_stats.process_frame_data(time);
-
-        self.call_event_handler(&Event::NextFrame(NextFrameEvent {set, time: time, frame: self.repaint_id}));
+        self.call_event_handler(&Event::NextFrame(NextFrameEvent {set, time, frame: self.repaint_id, seek}));
     }
 }
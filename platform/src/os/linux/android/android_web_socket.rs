@@ -19,13 +19,16 @@ impl OsWebSocket{
     pub fn send_message(&mut self, message:WebSocketMessage)->Result<(),()>{
         let frame = match &message{
             WebSocketMessage::String(data)=>{
-                let header = ServerWebSocketMessageHeader::from_len(data.len(), ServerWebSocketMessageFormat::Text, false);
+                let header = ServerWebSocketMessageHeader::from_len(data.len(), ServerWebSocketMessageFormat::Text, true);
                 ServerWebSocket::build_message(header, &data.to_string().into_bytes())
             }
             WebSocketMessage::Binary(data)=>{
-                let header = ServerWebSocketMessageHeader::from_len(data.len(), ServerWebSocketMessageFormat::Binary, false);
+                let header = ServerWebSocketMessageHeader::from_len(data.len(), ServerWebSocketMessageFormat::Binary, true);
                 ServerWebSocket::build_message(header, &data)
             }
+            WebSocketMessage::Close(code, reason)=>{
+                ServerWebSocket::build_close_message(*code, reason, true)
+            }
             _=>panic!()
         };
         unsafe {android_jni::to_java_websocket_send_message(self.request_id, frame);}
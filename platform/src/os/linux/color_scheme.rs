@@ -0,0 +1,103 @@
+use {
+    std::io::{BufRead, BufReader},
+    std::process::{Command, Stdio},
+    std::sync::mpsc::Sender,
+    crate::cx_api::ColorScheme,
+};
+
+// the XDG settings portal has the preference as org.freedesktop.appearance color-scheme,
+// 0 is no preference, 1 prefers dark and 2 prefers light. we talk to it through gdbus
+// so we don't need a dbus implementation of our own
+
+const PORTAL_ARGS: [&str; 5] = [
+    "--session",
+    "--dest", "org.freedesktop.portal.Desktop",
+    "--object-path", "/org/freedesktop/portal/desktop",
+];
+
+fn parse_color_scheme(line: &str) -> Option<ColorScheme> {
+    let at = line.rfind("uint32 ")?;
+    let value: String = line[at + 7..].chars().take_while(|c| c.is_ascii_digit()).collect();
+    match value.parse::<u32>().ok()? {
+        1 => Some(ColorScheme::Dark),
+        2 => Some(ColorScheme::Light),
+        _ => Some(ColorScheme::NoPreference)
+    }
+}
+
+// a line of gdbus monitor output, only the color-scheme changes count
+fn parse_setting_changed(line: &str) -> Option<ColorScheme> {
+    if line.contains("SettingChanged") && line.contains("'org.freedesktop.appearance'") && line.contains("'color-scheme'") {
+        parse_color_scheme(line)
+    }
+    else {
+        None
+    }
+}
+
+fn read_color_scheme() -> Option<ColorScheme> {
+    let output = Command::new("gdbus")
+        .arg("call")
+        .args(PORTAL_ARGS)
+        .args(["--method", "org.freedesktop.portal.Settings.Read", "org.freedesktop.appearance", "color-scheme"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None
+    }
+    parse_color_scheme(&String::from_utf8_lossy(&output.stdout))
+}
+
+pub fn start_color_scheme_watcher(sender: Sender<ColorScheme>) {
+    std::thread::spawn(move || {
+        if let Some(scheme) = read_color_scheme() {
+            if sender.send(scheme).is_err() {
+                return
+            }
+        }
+        let Ok(mut child) = Command::new("gdbus")
+            .arg("monitor")
+            .args(PORTAL_ARGS)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn() else {
+            return
+        };
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        for line in stdout.lines().map_while(Result::ok) {
+            if let Some(scheme) = parse_setting_changed(&line) {
+                if sender.send(scheme).is_err() {
+                    break
+                }
+            }
+        }
+        let _ = child.kill();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_replies() {
+        // Read wraps the value in two variants, ReadOne in one
+        assert_eq!(parse_color_scheme("(<<uint32 1>>,)"), Some(ColorScheme::Dark));
+        assert_eq!(parse_color_scheme("(<uint32 2>,)"), Some(ColorScheme::Light));
+        assert_eq!(parse_color_scheme("(<<uint32 0>>,)"), Some(ColorScheme::NoPreference));
+        assert_eq!(parse_color_scheme("(<<uint32 7>>,)"), Some(ColorScheme::NoPreference));
+        assert_eq!(parse_color_scheme("(<<'dark'>>,)"), None);
+        assert_eq!(parse_color_scheme(""), None);
+    }
+
+    #[test]
+    fn setting_changed_signals() {
+        let line = "/org/freedesktop/portal/desktop: org.freedesktop.portal.Settings.SettingChanged ('org.freedesktop.appearance', 'color-scheme', <uint32 1>)";
+        assert_eq!(parse_setting_changed(line), Some(ColorScheme::Dark));
+        let other_key = "/org/freedesktop/portal/desktop: org.freedesktop.portal.Settings.SettingChanged ('org.freedesktop.appearance', 'accent-color', <uint32 2>)";
+        assert_eq!(parse_setting_changed(other_key), None);
+        let other_namespace = "/org/freedesktop/portal/desktop: org.freedesktop.portal.Settings.SettingChanged ('org.gnome.desktop.interface', 'color-scheme', <'prefer-dark'>)";
+        assert_eq!(parse_setting_changed(other_namespace), None);
+    }
+}
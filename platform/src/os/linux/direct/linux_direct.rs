@@ -332,7 +332,10 @@ impl CxOsApi for Cx {
     fn seconds_since_app_start(&self)->f64{
         Instant::now().duration_since(self.os.start_time).as_secs_f64()
     }
-        
+    
+    fn start_color_scheme_watcher(&mut self){
+        crate::os::linux::color_scheme::start_color_scheme_watcher(self.color_scheme_sender.clone());
+    }
 }
 
 pub struct CxOs {
@@ -17,6 +17,8 @@ pub mod module_loader;
 pub mod dma_buf;
 #[cfg(not(any(target_env="ohos", target_os="android")))]
 pub mod ipc;
+#[cfg(not(any(target_env="ohos", target_os="android")))]
+pub mod color_scheme;
 
 #[cfg(not(any(target_env="ohos", target_os="android")))]
 pub mod alsa_sys;
@@ -35,9 +37,6 @@ pub mod pulse_audio;
 #[cfg(not(any(target_env="ohos", target_os="android")))]
 pub mod pulse_sys;
 
-#[cfg(not(target_os="android"))]
-mod web_socket;
-
 #[cfg(target_os="android")]
 pub mod android;
 
@@ -65,7 +64,7 @@ pub(crate) use self::android::android_midi::{OsMidiInput, OsMidiOutput};
 //pub(crate) use self::open_harmony::oh_media::{OsMidiInput, OsMidiOutput};
 
 #[cfg(not(target_os="android"))]
-pub (crate) use web_socket::OsWebSocket;
+pub (crate) use crate::os::native_web_socket::OsWebSocket;
 
 #[cfg(target_os="android")]
 pub (crate) use self::android::android_web_socket::OsWebSocket;
@@ -392,6 +392,10 @@ impl CxOsApi for Cx {
     fn open_url(&mut self, _url:&str, _in_place:OpenUrlInPlace){
         crate::error!("open_url not implemented on this platform");
     }
+    
+    fn start_color_scheme_watcher(&mut self){
+        crate::os::linux::color_scheme::start_color_scheme_watcher(self.color_scheme_sender.clone());
+    }
 }
 
 #[derive(Default)]
@@ -5,6 +5,10 @@ pub mod cx_native;
 #[macro_use]
 pub mod cx_shared;
 
+// the websocket client on a plain TcpStream, android and the others have their own
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+mod native_web_socket;
+
 pub mod cx_stdin;
 
 #[cfg(any(target_os = "macos", target_os="ios", target_os="tvos"))]
@@ -0,0 +1,213 @@
+use crate::event::HttpRequest;
+use crate::web_socket::{WebSocketMessage};
+use crate::thread::SignalToUI;
+use std::sync::mpsc::{channel, Sender, RecvTimeoutError};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::net::{TcpStream, Shutdown};
+use std::io::{Read, BufRead, BufReader};
+use std::time::{Duration, Instant};
+use makepad_http::utils::write_bytes_to_tcp_stream_no_error;
+use makepad_http::websocket::{ServerWebSocket, ServerWebSocketWriter, ServerWebSocketMessageFormat, ServerWebSocketMessage, SERVER_WEB_SOCKET_CLOSE_NORMAL, SERVER_WEB_SOCKET_CLOSE_NO_STATUS};
+use makepad_http::websocket_deflate::PerMessageDeflateConfig;
+
+// what the writer thread sends: app messages plus the control frames the reader needs answered
+enum WebSocketWrite{
+    Message(WebSocketMessage),
+    Pong(Vec<u8>),
+}
+
+const PING_INTERVAL: Duration = Duration::from_millis(5000);
+// a server that hasn't sent anything, pongs included, for this long is considered gone
+const PONG_TIMEOUT: Duration = Duration::from_millis(15000);
+
+pub struct OsWebSocket{
+    sender: Option<Sender<WebSocketWrite>>
+}
+
+impl OsWebSocket{
+    pub fn send_message(&mut self, message:WebSocketMessage)->Result<(),()>{
+        // lets encode the message into a membuffer and send it to the write thread
+        if let Some(sender) = &mut self.sender{
+            if sender.send(WebSocketWrite::Message(message)).is_err(){
+                return Err(());
+            }
+            return Ok(())
+        }
+        Err(())
+    }
+
+    fn fail(rx_sender: &Sender<WebSocketMessage>, error: String)->OsWebSocket{
+        let _ = rx_sender.send(WebSocketMessage::Error(error));
+        SignalToUI::set_ui_signal();
+        OsWebSocket{sender:None}
+    }
+
+    pub fn open(_socket_id:u64, request: HttpRequest, rx_sender:Sender<WebSocketMessage>)->OsWebSocket{
+        // parse the url
+        let split = request.split_url();
+        // strip off any hashes
+        // alright we have proto, host, port and hash now
+        // lets open a tcpstream
+        let stream = TcpStream::connect(format!("{}:{}", split.host, split.port));
+        // alright lets construct a http request
+        // lets join the headers
+        let key = ServerWebSocket::create_client_key();
+        let mut http_request = format!(
+            "GET /{} HTTP/1.1\r\nHost: {}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Extensions: {}\r\n",
+            split.file,
+            split.host,
+            key,
+            PerMessageDeflateConfig::client_offer()
+        );
+        http_request.push_str(&request.get_headers_string());
+        http_request.push_str("\r\n");
+
+        // lets write the http request
+        let mut stream = match stream{
+            Ok(stream)=>stream,
+            Err(_)=>return Self::fail(&rx_sender, "Error connecting websocket tcpstream".into())
+        };
+        if write_bytes_to_tcp_stream_no_error(&mut stream, http_request.as_bytes()){
+            return Self::fail(&rx_sender, "Error writing request to websocket".into())
+        }
+
+        // read the upgrade response. The reader keeps anything the server sent after it
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut lines = Vec::new();
+        loop{
+            let mut line = String::new();
+            match reader.read_line(&mut line){
+                Ok(0) | Err(_)=>return Self::fail(&rx_sender, "Websocket closed during handshake".into()),
+                Ok(_)=>{
+                    if line == "\r\n"{
+                        break
+                    }
+                    lines.push(line);
+                }
+            }
+        }
+        let deflate = match ServerWebSocket::validate_upgrade_response(&lines, &key){
+            Ok(deflate)=>deflate,
+            Err(e)=>return Self::fail(&rx_sender, e)
+        };
+        let _ = rx_sender.send(WebSocketMessage::Opened);
+        SignalToUI::set_ui_signal();
+
+        // lets start the thread
+        let mut output_stream = stream.try_clone().unwrap();
+        let (sender, receiver) = channel();
+        let start = Instant::now();
+        let last_seen = Arc::new(AtomicU64::new(0));
+
+        let mut writer = ServerWebSocketWriter::new(true);
+        if let Some(deflate) = &deflate{
+            writer = writer.with_deflate(deflate, false);
+        }
+        let writer_last_seen = last_seen.clone();
+        let writer_rx_sender = rx_sender.clone();
+        let _writer_thread = std::thread::spawn(move || {
+            loop{
+                let frame = match receiver.recv_timeout(PING_INTERVAL){
+                    Ok(WebSocketWrite::Message(WebSocketMessage::Binary(data)))=>{
+                        writer.message(ServerWebSocketMessageFormat::Binary, &data)
+                    }
+                    Ok(WebSocketWrite::Message(WebSocketMessage::String(data)))=>{
+                        writer.message(ServerWebSocketMessageFormat::Text, data.as_bytes())
+                    }
+                    Ok(WebSocketWrite::Message(WebSocketMessage::Close(code, reason)))=>{
+                        writer.close(code, &reason)
+                    }
+                    Ok(WebSocketWrite::Message(_))=>{
+                        crate::error!("WebSocketMessage of this type sending not implemented");
+                        continue
+                    }
+                    Ok(WebSocketWrite::Pong(data))=>{
+                        writer.pong(&data)
+                    }
+                    Err(RecvTimeoutError::Timeout)=>{
+                        // the reader can store a newer time between the two reads
+                        let idle = (start.elapsed().as_millis() as u64).saturating_sub(writer_last_seen.load(Ordering::Relaxed));
+                        if idle > PONG_TIMEOUT.as_millis() as u64{
+                            let _ = writer_rx_sender.send(WebSocketMessage::Error("Websocket ping timeout".into()));
+                            SignalToUI::set_ui_signal();
+                            let _ = output_stream.shutdown(Shutdown::Both);
+                            break;
+                        }
+                        writer.ping(&[])
+                    }
+                    Err(RecvTimeoutError::Disconnected)=>break
+                };
+                if write_bytes_to_tcp_stream_no_error(&mut output_stream, &frame){
+                    break;
+                }
+            }
+        });
+
+        let pong_sender = sender.clone();
+        let _reader_thread = std::thread::spawn(move || {
+            let mut web_socket = ServerWebSocket::new();
+            if let Some(deflate) = &deflate{
+                web_socket = web_socket.with_deflate(deflate, false);
+            }
+            let mut done = false;
+            while !done {
+                let mut buffer = [0u8; 65535];
+                match reader.read(&mut buffer) {
+                    Ok(0) => {
+                        let _ = rx_sender.send(WebSocketMessage::Closed);
+                        done = true;
+                    }
+                    Ok(bytes_read) => {
+                        last_seen.store(start.elapsed().as_millis() as u64, Ordering::Relaxed);
+                        web_socket.parse(&buffer[0..bytes_read], | result | {
+                            match result {
+                                Ok(ServerWebSocketMessage::Ping(data)) => {
+                                    if pong_sender.send(WebSocketWrite::Pong(data.to_vec())).is_err(){
+                                        done = true;
+                                        let _ = rx_sender.send(WebSocketMessage::Error("Pong message send failed".into()));
+                                    }
+                                },
+                                Ok(ServerWebSocketMessage::Pong(_)) => {
+                                },
+                                Ok(ServerWebSocketMessage::Text(text)) => {
+                                    if rx_sender.send(WebSocketMessage::String(text.into())).is_err(){
+                                        done = true;
+                                    };
+                                },
+                                Ok(ServerWebSocketMessage::Binary(data)) => {
+                                    if rx_sender.send(WebSocketMessage::Binary(data.into())).is_err(){
+                                        done = true;
+                                    };
+                                },
+                                Ok(ServerWebSocketMessage::Close{code, reason}) => {
+                                    // answer the close before reporting it, completing the handshake
+                                    let reply = if code == SERVER_WEB_SOCKET_CLOSE_NO_STATUS{SERVER_WEB_SOCKET_CLOSE_NORMAL}else{code};
+                                    let _ = pong_sender.send(WebSocketWrite::Message(WebSocketMessage::Close(reply, String::new())));
+                                    let _ = rx_sender.send(WebSocketMessage::Close(code, reason.to_string()));
+                                    let _ = rx_sender.send(WebSocketMessage::Closed);
+                                    done = true;
+                                },
+                                Err(e) => {
+                                    let _ = pong_sender.send(WebSocketWrite::Message(WebSocketMessage::Close(e.close_code(), String::new())));
+                                    let _ = rx_sender.send(WebSocketMessage::Error(format!("Websocket error {:?}", e)));
+                                    let _ = rx_sender.send(WebSocketMessage::Closed);
+                                    done = true;
+                                }
+                            }
+                        });
+                        SignalToUI::set_ui_signal();
+                    }
+                    Err(e) => {
+                        let _ = rx_sender.send(WebSocketMessage::Error(format!("Failed to receive data: {}", e)));
+                        let _ = rx_sender.send(WebSocketMessage::Closed);
+                        done = true;
+                    }
+                }
+            }
+            SignalToUI::set_ui_signal();
+        });
+
+        OsWebSocket{sender:Some(sender)}
+    }
+}
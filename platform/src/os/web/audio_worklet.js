@@ -37,6 +37,9 @@ class AudioWorklet extends AudioWorkletProcessor {
             js_web_socket_send_binary(id, bin_ptr, bin_len){
             },
     
+            js_web_socket_close(id, code, reason_ptr, reason_len){
+            },
+    
             js_open_web_socket:(id, url_ptr, url_len)=>{
                 
             }
@@ -15,6 +15,7 @@ extern "C" {
     pub fn js_open_web_socket(id:u32, url_ptr: u32, url_len: u32);
     pub fn js_web_socket_send_string(id:u32, str_ptr: u32, url_len: u32);
     pub fn js_web_socket_send_binary(id:u32, bin_ptr: u32, bin_len: u32);
+    pub fn js_web_socket_close(id:u32, code: u32, reason_ptr: u32, reason_len: u32);
 }
 
 // alright we need a global set of websockets
@@ -91,6 +92,10 @@ impl OsWebSocket{
                 let bytes = str.as_bytes();
                 unsafe{js_web_socket_send_string(self.id as u32, bytes.as_ptr() as u32, bytes.len() as u32)};
             }
+            WebSocketMessage::Close(code, reason)=>{
+                let bytes = reason.as_bytes();
+                unsafe{js_web_socket_close(self.id as u32, code as u32, bytes.as_ptr() as u32, bytes.len() as u32)};
+            }
             _=>()
         }
         //todo!();
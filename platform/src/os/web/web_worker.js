@@ -49,6 +49,14 @@ onmessage = async function(e) {
             }
         },
         
+        js_web_socket_close(id, code, reason_ptr, reason_len){
+            let reason = u8_to_string(reason_ptr, reason_len);
+            let web_socket = web_sockets[id];
+            if(web_socket !== undefined){
+                web_socket.close(code, reason);
+            }
+        },
+        
         js_time_now(){
             return Date.now()/ 1000.0;
         },
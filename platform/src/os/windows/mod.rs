@@ -19,10 +19,9 @@ pub mod http;
 pub mod d3d11;
 pub mod windows;
 pub mod windows_stdin;
-mod web_socket;
 
 pub(crate) use crate::os::windows::d3d11::*; 
 pub(crate) use crate::os::windows::windows::*;
 pub(crate) use crate::os::windows::winrt_midi::{OsMidiInput, OsMidiOutput};
-pub (crate) use web_socket::OsWebSocket;
+pub (crate) use crate::os::native_web_socket::OsWebSocket;
 
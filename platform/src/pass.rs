@@ -84,14 +84,13 @@ impl LiveNew for Pass {
     }
     
     fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
-        LiveTypeInfo {
-            module_id: LiveModuleId::from_str(&module_path!()).unwrap(),
-            live_type: LiveType::of::<Self>(),
-            fields: Vec::new(),
-            live_ignore: true,
-            //kind: LiveTypeKind::Object,
-            type_name: id_lut!(Pass)
-        }
+        LiveTypeInfo::new(
+            LiveModuleId::from_str(&module_path!()).unwrap(),
+            LiveType::of::<Self>(),
+            id_lut!(Pass),
+            true,
+            Vec::new()
+        )
     }
 }
 
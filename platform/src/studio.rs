@@ -76,6 +76,21 @@ pub struct EditFile{
     pub replace: String
 }
 
+#[derive(SerBin, DeBin, Debug, Clone)]
+pub struct MoveFileRange{
+    pub file_name: String,
+    pub line_start: u32,
+    pub line_end: u32,
+    pub column_start: u32,
+    pub column_end: u32,
+    pub to_line: u32,
+    pub to_column: u32,
+    // leaves the range where it was, so it ends up in the file twice
+    pub copy: bool,
+    // the name the first identifier in the range gets in the moved text
+    pub rename: Option<String>
+}
+
 #[derive(SerBin, DeBin, Debug)]
 pub enum AppToStudio{
     LogItem(StudioLogItem),
@@ -86,6 +101,7 @@ pub enum AppToStudio{
     DesignerComponentMoved(DesignerComponentPosition),
     DesignerZoomPan(DesignerZoomPan),
     EditFile(EditFile),
+    MoveFileRange(MoveFileRange),
     DesignerStarted,
     DesignerFileSelected{
         file_name:String,
@@ -109,6 +125,12 @@ pub enum StudioToApp{
     DesignerSelectFile{
         file_name: String,
     },
+    // stops the animation clock where it is and moves it `time` seconds on from there, or back
+    // when negative
+    AnimationClock{
+        paused: bool,
+        time: f64,
+    },
     None,
 }
 
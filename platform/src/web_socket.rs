@@ -49,6 +49,9 @@ pub enum WebSocketMessage{
     Binary(Vec<u8>),
     String(String),
     Opened,
+    // a close code and reason. Sent by the app it starts the closing handshake,
+    // received it is what the server closed with and is followed by Closed
+    Close(u16, String),
     Closed
 }
 
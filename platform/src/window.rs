@@ -114,13 +114,13 @@ impl LiveNew for WindowHandle {
     }
     
     fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
-        LiveTypeInfo {
-            module_id: LiveModuleId::from_str(&module_path!()).unwrap(),
-            live_type: LiveType::of::<Self>(),
-            fields: Vec::new(),
-            live_ignore: true,
-            type_name: id_lut!(Window)
-        }
+        LiveTypeInfo::new(
+            LiveModuleId::from_str(&module_path!()).unwrap(),
+            LiveType::of::<Self>(),
+            id_lut!(Window),
+            true,
+            Vec::new()
+        )
     }
 }
 impl LiveApply for WindowHandle {
@@ -0,0 +1,355 @@
+//! Types and functions for the deltas the collab server applies to open files.
+//!
+//! A delta is a list of changes that are applied one after the other, so each change is relative
+//! to the text the previous changes produced. Positions and lengths count lines and bytes, the
+//! same way the code editor does, so edits can be sent as they are made.
+//!
+//! Two deltas made concurrently against the same revision are brought back in line with
+//! `transform`, which rewrites each of them so it can be applied after the other. Both orders
+//! then produce the same text.
+
+use {
+    crate::makepad_micro_serde::{SerBin, DeBin, DeBinErr},
+    std::{
+        cmp::Ordering,
+        ops::{Add, Sub},
+    },
+};
+
+/// A position in a text, as a line index and a byte index into that line.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, SerBin, DeBin)]
+pub struct TextPosition {
+    pub line_index: usize,
+    pub byte_index: usize,
+}
+
+/// The length of a piece of text. `byte_count` counts the bytes on its last line.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, SerBin, DeBin)]
+pub struct TextLength {
+    pub line_count: usize,
+    pub byte_count: usize,
+}
+
+/// A single change to a text.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
+pub enum TextChange {
+    Insert(TextPosition, String),
+    Delete(TextPosition, TextLength),
+}
+
+/// A selection of a participant, used to show where others are in a file. `cursor` is one of
+/// the ends of the selection.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, SerBin, DeBin)]
+pub struct TextSelection {
+    pub anchor: TextPosition,
+    pub cursor: TextPosition,
+}
+
+impl TextLength {
+    pub fn of(text: &str) -> Self {
+        match text.rfind('\n') {
+            Some(index) => Self {
+                line_count: text.matches('\n').count(),
+                byte_count: text.len() - index - 1,
+            },
+            None => Self {
+                line_count: 0,
+                byte_count: text.len(),
+            },
+        }
+    }
+}
+
+impl TextPosition {
+    /// Adds a length, or returns `None` if the result doesn't fit in a `usize`.
+    pub fn checked_add(self, length: TextLength) -> Option<Self> {
+        if length.line_count == 0 {
+            Some(Self {
+                line_index: self.line_index,
+                byte_index: self.byte_index.checked_add(length.byte_count) ?,
+            })
+        }
+        else {
+            Some(Self {
+                line_index: self.line_index.checked_add(length.line_count) ?,
+                byte_index: length.byte_count,
+            })
+        }
+    }
+}
+
+impl Add<TextLength> for TextPosition {
+    type Output = Self;
+
+    fn add(self, length: TextLength) -> Self {
+        if length.line_count == 0 {
+            Self {
+                line_index: self.line_index,
+                byte_index: self.byte_index + length.byte_count,
+            }
+        }
+        else {
+            Self {
+                line_index: self.line_index + length.line_count,
+                byte_index: length.byte_count,
+            }
+        }
+    }
+}
+
+impl Sub for TextPosition {
+    type Output = TextLength;
+
+    fn sub(self, other: Self) -> TextLength {
+        if self.line_index == other.line_index {
+            TextLength {
+                line_count: 0,
+                byte_count: self.byte_index - other.byte_index,
+            }
+        }
+        else {
+            TextLength {
+                line_count: self.line_index - other.line_index,
+                byte_count: self.byte_index,
+            }
+        }
+    }
+}
+
+impl TextChange {
+    /// Where a position ends up after this change. A position at the point of an insertion
+    /// moves past it only if `after_insert` is set.
+    pub fn transform_position(&self, position: TextPosition, after_insert: bool) -> TextPosition {
+        match self {
+            Self::Insert(point, text) => match position.cmp(point) {
+                Ordering::Less => position,
+                Ordering::Equal if !after_insert => position,
+                _ => *point + TextLength::of(text) + (position - *point),
+            },
+            Self::Delete(start, length) => {
+                let end = *start + *length;
+                if position <= *start {
+                    position
+                }
+                else if position >= end {
+                    *start + (position - end)
+                }
+                else {
+                    *start
+                }
+            }
+        }
+    }
+}
+
+/// Applies a change to a text that is split into lines on `'\n'`.
+pub fn apply_change(lines: &mut Vec<String>, change: &TextChange) {
+    match change {
+        TextChange::Insert(point, text) => {
+            let line = &lines[point.line_index];
+            let mut inserted: Vec<String> = text.split('\n').map( | line | line.to_string()).collect();
+            let tail = line[point.byte_index..].to_string();
+            inserted[0].insert_str(0, &line[..point.byte_index]);
+            inserted.last_mut().unwrap().push_str(&tail);
+            lines.splice(point.line_index..point.line_index + 1, inserted);
+        }
+        TextChange::Delete(start, length) => {
+            let end = *start + *length;
+            let mut line = lines[start.line_index][..start.byte_index].to_string();
+            line.push_str(&lines[end.line_index][end.byte_index..]);
+            lines.splice(start.line_index..end.line_index + 1, std::iter::once(line));
+        }
+    }
+}
+
+/// Checks that a change fits the text, so a bad delta from a client can't bring the server
+/// down.
+pub fn is_valid_change(lines: &[String], change: &TextChange) -> bool {
+    fn is_valid_position(lines: &[String], position: TextPosition) -> bool {
+        lines.get(position.line_index).is_some_and( | line | line.is_char_boundary(position.byte_index))
+    }
+    match change {
+        TextChange::Insert(point, _) => is_valid_position(lines, *point),
+        TextChange::Delete(start, length) => {
+            let Some(end) = start.checked_add(*length) else {
+                return false
+            };
+            is_valid_position(lines, *start) && is_valid_position(lines, end) && *start <= end
+        }
+    }
+}
+
+/// The changes that turn `old` into `new`, as a deletion and an insertion of the part that
+/// differs. Used when a whole new text comes in, like a file that changed on disk.
+pub fn diff_text(old: &str, new: &str) -> Vec<TextChange> {
+    let mut prefix = old.bytes().zip(new.bytes()).take_while( | (a, b) | a == b).count();
+    while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+    let mut suffix = old[prefix..].bytes().rev().zip(new[prefix..].bytes().rev()).take_while( | (a, b) | a == b).count();
+    while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
+        suffix -= 1;
+    }
+    let start = TextPosition::default() + TextLength::of(&old[..prefix]);
+    let mut changes = Vec::new();
+    let deleted = &old[prefix..old.len() - suffix];
+    if !deleted.is_empty() {
+        changes.push(TextChange::Delete(start, TextLength::of(deleted)));
+    }
+    let inserted = &new[prefix..new.len() - suffix];
+    if !inserted.is_empty() {
+        changes.push(TextChange::Insert(start, inserted.to_string()));
+    }
+    changes
+}
+
+// Rewrites `change` so it applies after `other`. When both insert at the same position, the
+// insertion of `other` ends up first if `other_first` is set.
+fn transform_change(change: &TextChange, other: &TextChange, other_first: bool) -> Vec<TextChange> {
+    match (change, other) {
+        (TextChange::Insert(point, text), _) => {
+            vec![TextChange::Insert(other.transform_position(*point, other_first), text.clone())]
+        }
+        (TextChange::Delete(start, length), TextChange::Insert(point, text)) => {
+            let end = *start + *length;
+            if *point <= *start {
+                vec![TextChange::Delete(other.transform_position(*start, true), *length)]
+            }
+            else if *point >= end {
+                vec![change.clone()]
+            }
+            else {
+                // the insertion lands inside the deleted range, which is split around it
+                vec![
+                    TextChange::Delete(*start, *point - *start),
+                    TextChange::Delete(*start + TextLength::of(text), end - *point),
+                ]
+            }
+        }
+        (TextChange::Delete(start, length), TextChange::Delete(..)) => {
+            let new_start = other.transform_position(*start, false);
+            let new_end = other.transform_position(*start + *length, false);
+            if new_end > new_start {
+                vec![TextChange::Delete(new_start, new_end - new_start)]
+            }
+            else {
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Transforms two deltas made against the same text. Returns `a` rewritten to apply after `b`,
+/// and `b` rewritten to apply after `a`. Insertions of `b` go first when they tie with those of
+/// `a` if `b_first` is set.
+pub fn transform(a: &[TextChange], b: &[TextChange], b_first: bool) -> (Vec<TextChange>, Vec<TextChange>) {
+    match (a, b) {
+        ([], _) | (_, []) => (a.to_vec(), b.to_vec()),
+        ([a], [b]) => (transform_change(a, b, b_first), transform_change(b, a, !b_first)),
+        ([first, rest @ ..], _) if !rest.is_empty() => {
+            let (new_first, b) = transform(std::slice::from_ref(first), b, b_first);
+            let (mut new_a, b) = transform(rest, &b, b_first);
+            new_a.splice(0..0, new_first);
+            (new_a, b)
+        }
+        (_, [first, rest @ ..]) => {
+            let (a, new_first) = transform(a, std::slice::from_ref(first), b_first);
+            let (a, mut new_b) = transform(&a, rest, b_first);
+            new_b.splice(0..0, new_first);
+            (a, new_b)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(line_index: usize, byte_index: usize) -> TextPosition {
+        TextPosition {line_index, byte_index}
+    }
+
+    fn apply(text: &str, changes: &[TextChange]) -> String {
+        let mut lines: Vec<String> = text.split('\n').map( | line | line.to_string()).collect();
+        for change in changes {
+            assert!(is_valid_change(&lines, change), "{:?} doesn't fit {:?}", change, lines);
+            apply_change(&mut lines, change);
+        }
+        lines.join("\n")
+    }
+
+    // both orders of applying two concurrent deltas have to end up with the same text
+    fn converges(text: &str, a: &[TextChange], b: &[TextChange]) -> String {
+        let (new_a, new_b) = transform(a, b, true);
+        let ab = apply(&apply(text, a), &new_b);
+        let ba = apply(&apply(text, b), &new_a);
+        assert_eq!(ab, ba);
+        ab
+    }
+
+    #[test]
+    fn applies_changes() {
+        let text = "fn main() {\n}";
+        assert_eq!(apply(text, &[TextChange::Insert(pos(0, 11), "\n    x".into())]), "fn main() {\n    x\n}");
+        assert_eq!(apply(text, &[TextChange::Delete(pos(0, 10), TextLength {line_count: 1, byte_count: 0})]), "fn main() }");
+    }
+
+    #[test]
+    fn transforms_inserts() {
+        let text = "abc";
+        let a = [TextChange::Insert(pos(0, 1), "X".into())];
+        let b = [TextChange::Insert(pos(0, 2), "Y\nZ".into())];
+        assert_eq!(converges(text, &a, &b), "aXbY\nZc");
+        // at the same position the insert that goes first is kept in front
+        let b = [TextChange::Insert(pos(0, 1), "Y".into())];
+        assert_eq!(converges(text, &a, &b), "aYXbc");
+    }
+
+    #[test]
+    fn transforms_deletes() {
+        let text = "one\ntwo\nthree";
+        let a = [TextChange::Delete(pos(0, 2), TextLength {line_count: 1, byte_count: 1})];
+        let b = [TextChange::Delete(pos(1, 0), TextLength {line_count: 1, byte_count: 2})];
+        assert_eq!(converges(text, &a, &b), "onree");
+        // an insert inside a deleted range survives it
+        let b = [TextChange::Insert(pos(1, 0), "X".into())];
+        assert_eq!(converges(text, &a, &b), "onXwo\nthree");
+    }
+
+    #[test]
+    fn transforms_deltas() {
+        let text = "let a = 1;\nlet b = 2;";
+        let a = [
+            TextChange::Delete(pos(0, 8), TextLength {line_count: 0, byte_count: 1}),
+            TextChange::Insert(pos(0, 8), "10".into()),
+            TextChange::Insert(pos(1, 10), "\nlet c = 3;".into()),
+        ];
+        let b = [
+            TextChange::Insert(pos(0, 0), "// x\n".into()),
+            TextChange::Delete(pos(2, 4), TextLength {line_count: 0, byte_count: 1}),
+            TextChange::Insert(pos(2, 4), "bb".into()),
+        ];
+        assert_eq!(converges(text, &a, &b), "// x\nlet a = 10;\nlet bb = 2;\nlet c = 3;");
+    }
+
+    #[test]
+    fn rejects_changes_that_dont_fit() {
+        let lines = vec!["héllo".to_string(), "world".to_string()];
+        assert!(is_valid_change(&lines, &TextChange::Delete(pos(0, 3), TextLength {line_count: 1, byte_count: 2})));
+        assert!(!is_valid_change(&lines, &TextChange::Delete(pos(0, 2), TextLength {line_count: 0, byte_count: 1})));
+        assert!(!is_valid_change(&lines, &TextChange::Insert(pos(2, 0), "x".into())));
+        assert!(!is_valid_change(&lines, &TextChange::Delete(pos(0, 1), TextLength {line_count: 0, byte_count: usize::MAX})));
+        assert!(!is_valid_change(&lines, &TextChange::Delete(pos(1, 0), TextLength {line_count: usize::MAX, byte_count: 0})));
+    }
+    
+    #[test]
+    fn diffs_texts() {
+        let old = "héllo\nworld";
+        let new = "héllo\nbig world!";
+        assert_eq!(apply(old, &diff_text(old, new)), new);
+        assert_eq!(apply(new, &diff_text(new, old)), old);
+        assert!(diff_text(old, old).is_empty());
+        // a shared prefix that ends inside a character
+        assert_eq!(apply("é", &diff_text("é", "è")), "è");
+    }
+}
@@ -2,6 +2,7 @@ use {
     crate::{
         makepad_live_id::*,
         makepad_micro_serde::{SerBin, DeBin, DeBinErr},
+        file_delta::{TextChange, TextSelection},
     },
 };
 
@@ -24,9 +25,11 @@ use {
 /// a delta based on that revision) will the server remove that delta from its history.
 /// 
 /// Whenever a server applies a delta to a file, it notifies all the participants of that file
-/// except the one from which the request to apply the delta originated of this fact. This allows
-/// the participants to update their revision of the file accordingly.
- 
+/// of this fact. This allows the participants to update their revision of the file accordingly.
+/// The participant from which the request to apply the delta originated takes the notification
+/// as the acknowledgement of its delta. Because acknowledgements and the deltas of others travel
+/// the same way, every participant sees the revisions of a file in order.
+
 /// A type for representing a request to the collab server.
 #[derive(Clone, Debug, SerBin, DeBin)]
 pub enum FileRequest {
@@ -39,15 +42,58 @@ pub enum FileRequest {
         path: String, 
         id: u64
     },
-    /// Requests the collab server to apply the given delta to the given revision of the file with
-    /// the given id.
+    /// Requests the collab server to write the file with the given id to disk. When `revision` is
+    /// set the client takes part in the collaboration session of the file and has sent all its
+    /// changes as deltas, so the server writes the text of the session. Otherwise `data` is
+    /// written, and becomes a delta for the other participants.
     SaveFile{
         path: String,
         data: String,
         id: u64,
-        patch: bool
+        patch: bool,
+        revision: Option<u64>
+    },
+    /// Requests the collab server to apply the given delta to the given revision of the file with
+    /// the given id. `delta_id` comes back in the notification for the delta, so the client can
+    /// recognize its own delta.
+    ApplyDelta{
+        path: String,
+        id: u64,
+        revision: u64,
+        delta_id: u64,
+        changes: Vec<TextChange>
+    },
+    /// Requests the collab server to add the client as a participant to the file with the given
+    /// id again, after the connection to the server was lost. The client was at the given
+    /// revision.
+    RejoinFile{
+        path: String,
+        id: u64,
+        revision: u64
     },
+    /// Tells the other participants of the file with the given id where the client's selections
+    /// are.
+    SetSelections{
+        path: String,
+        id: u64,
+        selections: Vec<TextSelection>
+    },
+    /// Requests the collab server to search every file in its tree for the given query. Matches
+    /// are streamed back as `SearchResults` notifications, followed by a `SearchDone`
+    /// notification. Starting a new search cancels the one that is still running.
+    Search{
+        query: FileSearchQuery,
+        id: u64
+    },
+}
 
+/// A type for representing a query for a project wide search.
+#[derive(Clone, Debug, Default, SerBin, DeBin, PartialEq)]
+pub struct FileSearchQuery{
+    pub text: String,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
 }
 
 /// A type for representing either a response or a notification from the collab server.
@@ -55,13 +101,19 @@ pub enum FileRequest {
 pub enum FileClientMessage {
     Response(FileResponse),
     Notification(FileNotification),
+    /// Not sent by the collab server, but by the client itself when it got its connection to a
+    /// remote server back. Everything sent in between is lost, so open files have to be rejoined.
+    Reconnected,
 }
 
 #[derive(Clone, Debug, SerBin, DeBin, PartialEq)]
 pub enum SaveKind{
     Save,
     Patch,
-    Observation
+    Observation,
+    /// The file changed on disk while its collaboration session had changes that were not
+    /// saved yet. The change on disk was not applied to the session.
+    Conflict
 }
 
 /// A type for representing a response from the collab server.
@@ -73,6 +125,8 @@ pub struct SaveFileResponse{
     pub new_data: String, 
     pub kind: SaveKind,
     pub id: u64, 
+    /// The revision of the collaboration session that was written, 0 if there is none.
+    pub revision: u64,
 }
 
 #[derive(Clone, Debug, SerBin, DeBin)]
@@ -80,6 +134,32 @@ pub struct OpenFileResponse{
     pub path: String, 
     pub data: String, 
     pub id: u64, 
+    /// The revision `data` is at.
+    pub revision: u64,
+}
+
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct RejoinFileResponse{
+    pub path: String,
+    pub id: u64,
+    pub revision: u64,
+    /// The deltas since the revision the client was at.
+    pub deltas: Vec<FileDelta>,
+    /// The whole text at `revision`, when the server no longer has the deltas since the revision
+    /// the client was at.
+    pub data: Option<String>,
+}
+
+/// A delta the collab server applied to a file.
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct FileDelta{
+    pub path: String,
+    /// The revision the delta produced.
+    pub revision: u64,
+    pub delta_id: u64,
+    /// The participant the delta came from, 0 for changes made to the file on disk.
+    pub participant: u64,
+    pub changes: Vec<TextChange>,
 }
 
 
@@ -94,8 +174,17 @@ pub enum FileResponse {
     /// The result of requesting the collab server to apply a delta to a revision of the file with
     /// the given id.
     SaveFile(Result<SaveFileResponse, FileError>),
-    
-    // Existing variants...
+    /// The result of requesting the collab server to start a search. Contains the id of the
+    /// search, or an error if the query could not be compiled.
+    Search(Result<u64, FileError>),
+    /// The result of requesting the collab server to apply a delta. Contains the revision the
+    /// delta produced. The notification for the delta is what acknowledges it.
+    ApplyDelta(Result<u64, FileError>),
+    /// The result of requesting the collab server to add the client as a participant again.
+    RejoinFile(Result<RejoinFileResponse, FileError>),
+    /// The result of telling the collab server where the client's selections are. Contains the
+    /// id of the file.
+    SetSelections(Result<u64, FileError>),
 }
 
 /// A type for representing data about a file tree.
@@ -130,17 +219,66 @@ pub struct DirectoryEntry {
 /// A type for representing a notification from the collab server.
 #[derive(Clone, Debug, SerBin, DeBin)]
 pub enum FileNotification {
+    /// Notifies the client that a file changed on disk. `old_data` is what the server last knew
+    /// was on disk, which is empty for files that are not open.
     FileChangedOnDisk(SaveFileResponse),
-    // Notifies the client that another client applied the given delta to the file with the given
-    // id. This is only sent for files for which the client is a participant.
-   // DeltaWasApplied(TextFileId),
+    /// Notifies the client that files or directories were created, removed or renamed. Renames
+    /// the server could follow are given as old and new path.
+    FileTreeChanged{
+        renamed: Vec<(String, String)>
+    },
+    /// A batch of results for the search with the given id.
+    SearchResults{
+        id: u64,
+        results: Vec<FileSearchResult>
+    },
+    /// The search with the given id has finished. If `truncated` is set it stopped early
+    /// because it found too many matches.
+    SearchDone{
+        id: u64,
+        file_count: usize,
+        match_count: usize,
+        truncated: bool
+    },
+    /// Notifies the client that the given delta was applied to a file. This is only sent for
+    /// files for which the client is a participant.
+    DeltaWasApplied(FileDelta),
+    /// Notifies the client that a participant of a file moved its selections. A participant that
+    /// left has no selections.
+    SelectionsChanged{
+        path: String,
+        participant: u64,
+        selections: Vec<TextSelection>
+    },
+}
+
+/// A type for representing all matches of a search in a single file.
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct FileSearchResult {
+    /// The path of the file, relative to the root of the file tree.
+    pub path: String,
+    pub matches: Vec<FileSearchMatch>,
+}
+
+/// A type for representing a single match of a search. Matches never span lines. The start and
+/// end are byte offsets into the line.
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct FileSearchMatch {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+    /// The text of the line the match is on.
+    pub text: String,
 }
 
 /// A type for representing errors from the collab server.
 #[derive(Clone, Debug, SerBin, DeBin)]
 pub enum FileError {
     Unknown(String),
-    CannotOpen(String)
+    CannotOpen(String),
+    /// The client is not in step with the collaboration session of the file with the given path,
+    /// and has to rejoin it.
+    OutOfSync(String),
 }
 
 /// An identifier for files on the collab server.
@@ -1,5 +1,7 @@
 pub mod file_protocol;
+pub mod file_delta;
 
 pub use file_protocol::*;
+pub use file_delta::*;
 pub use makepad_live_id;
 pub use makepad_micro_serde;
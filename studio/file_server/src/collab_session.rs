@@ -0,0 +1,395 @@
+use {
+    crate::{
+        makepad_file_protocol::{
+            FileDelta,
+            FileError,
+            FileNotification,
+            SaveKind,
+            TextChange,
+            TextSelection,
+            apply_change,
+            diff_text,
+            is_valid_change,
+            transform,
+        },
+        file_server::{ConnectionId, NotificationSender},
+    },
+    std::{
+        collections::{HashMap, VecDeque},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+// A session keeps at most this many deltas around for participants that are behind. A
+// participant that falls further behind gets the whole text when it rejoins.
+const MAX_HISTORY: usize = 4096;
+
+/// The collaboration sessions of the open files, shared by every connection.
+#[derive(Default)]
+pub struct Collab {
+    sessions: HashMap<String, CollabSession>,
+    // What the server last wrote to files without a session, so writes of its own aren't taken
+    // for changes made by others.
+    written: HashMap<String, String>,
+    // Used to send notifications to the participants of a session.
+    senders: HashMap<ConnectionId, Box<dyn NotificationSender>>,
+}
+
+struct CollabSession {
+    lines: Vec<String>,
+    // The text as the server last read or wrote it, to tell changes made by others on disk apart
+    // from those of the server.
+    on_disk: String,
+    revision: u64,
+    // The deltas that produced the last revisions, oldest first.
+    history: VecDeque<FileDelta>,
+    // The participants of the session, with the last revision each of them has seen.
+    participants: HashMap<ConnectionId, u64>,
+}
+
+impl CollabSession {
+    fn new(text: &str) -> Self {
+        // Revisions of a new session start at the time it was opened, so a revision of an earlier
+        // session of the same file is never mistaken for one of this session.
+        let revision = SystemTime::now().duration_since(UNIX_EPOCH).map_or(1, | time | time.as_micros() as u64);
+        Self {
+            lines: text.split('\n').map( | line | line.to_string()).collect(),
+            on_disk: text.to_string(),
+            revision,
+            history: VecDeque::new(),
+            participants: HashMap::new(),
+        }
+    }
+
+    fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    // The oldest revision the deltas since can still be given for.
+    fn oldest_revision(&self) -> u64 {
+        self.revision - self.history.len() as u64
+    }
+
+    fn forget_seen_history(&mut self) {
+        let seen = self.participants.values().min().cloned().unwrap_or(self.revision);
+        while self.history.front().is_some_and( | delta | delta.revision <= seen) || self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+    }
+}
+
+impl Collab {
+    pub fn add_connection(&mut self, connection_id: ConnectionId, sender: Box<dyn NotificationSender>) {
+        self.senders.insert(connection_id, sender);
+    }
+
+    /// Removes a connection as a participant from every session. Sessions without participants
+    /// are closed.
+    pub fn remove_connection(&mut self, connection_id: ConnectionId) {
+        self.senders.remove(&connection_id);
+        let paths: Vec<String> = self.sessions.keys().cloned().collect();
+        for path in paths {
+            self.leave(connection_id, &path);
+        }
+    }
+
+    fn leave(&mut self, connection_id: ConnectionId, path: &str) {
+        let Some(session) = self.sessions.get_mut(path) else {return};
+        if session.participants.remove(&connection_id).is_none() {
+            return
+        }
+        if session.participants.is_empty() {
+            self.sessions.remove(path);
+            return
+        }
+        session.forget_seen_history();
+        self.broadcast(path, Some(connection_id), FileNotification::SelectionsChanged {
+            path: path.to_string(),
+            participant: connection_id.0 as u64,
+            selections: Vec::new()
+        });
+    }
+
+    fn broadcast(&self, path: &str, except: Option<ConnectionId>, notification: FileNotification) {
+        let Some(session) = self.sessions.get(path) else {return};
+        for connection_id in session.participants.keys() {
+            if Some(*connection_id) != except {
+                if let Some(sender) = self.senders.get(connection_id) {
+                    sender.send_notification(notification.clone());
+                }
+            }
+        }
+    }
+
+    /// Adds a connection as a participant to the session of a file, opening the session with
+    /// the text `read` returns if there is none. Returns the text and its revision.
+    pub fn open(
+        &mut self,
+        connection_id: ConnectionId,
+        path: &str,
+        read: impl FnOnce() -> Result<String, FileError>
+    ) -> Result<(String, u64), FileError> {
+        if !self.sessions.contains_key(path) {
+            self.sessions.insert(path.to_string(), CollabSession::new(&read() ?));
+        }
+        let session = self.sessions.get_mut(path).unwrap();
+        session.participants.insert(connection_id, session.revision);
+        Ok((session.text(), session.revision))
+    }
+
+    /// Adds a connection as a participant again after it lost its connection while at the given
+    /// revision. Returns the current revision, with either the deltas since the given revision or
+    /// the whole text.
+    pub fn rejoin(
+        &mut self,
+        connection_id: ConnectionId,
+        path: &str,
+        revision: u64,
+        read: impl FnOnce() -> Result<String, FileError>
+    ) -> Result<(u64, Vec<FileDelta>, Option<String>), FileError> {
+        let (text, current) = self.open(connection_id, path, read) ?;
+        let session = &self.sessions[path];
+        if revision >= session.oldest_revision() && revision <= current {
+            let deltas = session.history.iter().filter( | delta | delta.revision > revision).cloned().collect();
+            Ok((current, deltas, None))
+        }
+        else {
+            Ok((current, Vec::new(), Some(text)))
+        }
+    }
+
+    /// Applies a delta a participant made against the given revision. The delta is transformed
+    /// against the deltas it didn't know about, and sent to every participant, the one it came
+    /// from included. Returns the new revision.
+    pub fn apply_delta(
+        &mut self,
+        connection_id: ConnectionId,
+        path: &str,
+        revision: u64,
+        delta_id: u64,
+        mut changes: Vec<TextChange>
+    ) -> Result<u64, FileError> {
+        let out_of_sync = || FileError::OutOfSync(path.to_string());
+        let session = self.sessions.get_mut(path).ok_or_else(out_of_sync) ?;
+        if !session.participants.contains_key(&connection_id) || revision < session.oldest_revision() || revision > session.revision {
+            return Err(out_of_sync())
+        }
+        for delta in session.history.iter().filter( | delta | delta.revision > revision) {
+            changes = transform(&changes, &delta.changes, true).0;
+        }
+        let mut lines = session.lines.clone();
+        for change in &changes {
+            if !is_valid_change(&lines, change) {
+                return Err(out_of_sync())
+            }
+            apply_change(&mut lines, change);
+        }
+        session.lines = lines;
+        session.revision += 1;
+        session.participants.insert(connection_id, revision);
+        let delta = FileDelta {
+            path: path.to_string(),
+            revision: session.revision,
+            delta_id,
+            participant: connection_id.0 as u64,
+            changes
+        };
+        session.history.push_back(delta.clone());
+        session.forget_seen_history();
+        let revision = session.revision;
+        self.broadcast(path, None, FileNotification::DeltaWasApplied(delta));
+        Ok(revision)
+    }
+
+    /// Tells the session of a file what is on disk now. Changes that the server didn't write
+    /// itself become a delta, unless the session has edits that aren't on disk yet: then the
+    /// change is a conflict that the participants have to resolve. Returns the text that was on
+    /// disk before, the kind of change and the revision of the session, or None if nothing
+    /// changed.
+    pub fn disk_changed(&mut self, path: &str, text: &str) -> Option<(String, SaveKind, u64)> {
+        let Some(session) = self.sessions.get_mut(path) else {
+            if self.written.remove(path).as_deref() == Some(text) {
+                return None
+            }
+            return Some((String::new(), SaveKind::Observation, 0))
+        };
+        if session.on_disk == text {
+            return None
+        }
+        let old_data = std::mem::replace(&mut session.on_disk, text.to_string());
+        if session.text() != old_data {
+            return Some((old_data, SaveKind::Conflict, session.revision))
+        }
+        self.replace_text(None, path, text);
+        Some((old_data, SaveKind::Observation, self.sessions[path].revision))
+    }
+
+    /// Tells the session of a file that the server wrote the given text to disk.
+    pub fn saved(&mut self, path: &str, text: &str) {
+        match self.sessions.get_mut(path) {
+            Some(session) => session.on_disk = text.to_string(),
+            None => {
+                self.written.insert(path.to_string(), text.to_string());
+            }
+        }
+    }
+
+    /// Moves the sessions of a file, or of the files in a directory, that was renamed on disk.
+    /// Returns the paths of the sessions that moved, with their new paths.
+    pub fn rename(&mut self, from: &str, to: &str) -> Vec<(String, String)> {
+        let prefix = format!("{}/", from);
+        let paths: Vec<String> = self.sessions.keys().filter( | path | *path == from || path.starts_with(&prefix)).cloned().collect();
+        let mut renamed = Vec::new();
+        for path in paths {
+            let new_path = format!("{}{}", to, &path[from.len()..]);
+            let session = self.sessions.remove(&path).unwrap();
+            self.sessions.insert(new_path.clone(), session);
+            renamed.push((path, new_path));
+        }
+        self.written.retain( | path, _ | path != from && !path.starts_with(&prefix));
+        renamed
+    }
+
+    /// The paths of the files that have a session.
+    pub fn paths(&self) -> Vec<String> {
+        self.sessions.keys().cloned().collect()
+    }
+
+    /// Sends a notification to every connection.
+    pub fn notify_all(&self, notification: FileNotification) {
+        for sender in self.senders.values() {
+            sender.send_notification(notification.clone());
+        }
+    }
+
+    /// Makes the text of a session the given text, for changes that don't come as deltas, like
+    /// a file that changed on disk. `connection_id` is None for changes on disk.
+    pub fn replace_text(&mut self, connection_id: Option<ConnectionId>, path: &str, text: &str) {
+        let Some(session) = self.sessions.get_mut(path) else {return};
+        let changes = diff_text(&session.text(), text);
+        if changes.is_empty() {
+            return
+        }
+        for change in &changes {
+            apply_change(&mut session.lines, change);
+        }
+        session.revision += 1;
+        let delta = FileDelta {
+            path: path.to_string(),
+            revision: session.revision,
+            delta_id: 0,
+            participant: connection_id.map_or(0, | connection_id | connection_id.0 as u64),
+            changes
+        };
+        session.history.push_back(delta.clone());
+        session.forget_seen_history();
+        self.broadcast(path, None, FileNotification::DeltaWasApplied(delta));
+    }
+
+    /// The text of the session of a file and its revision.
+    pub fn text(&self, path: &str) -> Option<(String, u64)> {
+        self.sessions.get(path).map( | session | (session.text(), session.revision))
+    }
+
+    pub fn set_selections(&mut self, connection_id: ConnectionId, path: &str, selections: Vec<TextSelection>) -> Result<(), FileError> {
+        let session = self.sessions.get(path).ok_or_else( || FileError::OutOfSync(path.to_string())) ?;
+        if !session.participants.contains_key(&connection_id) {
+            return Err(FileError::OutOfSync(path.to_string()))
+        }
+        self.broadcast(path, Some(connection_id), FileNotification::SelectionsChanged {
+            path: path.to_string(),
+            participant: connection_id.0 as u64,
+            selections
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::makepad_file_protocol::{TextLength, TextPosition},
+        std::sync::{Arc, Mutex},
+    };
+
+    fn connect(collab: &mut Collab, id: usize) -> Arc<Mutex<Vec<FileNotification >>> {
+        let notifications = Arc::new(Mutex::new(Vec::new()));
+        collab.add_connection(ConnectionId(id), Box::new({
+            let notifications = notifications.clone();
+            move | notification | notifications.lock().unwrap().push(notification)
+        }));
+        notifications
+    }
+
+    fn pos(line_index: usize, byte_index: usize) -> TextPosition {
+        TextPosition {line_index, byte_index}
+    }
+
+    #[test]
+    fn rebases_concurrent_deltas() {
+        let mut collab = Collab::default();
+        let a = connect(&mut collab, 1);
+        let b = connect(&mut collab, 2);
+        let (_, revision) = collab.open(ConnectionId(1), "main.rs", || Ok("fn main() {\n}".into())).unwrap();
+        collab.open(ConnectionId(2), "main.rs", || unreachable!()).unwrap();
+        // both edit the same revision, the second delta is moved past the first
+        collab.apply_delta(ConnectionId(1), "main.rs", revision, 10, vec![
+            TextChange::Insert(pos(0, 11), "\n    a();".into())
+        ]).unwrap();
+        collab.apply_delta(ConnectionId(2), "main.rs", revision, 20, vec![
+            TextChange::Delete(pos(0, 3), TextLength {line_count: 0, byte_count: 4}),
+            TextChange::Insert(pos(0, 3), "run".into())
+        ]).unwrap();
+        assert_eq!(collab.text("main.rs").unwrap(), ("fn run() {\n    a();\n}".to_string(), revision + 2));
+        assert_eq!(a.lock().unwrap().len(), 2);
+        let FileNotification::DeltaWasApplied(delta) = &b.lock().unwrap()[1] else {panic!()};
+        assert_eq!((delta.delta_id, delta.revision, delta.changes[0].clone()), (20, revision + 2, TextChange::Delete(pos(0, 3), TextLength {line_count: 0, byte_count: 4})));
+        // a participant that lost its connection gets what it missed
+        let (current, deltas, data) = collab.rejoin(ConnectionId(1), "main.rs", revision + 1, || unreachable!()).unwrap();
+        assert_eq!((current, deltas.len(), data), (revision + 2, 1, None));
+    }
+
+    #[test]
+    fn closes_sessions_without_participants() {
+        let mut collab = Collab::default();
+        connect(&mut collab, 1);
+        let b = connect(&mut collab, 2);
+        collab.open(ConnectionId(1), "lib.rs", || Ok(String::new())).unwrap();
+        collab.open(ConnectionId(2), "lib.rs", || Ok(String::new())).unwrap();
+        collab.remove_connection(ConnectionId(1));
+        assert!(matches!(&b.lock().unwrap()[0], FileNotification::SelectionsChanged {participant: 1, selections, ..} if selections.is_empty()));
+        collab.remove_connection(ConnectionId(2));
+        assert!(collab.text("lib.rs").is_none());
+        assert!(collab.apply_delta(ConnectionId(2), "lib.rs", 0, 0, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn reports_conflicting_changes_on_disk() {
+        let mut collab = Collab::default();
+        connect(&mut collab, 1);
+        let (_, revision) = collab.open(ConnectionId(1), "app.rs", || Ok("a".into())).unwrap();
+        // without edits in the session a change on disk goes to the participants
+        assert_eq!(collab.disk_changed("app.rs", "b"), Some(("a".to_string(), SaveKind::Observation, revision + 1)));
+        assert_eq!(collab.disk_changed("app.rs", "b"), None);
+        // with edits that aren't on disk it doesn't, until they are saved or thrown away
+        collab.apply_delta(ConnectionId(1), "app.rs", revision + 1, 10, vec![TextChange::Insert(pos(0, 1), "c".into())]).unwrap();
+        assert_eq!(collab.disk_changed("app.rs", "d"), Some(("b".to_string(), SaveKind::Conflict, revision + 2)));
+        assert_eq!(collab.text("app.rs").unwrap().0, "bc");
+        // writes of the server itself are not changes
+        collab.saved("main.rs", "fn main() {}");
+        assert_eq!(collab.disk_changed("main.rs", "fn main() {}"), None);
+        assert_eq!(collab.disk_changed("main.rs", "fn main() {}"), Some((String::new(), SaveKind::Observation, 0)));
+    }
+
+    #[test]
+    fn moves_sessions_of_renamed_directories() {
+        let mut collab = Collab::default();
+        connect(&mut collab, 1);
+        collab.open(ConnectionId(1), "src/app.rs", || Ok("app".into())).unwrap();
+        collab.open(ConnectionId(1), "src_old.rs", || Ok("old".into())).unwrap();
+        assert_eq!(collab.rename("src", "lib"), vec![("src/app.rs".to_string(), "lib/app.rs".to_string())]);
+        assert_eq!(collab.text("lib/app.rs").unwrap().0, "app");
+        assert!(collab.text("src_old.rs").is_some());
+    }
+}
@@ -10,17 +10,27 @@ use {
             FileResponse,
             SaveKind,
             SaveFileResponse,
-            OpenFileResponse
+            OpenFileResponse,
+            FileSearchQuery,
+            FileSearchResult,
+            FileSearchMatch,
+            RejoinFileResponse,
+            TextChange,
+            TextSelection,
         },
+        makepad_regex::{Regex, is_whole_word},
+        gitignore::Gitignore,
+        collab_session::Collab,
+        file_watcher::{FileWatcher, WatchEvent},
     },
     std::{
         thread,
         cmp::Ordering,
         fmt,
         fs,
-        time::Duration,
+        time::{Duration, Instant},
         path::{Path, PathBuf},
-        sync::{Arc, RwLock, Mutex},
+        sync::{Arc, RwLock, Mutex, atomic::{AtomicU64, self}},
     },
 };
 
@@ -29,16 +39,29 @@ pub struct FileServer {
     next_connection_id: usize,
     // State that is shared between every connection
     shared: Arc<RwLock<Shared >>,
+    // The collaboration sessions of the open files
+    collab: Arc<Mutex<Collab >>,
 }
 
 impl FileServer {
     /// Creates a new collab server rooted at the given path.
     pub fn new<P: Into<PathBuf >> (root_path: P) -> FileServer {
+        let root_path = root_path.into();
+        let collab: Arc<Mutex<Collab >> = Default::default();
+        // One watcher for the whole tree tells every connection about changes made by others.
+        let watcher = FileWatcher::start(root_path.clone(), {
+            let root_path = root_path.clone();
+            let collab = collab.clone();
+            move | events | handle_watch_events(&root_path, &collab, events)
+        });
         FileServer {
-            next_connection_id: 0,
+            // Participant 0 stands for changes on disk, so connections start at 1.
+            next_connection_id: 1,
             shared: Arc::new(RwLock::new(Shared {
-                root_path: root_path.into(),
+                root_path,
+                _watcher: watcher,
             })),
+            collab,
         }
     }
     
@@ -49,12 +72,13 @@ impl FileServer {
     pub fn connect(&mut self, notification_sender: Box<dyn NotificationSender>) -> FileServerConnection {
         let connection_id = ConnectionId(self.next_connection_id);
         self.next_connection_id += 1;
+        self.collab.lock().unwrap().add_connection(connection_id, notification_sender.clone());
         FileServerConnection {
-            _connection_id:connection_id,
+            connection_id,
             shared: self.shared.clone(),
+            collab: self.collab.clone(),
             _notification_sender: notification_sender,
-            open_files: Default::default(),
-            stop_observation: Default::default()
+            active_search: Default::default(),
         }
     }
 }
@@ -62,13 +86,15 @@ impl FileServer {
 /// A connection to a collab server.
 pub struct FileServerConnection {
     // The id for this connection.
-    _connection_id: ConnectionId,
+    connection_id: ConnectionId,
     // State is shared between every connection.
     shared: Arc<RwLock<Shared >>,
+    // The collaboration sessions are shared between every connection.
+    collab: Arc<Mutex<Collab >>,
     // Used to send notifications for this connection.
     _notification_sender: Box<dyn NotificationSender>,
-    open_files: Arc<Mutex<Vec<(String, u64, Vec<u8>)>>>,
-    stop_observation: Arc<Mutex<bool>>,
+    // The id of the search that is running, a running search stops when this changes.
+    active_search: Arc<AtomicU64>,
 }
 
 impl FileServerConnection {
@@ -82,7 +108,11 @@ impl FileServerConnection {
         match request {
             FileRequest::LoadFileTree {with_data} => FileResponse::LoadFileTree(self.load_file_tree(with_data)),
             FileRequest::OpenFile{path,id} => FileResponse::OpenFile(self.open_file(path, id)),
-            FileRequest::SaveFile{path, data, id, patch} => FileResponse::SaveFile(self.save_file(path, data, id, patch)),
+            FileRequest::SaveFile{path, data, id, patch, revision} => FileResponse::SaveFile(self.save_file(path, data, id, patch, revision)),
+            FileRequest::Search{query, id} => FileResponse::Search(self.search(query, id)),
+            FileRequest::ApplyDelta{path, id: _, revision, delta_id, changes} => FileResponse::ApplyDelta(self.apply_delta(path, revision, delta_id, changes)),
+            FileRequest::RejoinFile{path, id, revision} => FileResponse::RejoinFile(self.rejoin_file(path, id, revision)),
+            FileRequest::SetSelections{path, id, selections} => FileResponse::SetSelections(self.set_selections(path, id, selections)),
         }
     }
     
@@ -100,13 +130,7 @@ impl FileServerConnection {
                 // Get the file name for the entry.
                 let name = entry.file_name();
                 if let Ok(name_string) = name.into_string() {
-                    if entry_path.is_dir() && name_string == "target"
-                        || name_string.starts_with('.') {
-                        // Skip over directories called "target". This is sort of a hack. The reason
-                        // it's here is that the "target" directory for Rust projects is huge, and
-                        // our current implementation of the file tree widget is not yet fast enough
-                        // to display vast numbers of nodes. We paper over this by pretending the
-                        // "target" directory does not exist.
+                    if is_hidden_entry(&name_string, entry_path.is_dir()) {
                         continue;
                     }
                 }
@@ -167,52 +191,59 @@ impl FileServerConnection {
         Ok(FileTreeData {root_path: "".into(), root})
     }
     
+    // Handles a `Search` request. The search runs on its own thread and streams its results
+    // back as notifications.
+    fn search(&self, query: FileSearchQuery, id: u64) -> Result<u64, FileError> {
+        self.active_search.store(id, atomic::Ordering::SeqCst);
+        if query.text.is_empty() {
+            self._notification_sender.send_notification(FileNotification::SearchDone {
+                id,
+                file_count: 0,
+                match_count: 0,
+                truncated: false
+            });
+            return Ok(id)
+        }
+        let regex = if query.regex {
+            Regex::new(&query.text, !query.case_sensitive).map_err( | error | FileError::Unknown(error.message)) ?
+        }
+        else {
+            Regex::literal(&query.text, !query.case_sensitive)
+        };
+        let root_path = self.shared.read().unwrap().root_path.clone();
+        let mut search = FileSearch {
+            id,
+            whole_word: query.whole_word,
+            regex,
+            notification_sender: self._notification_sender.clone(),
+            active_search: self.active_search.clone(),
+            batch: Vec::new(),
+            last_flush: Instant::now(),
+            file_count: 0,
+            match_count: 0,
+            truncated: false,
+        };
+        thread::spawn(move || {
+            search.search_directory(&root_path, "", &mut Gitignore::default());
+            if !search.is_cancelled() {
+                search.flush();
+                search.notification_sender.send_notification(FileNotification::SearchDone {
+                    id,
+                    file_count: search.file_count,
+                    match_count: search.match_count,
+                    truncated: search.truncated
+                });
+            }
+        });
+        Ok(id)
+    }
+    
     fn make_full_path(&self, child_path:&String)->PathBuf{
         let mut path = self.shared.read().unwrap().root_path.clone();
         path.push(child_path);
         path
     }
     
-    fn start_observation(&self) {
-        let open_files = self.open_files.clone();
-        let shared = self.shared.clone();
-        let notification_sender = self._notification_sender.clone();
-        let stop_observation = self.stop_observation.clone();
-        thread::spawn(move || {
-            let stop = *stop_observation.lock().unwrap();
-            while !stop{
-                if let Ok(mut files) = open_files.lock(){
-                    for (path, file_id, last_content) in files.iter_mut() {
-                        let full_path = {
-                            let shared = shared.read().unwrap();
-                            shared.root_path.join(&path)
-                        };
-                        if let Ok(bytes) = fs::read(&full_path) {
-                            if bytes.len() > 0 && bytes != *last_content {
-                                let new_data = String::from_utf8_lossy(&bytes);
-                                let old_data = String::from_utf8_lossy(&last_content);
-                                // Send notification of external file change.
-                                notification_sender
-                                .send_notification(FileNotification::FileChangedOnDisk(
-                                    SaveFileResponse{
-                                        path: path.to_string(),
-                                        new_data: new_data.to_string(),
-                                        old_data: old_data.to_string(),
-                                        kind: SaveKind::Observation,
-                                        id: *file_id
-                                    }
-                                ));
-                                *last_content = bytes;
-                            }
-                        }
-                    }
-                }
-                // Sleep for 500ms.
-                thread::sleep(Duration::from_millis(100));
-            }
-        });
-    }
-    
     // Handles an `OpenFile` request.
     fn open_file(&self, child_path: String, id:u64) -> Result<OpenFileResponse, FileError> {
         let path = self.make_full_path(&child_path);
@@ -221,47 +252,77 @@ impl FileServerConnection {
             | error | FileError::Unknown(error.to_string())
         ) ?;
         
-        let mut open_files = self.open_files.lock().unwrap();
-        
-        if open_files.iter().find(|(cp,_,_)| *cp == child_path).is_none(){
-            open_files.push((child_path.clone(), id, bytes.clone()));
-        }
-        
-        if open_files.len() == 1 {
-            self.start_observation();
-        }
         // Converts the file contents to a `Text`. This is necessarily a lossy conversion
         // because `Text` assumes everything is UTF-8 encoded, and this isn't always the
         // case for files on disk (is this a problem?)
-        /*let text: Text = Text::from_lines(String::from_utf8_lossy(&bytes)
-            .lines()
-            .map( | line | line.chars().collect::<Vec<_ >> ())
-            .collect::<Vec<_ >>());*/
+        // When the file is already open, the text of its session has edits that may not be
+        // on disk yet.
+        let (text, revision) = self.collab.lock().unwrap().open(
+            self.connection_id,
+            &child_path,
+            || Ok(String::from_utf8_lossy(&bytes).to_string())
+        ) ?;
         
-        let text = String::from_utf8_lossy(&bytes);
         Ok(OpenFileResponse{
             path: child_path,
-            data: text.to_string(),
-            id
+            data: text,
+            id,
+            revision
+        })
+    }
+    
+    // Handles a `RejoinFile` request.
+    fn rejoin_file(&self, child_path: String, id: u64, revision: u64) -> Result<RejoinFileResponse, FileError> {
+        let path = self.make_full_path(&child_path);
+        let bytes = fs::read(&path).map_err(
+            | error | FileError::Unknown(error.to_string())
+        ) ?;
+        let (revision, deltas, data) = self.collab.lock().unwrap().rejoin(
+            self.connection_id,
+            &child_path,
+            revision,
+            || Ok(String::from_utf8_lossy(&bytes).to_string())
+        ) ?;
+        Ok(RejoinFileResponse{
+            path: child_path,
+            id,
+            revision,
+            deltas,
+            data
         })
     }
     
     // Handles an `ApplyDelta` request.
+    fn apply_delta(&self, child_path: String, revision: u64, delta_id: u64, changes: Vec<TextChange>) -> Result<u64, FileError> {
+        self.collab.lock().unwrap().apply_delta(self.connection_id, &child_path, revision, delta_id, changes)
+    }
+    
+    // Handles a `SetSelections` request.
+    fn set_selections(&self, child_path: String, id: u64, selections: Vec<TextSelection>) -> Result<u64, FileError> {
+        self.collab.lock().unwrap().set_selections(self.connection_id, &child_path, selections) ?;
+        Ok(id)
+    }
+    
+    // Handles a `SaveFile` request.
     fn save_file(
         &self,
         child_path: String,
-        new_data: String,
+        data: String,
         id: u64,
-        patch: bool
+        patch: bool,
+        revision: Option<u64>
     ) -> Result<SaveFileResponse, FileError> {
-        let mut open_files = self.open_files.lock().unwrap();
-                
-        if let Some(of) = open_files.iter_mut().find(|(cp,_,_)| *cp == child_path){
-            of.2 =  new_data.as_bytes().to_vec();
-        }
-        else{
-            open_files.push((child_path.clone(), id, new_data.as_bytes().to_vec()));
-        }
+        let mut collab = self.collab.lock().unwrap();
+        
+        // A participant that sends deltas has its changes in the session already, with those of
+        // the others. A client that doesn't saves its data, which the others then get as a delta.
+        let (new_data, revision) = match (revision, collab.text(&child_path)) {
+            (Some(_), Some(session)) => session,
+            _ => {
+                collab.replace_text(Some(self.connection_id), &child_path, &data);
+                (data, collab.text(&child_path).map_or(0, | (_, revision) | revision))
+            }
+        };
         
         let path = self.make_full_path(&child_path);
         
@@ -272,17 +333,218 @@ impl FileServerConnection {
         fs::write(&path, &new_data).map_err(
             | error | FileError::Unknown(error.to_string())
         ) ?;
+        collab.saved(&child_path, &new_data);
         
         Ok(SaveFileResponse{
             path: child_path, 
             old_data,
             new_data,
             id,
-            kind: if patch{SaveKind::Patch}else{SaveKind::Save}
+            kind: if patch{SaveKind::Patch}else{SaveKind::Save},
+            revision
         })
     }
 }
 
+// Skip over directories called "target". This is sort of a hack. The reason it's here is that
+// the "target" directory for Rust projects is huge, and our current implementation of the file
+// tree widget is not yet fast enough to display vast numbers of nodes. We paper over this by
+// pretending the "target" directory does not exist. Hidden files are skipped as well.
+pub(crate) fn is_hidden_entry(name: &str, is_dir: bool) -> bool {
+    is_dir && name == "target" || name.starts_with('.')
+}
+
+// Tells the sessions and every connection about changes in the tree that the watcher found.
+fn handle_watch_events(root_path: &Path, collab: &Mutex<Collab>, events: Vec<WatchEvent>) {
+    let mut collab = collab.lock().unwrap();
+    let mut changed = Vec::new();
+    let mut renamed = Vec::new();
+    let mut tree_changed = false;
+    for event in events {
+        match event {
+            WatchEvent::Changed(path) => changed.push(path),
+            WatchEvent::Created(path) => {
+                tree_changed = true;
+                changed.push(path);
+            }
+            WatchEvent::Removed(_) => tree_changed = true,
+            WatchEvent::Renamed {from, to} => {
+                tree_changed = true;
+                collab.rename(&from, &to);
+                renamed.push((from, to.clone()));
+                changed.push(to);
+            }
+            WatchEvent::Rescan => {
+                tree_changed = true;
+                changed.extend(collab.paths());
+            }
+        }
+    }
+    changed.sort();
+    changed.dedup();
+    for path in changed {
+        let Ok(bytes) = fs::read(root_path.join(&path)) else {continue};
+        let new_data = String::from_utf8_lossy(&bytes).to_string();
+        if let Some((old_data, kind, revision)) = collab.disk_changed(&path, &new_data) {
+            collab.notify_all(FileNotification::FileChangedOnDisk(SaveFileResponse {
+                path,
+                old_data,
+                new_data,
+                kind,
+                id: 0,
+                revision
+            }));
+        }
+    }
+    if tree_changed {
+        collab.notify_all(FileNotification::FileTreeChanged {renamed});
+    }
+}
+
+// Files larger than this are not searched.
+const MAX_SEARCH_FILE_SIZE: u64 = 4 * 1024 * 1024;
+// A search stops after finding this many matches.
+const MAX_SEARCH_MATCHES: usize = 10000;
+
+// The state of a running search.
+struct FileSearch {
+    id: u64,
+    whole_word: bool,
+    regex: Regex,
+    notification_sender: Box<dyn NotificationSender>,
+    active_search: Arc<AtomicU64>,
+    batch: Vec<FileSearchResult>,
+    last_flush: Instant,
+    file_count: usize,
+    match_count: usize,
+    truncated: bool,
+}
+
+impl FileSearch {
+    fn is_cancelled(&self) -> bool {
+        self.active_search.load(atomic::Ordering::SeqCst) != self.id
+    }
+    
+    // Walks a directory in the same order as the file tree, skipping whatever the .gitignore
+    // files along the way exclude.
+    fn search_directory(&mut self, path: &Path, child_path: &str, gitignore: &mut Gitignore) {
+        let Ok(read_dir) = fs::read_dir(path) else {return};
+        let rule_count = gitignore.len();
+        if let Ok(contents) = fs::read_to_string(path.join(".gitignore")) {
+            gitignore.add_rules(child_path, &contents);
+        }
+        let mut entries = Vec::new();
+        for entry in read_dir.flatten() {
+            let Ok(name) = entry.file_name().into_string() else {continue};
+            let entry_path = entry.path();
+            let is_dir = entry_path.is_dir();
+            if is_hidden_entry(&name, is_dir) || !is_dir && !entry_path.is_file() {
+                continue;
+            }
+            let entry_child_path = if child_path.is_empty() {
+                name.clone()
+            }
+            else {
+                format!("{}/{}", child_path, name)
+            };
+            if gitignore.is_ignored(&entry_child_path, is_dir) {
+                continue;
+            }
+            entries.push((!is_dir, name, entry_path, entry_child_path));
+        }
+        entries.sort();
+        for (is_file, _, entry_path, entry_child_path) in entries {
+            if self.is_cancelled() || self.truncated {
+                break;
+            }
+            if is_file {
+                self.search_file(&entry_path, entry_child_path);
+            }
+            else {
+                self.search_directory(&entry_path, &entry_child_path, gitignore);
+            }
+        }
+        gitignore.truncate(rule_count);
+    }
+    
+    fn search_file(&mut self, path: &Path, child_path: String) {
+        if fs::metadata(path).map_or(true, | metadata | metadata.len() > MAX_SEARCH_FILE_SIZE) {
+            return
+        }
+        let Ok(bytes) = fs::read(path) else {return};
+        // Skip binary files
+        if bytes[..bytes.len().min(8000)].contains(&0) {
+            return
+        }
+        let mut matches = Vec::new();
+        for (line, line_bytes) in bytes.split( | byte | *byte == b'\n').enumerate() {
+            let line_bytes = line_bytes.strip_suffix(b"\r").unwrap_or(line_bytes);
+            let text = String::from_utf8_lossy(line_bytes);
+            for found in self.regex.find_all(&text) {
+                if found.end() == found.start() || self.whole_word && !is_whole_word(&text, found.range()) {
+                    continue;
+                }
+                if self.match_count == MAX_SEARCH_MATCHES {
+                    self.truncated = true;
+                    break;
+                }
+                self.match_count += 1;
+                matches.push(FileSearchMatch {
+                    line,
+                    start: original_offset(line_bytes, found.start()),
+                    end: original_offset(line_bytes, found.end()),
+                    text: text.to_string()
+                });
+            }
+            if self.truncated {
+                break;
+            }
+        }
+        if !matches.is_empty() {
+            self.file_count += 1;
+            self.batch.push(FileSearchResult {path: child_path, matches});
+            if self.last_flush.elapsed() > Duration::from_millis(50) {
+                self.flush();
+            }
+        }
+    }
+    
+    fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        if !self.batch.is_empty() {
+            self.notification_sender.send_notification(FileNotification::SearchResults {
+                id: self.id,
+                results: std::mem::take(&mut self.batch)
+            });
+        }
+    }
+}
+
+// Maps a byte offset into the lossy decoding of `bytes` back to an offset into `bytes`, so
+// matches after invalid UTF-8 still point at the right place in the file. Every invalid
+// sequence decodes to a single replacement character.
+fn original_offset(bytes: &[u8], lossy_offset: usize) -> usize {
+    let mut lossy = 0;
+    let mut original = 0;
+    for chunk in bytes.utf8_chunks() {
+        let valid = chunk.valid().len();
+        if lossy_offset <= lossy + valid {
+            return original + lossy_offset - lossy;
+        }
+        lossy += valid;
+        original += valid;
+        let invalid = chunk.invalid().len();
+        if invalid > 0 {
+            if lossy_offset < lossy + char::REPLACEMENT_CHARACTER.len_utf8() {
+                return original;
+            }
+            lossy += char::REPLACEMENT_CHARACTER.len_utf8();
+            original += invalid;
+        }
+    }
+    original
+}
+
 /// A trait for sending notifications over a connection.
 pub trait NotificationSender: Send {
     /// This method is necessary to create clones of boxed trait objects.
@@ -318,9 +580,78 @@ impl fmt::Debug for dyn NotificationSender {
 #[derive(Debug)]
 struct Shared {
     root_path: PathBuf,
+    // Watches the tree for as long as the server is around.
+    _watcher: FileWatcher,
+}
+
+impl Drop for FileServerConnection {
+    fn drop(&mut self) {
+        self.collab.lock().unwrap().remove_connection(self.connection_id);
+    }
 }
 
-/// An identifier for a connection.
+/// An identifier for a connection. It is also the participant id of the connection in the
+/// collaboration sessions.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-struct ConnectionId(usize);
+pub struct ConnectionId(pub usize);
+
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn search(name: &str, contents: &[u8], pattern: &str, match_count: usize) -> (Vec<FileSearchMatch>, bool) {
+        let path = std::env::temp_dir().join(format!("file_server_test_{}_{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        let notifications = Arc::new(Mutex::new(Vec::new()));
+        let mut search = FileSearch {
+            id: 1,
+            whole_word: false,
+            regex: Regex::new(pattern, false).unwrap(),
+            notification_sender: Box::new({
+                let notifications = notifications.clone();
+                move | notification | notifications.lock().unwrap().push(notification)
+            }),
+            active_search: Arc::new(AtomicU64::new(1)),
+            batch: Vec::new(),
+            last_flush: Instant::now(),
+            file_count: 0,
+            match_count,
+            truncated: false,
+        };
+        search.search_file(&path, name.to_string());
+        search.flush();
+        let _ = fs::remove_file(&path);
+        let mut matches = Vec::new();
+        for notification in notifications.lock().unwrap().drain(..) {
+            if let FileNotification::SearchResults {results, ..} = notification {
+                matches.extend(results.into_iter().flat_map( | result | result.matches));
+            }
+        }
+        (matches, search.truncated)
+    }
+    
+    #[test]
+    fn crlf_lines() {
+        let (matches, _) = search("crlf", b"let a = 1;\r\nlet b = 2;\r\n", "2;$", 0);
+        assert_eq!(matches.len(), 1);
+        assert_eq!((matches[0].line, matches[0].start, matches[0].end), (1, 8, 10));
+        assert_eq!(matches[0].text, "let b = 2;");
+    }
+    
+    #[test]
+    fn offsets_after_invalid_utf8() {
+        let (matches, _) = search("invalid", b"a\xff\xfeb needle", "needle", 0);
+        assert_eq!((matches[0].start, matches[0].end), (5, 11));
+        assert_eq!(original_offset(b"a\xffb", 1), 1);
+        assert_eq!(original_offset(b"a\xffb", 4), 2);
+        assert_eq!(original_offset(b"a\xffb", 5), 3);
+    }
+    
+    #[test]
+    fn stops_at_the_match_cap() {
+        let (matches, truncated) = search("cap", b"x\nx\nx\n", "x", MAX_SEARCH_MATCHES - 2);
+        assert_eq!(matches.iter().map( | found | found.line).collect::<Vec<_>>(), vec![0, 1]);
+        assert!(truncated);
+    }
+}
@@ -0,0 +1,329 @@
+use {
+    crate::file_server::is_hidden_entry,
+    std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+        sync::{Arc, atomic::{AtomicBool, Ordering}},
+        thread,
+        time::{Duration, SystemTime},
+    },
+};
+
+// How long the watcher waits for more changes before it reports the ones it has.
+const BATCH_DELAY: Duration = Duration::from_millis(50);
+// How often the tree is walked when there is no way to be told about changes.
+const POLL_DELAY: Duration = Duration::from_secs(1);
+
+/// A change in the tree, with paths relative to its root.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum WatchEvent {
+    Changed(String),
+    Created(String),
+    Removed(String),
+    Renamed {from: String, to: String},
+    // Changes were missed, everything has to be looked at again.
+    Rescan,
+}
+
+/// Watches every file and directory in a tree, except the ones the file tree hides. Uses inotify
+/// on Linux, and walks the tree every second elsewhere. Stops when dropped.
+#[derive(Debug)]
+pub struct FileWatcher {
+    stop: Arc<AtomicBool>,
+}
+
+impl FileWatcher {
+    /// Starts watching the tree at `root_path`. `on_events` is called on the watcher's thread
+    /// with the changes it found, a batch at a time.
+    pub fn start(root_path: PathBuf, on_events: impl FnMut(Vec<WatchEvent>) + Send + 'static) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        thread::spawn({
+            let stop = stop.clone();
+            move || {
+                #[cfg(target_os = "linux")]
+                let on_events = match inotify::watch(&root_path, &stop, on_events) {
+                    Ok(()) => return,
+                    Err(on_events) => on_events
+                };
+                poll(&root_path, &stop, on_events);
+            }
+        });
+        Self {stop}
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+fn relative_path(root_path: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root_path).ok()?;
+    let parts: Option<Vec<&str >> = relative.components().map( | component | component.as_os_str().to_str()).collect();
+    Some(parts?.join("/"))
+}
+
+// The modification time and length of every file, and the directories, in the tree.
+fn scan(root_path: &Path, path: &Path, entries: &mut HashMap<String, Option<(SystemTime, u64) >>) {
+    let Ok(dir_entries) = fs::read_dir(path) else {return};
+    for entry in dir_entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {continue};
+        let entry_path = entry.path();
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {continue};
+        if is_hidden_entry(name, metadata.is_dir()) {
+            continue
+        }
+        let Some(relative) = relative_path(root_path, &entry_path) else {continue};
+        if metadata.is_dir() {
+            entries.insert(relative, None);
+            scan(root_path, &entry_path, entries);
+        }
+        else if metadata.is_file() {
+            entries.insert(relative, Some((metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), metadata.len())));
+        }
+    }
+}
+
+fn poll(root_path: &Path, stop: &AtomicBool, mut on_events: impl FnMut(Vec<WatchEvent>)) {
+    let mut entries = HashMap::new();
+    scan(root_path, root_path, &mut entries);
+    while !stop.load(Ordering::SeqCst) {
+        thread::sleep(POLL_DELAY);
+        let mut new_entries = HashMap::new();
+        scan(root_path, root_path, &mut new_entries);
+        let mut events = Vec::new();
+        for (path, entry) in &new_entries {
+            match entries.get(path) {
+                None => events.push(WatchEvent::Created(path.clone())),
+                Some(old_entry) if old_entry != entry => events.push(WatchEvent::Changed(path.clone())),
+                _ => ()
+            }
+        }
+        for path in entries.keys() {
+            if !new_entries.contains_key(path) {
+                events.push(WatchEvent::Removed(path.clone()));
+            }
+        }
+        entries = new_entries;
+        if !events.is_empty() {
+            on_events(events);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use {
+        super::{WatchEvent, BATCH_DELAY},
+        crate::file_server::is_hidden_entry,
+        std::{
+            collections::HashMap,
+            ffi::{c_char, c_int, c_void, CString},
+            fs,
+            os::unix::ffi::OsStrExt,
+            path::Path,
+            sync::atomic::{AtomicBool, Ordering},
+            thread,
+        },
+    };
+
+    const IN_CLOSE_WRITE: u32 = 0x8;
+    const IN_MOVED_FROM: u32 = 0x40;
+    const IN_MOVED_TO: u32 = 0x80;
+    const IN_CREATE: u32 = 0x100;
+    const IN_DELETE: u32 = 0x200;
+    const IN_Q_OVERFLOW: u32 = 0x4000;
+    const IN_IGNORED: u32 = 0x8000;
+    const IN_ISDIR: u32 = 0x4000_0000;
+    const IN_NONBLOCK: c_int = 0o4000;
+    const IN_CLOEXEC: c_int = 0o2000000;
+    const WATCH_MASK: u32 = IN_CLOSE_WRITE | IN_MOVED_FROM | IN_MOVED_TO | IN_CREATE | IN_DELETE;
+
+    extern "C" {
+        fn inotify_init1(flags: c_int) -> c_int;
+        fn inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int;
+        fn read(fd: c_int, buf: *mut c_void, count: usize) -> isize;
+        fn close(fd: c_int) -> c_int;
+    }
+
+    struct Watches {
+        fd: c_int,
+        // The directory each watch is on, relative to the root.
+        dirs: HashMap<c_int, String>,
+    }
+
+    impl Watches {
+        // Watches a directory and every directory in it.
+        fn add(&mut self, root_path: &Path, dir: &str) {
+            let path = root_path.join(dir);
+            let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {return};
+            let wd = unsafe {inotify_add_watch(self.fd, c_path.as_ptr(), WATCH_MASK)};
+            if wd < 0 {
+                return
+            }
+            self.dirs.insert(wd, dir.to_string());
+            let Ok(entries) = fs::read_dir(&path) else {return};
+            for entry in entries.flatten() {
+                let (Ok(file_type), Some(name)) = (entry.file_type(), entry.file_name().to_str().map( | name | name.to_string())) else {continue};
+                if file_type.is_dir() && !is_hidden_entry(&name, true) {
+                    self.add(root_path, &join(dir, &name));
+                }
+            }
+        }
+
+        // Moves the watches on a directory that was renamed, and the ones in it.
+        fn rename(&mut self, from: &str, to: &str) {
+            for dir in self.dirs.values_mut() {
+                if dir == from {
+                    *dir = to.to_string();
+                }
+                else if let Some(rest) = dir.strip_prefix(&format!("{}/", from)) {
+                    *dir = join(to, rest);
+                }
+            }
+        }
+    }
+
+    impl Drop for Watches {
+        fn drop(&mut self) {
+            unsafe {close(self.fd)};
+        }
+    }
+
+    fn join(dir: &str, name: &str) -> String {
+        if dir.is_empty() {name.to_string()} else {format!("{}/{}", dir, name)}
+    }
+
+    // Watches the tree until stopped. Gives `on_events` back if inotify can't be used.
+    pub fn watch<F: FnMut(Vec<WatchEvent>)>(root_path: &Path, stop: &AtomicBool, mut on_events: F) -> Result<(), F> {
+        let fd = unsafe {inotify_init1(IN_NONBLOCK | IN_CLOEXEC)};
+        if fd < 0 {
+            return Err(on_events)
+        }
+        let mut watches = Watches {fd, dirs: HashMap::new()};
+        watches.add(root_path, "");
+        let mut buffer = vec![0u8; 64 * 1024];
+        while !stop.load(Ordering::SeqCst) {
+            thread::sleep(BATCH_DELAY);
+            let mut events = Vec::new();
+            // A move out of one place followed by a move into another is a rename, they share
+            // a cookie.
+            let mut moved_from: HashMap<u32, (String, bool)> = HashMap::new();
+            loop {
+                let len = unsafe {read(fd, buffer.as_mut_ptr() as *mut c_void, buffer.len())};
+                if len <= 0 {
+                    break
+                }
+                let mut offset = 0;
+                while offset + 16 <= len as usize {
+                    let field = | index: usize | {
+                        let start = offset + index * 4;
+                        u32::from_ne_bytes(buffer[start..start + 4].try_into().unwrap())
+                    };
+                    let (wd, mask, cookie, name_len) = (field(0) as c_int, field(1), field(2), field(3) as usize);
+                    let name_bytes = &buffer[offset + 16..offset + 16 + name_len];
+                    let name = String::from_utf8_lossy(name_bytes).trim_end_matches('\0').to_string();
+                    offset += 16 + name_len;
+                    if mask & IN_Q_OVERFLOW != 0 {
+                        events.push(WatchEvent::Rescan);
+                        continue
+                    }
+                    if mask & IN_IGNORED != 0 {
+                        watches.dirs.remove(&wd);
+                        continue
+                    }
+                    let is_dir = mask & IN_ISDIR != 0;
+                    let Some(dir) = watches.dirs.get(&wd) else {continue};
+                    if name.is_empty() || is_hidden_entry(&name, is_dir) {
+                        continue
+                    }
+                    let path = join(dir, &name);
+                    if mask & IN_CLOSE_WRITE != 0 {
+                        events.push(WatchEvent::Changed(path));
+                    }
+                    else if mask & IN_CREATE != 0 {
+                        if is_dir {
+                            watches.add(root_path, &path);
+                        }
+                        events.push(WatchEvent::Created(path));
+                    }
+                    else if mask & IN_DELETE != 0 {
+                        events.push(WatchEvent::Removed(path));
+                    }
+                    else if mask & IN_MOVED_FROM != 0 {
+                        moved_from.insert(cookie, (path, is_dir));
+                    }
+                    else if mask & IN_MOVED_TO != 0 {
+                        match moved_from.remove(&cookie) {
+                            Some((from, _)) => {
+                                if is_dir {
+                                    watches.rename(&from, &path);
+                                }
+                                events.push(WatchEvent::Renamed {from, to: path});
+                            }
+                            None => {
+                                if is_dir {
+                                    watches.add(root_path, &path);
+                                }
+                                events.push(WatchEvent::Created(path));
+                            }
+                        }
+                    }
+                }
+            }
+            // Whatever moved out without moving back in left the tree.
+            for (path, is_dir) in moved_from.into_values() {
+                if is_dir {
+                    watches.dirs.retain( | _, dir | *dir != path && !dir.starts_with(&format!("{}/", path)));
+                }
+                events.push(WatchEvent::Removed(path));
+            }
+            if !events.is_empty() {
+                on_events(events);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::sync::{Arc, Mutex},
+    };
+
+    #[test]
+    fn reports_changes_in_the_tree() {
+        let root_path = std::env::temp_dir().join(format!("file_watcher_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root_path);
+        fs::create_dir_all(root_path.join("src")).unwrap();
+        fs::write(root_path.join("src/lib.rs"), "").unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let watcher = FileWatcher::start(root_path.clone(), {
+            let events = events.clone();
+            move | new_events | events.lock().unwrap().extend(new_events)
+        });
+        // give the watcher time to look at the tree before it changes
+        thread::sleep(Duration::from_millis(200));
+        fs::write(root_path.join("src/lib.rs"), "mod app;").unwrap();
+        fs::write(root_path.join("src/app.rs"), "").unwrap();
+        let mut found = false;
+        for _ in 0..50 {
+            thread::sleep(Duration::from_millis(100));
+            let events = events.lock().unwrap();
+            let changed = events.iter().any( | event | matches!(event, WatchEvent::Changed(path) if path == "src/lib.rs"));
+            let created = events.iter().any( | event | matches!(event, WatchEvent::Created(path) if path == "src/app.rs"));
+            if changed && created {
+                found = true;
+                break
+            }
+        }
+        drop(watcher);
+        let _ = fs::remove_dir_all(&root_path);
+        assert!(found, "{:?}", events.lock().unwrap());
+    }
+}
@@ -0,0 +1,205 @@
+// A matcher for .gitignore files. Each directory can add the rules of its own .gitignore, which
+// apply to the paths below it. Later rules override earlier ones, so a rule in a subdirectory
+// can re-include a path that a parent excluded. Paths are relative to the root and use '/'.
+#[derive(Clone, Debug, Default)]
+pub struct Gitignore {
+    rules: Vec<Rule>,
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+    // the directory of the .gitignore this rule came from, empty for the root
+    base: String,
+    pattern: Vec<char>,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl Gitignore {
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.rules.truncate(len);
+    }
+
+    // adds the rules of the .gitignore in directory `base`
+    pub fn add_rules(&mut self, base: &str, contents: &str) {
+        for line in contents.lines() {
+            let mut line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let negated = line.starts_with('!');
+            if negated {
+                line = &line[1..];
+            }
+            let dir_only = line.ends_with('/');
+            if dir_only {
+                line = &line[..line.len() - 1];
+            }
+            // a slash anywhere but at the end anchors the pattern to the base directory
+            let anchored = line.contains('/');
+            let line = line.strip_prefix('/').unwrap_or(line);
+            if line.is_empty() {
+                continue;
+            }
+            self.rules.push(Rule {
+                base: base.to_string(),
+                pattern: line.chars().collect(),
+                negated,
+                dir_only,
+                anchored,
+            });
+        }
+    }
+
+    // returns true if the path is ignored. The parents of the path are expected not to be
+    // ignored, directory walkers skip ignored directories as a whole.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir || ignored != rule.negated {
+                continue;
+            }
+            let relative = if rule.base.is_empty() {
+                path
+            }
+            else if let Some(relative) = path.strip_prefix(&rule.base).and_then( | path | path.strip_prefix('/')) {
+                relative
+            }
+            else {
+                continue
+            };
+            let subject = if rule.anchored {
+                relative
+            }
+            else {
+                relative.rsplit('/').next().unwrap()
+            };
+            let subject: Vec<char> = subject.chars().collect();
+            if glob_match(&rule.pattern, &subject) {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            if pattern.get(2) == Some(&'/') {
+                // `**/` matches zero or more directories
+                let rest = &pattern[3..];
+                if glob_match(rest, text) {
+                    return true;
+                }
+                text.iter().enumerate().any( | (index, char) | *char == '/' && glob_match(rest, &text[index + 1..]))
+            }
+            else {
+                // `**` matches everything, including slashes
+                let rest = &pattern[2..];
+                (0..=text.len()).any( | index | glob_match(rest, &text[index..]))
+            }
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for index in 0..=text.len() {
+                if glob_match(rest, &text[index..]) {
+                    return true;
+                }
+                if text.get(index) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => !text.is_empty() && text[0] != '/' && glob_match(&pattern[1..], &text[1..]),
+        Some('[') => {
+            let Some(&char) = text.first() else {return false};
+            match match_class(&pattern[1..], char) {
+                Some((true, len)) => glob_match(&pattern[1 + len..], &text[1..]),
+                Some((false, _)) => false,
+                // an unterminated class matches a literal '['
+                None => char == '[' && glob_match(&pattern[1..], &text[1..]),
+            }
+        }
+        Some('\\') if pattern.len() > 1 => {
+            !text.is_empty() && text[0] == pattern[1] && glob_match(&pattern[2..], &text[1..])
+        }
+        Some(&char) => !text.is_empty() && text[0] == char && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+// matches a character against the class that starts after a '['. Returns whether it matched and
+// the length of the class including the closing ']'
+fn match_class(pattern: &[char], char: char) -> Option<(bool, usize)> {
+    let mut index = 0;
+    let negated = matches!(pattern.first(), Some('!') | Some('^'));
+    if negated {
+        index += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    while index < pattern.len() {
+        let start = pattern[index];
+        if start == ']' && !first {
+            return Some((matched != negated, index + 1));
+        }
+        first = false;
+        if index + 2 < pattern.len() && pattern[index + 1] == '-' && pattern[index + 2] != ']' {
+            matched |= start <= char && char <= pattern[index + 2];
+            index += 3;
+        }
+        else {
+            matched |= start == char;
+            index += 1;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        let mut gitignore = Gitignore::default();
+        gitignore.add_rules("", "# comment\ntarget/\n*.log\n!keep.log\n/root_only.txt\ndocs/**/*.tmp\nfile[0-9].rs\n");
+        assert!(gitignore.is_ignored("target", true));
+        assert!(gitignore.is_ignored("a/b/target", true));
+        assert!(!gitignore.is_ignored("target", false));
+        assert!(gitignore.is_ignored("a/debug.log", false));
+        assert!(!gitignore.is_ignored("a/keep.log", false));
+        assert!(gitignore.is_ignored("root_only.txt", false));
+        assert!(!gitignore.is_ignored("a/root_only.txt", false));
+        assert!(gitignore.is_ignored("docs/x.tmp", false));
+        assert!(gitignore.is_ignored("docs/a/b/x.tmp", false));
+        assert!(!gitignore.is_ignored("src/x.tmp", false));
+        assert!(gitignore.is_ignored("file1.rs", false));
+        assert!(!gitignore.is_ignored("filex.rs", false));
+    }
+
+    #[test]
+    fn nested() {
+        let mut gitignore = Gitignore::default();
+        gitignore.add_rules("", "*.txt\n");
+        let len = gitignore.len();
+        gitignore.add_rules("sub", "!notes.txt\n/build\n");
+        assert!(gitignore.is_ignored("sub/other.txt", false));
+        assert!(!gitignore.is_ignored("sub/notes.txt", false));
+        assert!(gitignore.is_ignored("sub/build", true));
+        assert!(!gitignore.is_ignored("build", true));
+        gitignore.truncate(len);
+        assert!(gitignore.is_ignored("sub/notes.txt", false));
+    }
+}
@@ -2,9 +2,16 @@
 #[cfg(not(target_arch = "wasm32"))]
 pub mod file_server;
 #[cfg(not(target_arch = "wasm32"))]
+pub mod gitignore;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod collab_session;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod file_watcher;
+#[cfg(not(target_arch = "wasm32"))]
 pub use file_server::*;
 
 pub use makepad_micro_serde;
 pub use makepad_live_id;
 pub use makepad_file_protocol;
 pub use makepad_file_protocol::*;
+pub use makepad_regex;
@@ -1,7 +1,7 @@
 use crate::{
     makepad_code_editor::code_editor::*,
     makepad_code_editor::selection::Affinity,
-    makepad_code_editor::session::SelectionMode,
+    makepad_code_editor::session::{SelectionMode, CodeSession},
     makepad_code_editor::history::NewGroup,
     makepad_widgets::*,
     makepad_micro_serde::*,
@@ -10,10 +10,18 @@ use crate::{
     file_system::file_system::*,
     studio_editor::*,
     run_view::*,
-    makepad_platform::studio::{JumpToFile,EditFile, PatchFile},
+    makepad_platform::studio::{JumpToFile,EditFile, PatchFile, MoveFileRange},
     log_list::*,
+    search::{
+        search_manager::SearchManager,
+        search_results::*,
+    },
+    makepad_file_protocol::FileSearchQuery,
     makepad_code_editor::text::{Position},
+    makepad_live_compiler::{format_live_design, lint_live_design, LiveFormatOptions, LiveFileId},
     ai_chat::ai_chat_manager::AiChatManager,
+    lsp::lsp_manager::{LspManager, LspAction},
+    git::git_manager::{GitManager, GitAction},
     build_manager::{
         build_protocol::BuildProcess,
         build_manager::{
@@ -25,6 +33,7 @@ use crate::{
 use std::fs::File;
 use std::io::Write;
 use std::env;
+use std::path::{Component, Path, PathBuf};
 live_design!{
     import crate::app_ui::*;
 
@@ -44,7 +53,10 @@ impl LiveRegister for App{
         crate::makepad_widgets::live_design(cx);
         crate::makepad_code_editor::live_design(cx);
         crate::run_list::live_design(cx);
+        crate::test_list::live_design(cx);
         crate::log_list::live_design(cx);
+        crate::search::search_results::live_design(cx);
+        crate::git::git_view::live_design(cx);
         crate::profiler::live_design(cx);
         crate::run_view::live_design(cx);
         crate::studio_editor::live_design(cx);
@@ -74,6 +86,27 @@ impl App {
         }
     }
     
+    pub fn open_search(&mut self, cx: &mut Cx){
+        let dock = self.ui.dock(id!(dock));
+        dock.select_tab(cx, live_id!(search));
+        self.ui.text_input(id!(search_input)).set_key_focus(cx);
+    }
+    
+    fn search_query(&self, cx: &Cx)->FileSearchQuery{
+        FileSearchQuery{
+            text: self.ui.text_input(id!(search_input)).text(),
+            case_sensitive: self.ui.check_box(id!(search_case_sensitive)).selected(cx),
+            whole_word: self.ui.check_box(id!(search_whole_word)).selected(cx),
+            regex: self.ui.check_box(id!(search_regex)).selected(cx),
+        }
+    }
+    
+    fn redraw_search(&mut self, cx: &mut Cx){
+        let status = self.data.search_manager.status_text();
+        self.ui.label(id!(search_status)).set_text_and_redraw(cx, &status);
+        self.ui.search_results(id!(search_results)).redraw(cx);
+    }
+    
     pub fn load_state(&mut self, cx:&mut Cx, slot:usize){
         
         if let Ok(contents) = std::fs::read_to_string(format!("makepad_state{}.ron", slot)) {
@@ -140,6 +173,9 @@ pub struct AppData{
     pub build_manager: BuildManager,
     pub file_system: FileSystem,
     pub ai_chat_manager: AiChatManager,
+    pub search_manager: SearchManager,
+    pub lsp_manager: LspManager,
+    pub git_manager: GitManager,
 }
 
 // all global app commands coming in from keybindings, and UI components
@@ -149,10 +185,12 @@ pub enum AppAction{
     JumpTo(JumpToFile),
     RedrawLog,
     RedrawProfiler,
+    RedrawTests,
     RedrawFile(LiveId),
     FocusDesign(LiveId),
     EditFile(EditFile),
     PatchFile(PatchFile),
+    MoveFileRange(MoveFileRange),
     StartRecompile,
     ReloadFileTree,
     RecompileStarted,
@@ -163,6 +201,11 @@ pub enum AppAction{
     RedrawAiChat{chat_id:LiveId},
     RunAiChat{chat_id:LiveId, history_slot:usize, item_id:usize},
     DestroyRunViews{run_view_id:LiveId},
+    // shows how an open file differs from what changed on disk under it
+    ShowDiskDiff(LiveId),
+    // runs the live_design formatter or linter over an open file
+    FormatLiveDesign(LiveId),
+    LintLiveDesign(LiveId),
     None
 }
 
@@ -179,6 +222,8 @@ impl MatchEvent for App{
                 
         self.data.file_system.init(cx, &root_path);
         self.data.build_manager.init(cx, &root_path);
+        self.data.lsp_manager.init(&root_path);
+        self.data.git_manager.init(&root_path);
         
                 
         //self.data.build_manager.discover_external_ip(cx);
@@ -281,6 +326,48 @@ impl MatchEvent for App{
                     }
                 }
             }
+            AppAction::MoveFileRange(mf)=>{
+                let start = Position{line_index: mf.line_start as usize, byte_index:mf.column_start as usize};
+                let end = Position{line_index: mf.line_end as usize, byte_index:mf.column_end as usize};
+                let to = Position{line_index: mf.to_line as usize, byte_index:mf.to_column as usize};
+                // a range can't be moved into itself
+                let inside = to > start && to < end;
+                if let Some(file_id) = self.data.file_system.path_to_file_node_id(&mf.file_name).filter(|_| !inside) {
+                    if let Some(tab_id) = self.data.file_system.file_node_id_to_tab_id(file_id){
+                        dock.select_tab(cx, tab_id);
+                        if let Some(mut editor) = dock.item(tab_id).studio_code_editor(id!(editor)).borrow_mut() {
+                            if let Some(EditSession::Code(session)) = self.data.file_system.get_session_mut(tab_id) {
+                                let mut text = session.document().as_text().slice(start, end - start);
+                                if let Some(rename) = &mf.rename{
+                                    text = rename_first_identifier(&text.to_string(), rename).into();
+                                }
+                                // one undo step for the whole move
+                                let group = LiveId::unique().0;
+                                let replace = |session:&mut CodeSession, from, to, text|{
+                                    session.set_selection(from, Affinity::After, SelectionMode::Simple, NewGroup::Yes);
+                                    session.move_to(to, Affinity::Before, NewGroup::Yes);
+                                    session.paste_grouped(text, group);
+                                };
+                                // edit the later position first, so the earlier one stays where it is
+                                if mf.copy{
+                                    replace(session, to, to, text);
+                                }
+                                else if to >= end{
+                                    replace(session, to, to, text);
+                                    replace(session, start, end, "".into());
+                                }
+                                else{
+                                    replace(session, start, end, "".into());
+                                    replace(session, to, to, text);
+                                }
+                            }
+                            self.data.file_system.handle_sessions();
+                            editor.redraw(cx);
+                            self.data.file_system.request_save_file_for_file_node_id(file_id, false)
+                        }
+                    }
+                }
+            }
             AppAction::RedrawFile(file_id)=>{
                 self.data.file_system.redraw_view_by_file_id(cx, file_id, &dock);
             }
@@ -296,6 +383,11 @@ impl MatchEvent for App{
             AppAction::RedrawProfiler=>{
                 profiler.redraw(cx);
             }
+            AppAction::RedrawTests=>{
+                self.ui.widget(id!(test_list)).redraw(cx);
+                self.data.build_manager.test_runner.update_all_markers(&self.data.file_system);
+                self.data.file_system.redraw_all_views(cx, &dock);
+            }
             AppAction::RedrawLog=>{
                 log_list.redraw(cx);
             }
@@ -347,6 +439,64 @@ impl MatchEvent for App{
             AppAction::RunAiChat{chat_id, history_slot, item_id}=>{
                 self.data.ai_chat_manager.run_ai_chat(cx, chat_id, history_slot, item_id, &mut self.data.file_system);
             }
+            AppAction::ShowDiskDiff(file_id)=>{
+                if let (Some(path), Some(disk_text)) = (self.data.file_system.file_node_id_to_path(file_id), self.data.file_system.conflicts.get(&file_id)){
+                    self.data.git_manager.show_disk_diff(&self.data.file_system, path, disk_text);
+                    dock.select_tab(cx, live_id!(git));
+                    self.ui.widget(id!(git_view)).redraw(cx);
+                }
+            }
+            AppAction::FormatLiveDesign(file_id)=>{
+                if let Some(tab_id) = self.data.file_system.file_node_id_to_tab_id(file_id){
+                    if let Some(mut editor) = dock.item(tab_id).studio_code_editor(id!(editor)).borrow_mut() {
+                        let mut changed = false;
+                        if let Some(EditSession::Code(session)) = self.data.file_system.get_session_mut(tab_id) {
+                            let text = session.document().as_text().to_string();
+                            match format_live_design(&text, &LiveFormatOptions::default()){
+                                Ok(formatted) if formatted != text=>{
+                                    let end = Position::zero() + session.document().as_text().length();
+                                    session.set_selection(Position::zero(), Affinity::After, SelectionMode::Simple, NewGroup::Yes);
+                                    session.move_to(end, Affinity::Before, NewGroup::Yes);
+                                    session.paste(formatted.into());
+                                    changed = true;
+                                }
+                                Ok(_)=>(),
+                                Err(err)=>log!("Cannot format live_design: {}", err.message)
+                            }
+                        }
+                        if changed{
+                            self.data.file_system.handle_sessions();
+                            editor.redraw(cx);
+                            self.data.file_system.request_save_file_for_file_node_id(file_id, false)
+                        }
+                    }
+                }
+            }
+            AppAction::LintLiveDesign(file_id)=>{
+                let path = self.data.file_system.file_node_id_to_path(file_id).map(|p| p.to_string());
+                let tab_id = self.data.file_system.file_node_id_to_tab_id(file_id);
+                let text = tab_id.and_then(|tab_id| match self.data.file_system.get_session_mut(tab_id){
+                    Some(EditSession::Code(session))=>Some(session.document().as_text().to_string()),
+                    _=>None
+                });
+                if let (Some(path), Some(text)) = (path, text){
+                    match lint_live_design(&text, LiveFileId::new(0)){
+                        Ok(mut lints)=>{
+                            // unknown properties need the type infos, we only have those for our own files.
+                            // file!() names are relative to where studio was built from, our paths to the root
+                            let registry = cx.live_registry.borrow();
+                            let full_path = normalize_path(&self.data.build_manager.root_path().join(&path));
+                            let cwd = env::current_dir().unwrap_or_default();
+                            if let Some(file_id) = registry.file_ids().iter().find(|(name, _)| normalize_path(&cwd.join(name)) == full_path).map(|(_, id)| *id){
+                                lints.extend(registry.lint_unknown_properties(file_id));
+                            }
+                            drop(registry);
+                            self.data.build_manager.add_live_design_lints(cx, &mut self.data.file_system, &path, lints);
+                        }
+                        Err(err)=>log!("Cannot lint live_design: {}", err.message)
+                    }
+                }
+            }
             AppAction::DestroyRunViews{run_view_id} => {
                 dock.close_tab(cx, run_view_id);
                 dock.close_tab(cx, run_view_id.add(1));
@@ -427,8 +577,14 @@ impl MatchEvent for App{
                 file_tree.redraw(cx);
                 self.load_state(cx, 0);
                 self.data.ai_chat_manager.init(&mut self.data.file_system);
+                self.data.git_manager.refresh_status();
                 //self.open_code_file_by_path(cx, "examples/slides/src/app.rs");
             }
+            FileSystemAction::TreeChanged => {
+                file_tree.redraw(cx);
+                self.data.file_system.ensure_unique_tab_names(cx, &dock);
+                self.data.git_manager.schedule_status_refresh(cx);
+            }
             FileSystemAction::RecompileNeeded => {
                 self.data.build_manager.start_recompile_timer(cx);
             }
@@ -438,18 +594,98 @@ impl MatchEvent for App{
                 log_list.redraw(cx);
             }
             FileSystemAction::FileChangedOnDisk(_res)=>{
-                
+                self.data.git_manager.schedule_status_refresh(cx);
+            }
+            FileSystemAction::SearchResults{id, results}=>{
+                self.data.search_manager.handle_results(id, results);
+                self.redraw_search(cx);
+            }
+            FileSystemAction::SearchDone{id, file_count, match_count, truncated}=>{
+                self.data.search_manager.handle_done(id, file_count, match_count, truncated);
+                self.redraw_search(cx);
+            }
+            FileSystemAction::SearchFailed(message)=>{
+                self.data.search_manager.handle_failed(message);
+                self.redraw_search(cx);
+            }
+            FileSystemAction::DocumentOpened(file_id)=>{
+                self.data.lsp_manager.did_open(&self.data.file_system, file_id);
+                self.data.git_manager.did_open(&self.data.file_system, file_id);
+                self.data.build_manager.test_runner.update_markers(&self.data.file_system, file_id);
+            }
+            FileSystemAction::DocumentChanged(file_id)=>{
+                self.data.lsp_manager.did_change(&self.data.file_system, file_id);
+                self.data.git_manager.did_change(cx, &mut self.data.file_system, file_id);
+                self.data.build_manager.test_runner.update_markers(&self.data.file_system, file_id);
             }
             FileSystemAction::None=>()
         }
         
+        match action.cast(){
+            LspAction::Symbols{path, matches}=>{
+                self.data.search_manager.show_symbols(path, matches);
+                self.ui.search_results(id!(search_results)).reset_scroll(cx);
+                dock.select_tab(cx, live_id!(search));
+                self.redraw_search(cx);
+            }
+            LspAction::Completions{file_id, items}=>{
+                if let Some(tab_id) = self.data.file_system.file_node_id_to_tab_id(file_id){
+                    if let Some(mut editor) = dock.item(tab_id).studio_code_editor(id!(editor)).borrow_mut() {
+                        if let Some(EditSession::Code(session)) = self.data.file_system.get_session_mut(tab_id) {
+                            editor.editor.add_completions(cx, session, items);
+                        }
+                    }
+                }
+            }
+            LspAction::None=>()
+        }
+        
+        match action.cast(){
+            GitAction::Changed=>{
+                file_tree.redraw(cx);
+                self.ui.widget(id!(git_view)).redraw(cx);
+            }
+            GitAction::None=>()
+        }
+        
         if let Some(action) = action.as_widget_action(){
             match action.cast(){
                 CodeEditorAction::TextDidChange => {
                     // lets write the file
-                    self.data.file_system.request_save_file_for_tab_id(action.path.from_end(1), false)
+                    let tab_id = action.path.from_end(1);
+                    self.data.file_system.request_save_file_for_tab_id(tab_id, false);
+                    if let Some(file_id) = self.data.file_system.tab_id_to_file_node_id.get(&tab_id).cloned(){
+                        self.data.lsp_manager.did_change(&self.data.file_system, file_id);
+                        self.data.git_manager.did_change(cx, &mut self.data.file_system, file_id);
+                        self.data.build_manager.test_runner.update_markers(&self.data.file_system, file_id);
+                    }
+                }
+                CodeEditorAction::GoToDefinition(pos)=>{
+                    if let Some(file_id) = self.data.file_system.tab_id_to_file_node_id.get(&action.path.from_end(1)).cloned(){
+                        self.data.lsp_manager.go_to_definition(&self.data.file_system, file_id, pos);
+                    }
+                }
+                CodeEditorAction::Hover(pos)=>{
+                    if let Some(file_id) = self.data.file_system.tab_id_to_file_node_id.get(&action.path.from_end(1)).cloned(){
+                        self.data.lsp_manager.hover(&self.data.file_system, file_id, pos);
+                    }
+                }
+                CodeEditorAction::Complete(pos)=>{
+                    if let Some(file_id) = self.data.file_system.tab_id_to_file_node_id.get(&action.path.from_end(1)).cloned(){
+                        self.data.lsp_manager.completion(&self.data.file_system, file_id, pos);
+                    }
+                }
+                CodeEditorAction::DocumentSymbols=>{
+                    if let Some(file_id) = self.data.file_system.tab_id_to_file_node_id.get(&action.path.from_end(1)).cloned(){
+                        self.data.lsp_manager.document_symbols(&self.data.file_system, file_id);
+                    }
                 }
-                CodeEditorAction::None=>{}
+                CodeEditorAction::CursorLineChanged(line)=>{
+                    if let Some(file_id) = self.data.file_system.tab_id_to_file_node_id.get(&action.path.from_end(1)).cloned(){
+                        self.data.git_manager.show_blame(cx, &mut self.data.file_system, file_id, line);
+                    }
+                }
+                CodeEditorAction::Find | CodeEditorAction::Replace | CodeEditorAction::None=>{}
             }
             
             match action.cast(){
@@ -459,7 +695,13 @@ impl MatchEvent for App{
                         log_list.redraw(cx);
                         run_list.redraw(cx);
                     }
+                    let file_id = self.data.file_system.tab_id_to_file_node_id.get(&tab_id).cloned();
                     self.data.file_system.remove_tab(tab_id);
+                    if let Some(file_id) = file_id{
+                        if self.data.file_system.file_node_id_to_tab_id(file_id).is_none(){
+                            self.data.lsp_manager.did_close(&self.data.file_system, file_id);
+                        }
+                    }
                     self.data.file_system.ensure_unique_tab_names(cx, &dock);
                 }
                 DockAction::ShouldTabStartDrag(tab_id)=>{
@@ -524,6 +766,9 @@ impl MatchEvent for App{
             else if let KeyCode::KeyR = key_code{
                 cx.action(AppAction::ReloadFileTree);
             }
+            else if event.modifiers.shift && (*key_code == KeyCode::KeyF || *key_code == KeyCode::KeyH){
+                self.open_search(cx);
+            }
         }
     }
     
@@ -541,6 +786,36 @@ impl MatchEvent for App{
             }); 
         }
         
+        let search_input = self.ui.text_input(id!(search_input));
+        if search_input.changed(actions).is_some() || search_input.returned(actions).is_some()
+            || [id!(search_case_sensitive), id!(search_whole_word), id!(search_regex)].iter()
+                .any(|id| self.ui.check_box(*id).changed(actions).is_some()){
+            let query = self.search_query(cx);
+            self.data.search_manager.search(&mut self.data.file_system, query);
+            self.ui.search_results(id!(search_results)).reset_scroll(cx);
+            self.redraw_search(cx);
+        }
+        let pause_animations = self.ui.check_box(id!(pause_animations));
+        let animation_time = self.ui.slider(id!(animation_time));
+        let paused = pause_animations.changed(actions);
+        if paused.is_some() || animation_time.slided(actions).is_some(){
+            let paused = paused.unwrap_or_else(|| pause_animations.selected(cx));
+            self.data.build_manager.set_animation_clock(paused, animation_time.value().unwrap_or(0.0));
+        }
+        if self.ui.button(id!(run_selected_test)).clicked(actions){
+            if let Some((process, name)) = self.data.build_manager.test_runner.selected.clone(){
+                self.data.build_manager.run_test(process, &name);
+                self.ui.widget(id!(test_list)).redraw(cx);
+            }
+        }
+        
+        let replace_input = self.ui.text_input(id!(replace_input));
+        if replace_input.returned(actions).is_some() || self.ui.button(id!(replace_all)).clicked(actions){
+            self.data.search_manager.replace_all(&mut self.data.file_system, &replace_input.text());
+            self.data.file_system.redraw_all_views(cx, &dock);
+            self.redraw_search(cx);
+        }
+        
         for (i,id) in [*id!(preset_1),*id!(preset_2),*id!(preset_3),*id!(preset_4)].iter().enumerate(){
             if let Some(km) = self.ui.button(id).pressed_modifiers(actions){
                 if km.control{
@@ -579,6 +854,7 @@ impl MatchEvent for App{
     
     fn handle_shutdown(&mut self, _cx:&mut Cx){
         self.data.build_manager.clear_active_builds();
+        self.data.lsp_manager.stop();
     }
 }
 
@@ -586,17 +862,47 @@ impl AppMain for App {
     
     fn handle_event(&mut self, cx: &mut Cx, event: &Event) {
         self.match_event(cx, event);
+        if let Event::MacosMenuCommand(command) = event{
+            if *command == live_id!(find_in_files) || *command == live_id!(replace_in_files){
+                self.open_search(cx);
+            }
+        }
         self.ui.handle_event(cx, event, &mut Scope::with_data(&mut self.data));
         
         self.data.file_system.handle_event(cx, event, &self.ui);
         self.data.build_manager.handle_event(cx, event, &mut self.data.file_system); 
         self.data.ai_chat_manager.handle_event(cx, event, &mut self.data.file_system);
+        self.data.lsp_manager.handle_event(cx, event, &mut self.data.file_system);
+        self.data.git_manager.handle_event(cx, event, &mut self.data.file_system);
         if self.ui.dock(id!(dock)).check_and_clear_need_save(){
             self.save_state(0);
         }
     }
 }
 
+fn normalize_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| {
+        let mut out = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => (),
+                Component::ParentDir => {out.pop();}
+                component => out.push(component)
+            }
+        }
+        out
+    })
+}
+
+// replaces the first identifier in text, the name a live_design instance starts with
+fn rename_first_identifier(text: &str, name: &str) -> String {
+    let Some(start) = text.find(|c: char| c.is_alphanumeric() || c == '_') else {
+        return text.to_string()
+    };
+    let end = text[start..].find(|c: char| !(c.is_alphanumeric() || c == '_')).map_or(text.len(), |end| start + end);
+    format!("{}{}{}", &text[..start], name, &text[end..])
+}
+
 // we should store probably also scroll position / which chat slot we're visiting
 use std::collections::HashMap;
 #[derive(SerRon, DeRon)]
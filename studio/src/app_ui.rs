@@ -10,7 +10,10 @@ live_design!{
     import makepad_studio::run_view::RunView;
     import makepad_studio::log_list::LogList;
     import makepad_studio::run_list::RunList;
+    import makepad_studio::test_list::TestList;
     import makepad_studio::profiler::Profiler;
+    import makepad_studio::search::search_results::SearchResults;
+    import makepad_studio::git::git_view::GitView;
 
     ICO_SEARCH = dep("crate://self/resources/icons/Icon_Search.svg")
 
@@ -146,10 +149,10 @@ live_design!{
             cut = Item {name: "Cut", enabled: false}
             copy = Item {name: "Copy", enabled: false}
             paste = Item {name: "Paste", enabled: false}
-            find = Item {name: "Find", enabled: false}
-            replace = Item {name: "Replace", enabled: false}
-            find_in_files = Item {name: "Find in Files", enabled: false}
-            replace_in_files = Item {name: "Replace in Files", enabled: false}
+            find = Item {name: "Find", key: KeyF}
+            replace = Item {name: "Replace", key: KeyH}
+            find_in_files = Item {name: "Find in Files", shift: true, key: KeyF}
+            replace_in_files = Item {name: "Replace in Files", shift: true, key: KeyH}
 
             selection = Sub {name: "Selection", items: [select_all]}
             select_all = Item {name: "Select All", enabled: false}
@@ -273,6 +276,17 @@ live_design!{
                         svg_file: dep("crate://self/resources/icons/icon_profiler.svg"),
                     }
                 }
+                TestsTab = <IconTab> {
+                    spacing: (THEME_SPACE_2)
+                    icon_walk: {
+                        width: 7.
+                        margin: { top: 5. }
+                    }
+                    draw_icon: {
+                        color: (STUDIO_PALETTE_6)
+                        svg_file: dep("crate://self/resources/icons/icon_run.svg"),
+                    }
+                }
                 SearchFirstTab = <IconTab> {
                     spacing: (THEME_SPACE_2)
                     icon_walk: {
@@ -284,6 +298,17 @@ live_design!{
                         svg_file: dep("crate://self/resources/icons/icon_search.svg"),
                     }
                 }
+                GitFirstTab = <IconTab> {
+                    spacing: (THEME_SPACE_2)
+                    icon_walk: {
+                        width: 10.5,
+                        margin: { top: 4. }
+                    }
+                    draw_icon: {
+                        color: (STUDIO_PALETTE_3)
+                        svg_file: dep("crate://self/resources/icons/icon_history_rew.svg"),
+                    }
+                }
             }
             root = Splitter {
                 axis: Horizontal,
@@ -326,7 +351,7 @@ live_design!{
             }*/
 
             file_tree_tabs = Tabs {
-                tabs: [file_tree_tab, run_list_tab, search],
+                tabs: [file_tree_tab, run_list_tab, search, git],
                 selected: 0
             }
 
@@ -346,7 +371,7 @@ live_design!{
             }
             
             log_tabs = Tabs {
-                tabs: [log_list_tab, profiler],
+                tabs: [log_list_tab, profiler, tests],
                 selected: 0
             }
 
@@ -372,6 +397,12 @@ live_design!{
                 kind: Search
             }
 
+            git = Tab {
+                name: "Git"
+                template: GitFirstTab,
+                kind: Git
+            }
+
             run_first = Tab {
                 name: ""
                 template: RunFirstTab,
@@ -418,6 +449,12 @@ live_design!{
                 kind: Profiler
             }
 
+            tests = Tab {
+                name: "Tests",
+                template: TestsTab,
+                kind: Tests
+            }
+
             CodeEditor = <View> {
                 flow: Down,
                 <DockToolbar> {
@@ -529,17 +566,17 @@ live_design!{
                 <RunList> {}
             }
             Search = <RectView> {
-            flow: Down,
+                flow: Down,
                 <DockToolbar> {
                     content = {
                         spacing: (THEME_SPACE_2)
                         align: { y: 0.5 }
-                        <TextInput> {
+                        search_input = <TextInput> {
                             width: Fill,
                             empty_message: "Search",
                         }
 
-                        <CheckBoxCustom> {
+                        search_case_sensitive = <CheckBoxCustom> {
                             padding: 0.
                             text: ""
                             draw_check: { check_type: None }
@@ -550,7 +587,7 @@ live_design!{
                                 svg_file: dep("crate://self/resources/icons/icon_search_case_sensitive.svg"),
                             }
                         }
-                        <CheckBoxCustom> {
+                        search_whole_word = <CheckBoxCustom> {
                             padding: 0.
                             text:""
                             draw_check: { check_type: None }
@@ -561,7 +598,7 @@ live_design!{
                                 svg_file: dep("crate://self/resources/icons/icon_search_full_word.svg"),
                             }
                         }
-                        <CheckBoxCustom> {
+                        search_regex = <CheckBoxCustom> {
                             padding: 0.
                             text:""
                             draw_check: { check_type: None }
@@ -574,11 +611,28 @@ live_design!{
                         }
                     }
                 }
-                <View> {
-                    flow: Down
-                    margin: <THEME_MSPACE_2> {}
-                    <P> { text: "this does not work yet." }
+                <DockToolbar> {
+                    content = {
+                        spacing: (THEME_SPACE_2)
+                        align: { y: 0.5 }
+                        replace_input = <TextInput> {
+                            width: Fill,
+                            empty_message: "Replace",
+                        }
+                        replace_all = <ButtonFlat> { width: Fit, text: "Replace All"}
+                    }
+                }
+                search_status = <P> {
+                    width: Fill,
+                    margin: <THEME_MSPACE_H_2> {}
+                    draw_text: { color: (THEME_COLOR_TEXT_META) }
+                    text: ""
                 }
+                search_results = <SearchResults> {}
+            }
+            Git = <RectView> {
+                flow: Down,
+                git_view = <GitView> {}
             }
             RunView = <RunView> {}
             StudioFileTree = <View> {
@@ -742,10 +796,33 @@ live_design!{
                                 draw_text: { color: (THEME_COLOR_D_4) }
                             }
                         }
+                        <Vr> {}
+                        pause_animations = <CheckBoxToggle> { text: "Pause animations" }
+                        animation_time = <Slider> {
+                            width: 200.,
+                            text: "Time",
+                            min: -2.0, max: 2.0, step: 0.01, precision: 2
+                        }
                     }
                 }
                 <Profiler> {}
             }
+            Tests = <View> {
+                flow: Down,
+                <DockToolbar> {
+                    content = {
+                        align: { x: 0., y: 0.5 }
+                        run_selected_test = <ButtonFlat> {
+                            text: "Run selected"
+                            icon_walk: { width: 8. }
+                            draw_icon: {
+                                svg_file: dep("crate://self/resources/icons/icon_run.svg"),
+                            }
+                        }
+                    }
+                }
+                test_list = <TestList> {}
+            }
         }}
     }
 }
\ No newline at end of file
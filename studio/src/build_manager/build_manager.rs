@@ -1,10 +1,10 @@
 use {
     crate::{
         app::AppAction,
-        build_manager::{build_client::BuildClient, build_protocol::*},
+        build_manager::{build_client::BuildClient, build_protocol::*, test_runner::TestRunner},
         file_system::file_system::FileSystem,
         makepad_micro_serde::*,
-        makepad_platform::makepad_live_compiler::LiveFileChange,
+        makepad_platform::makepad_live_compiler::{LiveFileChange, LiveLint},
         makepad_platform::os::cx_stdin::{
             HostToStdin, StdinKeyModifiers, StdinMouseDown, StdinMouseMove, StdinMouseUp,
             StdinScroll, StdinToHost,
@@ -22,8 +22,8 @@ use {
         text,
     },
     makepad_http::server::*,
+    makepad_http::websocket_hub::{WebSocketHub, WebSocketHubClientConfig, WebSocketHubEvent},
     std::{
-        cell::RefCell,
         collections::{hash_map, HashMap},
         fs::File,
         io::prelude::*,
@@ -31,7 +31,6 @@ use {
         path::Path,
         path::PathBuf,
         sync::mpsc,
-        sync::{Arc, Mutex},
         thread, time,
         time::{Duration, Instant},
     },
@@ -131,8 +130,16 @@ pub struct BuildManager {
     pub recv_external_ip: ToUIReceiver<SocketAddr>,
     pub tick_timer: Timer,
     pub designer_state: DesignerState,
+    pub test_runner: TestRunner,
     //pub send_file_change: FromUISender<LiveFileChange>,
-    pub active_build_websockets: Arc<Mutex<RefCell<Vec<(u64, LiveId, mpsc::Sender<Vec<u8>>)>>>>,
+    pub build_hub: Option<WebSocketHub>,
+}
+
+// every running app subscribes to the live change channel and to the channel of its own build
+const LIVE_CHANGE_CHANNEL: &str = "live_change";
+
+fn build_channel(build_id: LiveId) -> String {
+    format!("build/{}", build_id.0)
 }
 
 #[derive(Default, SerRon, DeRon)]
@@ -218,6 +225,10 @@ fn get_local_ip() -> String {
 }
 
 impl BuildManager {
+    pub fn root_path(&self) -> &Path {
+        &self.root_path
+    }
+    
     pub fn init(&mut self, cx: &mut Cx, path: &Path) {
         self.http_port = if std::option_env!("MAKEPAD_STUDIO_HTTP").is_some() {
             8002
@@ -291,6 +302,9 @@ impl BuildManager {
     pub fn start_recompile(&mut self, _cx: &mut Cx) {
         // alright so. a file was changed. now what.
         for (build_id, active_build) in &mut self.active.builds {
+            if active_build.process.target == BuildTarget::Test {
+                self.test_runner.start(&active_build.process);
+            }
             self.clients[0].send_cmd_with_id(*build_id, BuildCmd::Stop);
             self.clients[0].send_cmd_with_id(
                 *build_id,
@@ -312,13 +326,48 @@ impl BuildManager {
     }
 
     pub fn clear_log(&mut self, cx: &mut Cx, dock: &DockRef, file_system: &mut FileSystem) {
-        // lets clear all log related decorations
-        file_system.clear_all_decorations();
+        // lets clear all log related decorations, the language server ones stay
+        file_system.remove_all_decorations_with_id(0);
         file_system.redraw_all_views(cx, dock);
         self.log.clear();
         self.profile.clear();
     }
 
+    pub fn add_live_design_lints(&mut self, cx: &mut Cx, file_system: &mut FileSystem, file_name: &str, lints: Vec<LiveLint>) {
+        let file_id = file_system.path_to_file_node_id(file_name);
+        for lint in lints {
+            let start = text::Position {
+                line_index: lint.span.start.line as usize,
+                byte_index: lint.span.start.column as usize,
+            };
+            let end = text::Position {
+                line_index: lint.span.end.line as usize,
+                byte_index: lint.span.end.column as usize,
+            };
+            if let Some(file_id) = file_id {
+                file_system.add_decoration(
+                    file_id,
+                    Decoration::new(0, start, end, DecorationType::Warning),
+                );
+            }
+            self.log.push((
+                LiveId(0),
+                LogItem::Location(LogItemLocation {
+                    level: LogLevel::Warning,
+                    file_name: file_name.to_string(),
+                    start,
+                    end,
+                    message: lint.message,
+                    explanation: None
+                }),
+            ));
+        }
+        if let Some(file_id) = file_id {
+            cx.action(AppAction::RedrawFile(file_id));
+        }
+        cx.action(AppAction::RedrawLog);
+    }
+
     pub fn start_recompile_timer(&mut self, cx: &mut Cx) {
         cx.stop_timer(self.recompile_timer);
         self.recompile_timer = cx.start_timeout(self.recompile_timeout);
@@ -336,14 +385,22 @@ impl BuildManager {
                 contents: live_file_change.content.clone()
             }.to_json()));
         }*/
-        if let Ok(d) = self.active_build_websockets.lock() {
+        if let Some(hub) = &self.build_hub {
             let data = StudioToAppVec(vec![StudioToApp::LiveChange {
                 file_name: live_file_change.file_name.clone(),
                 content: live_file_change.content.clone(),
             }])
             .serialize_bin();
-            for (_,_,sender) in d.borrow_mut().iter_mut() {
-                let _ = sender.send(data.clone());
+            hub.broadcast(LIVE_CHANGE_CHANNEL, &data);
+        }
+    }
+
+    // stops the animation clock of the running apps and moves it time seconds on or back from there
+    pub fn set_animation_clock(&self, paused: bool, time: f64) {
+        if let Some(hub) = &self.build_hub {
+            let data = StudioToAppVec(vec![StudioToApp::AnimationClock { paused, time }]).serialize_bin();
+            for build_id in self.active.builds.keys() {
+                hub.broadcast(&build_channel(*build_id), &data);
             }
         }
     }
@@ -487,6 +544,7 @@ impl BuildManager {
                         AppToStudio::FocusDesign => cx.action(AppAction::FocusDesign(build_id)),
                         AppToStudio::PatchFile(ef) => cx.action(AppAction::PatchFile(ef)),
                         AppToStudio::EditFile(ef) => cx.action(AppAction::EditFile(ef)),
+                        AppToStudio::MoveFileRange(mf) => cx.action(AppAction::MoveFileRange(mf)),
                         AppToStudio::JumpToFile(jt) => {
                             cx.action(AppAction::JumpTo(jt));
                         }
@@ -509,7 +567,7 @@ impl BuildManager {
                         }
                         AppToStudio::DesignerStarted=>{
                             // send the app the select file init message
-                            if let Ok(d) = self.active_build_websockets.lock() {
+                            if let Some(hub) = &self.build_hub {
                                 if let Some(bs) = self.designer_state.state.get(&build_id){
                                     let data = StudioToAppVec(vec![
                                         StudioToApp::DesignerLoadState{
@@ -520,12 +578,7 @@ impl BuildManager {
                                             file_name: bs.selected_file.clone()
                                         },
                                     ]).serialize_bin();
-                                    
-                                    for (_,id,sender) in d.borrow_mut().iter_mut() {
-                                        if *id == build_id{
-                                            let _ = sender.send(data.clone());
-                                        }
-                                    }
+                                    hub.broadcast(&build_channel(build_id), &data);
                                 }
                             }
                             
@@ -576,6 +629,11 @@ impl BuildManager {
                         log.push((wrap.cmd_id, LogItem::Location(loc)));
                         cx.action(AppAction::RedrawLog)
                     }
+                    BuildClientMessage::TestResult(result) => {
+                        if self.test_runner.handle_result(wrap.cmd_id, result) {
+                            cx.action(AppAction::RedrawTests)
+                        }
+                    }
                     BuildClientMessage::LogItem(LogItem::Bare(bare)) => {
                         //log!("{:?}", bare);
                         log.push((wrap.cmd_id, LogItem::Bare(bare)));
@@ -635,7 +693,6 @@ impl BuildManager {
         let rx_file_change = self.send_file_change.receiver();
         //let (tx_live_file, rx_live_file) = mpsc::channel::<HttpServerRequest> ();
 
-        let active_build_websockets = self.active_build_websockets.clone();
         // livecoding observer
         std::thread::spawn(move || {
             loop{
@@ -646,7 +703,35 @@ impl BuildManager {
         });*/
 
         let studio_sender = self.recv_studio_msg.sender();
-        let active_build_websockets = self.active_build_websockets.clone();
+        let (build_hub, hub_events) = WebSocketHub::new(WebSocketHubClientConfig::default());
+        self.build_hub = Some(build_hub.clone());
+        let events_hub = build_hub.clone();
+        std::thread::spawn(move || {
+            // apps connect on /$studio_web_socket/<build_id>
+            let mut socket_id_to_build_id = HashMap::new();
+            while let Ok(event) = hub_events.recv() {
+                match event {
+                    WebSocketHubEvent::Connected {client_id, headers} => {
+                        if let Some(Ok(id)) = headers.path.rsplit("/").next().map(|id| id.parse::<u64>()) {
+                            socket_id_to_build_id.insert(client_id, LiveId(id));
+                            events_hub.subscribe(client_id, LIVE_CHANGE_CHANNEL);
+                            events_hub.subscribe(client_id, &build_channel(LiveId(id)));
+                        }
+                    }
+                    WebSocketHubEvent::Disconnected {client_id} => {
+                        socket_id_to_build_id.remove(&client_id);
+                    }
+                    WebSocketHubEvent::Message {client_id, data} => {
+                        if let Some(id) = socket_id_to_build_id.get(&client_id) {
+                            if let Ok(msg) = AppToStudioVec::deserialize_bin(&data) {
+                                let _ = studio_sender.send((*id, msg));
+                            }
+                        }
+                    }
+                    _ => ()
+                }
+            }
+        });
         std::thread::spawn(move || {
             // TODO fix this proper:
             let makepad_path = "./".to_string();
@@ -684,46 +769,8 @@ impl BuildManager {
                 ),
                 ("/".to_string(), "".to_string()),
             ];
-            let mut socket_id_to_build_id = HashMap::new();
-            while let Ok(message) = rx_request.recv() {
-                // only store last change, fix later
+            build_hub.serve(rx_request, |message| {
                 match message {
-                    HttpServerRequest::ConnectWebSocket {
-                        web_socket_id,
-                        response_sender,
-                        headers,
-                    } => {
-                        if let Some(id) = headers.path.rsplit("/").next() {
-                            if let Ok(id) = id.parse::<u64>() {
-                                socket_id_to_build_id.insert(web_socket_id, LiveId(id));
-                                active_build_websockets
-                                    .lock()
-                                    .unwrap()
-                                    .borrow_mut()
-                                    .push((web_socket_id, LiveId(id), response_sender));
-                            }
-                        }
-                    }
-                    HttpServerRequest::DisconnectWebSocket { web_socket_id } => {
-                        socket_id_to_build_id.remove(&web_socket_id);
-                        active_build_websockets
-                            .lock()
-                            .unwrap()
-                            .borrow_mut()
-                            .retain(|v| v.0 != web_socket_id);
-                    }
-                    HttpServerRequest::BinaryMessage {
-                        web_socket_id,
-                        response_sender: _,
-                        data,
-                    } => {
-                        if let Some(id) = socket_id_to_build_id.get(&web_socket_id) {
-                            if let Ok(msg) = AppToStudioVec::deserialize_bin(&data) {
-                                let _ = studio_sender.send((*id, msg));
-                            }
-                        }
-                        // new incombing message from client
-                    }
                     HttpServerRequest::Get {
                         headers,
                         response_sender,
@@ -745,15 +792,11 @@ impl BuildManager {
                                 header,
                                 body: vec![],
                             });
-                            continue;
+                            return;
                         }
                         if path == "/favicon.ico" {
-                            let header = "HTTP/1.1 200 OK\r\n\r\n".to_string();
-                            let _ = response_sender.send(HttpServerResponse {
-                                header,
-                                body: vec![],
-                            });
-                            continue;
+                            let _ = response_sender.send(HttpServerResponse::status(200));
+                            return;
                         }
 
                         let mime_type = if path.ends_with(".html") {
@@ -773,11 +816,11 @@ impl BuildManager {
                         } else if path.ends_with(".svg") {
                             "image/svg+xml"
                         } else {
-                            continue;
+                            return;
                         };
 
                         if path.contains("..") || path.contains('\\') {
-                            continue;
+                            return;
                         }
 
                         let mut strip = None;
@@ -811,8 +854,9 @@ impl BuildManager {
                     }
                     HttpServerRequest::Post { .. } => { //headers, body, response}=>{
                     }
+                    _ => ()
                 }
-            }
+            });
         });
     }
 
@@ -891,6 +935,9 @@ impl BuildManager {
             target
         };
         let item_id = process.as_id();
+        if target == BuildTarget::Test {
+            self.test_runner.start(&process);
+        }
         self.clients[0].send_cmd_with_id(item_id, BuildCmd::Run(process.clone(),self.studio_http.clone()));
         //let run_view_id = LiveId::unique();
         if self.active.builds.get(&item_id).is_none() {
@@ -910,6 +957,14 @@ impl BuildManager {
         //}
     }
     
+    // runs one test again, its result replaces the one from the last run
+    pub fn run_test(&mut self, process: BuildProcess, name: &str) {
+        let build_id = process.as_id();
+        self.test_runner.start_one(&process, name);
+        self.clients[0].send_cmd_with_id(build_id, BuildCmd::Stop);
+        self.clients[0].send_cmd_with_id(build_id, BuildCmd::RunTest(process, name.to_string()));
+    }
+    
     pub fn stop_all_active_builds(&mut self, cx:&mut Cx){
         while self.active.builds.len()>0{
             let build = &self.active.builds.values().next().unwrap();
@@ -21,6 +21,7 @@ pub enum BuildTarget {
     CheckWindows,
     CheckLinux,
     CheckAll,
+    Test,
 }
 
 impl BuildTarget {
@@ -48,8 +49,9 @@ impl BuildTarget {
     pub const CHECK_WINDOWS: u64 = 13;
     pub const CHECK_LINUX: u64 = 14;
     pub const CHECK_ALL: u64 = 15;
+    pub const TEST: u64 = 16;
     pub fn len() -> usize {
-        Self::CHECK_ALL as usize + 1
+        Self::TEST as usize + 1
     }
     pub fn name(&self) -> &'static str {
         match self {
@@ -69,6 +71,7 @@ impl BuildTarget {
             Self::CheckWindows => "Check Windows",
             Self::CheckLinux => "Check Linux",
             Self::CheckAll => "Check All",
+            Self::Test => "Test",
         }
     }
     pub fn as_id(&self) -> usize {
@@ -89,6 +92,7 @@ impl BuildTarget {
             Self::CheckWindows => Self::CHECK_WINDOWS,
             Self::CheckLinux => Self::CHECK_LINUX,
             Self::CheckAll => Self::CHECK_ALL,
+            Self::Test => Self::TEST,
         }) as usize
     }
     pub fn from_id(tgt: usize) -> Self {
@@ -109,6 +113,7 @@ impl BuildTarget {
             Self::CHECK_WINDOWS => Self::CheckWindows,
             Self::CHECK_LINUX => Self::CheckLinux,
             Self::CHECK_ALL => Self::CheckAll,
+            Self::TEST => Self::Test,
             _ => panic!(),
         }
     }
@@ -136,6 +141,8 @@ pub struct BuildCmdWrap {
 pub enum BuildCmd {
     Stop,
     Run(BuildProcess, String),
+    // runs the one test with this exact name, the process has to be a test target
+    RunTest(BuildProcess, String),
     HostToStdin(String),
 }
 
@@ -168,8 +175,25 @@ pub enum LogItem {
     StdinToHost(String),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestState {
+    Running,
+    Passed,
+    Failed,
+    Ignored,
+}
+
+#[derive(Clone, Debug)]
+pub struct TestResult {
+    pub name: String,
+    pub state: TestState,
+    // where a failed test panicked
+    pub location: Option<(String, Position)>,
+}
+
 #[derive(Clone)]
 pub enum BuildClientMessage {
     LogItem(LogItem),
+    TestResult(TestResult),
     AuxChanHostEndpointCreated(crate::makepad_platform::cx_stdin::aux_chan::HostEndpoint),
 }
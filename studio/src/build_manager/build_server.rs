@@ -77,7 +77,7 @@ impl BuildConnection {
         }
     }
 
-    pub fn run(&self, what: BuildProcess, cmd_id: LiveId, http: String) {
+    pub fn run(&self, what: BuildProcess, cmd_id: LiveId, http: String, test_name: Option<String>) {
         let shared = self.shared.clone();
         let msg_sender = self.msg_sender.clone();
         // alright lets run a cargo check and parse its output
@@ -86,7 +86,7 @@ impl BuildConnection {
         let http = format!("{}/{}", http, cmd_id.0);
         let mut env = vec![("MAKEPAD_STUDIO_HTTP", http.as_str()), ("MAKEPAD", "lines")];
 
-        let args: Vec<String> = match &what.target {
+        let mut args: Vec<String> = match &what.target {
             BuildTarget::ReleaseStudio => vec![
                 "run".into(),
                 "-p".into(),
@@ -248,7 +248,21 @@ impl BuildConnection {
                 "--release".into(),
                 "--message-format=json".into(),
             ],
+            BuildTarget::Test => vec![
+                "test".into(),
+                "-p".into(),
+                what.binary.clone(),
+                "--message-format=json".into(),
+                "--".into(),
+                "-Z".into(),
+                "unstable-options".into(),
+                "--format=json".into(),
+            ],
         };
+        if let Some(test_name) = test_name {
+            args.push("--exact".into());
+            args.push(test_name);
+        }
 
         let is_in_studio = match what.target {
             BuildTarget::ReleaseStudio | BuildTarget::DebugStudio => true,
@@ -314,6 +328,10 @@ impl BuildConnection {
                                 }
                             }
                             Err(_) => {
+                                if let Some(event) = RustcTestEvent::from_json(&line) {
+                                    msg_sender.process_test_event(cmd_id, event);
+                                    continue;
+                                }
                                 // we should output a log string
                                 //eprintln!("GOT ERROR {:?}", err);
                                 msg_sender.send_stdin_to_host_msg(cmd_id, line);
@@ -323,7 +341,9 @@ impl BuildConnection {
                     ChildStdIO::StdErr(line) => {
                         if line.trim().starts_with("Running ") {
                             msg_sender.send_bare_message(cmd_id, LogLevel::Wait, line);
-                        } else if line.trim().starts_with("Compiling ") {
+                        } else if line.trim().starts_with("Compiling ")
+                            || line.trim().starts_with("Doc-tests ")
+                        {
                             msg_sender.send_bare_message(cmd_id, LogLevel::Wait, line);
                         } else if line
                             .trim()
@@ -338,6 +358,7 @@ impl BuildConnection {
                             msg_sender.send_bare_message(cmd_id, LogLevel::Error, line);
                         }
                     }
+                    ChildStdIO::StdOutData(_) => (),
                     ChildStdIO::Term => {
                         msg_sender.send_bare_message(
                             cmd_id,
@@ -358,7 +379,10 @@ impl BuildConnection {
         match cmd_wrap.cmd {
             BuildCmd::Run(process, http) => {
                 // lets kill all other 'whats'
-                self.run(process, cmd_wrap.cmd_id, http);
+                self.run(process, cmd_wrap.cmd_id, http, None);
+            }
+            BuildCmd::RunTest(process, test_name) => {
+                self.run(process, cmd_wrap.cmd_id, String::new(), Some(test_name));
             }
             BuildCmd::Stop => {
                 // lets kill all other 'whats'
@@ -488,6 +512,68 @@ pub trait MsgSender: Send {
             }
         }
     }
+
+    fn send_test_result(&self, cmd_id: LiveId, result: TestResult) {
+        self.send_message(BuildClientMessageWrap {
+            cmd_id,
+            message: BuildClientMessage::TestResult(result),
+        });
+    }
+
+    fn process_test_event(&self, cmd_id: LiveId, event: RustcTestEvent) {
+        match (event._type.as_str(), event.event.as_str()) {
+            ("suite", "ok") | ("suite", "failed") => {
+                let level = if event.event == "ok" { LogLevel::Log } else { LogLevel::Error };
+                self.send_bare_message(
+                    cmd_id,
+                    level,
+                    format!(
+                        "test result: {}. {} passed; {} failed; {} ignored",
+                        event.event,
+                        event.passed.unwrap_or(0),
+                        event.failed.unwrap_or(0),
+                        event.ignored.unwrap_or(0)
+                    ),
+                );
+            }
+            ("test", state) => {
+                let Some(name) = event.name.clone() else {
+                    return;
+                };
+                let state = match state {
+                    "started" => TestState::Running,
+                    "ok" => TestState::Passed,
+                    "failed" => TestState::Failed,
+                    "ignored" => TestState::Ignored,
+                    _ => return,
+                };
+                let mut location = None;
+                if state == TestState::Failed {
+                    match event.panic_location() {
+                        Some((file_name, pos, message)) => {
+                            self.send_location_msg(
+                                cmd_id,
+                                LogLevel::Error,
+                                file_name.clone(),
+                                pos,
+                                pos,
+                                format!("test {} failed: {}", name, message),
+                                event.stdout.clone(),
+                            );
+                            location = Some((file_name.replace("\\", "/"), pos));
+                        }
+                        None => self.send_bare_message(
+                            cmd_id,
+                            LogLevel::Error,
+                            format!("test {} failed\n{}", name, event.stdout.as_deref().unwrap_or("")),
+                        ),
+                    }
+                }
+                self.send_test_result(cmd_id, TestResult { name, state, location });
+            }
+            _ => (),
+        }
+    }
 }
 
 impl<F: Clone + Fn(BuildClientMessageWrap) + Send + 'static> MsgSender for F {
@@ -511,3 +597,82 @@ impl fmt::Debug for dyn MsgSender {
         write!(f, "MsgSender")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // runs the lines through `process_test_event` and describes what gets sent
+    fn process(lines: &[&str]) -> Vec<String> {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let sender = {
+            let sent = sent.clone();
+            move |wrap: BuildClientMessageWrap| sent.lock().unwrap().push(wrap.message)
+        };
+        for line in lines {
+            sender.process_test_event(LiveId(1), RustcTestEvent::from_json(line).unwrap());
+        }
+        let sent = sent.lock().unwrap();
+        sent.iter()
+            .map(|message| match message {
+                BuildClientMessage::TestResult(result) => format!("{} {:?} {:?}", result.name, result.state, result.location),
+                BuildClientMessage::LogItem(LogItem::Bare(bare)) => format!("{:?} {}", bare.level, bare.line),
+                BuildClientMessage::LogItem(LogItem::Location(location)) => format!(
+                    "{:?} {}:{} {}",
+                    location.level,
+                    location.file_name,
+                    location.start.line_index,
+                    location.message
+                ),
+                _ => "other".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_events() {
+        assert_eq!(
+            process(&[
+                r#"{ "type": "suite", "event": "started", "test_count": 3 }"#,
+                r#"{ "type": "test", "event": "started", "name": "tests::a" }"#,
+                r#"{ "type": "test", "name": "tests::a", "event": "ok", "exec_time": 0.01 }"#,
+                r#"{ "type": "test", "name": "tests::b", "event": "ignored", "message": "slow" }"#,
+            ]),
+            vec![
+                "tests::a Running None",
+                "tests::a Passed None",
+                "tests::b Ignored None",
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_tests() {
+        assert_eq!(
+            process(&[
+                r#"{ "type": "test", "name": "tests::c", "event": "failed", "stdout": "\nthread 'tests::c' panicked at src\\lib.rs:4:5:\nboom\nnote: run with `RUST_BACKTRACE=1`\n" }"#,
+                r#"{ "type": "test", "name": "tests::d", "event": "failed", "stdout": "timed out" }"#,
+            ]),
+            vec![
+                "Error src/lib.rs:3 test tests::c failed: boom",
+                "tests::c Failed Some((\"src/lib.rs\", Position { line_index: 3, byte_index: 4 }))",
+                "Error test tests::d failed\ntimed out",
+                "tests::d Failed None",
+            ]
+        );
+    }
+
+    #[test]
+    fn suite_summary() {
+        assert_eq!(
+            process(&[
+                r#"{ "type": "suite", "event": "ok", "passed": 2, "failed": 0, "ignored": 1, "measured": 0, "filtered_out": 0, "exec_time": 0.5 }"#,
+                r#"{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 0, "measured": 0, "filtered_out": 0 }"#,
+            ]),
+            vec![
+                "Log test result: ok. 2 passed; 0 failed; 1 ignored",
+                "Error test result: failed. 1 passed; 1 failed; 0 ignored",
+            ]
+        );
+    }
+}
@@ -19,6 +19,8 @@ pub struct ChildProcess {
 
 pub enum ChildStdIO {
     StdOut(String),
+    // stdout as it arrives, for processes started with `start_raw`
+    StdOutData(Vec<u8>),
     StdErr(String),
     Term,
     Kill
@@ -32,6 +34,15 @@ pub enum ChildStdIn {
 impl ChildProcess {
     
     pub fn start(cmd: &str, args: &[String], current_dir: PathBuf, env: &[(&str, &str)], aux_chan:bool) -> Result<ChildProcess, std::io::Error> {
+        Self::start_inner(cmd, args, current_dir, env, aux_chan, false)
+    }
+    
+    // for protocols that aren't line based, stdout is forwarded in chunks as it arrives
+    pub fn start_raw(cmd: &str, args: &[String], current_dir: PathBuf, env: &[(&str, &str)]) -> Result<ChildProcess, std::io::Error> {
+        Self::start_inner(cmd, args, current_dir, env, false, true)
+    }
+    
+    fn start_inner(cmd: &str, args: &[String], current_dir: PathBuf, env: &[(&str, &str)], aux_chan:bool, raw_stdout:bool) -> Result<ChildProcess, std::io::Error> {
         let (mut child, aux_chan_host_endpoint) = if aux_chan{
             let (aux_chan_host_endpoint, aux_chan_client_endpoint) =
                 aux_chan::make_host_and_client_endpoint_pair()?;
@@ -82,6 +93,23 @@ impl ChildProcess {
             let line_sender = line_sender.clone();
             let stdin_sender = stdin_sender.clone();
             thread::spawn(move || {
+                if raw_stdout{
+                    let mut stdout = stdout;
+                    let mut buf = [0u8; 16384];
+                    loop{
+                        match stdout.read(&mut buf){
+                            Ok(0) | Err(_)=>{
+                                let _ = line_sender.send(ChildStdIO::Term);
+                                let _ = stdin_sender.send(ChildStdIn::Term);
+                                break;
+                            }
+                            Ok(len)=>if line_sender.send(ChildStdIO::StdOutData(buf[..len].to_vec())).is_err(){
+                                break;
+                            }
+                        }
+                    }
+                    return
+                }
                 let mut reader = BufReader::new(stdout);
                 loop{
                     let mut line = String::new();
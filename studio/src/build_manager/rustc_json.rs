@@ -116,3 +116,125 @@ pub struct RustcCompilerMessage {
     pub executable: Option<String>,
     pub fresh: Option<bool>
 }
+
+// test harness output json, from `cargo test -- -Z unstable-options --format=json`
+#[derive(Clone, Debug, Default)]
+pub struct RustcTestEvent {
+    pub _type: String,
+    pub event: String,
+    pub name: Option<String>,
+    pub test_count: Option<u64>,
+    pub passed: Option<u64>,
+    pub failed: Option<u64>,
+    pub ignored: Option<u64>,
+    pub measured: Option<u64>,
+    pub filtered_out: Option<u64>,
+    pub allowed_fail: Option<u64>,
+    pub shuffle_seed: Option<u64>,
+    pub exec_time: Option<f64>,
+    pub stdout: Option<String>,
+    pub message: Option<String>,
+    pub median: Option<f64>,
+    pub deviation: Option<f64>,
+    pub mib_per_second: Option<f64>,
+}
+
+impl RustcTestEvent {
+    // libtest adds keys to its events now and then, so they are read through a JsonValue and
+    // keys that aren't known here are skipped. None if the line isn't a test event
+    pub fn from_json(line: &str) -> Option<Self> {
+        let value: JsonValue = DeJson::deserialize_json(line).ok()?;
+        let string = |key| value.key(key).and_then(JsonValue::string).cloned();
+        let u64 = |key| value.key(key).and_then(JsonValue::u64);
+        let f64 = |key| match value.key(key)? {
+            JsonValue::F64(v) => Some(*v),
+            JsonValue::U64(v) => Some(*v as f64),
+            JsonValue::I64(v) => Some(*v as f64),
+            _ => None,
+        };
+        Some(Self {
+            _type: string("type")?,
+            event: string("event")?,
+            name: string("name"),
+            test_count: u64("test_count"),
+            passed: u64("passed"),
+            failed: u64("failed"),
+            ignored: u64("ignored"),
+            measured: u64("measured"),
+            filtered_out: u64("filtered_out"),
+            allowed_fail: u64("allowed_fail"),
+            shuffle_seed: u64("shuffle_seed"),
+            exec_time: f64("exec_time"),
+            stdout: string("stdout"),
+            message: string("message"),
+            median: f64("median"),
+            deviation: f64("deviation"),
+            mib_per_second: f64("mib_per_second"),
+        })
+    }
+
+    // where the test panicked and with what message, from output like
+    // "thread 'tests::a' panicked at src/lib.rs:10:9:\nmessage"
+    pub fn panic_location(&self) -> Option<(String, Position, String)> {
+        let stdout = self.stdout.as_ref()?;
+        let at = stdout.find("panicked at ")?;
+        let mut lines = stdout[at + "panicked at ".len()..].lines();
+        let location = lines.next()?.trim_end_matches(':');
+        let mut parts = location.rsplitn(3, ':');
+        let column: usize = parts.next()?.parse().ok()?;
+        let line: usize = parts.next()?.parse().ok()?;
+        let file_name = parts.next()?.to_string();
+        let message = lines
+            .take_while(|line| !line.starts_with("note:") && !line.starts_with("stack backtrace:"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Some((
+            file_name,
+            Position {
+                line_index: line.saturating_sub(1),
+                byte_index: column.saturating_sub(1),
+            },
+            message,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events() {
+        let event = RustcTestEvent::from_json(r#"{ "type": "suite", "event": "started", "test_count": 3 }"#).unwrap();
+        assert_eq!((event._type.as_str(), event.event.as_str(), event.test_count), ("suite", "started", Some(3)));
+        let event = RustcTestEvent::from_json(r#"{ "type": "test", "event": "ok", "name": "tests::a", "exec_time": 0.25 }"#).unwrap();
+        assert_eq!((event.name.as_deref(), event.exec_time), (Some("tests::a"), Some(0.25)));
+        // keys that aren't known don't stop an event from parsing
+        let event = RustcTestEvent::from_json(r#"{ "type": "test", "event": "ignored", "name": "tests::b", "reason": "slow", "extra": {"a": [1]} }"#).unwrap();
+        assert_eq!((event.event.as_str(), event.name.as_deref()), ("ignored", Some("tests::b")));
+        let event = RustcTestEvent::from_json(r#"{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1, "measured": 0, "filtered_out": 2, "exec_time": 1 }"#).unwrap();
+        assert_eq!((event.passed, event.failed, event.ignored, event.filtered_out, event.exec_time), (Some(1), Some(1), Some(1), Some(2), Some(1.0)));
+        assert!(RustcTestEvent::from_json(r#"{ "reason": "build-finished", "success": true }"#).is_none());
+        assert!(RustcTestEvent::from_json("running 3 tests").is_none());
+    }
+
+    #[test]
+    fn panic_locations() {
+        let event = RustcTestEvent {
+            stdout: Some("\nthread 'tests::a' panicked at src/lib.rs:10:9:\nassertion failed: x\nline two\nnote: run with `RUST_BACKTRACE=1`\n".to_string()),
+            ..RustcTestEvent::default()
+        };
+        assert_eq!(
+            event.panic_location(),
+            Some(("src/lib.rs".to_string(), Position {line_index: 9, byte_index: 8}, "assertion failed: x\nline two".to_string()))
+        );
+        // windows paths keep their drive letter
+        let event = RustcTestEvent {
+            stdout: Some("thread 'a' panicked at C:\\src\\lib.rs:2:5:\nboom".to_string()),
+            ..RustcTestEvent::default()
+        };
+        assert_eq!(event.panic_location().map(|(file_name, _, message)| (file_name, message)), Some(("C:\\src\\lib.rs".to_string(), "boom".to_string())));
+        assert!(RustcTestEvent {stdout: Some("no panic here".to_string()), ..RustcTestEvent::default()}.panic_location().is_none());
+        assert!(RustcTestEvent::default().panic_location().is_none());
+    }
+}
@@ -0,0 +1,209 @@
+use {
+    crate::{
+        build_manager::build_protocol::*,
+        file_system::file_system::{FileSystem, OpenDocument},
+        makepad_code_editor::{
+            line_marker::{LineMarker, LineMarkerKind},
+            text::Position,
+        },
+        makepad_widgets::*,
+    },
+    std::collections::HashMap,
+};
+
+// the gutter markers on tests, next to the ones from git
+pub const TEST_LINE_MARKER_ID: usize = 1;
+
+#[derive(Clone, Debug)]
+pub struct TestItem {
+    pub process: BuildProcess,
+    pub name: String,
+    pub state: TestState,
+    pub location: Option<(String, Position)>,
+}
+
+// Keeps the results of test runs. A test is known by its process and its full name, like
+// `tests::parses_empty_input`
+#[derive(Default)]
+pub struct TestRunner {
+    // the processes results come from, by build id
+    processes: HashMap<LiveId, BuildProcess>,
+    pub tests: Vec<TestItem>,
+    // the test picked in the test list, to run again
+    pub selected: Option<(BuildProcess, String)>,
+}
+
+// the state that matters most when showing tests together, failures first
+pub fn combine_test_states(a: TestState, b: TestState) -> TestState {
+    fn rank(state: TestState) -> usize {
+        match state {
+            TestState::Failed => 3,
+            TestState::Running => 2,
+            TestState::Passed => 1,
+            TestState::Ignored => 0,
+        }
+    }
+    if rank(b) > rank(a) {
+        b
+    } else {
+        a
+    }
+}
+
+impl TestRunner {
+    // a full run replaces what an earlier run of the same process found
+    pub fn start(&mut self, process: &BuildProcess) {
+        self.processes.insert(process.as_id(), process.clone());
+        self.tests.retain(|test| test.process != *process);
+    }
+
+    pub fn start_one(&mut self, process: &BuildProcess, name: &str) {
+        self.processes.insert(process.as_id(), process.clone());
+        if let Some(test) = self
+            .tests
+            .iter_mut()
+            .find(|test| test.process == *process && test.name == name)
+        {
+            test.state = TestState::Running;
+            test.location = None;
+        }
+    }
+
+    pub fn handle_result(&mut self, build_id: LiveId, result: TestResult) -> bool {
+        let Some(process) = self.processes.get(&build_id) else {
+            return false;
+        };
+        match self
+            .tests
+            .iter_mut()
+            .find(|test| test.process == *process && test.name == result.name)
+        {
+            Some(test) => {
+                test.state = result.state;
+                test.location = result.location;
+            }
+            None => self.tests.push(TestItem {
+                process: process.clone(),
+                name: result.name,
+                state: result.state,
+                location: result.location,
+            }),
+        }
+        true
+    }
+
+    // test names don't say which file a test is in, so functions are matched by name in any module
+    fn state_of_fn(&self, fn_name: &str) -> Option<TestState> {
+        self.tests
+            .iter()
+            .filter(|test| test.name == fn_name || test.name.ends_with(&format!("::{}", fn_name)))
+            .map(|test| test.state)
+            .reduce(combine_test_states)
+    }
+
+    // puts a marker on every `#[test]` of an open Rust file
+    pub fn update_markers(&self, file_system: &FileSystem, file_id: LiveId) {
+        let Some(OpenDocument::Code(document)) = file_system.open_documents.get(&file_id) else {
+            return;
+        };
+        if !file_system
+            .file_node_id_to_path(file_id)
+            .map_or(false, |path| path.ends_with(".rs"))
+        {
+            return;
+        }
+        let text = document.as_text();
+        let lines = text.as_lines();
+        let mut markers = Vec::new();
+        for (line_index, line) in lines.iter().enumerate() {
+            let Some(rest) = line.trim_start().strip_prefix("#[test]") else {
+                continue;
+            };
+            // the function follows the attribute, possibly after more attributes
+            let fn_name = std::iter::once(rest)
+                .chain(lines[line_index + 1..].iter().take(8).map(|line| line.as_str()))
+                .find_map(|line| {
+                    let (_, after) = line.split_once("fn ")?;
+                    let name: String = after
+                        .chars()
+                        .take_while(|c| c.is_alphanumeric() || *c == '_')
+                        .collect();
+                    Some(name)
+                });
+            let Some(fn_name) = fn_name else {
+                continue;
+            };
+            markers.push(LineMarker {
+                id: TEST_LINE_MARKER_ID,
+                kind: match self.state_of_fn(&fn_name) {
+                    None | Some(TestState::Running) => LineMarkerKind::Test,
+                    Some(TestState::Passed) => LineMarkerKind::TestPassed,
+                    Some(TestState::Failed) => LineMarkerKind::TestFailed,
+                    Some(TestState::Ignored) => LineMarkerKind::TestIgnored,
+                },
+                start_line: line_index,
+                end_line: line_index + 1,
+            });
+        }
+        document.set_line_markers(TEST_LINE_MARKER_ID, markers);
+    }
+
+    pub fn update_all_markers(&self, file_system: &FileSystem) {
+        for file_id in file_system.open_documents.keys() {
+            self.update_markers(file_system, *file_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(binary: &str) -> BuildProcess {
+        BuildProcess {
+            binary: binary.to_string(),
+            target: BuildTarget::Debug,
+        }
+    }
+
+    fn result(name: &str, state: TestState) -> TestResult {
+        TestResult {
+            name: name.to_string(),
+            state,
+            location: None,
+        }
+    }
+
+    #[test]
+    fn combines_states() {
+        assert_eq!(combine_test_states(TestState::Passed, TestState::Failed), TestState::Failed);
+        assert_eq!(combine_test_states(TestState::Running, TestState::Passed), TestState::Running);
+        assert_eq!(combine_test_states(TestState::Ignored, TestState::Passed), TestState::Passed);
+        assert_eq!(combine_test_states(TestState::Failed, TestState::Running), TestState::Failed);
+    }
+
+    #[test]
+    fn keeps_results_by_process() {
+        let (app, lib) = (process("app"), process("lib"));
+        let mut runner = TestRunner::default();
+        assert!(!runner.handle_result(app.as_id(), result("tests::a", TestState::Passed)));
+        runner.start(&app);
+        runner.start(&lib);
+        assert!(runner.handle_result(app.as_id(), result("tests::a", TestState::Running)));
+        assert!(runner.handle_result(app.as_id(), result("tests::a", TestState::Passed)));
+        assert!(runner.handle_result(app.as_id(), result("parser::tests::b", TestState::Failed)));
+        assert!(runner.handle_result(lib.as_id(), result("tests::a", TestState::Ignored)));
+        assert_eq!(runner.tests.len(), 3);
+        // functions are matched by name in any module, failures win
+        assert_eq!(runner.state_of_fn("a"), Some(TestState::Passed));
+        assert_eq!(runner.state_of_fn("b"), Some(TestState::Failed));
+        assert_eq!(runner.state_of_fn("tests"), None);
+
+        runner.start_one(&app, "tests::a");
+        assert_eq!(runner.state_of_fn("a"), Some(TestState::Running));
+        // a full run forgets what the earlier one of the same process found
+        runner.start(&app);
+        assert_eq!(runner.tests.len(), 1);
+        assert_eq!(runner.state_of_fn("a"), Some(TestState::Ignored));
+    }
+}
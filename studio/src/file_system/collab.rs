@@ -0,0 +1,284 @@
+use {
+    crate::{
+        file_system::FileClient,
+        makepad_code_editor::{
+            remote_cursor::RemoteCursor,
+            text::{Drift, Edit, Position},
+            CodeDocument, CodeSession,
+        },
+        makepad_file_protocol::{
+            apply_change, diff_text, is_valid_change, transform, FileDelta, FileRequest, RejoinFileResponse,
+            TextChange, TextPosition, TextSelection,
+        },
+        makepad_widgets::*,
+    },
+    std::{
+        collections::{BTreeMap, HashMap},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+// the colors of the cursors of others, picked by participant
+const PARTICIPANT_COLORS: [Vec4; 6] = [
+    Vec4{x: 0.95, y: 0.55, z: 0.25, w: 1.0},
+    Vec4{x: 0.35, y: 0.75, z: 0.95, w: 1.0},
+    Vec4{x: 0.75, y: 0.45, z: 0.95, w: 1.0},
+    Vec4{x: 0.45, y: 0.85, z: 0.45, w: 1.0},
+    Vec4{x: 0.95, y: 0.4, z: 0.6, w: 1.0},
+    Vec4{x: 0.95, y: 0.85, z: 0.3, w: 1.0},
+];
+
+// Where an open code document is in the collaboration session of its file on the file server.
+// Local edits go out as one delta at a time: while a delta is in flight, newer edits wait in the
+// buffer, and deltas from others are transformed past both before they go into the document
+#[derive(Default)]
+pub struct CollabFile{
+    pub revision: u64,
+    inflight: Option<(u64, Vec<TextChange>)>,
+    buffer: Vec<TextChange>,
+    // deltas that came in before the ones preceding them, while (re)joining
+    early: BTreeMap<u64, FileDelta>,
+    // false until the server told us the revision we are at
+    joined: bool,
+    // a save waits until the server has all our edits, the bool is whether it was a patch
+    save_pending: Option<bool>,
+    // whether deltas of others went into the document since this was last taken
+    changed: bool,
+}
+
+fn to_text_position(position: Position)->TextPosition{
+    TextPosition{line_index: position.line_index, byte_index: position.byte_index}
+}
+
+fn to_position(position: TextPosition)->Position{
+    Position{line_index: position.line_index, byte_index: position.byte_index}
+}
+
+fn to_edit(change: TextChange)->Edit{
+    Edit{change: change.into(), drift: Drift::Before}
+}
+
+impl CollabFile{
+    fn has_pending_edits(&self)->bool{
+        self.inflight.is_some() || !self.buffer.is_empty()
+    }
+
+    fn take_local_edits(&mut self, document: &CodeDocument){
+        self.buffer.extend(document.take_recorded_edits().into_iter().map(|edit| edit.change.into()));
+    }
+
+    // applies changes from the server to the document, false if they don't fit it
+    fn apply_to_document(&mut self, document: &CodeDocument, changes: Vec<TextChange>)->bool{
+        let mut lines = document.as_text().as_lines().to_vec();
+        for change in &changes{
+            if !is_valid_change(&lines, change){
+                return false
+            }
+            apply_change(&mut lines, change);
+        }
+        if !changes.is_empty(){
+            self.changed = true;
+            document.apply_remote_edits(changes.into_iter().map(to_edit).collect());
+        }
+        true
+    }
+
+    fn apply(&mut self, document: &CodeDocument, delta: FileDelta)->bool{
+        self.revision = delta.revision;
+        // our own delta coming back is the acknowledgement for it
+        if delta.delta_id != 0 && self.inflight.as_ref().is_some_and(|(delta_id, _)| *delta_id == delta.delta_id){
+            self.inflight = None;
+            return true
+        }
+        let mut changes = delta.changes;
+        if let Some((_, inflight)) = &mut self.inflight{
+            let (new_changes, new_inflight) = transform(&changes, inflight, false);
+            changes = new_changes;
+            *inflight = new_inflight;
+        }
+        let (changes, buffer) = transform(&changes, &self.buffer, false);
+        self.buffer = buffer;
+        self.apply_to_document(document, changes)
+    }
+
+    pub fn take_changed(&mut self)->bool{
+        std::mem::take(&mut self.changed)
+    }
+
+    // applies the deltas that were waiting for the ones before them
+    fn apply_early(&mut self, document: &CodeDocument)->bool{
+        self.early.retain(|revision, _| *revision > self.revision);
+        while let Some(delta) = self.early.remove(&(self.revision + 1)){
+            if !self.apply(document, delta){
+                return false
+            }
+        }
+        true
+    }
+
+    // false if the delta didn't fit, and the file has to be joined again
+    pub fn receive(&mut self, document: &CodeDocument, delta: FileDelta)->bool{
+        if delta.revision <= self.revision{
+            return true
+        }
+        if !self.joined || delta.revision > self.revision + 1{
+            self.early.insert(delta.revision, delta);
+            return true
+        }
+        self.take_local_edits(document);
+        self.apply(document, delta) && self.apply_early(document)
+    }
+
+    pub fn opened(&mut self, document: &CodeDocument, revision: u64)->bool{
+        document.record_edits();
+        self.revision = revision;
+        self.joined = true;
+        self.apply_early(document)
+    }
+
+    pub fn defer(&mut self, delta: FileDelta){
+        self.early.insert(delta.revision, delta);
+    }
+
+    pub fn leave(&mut self){
+        self.joined = false;
+    }
+
+    // takes what the server sent when the file was joined again
+    pub fn rejoined(&mut self, document: &CodeDocument, response: RejoinFileResponse)->bool{
+        self.take_local_edits(document);
+        // a delta in flight either made it to the server, then it is among the deltas, or it is
+        // lost and has to be sent again
+        match response.data{
+            Some(data)=>{
+                let text = document.as_text().to_string();
+                let pending = self.has_pending_edits();
+                self.inflight = None;
+                self.revision = response.revision;
+                // what we have wins over what the server has if we have edits it didn't get
+                if pending{
+                    self.buffer = diff_text(&data, &text);
+                }
+                else if !self.apply_to_document(document, diff_text(&text, &data)){
+                    return false
+                }
+            }
+            None=>{
+                for delta in response.deltas{
+                    if delta.revision > self.revision && !self.apply(document, delta){
+                        return false
+                    }
+                }
+                self.revision = self.revision.max(response.revision);
+                if let Some((_, inflight)) = self.inflight.take(){
+                    self.buffer.splice(0..0, inflight);
+                }
+            }
+        }
+        self.joined = true;
+        self.apply_early(document)
+    }
+}
+
+// the collaboration state of every open code document, and the ids our deltas are known by
+pub struct CollabClient{
+    pub files: HashMap<LiveId, CollabFile>,
+    next_delta_id: u64,
+    // the selections each tab last sent
+    tab_selections: HashMap<LiveId, Vec<TextSelection>>,
+}
+
+impl Default for CollabClient{
+    fn default()->Self{
+        // delta ids only have to differ from those of others editing the same file
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64);
+        Self{
+            files: HashMap::new(),
+            next_delta_id: LiveId::from_num(time, std::process::id() as u64).0 | 1,
+            tab_selections: HashMap::new(),
+        }
+    }
+}
+
+impl CollabClient{
+    pub fn participant_color(participant: u64)->Vec4{
+        PARTICIPANT_COLORS[participant as usize % PARTICIPANT_COLORS.len()]
+    }
+
+    // sends the local edits of a file to the server, if nothing is in flight. Returns whether a
+    // save that was waiting for the server can go out now
+    pub fn sync(&mut self, file_client: &mut FileClient, document: &CodeDocument, file_id: LiveId, path: &str)->Option<bool>{
+        let file = self.files.get_mut(&file_id)?;
+        file.take_local_edits(document);
+        if !file.joined || file.inflight.is_some(){
+            return None
+        }
+        if !file.buffer.is_empty(){
+            let delta_id = self.next_delta_id;
+            self.next_delta_id = self.next_delta_id.wrapping_add(2);
+            let changes = std::mem::take(&mut file.buffer);
+            file.inflight = Some((delta_id, changes.clone()));
+            file_client.send_request(FileRequest::ApplyDelta{
+                path: path.to_string(),
+                id: file_id.0,
+                revision: file.revision,
+                delta_id,
+                changes
+            });
+            return None
+        }
+        file.save_pending.take()
+    }
+
+    // the revision to save a file at, None when the save has to wait for the server to have
+    // our edits
+    pub fn save(&mut self, file_client: &mut FileClient, document: &CodeDocument, file_id: LiveId, path: &str, patch: bool)->Option<u64>{
+        self.sync(file_client, document, file_id, path);
+        let file = self.files.get_mut(&file_id)?;
+        if !file.joined || file.has_pending_edits(){
+            file.save_pending = Some(file.save_pending.map_or(patch, |pending| pending && patch));
+            return None
+        }
+        Some(file.revision)
+    }
+
+    pub fn is_joined(&self, file_id: LiveId)->bool{
+        self.files.get(&file_id).is_some_and(|file| file.joined)
+    }
+
+    pub fn revision(&self, file_id: LiveId)->Option<u64>{
+        self.files.get(&file_id).map(|file| file.revision)
+    }
+
+    // tells the others where the cursors of a tab are, when they moved
+    pub fn update_selections(&mut self, file_client: &mut FileClient, tab_id: LiveId, session: &CodeSession, file_id: LiveId, path: &str){
+        let selections: Vec<TextSelection> = session.selections().iter().map(|selection| TextSelection{
+            anchor: to_text_position(selection.anchor),
+            cursor: to_text_position(selection.cursor.position)
+        }).collect();
+        if self.tab_selections.get(&tab_id) == Some(&selections){
+            return
+        }
+        self.tab_selections.insert(tab_id, selections.clone());
+        file_client.send_request(FileRequest::SetSelections{path: path.to_string(), id: file_id.0, selections});
+    }
+
+    pub fn forget_tab(&mut self, tab_id: LiveId){
+        self.tab_selections.remove(&tab_id);
+    }
+
+    // after joining again every tab sends its selections again
+    pub fn forget_selections(&mut self){
+        self.tab_selections.clear();
+    }
+
+    pub fn set_remote_selections(document: &CodeDocument, participant: u64, selections: Vec<TextSelection>){
+        let color = Self::participant_color(participant);
+        document.set_remote_cursors(participant as usize, selections.into_iter().map(|selection| RemoteCursor{
+            id: participant as usize,
+            color,
+            anchor: to_position(selection.anchor),
+            cursor: to_position(selection.cursor)
+        }).collect());
+    }
+}
@@ -8,14 +8,18 @@ use {
     std::{
         //env,
         io::{Read, Write},
-        net::{ TcpStream},
-        sync::mpsc::{self, Receiver, Sender, TryRecvError},
+        net::{TcpListener, TcpStream},
+        sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError},
         thread,
+        time::Duration,
         path::Path,
         //path::PathBuf
     },
 };
 
+// how long to wait before trying to reach a remote server again
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
 #[derive(Default)]
 pub struct FileClient {
 //    bind: Option<String>,
@@ -32,7 +36,13 @@ pub struct FileClientInner {
 impl FileClient {
     pub fn init(&mut self, _cx:&mut Cx, path:&Path){
         if self.inner.is_none() {
-            self.inner = Some(FileClientInner::new_with_local_server(path))
+            // --collab-connect=host:port edits the files of another studio,
+            // --collab-listen=host:port lets others edit ours
+            let arg = |prefix: &str| std::env::args().find_map(|arg| arg.strip_prefix(prefix).map(|value| value.to_string()));
+            self.inner = Some(match arg("--collab-connect="){
+                Some(address)=>FileClientInner::new_connect_remote(&address),
+                None=>FileClientInner::new_with_local_server(path, arg("--collab-listen=").as_deref())
+            })
         }
     }
     
@@ -60,7 +70,7 @@ impl FileClient {
 }
 
 impl FileClientInner {
-    pub fn new_with_local_server(path:&Path) -> Self {
+    pub fn new_with_local_server(path:&Path, listen: Option<&str>) -> Self {
         let (request_sender, request_receiver) = mpsc::channel();
         let message_signal = SignalToUI::new();
         let (message_sender, message_receiver) = mpsc::channel();
@@ -90,7 +100,12 @@ impl FileClientInner {
             message_signal.clone(),
             message_sender,
         );
-        //spawn_connection_listener(TcpListener::bind("127.0.0.1:0").unwrap(), server);
+        if let Some(address) = listen{
+            match TcpListener::bind(address){
+                Ok(listener)=>spawn_connection_listener(listener, server),
+                Err(err)=>error!("Cannot listen for collaborators on {}: {}", address, err)
+            }
+        }
         
         Self {
             request_sender,
@@ -104,9 +119,7 @@ impl FileClientInner {
         let message_signal = SignalToUI::new();
         let (message_sender, message_receiver) = mpsc::channel();
         
-        let stream = TcpStream::connect(to_server).unwrap();
-        spawn_request_sender(request_receiver, stream.try_clone().unwrap());
-        spawn_response_or_notification_receiver(stream, message_signal.clone(), message_sender,);
+        spawn_remote_connection(to_server.to_string(), request_receiver, message_signal.clone(), message_sender);
         
         Self {
             request_sender,
@@ -116,72 +129,125 @@ impl FileClientInner {
     }
     
 }
-/*
-fn _spawn_connection_listener(listener: TcpListener, mut server: FileServer) {
+
+fn spawn_connection_listener(listener: TcpListener, mut server: FileServer) {
     thread::spawn(move || {
-        log!("Server listening on {}", listener.local_addr().unwrap());
+        log!("File server listening on {}", listener.local_addr().unwrap());
         for stream in listener.incoming() {
-            let stream = stream.unwrap();
-            log!("Incoming connection from {}", stream.peer_addr().unwrap());
-            let (action_sender, action_receiver) = mpsc::channel();
-            let _connection = server.connect(Box::new({
-                let action_sender = action_sender.clone();
+            let Ok(stream) = stream else{
+                continue
+            };
+            log!("Incoming connection from {:?}", stream.peer_addr());
+            let (message_sender, message_receiver) = mpsc::channel();
+            let connection = server.connect(Box::new({
+                let message_sender = message_sender.clone();
                 move | notification | {
-                    action_sender.send(FileClientAction::Notification(notification)).unwrap();
+                    let _ = message_sender.send(FileClientMessage::Notification(notification));
                 }
             }));
-            spawn_remote_request_handler(
-                connection,
-                stream.try_clone().unwrap(),
-                action_sender,
-            );
-            spawn_response_or_notification_sender(action_receiver, stream);
+            let Ok(read_stream) = stream.try_clone() else{
+                continue
+            };
+            spawn_remote_request_handler(connection, read_stream, message_sender);
+            spawn_response_or_notification_sender(message_receiver, stream);
         }
     });
-}*/
+}
 
-fn _spawn_remote_request_handler(
+fn write_message(stream: &mut TcpStream, message: &impl SerBin) -> std::io::Result<()> {
+    let mut message_bytes = Vec::new();
+    message.ser_bin(&mut message_bytes);
+    stream.write_all(&(message_bytes.len() as u32).to_be_bytes())?;
+    stream.write_all(&message_bytes)
+}
+
+fn read_message<T: DeBin>(stream: &mut TcpStream) -> Option<T> {
+    let mut len_bytes = [0; 4];
+    stream.read_exact(&mut len_bytes).ok()?;
+    let len = u32::from_be_bytes(len_bytes);
+    let mut message_bytes = vec![0; len as usize];
+    stream.read_exact(&mut message_bytes).ok()?;
+    DeBin::deserialize_bin(message_bytes.as_slice()).ok()
+}
+
+// the connection ends when the other side goes away, which drops it from the server
+fn spawn_remote_request_handler(
     connection: FileServerConnection,
     mut stream: TcpStream,
     message_sender: Sender<FileClientMessage>,
 ) {
-    thread::spawn(move || loop {
-        let mut len_bytes = [0; 4];
-        stream.read_exact(&mut len_bytes).unwrap();
-        let len = u32::from_be_bytes(len_bytes);
-        let mut request_bytes = vec![0; len as usize];
-        stream.read_exact(&mut request_bytes).unwrap();
-        
-        let request = DeBin::deserialize_bin(request_bytes.as_slice()).unwrap();
-        let response = connection.handle_request(request);
-        message_sender.send(FileClientMessage::Response(response)).unwrap();
+    thread::spawn(move || {
+        while let Some(request) = read_message(&mut stream) {
+            let response = connection.handle_request(request);
+            if message_sender.send(FileClientMessage::Response(response)).is_err(){
+                break
+            }
+        }
     });
 }
 
-fn _spawn_response_or_notification_sender(
+fn spawn_response_or_notification_sender(
     message_receiver: Receiver<FileClientMessage>,
     mut stream: TcpStream,
 ) {
-    thread::spawn(move || loop {
-        let message = message_receiver.recv().unwrap();
-        let mut message_bytes = Vec::new();
-        
-        message.ser_bin(&mut message_bytes);
-        
-        let len_bytes = message_bytes.len().to_be_bytes();
-        stream.write_all(&len_bytes).unwrap();
-        stream.write_all(&message_bytes).unwrap();
+    thread::spawn(move || {
+        while let Ok(message) = message_receiver.recv() {
+            if write_message(&mut stream, &message).is_err(){
+                break
+            }
+        }
     });
 }
 
-fn spawn_request_sender(request_receiver: Receiver<FileRequest>, mut stream: TcpStream) {
-    thread::spawn(move || loop {
-        let request = request_receiver.recv().unwrap();
-        let mut request_bytes = Vec::new();
-        request.ser_bin(&mut request_bytes);
-        let len_bytes = request_bytes.len().to_be_bytes();
-        stream.write_all(&len_bytes).unwrap();
-        stream.write_all(&request_bytes).unwrap();
+// Keeps a connection to a remote server up. Requests made while there is no connection are
+// dropped, and every time the connection comes back the client is told with `Reconnected`
+fn spawn_remote_connection(
+    to_server: String,
+    request_receiver: Receiver<FileRequest>,
+    message_signal: SignalToUI,
+    message_sender: Sender<FileClientMessage>,
+) {
+    thread::spawn(move || {
+        let mut was_connected = false;
+        loop {
+            let stream = match TcpStream::connect(&to_server){
+                Ok(stream)=>stream,
+                Err(_)=>{
+                    loop{
+                        match request_receiver.try_recv(){
+                            Ok(_)=>(),
+                            Err(TryRecvError::Empty)=>break,
+                            Err(TryRecvError::Disconnected)=>return
+                        }
+                    }
+                    thread::sleep(RECONNECT_DELAY);
+                    continue
+                }
+            };
+            if was_connected{
+                log!("Connection to {} is back", to_server);
+                let _ = message_sender.send(FileClientMessage::Reconnected);
+                message_signal.set();
+            }
+            was_connected = true;
+            let Ok(read_stream) = stream.try_clone() else{
+                continue
+            };
+            let receiver = spawn_response_or_notification_receiver(read_stream, message_signal.clone(), message_sender.clone());
+            let mut stream = stream;
+            // the receiver ending is how we notice the server went away while we have nothing to send
+            while !receiver.is_finished(){
+                match request_receiver.recv_timeout(RECONNECT_DELAY){
+                    Ok(request)=>if write_message(&mut stream, &request).is_err(){
+                        break
+                    }
+                    Err(RecvTimeoutError::Timeout)=>(),
+                    Err(RecvTimeoutError::Disconnected)=>return
+                }
+            }
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+            log!("Lost the connection to {}", to_server);
+        }
     });
 }
 
@@ -189,18 +255,15 @@ fn spawn_response_or_notification_receiver(
     mut stream: TcpStream,
     message_signal: SignalToUI,
     message_sender: Sender<FileClientMessage>,
-) {
-    thread::spawn(move || loop {
-        let mut len_bytes = [0; 4];
-        stream.read_exact(&mut len_bytes).unwrap();
-        
-        let len = u32::from_be_bytes(len_bytes);
-        let mut action_bytes = vec![0; len as usize];
-        stream.read_exact(&mut action_bytes).unwrap();
-        let action = DeBin::deserialize_bin(action_bytes.as_slice()).unwrap();
-        message_sender.send(action).unwrap();
-        message_signal.set()
-    });
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        while let Some(message) = read_message(&mut stream) {
+            if message_sender.send(message).is_err(){
+                break
+            }
+            message_signal.set()
+        }
+    })
 }
 
 fn spawn_local_request_handler(
@@ -215,4 +278,4 @@ fn spawn_local_request_handler(
         action_sender.send(FileClientMessage::Response(response)).unwrap();
         action_signal.set()
     });
-}
\ No newline at end of file
+}
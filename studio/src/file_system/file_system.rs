@@ -2,11 +2,11 @@ use {
     std::collections::{HashMap, hash_map},
     std::path::Path,
     crate::{
-        makepad_code_editor::{CodeDocument, decoration::{Decoration, DecorationSet}, CodeSession},
+        makepad_code_editor::{CodeDocument, decoration::{Decoration, DecorationSet}, CodeSession, Language, SearchQuery},
         makepad_platform::makepad_live_compiler::LiveFileChange,
         makepad_widgets::*,
         makepad_widgets::file_tree::*,
-        file_system::FileClient,
+        file_system::{FileClient, collab::CollabClient},
         ai_chat::ai_chat_manager::AiChatDocument,
         makepad_file_protocol::{
             FileRequest,
@@ -17,7 +17,10 @@ use {
             FileNodeData,
             FileTreeData,
             SaveKind,
-            SaveFileResponse
+            SaveFileResponse,
+            FileSearchQuery,
+            FileSearchResult,
+            FileDelta,
         },
     },
 };
@@ -30,7 +33,14 @@ pub struct FileSystem {
     pub path_to_file_node_id: HashMap<String, LiveId>,
     pub tab_id_to_file_node_id: HashMap<LiveId, LiveId>,
     pub tab_id_to_session: HashMap<LiveId, EditSession>,
-    pub open_documents: HashMap<LiveId, OpenDocument>
+    pub open_documents: HashMap<LiveId, OpenDocument>,
+    // replacements waiting for their file to be loaded
+    pub pending_replaces: HashMap<LiveId, (SearchQuery, String)>,
+    // code documents take part in the collaboration session of their file on the server
+    pub collab: CollabClient,
+    // what is on disk for files that changed there while they had edits that weren't, until
+    // the user keeps their edits or reloads
+    pub conflicts: HashMap<LiveId, String>,
 }
 
 pub enum EditSession {
@@ -68,9 +78,18 @@ pub struct FileEdge {
 #[derive(DefaultNone, Debug, Clone)]
 pub enum FileSystemAction {
     TreeLoaded,
+    // the tree was loaded again after files were created, removed or renamed
+    TreeChanged,
     RecompileNeeded,
     LiveReloadNeeded(LiveFileChange),
     FileChangedOnDisk(SaveFileResponse),
+    SearchResults{id: u64, results: Vec<FileSearchResult>},
+    SearchDone{id: u64, file_count: usize, match_count: usize, truncated: bool},
+    SearchFailed(String),
+    // a code document finished loading
+    DocumentOpened(LiveId),
+    // a code document was changed by someone else, or on disk
+    DocumentChanged(LiveId),
     None
 }
 
@@ -90,6 +109,13 @@ impl FileSystem {
         }
     }
     
+    // picks the tokenizer of a code editor
+    pub fn get_language_from_path(path:&str)->Language{
+        Path::new(path).extension()
+            .and_then(|ext| ext.to_str())
+            .map_or(Language::PlainText, Language::from_extension)
+    }
+    
     pub fn get_tab_after_from_path(path:&str)->LiveId{
         match Self::get_editor_template_from_path(path){
             live_id!(AiChat)=>live_id!(ai_first),
@@ -119,6 +145,7 @@ impl FileSystem {
     pub fn remove_tab(&mut self, tab_id: LiveId) {
         self.tab_id_to_file_node_id.remove(&tab_id);
         self.tab_id_to_session.remove(&tab_id);
+        self.collab.forget_tab(tab_id);
     }
     
     pub fn path_to_file_node_id(&self, path: &str) -> Option<LiveId> {
@@ -176,8 +203,9 @@ impl FileSystem {
                 match message {
                     FileClientMessage::Response(response) => match response {
                         FileResponse::LoadFileTree(response) => {
+                            let first_load = self.file_nodes.is_empty();
                             self.load_file_tree(response.unwrap());
-                            cx.action(FileSystemAction::TreeLoaded)
+                            cx.action(if first_load{FileSystemAction::TreeLoaded}else{FileSystemAction::TreeChanged})
                             // dock.select_tab(cx, dock, state, live_id!(file_tree).into(), live_id!(file_tree).into(), Animate::No);
                         }
                         FileResponse::OpenFile(result) => {
@@ -193,7 +221,23 @@ impl FileSystem {
                                     match self.open_documents.get(&file_id){
                                         Some(OpenDocument::CodeLoading(dec))=>{
                                             let dec = dec.clone();
-                                            self.open_documents.insert(file_id, OpenDocument::Code(CodeDocument::new(response.data.into(), dec)));
+                                            let language = Self::get_language_from_path(&response.path);
+                                            let document = CodeDocument::new_with_language(response.data.into(), dec, language);
+                                            // edits are recorded from here, the replaces below included
+                                            let file = self.collab.files.entry(file_id).or_default();
+                                            let in_sync = file.opened(&document, response.revision);
+                                            file.take_changed();
+                                            if !in_sync{
+                                                self.rejoin_file(file_id, 0);
+                                            }
+                                            let replaced = self.pending_replaces.remove(&file_id).map_or(false, |(query, replace)|{
+                                                Self::replace_in_document(&document, &query, &replace) > 0
+                                            });
+                                            self.open_documents.insert(file_id, OpenDocument::Code(document));
+                                            if replaced{
+                                                self.request_save_file_for_file_node_id(file_id, true);
+                                            }
+                                            cx.action(FileSystemAction::DocumentOpened(file_id));
                                         }
                                         Some(OpenDocument::Code(_))=>{
                                         }
@@ -207,7 +251,7 @@ impl FileSystem {
                                     
                                     dock.redraw(cx);
                                 }
-                                Err(FileError::CannotOpen(_unix_path)) => {
+                                Err(FileError::CannotOpen(_unix_path)) | Err(FileError::OutOfSync(_unix_path)) => {
                                 }
                                 Err(FileError::Unknown(err)) => {
                                     log!("File error unknown {}", err);
@@ -224,27 +268,248 @@ impl FileSystem {
                             // to see if we need a recompile
                             
                         }
+                        FileResponse::Search(result) => {
+                            if let Err(FileError::Unknown(err) | FileError::CannotOpen(err) | FileError::OutOfSync(err)) = result{
+                                cx.action(FileSystemAction::SearchFailed(err));
+                            }
+                        }
+                        // our deltas are acknowledged by their notifications
+                        FileResponse::ApplyDelta(Ok(_)) | FileResponse::SetSelections(Ok(_)) => {}
+                        FileResponse::ApplyDelta(Err(err)) | FileResponse::SetSelections(Err(err)) | FileResponse::RejoinFile(Err(err)) => {
+                            match err{
+                                FileError::OutOfSync(path) => if let Some(file_id) = self.path_to_file_node_id(&path){
+                                    self.rejoin_file(file_id, 0);
+                                }
+                                err => log!("Collab error {:?}", err)
+                            }
+                        }
+                        FileResponse::RejoinFile(Ok(response)) => {
+                            let file_id = LiveId(response.id);
+                            if let (Some(file), Some(OpenDocument::Code(document))) = (self.collab.files.get_mut(&file_id), self.open_documents.get(&file_id)){
+                                if file.rejoined(document, response){
+                                    if file.take_changed(){
+                                        self.document_changed(cx, ui, file_id);
+                                    }
+                                }
+                                else{
+                                    self.rejoin_file(file_id, 0);
+                                }
+                            }
+                        }
                     },
                     FileClientMessage::Notification(notification) => {
                         match notification{
                             FileNotification::FileChangedOnDisk(response)=>{
-                               //println!("FILE CHANGED ON DISK {}", response.path);
-                                if let Some(file_id) = self.path_to_file_node_id.get(&response.path){
-                                    
+                                let file_id = self.path_to_file_node_id(&response.path);
+                                let is_open = file_id.is_some_and(|file_id| self.open_documents.contains_key(&file_id));
+                                if response.kind == SaveKind::Conflict{
+                                    // the edits stay, the user picks between them and the disk
+                                    if let (Some(file_id), true) = (file_id, is_open){
+                                        self.conflicts.insert(file_id, response.new_data.clone());
+                                        self.redraw_view_by_file_id(cx, file_id, &ui.dock(id!(dock)));
+                                    }
+                                }
+                                else if is_open{
+                                    let file_id = file_id.unwrap();
+                                    // documents in a collaboration session get the change as a delta
                                     if let Some(OpenDocument::Code(doc)) = self.open_documents.get_mut(&file_id){
-                                        doc.replace(response.new_data.clone().into());
+                                        if !self.collab.files.contains_key(&file_id){
+                                            doc.replace(response.new_data.clone().into());
+                                        }
                                     }
+                                    self.conflicts.remove(&file_id);
                                     ui.redraw(cx);
+                                    self.process_save_response(cx, response.clone());
+                                }
+                                else if Self::get_language_from_path(&response.path) == Language::Rust{
+                                    // there is nothing to compare a file nobody has open with
+                                    cx.action(FileSystemAction::RecompileNeeded);
                                 }
-                                self.process_save_response(cx, response.clone());
                                 // alright now what.
                                 // we should chuck this into the load comparison
                                 cx.action( FileSystemAction::FileChangedOnDisk(response));
                             }
+                            FileNotification::FileTreeChanged{renamed}=>{
+                                self.rename_files(cx, renamed);
+                                self.reload_file_tree();
+                            }
+                            FileNotification::SearchResults{id, results}=>{
+                                cx.action(FileSystemAction::SearchResults{id, results});
+                            }
+                            FileNotification::SearchDone{id, file_count, match_count, truncated}=>{
+                                cx.action(FileSystemAction::SearchDone{id, file_count, match_count, truncated});
+                            }
+                            FileNotification::DeltaWasApplied(delta)=>{
+                                self.receive_delta(cx, ui, delta);
+                            }
+                            FileNotification::SelectionsChanged{path, participant, selections}=>{
+                                if let Some(file_id) = self.path_to_file_node_id(&path){
+                                    if let Some(OpenDocument::Code(document)) = self.open_documents.get(&file_id){
+                                        CollabClient::set_remote_selections(document, participant, selections);
+                                        self.redraw_view_by_file_id(cx, file_id, &ui.dock(id!(dock)));
+                                    }
+                                }
+                            }
                         }
                         //self.editors.handle_collab_notification(cx, &mut state.editor_state, notification)
                     }
+                    FileClientMessage::Reconnected => {
+                        // what happened while we were away comes with joining the files again
+                        self.reload_file_tree();
+                        self.collab.forget_selections();
+                        let file_ids: Vec<LiveId> = self.collab.files.keys().cloned().collect();
+                        for file_id in file_ids{
+                            match self.open_documents.get(&file_id){
+                                Some(OpenDocument::Code(_))=>{
+                                    let revision = self.collab.revision(file_id).unwrap_or(0);
+                                    self.rejoin_file(file_id, revision);
+                                }
+                                _=>{
+                                    let path = self.file_node_path(file_id);
+                                    self.file_client.send_request(FileRequest::OpenFile{path, id: file_id.0});
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        self.sync_collab_files();
+    }
+    
+    // moves the open code documents of files that were renamed on disk to their new paths, the
+    // file tree catches up when it is loaded again
+    fn rename_files(&mut self, cx: &mut Cx, renamed: Vec<(String, String)>){
+        for (from, to) in renamed{
+            let prefix = format!("{}/", from);
+            let moved: Vec<(LiveId, String)> = self.open_documents.iter().filter_map(|(file_id, document)|{
+                let OpenDocument::Code(_) = document else{
+                    return None
+                };
+                let path = self.file_node_id_to_path(*file_id)?;
+                (path == from || path.starts_with(&prefix)).then(|| (*file_id, format!("{}{}", to, &path[from.len()..])))
+            }).collect();
+            for (old_id, path) in moved{
+                let new_id = LiveId::from_str(&path);
+                self.insert_detached_node(new_id, &path);
+                if let Some(document) = self.open_documents.remove(&old_id){
+                    self.open_documents.insert(new_id, document);
+                }
+                if let Some(file) = self.collab.files.remove(&old_id){
+                    self.collab.files.insert(new_id, file);
+                }
+                if let Some(disk_text) = self.conflicts.remove(&old_id){
+                    self.conflicts.insert(new_id, disk_text);
+                }
+                for file_id in self.tab_id_to_file_node_id.values_mut(){
+                    if *file_id == old_id{
+                        *file_id = new_id;
+                    }
                 }
+                cx.action(FileSystemAction::DocumentOpened(new_id));
+            }
+        }
+    }
+    
+    // a file node outside of the tree, for open files the tree doesn't have (anymore)
+    fn insert_detached_node(&mut self, file_id: LiveId, path: &str){
+        self.file_nodes.insert(file_id, FileNode{
+            parent_edge: Some(FileEdge{name: path.to_string(), file_node_id: live_id!(root)}),
+            name: path.rsplit('/').next().unwrap_or(path).to_string(),
+            child_edges: None,
+        });
+        self.path_to_file_node_id.insert(path.to_string(), file_id);
+    }
+    
+    // keep: the edits are saved over what is on disk. Reload: what is on disk replaces them, for
+    // everyone editing the file
+    pub fn resolve_conflict(&mut self, file_id: LiveId, keep: bool){
+        let Some(disk_text) = self.conflicts.remove(&file_id) else{
+            return
+        };
+        if keep{
+            self.request_save_file_for_file_node_id(file_id, false);
+            return
+        }
+        let path = self.file_node_path(file_id);
+        match self.open_documents.get(&file_id){
+            // the server makes the text the disk's and sends it back as a delta
+            Some(OpenDocument::Code(document)) if self.collab.files.contains_key(&file_id)=>{
+                self.collab.sync(&mut self.file_client, document, file_id, &path);
+                self.file_client.send_request(FileRequest::SaveFile{
+                    path,
+                    data: disk_text,
+                    id: file_id.0,
+                    patch: false,
+                    revision: None
+                });
+            }
+            Some(OpenDocument::Code(document))=>{
+                document.replace(disk_text.into());
+                self.request_save_file_for_file_node_id(file_id, false);
+            }
+            _=>()
+        }
+    }
+    
+    fn rejoin_file(&mut self, file_id: LiveId, revision: u64){
+        let Some(file) = self.collab.files.get_mut(&file_id) else{
+            return
+        };
+        file.leave();
+        let path = self.file_node_path(file_id);
+        self.file_client.send_request(FileRequest::RejoinFile{path, id: file_id.0, revision});
+    }
+    
+    fn receive_delta(&mut self, cx: &mut Cx, ui: &WidgetRef, delta: FileDelta){
+        let Some(file_id) = self.path_to_file_node_id(&delta.path) else{
+            return
+        };
+        let Some(file) = self.collab.files.get_mut(&file_id) else{
+            return
+        };
+        match self.open_documents.get(&file_id){
+            Some(OpenDocument::Code(document))=>{
+                if file.receive(document, delta){
+                    if file.take_changed(){
+                        self.document_changed(cx, ui, file_id);
+                    }
+                }
+                else{
+                    self.rejoin_file(file_id, 0);
+                }
+            }
+            // the document is still loading, the delta is kept until it knows its revision
+            _=>file.defer(delta)
+        }
+    }
+    
+    fn document_changed(&mut self, cx: &mut Cx, ui: &WidgetRef, file_id: LiveId){
+        self.handle_sessions();
+        self.redraw_view_by_file_id(cx, file_id, &ui.dock(id!(dock)));
+        cx.action(FileSystemAction::DocumentChanged(file_id));
+    }
+    
+    // sends what changed in the code documents since the last event: edits, saves that waited
+    // for them and where the cursors went
+    fn sync_collab_files(&mut self){
+        let file_ids: Vec<LiveId> = self.collab.files.keys().cloned().collect();
+        for file_id in file_ids{
+            let Some(OpenDocument::Code(document)) = self.open_documents.get(&file_id) else{
+                continue
+            };
+            let path = self.file_node_path(file_id);
+            if let Some(patch) = self.collab.sync(&mut self.file_client, document, file_id, &path){
+                self.send_save(file_id, patch, self.collab.revision(file_id));
+            }
+        }
+        for (tab_id, session) in &self.tab_id_to_session{
+            let (EditSession::Code(session), Some(file_id)) = (session, self.tab_id_to_file_node_id.get(tab_id)) else{
+                continue
+            };
+            if self.collab.is_joined(*file_id){
+                let path = self.file_node_path(*file_id);
+                self.collab.update_selections(&mut self.file_client, *tab_id, session, *file_id, &path);
             }
         }
     }
@@ -376,6 +641,7 @@ impl FileSystem {
         match template{
             live_id!(CodeEditor)=>{
                 self.open_documents.insert(file_id, OpenDocument::CodeLoading(dec));
+                self.collab.files.entry(file_id).or_default();
             }
             live_id!(AiChat)=>{
                 self.open_documents.insert(file_id, OpenDocument::AiChatLoading);
@@ -387,6 +653,38 @@ impl FileSystem {
         self.file_client.send_request(FileRequest::OpenFile{path, id: file_id.0});
     }
     
+    pub fn request_search(&mut self, query: FileSearchQuery, id: u64) {
+        self.file_client.send_request(FileRequest::Search{query, id});
+    }
+    
+    // replaces every match of the query in a file and saves it as a patch. Files that aren't
+    // loaded yet are opened in the background first
+    pub fn replace_in_file(&mut self, file_id: LiveId, query: &SearchQuery, replace: &str) {
+        if self.get_editor_template_from_file_id(file_id) != Some(live_id!(CodeEditor)){
+            return
+        }
+        match self.open_documents.get(&file_id){
+            Some(OpenDocument::Code(document))=>{
+                if Self::replace_in_document(document, query, replace) > 0{
+                    self.request_save_file_for_file_node_id(file_id, true);
+                }
+            }
+            _=>{
+                self.pending_replaces.insert(file_id, (query.clone(), replace.to_string()));
+                self.request_open_file(LiveId(0), file_id);
+            }
+        }
+    }
+    
+    fn replace_in_document(document: &CodeDocument, query: &SearchQuery, replace: &str)->usize{
+        // a throwaway session so the replace lands in the document's undo history
+        let session = CodeSession::new(document.clone());
+        if session.set_search_query(Some(query)).is_err(){
+            return 0
+        }
+        session.replace_all(replace)
+    }
+    
     pub fn request_save_file_for_tab_id(&mut self, tab_id: LiveId, was_patch:bool) {
         // ok lets see if we have a document
         // ifnot, we create a new one
@@ -430,13 +728,33 @@ impl FileSystem {
     }
     
     pub fn request_save_file_for_file_node_id(&mut self, file_id: LiveId, patch:bool) {
+        // nothing is written over a change on disk until the user picked what to keep
+        if self.conflicts.contains_key(&file_id){
+            return
+        }
+        // a code document in a collaboration session saves once the server has all its edits
+        let revision = match self.open_documents.get(&file_id){
+            Some(OpenDocument::Code(document)) if self.collab.files.contains_key(&file_id)=>{
+                let path = self.file_node_path(file_id);
+                match self.collab.save(&mut self.file_client, document, file_id, &path, patch){
+                    Some(revision)=>Some(revision),
+                    None=>return
+                }
+            }
+            _=>None
+        };
+        self.send_save(file_id, patch, revision);
+    }
+    
+    fn send_save(&mut self, file_id: LiveId, patch:bool, revision: Option<u64>) {
         if let Some(text) = self.file_id_as_string(file_id){
             let path = self.file_node_path(file_id);
             self.file_client.send_request(FileRequest::SaveFile{
                 path: path.clone(), 
                 data: text, 
                 id: file_id.0,
-                patch
+                patch,
+                revision
             });
         }
     }
@@ -463,6 +781,24 @@ impl FileSystem {
         }
     }
     
+    pub fn remove_decorations_with_id(&mut self, file_node_id: LiveId, id: usize) {
+        match self.open_documents.get_mut(&file_node_id) {
+            Some(OpenDocument::CodeLoading(dec)) => dec.remove_decorations_with_id(id),
+            Some(OpenDocument::Code(doc)) => doc.remove_decorations_with_id(id),
+            Some(_) | None=>()
+        };
+    }
+    
+    pub fn remove_all_decorations_with_id(&mut self, id: usize) {
+        for document in self.open_documents.values_mut() {
+            match document {
+                OpenDocument::CodeLoading(dec) => dec.remove_decorations_with_id(id),
+                OpenDocument::Code(doc) => doc.remove_decorations_with_id(id),
+                _=>()
+            }
+        }
+    }
+    
     pub fn redraw_view_by_file_id(&mut self, cx: &mut Cx, id: LiveId, dock: &DockRef) {
         for (tab_id, file_id) in &self.tab_id_to_file_node_id {
             if id == *file_id {
@@ -494,19 +830,20 @@ impl FileSystem {
         };
     }
     
-    pub fn draw_file_node(&self, cx: &mut Cx2d, file_node_id: LiveId, file_tree: &mut FileTree) {
+    // `tint` colors the name of a node, by its alpha
+    pub fn draw_file_node(&self, cx: &mut Cx2d, file_node_id: LiveId, file_tree: &mut FileTree, tint: &dyn Fn(LiveId)->Vec4) {
         if let Some(file_node) = self.file_nodes.get(&file_node_id) {
             match &file_node.child_edges {
                 Some(child_edges) => {
-                    if file_tree.begin_folder(cx, file_node_id, &file_node.name).is_ok() {
+                    if file_tree.begin_folder_tinted(cx, file_node_id, &file_node.name, tint(file_node_id)).is_ok() {
                         for child_edge in child_edges {
-                            self.draw_file_node(cx, child_edge.file_node_id, file_tree);
+                            self.draw_file_node(cx, child_edge.file_node_id, file_tree, tint);
                         }
                         file_tree.end_folder();
                     }
                 }
                 None => {
-                    file_tree.file(cx, file_node_id, &file_node.name);
+                    file_tree.file_tinted(cx, file_node_id, &file_node.name, tint(file_node_id));
                 }
             }
         }
@@ -659,10 +996,15 @@ impl FileSystem {
             file_node_id
         }
         
-        self.root_path = tree_data.root_path;
+        // open files that are gone from the tree keep a node, so they still have a path
+        let open_files: Vec<(LiveId, String)> = self.tab_id_to_file_node_id.values().chain(self.open_documents.keys())
+            .filter_map(|file_id| Some((*file_id, self.file_node_id_to_path(*file_id)?.to_string())))
+            .collect();
         
+        self.root_path = tree_data.root_path;
         
         self.file_nodes.clear();
+        self.path_to_file_node_id.clear();
         
         create_file_node(
             Some(live_id!(root).into()),
@@ -672,5 +1014,11 @@ impl FileSystem {
             None,
             tree_data.root,
         );
+        
+        for (file_id, path) in open_files{
+            if !self.file_nodes.contains_key(&file_id){
+                self.insert_detached_node(file_id, &path);
+            }
+        }
     }
 }
\ No newline at end of file
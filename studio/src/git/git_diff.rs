@@ -0,0 +1,272 @@
+use {
+    crate::makepad_code_editor::line_marker::{LineMarker, LineMarkerKind},
+};
+
+// a run of lines that differs between an old and a new text, either length can be 0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiffHunk{
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+}
+
+// past this many edits the diff stops looking for a shortest one and marks everything in between
+const MAX_EDITS: isize = 2000;
+
+// the hunks that turn `old` into `new`, in order
+pub fn diff_lines(old: &[&str], new: &[&str])->Vec<DiffHunk>{
+    let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+    let mut hunks = Vec::new();
+    if old_mid.is_empty() && new_mid.is_empty(){
+        return hunks
+    }
+    let Some(matches) = myers_matches(old_mid, new_mid) else{
+        hunks.push(DiffHunk{
+            old_start: prefix,
+            old_len: old_mid.len(),
+            new_start: prefix,
+            new_len: new_mid.len(),
+        });
+        return hunks
+    };
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for (old_match, new_match) in matches.into_iter().chain(std::iter::once((old_mid.len(), new_mid.len()))){
+        if old_match > old_pos || new_match > new_pos{
+            hunks.push(DiffHunk{
+                old_start: prefix + old_pos,
+                old_len: old_match - old_pos,
+                new_start: prefix + new_pos,
+                new_len: new_match - new_pos,
+            });
+        }
+        old_pos = old_match + 1;
+        new_pos = new_match + 1;
+    }
+    hunks
+}
+
+// the pairs of equal lines on a shortest edit path, found with Myers' algorithm. The frontier of
+// every round is kept to walk the path back, which is why the number of edits is capped
+fn myers_matches(old: &[&str], new: &[&str])->Option<Vec<(usize, usize)>>{
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let offset = n + m + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut end = None;
+    'rounds: for d in 0..=(n + m).min(MAX_EDITS){
+        for k in (-d..=d).step_by(2){
+            let mut x = if k == -d || (k != d && v[(offset + k - 1) as usize] < v[(offset + k + 1) as usize]){
+                v[(offset + k + 1) as usize]
+            }
+            else{
+                v[(offset + k - 1) as usize] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize]{
+                x += 1;
+                y += 1;
+            }
+            v[(offset + k) as usize] = x;
+            if x >= n && y >= m{
+                end = Some(d);
+            }
+        }
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        if end.is_some(){
+            break 'rounds
+        }
+    }
+    let end = end?;
+    let mut matches = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..=end).rev(){
+        let prev = &trace[d as usize - 1];
+        let get = |k: isize| prev[(k + d - 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && get(k - 1) < get(k + 1)){k + 1}else{k - 1};
+        let prev_x = get(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y{
+            x -= 1;
+            y -= 1;
+            matches.push((x as usize, y as usize));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    while x > 0 && y > 0{
+        x -= 1;
+        y -= 1;
+        matches.push((x as usize, y as usize));
+    }
+    matches.reverse();
+    Some(matches)
+}
+
+// the gutter markers for the new text
+pub fn line_markers(id: usize, hunks: &[DiffHunk])->Vec<LineMarker>{
+    hunks.iter().map(|hunk| LineMarker{
+        id,
+        kind: if hunk.new_len == 0{
+            LineMarkerKind::Deleted
+        }
+        else if hunk.old_len == 0{
+            LineMarkerKind::Added
+        }
+        else{
+            LineMarkerKind::Modified
+        },
+        start_line: hunk.new_start,
+        end_line: hunk.new_start + hunk.new_len,
+    }).collect()
+}
+
+// the line of the old text a line of the new text is unchanged from, None if it changed
+pub fn old_line(hunks: &[DiffHunk], new_line: usize)->Option<usize>{
+    let mut delta = 0isize;
+    for hunk in hunks{
+        if new_line < hunk.new_start{
+            break
+        }
+        if new_line < hunk.new_start + hunk.new_len{
+            return None
+        }
+        delta = (hunk.old_start + hunk.old_len) as isize - (hunk.new_start + hunk.new_len) as isize;
+    }
+    Some((new_line as isize + delta) as usize)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffLineKind{
+    Header,
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Clone, Debug)]
+pub struct DiffLine{
+    pub kind: DiffLineKind,
+    // the line in the new text, for jumping to it
+    pub new_line: usize,
+    pub text: String,
+}
+
+// the hunks as a unified diff, with `context` unchanged lines around them. Hunks that are
+// close together share one header
+pub fn unified_diff(old: &[&str], new: &[&str], hunks: &[DiffHunk], context: usize)->Vec<DiffLine>{
+    let mut lines = Vec::new();
+    let mut i = 0;
+    while i < hunks.len(){
+        let mut j = i;
+        while j + 1 < hunks.len() && hunks[j + 1].old_start - (hunks[j].old_start + hunks[j].old_len) <= 2 * context{
+            j += 1;
+        }
+        let (first, last) = (hunks[i], hunks[j]);
+        let old_start = first.old_start.saturating_sub(context);
+        let new_start = first.new_start - (first.old_start - old_start);
+        let old_end = (last.old_start + last.old_len + context).min(old.len());
+        let new_end = last.new_start + last.new_len + (old_end - last.old_start - last.old_len);
+        lines.push(DiffLine{
+            kind: DiffLineKind::Header,
+            new_line: new_start,
+            text: format!("@@ -{},{} +{},{} @@", old_start + 1, old_end - old_start, new_start + 1, new_end - new_start),
+        });
+        let (mut old_pos, mut new_pos) = (old_start, new_start);
+        for hunk in &hunks[i..=j]{
+            while old_pos < hunk.old_start{
+                lines.push(DiffLine{kind: DiffLineKind::Context, new_line: new_pos, text: old[old_pos].to_string()});
+                old_pos += 1;
+                new_pos += 1;
+            }
+            for line in &old[hunk.old_start..hunk.old_start + hunk.old_len]{
+                lines.push(DiffLine{kind: DiffLineKind::Removed, new_line: hunk.new_start, text: line.to_string()});
+            }
+            for (index, line) in new[hunk.new_start..hunk.new_start + hunk.new_len].iter().enumerate(){
+                lines.push(DiffLine{kind: DiffLineKind::Added, new_line: hunk.new_start + index, text: line.to_string()});
+            }
+            old_pos = hunk.old_start + hunk.old_len;
+            new_pos = hunk.new_start + hunk.new_len;
+        }
+        while old_pos < old_end{
+            lines.push(DiffLine{kind: DiffLineKind::Context, new_line: new_pos, text: old[old_pos].to_string()});
+            old_pos += 1;
+            new_pos += 1;
+        }
+        i = j + 1;
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn hunk(old_start: usize, old_len: usize, new_start: usize, new_len: usize)->DiffHunk{
+        DiffHunk{old_start, old_len, new_start, new_len}
+    }
+
+    #[test]
+    fn hunks(){
+        assert_eq!(diff_lines(&["a", "b"], &["a", "b"]), vec![]);
+        assert_eq!(diff_lines(&[], &["a"]), vec![hunk(0, 0, 0, 1)]);
+        assert_eq!(diff_lines(&["a", "b", "c"], &["a", "x", "c"]), vec![hunk(1, 1, 1, 1)]);
+        assert_eq!(diff_lines(&["a", "b", "c"], &["a", "c"]), vec![hunk(1, 1, 1, 0)]);
+        assert_eq!(diff_lines(&["a", "c"], &["a", "b", "c"]), vec![hunk(1, 0, 1, 1)]);
+        assert_eq!(
+            diff_lines(&["a", "b", "c", "d", "e"], &["x", "a", "c", "d", "y", "e"]),
+            vec![hunk(0, 0, 0, 1), hunk(1, 1, 2, 0), hunk(4, 0, 4, 1)]
+        );
+    }
+
+    #[test]
+    fn hunks_rebuild_the_new_text(){
+        let old: Vec<&str> = "fn main(){\n    let a = 1;\n    let b = 2;\n    a + b\n}\n// end".lines().collect();
+        let new: Vec<&str> = "// start\nfn main(){\n    let a = 1;\n    let c = 3;\n    a + c\n}".lines().collect();
+        let hunks = diff_lines(&old, &new);
+        let mut rebuilt = Vec::new();
+        let mut old_pos = 0;
+        for hunk in &hunks{
+            rebuilt.extend_from_slice(&old[old_pos..hunk.old_start]);
+            rebuilt.extend_from_slice(&new[hunk.new_start..hunk.new_start + hunk.new_len]);
+            old_pos = hunk.old_start + hunk.old_len;
+        }
+        rebuilt.extend_from_slice(&old[old_pos..]);
+        assert_eq!(rebuilt, new);
+    }
+
+    #[test]
+    fn markers_and_old_lines(){
+        let hunks = diff_lines(&["a", "b", "c", "d"], &["a", "x", "y", "c"]);
+        let kinds: Vec<(LineMarkerKind, usize, usize)> = line_markers(0, &hunks)
+            .into_iter()
+            .map(|marker| (marker.kind, marker.start_line, marker.end_line))
+            .collect();
+        assert_eq!(kinds, vec![(LineMarkerKind::Modified, 1, 3), (LineMarkerKind::Deleted, 4, 4)]);
+        assert_eq!(old_line(&hunks, 0), Some(0));
+        assert_eq!(old_line(&hunks, 1), None);
+        assert_eq!(old_line(&hunks, 3), Some(2));
+    }
+
+    #[test]
+    fn unified(){
+        let old = ["1", "2", "3", "4", "5", "6", "7", "8"];
+        let new = ["1", "2", "x", "4", "5", "6", "7", "8"];
+        let hunks = diff_lines(&old, &new);
+        let lines: Vec<(DiffLineKind, String)> = unified_diff(&old, &new, &hunks, 1)
+            .into_iter()
+            .map(|line| (line.kind, line.text))
+            .collect();
+        assert_eq!(lines, vec![
+            (DiffLineKind::Header, "@@ -2,3 +2,3 @@".to_string()),
+            (DiffLineKind::Context, "2".to_string()),
+            (DiffLineKind::Removed, "3".to_string()),
+            (DiffLineKind::Added, "x".to_string()),
+            (DiffLineKind::Context, "4".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,339 @@
+use {
+    crate::{
+        app::AppAction,
+        file_system::file_system::{FileSystem, OpenDocument},
+        git::{
+            git_diff::{self, DiffHunk, DiffLine, DiffLineKind},
+            git_repo::{GitBlameLine, GitFileStatus, GitRepo, GitStatusEntry, time_ago},
+        },
+        makepad_code_editor::{inlays::InlineInlay, CodeDocument},
+        makepad_widgets::*,
+    },
+    std::{
+        collections::{HashMap, HashSet},
+        path::Path,
+        thread,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+#[derive(DefaultNone, Debug, Clone)]
+pub enum GitAction{
+    // the status of files or the diff being shown changed
+    Changed,
+    None
+}
+
+enum GitResult{
+    Status{branch: String, head: Option<String>, entries: Result<Vec<GitStatusEntry>, String>},
+    HeadText{path: String, text: Option<String>},
+    Blame{path: String, lines: Vec<GitBlameLine>},
+}
+
+// the gutter markers from git, tests put theirs next to them
+pub const GIT_LINE_MARKER_ID: usize = 0;
+// the blame at the end of the cursor line, next to the hover text of the language server
+pub const GIT_INLAY_ID: usize = 0;
+
+// seconds of quiet after an edit before the status is asked for again
+const STATUS_DELAY: f64 = 1.0;
+const DIFF_CONTEXT: usize = 3;
+
+// Talks to git for the project: the status of files for the file tree, the HEAD version of open
+// files for the gutter markers and the diff view, and blame for the line the cursor is on. Git
+// runs on its own threads, results arrive as signals
+#[derive(Default)]
+pub struct GitManager{
+    repo: Option<GitRepo>,
+    receiver: ToUIReceiver<GitResult>,
+    status_timer: Timer,
+    head: Option<String>,
+    pub branch: String,
+    pub entries: Vec<GitStatusEntry>,
+    pub error: Option<String>,
+    // folders take the status of the files in them
+    node_status: HashMap<LiveId, GitFileStatus>,
+    // None when HEAD doesn't have the file
+    head_texts: HashMap<String, Option<String>>,
+    hunks: HashMap<String, Vec<DiffHunk>>,
+    blames: HashMap<String, Vec<GitBlameLine>>,
+    requested: HashSet<String>,
+    blame_cursor: Option<(LiveId, usize)>,
+    pub diff_path: Option<String>,
+    pub diff_lines: Vec<DiffLine>,
+    // when set the diff is against what is on disk instead of HEAD
+    disk_text: Option<String>,
+}
+
+impl GitManager{
+    pub fn init(&mut self, root_path: &Path){
+        self.repo = GitRepo::open(root_path);
+        self.refresh_status();
+    }
+
+    pub fn is_active(&self)->bool{
+        self.repo.is_some()
+    }
+
+    fn code_document(file_system: &FileSystem, file_id: LiveId)->Option<&CodeDocument>{
+        match file_system.open_documents.get(&file_id){
+            Some(OpenDocument::Code(document))=>Some(document),
+            _=>None
+        }
+    }
+
+    pub fn node_status(&self, file_id: LiveId)->Option<GitFileStatus>{
+        self.node_status.get(&file_id).cloned()
+    }
+
+    pub fn refresh_status(&mut self){
+        let Some(repo) = self.repo.clone() else{
+            return
+        };
+        let sender = self.receiver.sender();
+        thread::spawn(move ||{
+            let _ = sender.send(GitResult::Status{
+                branch: repo.branch().unwrap_or_default(),
+                head: repo.head(),
+                entries: repo.status(),
+            });
+        });
+    }
+
+    pub fn schedule_status_refresh(&mut self, cx: &mut Cx){
+        if self.repo.is_some(){
+            cx.stop_timer(self.status_timer);
+            self.status_timer = cx.start_timeout(STATUS_DELAY);
+        }
+    }
+
+    fn request_head_text(&mut self, path: &str){
+        let Some(repo) = self.repo.clone() else{
+            return
+        };
+        if self.head_texts.contains_key(path) || !self.requested.insert(format!("head:{}", path)){
+            return
+        }
+        let sender = self.receiver.sender();
+        let path = path.to_string();
+        thread::spawn(move ||{
+            let text = repo.head_text(&path);
+            let _ = sender.send(GitResult::HeadText{path, text});
+        });
+    }
+
+    fn request_blame(&mut self, path: &str){
+        let Some(repo) = self.repo.clone() else{
+            return
+        };
+        if !self.requested.insert(format!("blame:{}", path)){
+            return
+        }
+        let sender = self.receiver.sender();
+        let path = path.to_string();
+        thread::spawn(move ||{
+            let lines = repo.blame(&path).unwrap_or_default();
+            let _ = sender.send(GitResult::Blame{path, lines});
+        });
+    }
+
+    pub fn did_open(&mut self, file_system: &FileSystem, file_id: LiveId){
+        if let Some(path) = file_system.file_node_id_to_path(file_id){
+            self.request_head_text(path);
+        }
+        self.update_markers(file_system, file_id);
+    }
+
+    pub fn did_change(&mut self, cx: &mut Cx, file_system: &mut FileSystem, file_id: LiveId){
+        self.update_markers(file_system, file_id);
+        self.schedule_status_refresh(cx);
+        if let Some((blame_file, line)) = self.blame_cursor{
+            if blame_file == file_id{
+                self.show_blame(cx, file_system, file_id, line);
+            }
+        }
+    }
+
+    // diffs the open document against HEAD and puts the result in the gutter
+    fn update_markers(&mut self, file_system: &FileSystem, file_id: LiveId){
+        let (Some(path), Some(document)) = (file_system.file_node_id_to_path(file_id), Self::code_document(file_system, file_id)) else{
+            return
+        };
+        let Some(head_text) = self.head_texts.get(path) else{
+            return
+        };
+        let text = document.as_text();
+        let new: Vec<&str> = text.as_lines().iter().map(|line| line.as_str()).collect();
+        let hunks = match head_text{
+            // split like the document does, so a final newline is an empty last line on both sides
+            Some(head_text)=>git_diff::diff_lines(&head_text.split('\n').collect::<Vec<_>>(), &new),
+            None=>vec![DiffHunk{old_start: 0, old_len: 0, new_start: 0, new_len: new.len()}]
+        };
+        document.set_line_markers(GIT_LINE_MARKER_ID, git_diff::line_markers(GIT_LINE_MARKER_ID, &hunks));
+        self.hunks.insert(path.to_string(), hunks);
+        if self.diff_path.as_deref() == Some(path){
+            self.update_diff(file_system);
+        }
+    }
+
+    // shows who last changed a line at its end, replacing what was shown for the line before
+    pub fn show_blame(&mut self, cx: &mut Cx, file_system: &mut FileSystem, file_id: LiveId, line: usize){
+        if self.repo.is_none(){
+            return
+        }
+        if let Some((blame_file, _)) = self.blame_cursor.take(){
+            if let Some(document) = Self::code_document(file_system, blame_file){
+                document.clear_inline_inlays(GIT_INLAY_ID);
+            }
+        }
+        let (Some(path), Some(document)) = (file_system.file_node_id_to_path(file_id), Self::code_document(file_system, file_id)) else{
+            return
+        };
+        let path = path.to_string();
+        self.blame_cursor = Some((file_id, line));
+        let text = match (self.head_texts.get(&path), self.hunks.get(&path)){
+            (Some(None), _)=>Some("Not committed yet".to_string()),
+            (Some(Some(_)), Some(hunks))=>match git_diff::old_line(hunks, line){
+                None=>Some("Uncommitted changes".to_string()),
+                Some(old_line)=>match self.blames.get(&path){
+                    Some(blames)=>blames.get(old_line).filter(|blame| !blame.commit.is_empty()).map(|blame|{
+                        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_secs() as i64);
+                        format!("{}, {} - {}", blame.author, time_ago(blame.time, now), blame.summary)
+                    }),
+                    None=>{
+                        self.request_blame(&path);
+                        None
+                    }
+                }
+            }
+            _=>None
+        };
+        if let Some(text) = text{
+            let line_len = document.as_text().as_lines().get(line).map_or(0, |line| line.len());
+            document.set_inline_inlays(GIT_INLAY_ID, line, vec![(line_len, InlineInlay::Text(format!("    {}", text)))]);
+        }
+        file_system.handle_sessions();
+        cx.action(AppAction::RedrawFile(file_id));
+    }
+
+    // shows the changes to a file in the diff list
+    pub fn show_diff(&mut self, file_system: &FileSystem, path: &str){
+        self.diff_path = Some(path.to_string());
+        self.disk_text = None;
+        self.request_head_text(path);
+        self.update_diff(file_system);
+    }
+
+    // shows how an open file differs from what someone else wrote to disk
+    pub fn show_disk_diff(&mut self, file_system: &FileSystem, path: &str, disk_text: &str){
+        self.diff_path = Some(path.to_string());
+        self.disk_text = Some(disk_text.to_string());
+        self.update_diff(file_system);
+    }
+
+    fn update_diff(&mut self, file_system: &FileSystem){
+        self.diff_lines.clear();
+        let Some(path) = self.diff_path.clone() else{
+            return
+        };
+        let old_text = match &self.disk_text{
+            Some(disk_text)=>Some(disk_text.clone()),
+            None=>{
+                let (Some(_), Some(head_text)) = (&self.repo, self.head_texts.get(&path)) else{
+                    return
+                };
+                head_text.clone()
+            }
+        };
+        // open files are diffed as they are in the editor, others as they are on disk
+        let text = match file_system.path_to_file_node_id(&path).and_then(|file_id| Self::code_document(file_system, file_id)){
+            Some(document)=>document.as_text().to_string(),
+            None=>self.repo.as_ref().map_or(String::new(), |repo| std::fs::read_to_string(repo.dir().join(&path)).unwrap_or_default())
+        };
+        let old: Vec<&str> = old_text.as_deref().map_or(Vec::new(), |text| text.lines().collect());
+        let new: Vec<&str> = text.lines().collect();
+        let hunks = git_diff::diff_lines(&old, &new);
+        self.diff_lines.push(DiffLine{kind: DiffLineKind::Header, new_line: 0, text: path.clone()});
+        self.diff_lines.extend(git_diff::unified_diff(&old, &new, &hunks, DIFF_CONTEXT));
+    }
+
+    fn update_node_status(&mut self){
+        self.node_status.clear();
+        for entry in &self.entries{
+            self.node_status.insert(LiveId::from_str(&entry.path), entry.status);
+            let mut folder = entry.path.as_str();
+            while let Some((parent, _)) = folder.rsplit_once('/'){
+                self.node_status.entry(LiveId::from_str(parent)).or_insert(GitFileStatus::Modified);
+                folder = parent;
+            }
+        }
+    }
+
+    pub fn handle_event(&mut self, cx: &mut Cx, event: &Event, file_system: &mut FileSystem){
+        if self.status_timer.is_event(event).is_some(){
+            self.refresh_status();
+        }
+        if let Event::Signal = event{
+            while let Ok(result) = self.receiver.try_recv(){
+                self.handle_result(cx, file_system, result);
+            }
+        }
+    }
+
+    fn handle_result(&mut self, cx: &mut Cx, file_system: &mut FileSystem, result: GitResult){
+        match result{
+            GitResult::Status{branch, head, entries}=>{
+                // a new commit makes everything we know about HEAD stale
+                if head != self.head{
+                    self.head = head;
+                    self.head_texts.clear();
+                    self.blames.clear();
+                    self.requested.clear();
+                    let paths: Vec<String> = file_system.open_documents.keys()
+                        .filter_map(|file_id| file_system.file_node_id_to_path(*file_id))
+                        .map(|path| path.to_string())
+                        .chain(self.diff_path.clone())
+                        .collect();
+                    for path in paths{
+                        self.request_head_text(&path);
+                    }
+                }
+                self.branch = branch;
+                match entries{
+                    Ok(entries)=>{
+                        self.entries = entries;
+                        self.error = None;
+                    }
+                    Err(err)=>self.error = Some(err)
+                }
+                self.update_node_status();
+                cx.action(GitAction::Changed);
+            }
+            GitResult::HeadText{path, text}=>{
+                self.requested.remove(&format!("head:{}", path));
+                self.head_texts.insert(path.clone(), text);
+                if let Some(file_id) = file_system.path_to_file_node_id(&path){
+                    self.update_markers(file_system, file_id);
+                    cx.action(AppAction::RedrawFile(file_id));
+                    if let Some((blame_file, line)) = self.blame_cursor{
+                        if blame_file == file_id{
+                            self.show_blame(cx, file_system, file_id, line);
+                        }
+                    }
+                }
+                if self.diff_path.as_ref() == Some(&path){
+                    self.update_diff(file_system);
+                    cx.action(GitAction::Changed);
+                }
+            }
+            GitResult::Blame{path, lines}=>{
+                self.blames.insert(path.clone(), lines);
+                if let Some((file_id, line)) = self.blame_cursor{
+                    if file_system.file_node_id_to_path(file_id) == Some(path.as_str()){
+                        self.show_blame(cx, file_system, file_id, line);
+                    }
+                }
+            }
+        }
+    }
+}
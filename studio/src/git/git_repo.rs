@@ -0,0 +1,300 @@
+use {
+    crate::makepad_shell::*,
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GitFileStatus{
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+impl GitFileStatus{
+    pub fn letter(&self)->&'static str{
+        match self{
+            Self::Modified=>"M",
+            Self::Added=>"A",
+            Self::Deleted=>"D",
+            Self::Renamed=>"R",
+            Self::Untracked=>"U",
+            Self::Conflicted=>"C",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GitStatusEntry{
+    pub path: String,
+    pub status: GitFileStatus,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GitBlameLine{
+    pub commit: String,
+    pub author: String,
+    pub time: i64,
+    pub summary: String,
+}
+
+// A git repository seen from a directory somewhere inside it. Paths going in and out are
+// relative to that directory, like the paths of the file tree.
+#[derive(Clone, Debug)]
+pub struct GitRepo{
+    dir: PathBuf,
+    // where the directory is in the repository, empty or ending in a slash
+    prefix: String,
+}
+
+fn run_git(dir: &Path, args: &[&str])->Result<String, String>{
+    let (stdout, stderr, success) = shell_env_cap_split(&[], dir, "git", args);
+    if success{
+        Ok(stdout)
+    }
+    else{
+        Err(stderr.trim().to_string())
+    }
+}
+
+impl GitRepo{
+    // None if git isn't installed or the directory isn't in a repository
+    pub fn open(dir: &Path)->Option<Self>{
+        let prefix = run_git(dir, &["rev-parse", "--show-prefix"]).ok()?;
+        Some(Self{
+            dir: dir.to_path_buf(),
+            prefix: prefix.trim_end().to_string(),
+        })
+    }
+
+    pub fn dir(&self)->&Path{
+        &self.dir
+    }
+
+    pub fn branch(&self)->Result<String, String>{
+        run_git(&self.dir, &["rev-parse", "--abbrev-ref", "HEAD"]).map(|branch| branch.trim().to_string())
+    }
+
+    // the commit HEAD points at, None in a repository without commits
+    pub fn head(&self)->Option<String>{
+        run_git(&self.dir, &["rev-parse", "HEAD"]).ok().map(|head| head.trim().to_string())
+    }
+
+    pub fn status(&self)->Result<Vec<GitStatusEntry>, String>{
+        let output = run_git(&self.dir, &["status", "--porcelain", "-z", "--untracked-files=all", "--", "."])?;
+        Ok(parse_status(&output, &self.prefix))
+    }
+
+    // the text of a file as committed in HEAD, None if it isn't in there
+    pub fn head_text(&self, path: &str)->Option<String>{
+        // `./` makes the path relative to our directory instead of the top of the repository
+        run_git(&self.dir, &["show", &format!("HEAD:./{}", path)]).ok()
+    }
+
+    // who last changed each line of a file as committed in HEAD
+    pub fn blame(&self, path: &str)->Result<Vec<GitBlameLine>, String>{
+        let output = run_git(&self.dir, &["blame", "--porcelain", "HEAD", "--", path])?;
+        Ok(parse_blame(&output))
+    }
+}
+
+// parses `git status --porcelain -z`, which lists paths from the top of the repository
+pub fn parse_status(output: &str, prefix: &str)->Vec<GitStatusEntry>{
+    let mut entries = Vec::new();
+    let mut records = output.split('\0');
+    while let Some(record) = records.next(){
+        if record.len() < 4{
+            continue
+        }
+        let (code, path) = record.split_at(3);
+        let mut code = code.chars();
+        let (x, y) = (code.next().unwrap_or(' '), code.next().unwrap_or(' '));
+        let status = match (x, y){
+            ('?', '?')=>GitFileStatus::Untracked,
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D')=>GitFileStatus::Conflicted,
+            ('R', _) | ('C', _)=>{
+                // renames and copies are followed by the path they came from
+                records.next();
+                GitFileStatus::Renamed
+            }
+            ('A', _)=>GitFileStatus::Added,
+            ('D', _) | (_, 'D')=>GitFileStatus::Deleted,
+            _=>GitFileStatus::Modified,
+        };
+        if let Some(path) = path.strip_prefix(prefix){
+            entries.push(GitStatusEntry{path: path.to_string(), status});
+        }
+    }
+    entries
+}
+
+// parses `git blame --porcelain`. Every line starts with a header naming its commit, the details
+// of a commit only follow the first time it shows up
+pub fn parse_blame(output: &str)->Vec<GitBlameLine>{
+    let mut commits: HashMap<&str, GitBlameLine> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<(&str, usize)> = None;
+    for line in output.lines(){
+        if line.starts_with('\t'){
+            if let Some((commit, final_line)) = current.take(){
+                if lines.len() < final_line{
+                    lines.resize(final_line, GitBlameLine::default());
+                }
+                lines[final_line - 1] = commits.get(commit).cloned().unwrap_or_default();
+            }
+            continue
+        }
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match current{
+            None=>{
+                let final_line = value.split(' ').nth(1).and_then(|line| line.parse::<usize>().ok()).unwrap_or(0);
+                if final_line == 0{
+                    continue
+                }
+                commits.entry(key).or_insert_with(|| GitBlameLine{
+                    commit: key.chars().take(8).collect(),
+                    ..GitBlameLine::default()
+                });
+                current = Some((key, final_line));
+            }
+            Some((commit, _))=>{
+                let blame = commits.get_mut(commit).unwrap();
+                match key{
+                    "author"=>blame.author = value.to_string(),
+                    "author-time"=>blame.time = value.parse().unwrap_or(0),
+                    "summary"=>blame.summary = value.to_string(),
+                    _=>()
+                }
+            }
+        }
+    }
+    lines
+}
+
+// formats a unix time like "3 days ago"
+pub fn time_ago(time: i64, now: i64)->String{
+    let seconds = (now - time).max(0);
+    let (count, unit) = match seconds{
+        s if s < 60=>return "just now".to_string(),
+        s if s < 3600=>(s / 60, "minute"),
+        s if s < 86400=>(s / 3600, "hour"),
+        s if s < 86400 * 30=>(s / 86400, "day"),
+        s if s < 86400 * 365=>(s / (86400 * 30), "month"),
+        s=>(s / (86400 * 365), "year"),
+    };
+    format!("{} {}{} ago", count, unit, if count == 1{""}else{"s"})
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        std::{fs, process::Command},
+    };
+
+    #[test]
+    fn status_codes(){
+        let output = " M src/a.rs\0A  src/b.rs\0?? src/new.rs\0R  src/c.rs\0src/old_c.rs\0 D src/d.rs\0UU src/e.rs\0M  other/f.rs\0";
+        let entries: Vec<(String, GitFileStatus)> = parse_status(output, "src/")
+            .into_iter()
+            .map(|entry| (entry.path, entry.status))
+            .collect();
+        assert_eq!(entries, vec![
+            ("a.rs".to_string(), GitFileStatus::Modified),
+            ("b.rs".to_string(), GitFileStatus::Added),
+            ("new.rs".to_string(), GitFileStatus::Untracked),
+            ("c.rs".to_string(), GitFileStatus::Renamed),
+            ("d.rs".to_string(), GitFileStatus::Deleted),
+            ("e.rs".to_string(), GitFileStatus::Conflicted),
+        ]);
+        assert!(parse_status("", "").is_empty());
+    }
+
+    #[test]
+    fn blame_details_are_shared_between_lines(){
+        let output = "\
+aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa 1 1 2
+author Ada
+author-time 1000
+summary first commit
+filename a.rs
+\tline one
+bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb 1 2 1
+author Bob
+author-time 2000
+summary second commit
+filename a.rs
+\tline two
+aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa 2 3
+\tline three
+";
+        let lines = parse_blame(output);
+        assert_eq!(lines.len(), 3);
+        assert_eq!((lines[0].commit.as_str(), lines[0].author.as_str(), lines[0].time), ("aaaaaaaa", "Ada", 1000));
+        assert_eq!((lines[1].commit.as_str(), lines[1].summary.as_str()), ("bbbbbbbb", "second commit"));
+        assert_eq!((lines[2].commit.as_str(), lines[2].summary.as_str()), ("aaaaaaaa", "first commit"));
+    }
+
+    #[test]
+    fn time_ago_units(){
+        assert_eq!(time_ago(100, 130), "just now");
+        assert_eq!(time_ago(200, 100), "just now");
+        assert_eq!(time_ago(0, 60), "1 minute ago");
+        assert_eq!(time_ago(0, 7200), "2 hours ago");
+        assert_eq!(time_ago(0, 86400 * 3), "3 days ago");
+        assert_eq!(time_ago(0, 86400 * 60), "2 months ago");
+        assert_eq!(time_ago(0, 86400 * 365), "1 year ago");
+    }
+
+    #[test]
+    fn temporary_repository(){
+        let root = std::env::temp_dir().join(format!("makepad_git_repo_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src")).unwrap();
+        let git = |args: &[&str]| Command::new("git")
+            .args(["-c", "user.name=Tester", "-c", "user.email=tester@example.com", "-c", "commit.gpgsign=false"])
+            .args(args)
+            .current_dir(&root)
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false);
+        if !git(&["init", "-q"]){
+            // no git on this machine
+            let _ = fs::remove_dir_all(&root);
+            return
+        }
+        fs::write(root.join("src/a.rs"), "one\ntwo\n").unwrap();
+        fs::write(root.join("top.rs"), "top\n").unwrap();
+        assert!(git(&["add", "."]));
+        assert!(git(&["commit", "-q", "-m", "first"]));
+        fs::write(root.join("src/a.rs"), "one\n2\n").unwrap();
+        fs::write(root.join("src/b.rs"), "new\n").unwrap();
+        fs::write(root.join("top.rs"), "changed\n").unwrap();
+
+        let repo = GitRepo::open(&root.join("src")).unwrap();
+        assert!(repo.head().is_some());
+        let mut status: Vec<(String, GitFileStatus)> = repo.status().unwrap()
+            .into_iter()
+            .map(|entry| (entry.path, entry.status))
+            .collect();
+        status.sort_by(|a, b| a.0.cmp(&b.0));
+        // top.rs is outside the directory and not listed
+        assert_eq!(status, vec![
+            ("a.rs".to_string(), GitFileStatus::Modified),
+            ("b.rs".to_string(), GitFileStatus::Untracked),
+        ]);
+        assert_eq!(repo.head_text("a.rs").as_deref(), Some("one\ntwo\n"));
+        assert_eq!(repo.head_text("b.rs"), None);
+        let blame = repo.blame("a.rs").unwrap();
+        assert_eq!(blame.len(), 2);
+        assert_eq!((blame[0].author.as_str(), blame[0].summary.as_str()), ("Tester", "first"));
+
+        assert!(GitRepo::open(&std::env::temp_dir().join("makepad_git_no_such_dir")).is_none());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
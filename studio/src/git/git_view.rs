@@ -0,0 +1,230 @@
+use {
+    crate::{
+        makepad_platform::studio::JumpToFile,
+        app::{AppAction, AppData},
+        git::{
+            git_diff::DiffLineKind,
+            git_manager::GitManager,
+        },
+        makepad_widgets::*,
+    },
+};
+
+live_design!{
+    import makepad_draw::shader::std::*;
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+
+    GitItem = <View> {
+        height: Fit, width: Fill
+        padding: {left: (THEME_SPACE_2), top: (THEME_SPACE_1), bottom: (THEME_SPACE_1)}
+        show_bg: true,
+        draw_bg: {
+            instance is_even: 0.0
+            fn pixel(self) -> vec4 {
+                return mix(
+                    THEME_COLOR_BG_EVEN,
+                    THEME_COLOR_BG_ODD,
+                    self.is_even
+                );
+            }
+        }
+        flow = <TextFlow>{
+            width: Fill,
+            height: Fit
+        }
+    }
+
+    GitDiffLine = <GitItem> {
+        padding: {left: (THEME_SPACE_2), top: 1, bottom: 1}
+        draw_bg: {
+            fn pixel(self) -> vec4 {
+                return THEME_COLOR_BG_EVEN;
+            }
+        }
+    }
+
+    GitView = {{GitView}}{
+        height: Fill, width: Fill,
+        list = <PortalList> {
+            capture_overload: false,
+            grab_key_focus: false
+            drag_scrolling: false
+            height: Fill, width: Fill,
+            flow: Down
+            Branch = <GitItem> {
+                padding: {left: (THEME_SPACE_2), top: (THEME_SPACE_2), bottom: (THEME_SPACE_2)}
+            }
+            File = <GitItem> {}
+            DiffFile = <GitItem> {
+                padding: {left: (THEME_SPACE_2), top: (THEME_SPACE_2), bottom: (THEME_SPACE_1)}
+            }
+            Hunk = <GitDiffLine> {
+                flow = {font_color: #8a9bd6}
+            }
+            Context = <GitDiffLine> {
+                flow = {font_color: #9a9a9a}
+            }
+            Added = <GitDiffLine> {
+                draw_bg: {fn pixel(self) -> vec4 {return #2a3a22;}}
+                flow = {font_color: #b5d99c}
+            }
+            Removed = <GitDiffLine> {
+                draw_bg: {fn pixel(self) -> vec4 {return #452626;}}
+                flow = {font_color: #e8a0a0}
+            }
+        }
+    }
+}
+
+// the branch and changed files of the project, followed by the diff of the file clicked on
+#[derive(Live, LiveHook, Widget)]
+pub struct GitView{
+    #[deref] view:View
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GitLink{item_id:usize}
+
+enum GitItem{
+    Branch,
+    File(usize),
+    DiffLine(usize),
+}
+
+fn git_item(git_manager: &GitManager, item_id: usize)->Option<GitItem>{
+    if item_id == 0{
+        return Some(GitItem::Branch)
+    }
+    let file = item_id - 1;
+    if file < git_manager.entries.len(){
+        return Some(GitItem::File(file))
+    }
+    let line = file - git_manager.entries.len();
+    if line < git_manager.diff_lines.len(){
+        return Some(GitItem::DiffLine(line))
+    }
+    None
+}
+
+impl GitView{
+    fn draw_items(&mut self, cx: &mut Cx2d, list:&mut PortalList, git_manager:&GitManager){
+        list.set_item_range(cx, 0, 1 + git_manager.entries.len() + git_manager.diff_lines.len());
+        while let Some(item_id) = list.next_visible_item(cx) {
+            let Some(item) = git_item(git_manager, item_id) else{
+                continue
+            };
+            let template = match item{
+                GitItem::Branch=>live_id!(Branch),
+                GitItem::File(_)=>live_id!(File),
+                GitItem::DiffLine(line)=>match git_manager.diff_lines[line].kind{
+                    DiffLineKind::Header if line == 0=>live_id!(DiffFile),
+                    DiffLineKind::Header=>live_id!(Hunk),
+                    DiffLineKind::Context=>live_id!(Context),
+                    DiffLineKind::Added=>live_id!(Added),
+                    DiffLineKind::Removed=>live_id!(Removed),
+                }
+            };
+            let mut item_view = list.item(cx, item_id, template).as_view();
+            if let GitItem::File(file) = item{
+                item_view.apply_over(cx, live!{
+                    draw_bg: {is_even: (if file & 1 == 0 {1.0} else {0.0})}
+                });
+            }
+            while let Some(step) = item_view.draw(cx, &mut Scope::empty()).step(){
+                if let Some(mut tf) = step.as_text_flow().borrow_mut(){
+                    match item{
+                        GitItem::Branch=>{
+                            if !git_manager.is_active(){
+                                tf.draw_text(cx, "Not a git repository");
+                                continue
+                            }
+                            tf.draw_text(cx, "On branch ");
+                            tf.bold.push();
+                            tf.draw_text(cx, &git_manager.branch);
+                            tf.bold.pop();
+                            if let Some(error) = &git_manager.error{
+                                tf.draw_text(cx, " - ");
+                                tf.draw_text(cx, error);
+                            }
+                            else if git_manager.entries.is_empty(){
+                                tf.draw_text(cx, ", nothing changed");
+                            }
+                        }
+                        GitItem::File(file)=>{
+                            let entry = &git_manager.entries[file];
+                            tf.fixed.push();
+                            tf.draw_text(cx, entry.status.letter());
+                            tf.fixed.pop();
+                            tf.draw_text(cx, " ");
+                            tf.draw_link(cx, live_id!(link), GitLink{item_id}, &entry.path);
+                        }
+                        GitItem::DiffLine(line)=>{
+                            let diff_line = &git_manager.diff_lines[line];
+                            if line == 0{
+                                tf.bold.push();
+                                tf.draw_link(cx, live_id!(link), GitLink{item_id}, &diff_line.text);
+                                tf.bold.pop();
+                                continue
+                            }
+                            let prefix = match diff_line.kind{
+                                DiffLineKind::Header=>"",
+                                DiffLineKind::Context=>" ",
+                                DiffLineKind::Added=>"+",
+                                DiffLineKind::Removed=>"-",
+                            };
+                            tf.fixed.push();
+                            tf.combine_spaces.push(false);
+                            tf.draw_text(cx, prefix);
+                            tf.draw_link(cx, live_id!(link), GitLink{item_id}, &diff_line.text);
+                            tf.combine_spaces.pop();
+                            tf.fixed.pop();
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Widget for GitView {
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope:&mut Scope, walk:Walk)->DrawStep{
+        while let Some(step) = self.view.draw_walk(cx, scope, walk).step(){
+            if let Some(mut list) = step.as_portal_list().borrow_mut(){
+                self.draw_items(cx, &mut *list, &scope.data.get::<AppData>().unwrap().git_manager)
+            }
+        }
+        DrawStep::done()
+    }
+
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope){
+        let list = self.view.portal_list(id!(list));
+        self.view.handle_event(cx, event, scope);
+        let data = scope.data.get_mut::<AppData>().unwrap();
+        if let Event::Actions(actions) = event{
+            if list.any_items_with_actions(&actions) {
+                for link in actions.filter_actions_data::<GitLink>(){
+                    let git_manager = &mut data.git_manager;
+                    match git_item(git_manager, link.item_id){
+                        Some(GitItem::File(file))=>{
+                            let path = git_manager.entries[file].path.clone();
+                            git_manager.show_diff(&data.file_system, &path);
+                            self.view.redraw(cx);
+                        }
+                        Some(GitItem::DiffLine(line))=>{
+                            let Some(path) = git_manager.diff_path.clone() else{
+                                continue
+                            };
+                            cx.action(AppAction::JumpTo(JumpToFile{
+                                file_name: path,
+                                line: git_manager.diff_lines[line].new_line as u32,
+                                column: 0
+                            }));
+                        }
+                        _=>()
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod git_repo;
+pub mod git_diff;
+pub mod git_manager;
+pub mod git_view;
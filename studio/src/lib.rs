@@ -8,10 +8,14 @@ pub mod studio_editor;
 pub mod studio_file_tree;
 pub mod log_list;
 pub mod run_list;
+pub mod test_list;
 pub mod run_view;
 pub mod profiler;
 pub mod integration;
 pub mod ai_chat;
+pub mod search;
+pub mod lsp;
+pub mod git;
 
 //pub use makepad_code_editor;
 
@@ -0,0 +1,142 @@
+use {
+    crate::{
+        build_manager::child_process::{ChildProcess, ChildStdIO, ChildStdIn},
+        lsp::lsp_protocol::*,
+        makepad_micro_serde::*,
+        makepad_widgets::*,
+    },
+    std::{
+        path::PathBuf,
+        process::Child,
+        sync::mpsc::Sender,
+        thread,
+    },
+};
+
+pub enum LspClientEvent{
+    Message(LspMessage),
+    Exited,
+}
+
+// one running language server, messages from it arrive on `receiver` and wake up the UI thread
+pub struct LspClient{
+    child: Child,
+    stdin_sender: Sender<ChildStdIn>,
+    next_id: u64,
+    pub receiver: ToUIReceiver<LspClientEvent>,
+}
+
+impl LspClient{
+    pub fn start(cmd: &str, args: &[String], current_dir: PathBuf)->Result<Self, std::io::Error>{
+        let ChildProcess{child, stdin_sender, line_receiver, ..} = ChildProcess::start_raw(cmd, args, current_dir, &[])?;
+        let receiver = ToUIReceiver::default();
+        let sender = receiver.sender();
+        thread::spawn(move ||{
+            let mut decoder = MessageDecoder::default();
+            while let Ok(io) = line_receiver.recv(){
+                match io{
+                    ChildStdIO::StdOutData(data)=>{
+                        decoder.push(&data);
+                        while let Some(message) = decoder.next_message(){
+                            match message{
+                                Ok(message)=>if sender.send(LspClientEvent::Message(message)).is_err(){
+                                    return
+                                }
+                                Err(err)=>log!("Language server sent a broken message: {}", err)
+                            }
+                        }
+                    }
+                    ChildStdIO::StdOut(_) | ChildStdIO::StdErr(_)=>(),
+                    ChildStdIO::Term | ChildStdIO::Kill=>break
+                }
+            }
+            let _ = sender.send(LspClientEvent::Exited);
+        });
+        Ok(Self{
+            child,
+            stdin_sender,
+            next_id: 1,
+            receiver,
+        })
+    }
+
+    fn send(&self, message: JsonValue){
+        let _ = self.stdin_sender.send(ChildStdIn::Send(encode_message(&message)));
+    }
+
+    // returns the id the response will carry
+    pub fn request(&mut self, method: &str, params: JsonValue)->u64{
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(json_object(vec![
+            ("jsonrpc", json_string("2.0")),
+            ("id", JsonValue::U64(id)),
+            ("method", json_string(method)),
+            ("params", params),
+        ]));
+        id
+    }
+
+    pub fn notify(&self, method: &str, params: JsonValue){
+        self.send(json_object(vec![
+            ("jsonrpc", json_string("2.0")),
+            ("method", json_string(method)),
+            ("params", params),
+        ]));
+    }
+
+    pub fn respond(&self, id: JsonValue, result: JsonValue){
+        self.send(json_object(vec![
+            ("jsonrpc", json_string("2.0")),
+            ("id", id),
+            ("result", result),
+        ]));
+    }
+
+    pub fn stop(mut self){
+        self.request("shutdown", JsonValue::Null);
+        self.notify("exit", JsonValue::Null);
+        let _ = self.stdin_sender.send(ChildStdIn::Term);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests{
+    use {
+        super::*,
+        std::time::{Duration, Instant},
+    };
+
+    // a stand-in server that answers the two requests below out of order
+    #[test]
+    fn responses_match_their_requests(){
+        let reply = |id: u64, result: &str| encode_message(&json_object(vec![
+            ("jsonrpc", json_string("2.0")),
+            ("id", JsonValue::U64(id)),
+            ("result", json_string(result)),
+        ]));
+        let script = format!("{}{}", reply(2, "second"), reply(1, "first"));
+        let args = ["-c".to_string(), "printf '%s' \"$1\"; sleep 5".to_string(), "sh".to_string(), script];
+        let mut client = LspClient::start("sh", &args, std::env::temp_dir()).unwrap();
+        let first = client.request("first", JsonValue::Null);
+        let second = client.request("second", JsonValue::Null);
+        assert_eq!((first, second), (1, 2));
+
+        let mut results = Vec::new();
+        let start = Instant::now();
+        while results.len() < 2 && start.elapsed() < Duration::from_secs(5){
+            match client.receiver.try_recv(){
+                Ok(LspClientEvent::Message(LspMessage::Response{id, result: Ok(result)}))=>{
+                    results.push((id, result.string().cloned().unwrap()));
+                }
+                Ok(_)=>(),
+                Err(_)=>thread::sleep(Duration::from_millis(10))
+            }
+        }
+        client.stop();
+        results.sort();
+        assert_eq!(results, vec![(first, "first".to_string()), (second, "second".to_string())]);
+    }
+}
@@ -0,0 +1,481 @@
+use {
+    crate::{
+        app::AppAction,
+        file_system::file_system::{FileSystem, OpenDocument},
+        lsp::{
+            lsp_client::{LspClient, LspClientEvent},
+            lsp_protocol::*,
+        },
+        makepad_code_editor::{
+            completion::{word_start, CompletionItem, CompletionKind},
+            decoration::{Decoration, DecorationType},
+            inlays::InlineInlay,
+            text::Position,
+            CodeDocument,
+        },
+        makepad_file_protocol::FileSearchMatch,
+        makepad_micro_serde::*,
+        makepad_platform::studio::JumpToFile,
+        makepad_widgets::*,
+    },
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+// diagnostics from the language server live next to the ones from the build, which use id 0
+pub const LSP_DECORATION_ID: usize = 1;
+// the hover text at the end of a line, git blame uses id 0
+pub const LSP_INLAY_ID: usize = 1;
+
+#[derive(DefaultNone, Debug, Clone)]
+pub enum LspAction{
+    Symbols{path: String, matches: Vec<FileSearchMatch>},
+    Completions{file_id: LiveId, items: Vec<CompletionItem>},
+    None
+}
+
+enum PendingRequest{
+    Initialize,
+    Hover{file_id: LiveId, line: usize},
+    Definition,
+    Symbols{file_id: LiveId},
+    Completion{file_id: LiveId, position: Position},
+}
+
+// Runs a language server for the Rust files in the project. The server is rust-analyzer unless
+// another command is passed with `--lsp=`, `--lsp=off` turns it off.
+#[derive(Default)]
+pub struct LspManager{
+    client: Option<LspClient>,
+    initialized: bool,
+    root_path: PathBuf,
+    encoding: PositionEncoding,
+    // the files the server has open, with the version of their text it knows about
+    versions: HashMap<LiveId, i64>,
+    pending: HashMap<u64, PendingRequest>,
+    hover_file: Option<LiveId>,
+}
+
+impl LspManager{
+    pub fn init(&mut self, root_path: &Path){
+        let mut command = "rust-analyzer".to_string();
+        for arg in std::env::args(){
+            if let Some(value) = arg.strip_prefix("--lsp="){
+                command = value.to_string();
+            }
+        }
+        if command == "off"{
+            return
+        }
+        self.root_path = root_path.canonicalize().unwrap_or(root_path.to_path_buf());
+        let mut parts = command.split_whitespace();
+        let Some(cmd) = parts.next() else{
+            return
+        };
+        let args: Vec<String> = parts.map(|part| part.to_string()).collect();
+        let mut client = match LspClient::start(cmd, &args, self.root_path.clone()){
+            Ok(client)=>client,
+            Err(err)=>{
+                log!("Cannot start language server {}: {}", cmd, err);
+                return
+            }
+        };
+        let root_uri = path_to_uri(&self.root_path);
+        let id = client.request("initialize", json_object(vec![
+            ("processId", JsonValue::U64(std::process::id() as u64)),
+            ("rootUri", json_string(&root_uri)),
+            ("workspaceFolders", JsonValue::Array(vec![json_object(vec![
+                ("uri", json_string(&root_uri)),
+                ("name", json_string("root")),
+            ])])),
+            ("capabilities", json_object(vec![
+                ("general", json_object(vec![
+                    ("positionEncodings", JsonValue::Array(vec![json_string("utf-8"), json_string("utf-16")])),
+                ])),
+                ("textDocument", json_object(vec![
+                    ("hover", json_object(vec![
+                        ("contentFormat", JsonValue::Array(vec![json_string("plaintext"), json_string("markdown")])),
+                    ])),
+                    ("definition", json_object(vec![("linkSupport", JsonValue::Bool(true))])),
+                    ("documentSymbol", json_object(vec![("hierarchicalDocumentSymbolSupport", JsonValue::Bool(true))])),
+                    ("completion", json_object(vec![
+                        ("completionItem", json_object(vec![("snippetSupport", JsonValue::Bool(true))])),
+                    ])),
+                    ("publishDiagnostics", empty_object()),
+                ])),
+            ])),
+        ]));
+        self.pending.insert(id, PendingRequest::Initialize);
+        self.client = Some(client);
+    }
+
+    pub fn stop(&mut self){
+        if let Some(client) = self.client.take(){
+            client.stop();
+        }
+    }
+
+    fn serves(path: &str)->bool{
+        path.ends_with(".rs")
+    }
+
+    fn code_document(file_system: &FileSystem, file_id: LiveId)->Option<&CodeDocument>{
+        match file_system.open_documents.get(&file_id){
+            Some(OpenDocument::Code(document))=>Some(document),
+            _=>None
+        }
+    }
+
+    fn uri(&self, file_system: &FileSystem, file_id: LiveId)->Option<String>{
+        let path = file_system.file_node_id_to_path(file_id)?;
+        if !Self::serves(path){
+            return None
+        }
+        Some(path_to_uri(&self.root_path.join(path)))
+    }
+
+    fn text_document(&self, file_system: &FileSystem, file_id: LiveId)->Option<JsonValue>{
+        Some(json_object(vec![("uri", json_string(&self.uri(file_system, file_id)?))]))
+    }
+
+    fn to_lsp_position(&self, document: &CodeDocument, position: Position)->JsonValue{
+        let text = document.as_text();
+        let line = text.as_lines().get(position.line_index).map_or("", |line| line.as_str());
+        LspPosition{
+            line: position.line_index,
+            character: self.encoding.to_character(line, position.byte_index),
+        }.to_json()
+    }
+
+    fn from_lsp_position(&self, lines: &[String], position: LspPosition)->Position{
+        let line = lines.get(position.line).map_or("", |line| line.as_str());
+        Position{
+            line_index: position.line,
+            byte_index: self.encoding.to_byte_index(line, position.character),
+        }
+    }
+
+    fn relative_path(&self, path: &Path)->Option<String>{
+        let path = path.strip_prefix(&self.root_path).ok()?;
+        Some(path.to_string_lossy().replace('\\', "/"))
+    }
+
+    pub fn did_open(&mut self, file_system: &FileSystem, file_id: LiveId){
+        if !self.initialized || self.versions.contains_key(&file_id){
+            return
+        }
+        let (Some(client), Some(uri), Some(document)) = (&self.client, self.uri(file_system, file_id), Self::code_document(file_system, file_id)) else{
+            return
+        };
+        client.notify("textDocument/didOpen", json_object(vec![
+            ("textDocument", json_object(vec![
+                ("uri", json_string(&uri)),
+                ("languageId", json_string("rust")),
+                ("version", JsonValue::I64(0)),
+                ("text", JsonValue::String(document.as_text().to_string())),
+            ])),
+        ]));
+        self.versions.insert(file_id, 0);
+    }
+
+    // the server forgets a file when its last tab closes, the document itself stays loaded
+    pub fn did_close(&mut self, file_system: &FileSystem, file_id: LiveId){
+        if self.versions.remove(&file_id).is_none(){
+            return
+        }
+        if self.hover_file == Some(file_id){
+            self.hover_file = None;
+            if let Some(document) = Self::code_document(file_system, file_id){
+                document.clear_inline_inlays(LSP_INLAY_ID);
+            }
+        }
+        let (Some(client), Some(text_document)) = (&self.client, self.text_document(file_system, file_id)) else{
+            return
+        };
+        client.notify("textDocument/didClose", json_object(vec![("textDocument", text_document)]));
+    }
+
+    // a file that was closed and gets a tab again is opened on the server when it is used
+    fn ensure_open(&mut self, file_system: &FileSystem, file_id: LiveId)->bool{
+        self.did_open(file_system, file_id);
+        self.versions.contains_key(&file_id)
+    }
+
+    // sends the whole text, documents are small enough that incremental sync isn't worth it
+    pub fn did_change(&mut self, file_system: &FileSystem, file_id: LiveId){
+        if let Some(hover_file) = self.hover_file.take(){
+            if let Some(document) = Self::code_document(file_system, hover_file){
+                document.clear_inline_inlays(LSP_INLAY_ID);
+            }
+        }
+        let Some(version) = self.versions.get(&file_id).map(|version| version + 1) else{
+            // opening it sends the text as it is now
+            self.did_open(file_system, file_id);
+            return
+        };
+        let (Some(client), Some(uri), Some(document)) = (&self.client, self.uri(file_system, file_id), Self::code_document(file_system, file_id)) else{
+            return
+        };
+        client.notify("textDocument/didChange", json_object(vec![
+            ("textDocument", json_object(vec![
+                ("uri", json_string(&uri)),
+                ("version", JsonValue::I64(version)),
+            ])),
+            ("contentChanges", JsonValue::Array(vec![json_object(vec![
+                ("text", JsonValue::String(document.as_text().to_string())),
+            ])])),
+        ]));
+        self.versions.insert(file_id, version);
+    }
+
+    fn position_request(&mut self, file_system: &FileSystem, file_id: LiveId, method: &str, position: Position)->Option<u64>{
+        if !self.ensure_open(file_system, file_id){
+            return None
+        }
+        let document = Self::code_document(file_system, file_id)?;
+        let params = json_object(vec![
+            ("textDocument", self.text_document(file_system, file_id)?),
+            ("position", self.to_lsp_position(document, position)),
+        ]);
+        Some(self.client.as_mut()?.request(method, params))
+    }
+
+    pub fn hover(&mut self, file_system: &FileSystem, file_id: LiveId, position: Position){
+        if let Some(id) = self.position_request(file_system, file_id, "textDocument/hover", position){
+            self.pending.insert(id, PendingRequest::Hover{file_id, line: position.line_index});
+        }
+    }
+
+    pub fn go_to_definition(&mut self, file_system: &FileSystem, file_id: LiveId, position: Position){
+        if let Some(id) = self.position_request(file_system, file_id, "textDocument/definition", position){
+            self.pending.insert(id, PendingRequest::Definition);
+        }
+    }
+
+    pub fn completion(&mut self, file_system: &FileSystem, file_id: LiveId, position: Position){
+        if let Some(id) = self.position_request(file_system, file_id, "textDocument/completion", position){
+            self.pending.insert(id, PendingRequest::Completion{file_id, position});
+        }
+    }
+
+    pub fn document_symbols(&mut self, file_system: &FileSystem, file_id: LiveId){
+        if !self.ensure_open(file_system, file_id){
+            return
+        }
+        let (Some(text_document), Some(client)) = (self.text_document(file_system, file_id), self.client.as_mut()) else{
+            return
+        };
+        let id = client.request("textDocument/documentSymbol", json_object(vec![("textDocument", text_document)]));
+        self.pending.insert(id, PendingRequest::Symbols{file_id});
+    }
+
+    pub fn handle_event(&mut self, cx: &mut Cx, event: &Event, file_system: &mut FileSystem){
+        if let Event::Signal = event{
+            let mut events = Vec::new();
+            if let Some(client) = &self.client{
+                while let Ok(event) = client.receiver.try_recv(){
+                    events.push(event);
+                }
+            }
+            for event in events{
+                match event{
+                    LspClientEvent::Message(message)=>self.handle_message(cx, file_system, message),
+                    LspClientEvent::Exited=>{
+                        log!("Language server exited");
+                        self.client = None;
+                        self.initialized = false;
+                        self.versions.clear();
+                        self.pending.clear();
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_message(&mut self, cx: &mut Cx, file_system: &mut FileSystem, message: LspMessage){
+        match message{
+            LspMessage::Request{id, method, params}=>{
+                // we don't offer any of the things servers ask for, but they do need an answer
+                let result = match method.as_str(){
+                    "workspace/configuration"=>{
+                        let count = params.key("items").and_then(|items| items.array()).map_or(0, |items| items.len());
+                        JsonValue::Array(vec![JsonValue::Null; count])
+                    }
+                    _=>JsonValue::Null
+                };
+                if let Some(client) = &self.client{
+                    client.respond(id, result);
+                }
+            }
+            LspMessage::Notification{method, params}=>{
+                if method == "textDocument/publishDiagnostics"{
+                    self.handle_diagnostics(cx, file_system, &params);
+                }
+            }
+            LspMessage::Response{id, result}=>{
+                let Some(request) = self.pending.remove(&id) else{
+                    return
+                };
+                let result = match result{
+                    Ok(result)=>result,
+                    Err(err)=>{
+                        if let PendingRequest::Initialize = request{
+                            log!("Language server failed to initialize: {}", err);
+                        }
+                        return
+                    }
+                };
+                self.handle_response(cx, file_system, request, result);
+            }
+        }
+    }
+
+    fn handle_response(&mut self, cx: &mut Cx, file_system: &mut FileSystem, request: PendingRequest, result: JsonValue){
+        match request{
+            PendingRequest::Initialize=>{
+                if let Some(encoding) = result.key("capabilities").and_then(|caps| caps.key("positionEncoding")).and_then(|encoding| encoding.string()){
+                    self.encoding = PositionEncoding::from_name(encoding);
+                }
+                self.initialized = true;
+                if let Some(client) = &self.client{
+                    client.notify("initialized", empty_object());
+                }
+                let file_ids: Vec<LiveId> = file_system.open_documents.keys().cloned().collect();
+                for file_id in file_ids{
+                    self.did_open(file_system, file_id);
+                }
+            }
+            PendingRequest::Hover{file_id, line}=>{
+                let Some(document) = Self::code_document(file_system, file_id) else{
+                    return
+                };
+                if let Some(hover_file) = self.hover_file.take(){
+                    if let Some(document) = Self::code_document(file_system, hover_file){
+                        document.clear_inline_inlays(LSP_INLAY_ID);
+                    }
+                }
+                if let Some(summary) = hover_text_from_json(&result).as_deref().and_then(hover_summary){
+                    let line_len = document.as_text().as_lines().get(line).map_or(0, |line| line.len());
+                    document.set_inline_inlays(LSP_INLAY_ID, line, vec![(line_len, InlineInlay::Text(format!("  {}", summary)))]);
+                    self.hover_file = Some(file_id);
+                }
+                file_system.handle_sessions();
+                cx.action(AppAction::RedrawFile(file_id));
+            }
+            PendingRequest::Definition=>{
+                let Some(location) = LspLocation::list_from_json(&result).into_iter().next() else{
+                    return
+                };
+                let Some(file_name) = self.relative_path(&location.path) else{
+                    log!("Definition is outside of the project: {}", location.path.display());
+                    return
+                };
+                // the column depends on the text, which we only have for open files
+                let column = file_system.path_to_file_node_id(&file_name)
+                    .and_then(|file_id| Self::code_document(file_system, file_id))
+                    .map_or(location.range.start.character, |document|{
+                        self.from_lsp_position(document.as_text().as_lines(), location.range.start).byte_index
+                    });
+                cx.action(AppAction::JumpTo(JumpToFile{
+                    file_name,
+                    line: location.range.start.line as u32,
+                    column: column as u32,
+                }));
+            }
+            PendingRequest::Symbols{file_id}=>{
+                let (Some(path), Some(document)) = (file_system.file_node_id_to_path(file_id), Self::code_document(file_system, file_id)) else{
+                    return
+                };
+                let text = document.as_text();
+                let lines = text.as_lines();
+                let matches = LspSymbol::list_from_json(&result).into_iter().filter_map(|symbol|{
+                    let start = self.from_lsp_position(lines, symbol.range.start);
+                    let end = self.from_lsp_position(lines, symbol.range.end);
+                    Some(FileSearchMatch{
+                        line: start.line_index,
+                        start: start.byte_index,
+                        end: if end.line_index == start.line_index{end.byte_index}else{start.byte_index},
+                        text: lines.get(start.line_index)?.clone(),
+                    })
+                }).collect();
+                cx.action(LspAction::Symbols{path: path.to_string(), matches});
+            }
+            PendingRequest::Completion{file_id, position}=>{
+                let Some(document) = Self::code_document(file_system, file_id) else{
+                    return
+                };
+                let text = document.as_text();
+                let items = LspCompletionItem::list_from_json(&result).into_iter().map(|item|{
+                    CompletionItem{
+                        start: match item.range{
+                            Some(range) if range.start.line == position.line_index=>self.from_lsp_position(text.as_lines(), range.start),
+                            _=>word_start(&text, position)
+                        },
+                        kind: match item.kind{
+                            2 | 3 | 4=>CompletionKind::Function,
+                            5=>CompletionKind::Field,
+                            6=>CompletionKind::Variable,
+                            7 | 8 | 13 | 22 | 25=>CompletionKind::Type,
+                            9=>CompletionKind::Module,
+                            10=>CompletionKind::Property,
+                            14=>CompletionKind::Keyword,
+                            15=>CompletionKind::Snippet,
+                            20 | 21=>CompletionKind::Constant,
+                            _=>CompletionKind::Word,
+                        },
+                        label: item.label,
+                        detail: item.detail,
+                        text: item.insert_text,
+                        is_snippet: item.is_snippet,
+                    }
+                }).collect();
+                cx.action(LspAction::Completions{file_id, items});
+            }
+        }
+    }
+
+    fn handle_diagnostics(&mut self, cx: &mut Cx, file_system: &mut FileSystem, params: &JsonValue){
+        let Some(path) = params.key("uri").and_then(|uri| uri.string()).and_then(|uri| uri_to_path(uri)) else{
+            return
+        };
+        let Some(file_id) = self.relative_path(&path).and_then(|path| file_system.path_to_file_node_id(&path)) else{
+            return
+        };
+        // ranges computed for an older text would land in the wrong place
+        if !diagnostics_version_matches(params, self.versions.get(&file_id).copied()){
+            return
+        }
+        let Some(document) = Self::code_document(file_system, file_id) else{
+            return
+        };
+        let decorations: Vec<Decoration> = {
+            let text = document.as_text();
+            let lines = text.as_lines();
+            params.key("diagnostics").and_then(|diagnostics| diagnostics.array()).into_iter().flatten()
+                .filter_map(LspDiagnostic::from_json)
+                .filter_map(|diagnostic|{
+                    let ty = match diagnostic.severity{
+                        DiagnosticSeverity::Error=>DecorationType::Error,
+                        DiagnosticSeverity::Warning=>DecorationType::Warning,
+                        _=>return None
+                    };
+                    let start = self.from_lsp_position(lines, diagnostic.range.start);
+                    let mut end = self.from_lsp_position(lines, diagnostic.range.end);
+                    if start == end{
+                        // make empty ranges cover a character so they can be seen
+                        let line = lines.get(end.line_index)?;
+                        end.byte_index = line[end.byte_index..].chars().next().map_or(end.byte_index, |char| end.byte_index + char.len_utf8());
+                    }
+                    Some(Decoration::new(LSP_DECORATION_ID, start, end, ty))
+                })
+                .collect()
+        };
+        file_system.remove_decorations_with_id(file_id, LSP_DECORATION_ID);
+        for decoration in decorations{
+            file_system.add_decoration(file_id, decoration);
+        }
+        cx.action(AppAction::RedrawFile(file_id));
+    }
+}
@@ -0,0 +1,565 @@
+use {
+    crate::makepad_micro_serde::*,
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+// Language servers speak JSON-RPC with a `Content-Length` header in front of every message.
+// Only the parts of the protocol Studio uses are modelled here, everything else stays a JsonValue.
+
+pub fn json_object(fields: Vec<(&str, JsonValue)>)->JsonValue{
+    JsonValue::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+}
+
+pub fn json_string(value: &str)->JsonValue{
+    JsonValue::String(value.to_string())
+}
+
+pub fn encode_message(message: &JsonValue)->String{
+    let body = message.serialize_json();
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+#[derive(Debug)]
+pub enum LspMessage{
+    // a request from the server to us
+    Request{id: JsonValue, method: String, params: JsonValue},
+    Response{id: u64, result: Result<JsonValue, String>},
+    Notification{method: String, params: JsonValue},
+}
+
+impl LspMessage{
+    fn from_json(value: JsonValue)->Result<Self, String>{
+        let JsonValue::Object(mut object) = value else{
+            return Err("message is not an object".to_string())
+        };
+        let params = object.remove("params").unwrap_or(JsonValue::Null);
+        let method = object.get("method").and_then(|method| method.string()).cloned();
+        match (object.remove("id"), method){
+            (Some(id), Some(method))=>Ok(LspMessage::Request{id, method, params}),
+            (None, Some(method))=>Ok(LspMessage::Notification{method, params}),
+            (Some(id), None)=>{
+                let id = id.u64().ok_or_else(|| "response id is not a number".to_string())?;
+                let result = match object.remove("error"){
+                    Some(error)=>Err(error.key("message").and_then(|message| message.string()).cloned().unwrap_or_default()),
+                    None=>Ok(object.remove("result").unwrap_or(JsonValue::Null))
+                };
+                Ok(LspMessage::Response{id, result})
+            }
+            (None, None)=>Err("message has no id and no method".to_string())
+        }
+    }
+}
+
+// collects the bytes coming from the server and splits them into messages
+#[derive(Default)]
+pub struct MessageDecoder{
+    buffer: Vec<u8>,
+}
+
+impl MessageDecoder{
+    pub fn push(&mut self, data: &[u8]){
+        self.buffer.extend_from_slice(data);
+    }
+
+    pub fn next_message(&mut self)->Option<Result<LspMessage, String>>{
+        let header_end = self.buffer.windows(4).position(|window| window == b"\r\n\r\n")?;
+        let header = String::from_utf8_lossy(&self.buffer[..header_end]).to_string();
+        let content_length = header.lines().find_map(|line|{
+            let (name, value) = line.split_once(':')?;
+            if name.trim().eq_ignore_ascii_case("content-length"){
+                value.trim().parse::<usize>().ok()
+            }
+            else{
+                None
+            }
+        });
+        let Some(content_length) = content_length else{
+            // skip the broken header so we don't get stuck on it
+            self.buffer.drain(..header_end + 4);
+            return Some(Err(format!("message without content length: {}", header)))
+        };
+        let body_start = header_end + 4;
+        if self.buffer.len() < body_start + content_length{
+            return None
+        }
+        let body: Vec<u8> = self.buffer.drain(..body_start + content_length).skip(body_start).collect();
+        let body = match String::from_utf8(body){
+            Ok(body)=>body,
+            Err(_)=>return Some(Err("message is not valid utf8".to_string()))
+        };
+        Some(JsonValue::deserialize_json(&body)
+            .map_err(|err| format!("{:?}", err))
+            .and_then(LspMessage::from_json))
+    }
+}
+
+// how the server counts the characters in a line
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PositionEncoding{
+    Utf8,
+    #[default]
+    Utf16,
+}
+
+impl PositionEncoding{
+    pub fn from_name(name: &str)->Self{
+        match name{
+            "utf-8"=>PositionEncoding::Utf8,
+            _=>PositionEncoding::Utf16
+        }
+    }
+
+    pub fn to_character(self, line: &str, byte_index: usize)->usize{
+        let byte_index = byte_index.min(line.len());
+        match self{
+            PositionEncoding::Utf8=>byte_index,
+            PositionEncoding::Utf16=>line[..byte_index].chars().map(char::len_utf16).sum()
+        }
+    }
+
+    pub fn to_byte_index(self, line: &str, character: usize)->usize{
+        match self{
+            PositionEncoding::Utf8=>{
+                let mut byte_index = character.min(line.len());
+                while !line.is_char_boundary(byte_index){
+                    byte_index -= 1;
+                }
+                byte_index
+            }
+            PositionEncoding::Utf16=>{
+                let mut units = 0;
+                for (byte_index, char) in line.char_indices(){
+                    if units >= character{
+                        return byte_index
+                    }
+                    units += char.len_utf16();
+                }
+                line.len()
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LspPosition{
+    pub line: usize,
+    pub character: usize,
+}
+
+impl LspPosition{
+    pub fn from_json(value: &JsonValue)->Option<Self>{
+        Some(Self{
+            line: value.key("line")?.u64()? as usize,
+            character: value.key("character")?.u64()? as usize,
+        })
+    }
+
+    pub fn to_json(self)->JsonValue{
+        json_object(vec![
+            ("line", JsonValue::U64(self.line as u64)),
+            ("character", JsonValue::U64(self.character as u64)),
+        ])
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LspRange{
+    pub start: LspPosition,
+    pub end: LspPosition,
+}
+
+impl LspRange{
+    pub fn from_json(value: &JsonValue)->Option<Self>{
+        Some(Self{
+            start: LspPosition::from_json(value.key("start")?)?,
+            end: LspPosition::from_json(value.key("end")?)?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiagnosticSeverity{
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+#[derive(Clone, Debug)]
+pub struct LspDiagnostic{
+    pub range: LspRange,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+impl LspDiagnostic{
+    pub fn from_json(value: &JsonValue)->Option<Self>{
+        Some(Self{
+            range: LspRange::from_json(value.key("range")?)?,
+            severity: match value.key("severity").and_then(|severity| severity.u64()){
+                Some(2)=>DiagnosticSeverity::Warning,
+                Some(3)=>DiagnosticSeverity::Information,
+                Some(4)=>DiagnosticSeverity::Hint,
+                _=>DiagnosticSeverity::Error,
+            },
+            message: value.key("message")?.string()?.clone(),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LspLocation{
+    pub path: PathBuf,
+    pub range: LspRange,
+}
+
+impl LspLocation{
+    // definition results are a Location, a list of them or a list of LocationLinks
+    pub fn list_from_json(value: &JsonValue)->Vec<Self>{
+        let one = |value: &JsonValue|->Option<Self>{
+            let (uri, range) = match value.key("targetUri"){
+                Some(uri)=>(uri, value.key("targetSelectionRange")?),
+                None=>(value.key("uri")?, value.key("range")?)
+            };
+            Some(Self{
+                path: uri_to_path(uri.string()?)?,
+                range: LspRange::from_json(range)?,
+            })
+        };
+        match value.array(){
+            Some(array)=>array.iter().filter_map(one).collect(),
+            None=>one(value).into_iter().collect()
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LspSymbol{
+    pub name: String,
+    pub kind: u64,
+    // nesting depth, 0 for top level symbols
+    pub depth: usize,
+    pub range: LspRange,
+}
+
+impl LspSymbol{
+    // flattens both the nested DocumentSymbol and the flat SymbolInformation form
+    pub fn list_from_json(value: &JsonValue)->Vec<Self>{
+        fn visit(value: &JsonValue, depth: usize, out: &mut Vec<LspSymbol>){
+            let range = value.key("selectionRange")
+                .or_else(|| value.key("location").and_then(|location| location.key("range")))
+                .and_then(LspRange::from_json);
+            let name = value.key("name").and_then(|name| name.string());
+            if let (Some(name), Some(range)) = (name, range){
+                out.push(LspSymbol{
+                    name: name.clone(),
+                    kind: value.key("kind").and_then(|kind| kind.u64()).unwrap_or(0),
+                    depth,
+                    range,
+                });
+            }
+            if let Some(children) = value.key("children").and_then(|children| children.array()){
+                for child in children{
+                    visit(child, depth + 1, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        for symbol in value.array().into_iter().flatten(){
+            visit(symbol, 0, &mut out);
+        }
+        out
+    }
+
+    pub fn kind_name(&self)->&'static str{
+        match self.kind{
+            2=>"mod",
+            5=>"class",
+            6=>"method",
+            7=>"property",
+            8=>"field",
+            10=>"enum",
+            11=>"trait",
+            12=>"fn",
+            13=>"var",
+            14=>"const",
+            22=>"variant",
+            23=>"struct",
+            26=>"type",
+            _=>"symbol"
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LspCompletionItem{
+    pub label: String,
+    pub kind: u64,
+    pub detail: Option<String>,
+    // the text to insert and the range it replaces, if the server gave one
+    pub insert_text: String,
+    pub range: Option<LspRange>,
+    pub is_snippet: bool,
+}
+
+impl LspCompletionItem{
+    // completion results are a list of items or a CompletionList
+    pub fn list_from_json(value: &JsonValue)->Vec<Self>{
+        let items = value.array().or_else(|| value.key("items").and_then(|items| items.array()));
+        items.into_iter().flatten().filter_map(|item|{
+            let label = item.key("label")?.string()?.clone();
+            let text_edit = item.key("textEdit");
+            let insert_text = text_edit.and_then(|edit| edit.key("newText"))
+                .or_else(|| item.key("insertText"))
+                .and_then(|text| text.string())
+                .cloned()
+                .unwrap_or_else(|| label.clone());
+            let range = text_edit.and_then(|edit| edit.key("range").or_else(|| edit.key("replace")))
+                .and_then(LspRange::from_json);
+            Some(LspCompletionItem{
+                kind: item.key("kind").and_then(|kind| kind.u64()).unwrap_or(0),
+                detail: item.key("detail").and_then(|detail| detail.string()).cloned(),
+                is_snippet: item.key("insertTextFormat").and_then(|format| format.u64()) == Some(2),
+                label,
+                insert_text,
+                range,
+            })
+        }).collect()
+    }
+}
+
+// hover contents come as MarkupContent, a MarkedString or a list of MarkedStrings
+pub fn hover_text_from_json(value: &JsonValue)->Option<String>{
+    let contents = value.key("contents")?;
+    let part = |value: &JsonValue|->Option<String>{
+        match value{
+            JsonValue::String(text)=>Some(text.clone()),
+            value=>value.key("value")?.string().cloned()
+        }
+    };
+    let text = match contents.array(){
+        Some(array)=>array.iter().filter_map(part).collect::<Vec<_>>().join("\n"),
+        None=>part(contents)?
+    };
+    if text.trim().is_empty(){
+        None
+    }
+    else{
+        Some(text)
+    }
+}
+
+// the first line of a hover that says something, skipping markdown fences and rules
+pub fn hover_summary(text: &str)->Option<String>{
+    text.lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty() && !line.starts_with("```") && !line.starts_with("---"))
+        .map(|line| line.to_string())
+}
+
+// diagnostics that say which version of the text they are for only apply to that version
+pub fn diagnostics_version_matches(params: &JsonValue, version: Option<i64>)->bool{
+    match params.key("version").and_then(|version| version.i64()){
+        Some(diagnostics_version)=>version == Some(diagnostics_version),
+        None=>true
+    }
+}
+
+pub fn path_to_uri(path: &Path)->String{
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/'){
+        uri.push('/');
+    }
+    for byte in path.bytes(){
+        match byte{
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':'=>uri.push(byte as char),
+            byte=>uri.push_str(&format!("%{:02X}", byte))
+        }
+    }
+    uri
+}
+
+pub fn uri_to_path(uri: &str)->Option<PathBuf>{
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::new();
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next(){
+        if byte == b'%'{
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        }
+        else{
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // windows paths look like /C:/dir
+    let path = match path.as_bytes(){
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic()=>path[1..].to_string(),
+        _=>path
+    };
+    Some(PathBuf::from(path))
+}
+
+pub fn empty_object()->JsonValue{
+    JsonValue::Object(HashMap::new())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn framed(body: &str)->String{
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    fn decode_all(decoder: &mut MessageDecoder)->Vec<Result<LspMessage, String>>{
+        std::iter::from_fn(|| decoder.next_message()).collect()
+    }
+
+    #[test]
+    fn decoder_waits_for_the_whole_message(){
+        let data = framed(r#"{"jsonrpc":"2.0","method":"initialized","params":{"text":"é😀"}}"#);
+        let mut decoder = MessageDecoder::default();
+        for split in [1, 10, data.len() - 1]{
+            decoder.push(&data.as_bytes()[..split]);
+            assert!(decoder.next_message().is_none());
+            decoder.push(&data.as_bytes()[split..]);
+            match decoder.next_message(){
+                Some(Ok(LspMessage::Notification{method, params}))=>{
+                    assert_eq!(method, "initialized");
+                    assert_eq!(params.key("text").and_then(|text| text.string()).map(|text| text.as_str()), Some("é😀"));
+                }
+                other=>panic!("unexpected {:?}", other)
+            }
+            assert!(decoder.next_message().is_none());
+        }
+    }
+
+    #[test]
+    fn decoder_splits_messages_in_one_chunk(){
+        let data = format!(
+            "{}content-length: {}\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}",
+            framed(r#"{"jsonrpc":"2.0","id":1,"result":null}"#),
+            r#"{"jsonrpc":"2.0","id":2,"result":[]}"#.len(),
+            r#"{"jsonrpc":"2.0","id":2,"result":[]}"#,
+        );
+        let mut decoder = MessageDecoder::default();
+        decoder.push(data.as_bytes());
+        let ids: Vec<u64> = decode_all(&mut decoder).into_iter().map(|message| match message{
+            Ok(LspMessage::Response{id, result: Ok(_)})=>id,
+            other=>panic!("unexpected {:?}", other)
+        }).collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn decoder_skips_a_header_without_length(){
+        let data = format!("Content-Type: text\r\n\r\n{}", framed(r#"{"jsonrpc":"2.0","id":3,"result":1}"#));
+        let mut decoder = MessageDecoder::default();
+        decoder.push(data.as_bytes());
+        let messages = decode_all(&mut decoder);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].is_err());
+        assert!(matches!(messages[1], Ok(LspMessage::Response{id: 3, ..})));
+    }
+
+    #[test]
+    fn messages_are_told_apart(){
+        let decode = |body: &str|{
+            let mut decoder = MessageDecoder::default();
+            decoder.push(framed(body).as_bytes());
+            decoder.next_message().unwrap()
+        };
+        match decode(r#"{"jsonrpc":"2.0","id":"a7","method":"workspace/configuration","params":{}}"#){
+            Ok(LspMessage::Request{id: JsonValue::String(id), method, ..})=>{
+                assert_eq!(id, "a7");
+                assert_eq!(method, "workspace/configuration");
+            }
+            other=>panic!("unexpected {:?}", other)
+        }
+        assert!(matches!(decode(r#"{"jsonrpc":"2.0","method":"$/progress"}"#), Ok(LspMessage::Notification{..})));
+        match decode(r#"{"jsonrpc":"2.0","id":4,"error":{"code":-32601,"message":"unknown method"}}"#){
+            Ok(LspMessage::Response{id: 4, result: Err(message)})=>assert_eq!(message, "unknown method"),
+            other=>panic!("unexpected {:?}", other)
+        }
+        assert!(decode(r#"{"jsonrpc":"2.0","id":"x","result":null}"#).is_err());
+        assert!(decode(r#"[1]"#).is_err());
+    }
+
+    #[test]
+    fn encoded_messages_decode_again(){
+        let message = json_object(vec![
+            ("jsonrpc", json_string("2.0")),
+            ("id", JsonValue::U64(12)),
+            ("method", json_string("textDocument/hover")),
+            ("params", json_object(vec![("text", json_string("a\"b\nc é"))])),
+        ]);
+        let mut decoder = MessageDecoder::default();
+        decoder.push(encode_message(&message).as_bytes());
+        match decoder.next_message(){
+            Some(Ok(LspMessage::Request{id, method, params}))=>{
+                assert_eq!(id.u64(), Some(12));
+                assert_eq!(method, "textDocument/hover");
+                assert_eq!(params.key("text").and_then(|text| text.string()).map(|text| text.as_str()), Some("a\"b\nc é"));
+            }
+            other=>panic!("unexpected {:?}", other)
+        }
+    }
+
+    #[test]
+    fn utf16_positions(){
+        let line = "aé😀b";
+        let encoding = PositionEncoding::Utf16;
+        // a is 1 unit, é is 1 unit, the emoji is 2 units
+        assert_eq!(encoding.to_character(line, 0), 0);
+        assert_eq!(encoding.to_character(line, 1), 1);
+        assert_eq!(encoding.to_character(line, 3), 2);
+        assert_eq!(encoding.to_character(line, 7), 4);
+        assert_eq!(encoding.to_character(line, 100), 5);
+        assert_eq!(encoding.to_byte_index(line, 2), 3);
+        assert_eq!(encoding.to_byte_index(line, 4), 7);
+        assert_eq!(encoding.to_byte_index(line, 5), 8);
+        assert_eq!(encoding.to_byte_index(line, 100), 8);
+        for (byte_index, _) in line.char_indices(){
+            assert_eq!(encoding.to_byte_index(line, encoding.to_character(line, byte_index)), byte_index);
+        }
+    }
+
+    #[test]
+    fn utf8_positions(){
+        let line = "aé😀b";
+        let encoding = PositionEncoding::Utf8;
+        assert_eq!(encoding.to_character(line, 3), 3);
+        assert_eq!(encoding.to_byte_index(line, 3), 3);
+        // halfway into a char moves back to its start
+        assert_eq!(encoding.to_byte_index(line, 2), 1);
+        assert_eq!(encoding.to_byte_index(line, 5), 3);
+        assert_eq!(encoding.to_byte_index(line, 100), 8);
+        assert_eq!(PositionEncoding::from_name("utf-8"), PositionEncoding::Utf8);
+        assert_eq!(PositionEncoding::from_name("utf-32"), PositionEncoding::Utf16);
+    }
+
+    #[test]
+    fn diagnostics_versions(){
+        let with_version = json_object(vec![("version", JsonValue::U64(3))]);
+        assert!(diagnostics_version_matches(&with_version, Some(3)));
+        assert!(!diagnostics_version_matches(&with_version, Some(4)));
+        assert!(!diagnostics_version_matches(&with_version, None));
+        assert!(diagnostics_version_matches(&empty_object(), Some(4)));
+    }
+
+    #[test]
+    fn uris_roundtrip(){
+        let path = Path::new("/home/me/my project/ü.rs");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///home/me/my%20project/%C3%BC.rs");
+        assert_eq!(uri_to_path(&uri).as_deref(), Some(path));
+        assert_eq!(uri_to_path("file:///C:/dir/a.rs"), Some(PathBuf::from("C:/dir/a.rs")));
+        assert_eq!(uri_to_path("untitled:1"), None);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod lsp_protocol;
+pub mod lsp_client;
+pub mod lsp_manager;
@@ -0,0 +1,121 @@
+use {
+    crate::{
+        file_system::file_system::FileSystem,
+        makepad_code_editor::SearchQuery,
+        makepad_file_protocol::{FileSearchQuery, FileSearchResult, FileSearchMatch},
+    },
+};
+
+#[derive(Default)]
+pub struct SearchManager{
+    pub query: FileSearchQuery,
+    search_id: u64,
+    pub results: Vec<FileSearchResult>,
+    // the rows of the result list, a file header followed by its matches
+    pub items: Vec<SearchItem>,
+    pub status: SearchStatus,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum SearchItem{
+    File{file: usize},
+    Match{file: usize, index: usize},
+}
+
+#[derive(Clone, Debug, Default)]
+pub enum SearchStatus{
+    #[default]
+    Idle,
+    Searching,
+    Done{file_count: usize, match_count: usize, truncated: bool},
+    Failed(String),
+    Replaced{file_count: usize},
+    Symbols{path: String, count: usize},
+}
+
+impl SearchManager{
+    pub fn search(&mut self, file_system: &mut FileSystem, query: FileSearchQuery){
+        if query == self.query && !matches!(self.status, SearchStatus::Replaced{..} | SearchStatus::Symbols{..}){
+            return
+        }
+        self.search_id += 1;
+        self.results.clear();
+        self.items.clear();
+        self.status = if query.text.is_empty(){SearchStatus::Idle}else{SearchStatus::Searching};
+        self.query = query.clone();
+        file_system.request_search(query, self.search_id);
+    }
+
+    pub fn handle_results(&mut self, id: u64, results: Vec<FileSearchResult>){
+        if id != self.search_id{
+            return
+        }
+        for result in results{
+            let file = self.results.len();
+            self.items.push(SearchItem::File{file});
+            for index in 0..result.matches.len(){
+                self.items.push(SearchItem::Match{file, index});
+            }
+            self.results.push(result);
+        }
+    }
+
+    pub fn handle_done(&mut self, id: u64, file_count: usize, match_count: usize, truncated: bool){
+        if id == self.search_id && !self.query.text.is_empty(){
+            self.status = SearchStatus::Done{file_count, match_count, truncated};
+        }
+    }
+
+    pub fn handle_failed(&mut self, message: String){
+        self.status = SearchStatus::Failed(message);
+    }
+
+    // replaces all matches in every file with results. The results are stale afterwards so
+    // they are cleared, searching again shows what is left
+    pub fn replace_all(&mut self, file_system: &mut FileSystem, replace: &str){
+        let query = SearchQuery{
+            text: self.query.text.clone(),
+            case_sensitive: self.query.case_sensitive,
+            whole_word: self.query.whole_word,
+            regex: self.query.regex,
+        };
+        let mut file_count = 0;
+        for result in &self.results{
+            if let Some(file_id) = file_system.path_to_file_node_id(&result.path){
+                file_system.replace_in_file(file_id, &query, replace);
+                file_count += 1;
+            }
+        }
+        file_system.handle_sessions();
+        self.search_id += 1;
+        self.results.clear();
+        self.items.clear();
+        self.status = SearchStatus::Replaced{file_count};
+    }
+
+    // shows the symbols of a file in the result list, so they can be jumped to like matches
+    pub fn show_symbols(&mut self, path: String, matches: Vec<FileSearchMatch>){
+        self.search_id += 1;
+        self.results.clear();
+        self.items.clear();
+        self.status = SearchStatus::Symbols{path: path.clone(), count: matches.len()};
+        self.handle_results(self.search_id, vec![FileSearchResult{path, matches}]);
+    }
+
+    pub fn status_text(&self)->String{
+        match &self.status{
+            SearchStatus::Idle=>String::new(),
+            SearchStatus::Searching=>"Searching...".to_string(),
+            SearchStatus::Done{match_count: 0, ..}=>"No results".to_string(),
+            SearchStatus::Done{file_count, match_count, truncated}=>format!(
+                "{}{} results in {} files",
+                if *truncated{"More than "}else{""},
+                match_count,
+                file_count
+            ),
+            SearchStatus::Failed(message)=>message.clone(),
+            SearchStatus::Replaced{file_count}=>format!("Replaced in {} files", file_count),
+            SearchStatus::Symbols{path, count}=>format!("{} symbols in {}", count, path),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use {
+    crate::{
+        makepad_platform::studio::JumpToFile,
+        app::{AppAction, AppData},
+        search::search_manager::{SearchManager, SearchItem},
+        makepad_widgets::*,
+    },
+};
+
+live_design!{
+    import makepad_draw::shader::std::*;
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+
+    SearchItem = <View> {
+        height: Fit, width: Fill
+        padding: <THEME_MSPACE_1> {}
+        show_bg: true,
+        draw_bg: {
+            instance is_even: 0.0
+            fn pixel(self) -> vec4 {
+                return mix(
+                    THEME_COLOR_BG_EVEN,
+                    THEME_COLOR_BG_ODD,
+                    self.is_even
+                );
+            }
+        }
+        flow = <TextFlow>{
+            width: Fill,
+            height: Fit
+        }
+    }
+
+    SearchResults = {{SearchResults}}{
+        height: Fill, width: Fill,
+        list = <PortalList> {
+            capture_overload: false,
+            grab_key_focus: false
+            drag_scrolling: false
+            height: Fill, width: Fill,
+            flow: Down
+            File = <SearchItem> {
+                padding: {left: (THEME_SPACE_2), top: (THEME_SPACE_2), bottom: (THEME_SPACE_1)}
+            }
+            Match = <SearchItem> {
+                padding: {left: 20, top: (THEME_SPACE_1), bottom: (THEME_SPACE_1)}
+            }
+        }
+    }
+}
+
+#[derive(Live, LiveHook, Widget)]
+pub struct SearchResults{
+    #[deref] view:View
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResultLink{item_id:usize}
+
+// context shown around a match
+const MAX_PREFIX_CHARS: usize = 40;
+const MAX_SUFFIX_CHARS: usize = 160;
+
+impl SearchResults{
+    fn draw_results(&mut self, cx: &mut Cx2d, list:&mut PortalList, search_manager:&SearchManager){
+        list.set_item_range(cx, 0, search_manager.items.len());
+        let mut location = String::new();
+        while let Some(item_id) = list.next_visible_item(cx) {
+            let Some(item) = search_manager.items.get(item_id) else{
+                continue
+            };
+            let is_even = item_id & 1 == 0;
+            let template = match item{
+                SearchItem::File{..}=>live_id!(File),
+                SearchItem::Match{..}=>live_id!(Match),
+            };
+            let mut item_view = list.item(cx, item_id, template).as_view();
+            item_view.apply_over(cx, live!{
+                draw_bg: {is_even: (if is_even {1.0} else {0.0})}
+            });
+            while let Some(step) = item_view.draw(cx, &mut Scope::empty()).step(){
+                if let Some(mut tf) = step.as_text_flow().borrow_mut(){
+                    match *item{
+                        SearchItem::File{file}=>{
+                            let result = &search_manager.results[file];
+                            tf.bold.push();
+                            tf.draw_link(cx, live_id!(link), SearchResultLink{item_id}, &result.path);
+                            tf.bold.pop();
+                            fmt_over!(location, " ({})", result.matches.len());
+                            tf.draw_text(cx, &location);
+                        }
+                        SearchItem::Match{file, index}=>{
+                            let found = &search_manager.results[file].matches[index];
+                            fmt_over!(location, "{}:{}", found.line + 1, found.start + 1);
+                            tf.draw_link(cx, live_id!(link), SearchResultLink{item_id}, &location);
+                            // the offsets are into the file, which can differ from the text
+                            // after invalid UTF-8
+                            let line = found.text.as_str();
+                            let start = floor_char_boundary(line, found.start);
+                            let end = floor_char_boundary(line, found.end).max(start);
+                            let prefix = line[..start].trim_start();
+                            let prefix_chars = prefix.chars().count();
+                            if prefix_chars > MAX_PREFIX_CHARS{
+                                let skip = prefix.char_indices().nth(prefix_chars - MAX_PREFIX_CHARS).unwrap().0;
+                                tf.draw_text(cx, "...");
+                                tf.draw_text(cx, &prefix[skip..]);
+                            }
+                            else{
+                                tf.draw_text(cx, prefix);
+                            }
+                            tf.bold.push();
+                            tf.draw_text(cx, &line[start..end]);
+                            tf.bold.pop();
+                            let suffix = &line[end..];
+                            match suffix.char_indices().nth(MAX_SUFFIX_CHARS){
+                                Some((cut, _))=>{
+                                    tf.draw_text(cx, &suffix[..cut]);
+                                    tf.draw_text(cx, "...");
+                                }
+                                None=>tf.draw_text(cx, suffix)
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Widget for SearchResults {
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope:&mut Scope, walk:Walk)->DrawStep{
+        while let Some(step) = self.view.draw_walk(cx, scope, walk).step(){
+            if let Some(mut list) = step.as_portal_list().borrow_mut(){
+                self.draw_results(cx, &mut *list, &scope.data.get::<AppData>().unwrap().search_manager)
+            }
+        }
+        DrawStep::done()
+    }
+
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope){
+        let list = self.view.portal_list(id!(list));
+        self.view.handle_event(cx, event, scope);
+        let data = scope.data.get::<AppData>().unwrap();
+        if let Event::Actions(actions) = event{
+            if list.any_items_with_actions(&actions) {
+                for link in actions.filter_actions_data::<SearchResultLink>(){
+                    let search_manager = &data.search_manager;
+                    let (file, line, column) = match search_manager.items.get(link.item_id){
+                        Some(SearchItem::File{file})=>(*file, 0, 0),
+                        Some(SearchItem::Match{file, index})=>{
+                            let found = &search_manager.results[*file].matches[*index];
+                            (*file, found.line, found.start)
+                        }
+                        None=>continue
+                    };
+                    cx.action(AppAction::JumpTo(JumpToFile{
+                        file_name: search_manager.results[file].path.clone(),
+                        line: line as u32,
+                        column: column as u32
+                    }));
+                }
+            }
+        }
+    }
+}
+
+impl SearchResultsRef{
+    pub fn reset_scroll(&self, cx:&mut Cx){
+        if let Some(inner) = self.borrow_mut() {
+            let list = inner.view.portal_list(id!(list));
+            list.set_first_id_and_scroll(0,0.0);
+            list.redraw(cx);
+        }
+    }
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
@@ -1,9 +1,14 @@
-
 use {
     crate::{
-        app::{AppData},
+        app::{AppData, AppAction},
         makepad_widgets::*,
-        makepad_code_editor::CodeEditor,
+        makepad_code_editor::{
+            CodeEditor,
+            CodeSession,
+            code_editor::CodeEditorAction,
+            completion::LiveDesignCompletionProvider,
+            find_bar::{FindBar, FindBarAction},
+        },
         file_system::file_system::EditSession,
     },
     std::{
@@ -12,17 +17,55 @@ use {
 };
 
 live_design!{
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
     import makepad_code_editor::code_editor::CodeEditor;
-    
+    import makepad_code_editor::find_bar::FindBar;
+
+    ConflictBarButton = <ButtonFlat> {
+        width: Fit, height: Fit,
+        padding: {left: 6, right: 6, top: 3, bottom: 3}
+        margin: 0
+    }
+
     StudioCodeEditor = {{StudioCodeEditor}}{
+        conflict_bar: <View>{
+            width: Fill, height: Fit,
+            flow: Right,
+            spacing: 4,
+            align: {y: 0.5}
+            padding: {left: 8, right: 8, top: 4, bottom: 4}
+            show_bg: true,
+            draw_bg: {color: #5a3a1a}
+            message = <Label>{
+                width: Fill, height: Fit,
+                text: "This file changed on disk while it has unsaved edits"
+            }
+            keep = <ConflictBarButton>{text: "Keep mine"}
+            reload = <ConflictBarButton>{text: "Reload"}
+            diff = <ConflictBarButton>{text: "Diff"}
+        }
+        find_bar: <FindBar>{}
         editor: <CodeEditor>{
         }
     }
-} 
- 
-#[derive(Live, LiveHook, Widget)] 
+}
+
+#[derive(Live, Widget)]
 pub struct StudioCodeEditor{
-    #[wrap] #[live] pub editor: CodeEditor
+    #[wrap] #[live] pub editor: CodeEditor,
+    // shown while the file changed on disk and the editor has edits that aren't on it
+    #[live] conflict_bar: View,
+    #[live] find_bar: FindBar,
+    #[rust] find_bar_open: bool,
+}
+
+impl LiveHook for StudioCodeEditor{
+    fn after_new_from_doc(&mut self, cx: &mut Cx){
+        // the widgets studio knows about are the ones its users design with
+        let provider = LiveDesignCompletionProvider::from_registry(&cx.live_registry.borrow());
+        self.editor.add_completion_provider(Box::new(provider));
+    }
 }
 
 impl Widget for StudioCodeEditor {
@@ -30,24 +73,149 @@ impl Widget for StudioCodeEditor {
         // alright we have a scope, and an id, so now we can properly draw the editor.
         let session_id = scope.path.from_end(1);
         let app_scope = scope.data.get_mut::<AppData>().unwrap();
+        let conflict = app_scope.file_system.tab_id_to_file_node_id.get(&session_id)
+            .is_some_and(|file_id| app_scope.file_system.conflicts.contains_key(file_id));
         if let Some(EditSession::Code(session)) = app_scope.file_system.get_session_mut(session_id){
-            self.editor.draw_walk_editor(cx, session, walk);
+            if self.find_bar_open || conflict{
+                cx.begin_turtle(walk, Layout::flow_down());
+                if conflict{
+                    let _ = self.conflict_bar.draw_walk(cx, &mut Scope::empty(), Walk::fill_fit());
+                }
+                if self.find_bar_open{
+                    let _ = self.find_bar.draw_walk(cx, &mut Scope::empty(), Walk::fill_fit());
+                }
+                self.editor.draw_walk_editor(cx, session, Walk::fill());
+                cx.end_turtle();
+            }
+            else{
+                self.editor.draw_walk_editor(cx, session, walk);
+            }
         }
         else{
             self.editor.draw_empty_editor(cx, walk);
         }
         DrawStep::done()
     }
-    
+
     fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope){
         let session_id = scope.path.from_end(1);
         let data = scope.data.get_mut::<AppData>().unwrap();
         let uid = self.widget_uid();
+        if let Some(file_id) = data.file_system.tab_id_to_file_node_id.get(&session_id).cloned(){
+            // format and lint the live_design!{} of the file
+            if let Event::KeyDown(ke) = event{
+                if (ke.modifiers.control || ke.modifiers.logo) && ke.modifiers.shift && cx.has_key_focus(self.editor.area()){
+                    match ke.key_code{
+                        KeyCode::KeyL=>cx.action(AppAction::FormatLiveDesign(file_id)),
+                        KeyCode::KeyK=>cx.action(AppAction::LintLiveDesign(file_id)),
+                        _=>()
+                    }
+                }
+            }
+            if data.file_system.conflicts.contains_key(&file_id){
+                let actions = cx.capture_actions(|cx| self.conflict_bar.handle_event(cx, event, &mut Scope::empty()));
+                // every tab of the file shows the bar, they all go without it
+                if self.conflict_bar.button(id!(keep)).clicked(&actions){
+                    data.file_system.resolve_conflict(file_id, true);
+                    cx.action(AppAction::RedrawFile(file_id));
+                }
+                else if self.conflict_bar.button(id!(reload)).clicked(&actions){
+                    data.file_system.resolve_conflict(file_id, false);
+                    cx.action(AppAction::RedrawFile(file_id));
+                }
+                else if self.conflict_bar.button(id!(diff)).clicked(&actions){
+                    cx.action(AppAction::ShowDiskDiff(file_id));
+                }
+            }
+        }
         if let Some(EditSession::Code(session)) = data.file_system.get_session_mut(session_id){
-            for action in self.editor.handle_event(cx, event, &mut Scope::empty(), session){
-                cx.widget_action(uid, &scope.path, action);
+            let mut actions = Vec::new();
+            if let Event::MacosMenuCommand(command) = event{
+                if cx.has_key_focus(self.editor.area()){
+                    if *command == live_id!(find){
+                        actions.push(CodeEditorAction::Find);
+                    }
+                    else if *command == live_id!(replace){
+                        actions.push(CodeEditorAction::Replace);
+                    }
+                }
+            }
+            if self.find_bar_open{
+                let find_actions = cx.capture_actions(|cx| self.find_bar.handle_event(cx, event, &mut Scope::empty()));
+                let find_uid = self.find_bar.widget_uid();
+                for action in find_actions.filter_widget_actions_cast::<FindBarAction>(find_uid){
+                    if self.handle_find_bar_action(cx, session, action){
+                        actions.push(CodeEditorAction::TextDidChange);
+                    }
+                }
+            }
+            actions.extend(self.editor.handle_event(cx, event, &mut Scope::empty(), session));
+            for action in actions{
+                match action{
+                    CodeEditorAction::Find | CodeEditorAction::Replace=>{
+                        self.open_find_bar(cx, session, matches!(action, CodeEditorAction::Replace));
+                    }
+                    action=>cx.widget_action(uid, &scope.path, action)
+                }
             }
             data.file_system.handle_sessions();
         }
     }
-}
\ No newline at end of file
+}
+
+impl StudioCodeEditor{
+    fn open_find_bar(&mut self, cx: &mut Cx, session: &mut CodeSession, replace: bool){
+        self.find_bar_open = true;
+        self.find_bar.open(cx, replace, session.search_seed());
+        let result = session.set_search_query(Some(&self.find_bar.query(cx)));
+        self.find_bar.set_status(cx, &result, session.current_search_match_index());
+        self.editor.redraw(cx);
+    }
+
+    // returns true if the action changed the text
+    fn handle_find_bar_action(&mut self, cx: &mut Cx, session: &mut CodeSession, action: FindBarAction)->bool{
+        let mut text_changed = false;
+        match action{
+            FindBarAction::QueryChanged(query)=>{
+                if let Ok(count) = session.set_search_query(Some(&query)){
+                    if count > 0 && session.find_nearest(){
+                        self.editor.show_search_result(cx);
+                    }
+                }
+            }
+            FindBarAction::Next=>{
+                self.editor.find_next(cx, session, false);
+            }
+            FindBarAction::Previous=>{
+                self.editor.find_next(cx, session, true);
+            }
+            FindBarAction::SelectAll=>{
+                if session.select_all_matches(){
+                    self.editor.set_key_focus(cx);
+                    self.editor.show_search_result(cx);
+                }
+            }
+            FindBarAction::Replace(text)=>{
+                text_changed = session.current_search_match_index().is_some();
+                session.replace(&text);
+                self.editor.show_search_result(cx);
+            }
+            FindBarAction::ReplaceAll(text)=>{
+                text_changed = session.replace_all(&text) > 0;
+                self.editor.redraw(cx);
+            }
+            FindBarAction::Close=>{
+                self.find_bar_open = false;
+                self.find_bar.close(cx);
+                let _ = session.set_search_query(None);
+                self.editor.set_key_focus(cx);
+                self.editor.redraw(cx);
+                return false
+            }
+            FindBarAction::None=>()
+        }
+        let result = session.set_search_query(Some(&self.find_bar.query(cx)));
+        self.find_bar.set_status(cx, &result, session.current_search_match_index());
+        text_changed
+    }
+}
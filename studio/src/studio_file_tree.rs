@@ -2,6 +2,7 @@
 use {
     crate::{
         app::{AppData},
+        git::git_repo::GitFileStatus,
         makepad_widgets::*,
         makepad_widgets::file_tree::FileTree,
     },
@@ -12,22 +13,44 @@ live_design!{
         
     StudioFileTree = {{StudioFileTree}}{
         file_tree: <FileTree>{}
+        modified_color: #E2C08D
+        added_color: #81B88B
+        untracked_color: #73C991
+        deleted_color: #C74E39
+        conflicted_color: #E4676B
     }
 }
  
 #[derive(Live, LiveHook, Widget)] 
 pub struct StudioFileTree{
-    #[wrap] #[live] pub file_tree: FileTree
+    #[wrap] #[live] pub file_tree: FileTree,
+    // names are tinted by their git status
+    #[live] modified_color: Vec4,
+    #[live] added_color: Vec4,
+    #[live] untracked_color: Vec4,
+    #[live] deleted_color: Vec4,
+    #[live] conflicted_color: Vec4,
 }
 
 impl Widget for StudioFileTree {
     fn draw_walk(&mut self, cx: &mut Cx2d, scope:&mut Scope, walk:Walk)->DrawStep{
         while self.file_tree.draw_walk(cx, scope, walk).is_step() {
             self.file_tree.set_folder_is_open(cx, live_id!(root).into(), true, Animate::No);
-            scope.data.get_mut::<AppData>().unwrap().file_system.draw_file_node(
+            let data = scope.data.get_mut::<AppData>().unwrap();
+            let git_manager = &data.git_manager;
+            let tint = |file_id| match git_manager.node_status(file_id){
+                Some(GitFileStatus::Modified) | Some(GitFileStatus::Renamed)=>self.modified_color,
+                Some(GitFileStatus::Added)=>self.added_color,
+                Some(GitFileStatus::Untracked)=>self.untracked_color,
+                Some(GitFileStatus::Deleted)=>self.deleted_color,
+                Some(GitFileStatus::Conflicted)=>self.conflicted_color,
+                None=>Vec4::default()
+            };
+            data.file_system.draw_file_node(
                 cx,
                 live_id!(root).into(),
-                &mut self.file_tree
+                &mut self.file_tree,
+                &tint
             );
         }
         DrawStep::done()
@@ -36,4 +59,4 @@ impl Widget for StudioFileTree {
     fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope){
         self.file_tree.handle_event(cx, event, scope);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,124 @@
+
+use {
+    crate::{
+        app::{AppAction, AppData},
+        build_manager::{
+            build_protocol::TestState,
+            test_runner::{combine_test_states, TestItem},
+        },
+        makepad_platform::studio::JumpToFile,
+        makepad_widgets::*,
+        makepad_widgets::file_tree::{FileTree, FileTreeAction},
+    },
+    std::collections::BTreeMap,
+};
+
+live_design!{
+    import makepad_widgets::theme_desktop_dark::*;
+
+    TestList = {{TestList}}{
+        file_tree: <FileTree>{}
+        running_color: #9CDCFE
+        passed_color: #73C991
+        failed_color: #F14C4C
+        ignored_color: #CCA700
+    }
+}
+
+// the tests of every test run as a tree of their modules, tinted by how they did
+#[derive(Live, LiveHook, Widget)]
+pub struct TestList{
+    #[wrap] #[live] pub file_tree: FileTree,
+    #[live] running_color: Vec4,
+    #[live] passed_color: Vec4,
+    #[live] failed_color: Vec4,
+    #[live] ignored_color: Vec4,
+}
+
+#[derive(Default)]
+struct TestNode<'a>{
+    children: BTreeMap<&'a str, TestNode<'a>>,
+    test: Option<usize>,
+}
+
+fn test_node_id(test: &TestItem)->LiveId{
+    LiveId::from_str(&format!("{}/{}", test.process.binary, test.name))
+}
+
+impl TestList{
+    fn tint(&self, state: Option<TestState>)->Vec4{
+        match state{
+            Some(TestState::Running)=>self.running_color,
+            Some(TestState::Passed)=>self.passed_color,
+            Some(TestState::Failed)=>self.failed_color,
+            Some(TestState::Ignored)=>self.ignored_color,
+            None=>Vec4::default()
+        }
+    }
+
+    fn node_state(node: &TestNode, tests: &[TestItem])->Option<TestState>{
+        node.test.map(|test| tests[test].state).into_iter()
+            .chain(node.children.values().filter_map(|child| Self::node_state(child, tests)))
+            .reduce(combine_test_states)
+    }
+
+    fn draw_node(&mut self, cx: &mut Cx2d, tests: &[TestItem], path: &str, name: &str, node: &TestNode){
+        let node_id = LiveId::from_str(path);
+        let tint = self.tint(Self::node_state(node, tests));
+        if node.children.is_empty(){
+            self.file_tree.file_tinted(cx, node_id, name, tint);
+            return
+        }
+        if self.file_tree.begin_folder_tinted(cx, node_id, name, tint).is_ok(){
+            for (child_name, child) in &node.children{
+                let separator = if path.contains('/'){"::"}else{"/"};
+                self.draw_node(cx, tests, &format!("{}{}{}", path, separator, child_name), child_name, child);
+            }
+            self.file_tree.end_folder();
+        }
+    }
+}
+
+impl Widget for TestList {
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope:&mut Scope, walk:Walk)->DrawStep{
+        while self.file_tree.draw_walk(cx, scope, walk).is_step() {
+            let tests = &scope.data.get::<AppData>().unwrap().build_manager.test_runner.tests;
+            // a folder per process with the modules of the test names in it
+            let mut processes: BTreeMap<&str, TestNode> = BTreeMap::new();
+            for (index, test) in tests.iter().enumerate(){
+                let mut node = processes.entry(test.process.binary.as_str()).or_default();
+                for part in test.name.split("::"){
+                    node = node.children.entry(part).or_default();
+                }
+                node.test = Some(index);
+            }
+            for (binary, node) in &processes{
+                self.file_tree.set_folder_is_open(cx, LiveId::from_str(binary), true, Animate::No);
+                self.draw_node(cx, tests, binary, binary, node);
+            }
+        }
+        DrawStep::done()
+    }
+
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope){
+        self.file_tree.handle_event(cx, event, scope);
+        if let Event::Actions(actions) = event{
+            if let Some(action) = actions.find_widget_action(self.file_tree.widget_uid()){
+                if let FileTreeAction::FileClicked(node_id) = action.cast(){
+                    let test_runner = &mut scope.data.get_mut::<AppData>().unwrap().build_manager.test_runner;
+                    let Some(test) = test_runner.tests.iter().find(|test| test_node_id(test) == node_id) else{
+                        return
+                    };
+                    if let Some((file_name, pos)) = &test.location{
+                        cx.action(AppAction::JumpTo(JumpToFile{
+                            file_name: file_name.clone(),
+                            line: pos.line_index as u32,
+                            column: pos.byte_index as u32
+                        }));
+                    }
+                    test_runner.selected = Some((test.process.clone(), test.name.clone()));
+                }
+            }
+        }
+    }
+}
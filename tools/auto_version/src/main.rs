@@ -1,7 +1,7 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use makepad_toml_parser::Toml;
+use makepad_toml_parser::{Toml, toml_string};
 use makepad_digest::sha1;
 use makepad_base64::base64;
 use std::io::prelude::*;
@@ -181,17 +181,8 @@ fn patch_cargo(cargo: &Path, toml_path: &str, with: &str, write: bool) {
     let toml = makepad_toml_parser::parse_toml(&old_cargo).unwrap();
     
     if let Some(Toml::Str(_, span)) = toml.get(toml_path) {
-        let mut new_cargo = String::new();
-        for (i, c) in old_cargo.chars().enumerate() {
-            if i == span.start {
-                for c in with.chars() {
-                    new_cargo.push(c);
-                }
-            }
-            if i < span.start || i >= span.start + span.len - 2 {
-                new_cargo.push(c);
-            }
-        }
+        // spans cover the quotes, so the new value goes in as a complete string
+        let new_cargo = format!("{}{}{}", &old_cargo[..span.start], toml_string(with), &old_cargo[span.end()..]);
         // lets write it back to disk
         if write {
             fs::File::create(cargo).unwrap().write_all(new_cargo.as_bytes()).unwrap();
@@ -1,4 +1,5 @@
 use std::path::{Path, PathBuf};
+use makepad_miniz::zip_file::*;
 use crate::android::{HostOs, AndroidTarget};
 use crate::utils::*;
 use crate::makepad_shell::*;
@@ -16,10 +17,6 @@ fn apksigner_jar_path(sdk_dir: &Path) -> PathBuf {
     sdk_dir.join(BUILD_TOOLS_DIR).join(ANDROID_BUILD_TOOLS_VERSION).join("lib/apksigner.jar")
 }
 
-fn zipalign_path(sdk_dir: &Path) -> PathBuf {
-    sdk_dir.join(BUILD_TOOLS_DIR).join(ANDROID_BUILD_TOOLS_VERSION).join("zipalign")
-}
-
 fn android_jar_path(sdk_dir: &Path) -> PathBuf {
     sdk_dir.join(PLATFORMS_DIR).join(ANDROID_PLATFORM).join("android.jar")
 }
@@ -348,10 +345,11 @@ fn build_unaligned_apk(sdk_dir: &Path, build_paths: &BuildPaths) -> Result<(), S
     Ok(())
 }
 
-fn add_rust_library(sdk_dir: &Path, underscore_target: &str, build_paths: &BuildPaths, android_targets: &[AndroidTarget], args: &[String]) -> Result<PathBuf, String> {
+fn add_rust_library(underscore_target: &str, build_paths: &BuildPaths, android_targets: &[AndroidTarget], args: &[String]) -> Result<(PathBuf, Vec<String>), String> {
     let cwd = std::env::current_dir().unwrap();
     let profile = get_profile_from_args(args);
     let mut build_dir = None;
+    let mut libraries = Vec::new();
     for android_target in android_targets {
         let abi = android_target.abi_identifier();
         mkdir(&build_paths.out_dir.join(format!("lib/{abi}"))) ?;
@@ -365,18 +363,13 @@ fn add_rust_library(sdk_dir: &Path, underscore_target: &str, build_paths: &Build
         build_dir = Some(cwd.join(format!("target/{android_target_dir}/{profile}")));
         let dst_lib = build_paths.out_dir.join(binary_path.clone());
         cp(&src_lib, &dst_lib, false) ?;
-
-        shell_env_cap(&[], &build_paths.out_dir, aapt_path(sdk_dir).to_str().unwrap(), &[
-            "add",
-            (build_paths.dst_unaligned_apk.to_str().unwrap()),
-            &binary_path,
-        ]) ?;
+        libraries.push(binary_path);
     }
 
-    Ok(build_dir.unwrap())
+    Ok((build_dir.unwrap(), libraries))
 }
 
-fn add_resources(sdk_dir: &Path, build_crate: &str, build_paths: &BuildPaths, build_dir:&Path, android_targets:&[AndroidTarget]) -> Result<(), String> {
+fn add_resources(build_crate: &str, build_paths: &BuildPaths, build_dir:&Path, android_targets:&[AndroidTarget]) -> Result<Vec<String>, String> {
     let mut assets_to_add: Vec<String> = Vec::new();
     
     let build_crate_dir = get_crate_dir(build_crate) ?;
@@ -407,34 +400,55 @@ fn add_resources(sdk_dir: &Path, build_crate: &str, build_paths: &BuildPaths, bu
     
             let assets = ls(&dst_dir) ?;
             for path in &assets {
-                let path = path.display().to_string();
+                let path = path.display().to_string().replace("\\","/");
                 assets_to_add.push(format!("assets/makepad/{name}/resources/{path}"));
             }
         }
     }
 
-    let mut aapt_args = vec![
-        "add",
-        build_paths.dst_unaligned_apk.to_str().unwrap(),
-    ];
-    for asset in &assets_to_add {  
-        aapt_args.push(asset);
-    }
-
-    shell_env_cap(&[], &build_paths.out_dir, aapt_path(sdk_dir).to_str().unwrap(), &aapt_args) ?;
-
-    Ok(())
+    Ok(assets_to_add)
 }
 
-fn build_zipaligned_apk(sdk_dir: &Path, build_paths: &BuildPaths) -> Result<(), String> {
-    shell_env_cap(&[], &build_paths.out_dir, zipalign_path(sdk_dir).to_str().unwrap(), &[
-       "-v",
-       "-f",
-       "4",
-       (build_paths.dst_unaligned_apk.to_str().unwrap()),
-       (build_paths.dst_apk.to_str().unwrap()),
-    ]) ?;
+// files that are already compressed go in stored, the platform can then mmap them straight out of the apk
+fn is_stored_in_apk(path: &str) -> bool {
+    let ext = path.rsplit('.').next().unwrap_or("").to_lowercase();
+    path.ends_with(".so") || matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "gif" | "webp" | "mp3" | "mp4" | "ogg" | "m4a" | "zip" | "gz" | "arsc")
+}
 
+// copies the aapt output into the final apk, adds the libraries and assets and aligns stored
+// entries the way zipalign does: 4 bytes, and page aligned for native libraries
+fn build_aligned_apk(build_paths: &BuildPaths, files: &[String]) -> Result<(), String> {
+    let mut unaligned = std::fs::File::open(&build_paths.dst_unaligned_apk)
+        .map_err(|e| format!("Cannot open {:?}: {e}", build_paths.dst_unaligned_apk))?;
+    let directory = zip_read_central_directory(&mut unaligned)
+        .map_err(|e| format!("Cannot read {:?}: {e:?}", build_paths.dst_unaligned_apk))?;
+    let apk = std::fs::File::create(&build_paths.dst_apk)
+        .map_err(|e| format!("Cannot create {:?}: {e}", build_paths.dst_apk))?;
+    let mut writer = ZipWriter::new(std::io::BufWriter::new(apk));
+    
+    for header in &directory.file_headers {
+        if files.contains(&header.file_name) {
+            continue;
+        }
+        writer.copy_entry(header, &mut unaligned, 4)
+            .map_err(|e| format!("Cannot copy {} into apk: {e:?}", header.file_name))?;
+    }
+    for file in files {
+        let data = std::fs::read(build_paths.out_dir.join(file))
+            .map_err(|e| format!("Cannot read {file}: {e}"))?;
+        let options = if file.ends_with(".so") {
+            ZipEntryOptions::stored().with_alignment(4096)
+        }
+        else if is_stored_in_apk(file) {
+            ZipEntryOptions::stored().with_alignment(4)
+        }
+        else {
+            ZipEntryOptions::default()
+        };
+        writer.add_entry(file, &data, &options)
+            .map_err(|e| format!("Cannot add {file} to apk: {e:?}"))?;
+    }
+    writer.finish().map_err(|e| format!("Cannot write {:?}: {e:?}", build_paths.dst_apk))?;
     Ok(())
 }
 
@@ -482,9 +496,9 @@ pub fn build(sdk_dir: &Path, host_os: HostOs, package_name: Option<String>, app_
     println!("Building APK");
     build_dex(sdk_dir, &build_paths)?;
     build_unaligned_apk(sdk_dir, &build_paths)?;
-    let build_dir = add_rust_library(sdk_dir, &underscore_build_crate, &build_paths, android_targets, args)?;
-    add_resources(sdk_dir, build_crate, &build_paths, &build_dir, android_targets)?;
-    build_zipaligned_apk(sdk_dir, &build_paths)?;
+    let (build_dir, mut files) = add_rust_library(&underscore_build_crate, &build_paths, android_targets, args)?;
+    files.extend(add_resources(build_crate, &build_paths, &build_dir, android_targets)?);
+    build_aligned_apk(&build_paths, &files)?;
     sign_apk(sdk_dir, &build_paths)?;
 
     println!("Compile APK completed");
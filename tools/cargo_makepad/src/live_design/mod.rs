@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+use makepad_live_compiler::*;
+
+fn collect_rs_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let Ok(entries) = std::fs::read_dir(path) else {return};
+        let mut entries: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+        entries.sort();
+        for entry in entries {
+            let name = entry.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            if name.starts_with('.') || name == "target" {
+                continue;
+            }
+            collect_rs_files(&entry, files);
+        }
+    }
+    else if path.extension().is_some_and(|e| e == "rs") {
+        files.push(path.to_path_buf());
+    }
+}
+
+fn files_from_args(args: &[String]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let paths: Vec<&String> = args.iter().filter(|a| !a.starts_with("--")).collect();
+    if paths.is_empty() {
+        collect_rs_files(&std::env::current_dir().unwrap(), &mut files);
+    }
+    for path in paths {
+        collect_rs_files(Path::new(path), &mut files);
+    }
+    files
+}
+
+pub fn handle_fmt(args: &[String]) -> Result<(), String> {
+    let mut options = LiveFormatOptions::default();
+    let mut check = false;
+    for arg in args {
+        if arg == "--check" {
+            check = true;
+        }
+        else if arg == "--properties-first" {
+            options.property_order = LivePropertyOrder::PropertiesFirst;
+        }
+        else if let Some(indent) = arg.strip_prefix("--indent=") {
+            options.indent = indent.parse().map_err(|_| format!("Invalid indent {}", indent))?;
+        }
+        else if arg.starts_with("--") {
+            return Err(format!("Unknown option {}", arg))
+        }
+    }
+    let mut unformatted = 0;
+    for file in files_from_args(args) {
+        let Ok(source) = std::fs::read_to_string(&file) else {continue};
+        if !source.contains("live_design!") {
+            continue;
+        }
+        match format_live_design(&source, &options) {
+            Ok(formatted) if formatted != source => {
+                if check {
+                    println!("{} is not formatted", file.display());
+                }
+                else if let Err(e) = std::fs::write(&file, formatted) {
+                    return Err(format!("Cannot write {}: {}", file.display(), e))
+                }
+                unformatted += 1;
+            }
+            Ok(_) => (),
+            Err(err) => println!("{}", err.into_live_file_error(&file.display().to_string()))
+        }
+    }
+    if check && unformatted > 0 {
+        return Err(format!("{} files need formatting", unformatted))
+    }
+    Ok(())
+}
+
+// only what the tokens tell, unknown properties need the LiveTypeInfos of a compiled app
+pub fn handle_lint(args: &[String]) -> Result<(), String> {
+    let mut count = 0;
+    for file in files_from_args(args) {
+        let Ok(source) = std::fs::read_to_string(&file) else {continue};
+        if !source.contains("live_design!") {
+            continue;
+        }
+        match lint_live_design(&source, LiveFileId::new(0)) {
+            Ok(lints) => for lint in lints {
+                println!("{}:{}", file.display(), lint);
+                count += 1;
+            }
+            Err(err) => println!("{}", err.into_live_file_error(&file.display().to_string()))
+        }
+    }
+    if count > 0 {
+        return Err(format!("{} warnings", count))
+    }
+    Ok(())
+}
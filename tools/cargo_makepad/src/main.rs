@@ -3,10 +3,12 @@ mod wasm;
 mod utils;
 mod apple;
 mod check;
+mod live_design;
 use android::*;
 use wasm::*;
 use apple::*;
 use check::*;
+use live_design::*;
 pub use makepad_shell;
 pub use makepad_http;
 pub use makepad_wasm_strip;
@@ -76,6 +78,19 @@ println!("    wasm [options] run <cargo args>              Build and run a wasm
     println!("    android [options] expand-sdk");
     println!("    android [options] remove-sdk-sources");
     println!();
+    println!("live_design commands:");
+    println!();
+    println!("    fmt [options] [paths]                        Format the live_design! blocks of the .rs files in paths (default: the current directory)");
+    println!("    lint [paths]                                 Report unused imports and ids set twice in live_design! blocks");
+    println!("                                                 Unknown properties are not checked here, they need the widget types compiled");
+    println!("                                                 into an app: the Lint action in studio checks them for the files of studio itself");
+    println!();
+    println!("    [options] with its default value:");
+    println!();
+    println!("       --check                                   Only list the files that are not formatted");
+    println!("       --properties-first                        Move properties above the child widgets in each block");
+    println!("       --indent=4                                The number of spaces per indent");
+    println!();
     println!("Linux commands:");
     println!();
     println!("    linux apt-get-install-makepad-deps           Call apt-get install with all dependencies needed for makepad.");
@@ -98,7 +113,7 @@ fn main() {
         args
     };
 
-    if args.len() <= 1 {
+    if args.is_empty() || args.len() <= 1 && args[0] != "fmt" && args[0] != "lint" {
         return show_help("not enough arguments");
     }
     match args[0].as_ref(){
@@ -114,6 +129,12 @@ fn main() {
         "check" => if let Err(e) = handle_check(&args[1..]){
             println!("Got error: {}", e);
         }
+        "fmt" => if let Err(e) = handle_fmt(&args[1..]){
+            println!("Got error: {}", e);
+        }
+        "lint" => if let Err(e) = handle_lint(&args[1..]){
+            println!("Got error: {}", e);
+        }
         _=> show_help("not implemented yet")
     }
 }
\ No newline at end of file
@@ -0,0 +1,398 @@
+use crate::{
+    makepad_derive_widget::*,
+    makepad_draw::*,
+    widget::*,
+};
+
+live_design! {
+    AdaptiveViewBase = {{AdaptiveView}} {}
+}
+
+/// A container that shows one of several child variants, picked by the size of the window,
+/// its orientation and the OS.
+///
+/// ```
+/// <AdaptiveView> {
+///     breakpoints: {
+///         Mobile: {max_width: 600, os: Mobile}
+///         Tablet: {min_width: 600, max_width: 1100}
+///     }
+///     Mobile = <View> { ... }
+///     Tablet = <View> { ... }
+///     Desktop = <View> { ... }
+/// }
+/// ```
+///
+/// The variants with a breakpoint are tried in the order they are declared, a variant without one
+/// is used when none of them match. The variant is picked again when the window geometry changes.
+/// Switching builds the new variant from scratch and replaces the old one. Widgets without child
+/// widgets whose id occurs once in the new variant are moved over from the old one when it has a
+/// widget of the same type with that id, so their state, like the text of a text input, is kept.
+#[derive(Live, LiveRegisterWidget, WidgetRef)]
+pub struct AdaptiveView {
+    #[rust]
+    area: Area,
+
+    #[walk]
+    walk: Walk,
+
+    #[live]
+    breakpoints: Breakpoints,
+
+    #[rust]
+    templates: Vec<(LiveId, LivePtr)>,
+
+    #[rust]
+    active: Option<LiveId>,
+
+    #[rust]
+    widget: WidgetRef,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Live, LiveHook)]
+#[live_ignore]
+pub enum BreakpointOrientation {
+    #[pick] Any,
+    Portrait,
+    Landscape,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Live, LiveHook)]
+#[live_ignore]
+pub enum BreakpointOs {
+    #[pick] Any,
+    Mobile,
+    Desktop,
+    Web,
+    Android,
+    Ios,
+    Macos,
+    Windows,
+    Linux,
+}
+
+/// The window a variant is meant for. The ranges include their minimum and exclude their maximum.
+#[derive(Clone, Debug, Live, LiveHook, LiveRegister)]
+#[live_ignore]
+pub struct Breakpoint {
+    #[live] min_width: f64,
+    #[live] max_width: Option<f64>,
+    #[live] min_height: f64,
+    #[live] max_height: Option<f64>,
+    #[live] orientation: BreakpointOrientation,
+    #[live] os: BreakpointOs,
+}
+
+/// The breakpoints of an AdaptiveView by the id of the variant they are for.
+#[derive(Clone, Debug, Default)]
+pub struct Breakpoints(Vec<(LiveId, Breakpoint)>);
+
+impl LiveHook for Breakpoints {}
+impl LiveNew for Breakpoints {
+    fn live_design_with(_cx: &mut Cx) {}
+    fn new(_cx: &mut Cx) -> Self {
+        Self::default()
+    }
+
+    fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
+        // the ids are variant names, so any of them is accepted
+        LiveTypeInfo::new(
+            LiveModuleId::from_str(&module_path!()).unwrap(),
+            LiveType::of::<Self>(),
+            id_lut!(Breakpoints),
+            true,
+            Vec::new()
+        )
+    }
+}
+
+impl LiveApply for Breakpoints {
+    fn apply(&mut self, cx: &mut Cx, apply: &mut Apply, index: usize, nodes: &[LiveNode]) -> usize {
+        if !nodes[index].value.is_object() {
+            cx.apply_error_wrong_type_for_struct(live_error_origin!(), index, nodes, live_id!(Breakpoints));
+            return nodes.skip_node(index)
+        }
+        let mut child = index + 1;
+        while !nodes[child].is_close() {
+            let id = nodes[child].id;
+            let breakpoint = Breakpoint::new_apply(cx, apply, child, nodes);
+            self.0.retain(|(b, _)| *b != id);
+            self.0.push((id, breakpoint));
+            child = nodes.skip_node(child);
+        }
+        child + 1
+    }
+}
+
+impl Breakpoint {
+    fn matches(&self, os_type: &OsType, size: DVec2) -> bool {
+        let in_range = |v: f64, min: f64, max: Option<f64>| v >= min && max.map_or(true, |max| v < max);
+        if !in_range(size.x, self.min_width, self.max_width) || !in_range(size.y, self.min_height, self.max_height) {
+            return false
+        }
+        let orientation = match self.orientation {
+            BreakpointOrientation::Any => true,
+            BreakpointOrientation::Portrait => size.y >= size.x,
+            BreakpointOrientation::Landscape => size.x > size.y,
+        };
+        let os = match self.os {
+            BreakpointOs::Any => true,
+            BreakpointOs::Mobile => matches!(os_type, OsType::Android(_) | OsType::Ios | OsType::OpenHarmony(_)),
+            BreakpointOs::Desktop => matches!(os_type, OsType::Macos | OsType::Windows | OsType::LinuxWindow(_) | OsType::LinuxDirect),
+            BreakpointOs::Web => matches!(os_type, OsType::Web(_)),
+            BreakpointOs::Android => matches!(os_type, OsType::Android(_)),
+            BreakpointOs::Ios => matches!(os_type, OsType::Ios),
+            BreakpointOs::Macos => matches!(os_type, OsType::Macos),
+            BreakpointOs::Windows => matches!(os_type, OsType::Windows),
+            BreakpointOs::Linux => matches!(os_type, OsType::LinuxWindow(_) | OsType::LinuxDirect),
+        };
+        orientation && os
+    }
+}
+
+impl LiveHook for AdaptiveView {
+    fn before_apply(&mut self, _cx: &mut Cx, apply: &mut Apply, _index: usize, _nodes: &[LiveNode]) {
+        if apply.from.is_from_doc() {
+            self.breakpoints.0.clear();
+            self.templates.clear();
+        }
+    }
+
+    fn apply_value_instance(&mut self, cx: &mut Cx, apply: &mut Apply, index: usize, nodes: &[LiveNode]) -> usize {
+        if nodes[index].is_instance_prop() {
+            if let Some(live_ptr) = apply.from.to_live_ptr(cx, index) {
+                let id = nodes[index].id;
+                self.templates.retain(|(t, _)| *t != id);
+                self.templates.push((id, live_ptr));
+            }
+        }
+        else {
+            cx.apply_error_no_matching_field(live_error_origin!(), index, nodes);
+        }
+        nodes.skip_node(index)
+    }
+
+    fn after_apply(&mut self, cx: &mut Cx, apply: &mut Apply, _index: usize, _nodes: &[LiveNode]) {
+        // live editing changed the variants, put the one we show back on
+        if apply.from.is_update_from_doc() {
+            if let Some(active) = self.active.take() {
+                self.set_variant(cx, active);
+            }
+        }
+    }
+}
+
+impl AdaptiveView {
+    fn select_variant(&self, os_type: &OsType, size: DVec2) -> Option<LiveId> {
+        for (id, breakpoint) in &self.breakpoints.0 {
+            if self.templates.iter().any(|(t, _)| t == id) && breakpoint.matches(os_type, size) {
+                return Some(*id)
+            }
+        }
+        self.templates.iter()
+            .find(|(id, _)| !self.breakpoints.0.iter().any(|(b, _)| b == id))
+            .or(self.templates.first())
+            .map(|(id, _)| *id)
+    }
+
+    fn set_variant(&mut self, cx: &mut Cx, id: LiveId) {
+        let Some((_, live_ptr)) = self.templates.iter().find(|(t, _)| *t == id).cloned() else {
+            return
+        };
+        let widget = WidgetRef::new_from_ptr(cx, Some(live_ptr));
+        if !self.widget.is_empty() {
+            let old = self.widget.clone();
+            cx.get_nodes_from_live_ptr(live_ptr, |cx, file_id, index, nodes| {
+                for (child_id, leaf) in shared_leaves(index, nodes) {
+                    let new = widget.widget(&[child_id]);
+                    if new.swap_same_type(&old.widget(&[child_id])) {
+                        // the moved widget keeps its state and takes the properties of this variant
+                        LiveApply::apply(&mut new.clone(), cx, &mut ApplyFrom::UpdateFromDoc {file_id}.into(), leaf, nodes);
+                    }
+                }
+                nodes.skip_node(index)
+            });
+        }
+        self.widget = widget;
+        self.active = Some(id);
+        self.redraw(cx);
+    }
+
+    fn update_variant(&mut self, cx: &mut Cx, size: DVec2) {
+        let os_type = cx.os_type().clone();
+        if let Some(id) = self.select_variant(&os_type, size) {
+            if self.active != Some(id) {
+                self.set_variant(cx, id);
+            }
+        }
+    }
+}
+
+/// The widgets of a variant without child widgets of their own whose id is used once in it, with
+/// the index of their node. These are moved over from the variant that was shown before.
+fn shared_leaves(index: usize, nodes: &[LiveNode]) -> Vec<(LiveId, usize)> {
+    let is_widget = |i: usize| nodes[i].is_instance_prop() && nodes[i].value.is_class();
+    let mut leaves = Vec::new();
+    let mut seen = Vec::new();
+    let end = nodes.skip_node(index) - 1;
+    for i in index + 1..end {
+        if !is_widget(i) {
+            continue
+        }
+        let id = nodes[i].id;
+        if seen.contains(&id) {
+            leaves.retain(|(l, _)| *l != id);
+            continue
+        }
+        seen.push(id);
+        if !(i + 1..nodes.skip_node(i) - 1).any(is_widget) {
+            leaves.push((id, i));
+        }
+    }
+    leaves
+}
+
+impl WidgetNode for AdaptiveView {
+    fn walk(&mut self, cx: &mut Cx) -> Walk {
+        if !self.widget.is_empty() {
+            self.widget.walk(cx)
+        } else {
+            self.walk
+        }
+    }
+
+    fn area(&self) -> Area {
+        if !self.widget.is_empty() {
+            self.widget.area()
+        } else {
+            self.area
+        }
+    }
+
+    fn redraw(&mut self, cx: &mut Cx) {
+        self.area.redraw(cx);
+        self.widget.redraw(cx);
+    }
+
+    fn find_widgets(&self, path: &[LiveId], cached: WidgetCache, results: &mut WidgetSet) {
+        self.widget.find_widgets(path, cached, results);
+    }
+
+    fn uid_to_widget(&self, uid: WidgetUid) -> WidgetRef {
+        self.widget.uid_to_widget(uid)
+    }
+}
+
+impl Widget for AdaptiveView {
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
+        if let Event::WindowGeomChange(ev) = event {
+            self.update_variant(cx, ev.new_geom.inner_size);
+        }
+        self.widget.handle_event(cx, event, scope);
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope: &mut Scope, walk: Walk) -> DrawStep {
+        if self.active.is_none() {
+            let size = cx.current_pass_size();
+            self.update_variant(cx, size);
+        }
+        self.widget.draw_walk(cx, scope, walk)
+    }
+}
+
+impl AdaptiveViewRef {
+    /// The id of the variant that is shown.
+    pub fn active_variant(&self) -> Option<LiveId> {
+        self.borrow().and_then(|inner| inner.active)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::makepad_draw::makepad_platform::makepad_live_compiler::live_node::LiveDesignInfoIndex;
+
+    fn breakpoint(min_width: f64, max_width: Option<f64>, orientation: BreakpointOrientation, os: BreakpointOs) -> Breakpoint {
+        Breakpoint {min_width, max_width, min_height: 0.0, max_height: None, orientation, os}
+    }
+
+    #[test]
+    fn size_ranges_exclude_their_maximum() {
+        let tablet = breakpoint(600.0, Some(1100.0), BreakpointOrientation::Any, BreakpointOs::Any);
+        assert!(!tablet.matches(&OsType::LinuxDirect, dvec2(599.0, 800.0)));
+        assert!(tablet.matches(&OsType::LinuxDirect, dvec2(600.0, 800.0)));
+        assert!(tablet.matches(&OsType::LinuxDirect, dvec2(1099.0, 800.0)));
+        assert!(!tablet.matches(&OsType::LinuxDirect, dvec2(1100.0, 800.0)));
+        let short = Breakpoint {max_height: Some(500.0), ..breakpoint(0.0, None, BreakpointOrientation::Any, BreakpointOs::Any)};
+        assert!(short.matches(&OsType::LinuxDirect, dvec2(5000.0, 499.0)));
+        assert!(!short.matches(&OsType::LinuxDirect, dvec2(5000.0, 500.0)));
+    }
+
+    #[test]
+    fn orientation_follows_the_longer_side() {
+        let portrait = breakpoint(0.0, None, BreakpointOrientation::Portrait, BreakpointOs::Any);
+        let landscape = breakpoint(0.0, None, BreakpointOrientation::Landscape, BreakpointOs::Any);
+        assert!(portrait.matches(&OsType::LinuxDirect, dvec2(400.0, 800.0)));
+        assert!(!landscape.matches(&OsType::LinuxDirect, dvec2(400.0, 800.0)));
+        assert!(landscape.matches(&OsType::LinuxDirect, dvec2(800.0, 400.0)));
+        assert!(!portrait.matches(&OsType::LinuxDirect, dvec2(800.0, 400.0)));
+        // a square window counts as portrait
+        assert!(portrait.matches(&OsType::LinuxDirect, dvec2(500.0, 500.0)));
+        assert!(!landscape.matches(&OsType::LinuxDirect, dvec2(500.0, 500.0)));
+    }
+
+    #[test]
+    fn os_families() {
+        let size = dvec2(800.0, 600.0);
+        let on = |os: BreakpointOs, os_type: OsType| breakpoint(0.0, None, BreakpointOrientation::Any, os).matches(&os_type, size);
+        assert!(on(BreakpointOs::Any, OsType::Unknown));
+        assert!(on(BreakpointOs::Mobile, OsType::Ios));
+        assert!(!on(BreakpointOs::Mobile, OsType::Macos));
+        assert!(on(BreakpointOs::Desktop, OsType::Macos));
+        assert!(on(BreakpointOs::Desktop, OsType::Windows));
+        assert!(on(BreakpointOs::Desktop, OsType::LinuxDirect));
+        assert!(!on(BreakpointOs::Desktop, OsType::Ios));
+        assert!(!on(BreakpointOs::Desktop, OsType::Unknown));
+        assert!(on(BreakpointOs::Linux, OsType::LinuxDirect));
+        assert!(!on(BreakpointOs::Linux, OsType::Windows));
+        assert!(on(BreakpointOs::Ios, OsType::Ios));
+        assert!(!on(BreakpointOs::Macos, OsType::Ios));
+    }
+
+    fn widget(id: LiveId) -> LiveNode {
+        let class_parent = LivePtr {file_id: LiveFileId(0), generation: Default::default(), index: 0};
+        LiveNode {
+            origin: LiveNodeOrigin::instance(),
+            id,
+            value: LiveValue::Class {live_type: LiveType::of::<crate::view::View>(), class_parent, design_info: LiveDesignInfoIndex::invalid()},
+        }
+    }
+
+    fn close() -> LiveNode {
+        LiveNode::from_value(LiveValue::Close)
+    }
+
+    #[test]
+    fn leaves_used_once_are_carried_over() {
+        let nodes = [
+            widget(live_id!(Mobile)),
+            widget(live_id!(header)),
+            LiveNode::from_id_value(live_id!(width), LiveValue::Float64(100.0)),
+            close(),
+            widget(live_id!(body)),
+            widget(live_id!(label)),
+            close(),
+            widget(live_id!(button)),
+            close(),
+            widget(live_id!(label)),
+            close(),
+            close(),
+            LiveNode::from_id_value(live_id!(height), LiveValue::Float64(100.0)),
+            close(),
+        ];
+        // body has child widgets and label is used twice, so neither can be moved over
+        assert_eq!(shared_leaves(0, &nodes), vec![(live_id!(header), 1), (live_id!(button), 7)]);
+        // only the variant that is passed in is looked at
+        assert_eq!(shared_leaves(4, &nodes), vec![(live_id!(button), 7)]);
+    }
+}
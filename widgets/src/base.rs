@@ -3,6 +3,7 @@ use crate::makepad_platform::*;
 live_design!{
     import crate::button::ButtonBase;
     import crate::cached_widget::CachedWidgetBase;
+    import crate::adaptive_view::AdaptiveViewBase;
     import crate::check_box::CheckBoxBase;
     import crate::dock::DockBase;
     import crate::splitter::SplitterBase;
@@ -66,6 +67,7 @@ live_design!{
     import crate::designer_outline_tree::DesignerOutlineTreeBase;
     import crate::designer_outline_tree::DesignerOutlineTreeNodeBase;
     import crate::designer_toolbox::DesignerToolboxBase
+    import crate::designer_inspector::DesignerInspectorBase;
     import crate::color_picker::ColorPicker;
     
     import crate::bare_step::BareStep;
@@ -989,6 +991,7 @@ live_design!{
     ColorPicker = <ColorPicker>{}
     TogglePanelBase = <TogglePanelBase>{}
     CachedWidget = <CachedWidgetBase>{}
+    AdaptiveView = <AdaptiveViewBase>{}
     
     DesignerBase = <DesignerBase>{}
     DesignerOutlineBase = <DesignerOutlineBase>{}
@@ -997,4 +1000,5 @@ live_design!{
     DesignerOutlineTreeBase = <DesignerOutlineTreeBase> {}
     DesignerOutlineTreeNodeBase = <DesignerOutlineTreeNodeBase> {}
     DesignerToolboxBase = <DesignerToolboxBase> {}
+    DesignerInspectorBase = <DesignerInspectorBase> {}
 }
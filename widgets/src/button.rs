@@ -11,6 +11,9 @@ live_design! {
 /// 2. Then, either one of the following, but not both:
 ///    * `ButtonAction::Clicked` when the mouse/finger is lifted up while over the button area.
 ///    * `ButtonAction::Released` when the mouse/finger is lifted up while *not* over the button area.
+///
+/// A button with the key focus does the same for Space and Enter, it is pressed when the key
+/// goes down and clicked when it comes back up, or released when it loses the focus in between.
 #[derive(Clone, Debug, DefaultNone)]
 pub enum ButtonAction {
     None,
@@ -55,6 +58,10 @@ pub struct Button {
     #[live(true)]
     visible: bool,
 
+    /// Where this button goes in the Tab order.
+    #[live]
+    nav_order: NavOrder,
+
     /// It indicates if the hover state will be reset when the button is clicked.
     /// This could be useful for buttons that disappear when clicked, where the hover state
     /// should not be preserved.
@@ -64,6 +71,11 @@ pub struct Button {
     #[live]
     pub text: ArcStringMut,
     
+    /// Set while Space or Enter went down on this button, so a key up that
+    /// belongs to a press somewhere else does not click it.
+    #[rust]
+    key_pressed: bool,
+    
     #[action_data] #[rust] action_data: WidgetActionData,
 }
 
@@ -93,6 +105,21 @@ impl Widget for Button {
                     cx.widget_action_with_data(&self.action_data, uid, &scope.path, ButtonAction::Pressed(fe.modifiers));
                     self.animator_play(cx, id!(hover.pressed));
                 }
+                Hit::KeyDown(ke) if self.enabled && !ke.is_repeat && is_press_key(&ke) => {
+                    self.key_pressed = true;
+                    cx.widget_action_with_data(&self.action_data, uid, &scope.path, ButtonAction::Pressed(ke.modifiers));
+                    self.animator_play(cx, id!(hover.pressed));
+                }
+                Hit::KeyUp(ke) if self.key_pressed && is_press_key(&ke) => {
+                    self.key_pressed = false;
+                    cx.widget_action_with_data(&self.action_data, uid, &scope.path, ButtonAction::Clicked(ke.modifiers));
+                    self.animator_play(cx, id!(hover.off));
+                }
+                Hit::KeyFocusLost(_) if self.key_pressed => {
+                    self.key_pressed = false;
+                    cx.widget_action_with_data(&self.action_data, uid, &scope.path, ButtonAction::Released(KeyModifiers::default()));
+                    self.animator_play(cx, id!(hover.off));
+                }
                 Hit::FingerHoverIn(_) => {
                     if self.enabled {
                         cx.set_cursor(MouseCursor::Hand);
@@ -135,6 +162,9 @@ impl Widget for Button {
         self.draw_text
             .draw_walk(cx, self.label_walk, Align::default(), self.text.as_ref());
         self.draw_bg.end(cx);
+        if self.enabled {
+            cx.add_nav_stop_with_order(self.draw_bg.area(), NavRole::Button, Margin::default(), self.nav_order);
+        }
         DrawStep::done()
     }
 
@@ -147,6 +177,10 @@ impl Widget for Button {
     }
 }
 
+fn is_press_key(ke: &KeyEvent) -> bool {
+    matches!(ke.key_code, KeyCode::Space | KeyCode::ReturnKey)
+}
+
 impl Button {
         
     pub fn draw_button(&mut self, cx: &mut Cx2d, label:&str) {
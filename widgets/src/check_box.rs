@@ -49,6 +49,7 @@ pub struct CheckBox {
     #[live] text: ArcStringMut,
     
     #[live] bind: String,
+    #[live] nav_order: NavOrder,
     #[action_data] #[rust] action_data: WidgetActionData,
 }
 
@@ -72,6 +73,19 @@ impl CheckBox {
         self.draw_icon.draw_walk(cx, self.icon_walk);
         self.draw_text.draw_walk(cx, self.label_walk, self.label_align, self.text.as_ref());
         self.draw_check.end(cx);
+        cx.add_nav_stop_with_order(self.draw_check.area(), NavRole::CheckBox, Margin::default(), self.nav_order);
+    }
+    
+    fn toggle(&mut self, cx: &mut Cx, scope: &mut Scope) {
+        let uid = self.widget_uid();
+        if self.animator_in_state(cx, id!(selected.on)) {
+            self.animator_play(cx, id!(selected.off));
+            cx.widget_action_with_data(&self.action_data, uid, &scope.path, CheckBoxAction::Change(false));
+        }
+        else {
+            self.animator_play(cx, id!(selected.on));
+            cx.widget_action_with_data(&self.action_data, uid, &scope.path, CheckBoxAction::Change(true));
+        }
     }
 }
 
@@ -96,7 +110,6 @@ impl Widget for CheckBox {
     }
     
     fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
-        let uid = self.widget_uid();
         self.animator_handle_event(cx, event);
                 
         match event.hits(cx, self.draw_check.area()) {
@@ -108,15 +121,11 @@ impl Widget for CheckBox {
                 self.animator_play(cx, id!(hover.off));
             },
             Hit::FingerDown(_fe) => {
-                if self.animator_in_state(cx, id!(selected.on)) {
-                    self.animator_play(cx, id!(selected.off));
-                    cx.widget_action_with_data(&self.action_data, uid, &scope.path, CheckBoxAction::Change(false));
-                }
-                else {
-                    self.animator_play(cx, id!(selected.on));
-                    cx.widget_action_with_data(&self.action_data, uid, &scope.path, CheckBoxAction::Change(true));
-                }
+                self.toggle(cx, scope);
             },
+            Hit::KeyDown(ke) if !ke.is_repeat && matches!(ke.key_code, KeyCode::Space | KeyCode::ReturnKey) => {
+                self.toggle(cx, scope);
+            }
             Hit::FingerUp(_fe) => {
                                 
             }
@@ -270,7 +270,36 @@ impl Widget for ColorPicker {
     }
         
     fn draw_walk(&mut self, cx: &mut Cx2d, _scope: &mut Scope, walk: Walk) -> DrawStep {
-        self.draw_color_picker(cx, vec4(1.0,0.0,0.0,1.0), walk);
+        let rgba = self.to_rgba();
+        self.draw_color_picker(cx, rgba, walk);
         DrawStep::done()
     }
+}
+
+impl ColorPickerRef {
+    pub fn set_rgba(&self, cx: &mut Cx, rgba: Vec4) {
+        if let Some(mut inner) = self.borrow_mut() {
+            if inner.drag_mode == ColorPickerDragMode::None {
+                let hsva = rgba.to_hsva();
+                inner.hue = hsva.x;
+                inner.sat = hsva.y;
+                inner.val = hsva.z;
+                inner.redraw(cx);
+            }
+        }
+    }
+    
+    pub fn changed(&self, actions: &Actions) -> Option<Vec4> {
+        if let ColorPickerAction::Change {rgba} = actions.find_widget_action_cast(self.widget_uid()) {
+            return Some(rgba)
+        }
+        None
+    }
+    
+    pub fn done_changing(&self, actions: &Actions) -> bool {
+        if let ColorPickerAction::DoneChanging = actions.find_widget_action_cast(self.widget_uid()) {
+            return true
+        }
+        false
+    }
 }
\ No newline at end of file
@@ -6,6 +6,10 @@ use crate::{
     designer_data::*,
     designer_view::*,
     designer_outline_tree::*,
+    designer_inspector::*,
+    designer_toolbox::*,
+    makepad_live_compiler::{LiveTypeInfo, LiveFieldKind, TextPos},
+    view::View,
     widget::*,
     makepad_platform::studio::*,
 };
@@ -32,6 +36,8 @@ impl LiveHook for Designer {
         designer_view.reload_view(cx);
         let outline_tree = self.ui.designer_outline_tree(id!(outline_tree));
         outline_tree.redraw(cx);
+        let inspector = self.ui.designer_inspector(id!(inspector));
+        inspector.reload(cx);
     }
     
     fn after_new_from_doc(&mut self, _cx:&mut Cx){
@@ -56,6 +62,150 @@ impl Designer{
         }
     }
     
+    // only widgets that keep their children in a View can get one dropped in
+    fn takes_children(&self, cx:&Cx, component:LiveId)->bool{
+        let Some(OutlineNode::Component{ptr,..}) = self.data.node_map.get(&component) else{
+            return false
+        };
+        fn has_view(info:&LiveTypeInfo)->bool{
+            info.live_type == LiveType::of::<View>() || info.fields.iter().any(|f| f.live_field_kind == LiveFieldKind::Deref && has_view(&f.live_type_info))
+        }
+        let live_registry = cx.live_registry.borrow();
+        match live_registry.ptr_to_node(*ptr).value{
+            LiveValue::Class{live_type, ..}=>live_registry.live_type_infos.get(&live_type).is_some_and(has_view),
+            _=>false
+        }
+    }
+    
+    // adds a new instance of a widget at the end of the body of a component
+    fn studio_insert_component(&self, cx:&Cx, parent:LiveId, class:LiveId){
+        let Some(OutlineNode::Component{ptr,..}) = self.data.node_map.get(&parent) else{
+            return
+        };
+        let live_registry = cx.live_registry.borrow();
+        let (nodes, index) = live_registry.ptr_to_nodes_index(*ptr);
+        // snake_case the class and number it until the name is free
+        let mut base = String::new();
+        for (i, c) in class.to_string().chars().enumerate(){
+            if c.is_uppercase(){
+                if i != 0{
+                    base.push('_');
+                }
+                base.extend(c.to_lowercase());
+            }
+            else{
+                base.push(c);
+            }
+        }
+        let name = (1..).map(|n| format!("{}{}", base, n)).find(|name|{
+            nodes.child_by_name(index, LiveId::from_str(name).as_instance()).is_none()
+        }).unwrap();
+        // widgets that show text get their class name, so they aren't invisible
+        fn has_text(info:&LiveTypeInfo)->bool{
+            info.fields.iter().any(|f| f.id == live_id!(text) || f.live_field_kind == LiveFieldKind::Deref && has_text(&f.live_type_info))
+        }
+        let text = if live_registry.live_type_infos.values().any(|i| i.type_name == class && has_text(i)){
+            format!("{} = <{}> {{ text: \"{}\" }}", name, class, class)
+        }
+        else{
+            format!("{} = <{}> {{}}", name, class)
+        };
+        let Some((file_name, pos, indent)) = live_registry.new_child_location(*ptr) else{
+            return
+        };
+        let replace = if let Some(indent) = indent{
+            format!("{}    {}\n", " ".repeat(indent as usize), text)
+        }
+        else{
+            format!(" {} ", text)
+        };
+        Cx::send_studio_message(AppToStudio::EditFile(EditFile{
+            file_name: file_name.into(),
+            line_start: pos.line,
+            line_end: pos.line,
+            column_start: pos.column,
+            column_end: pos.column,
+            replace
+        }));
+    }
+    
+    fn studio_edit_component(&self, cx:&Cx, component:LiveId, edit:OutlineTreeEdit){
+        let Some(OutlineNode::Component{ptr, id, ..}) = self.data.node_map.get(&component) else{
+            return
+        };
+        let Some(parent) = self.data.find_parent(component) else{
+            return
+        };
+        let siblings = self.data.node_map.get(&parent).unwrap().children();
+        let live_registry = cx.live_registry.borrow();
+        let Some((file_name, span, own_lines)) = live_registry.node_source_range(*ptr) else{
+            return
+        };
+        let move_range = |to:TextPos, copy:bool, rename:Option<String>|{
+            Cx::send_studio_message(AppToStudio::MoveFileRange(MoveFileRange{
+                file_name: file_name.into(),
+                line_start: span.start.line,
+                line_end: span.end.line,
+                column_start: span.start.column,
+                column_end: span.end.column,
+                to_line: to.line,
+                to_column: to.column,
+                copy,
+                rename
+            }));
+        };
+        match edit{
+            OutlineTreeEdit::Delete=>{
+                Cx::send_studio_message(AppToStudio::EditFile(EditFile{
+                    file_name: file_name.into(),
+                    line_start: span.start.line,
+                    line_end: span.end.line,
+                    column_start: span.start.column,
+                    column_end: span.end.column,
+                    replace: String::new()
+                }));
+            }
+            OutlineTreeEdit::Duplicate=>{
+                if id.is_unique(){
+                    move_range(span.end, true, None);
+                    return
+                }
+                // two instances with the same name would merge, the copy goes in renamed
+                let name = id.to_string();
+                let base = name.trim_end_matches(|c:char| c.is_ascii_digit());
+                let new_name = (1..).map(|n| format!("{}{}", base, n)).find(|new_name|{
+                    let new_id = LiveId::from_str(new_name);
+                    !siblings.iter().any(|s| matches!(self.data.node_map.get(s), Some(OutlineNode::Component{id,..}) if *id == new_id))
+                }).unwrap();
+                move_range(span.end, true, Some(new_name));
+            }
+            OutlineTreeEdit::MoveUp | OutlineTreeEdit::MoveDown=>{
+                let index = siblings.iter().position(|s| *s == component).unwrap();
+                let sibling = if edit == OutlineTreeEdit::MoveUp{
+                    index.checked_sub(1).and_then(|i| siblings.get(i))
+                }
+                else{
+                    siblings.get(index + 1)
+                };
+                let Some(OutlineNode::Component{ptr: sibling_ptr, ..}) = sibling.and_then(|s| self.data.node_map.get(s)) else{
+                    return
+                };
+                let Some((sibling_file, sibling_span, sibling_own_lines)) = live_registry.node_source_range(*sibling_ptr) else{
+                    return
+                };
+                if sibling_file != file_name || sibling_own_lines != own_lines{
+                    return
+                }
+                if edit == OutlineTreeEdit::MoveUp{
+                    move_range(sibling_span.start, false, None);
+                }
+                else{
+                    move_range(sibling_span.end, false, None);
+                }
+            }
+        }
+    }
+    
     fn studio_jump_to_file(&self, cx:&Cx, file_id:LiveFileId){
         let file_name = cx.live_registry.borrow().file_id_to_file(file_id).file_name.clone();
         Cx::send_studio_message(AppToStudio::JumpToFile(JumpToFile{
@@ -70,8 +220,23 @@ impl WidgetMatchEvent for Designer{
     fn handle_actions(&mut self, cx: &mut Cx, actions: &Actions, _scope: &mut Scope){
         let outline_tree = self.ui.designer_outline_tree(id!(outline_tree));
         let designer_view = self.ui.designer_view(id!(designer_view));
+        let inspector = self.ui.designer_inspector(id!(inspector));
+        let toolbox = self.ui.designer_toolbox(id!(toolbox));
+        if let Some((class, abs)) = toolbox.dropped(&actions){
+            if let Some(component) = designer_view.component_at(cx, abs).filter(|c| self.takes_children(cx, *c)){
+                self.studio_insert_component(cx, component, class);
+            }
+        }
+        if let Some((component, edit)) = outline_tree.edited(&actions){
+            self.studio_edit_component(cx, component, edit);
+        }
+        if inspector.changed(&actions){
+            designer_view.reload_view(cx);
+        }
         if let Some((outline_id, km, tap_count)) = designer_view.selected(&actions){
             // select the right node in the filetree
+            self.data.selected = Some(outline_id);
+            inspector.redraw(cx);
             let path_ids = self.data.construct_path_ids(outline_id);
             outline_tree.select_and_show_node(cx, &path_ids);
             // if we click with control
@@ -84,6 +249,8 @@ impl WidgetMatchEvent for Designer{
             if let StudioToApp::DesignerSelectFile{file_name} = action.cast_ref(){
                 let path_ids = DesignerData::path_str_to_path_ids(&file_name);
                 outline_tree.select_and_show_node(cx, &path_ids);
+                self.data.selected = None;
+                inspector.redraw(cx);
                 designer_view.select_component_and_redraw(cx, None);
                 designer_view.view_file_and_redraw(cx, *path_ids.last().unwrap());
             }
@@ -111,6 +278,8 @@ impl WidgetMatchEvent for Designer{
                             Cx::send_studio_message(AppToStudio::FocusDesign);
                         }
                         else{
+                            self.data.selected = None;
+                            inspector.redraw(cx);
                             designer_view.select_component_and_redraw(cx, None);
                             designer_view.view_file_and_redraw(cx, outline_id);
                         }        
@@ -125,6 +294,8 @@ impl WidgetMatchEvent for Designer{
                         else{
                             // only select the file 
                             if let Some(file_id) = self.data.find_file_parent(outline_id){
+                                self.data.selected = Some(outline_id);
+                                inspector.redraw(cx);
                                 designer_view.select_component_and_redraw(cx, Some(outline_id));
                                 designer_view.view_file_and_redraw(cx, file_id);
                             }
@@ -33,7 +33,7 @@ pub enum OutlineNode{
 }
 
 impl OutlineNode{
-    fn children(&self)->&[LiveId]{
+    pub fn children(&self)->&[LiveId]{
         match self{
             Self::Virtual{children,..}=>children,
             Self::File{children,..}=>children,
@@ -0,0 +1,340 @@
+use crate::{
+    makepad_derive_widget::*,
+    makepad_draw::*,
+    makepad_platform::studio::*,
+    makepad_live_compiler::{LiveTypeInfo, LiveFieldKind},
+    designer_data::*,
+    check_box::*,
+    color_picker::*,
+    drop_down::*,
+    flat_list::*,
+    label::*,
+    slider::*,
+    text_input::*,
+    view::View,
+    widget_match_event::*,
+    widget::*,
+};
+use std::sync::Arc;
+
+live_design!{
+    DesignerInspectorBase = {{DesignerInspector}}{
+    }
+}
+
+// the editor a property gets, from the type of its field
+#[derive(Clone, Debug, PartialEq)]
+enum PropKind{
+    Color,
+    Number{int: bool},
+    Bool,
+    Enum(Vec<LiveId>),
+    Text,
+}
+
+struct InspectorProp{
+    id: LiveId,
+    kind: PropKind,
+    value: LiveValue,
+    // the property node, when it is written in the component itself and can be patched in place
+    ptr: Option<LivePtr>,
+}
+
+#[derive(Clone, Debug, DefaultNone)]
+pub enum DesignerInspectorAction{
+    Changed,
+    None
+}
+
+#[derive(Live, Widget, LiveHook)]
+pub struct DesignerInspector {
+    #[deref] view: View,
+    #[rust] component: Option<(LiveId, LivePtr)>,
+    #[rust] props: Vec<InspectorProp>,
+    #[rust] refresh: bool,
+    // a property was added to the source, wait for the reload before adding it again
+    #[rust] pending: bool,
+    #[rust] undo_group: u64,
+}
+
+impl DesignerInspector{
+    fn prop_kind(info: &LiveTypeInfo)->Option<PropKind>{
+        match info.type_name{
+            live_id!(Vec4)=>Some(PropKind::Color),
+            live_id!(f64) | live_id!(f32)=>Some(PropKind::Number{int: false}),
+            live_id!(i64) | live_id!(u64) | live_id!(i32) | live_id!(u32) | live_id!(usize)=>Some(PropKind::Number{int: true}),
+            live_id!(bool)=>Some(PropKind::Bool),
+            live_id!(String) | live_id!(ArcStringMut)=>Some(PropKind::Text),
+            _ if !info.variants.is_empty()=>Some(PropKind::Enum(info.variants.clone())),
+            _=>None
+        }
+    }
+
+    fn collect_fields(info: &LiveTypeInfo, out: &mut Vec<(LiveId, PropKind)>){
+        for field in &info.fields{
+            match field.live_field_kind{
+                LiveFieldKind::Deref=>Self::collect_fields(&field.live_type_info, out),
+                LiveFieldKind::Live | LiveFieldKind::LiveOption=>if let Some(kind) = Self::prop_kind(&field.live_type_info){
+                    if !out.iter().any(|(id,_)| *id == field.id){
+                        out.push((field.id, kind));
+                    }
+                }
+                _=>()
+            }
+        }
+    }
+
+    fn load_props(&mut self, cx: &Cx, data: &DesignerData){
+        self.props.clear();
+        self.pending = false;
+        self.component = None;
+        let Some(OutlineNode::Component{ptr, token_id, name, ..}) = data.selected.and_then(|s| data.node_map.get(&s)) else{
+            return
+        };
+        self.component = Some((data.selected.unwrap(), *ptr));
+        self.view.label(id!(title)).set_text(name);
+
+        let live_registry = cx.live_registry.borrow();
+        let (nodes, index) = live_registry.ptr_to_nodes_index(*ptr);
+        let LiveValue::Class{live_type, ..} = &nodes[index].value else{
+            return
+        };
+        let Some(info) = live_registry.live_type_infos.get(live_type) else{
+            return
+        };
+        let mut fields = Vec::new();
+        Self::collect_fields(info, &mut fields);
+
+        // the properties the component writes itself, the rest comes from its class
+        let original = &live_registry.file_id_to_file(ptr.file_id).original.nodes;
+        let own_index = original.first_node_with_token_id(*token_id, false);
+
+        for (id, kind) in fields{
+            let child = nodes.child_by_name(index, id.as_field());
+            let value = child.map(|c| nodes[c].value.clone()).unwrap_or(LiveValue::None);
+            let ptr = child.filter(|c| {
+                let token_id = nodes[*c].origin.token_id();
+                own_index.and_then(|own| original.child_by_name(own, id.as_field())).map_or(false, |o| {
+                    original[o].origin.token_id() == token_id
+                })
+            }).map(|c| ptr.with_index(c));
+            self.props.push(InspectorProp{id, kind, value, ptr});
+        }
+    }
+
+    fn draw_props(&mut self, cx: &mut Cx2d, list: &mut FlatList, refresh: bool){
+        for prop in &self.props{
+            let template = match prop.kind{
+                PropKind::Color=>live_id!(ColorProp),
+                PropKind::Number{..}=>live_id!(NumberProp),
+                PropKind::Bool=>live_id!(BoolProp),
+                PropKind::Enum(_)=>live_id!(EnumProp),
+                PropKind::Text=>live_id!(TextProp),
+            };
+            let item = list.item(cx, prop.id, template).unwrap();
+            if refresh{
+                let name = prop.id.to_string();
+                item.label(id!(label)).set_text(&name);
+                match &prop.kind{
+                    PropKind::Color=>{
+                        let rgba = match prop.value{
+                            LiveValue::Color(c)=>Vec4::from_u32(c),
+                            LiveValue::Vec4(v)=>v,
+                            _=>vec4(1.0,1.0,1.0,1.0)
+                        };
+                        item.color_picker(id!(value)).set_rgba(cx, rgba);
+                    }
+                    PropKind::Number{int}=>{
+                        let value = prop.value.as_float().unwrap_or(0.0);
+                        // the type doesn't say what range makes sense, so pick one around the value
+                        let span = if value.abs() <= 1.0 && !int{1.0}else{(value.abs() * 2.0).max(10.0).ceil()};
+                        let min = if value < 0.0{-span}else{0.0};
+                        let step = if *int{1.0}else{0.0};
+                        let slider = item.slider(id!(value));
+                        slider.apply_over(cx, live!{min: (min), max: (span), step: (step)});
+                        slider.set_value(value);
+                    }
+                    PropKind::Bool=>{
+                        item.check_box(id!(value)).set_selected(cx, prop.value.as_bool().unwrap_or(false));
+                    }
+                    PropKind::Enum(variants)=>{
+                        let drop_down = item.drop_down(id!(value));
+                        drop_down.set_labels(variants.iter().map(|v| v.to_string()).collect());
+                        let selected = match prop.value{
+                            LiveValue::BareEnum(v)=>variants.iter().position(|c| *c == v),
+                            _=>None
+                        };
+                        drop_down.set_selected_item(selected.unwrap_or(0));
+                    }
+                    PropKind::Text=>{
+                        let text = match &prop.value{
+                            LiveValue::Str(s)=>s.to_string(),
+                            LiveValue::String(s)=>s.as_str().to_string(),
+                            LiveValue::InlineString(s)=>s.as_str().to_string(),
+                            _=>String::new()
+                        };
+                        item.text_input(id!(value)).set_text(&text);
+                    }
+                }
+            }
+            item.draw_all(cx, &mut Scope::empty());
+        }
+    }
+
+    // writes a new value for a property back into the live_design source
+    fn set_prop(&mut self, cx: &mut Cx, scope: &mut Scope, index: usize, value: LiveValue, replace: String){
+        let Some((_, component_ptr)) = self.component else{return};
+        let uid = self.widget_uid();
+        let prop = &mut self.props[index];
+        let live_registry_rc = cx.live_registry.clone();
+        let mut live_registry = live_registry_rc.borrow_mut();
+        if let Some(ptr) = prop.ptr{
+            if let Some((file_name, range)) = live_registry.patch_value(ptr, value.clone(), &replace){
+                Cx::send_studio_message(AppToStudio::PatchFile(PatchFile{
+                    file_name: file_name.into(),
+                    line: range.line,
+                    column_start: range.start_column,
+                    column_end: range.end_column,
+                    undo_group: self.undo_group,
+                    replace
+                }));
+                cx.widget_action(uid, &scope.path, DesignerInspectorAction::Changed);
+            }
+            else{
+                error!("Can't patch {} in the live_design source, values spanning lines are left alone", prop.id);
+            }
+        }
+        else if !self.pending{
+            // not written in the component yet, add it and let the reload pick it up
+            if let Some((file_name, range)) = live_registry.new_property_location(component_ptr){
+                Cx::send_studio_message(AppToStudio::EditFile(EditFile{
+                    file_name: file_name.into(),
+                    line_start: range.line,
+                    line_end: range.line,
+                    column_start: range.start_column,
+                    column_end: range.end_column,
+                    replace: format!(" {}: {},", prop.id, replace)
+                }));
+                self.pending = true;
+            }
+        }
+        prop.value = value;
+    }
+}
+
+// a string as the live DSL writes it, which only knows the \\, \" and \n escapes
+fn live_string_literal(text: &str)->String{
+    let mut out = String::from("\"");
+    for c in text.chars(){
+        match c{
+            '\\'=>out.push_str("\\\\"),
+            '"'=>out.push_str("\\\""),
+            '\n'=>out.push_str("\\n"),
+            c=>out.push(c)
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn format_float(v: f64)->String{
+    let s = format!("{:.3}", v);
+    let s = s.trim_end_matches('0');
+    if s.ends_with('.'){format!("{}0", s)}else{s.to_string()}
+}
+
+impl WidgetMatchEvent for DesignerInspector{
+    fn handle_actions(&mut self, cx: &mut Cx, actions: &Actions, scope: &mut Scope){
+        let list = self.view.flat_list(id!(list));
+        for (item_id, item) in list.items_with_actions(actions){
+            let Some(index) = self.props.iter().position(|p| p.id == item_id) else{continue};
+            match self.props[index].kind.clone(){
+                PropKind::Color=>{
+                    let picker = item.color_picker(id!(value));
+                    if let Some(rgba) = picker.changed(actions){
+                        let color = rgba.to_u32();
+                        self.set_prop(cx, scope, index, LiveValue::Color(color), format!("#{:08x}", color));
+                    }
+                    if picker.done_changing(actions){
+                        self.undo_group += 1;
+                    }
+                }
+                PropKind::Number{int}=>{
+                    let slider = item.slider(id!(value));
+                    if let Some(v) = slider.slided(actions){
+                        if int{
+                            self.set_prop(cx, scope, index, LiveValue::Int64(v as i64), format!("{}", v as i64));
+                        }
+                        else{
+                            self.set_prop(cx, scope, index, LiveValue::Float64(v), format_float(v));
+                        }
+                    }
+                    if let SliderAction::EndSlide = actions.find_widget_action_cast(slider.widget_uid()){
+                        self.undo_group += 1;
+                    }
+                }
+                PropKind::Bool=>if let Some(selected) = item.check_box(id!(value)).changed(actions){
+                    self.set_prop(cx, scope, index, LiveValue::Bool(selected), format!("{}", selected));
+                    self.undo_group += 1;
+                }
+                PropKind::Enum(variants)=>if let Some(selected) = item.drop_down(id!(value)).selected(actions){
+                    let variant = variants[selected];
+                    self.set_prop(cx, scope, index, LiveValue::BareEnum(variant), variant.to_string());
+                    self.undo_group += 1;
+                }
+                PropKind::Text=>{
+                    let text_input = item.text_input(id!(value));
+                    if let Some(text) = text_input.changed(actions){
+                        let replace = live_string_literal(&text);
+                        self.set_prop(cx, scope, index, LiveValue::String(Arc::new(text)), replace);
+                    }
+                    if text_input.returned(actions).is_some(){
+                        self.undo_group += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Widget for DesignerInspector {
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope){
+        self.widget_match_event(cx, event, scope);
+        self.view.handle_event(cx, event, scope);
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope:&mut Scope, walk: Walk) -> DrawStep {
+        let data = scope.data.get::<DesignerData>().unwrap();
+        let refresh = self.refresh || self.component.map(|(id,_)| id) != data.selected;
+        if refresh{
+            self.load_props(cx, data);
+            self.refresh = false;
+        }
+        if self.component.is_none(){
+            return DrawStep::done()
+        }
+        while let Some(item) = self.view.draw_walk(cx, &mut Scope::empty(), walk).step(){
+            if let Some(mut list) = item.as_flat_list().borrow_mut(){
+                self.draw_props(cx, &mut *list, refresh)
+            }
+        }
+        DrawStep::done()
+    }
+}
+
+impl DesignerInspectorRef{
+    // reads the properties again, after the live registry changed
+    pub fn reload(&self, cx: &mut Cx){
+        if let Some(mut inner) = self.borrow_mut(){
+            inner.refresh = true;
+            inner.redraw(cx);
+        }
+    }
+
+    pub fn changed(&self, actions: &Actions) -> bool {
+        if let DesignerInspectorAction::Changed = actions.find_widget_action_cast(self.widget_uid()) {
+            return true
+        }
+        false
+    }
+}
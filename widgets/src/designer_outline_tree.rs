@@ -129,6 +129,16 @@ pub enum OutlineTreeAction {
     Selected(LiveId, KeyModifiers),
     EyeClicked(LiveId, bool),
     ShouldStartDrag(LiveId),
+    Edit(LiveId, OutlineTreeEdit),
+}
+
+// changes to the source of a component, asked for with the keyboard on the selected node
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutlineTreeEdit {
+    Delete,
+    Duplicate,
+    MoveUp,
+    MoveDown,
 }
 
 pub enum OutlineTreeNodeAction {
@@ -540,6 +550,18 @@ impl Widget for DesignerOutlineTree {
                     self.tree_nodes.get_mut(&node_id).unwrap().0.set_is_focussed(cx, false, Animate::Yes);
                 }
             }
+            Hit::KeyDown(ke) => if let Some(node_id) = self.selected_node_id {
+                let edit = match ke.key_code {
+                    KeyCode::Delete | KeyCode::Backspace => Some(OutlineTreeEdit::Delete),
+                    KeyCode::KeyD if ke.modifiers.control || ke.modifiers.logo => Some(OutlineTreeEdit::Duplicate),
+                    KeyCode::ArrowUp if ke.modifiers.alt => Some(OutlineTreeEdit::MoveUp),
+                    KeyCode::ArrowDown if ke.modifiers.alt => Some(OutlineTreeEdit::MoveDown),
+                    _ => None
+                };
+                if let Some(edit) = edit {
+                    cx.widget_action(uid, &scope.path, OutlineTreeAction::Edit(node_id, edit));
+                }
+            }
             _ => ()
         }
     }
@@ -594,6 +616,15 @@ impl DesignerOutlineTreeRef{
     }
     
     
+    pub fn edited(&self, actions: &Actions) -> Option<(LiveId, OutlineTreeEdit)> {
+        if let Some(item) = actions.find_widget_action(self.widget_uid()) {
+            if let OutlineTreeAction::Edit(node_id, edit) = item.cast() {
+                return Some((node_id, edit))
+            }
+        }
+        None
+    }
+    
     pub fn start_drag(&self, cx: &mut Cx, _file_id: LiveId, item: DragItem) {
         cx.start_dragging(vec![item]);
     }
@@ -1,30 +1,108 @@
 use crate::{
     makepad_derive_widget::*,
     makepad_draw::*,
-    designer_data::*,
-    view::View,
+    flat_list::*,
+    label::*,
+    view::*,
+    widget_match_event::*,
     widget::*,
 };
+use std::collections::HashSet;
 
 live_design!{
     DesignerToolboxBase = {{DesignerToolbox}}{
     }
 }
 
+#[derive(Clone, Debug, DefaultNone)]
+pub enum DesignerToolboxAction {
+    Dropped{class: LiveId, abs: DVec2},
+    None
+}
+
 #[derive(Live, Widget, LiveHook)]
 pub struct DesignerToolbox {
-    #[deref] view: View
+    #[deref] view: View,
+    #[rust] palette: Vec<LiveId>,
+}
+
+impl DesignerToolbox{
+    // the registered widgets a live_design can instance by name, the ones with a definition at
+    // the top of a file like the theme has
+    fn load_palette(&mut self, cx: &Cx){
+        let live_registry = cx.live_registry.borrow();
+        let mut defined = HashSet::new();
+        for file_id in live_registry.file_ids().values(){
+            let nodes = &live_registry.file_id_to_file(*file_id).expanded.nodes;
+            let mut index = 1;
+            while index < nodes.len().saturating_sub(1){
+                defined.insert(nodes[index].id);
+                index = nodes.skip_node(index);
+            }
+        }
+        let wr = live_registry.components.get::<WidgetRegistry>();
+        let mut palette: Vec<(String, LiveId)> = wr.map.values().filter_map(|(info, _)|{
+            let name = info.name.to_string();
+            if defined.contains(&info.name) && !name.starts_with("Designer"){
+                Some((name, info.name))
+            }
+            else{
+                None
+            }
+        }).collect();
+        palette.sort();
+        palette.dedup();
+        self.palette = palette.into_iter().map(|(_, id)| id).collect();
+    }
+}
+
+impl WidgetMatchEvent for DesignerToolbox{
+    fn handle_actions(&mut self, cx: &mut Cx, actions: &Actions, scope: &mut Scope){
+        let uid = self.widget_uid();
+        let palette = self.view.flat_list(id!(palette));
+        for (class, item) in palette.items_with_actions(actions){
+            let item = item.as_view();
+            if item.finger_move(actions).is_some(){
+                cx.set_cursor(MouseCursor::Move);
+            }
+            // let go somewhere outside the palette, the designer decides where it lands
+            if let Some(fe) = item.finger_up(actions){
+                if !fe.is_over{
+                    cx.widget_action(uid, &scope.path, DesignerToolboxAction::Dropped{class, abs: fe.abs});
+                }
+            }
+        }
+    }
 }
 
 impl Widget for DesignerToolbox {
     fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope){
+        self.widget_match_event(cx, event, scope);
         self.view.handle_event(cx, event, scope);
     }
-    
-    fn draw_walk(&mut self, cx: &mut Cx2d, scope:&mut Scope, _walk: Walk) -> DrawStep {
-        let _data = scope.data.get::<DesignerData>().unwrap();
-        while let Some(_next) = self.view.draw(cx, &mut Scope::empty()).step() {
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, _scope:&mut Scope, _walk: Walk) -> DrawStep {
+        if self.palette.is_empty(){
+            self.load_palette(cx);
+        }
+        while let Some(next) = self.view.draw(cx, &mut Scope::empty()).step() {
+            if let Some(mut list) = next.as_flat_list().borrow_mut(){
+                for class in &self.palette{
+                    let item = list.item(cx, *class, live_id!(PaletteItem)).unwrap();
+                    item.label(id!(label)).set_text(&class.to_string());
+                    item.draw_all(cx, &mut Scope::empty());
+                }
+            }
         }
         DrawStep::done()
     }
-}
\ No newline at end of file
+}
+
+impl DesignerToolboxRef{
+    pub fn dropped(&self, actions: &Actions) -> Option<(LiveId, DVec2)> {
+        if let DesignerToolboxAction::Dropped{class, abs} = actions.find_widget_action_cast(self.widget_uid()) {
+            return Some((class, abs))
+        }
+        None
+    }
+}
@@ -670,6 +670,23 @@ impl DesignerViewRef{
         }
     }
     
+    // the component whose container is under an absolute position
+    pub fn component_at(&self, cx:&Cx, abs:DVec2) -> Option<LiveId> {
+        if let Some(inner) = self.borrow(){
+            let rect = inner.area.rect(cx);
+            if !rect.contains(abs){
+                return None
+            }
+            let cp = (abs - rect.pos) * inner.zoom + inner.pan;
+            for (id, cd) in inner.containers.iter(){
+                if cd.rect.contains(cp){
+                    return Some(*id)
+                }
+            }
+        }
+        None
+    }
+    
     pub fn selected(&self, actions: &Actions) -> Option<(LiveId,KeyModifiers,u32)> {
         if let Some(item) = actions.find_widget_action(self.widget_uid()) {
             if let DesignerViewAction::Selected{id, km, tap_count} = item.cast() {
@@ -43,6 +43,7 @@ pub struct DropDown {
     #[rust] is_open: bool,
     
     #[live] selected_item: usize,
+    #[live] nav_order: NavOrder,
     
     #[layout] layout: Layout,
 }
@@ -128,7 +129,7 @@ impl DropDown {
         }
         self.draw_bg.end(cx);
         
-        cx.add_nav_stop(self.draw_bg.area(), NavRole::DropDown, Margin::default());
+        cx.add_nav_stop_with_order(self.draw_bg.area(), NavRole::DropDown, Margin::default(), self.nav_order);
         
         if self.is_open && self.popup_menu.is_some() {
             //cx.set_sweep_lock(self.draw_bg.area());
@@ -203,6 +204,8 @@ impl Widget for DropDown {
     fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope)  {
         self.animator_handle_event(cx, event);
         let uid = self.widget_uid();
+        // an open menu takes the keys, the ones that close it should not open it again below
+        let was_open = self.is_open;
                 
         if self.is_open && self.popup_menu.is_some() {
             // ok so how will we solve this one
@@ -249,7 +252,13 @@ impl Widget for DropDown {
                 self.animator_play(cx, id!(focus.on));
             }
             Hit::KeyDown(ke) => match ke.key_code {
-                KeyCode::ArrowUp => {
+                KeyCode::Space | KeyCode::ReturnKey if !was_open && !ke.is_repeat => {
+                    self.set_open(cx);
+                }
+                KeyCode::Escape if self.is_open => {
+                    self.set_closed(cx);
+                }
+                KeyCode::ArrowUp if !was_open => {
                     if self.selected_item > 0 {
                         self.selected_item -= 1;
                         cx.widget_action(uid, &scope.path, DropDownAction::Select(self.selected_item, self.values.get(self.selected_item).cloned().unwrap_or(LiveValue::None)));
@@ -257,7 +266,7 @@ impl Widget for DropDown {
                         self.draw_bg.redraw(cx);
                     }
                 }
-                KeyCode::ArrowDown => {
+                KeyCode::ArrowDown if !was_open => {
                     if self.values.len() > 0 && self.selected_item < self.values.len() - 1 {
                         self.selected_item += 1;
                         cx.widget_action(uid, &scope.path, DropDownAction::Select(self.selected_item, self.values.get(self.selected_item).cloned().unwrap_or(LiveValue::None)));
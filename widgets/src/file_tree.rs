@@ -43,6 +43,8 @@ struct DrawNameText {
     #[live] selected: f32,
     #[live] hover: f32,
     #[live] opened: f32,
+    // mixed into the name by its alpha, for things like version control status
+    #[live] tint: Vec4,
 }
 
 #[derive(Live, LiveHook, LiveRegister)]#[repr(C)]
@@ -133,7 +135,8 @@ pub enum FileTreeNodeAction {
 }
 
 impl FileTreeNode {
-    pub fn set_draw_state(&mut self, is_even: f32, scale: f64) {
+    pub fn set_draw_state(&mut self, is_even: f32, scale: f64, tint: Vec4) {
+        self.draw_name.tint = tint;
         self.draw_bg.scale = scale as f32;
         self.draw_bg.is_even = is_even;
         self.draw_name.scale = scale as f32;
@@ -143,8 +146,8 @@ impl FileTreeNode {
         self.draw_name.font_scale = scale;
     }
     
-    pub fn draw_folder(&mut self, cx: &mut Cx2d, name: &str, is_even: f32, node_height: f64, depth: usize, scale: f64) {
-        self.set_draw_state(is_even, scale);
+    pub fn draw_folder(&mut self, cx: &mut Cx2d, name: &str, is_even: f32, node_height: f64, depth: usize, scale: f64, tint: Vec4) {
+        self.set_draw_state(is_even, scale, tint);
         
         self.draw_bg.begin(cx, Walk::size(Size::Fill, Size::Fixed(scale * node_height)), self.layout);
         
@@ -156,8 +159,8 @@ impl FileTreeNode {
         self.draw_bg.end(cx);
     }
     
-    pub fn draw_file(&mut self, cx: &mut Cx2d, name: &str, is_even: f32, node_height: f64, depth: usize, scale: f64) {
-        self.set_draw_state(is_even, scale);
+    pub fn draw_file(&mut self, cx: &mut Cx2d, name: &str, is_even: f32, node_height: f64, depth: usize, scale: f64, tint: Vec4) {
+        self.set_draw_state(is_even, scale, tint);
         
         self.draw_bg.begin(cx, Walk::size(Size::Fill, Size::Fixed(scale * node_height)), self.layout);
         
@@ -283,6 +286,16 @@ impl FileTree {
         cx: &mut Cx2d,
         node_id: LiveId,
         name: &str,
+    ) -> Result<(), ()> {
+        self.begin_folder_tinted(cx, node_id, name, Vec4::default())
+    }
+    
+    pub fn begin_folder_tinted(
+        &mut self,
+        cx: &mut Cx2d,
+        node_id: LiveId,
+        name: &str,
+        tint: Vec4,
     ) -> Result<(), ()> {
         let scale = self.stack.last().cloned().unwrap_or(1.0);
         
@@ -301,7 +314,7 @@ impl FileTree {
                 }
                 (tree_node, live_id!(folder_node))
             });
-            tree_node.draw_folder(cx, name, Self::is_even(self.count), self.node_height, self.stack.len(), scale);
+            tree_node.draw_folder(cx, name, Self::is_even(self.count), self.node_height, self.stack.len(), scale, tint);
             self.stack.push(tree_node.opened as f64 * scale);
             if tree_node.opened <= 0.001 {
                 self.end_folder();
@@ -324,6 +337,10 @@ impl FileTree {
     }
     
     pub fn file(&mut self, cx: &mut Cx2d, node_id: LiveId, name: &str) {
+        self.file_tinted(cx, node_id, name, Vec4::default())
+    }
+    
+    pub fn file_tinted(&mut self, cx: &mut Cx2d, node_id: LiveId, name: &str, tint: Vec4) {
         let scale = self.stack.last().cloned().unwrap_or(1.0);
         
         if scale > 0.2 {
@@ -334,7 +351,7 @@ impl FileTree {
             let (tree_node, _) = self.tree_nodes.get_or_insert(cx, node_id, | cx | {
                 (FileTreeNode::new_from_ptr(cx, file_node), live_id!(file_node))
             });
-            tree_node.draw_file(cx, name, Self::is_even(self.count), self.node_height, self.stack.len(), scale);
+            tree_node.draw_file(cx, name, Self::is_even(self.count), self.node_height, self.stack.len(), scale, tint);
         }
     }
     
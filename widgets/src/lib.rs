@@ -8,6 +8,7 @@ pub use makepad_markdown;
 
 pub mod button;
 pub mod cached_widget;
+pub mod adaptive_view;
 pub mod label;
 pub mod image;
 pub mod image_blend;
@@ -73,6 +74,8 @@ pub mod data_binding;
 
 pub mod base;
 pub mod theme_desktop_dark;
+pub mod theme_desktop_light;
+pub mod theme;
 pub mod image_cache;
 pub mod bare_step;
 pub mod turtle_step;
@@ -83,11 +86,13 @@ pub mod designer_view;
 pub mod designer_outline;
 pub mod designer_data;
 pub mod designer_toolbox;
+pub mod designer_inspector;
 
 pub use crate::{
     data_binding::{DataBindingStore, DataBindingMap},
     button::*,
     cached_widget::*,
+    adaptive_view::{AdaptiveView, AdaptiveViewRef, AdaptiveViewWidgetExt, AdaptiveViewWidgetRefExt},
     view::*,
     image::*,
     image_blend::*,
@@ -123,6 +128,7 @@ pub use crate::{
     slides_view::{SlidesView},
     widget_match_event::WidgetMatchEvent,
     toggle_panel::*,
+    theme::{Theme, CxThemeExt},
     widget::{
         WidgetSet,
         WidgetUid,
@@ -157,6 +163,7 @@ pub fn live_design(cx: &mut Cx) {
     crate::splitter::live_design(cx);
     crate::base::live_design(cx);
     crate::theme_desktop_dark::live_design(cx);
+    crate::theme_desktop_light::live_design(cx);
     crate::slider::live_design(cx);
     crate::label::live_design(cx);
     crate::nav_control::live_design(cx);
@@ -207,10 +214,12 @@ pub fn live_design(cx: &mut Cx) {
     crate::turtle_step::live_design(cx);
     crate::toggle_panel::live_design(cx);
     crate::cached_widget::live_design(cx);
+    crate::adaptive_view::live_design(cx);
     
     crate::designer::live_design(cx);
     crate::designer_view::live_design(cx);
     crate::designer_outline::live_design(cx);
     crate::designer_outline_tree::live_design(cx);
     crate::designer_toolbox::live_design(cx);
+    crate::designer_inspector::live_design(cx);
 }
@@ -76,6 +76,7 @@ impl Widget for Modal {
         self.draw_bg.begin(cx, self.walk, self.layout);
 
         if self.opened {
+            cx.add_nav_trap();
             let _ = self
                 .bg_view
                 .draw_walk(cx, scope, walk.with_abs_pos(DVec2 { x: 0., y: 0. }));
@@ -92,10 +93,13 @@ impl Widget for Modal {
 }
 
 impl Modal {
+    /// Opens the modal. The key focus is taken from the widgets behind it, Tab then moves it
+    /// through the content and does not leave it until the modal is closed.
     pub fn open(&mut self, cx: &mut Cx) {
         self.opened = true;
         self.draw_bg.redraw(cx);
         cx.sweep_lock(self.draw_bg.area());
+        cx.set_key_focus(Area::Empty);
     }
 
     pub fn close(&mut self, cx: &mut Cx) {
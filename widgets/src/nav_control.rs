@@ -12,7 +12,10 @@ pub struct NavControl {
     #[live] draw_list: DrawList2d,
     #[live] draw_focus: DrawQuad,
     #[live] draw_text: DrawText,
-    #[rust] _recent_focus: Area,
+    #[live(3.0)] focus_ring_margin: f64,
+    // the stop Tab moved to, the focus ring is only shown for focus that came from the keyboard
+    #[rust] recent_focus: Area,
+    #[rust] recent_margin: Margin,
 }
 
 impl NavControl {
@@ -30,54 +33,158 @@ impl NavControl {
         }
     }
     
+    fn focus_stop(&mut self, cx: &mut Cx, stop: NavStop, scroll_stack: Vec<Area>) {
+        Self::send_trigger_to_scroll_stack(cx, scroll_stack);
+        cx.set_key_focus(stop.area);
+        self.recent_focus = stop.area;
+        self.recent_margin = stop.margin;
+        self.draw_list.redraw(cx);
+    }
+    
+    fn navigate(&mut self, cx: &mut Cx, root: DrawListId, backward: bool) {
+        // an open popup menu traps without stops of its own, the keys stay with its owner
+        let NavStops {mut stops, trapped} = Cx2d::collect_nav_stops(cx, root);
+        if stops.is_empty() {
+            return
+        }
+        // Tab is left alone when something that is not a stop has the focus, like the code
+        // editor, unless a trap has to pull the focus into it
+        let current = stops.iter().position( | (stop, _) | cx.has_key_focus(stop.area));
+        let next = match current {
+            Some(i) if backward => (i + stops.len() - 1) % stops.len(),
+            Some(i) => (i + 1) % stops.len(),
+            None if !trapped && !cx.has_key_focus(Area::Empty) => return,
+            None if backward => stops.len() - 1,
+            None => 0
+        };
+        let (stop, scroll_stack) = stops.swap_remove(next);
+        self.focus_stop(cx, stop, scroll_stack);
+    }
+    
+    // the radio button the arrow keys move to from current, the next one of the same
+    // RadioButtonGroup. radio buttons outside of a group go with their neighbours in Tab order
+    fn next_radio_button(stops: &[(NavStop, Vec<Area>)], current: usize, backward: bool) -> usize {
+        let group = stops[current].0.group;
+        let is_radio = | (stop, _): &(NavStop, Vec<Area>) | matches!(stop.role, NavRole::RadioButton);
+        let radios: Vec<usize> = if group.is_some() {
+            (0..stops.len()).filter( | i | is_radio(&stops[*i]) && stops[*i].0.group == group).collect()
+        }
+        else {
+            let in_run = | s: &(NavStop, Vec<Area>) | is_radio(s) && s.0.group.is_none();
+            let first = stops[..current].iter().rposition( | s | !in_run(s)).map(|i| i + 1).unwrap_or(0);
+            let end = stops[current..].iter().position( | s | !in_run(s)).map(|i| current + i).unwrap_or(stops.len());
+            (first..end).collect()
+        };
+        let at = radios.iter().position( | i | *i == current).unwrap();
+        let len = radios.len();
+        radios[if backward {(at + len - 1) % len} else {(at + 1) % len}]
+    }
+    
+    /// Arrows move on from buttons and check boxes like Tab does, and within a radio button
+    /// group they move to the next one and select it. The other stops use them themselves.
+    fn navigate_arrow(&mut self, cx: &mut Cx, root: DrawListId, backward: bool) {
+        let NavStops {mut stops, ..} = Cx2d::collect_nav_stops(cx, root);
+        let Some(current) = stops.iter().position( | (stop, _) | cx.has_key_focus(stop.area)) else {
+            return
+        };
+        match stops[current].0.role {
+            NavRole::Button | NavRole::CheckBox => self.navigate(cx, root, backward),
+            NavRole::RadioButton => {
+                let next = Self::next_radio_button(&stops, current, backward);
+                let (stop, scroll_stack) = stops.swap_remove(next);
+                let area = stop.area;
+                self.focus_stop(cx, stop, scroll_stack);
+                cx.send_trigger(area, Trigger {
+                    id: live_id!(nav_select),
+                    from: area
+                });
+            }
+            _ => ()
+        }
+    }
+    
     pub fn handle_event(&mut self, cx: &mut Cx, event: &Event, root: DrawListId) {
         match event {
-            Event::KeyDown(ke) => match ke.key_code {
-                KeyCode::Tab => {
-                    if ke.modifiers.shift {
-                        let mut prev_area = Area::Empty;
-                        if let Some((prev_area, scroll_stack)) = Cx2d::iterate_nav_stops(cx, root, | cx, stop | {
-                            if cx.has_key_focus(stop.area) {
-                                return Some(prev_area);
-                            }
-                            prev_area = stop.area;
-                            None
-                        }) {
-                            if !prev_area.is_empty() {
-                                Self::send_trigger_to_scroll_stack(cx, scroll_stack);
-                                cx.set_key_focus(prev_area);
-                            }
-                        }
-                    }
-                    else {
-                        let mut next_stop = false;
-                        if let Some((next_area, scroll_stack)) = Cx2d::iterate_nav_stops(cx, root, | cx, stop | {
-                            if next_stop {
-                                return Some(stop.area)
-                            }
-                            if cx.has_key_focus(stop.area) {
-                                next_stop = true;
-                            }
-                            None
-                        }) {
-                            Self::send_trigger_to_scroll_stack(cx, scroll_stack);
-                            cx.set_key_focus(next_area);
-                        }
-                    }
+            Event::KeyDown(ke) => {
+                let m = &ke.modifiers;
+                if m.control || m.alt || m.logo {
+                    return
+                }
+                match ke.key_code {
+                    KeyCode::Tab => self.navigate(cx, root, m.shift),
+                    KeyCode::ArrowUp | KeyCode::ArrowLeft if !m.shift => self.navigate_arrow(cx, root, true),
+                    KeyCode::ArrowDown | KeyCode::ArrowRight if !m.shift => self.navigate_arrow(cx, root, false),
+                    _ => ()
                 }
-                _ => ()
-            },
+            }
+            Event::KeyFocus(_) | Event::KeyFocusLost(_) if !self.recent_focus.is_empty() => {
+                self.draw_list.redraw(cx);
+            }
             _ => ()
         }
     }
     
     pub fn draw(&mut self, cx: &mut Cx2d) {
-        if !self.draw_list.begin(cx, Walk::default()).is_redrawing() {
-            return
+        self.draw_list.begin_overlay_last(cx);
+        if !self.recent_focus.is_empty() {
+            if cx.has_key_focus(self.recent_focus) && self.recent_focus.is_valid(cx) {
+                let rect = self.recent_focus.clipped_rect(cx);
+                let ring = dvec2(self.focus_ring_margin, self.focus_ring_margin);
+                self.draw_focus.draw_abs(cx, Rect {
+                    pos: rect.pos - self.recent_margin.left_top() - ring,
+                    size: rect.size + self.recent_margin.size() + ring * 2.0
+                });
+            }
+            else {
+                // focus went elsewhere, by mouse or from code
+                self.recent_focus = Area::Empty;
+            }
         }
-        
         self.draw_list.end(cx);
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stop(role: NavRole, group: Option<usize>) -> (NavStop, Vec<Area>) {
+        (NavStop {role, order: NavOrder::Default, margin: Margin::default(), area: Area::Empty, group}, Vec::new())
+    }
 
+    #[test]
+    fn arrows_stay_in_a_radio_button_group() {
+        use NavRole::*;
+        let stops = [
+            stop(RadioButton, Some(0)),
+            stop(RadioButton, Some(0)),
+            stop(RadioButton, Some(1)),
+            stop(RadioButton, Some(1)),
+            stop(Button, None),
+            stop(RadioButton, Some(0)),
+        ];
+        // neighbouring groups don't merge, a group can be split up by other stops
+        assert_eq!(NavControl::next_radio_button(&stops, 1, false), 5);
+        assert_eq!(NavControl::next_radio_button(&stops, 5, false), 0);
+        assert_eq!(NavControl::next_radio_button(&stops, 0, true), 5);
+        assert_eq!(NavControl::next_radio_button(&stops, 2, true), 3);
+        assert_eq!(NavControl::next_radio_button(&stops, 3, false), 2);
+    }
+
+    #[test]
+    fn arrows_follow_a_run_of_ungrouped_radio_buttons() {
+        use NavRole::*;
+        let stops = [
+            stop(RadioButton, None),
+            stop(RadioButton, None),
+            stop(RadioButton, Some(0)),
+            stop(RadioButton, None),
+            stop(CheckBox, None),
+            stop(RadioButton, None),
+        ];
+        assert_eq!(NavControl::next_radio_button(&stops, 1, false), 0);
+        assert_eq!(NavControl::next_radio_button(&stops, 0, true), 1);
+        assert_eq!(NavControl::next_radio_button(&stops, 3, false), 3);
+        assert_eq!(NavControl::next_radio_button(&stops, 5, true), 5);
+    }
+}
@@ -40,6 +40,9 @@ pub struct PopupMenu {
     #[rust] first_tap: bool,
     #[rust] menu_items: ComponentMap<PopupMenuItemId, PopupMenuItem>,
     #[rust] init_select_item: Option<PopupMenuItemId>,
+    // the items in the order they were drawn, and the one the keyboard is on
+    #[rust] item_order: Vec<PopupMenuItemId>,
+    #[rust] focus_item: Option<PopupMenuItemId>,
     
     #[rust] count: usize,
 }
@@ -139,12 +142,15 @@ impl PopupMenu {
     
     pub fn begin(&mut self, cx: &mut Cx2d) {
         self.draw_list.begin_overlay_reuse(cx);
+        // the menu has no stops, so Tab stays with the owner that has the key focus
+        cx.add_nav_trap();
         
         cx.begin_pass_sized_turtle(Layout::flow_down());
         
         // ok so. this thing needs a complete position reset
         self.draw_bg.begin(cx, self.walk, self.layout);
         self.count = 0;
+        self.item_order.clear();
     }
     
     pub fn end(&mut self, cx: &mut Cx2d, shift_area: Area, shift: DVec2) {
@@ -188,9 +194,27 @@ impl PopupMenu {
     
     pub fn init_select_item(&mut self, which_id: PopupMenuItemId) {
         self.init_select_item = Some(which_id);
+        self.focus_item = Some(which_id);
         self.first_tap = true;
     }
     
+    fn move_focus(&mut self, cx: &mut Cx, forward: bool) {
+        let len = self.item_order.len();
+        if len == 0 {
+            return
+        }
+        let next = match self.item_order.iter().position( | id | Some(*id) == self.focus_item) {
+            Some(i) if forward => (i + 1) % len,
+            Some(i) => (i + len - 1) % len,
+            None if forward => 0,
+            None => len - 1
+        };
+        let id = self.item_order[next];
+        self.focus_item = Some(id);
+        self.select_item_state(cx, id);
+        self.draw_list.redraw(cx);
+    }
+    
     fn select_item_state(&mut self, cx: &mut Cx, which_id: PopupMenuItemId) {
         for (id, item) in &mut *self.menu_items {
             if *id == which_id {
@@ -211,6 +235,20 @@ impl PopupMenu {
         sweep_area: Area,
         dispatch_action: &mut dyn FnMut(&mut Cx, PopupMenuAction),
     ) {
+        // the keys go to the owner of the menu, which is what has the key focus
+        if let Event::KeyDown(ke) = event {
+            if cx.has_key_focus(sweep_area) {
+                match ke.key_code {
+                    KeyCode::ArrowDown => self.move_focus(cx, true),
+                    KeyCode::ArrowUp => self.move_focus(cx, false),
+                    KeyCode::Tab => self.move_focus(cx, !ke.modifiers.shift),
+                    KeyCode::Space | KeyCode::ReturnKey => if let Some(item_id) = self.focus_item {
+                        dispatch_action(cx, PopupMenuAction::WasSelected(item_id));
+                    }
+                    _ => ()
+                }
+            }
+        }
         let mut actions = Vec::new();
         for (item_id, node) in self.menu_items.iter_mut() {
             node.handle_event_with(cx, event, sweep_area, &mut | _, e | actions.push((*item_id, e)));
@@ -228,6 +266,7 @@ impl PopupMenu {
                     }
                 }
                 PopupMenuItemAction::WasSweeped => {
+                    self.focus_item = Some(node_id);
                     self.select_item_state(cx, node_id);
                     dispatch_action(cx, PopupMenuAction::WasSweeped(node_id));
                 }
@@ -41,9 +41,11 @@ pub enum MediaType {
     None,
 }
 
+// the arrow keys move between the radio buttons inside one group
 #[derive(Live, LiveHook, Widget)]
 pub struct RadioButtonGroup {
-    #[deref] frame: View
+    #[deref] frame: View,
+    #[rust] drawing: bool,
 }
 
 #[derive(Live, LiveHook, Widget)]
@@ -69,6 +71,7 @@ pub struct RadioButton {
     #[live] text: ArcStringMut,
     
     #[live] bind: String,
+    #[live] nav_order: NavOrder,
 }
 
 #[derive(Clone, Debug, DefaultNone)]
@@ -78,10 +81,6 @@ pub enum RadioButtonAction {
 }
 
 
-impl RadioButtonGroup {
-    pub fn draw_walk(&mut self, _cx: &mut Cx2d, _walk: Walk) {}
-}
-
 impl RadioButton {
     pub fn draw_walk(&mut self, cx: &mut Cx2d, walk: Walk) {
         self.draw_radio.begin(cx, walk, self.layout);
@@ -97,21 +96,35 @@ impl RadioButton {
         }
         self.draw_text.draw_walk(cx, self.label_walk, self.label_align, self.text.as_ref());
         self.draw_radio.end(cx);
+        cx.add_nav_stop_with_order(self.draw_radio.area(), NavRole::RadioButton, Margin::default(), self.nav_order);
+    }
+    
+    fn select(&mut self, cx: &mut Cx, scope: &mut Scope) {
+        if self.animator_in_state(cx, id!(selected.off)) {
+            self.animator_play(cx, id!(selected.on));
+            cx.widget_action(self.widget_uid(), &scope.path, RadioButtonAction::Clicked);
+        }
     }
-        
 }
 
 impl Widget for RadioButtonGroup {
     
-    fn handle_event(&mut self, cx: &mut Cx, event: &Event, _scope: &mut Scope) {
-        //let uid = self.widget_uid();
-        self.animator_handle_event(cx, event);
-              
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
+        self.frame.handle_event(cx, event, scope);
     }
     
-    fn draw_walk(&mut self, cx: &mut Cx2d, _scope:&mut Scope, walk: Walk) -> DrawStep {
-        self.draw_walk(cx, walk);
-        DrawStep::done()
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope:&mut Scope, walk: Walk) -> DrawStep {
+        // a view can return halfway to have a child drawn, the group goes on when it comes back
+        if !self.drawing {
+            cx.add_nav_group_begin();
+            self.drawing = true;
+        }
+        let step = self.frame.draw_walk(cx, scope, walk);
+        if step.is_done() {
+            cx.add_nav_group_end();
+            self.drawing = false;
+        }
+        step
     }
     
 }
@@ -119,8 +132,16 @@ impl Widget for RadioButtonGroup {
 impl Widget for RadioButton {
     
     fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
-        let uid = self.widget_uid();
         self.animator_handle_event(cx, event);
+        
+        // the arrow keys moved the focus onto this one from another radio button
+        if let Event::Trigger(te) = event {
+            if let Some(triggers) = te.triggers.get(&self.draw_radio.area()) {
+                if triggers.iter().any( | t | t.id == live_id!(nav_select)) {
+                    self.select(cx, scope);
+                }
+            }
+        }
                 
         match event.hits(cx, self.draw_radio.area()) {
             Hit::FingerHoverIn(_) => {
@@ -132,11 +153,11 @@ impl Widget for RadioButton {
                 self.animator_play(cx, id!(hover.off));
             },
             Hit::FingerDown(_fe) => {
-                if self.animator_in_state(cx, id!(selected.off)) {
-                    self.animator_play(cx, id!(selected.on));
-                    cx.widget_action(uid, &scope.path, RadioButtonAction::Clicked);
-                }
+                self.select(cx, scope);
             },
+            Hit::KeyDown(ke) if !ke.is_repeat && matches!(ke.key_code, KeyCode::Space | KeyCode::ReturnKey) => {
+                self.select(cx, scope);
+            }
             Hit::FingerUp(_fe) => {
                                 
             }
@@ -170,10 +191,7 @@ impl RadioButtonRef{
 
     pub fn select(&self, cx: &mut Cx, scope: &mut Scope){
         if let Some(mut inner) = self.borrow_mut(){
-            if inner.animator_in_state(cx, id!(selected.off)) {
-                inner.animator_play(cx, id!(selected.on));
-                cx.widget_action(inner.widget_uid(), &scope.path, RadioButtonAction::Clicked);
-            }
+            inner.select(cx, scope);
         }
     }
 }
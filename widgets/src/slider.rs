@@ -63,6 +63,7 @@ pub struct Slider {
     #[live] default: f64,
     
     #[live] bind: String,
+    #[live] nav_order: NavOrder,
 
     // Indicates if the label of the slider responds to hover events
     // The primary use case for this kind of emitted actions is for tooltips displaying
@@ -129,6 +130,8 @@ impl Slider {
         
         if let Some(mut dw) = cx.defer_walk(self.label_walk) {
             //, (self.value*100.0) as usize);
+            // the text input is what takes the key focus, so it is the nav stop
+            self.text_input.nav_order = self.nav_order;
             let walk = self.text_input.walk(cx);
             let mut scope = Scope::default();
             let _ = self.text_input.draw_walk(cx, &mut scope, walk);
@@ -142,6 +145,20 @@ impl Slider {
         self.draw_slider.end(cx);
     }
 
+    // arrow up and down step the value while the slider has the key focus, shift steps by 10
+    fn step_by_key(&mut self, cx: &mut Cx, steps: f64) -> bool {
+        let step = if self.step != 0.0 {self.step} else {(self.max - self.min) / 100.0};
+        let value = self.to_external() + steps * step;
+        // to_external rounds down to a step, aim in the middle of it
+        let bias = if self.step != 0.0 {self.step * 0.5} else {0.0};
+        if !self.set_internal((value + bias).max(self.min).min(self.max)) {
+            return false
+        }
+        self.draw_slider.redraw(cx);
+        self.update_text_input_and_redraw(cx);
+        true
+    }
+
     pub fn value(&self) -> f64 {
         self.to_external()
     }
@@ -173,6 +190,20 @@ impl Widget for Slider {
             _=>()
         }
         
+        if let Event::KeyDown(ke) = event {
+            if cx.has_key_focus(self.text_input.area()) {
+                let steps = if ke.modifiers.shift {10.0} else {1.0};
+                let steps = match ke.key_code {
+                    KeyCode::ArrowUp => steps,
+                    KeyCode::ArrowDown => -steps,
+                    _ => 0.0
+                };
+                if steps != 0.0 && self.step_by_key(cx, steps) {
+                    cx.widget_action(uid, &scope.path, SliderAction::Slide(self.to_external()));
+                }
+            }
+        }
+        
         for action in cx.capture_actions(|cx| self.text_input.handle_event(cx, event, scope)) {
             match action.as_widget_action().cast() {
                 TextInputAction::KeyFocus => {
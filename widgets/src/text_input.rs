@@ -38,6 +38,7 @@ pub struct TextInput {
     #[live] pub is_numeric_only: bool,
     #[live] pub empty_message: String,
     #[live] pub text: String,
+    #[live] pub nav_order: NavOrder,
 
     #[rust] cursor: Cursor,
     #[rust] history: History,
@@ -638,7 +639,7 @@ impl Widget for TextInput {
             );
         }
 
-        cx.add_nav_stop(self.draw_bg.area(), NavRole::TextInput, Margin::default());
+        cx.add_nav_stop_with_order(self.draw_bg.area(), NavRole::TextInput, Margin::default(), self.nav_order);
 
         DrawStep::done()
     }
@@ -0,0 +1,35 @@
+use crate::makepad_platform::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+fn theme_module(name: &str) -> LiveModuleId {
+    LiveModuleId::from_str(&format!("makepad_widgets::{}", name)).unwrap()
+}
+
+// the dark theme is the base everything imports, other theme sets are swapped in over its palette
+pub trait CxThemeExt {
+    fn set_theme(&mut self, theme: Theme);
+    fn follow_os_theme(&mut self);
+}
+
+impl CxThemeExt for Cx {
+    fn set_theme(&mut self, theme: Theme) {
+        let overlay = match theme {
+            Theme::Dark => None,
+            Theme::Light => Some(theme_module("theme_desktop_light")),
+        };
+        self.set_live_overlay(theme_module("theme_desktop_dark"), overlay);
+    }
+    
+    fn follow_os_theme(&mut self) {
+        self.follow_color_scheme(ColorSchemeOverlays {
+            target: theme_module("theme_desktop_dark"),
+            dark: None,
+            light: Some(theme_module("theme_desktop_light")),
+        });
+    }
+}
@@ -83,6 +83,7 @@ live_design! {
     THEME_COLOR_BG_UNFOCUSSED = (THEME_COLOR_BG_HIGHLIGHT * 0.85)
     THEME_COLOR_APP_CAPTION_BAR = (THEME_COLOR_D_HIDDEN)
     THEME_COLOR_DRAG_QUAD = (THEME_COLOR_U_5)
+    THEME_COLOR_FOCUS_RING = (THEME_COLOR_MAKEPAD)
 
     THEME_COLOR_CURSOR_BG = (THEME_COLOR_BLACK)
     THEME_COLOR_CURSOR_BORDER = (THEME_COLOR_WHITE)
@@ -157,6 +158,41 @@ live_design! {
     THEME_COLOR_DOCK_TAB_SELECTED = (THEME_COLOR_FG_APP)
     THEME_COLOR_DOCK_TAB_SELECTED_MINIMAL = (THEME_COLOR_U_4)
 
+    THEME_COLOR_HOVER_HIGHLIGHT = #f // hovered icons and tabs are mixed towards it
+
+    THEME_COLOR_LINK_HTML = #x0000EE
+    THEME_COLOR_LINK_HTML_HOVER = #x00EE00
+    THEME_COLOR_LINK_HTML_PRESSED = #xEE0000
+    THEME_COLOR_LINK_TEXTFLOW = #xa
+    THEME_COLOR_LINK_TEXTFLOW_HOVER = #xf
+    THEME_COLOR_LINK_TEXTFLOW_PRESSED = #x3
+
+    // the window buttons in the caption bar
+    THEME_COLOR_CAPTION_BUTTON = #3
+    THEME_COLOR_CAPTION_BUTTON_HOVER = #6
+    THEME_COLOR_CAPTION_BUTTON_PRESSED = #9
+    THEME_COLOR_CAPTION_BUTTON_ICON = #f
+    THEME_COLOR_CAPTION_CLOSE_HOVER = #e00
+    THEME_COLOR_CAPTION_CLOSE_PRESSED = #c00
+    THEME_COLOR_CAPTION_CLOSE_ICON_HOVER = #f
+    THEME_COLOR_CAPTION_XR_HOVER = #0aa
+    THEME_COLOR_CAPTION_XR_PRESSED = #077
+    THEME_COLOR_CAPTION_XR_ICON = #8
+
+    THEME_COLOR_FOLD_ICON = #8F
+    THEME_COLOR_FOLD_ICON_HOVER = #FF
+
+    THEME_COLOR_DESIGNER_BG = #3
+    THEME_COLOR_DESIGNER_OUTLINE = #c
+    THEME_COLOR_DESIGNER_OUTLINE_DASH = #555f
+    THEME_COLOR_DESIGNER_STEP = #4
+    THEME_COLOR_DESIGNER_STEP_BORDER = #5
+    THEME_COLOR_DESIGNER_STEP_SELECTED = #c
+
+    THEME_COLOR_TOOLTIP_BG = #fff
+    THEME_COLOR_TOOLTIP_BORDER = #D0D5DD
+    THEME_COLOR_TOOLTIP_TEXT = #000
+
 
     // TODO: THESE ARE APPLICATION SPECIFIC COLORS THAT SHOULD BE MOVED FROM THE GENERAL THEME TO THE GIVEN PROJECT
     THEME_COLOR_HIGH = #C00
@@ -513,7 +549,7 @@ live_design! {
                 return mix(
                     mix(
                         self.color,
-                        mix(self.color, #f, 0.5),
+                        mix(self.color, THEME_COLOR_HOVER_HIGHLIGHT, 0.5),
                         self.hover
                     ),
                     self.color * 0.75,
@@ -527,9 +563,9 @@ live_design! {
         width: Fit, height: Fit,
         align: {x: 0., y: 0.}
 
-        color: #x0000EE,
-        hover_color: #x00EE00,
-        pressed_color: #xEE0000,
+        color: (THEME_COLOR_LINK_HTML),
+        hover_color: (THEME_COLOR_LINK_HTML_HOVER),
+        pressed_color: (THEME_COLOR_LINK_HTML_PRESSED),
         
         // instance hovered: 0.0
         // instance pressed: 0.0
@@ -735,9 +771,9 @@ live_design! {
     }
     
     TextFlowLink = <TextFlowLinkBase> {
-        color: #xa,
-        hover_color: #xf,
-        pressed_color: #x3,
+        color: (THEME_COLOR_LINK_TEXTFLOW),
+        hover_color: (THEME_COLOR_LINK_TEXTFLOW_HOVER),
+        pressed_color: (THEME_COLOR_LINK_TEXTFLOW_PRESSED),
         
         margin:{right:5}
         
@@ -1402,7 +1438,7 @@ live_design! {
                 return mix(
                     mix(
                         self.color,
-                        mix(self.color, #f, 0.5),
+                        mix(self.color, THEME_COLOR_HOVER_HIGHLIGHT, 0.5),
                         self.hover
                     ),
                     self.color * 0.75,
@@ -1938,40 +1974,40 @@ live_design! {
                 // WindowsMin
                 match self.button_type {
                     DesktopButtonType::WindowsMin => {
-                        sdf.clear(mix(THEME_COLOR_APP_CAPTION_BAR, mix(#6, #9, self.pressed), self.hover));
+                        sdf.clear(mix(THEME_COLOR_APP_CAPTION_BAR, mix(THEME_COLOR_CAPTION_BUTTON_HOVER, THEME_COLOR_CAPTION_BUTTON_PRESSED, self.pressed), self.hover));
                         sdf.move_to(c.x - sz, c.y);
                         sdf.line_to(c.x + sz, c.y);
-                        sdf.stroke(#f, 0.5 + 0.5 * self.dpi_dilate);
+                        sdf.stroke(THEME_COLOR_CAPTION_BUTTON_ICON, 0.5 + 0.5 * self.dpi_dilate);
                         return sdf.result;
                     }
                     DesktopButtonType::WindowsMax => {
-                        sdf.clear(mix(THEME_COLOR_APP_CAPTION_BAR, mix(#6, #9, self.pressed), self.hover));
+                        sdf.clear(mix(THEME_COLOR_APP_CAPTION_BAR, mix(THEME_COLOR_CAPTION_BUTTON_HOVER, THEME_COLOR_CAPTION_BUTTON_PRESSED, self.pressed), self.hover));
                         sdf.rect(c.x - sz, c.y - sz, 2. * sz, 2. * sz);
-                        sdf.stroke(#f, 0.5 + 0.5 * self.dpi_dilate);
+                        sdf.stroke(THEME_COLOR_CAPTION_BUTTON_ICON, 0.5 + 0.5 * self.dpi_dilate);
                         return sdf.result;
                     }
                     DesktopButtonType::WindowsMaxToggled => {
-                        let clear = mix(THEME_COLOR_APP_CAPTION_BAR, mix(#6, #9, self.pressed), self.hover);
+                        let clear = mix(THEME_COLOR_APP_CAPTION_BAR, mix(THEME_COLOR_CAPTION_BUTTON_HOVER, THEME_COLOR_CAPTION_BUTTON_PRESSED, self.pressed), self.hover);
                         sdf.clear(clear);
                         let sz = 3.5;
                         sdf.rect(c.x - sz + 1., c.y - sz - 1., 2. * sz, 2. * sz);
-                        sdf.stroke(#f, 0.5 + 0.5 * self.dpi_dilate);
+                        sdf.stroke(THEME_COLOR_CAPTION_BUTTON_ICON, 0.5 + 0.5 * self.dpi_dilate);
                         sdf.rect(c.x - sz - 1., c.y - sz + 1., 2. * sz, 2. * sz);
                         sdf.fill_keep(clear);
-                        sdf.stroke(#f, 0.5 + 0.5 * self.dpi_dilate);
+                        sdf.stroke(THEME_COLOR_CAPTION_BUTTON_ICON, 0.5 + 0.5 * self.dpi_dilate);
                         return sdf.result;
                     }
                     DesktopButtonType::WindowsClose => {
-                        sdf.clear(mix(THEME_COLOR_APP_CAPTION_BAR, mix(#e00, #c00, self.pressed), self.hover));
+                        sdf.clear(mix(THEME_COLOR_APP_CAPTION_BAR, mix(THEME_COLOR_CAPTION_CLOSE_HOVER, THEME_COLOR_CAPTION_CLOSE_PRESSED, self.pressed), self.hover));
                         sdf.move_to(c.x - sz, c.y - sz);
                         sdf.line_to(c.x + sz, c.y + sz);
                         sdf.move_to(c.x - sz, c.y + sz);
                         sdf.line_to(c.x + sz, c.y - sz);
-                        sdf.stroke(#f, 0.5 + 0.5 * self.dpi_dilate);
+                        sdf.stroke(mix(THEME_COLOR_CAPTION_BUTTON_ICON, THEME_COLOR_CAPTION_CLOSE_ICON_HOVER, self.hover), 0.5 + 0.5 * self.dpi_dilate);
                         return sdf.result;
                     }
                     DesktopButtonType::XRMode => {
-                        sdf.clear(mix(THEME_COLOR_APP_CAPTION_BAR, mix(#0aa, #077, self.pressed), self.hover));
+                        sdf.clear(mix(THEME_COLOR_APP_CAPTION_BAR, mix(THEME_COLOR_CAPTION_XR_HOVER, THEME_COLOR_CAPTION_XR_PRESSED, self.pressed), self.hover));
                         let w = 12.;
                         let h = 8.;
                         sdf.box(c.x - w, c.y - h, 2. * w, 2. * h, 2.);
@@ -1982,13 +2018,13 @@ live_design! {
                         sdf.subtract();
                         sdf.circle(c.x, c.y + h - 0.75, 2.5);
                         sdf.subtract();
-                        sdf.fill(#8);
+                        sdf.fill(THEME_COLOR_CAPTION_XR_ICON);
 
                         return sdf.result;
                     }
                     DesktopButtonType::Fullscreen => {
                         sz = 8.;
-                        sdf.clear(mix(#3, mix(#6, #9, self.pressed), self.hover));
+                        sdf.clear(mix(THEME_COLOR_CAPTION_BUTTON, mix(THEME_COLOR_CAPTION_BUTTON_HOVER, THEME_COLOR_CAPTION_BUTTON_PRESSED, self.pressed), self.hover));
                         sdf.rect(c.x - sz, c.y - sz, 2. * sz, 2. * sz);
                         sdf.rect(c.x - sz + 1.5, c.y - sz + 1.5, 2. * (sz - 1.5), 2. * (sz - 1.5));
                         sdf.subtract();
@@ -1996,7 +2032,7 @@ live_design! {
                         sdf.subtract();
                         sdf.rect(c.x - sz - 2., c.y - sz + 4., 2. * (sz + 2.), 2. * (sz - 4.));
                         sdf.subtract();
-                        sdf.fill(#f); //, 0.5 + 0.5 * dpi_dilate);
+                        sdf.fill(THEME_COLOR_CAPTION_BUTTON_ICON); //, 0.5 + 0.5 * dpi_dilate);
 
                         return sdf.result;
                     }
@@ -2041,9 +2077,12 @@ live_design! {
     }
 
     NavControl = <NavControlBase> {
+        focus_ring_margin: 2.0
         draw_focus: {
             fn pixel(self) -> vec4 {
-                return #000f
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(1., 1., self.rect_size.x - 2., self.rect_size.y - 2., THEME_CORNER_RADIUS * 1.5);
+                return sdf.stroke(THEME_COLOR_FOCUS_RING, 1.5);
             }
         }
         draw_text: {
@@ -2994,7 +3033,7 @@ live_design! {
         draw_name: {
             fn get_color(self) -> vec4 {
                 return mix(
-                    THEME_COLOR_TEXT_DEFAULT * self.scale,
+                    mix(THEME_COLOR_TEXT_DEFAULT, vec4(self.tint.xyz, 1.0), self.tint.w) * self.scale,
                     THEME_COLOR_TEXT_SELECTED,
                     self.selected
                 )
@@ -3374,12 +3413,12 @@ live_design! {
                 return mix(
                     mix(
                         self.color,
-                        mix(self.color, #f, 0.4),
+                        mix(self.color, THEME_COLOR_HOVER_HIGHLIGHT, 0.4),
                         self.hover
                     ),
                     mix(
                         self.color_active,
-                        mix(self.color_active, #f, 0.75),
+                        mix(self.color_active, THEME_COLOR_HOVER_HIGHLIGHT, 0.75),
                         self.hover
                     ),
                     self.selected
@@ -3514,6 +3553,11 @@ live_design! {
         }
     }
 
+    RadioButtonGroup = <RadioButtonGroupBase> {
+        width: Fit, height: Fit,
+        flow: Down,
+    }
+
     ButtonGroup = <CachedRoundedView> {
         height: Fit, width: Fit,
         spacing: 0.0,
@@ -4305,10 +4349,10 @@ live_design! {
                     // PLUS
                     sdf.box(0.5, sz * 3.0, sz * 2.5, sz * 0.7, 1.0); // rounding = 3rd value
                     // vertical
-                    sdf.fill_keep(mix(#8F, #FF, self.hover));
+                    sdf.fill_keep(mix(THEME_COLOR_FOLD_ICON, THEME_COLOR_FOLD_ICON_HOVER, self.hover));
                     sdf.box(sz * 1.0, sz * 2.125, sz * 0.7, sz * 2.5, 1.0); // rounding = 3rd value
 
-                    sdf.fill_keep(mix(mix(#8F, #FF, self.hover), #FFF0, self.open))
+                    sdf.fill_keep(mix(mix(THEME_COLOR_FOLD_ICON, THEME_COLOR_FOLD_ICON_HOVER, self.hover), THEME_COLOR_U_HIDDEN, self.open))
 
                     return sdf.result
                 }
@@ -4729,6 +4773,32 @@ live_design! {
                 }
             }
         }
+        <RoundedShadowView>{
+            abs_pos: vec2(25., 325.)
+            width: 180., height: 350.,
+            padding: <THEME_MSPACE_1> {}
+            flow: Down,
+
+            draw_bg: {
+                border_width: 1.0
+                border_color: (THEME_COLOR_BEVEL_LIGHT)
+                shadow_color: (THEME_COLOR_D_4)
+                shadow_radius: 10.0,
+                shadow_offset: vec2(0.0, 5.0)
+                radius: 2.5
+                color: (THEME_COLOR_FG_APP),
+            }
+
+            palette = <FlatList> {
+                PaletteItem = <View> {
+                    width: Fill, height: Fit,
+                    padding: <THEME_MSPACE_H_2> {}
+                    cursor: Hand,
+                    grab_key_focus: false,
+                    label = <P> { width: Fill, margin: 0. }
+                }
+            }
+        }
         /*
         <RoundedShadowView>{
             width: 250., height: 350.,
@@ -4840,6 +4910,43 @@ live_design! {
         }*/
     }
 
+    DesignerInspectorProp = <View> {
+        width: Fill, height: Fit,
+        flow: Right,
+        align: { y: 0.5 }
+        padding: <THEME_MSPACE_H_2> {}
+        spacing: (THEME_SPACE_2)
+        label = <P> { width: 100., margin: 0. }
+    }
+
+    DesignerInspector = <DesignerInspectorBase>{
+        width: 280., height: Fill,
+        margin: { top: 38. }
+        flow: Down,
+        show_bg: true,
+        draw_bg: { color: (THEME_COLOR_BG_CONTAINER) }
+
+        title = <Pbold> { width: Fill, padding: <THEME_MSPACE_2> {} }
+        list = <FlatList> {
+            ColorProp = <DesignerInspectorProp> {
+                align: { y: 0.0 }
+                value = <ColorPicker> { width: 120., height: 120. }
+            }
+            NumberProp = <DesignerInspectorProp> {
+                value = <Slider> { width: Fill, text: "" }
+            }
+            BoolProp = <DesignerInspectorProp> {
+                value = <CheckBox> { text: "" }
+            }
+            EnumProp = <DesignerInspectorProp> {
+                value = <DropDown> { width: Fill }
+            }
+            TextProp = <DesignerInspectorProp> {
+                value = <TextInput> { width: Fill }
+            }
+        }
+    }
+
     DesignerContainer = <DesignerContainerBase>{
         width: 1200,
         height: 1200,
@@ -4853,13 +4960,13 @@ live_design! {
                 off = {
                     from: {all: Forward {duration: 0.1}}
                     apply: {
-                        view = {draw_bg:{border_color:#5}}
+                        view = {draw_bg:{border_color:(THEME_COLOR_DESIGNER_STEP_BORDER)}}
                     }
                 }
                 on = {
                     from: {all: Snap}
                     apply: {
-                        view = {draw_bg:{border_color:#c}}
+                        view = {draw_bg:{border_color:(THEME_COLOR_DESIGNER_STEP_SELECTED)}}
                     }
                 }
 
@@ -4867,9 +4974,9 @@ live_design! {
         }
         view = <RoundedView>{
             draw_bg:{
-                color:#4,
+                color:(THEME_COLOR_DESIGNER_STEP),
                 border_width:2
-                border_color:#5
+                border_color:(THEME_COLOR_DESIGNER_STEP_BORDER)
             }
             padding: 10
             inner = <BareStep>{}
@@ -4903,7 +5010,7 @@ live_design! {
                     instance pressed: 0.0
                     uniform border_radius: (THEME_CORNER_RADIUS)
                     instance bodytop: (THEME_COLOR_FG_APP)
-                    instance bodybottom: #f00
+                    instance bodybottom: (THEME_COLOR_CTRL_HOVER)
                     fn pixel(self) -> vec4 {
                         let sdf = Sdf2d::viewport(self.pos * self.rect_size);
                         let grad_top = 5.0;
@@ -4944,7 +5051,7 @@ live_design! {
     }
 
     DesignerView = <DesignerViewBase>{
-        clear_color: #3
+        clear_color: (THEME_COLOR_DESIGNER_BG)
         draw_outline:{
             fn pixel(self) -> vec4 {
                 let p = self.pos * self.rect_size;
@@ -4957,7 +5064,7 @@ live_design! {
                 let dash_pattern = fract(pos / dash_length);
                 let alpha = step(dash_pattern, line_width);
                 
-                let c = vec4(mix(#c, #555f, alpha))
+                let c = mix(THEME_COLOR_DESIGNER_OUTLINE, THEME_COLOR_DESIGNER_OUTLINE_DASH, alpha)
                 
                 sdf.stroke(c, 2.5);
                 return sdf.result;
@@ -5066,11 +5173,14 @@ live_design! {
             window:{ kind_id: 1 }
             body = <View>{
                 flow: Overlay
+                align: { x: 1.0 }
                 designer_view = <DesignerView> {
                     width: Fill, height: Fill
                 }
                 toolbox = <DesignerToolbox>{
                 }
+                inspector = <DesignerInspector>{
+                }
             }
         }
     }
@@ -5130,9 +5240,9 @@ live_design! {
                 padding: 16,
 
                 draw_bg: {
-                    color: #fff,
+                    color: (THEME_COLOR_TOOLTIP_BG),
                     border_width: 1.0,
-                    border_color: #D0D5DD,
+                    border_color: (THEME_COLOR_TOOLTIP_BORDER),
                     radius: 2.
                 }
 
@@ -5141,7 +5251,7 @@ live_design! {
                     draw_text: {
                         text_style: <THEME_FONT_REGULAR>{font_size: 9},
                         text_wrap: Word,
-                        color: #000
+                        color: (THEME_COLOR_TOOLTIP_TEXT)
                     }
                 }
             }
@@ -0,0 +1,168 @@
+use crate::makepad_platform::*;
+
+live_design! {
+    // The light theme set. It is swapped in over the palette of theme_desktop_dark at runtime,
+    // see theme::CxThemeExt, so it defines the same names and nothing else.
+
+    // GLOBAL PARAMETERS
+    THEME_COLOR_CONTRAST = 1.0
+    THEME_COLOR_TINT = #f00
+    THEME_COLOR_TINT_AMOUNT = 0.0
+
+    // COLOR PALETTE
+    // HIGHER VALUE = HIGHER CONTRAST, RECOMMENDED VALUES: 0.5 - 2.5
+
+    THEME_COLOR_W = #FFFFFFFF
+    THEME_COLOR_W_H = #FFFFFF00
+    THEME_COLOR_B = #000000FF
+    THEME_COLOR_B_H = #00000000
+
+    THEME_COLOR_WHITE = (mix(THEME_COLOR_W, #FFFFFF00, pow(0.1, THEME_COLOR_CONTRAST)))
+    THEME_COLOR_U_5 = (mix(THEME_COLOR_W, THEME_COLOR_W_H, pow(0.35, THEME_COLOR_CONTRAST)))
+    THEME_COLOR_U_4 = (mix(THEME_COLOR_W, THEME_COLOR_W_H, pow(0.6, THEME_COLOR_CONTRAST)))
+    THEME_COLOR_U_3 = (mix(THEME_COLOR_W, THEME_COLOR_W_H, pow(0.75, THEME_COLOR_CONTRAST)))
+    THEME_COLOR_U_2 = (mix(THEME_COLOR_W, THEME_COLOR_W_H, pow(0.9, THEME_COLOR_CONTRAST)))
+    THEME_COLOR_U_1 = (mix(THEME_COLOR_W, THEME_COLOR_W_H, pow(0.95, THEME_COLOR_CONTRAST)))
+    THEME_COLOR_U_HIDDEN = (THEME_COLOR_W_H)
+
+    THEME_COLOR_D_HIDDEN = (THEME_COLOR_B_H)
+    THEME_COLOR_D_1 = (mix(THEME_COLOR_B, THEME_COLOR_B_H, pow(0.85, THEME_COLOR_CONTRAST)))
+    THEME_COLOR_D_2 = (mix(THEME_COLOR_B, THEME_COLOR_B_H, pow(0.75, THEME_COLOR_CONTRAST)))
+    THEME_COLOR_D_3 = (mix(THEME_COLOR_B, THEME_COLOR_B_H, pow(0.6, THEME_COLOR_CONTRAST)))
+    THEME_COLOR_D_4 = (mix(THEME_COLOR_B, THEME_COLOR_B_H, pow(0.4, THEME_COLOR_CONTRAST)))
+    THEME_COLOR_BLACK = (mix(THEME_COLOR_B, THEME_COLOR_B_H, pow(0.1, THEME_COLOR_CONTRAST)))
+
+    // BASICS
+    THEME_COLOR_MAKEPAD = #FF5C39FF
+
+    THEME_COLOR_BG_APP = (mix(
+        mix(THEME_COLOR_B, THEME_COLOR_TINT, THEME_COLOR_TINT_AMOUNT),
+        mix(THEME_COLOR_W, THEME_COLOR_TINT, THEME_COLOR_TINT_AMOUNT),
+        pow(0.9, THEME_COLOR_CONTRAST)))
+    THEME_COLOR_FG_APP = (mix(
+        mix(THEME_COLOR_B, THEME_COLOR_TINT, THEME_COLOR_TINT_AMOUNT),
+        mix(THEME_COLOR_W, THEME_COLOR_TINT, THEME_COLOR_TINT_AMOUNT),
+        pow(0.96, THEME_COLOR_CONTRAST))
+    )
+    THEME_COLOR_BG_UNFOCUSSED = (THEME_COLOR_D_1)
+    THEME_COLOR_APP_CAPTION_BAR = (THEME_COLOR_D_HIDDEN)
+    THEME_COLOR_DRAG_QUAD = (THEME_COLOR_D_3)
+    THEME_COLOR_FOCUS_RING = (THEME_COLOR_MAKEPAD)
+
+    THEME_COLOR_CURSOR_BG = (THEME_COLOR_WHITE)
+    THEME_COLOR_CURSOR_BORDER = (THEME_COLOR_BLACK)
+
+    THEME_COLOR_TEXT_DEFAULT = (mix(THEME_COLOR_B, THEME_COLOR_B_H, pow(0.2, THEME_COLOR_CONTRAST)))
+    THEME_COLOR_TEXT_DEFAULT_DARK = (THEME_COLOR_U_5)
+    THEME_COLOR_TEXT_HL = (THEME_COLOR_BLACK)
+
+    THEME_COLOR_TEXT_PRESSED = (THEME_COLOR_D_4)
+    THEME_COLOR_TEXT_HOVER = (THEME_COLOR_BLACK)
+    THEME_COLOR_TEXT_ACTIVE = (THEME_COLOR_TEXT_DEFAULT)
+    THEME_COLOR_TEXT_INACTIVE = (THEME_COLOR_D_4)
+    THEME_COLOR_TEXT_SELECTED = (THEME_COLOR_BLACK)
+    THEME_COLOR_TEXT_FOCUSED = (THEME_COLOR_TEXT_DEFAULT)
+    THEME_COLOR_TEXT_PLACEHOLDER = (THEME_COLOR_D_3)
+    THEME_COLOR_TEXT_META = (THEME_COLOR_D_4)
+
+    THEME_COLOR_TEXT_CURSOR = (THEME_COLOR_BLACK)
+
+    THEME_COLOR_BG_CONTAINER = (THEME_COLOR_D_1)
+    THEME_COLOR_BG_EVEN = (THEME_COLOR_BG_CONTAINER * 0.875)
+    THEME_COLOR_BG_ODD = (THEME_COLOR_BG_CONTAINER * 1.125)
+    THEME_COLOR_BG_HIGHLIGHT = (THEME_COLOR_D_1) // Code-blocks and quotes.
+    THEME_COLOR_BG_HIGHLIGHT_INLINE = (THEME_COLOR_D_2) // i.e. inline code
+
+    THEME_COLOR_BEVEL_LIGHT = (THEME_COLOR_U_5)
+    THEME_COLOR_BEVEL_SHADOW = (THEME_COLOR_D_2)
+
+    // WIDGET COLORS
+    THEME_COLOR_CTRL_DEFAULT = (THEME_COLOR_U_4)
+    THEME_COLOR_CTRL_PRESSED = (THEME_COLOR_D_1)
+    THEME_COLOR_CTRL_HOVER = (THEME_COLOR_U_5)
+    THEME_COLOR_CTRL_ACTIVE = (THEME_COLOR_D_2)
+    THEME_COLOR_CTRL_SELECTED = (THEME_COLOR_D_1)
+    THEME_COLOR_CTRL_INACTIVE = (THEME_COLOR_D_HIDDEN)
+
+    THEME_COLOR_FLOATING_BG = #F4F4F4FF // Elements that live on top of the UI like dialogs, popovers, and context menus.
+
+    // Background of textinputs, radios, checkboxes etc.
+    THEME_COLOR_INSET_DEFAULT = (THEME_COLOR_D_1)
+    THEME_COLOR_INSET_PIT_TOP = (THEME_COLOR_D_2)
+    THEME_COLOR_INSET_PIT_TOP_HOVER = (THEME_COLOR_D_3)
+    THEME_COLOR_INSET_PIT_BOTTOM = (THEME_COLOR_U_HIDDEN)
+
+    // Progress bars, slider amounts etc.
+    THEME_COLOR_AMOUNT_DEFAULT = (THEME_COLOR_D_3)
+    THEME_COLOR_AMOUNT_DEFAULT_BIG = #6
+    THEME_COLOR_AMOUNT_HOVER = (THEME_COLOR_D_4)
+    THEME_COLOR_AMOUNT_ACTIVE = (THEME_COLOR_BLACK)
+    THEME_COLOR_AMOUNT_TRACK_DEFAULT = (THEME_COLOR_D_1)
+    THEME_COLOR_AMOUNT_TRACK_HOVER = (THEME_COLOR_D_1)
+    THEME_COLOR_AMOUNT_TRACK_ACTIVE = (THEME_COLOR_D_2)
+
+    // WIDGET SPECIFIC COLORS
+    THEME_COLOR_DIVIDER = (THEME_COLOR_D_2)
+
+    THEME_COLOR_SLIDER_NUB_DEFAULT = (THEME_COLOR_WHITE)
+    THEME_COLOR_SLIDER_NUB_HOVER = (THEME_COLOR_WHITE)
+    THEME_COLOR_SLIDER_NUB_ACTIVE = (THEME_COLOR_WHITE)
+
+    THEME_COLOR_SLIDES_CHAPTER = (THEME_COLOR_MAKEPAD)
+    THEME_COLOR_SLIDES_BG = (THEME_COLOR_U_5)
+
+    THEME_COLOR_SLIDER_BIG_NUB_TOP = #F
+    THEME_COLOR_SLIDER_BIG_NUB_TOP_HOVER = #F
+    THEME_COLOR_SLIDER_BIG_NUB_BOTTOM = #C
+    THEME_COLOR_SLIDER_BIG_NUB_BOTTOM_HOVER = #B
+
+    THEME_COLOR_CTRL_SCROLLBAR_HOVER = (THEME_COLOR_D_3)
+
+    THEME_COLOR_DOCK_CONTAINER = (THEME_COLOR_BG_CONTAINER)
+    THEME_COLOR_DOCK_TAB_SELECTED = (THEME_COLOR_FG_APP)
+    THEME_COLOR_DOCK_TAB_SELECTED_MINIMAL = (THEME_COLOR_D_4)
+
+    THEME_COLOR_HOVER_HIGHLIGHT = #0 // hovered icons and tabs are mixed towards it
+
+    THEME_COLOR_LINK_HTML = #x0000EE
+    THEME_COLOR_LINK_HTML_HOVER = #x00EE00
+    THEME_COLOR_LINK_HTML_PRESSED = #xEE0000
+    THEME_COLOR_LINK_TEXTFLOW = #x4
+    THEME_COLOR_LINK_TEXTFLOW_HOVER = #x0
+    THEME_COLOR_LINK_TEXTFLOW_PRESSED = #x8
+
+    // the window buttons in the caption bar
+    THEME_COLOR_CAPTION_BUTTON = #e
+    THEME_COLOR_CAPTION_BUTTON_HOVER = #c
+    THEME_COLOR_CAPTION_BUTTON_PRESSED = #a
+    THEME_COLOR_CAPTION_BUTTON_ICON = #3
+    THEME_COLOR_CAPTION_CLOSE_HOVER = #e00
+    THEME_COLOR_CAPTION_CLOSE_PRESSED = #c00
+    THEME_COLOR_CAPTION_CLOSE_ICON_HOVER = #f
+    THEME_COLOR_CAPTION_XR_HOVER = #0aa
+    THEME_COLOR_CAPTION_XR_PRESSED = #077
+    THEME_COLOR_CAPTION_XR_ICON = #6
+
+    THEME_COLOR_FOLD_ICON = #80
+    THEME_COLOR_FOLD_ICON_HOVER = #30
+
+    THEME_COLOR_DESIGNER_BG = #e
+    THEME_COLOR_DESIGNER_OUTLINE = #3
+    THEME_COLOR_DESIGNER_OUTLINE_DASH = #aaaf
+    THEME_COLOR_DESIGNER_STEP = #f
+    THEME_COLOR_DESIGNER_STEP_BORDER = #c
+    THEME_COLOR_DESIGNER_STEP_SELECTED = #4
+
+    THEME_COLOR_TOOLTIP_BG = #fff
+    THEME_COLOR_TOOLTIP_BORDER = #D0D5DD
+    THEME_COLOR_TOOLTIP_TEXT = #000
+
+    THEME_COLOR_HIGH = #C00
+    THEME_COLOR_MID = #E80
+    THEME_COLOR_LOW = #590
+    THEME_COLOR_PANIC = #f0f
+    THEME_COLOR_ICON_WAIT = (THEME_COLOR_LOW),
+    THEME_COLOR_ERROR = (THEME_COLOR_HIGH),
+    THEME_COLOR_WARNING = (THEME_COLOR_MID),
+    THEME_COLOR_ICON_PANIC = (THEME_COLOR_HIGH)
+}
@@ -361,6 +361,26 @@ impl WidgetRef {
     pub fn new_with_inner(widget: Box<dyn Widget>) -> Self {
         Self(Rc::new(RefCell::new(Some(WidgetRefInner { widget }))))
     }
+    
+    /// Instantiates a top level definition of a live module, like one loaded at runtime with
+    /// `cx.load_live_file`. Fails when the definition does not exist or is not a widget.
+    pub fn new_from_module(cx: &mut Cx, module_id: LiveModuleId, name: LiveId) -> Result<Self, LiveFileError> {
+        let live_ptr = cx.live_ptr_from_module(module_id, name)?;
+        let live_registry_rc = cx.live_registry.clone();
+        let live_registry = live_registry_rc.borrow();
+        if let LiveValue::Class{live_type, ..} = live_registry.ptr_to_node(live_ptr).value {
+            if !live_registry.components.get::<WidgetRegistry>().map.contains_key(&live_type) {
+                return Err(LiveFileError {
+                    origin: live_error_origin!(),
+                    file: live_registry.file_id_to_file_name(live_ptr.file_id).to_string(),
+                    span: Default::default(),
+                    message: format!("{} in module {} is not a widget", name, module_id)
+                })
+            }
+        }
+        drop(live_registry);
+        Ok(WidgetRef::new_from_ptr(cx, Some(live_ptr)))
+    }
     /// ## handle event with a sweep area
     ///
     /// this is used for the sweep event, this fn can help to pass the event into popup,
@@ -630,6 +650,23 @@ impl WidgetRef {
         }
     }
 
+    /// Swaps the widgets of two refs when they are of the same type, so a widget can be moved
+    /// into a tree that was rebuilt around it. Returns false when nothing was swapped.
+    pub fn swap_same_type(&self, other: &WidgetRef) -> bool {
+        if Rc::ptr_eq(&self.0, &other.0) {
+            return false
+        }
+        let mut inner = self.0.borrow_mut();
+        let mut other = other.0.borrow_mut();
+        match (&*inner, &*other) {
+            (Some(a), Some(b)) if a.widget.ref_cast_type_id() == b.widget.ref_cast_type_id() => {
+                std::mem::swap(&mut *inner, &mut *other);
+                true
+            }
+            _ => false
+        }
+    }
+
     pub fn apply_over(&self, cx: &mut Cx, nodes: &[LiveNode]) {
         self.apply(cx, &mut ApplyFrom::Over.into(), 0, nodes);
     }
@@ -696,13 +733,13 @@ impl LiveNew for WidgetRef {
     }
 
     fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
-        LiveTypeInfo {
-            module_id: LiveModuleId::from_str(&module_path!()).unwrap(),
-            live_type: LiveType::of::<dyn Widget>(),
-            fields: Vec::new(),
-            live_ignore: true,
-            type_name: LiveId(0),
-        }
+        LiveTypeInfo::new(
+            LiveModuleId::from_str(&module_path!()).unwrap(),
+            LiveType::of::<dyn Widget>(),
+            LiveId(0),
+            true,
+            Vec::new()
+        )
     }
 }
 
@@ -141,6 +141,7 @@ impl Window {
     pub fn end(&mut self, cx: &mut Cx2d) {
         //while self.frame.draw_widget_continue(cx).is_not_done() {}
         self.debug_view.draw(cx);
+        self.nav_control.draw(cx);
         
         // lets draw our cursor
         if let OsType::LinuxDirect = cx.os_type() {